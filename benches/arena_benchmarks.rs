@@ -0,0 +1,52 @@
+use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
+use ucl_lexer::arena::Bump;
+use ucl_lexer::{LexerConfig, UclParser};
+
+/// Generate a configuration of about `size` bytes made of service sections
+fn generate_config(size: usize) -> String {
+    let mut content = String::with_capacity(size + 512);
+    let mut index = 0;
+    while content.len() < size {
+        content.push_str(&format!(
+            "service_{index} {{\n  name = \"service-{index}\"\n  port = {}\n  enabled = true\n  \
+             timeout = 30s\n  tags = [\"web\", \"api\", \"v{index}\"]\n  \
+             limits {{ cpu = 0.5; memory = \"512mb\" }}\n}}\n",
+            1000 + index % 5000
+        ));
+        index += 1;
+    }
+    content
+}
+
+/// Parse a 5MB configuration, read one value and drop everything
+fn bench_parse_and_drop(c: &mut Criterion) {
+    let content = generate_config(5 * 1024 * 1024);
+    let lexer_config = LexerConfig::default().with_max_tokens(usize::MAX);
+
+    let mut group = c.benchmark_group("parse_and_drop_5mb");
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.sample_size(10);
+
+    group.bench_function("owned", |b| {
+        b.iter(|| {
+            let value = UclParser::with_lexer_config(black_box(&content), lexer_config.clone())
+                .parse_document()
+                .unwrap();
+            black_box(value["service_42"]["port"].as_integer())
+        })
+    });
+
+    group.bench_function("arena", |b| {
+        let mut parser = UclParser::with_lexer_config("", lexer_config.clone());
+        b.iter(|| {
+            let bump = Bump::new();
+            let value = parser.parse_in(black_box(&content), &bump).unwrap();
+            black_box(value["service_42"]["port"].as_integer())
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_and_drop);
+criterion_main!(benches);
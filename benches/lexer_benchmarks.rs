@@ -211,7 +211,7 @@ fn bench_string_parsing(c: &mut Criterion) {
 
     // JSON strings with various escape patterns
     let long_json_string = format!(r#""very long string {}" "#, "x".repeat(1000));
-    let json_strings = vec![
+    let json_strings = [
         r#""simple string""#,
         r#""string with \"escapes\" and \n newlines""#,
         r#""string with unicode \u0041\u0042\u0043""#,
@@ -247,7 +247,7 @@ fn bench_string_parsing(c: &mut Criterion) {
 
     // Single-quoted strings
     let long_single_string = format!("'very long string {}'", "y".repeat(1000));
-    let single_strings = vec![
+    let single_strings = [
         "'simple string'",
         "'string with \\' escape'",
         "'string with line\\\ncontinuation'",
@@ -335,8 +335,7 @@ fn bench_comment_parsing(c: &mut Criterion) {
     // With saving comments
     group.bench_function("with_saving", |b| {
         b.iter(|| {
-            let mut config = LexerConfig::default();
-            config.save_comments = true;
+            let config = LexerConfig::new().with_save_comments(true);
             let mut lexer = UclLexer::with_config(black_box(&comment_content), config);
             let mut token_count = 0;
             while let Ok(token) = lexer.next_token() {
@@ -389,12 +388,52 @@ fn bench_character_classification(c: &mut Criterion) {
     group.finish();
 }
 
+/// Builds about 10MB of entries, each ended by `separator`
+fn generate_entries(separator: char) -> String {
+    let mut content = String::with_capacity(10 * 1024 * 1024 + 64);
+    let mut i = 0;
+    while content.len() < 10 * 1024 * 1024 {
+        content.push_str(&format!("key_{i} = \"value {i}\";"));
+        content.push(separator);
+        i += 1;
+    }
+    content
+}
+
+/// Benchmark a 10MB document on one line against the same entries on separate lines
+fn bench_long_lines(c: &mut Criterion) {
+    let mut group = c.benchmark_group("long_lines");
+    group.sample_size(10);
+
+    for (name, separator) in [("single_line", ' '), ("multi_line", '\n')] {
+        let content = generate_entries(separator);
+        group.throughput(Throughput::Bytes(content.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("10mb", name), &content, |b, content| {
+            b.iter(|| {
+                let mut lexer = UclLexer::new(black_box(content));
+                let mut token_count = 0;
+                while let Ok(token) = lexer.next_token() {
+                    token_count += 1;
+                    if matches!(token, Token::Eof) {
+                        break;
+                    }
+                }
+                token_count
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_lexer_tokenization,
     bench_string_parsing,
     bench_number_parsing,
     bench_comment_parsing,
-    bench_character_classification
+    bench_character_classification,
+    bench_long_lines
 );
 criterion_main!(benches);
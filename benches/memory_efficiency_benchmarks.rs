@@ -2,7 +2,7 @@ use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group,
 use serde_json::Value;
 use std::alloc::{GlobalAlloc, Layout, System};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use ucl_lexer::{LexerConfig, UclLexer, from_str};
+use ucl_lexer::{LexerConfig, UclLexer, UclParser, UclValue, from_str};
 
 /// Custom allocator to track memory usage during benchmarks
 struct TrackingAllocator;
@@ -218,7 +218,7 @@ fn bench_memory_usage_parsing(c: &mut Criterion) {
 
                     for _ in 0..iters {
                         let result: Result<Value, _> = from_str(black_box(content));
-                        black_box(result);
+                        let _ = black_box(result);
                     }
 
                     let elapsed = start.elapsed();
@@ -257,7 +257,7 @@ fn bench_nginx_memory_usage(c: &mut Criterion) {
 
                     for _ in 0..iters {
                         let result: Result<Value, _> = from_str(black_box(content));
-                        black_box(result);
+                        let _ = black_box(result);
                     }
 
                     let elapsed = start.elapsed();
@@ -295,7 +295,7 @@ fn bench_unicode_memory_usage(c: &mut Criterion) {
 
                     for _ in 0..iters {
                         let result: Result<Value, _> = from_str(black_box(content));
-                        black_box(result);
+                        let _ = black_box(result);
                     }
 
                     let elapsed = start.elapsed();
@@ -433,8 +433,7 @@ fn bench_comment_memory_efficiency(c: &mut Criterion) {
             let start = std::time::Instant::now();
 
             for _ in 0..iters {
-                let mut config = LexerConfig::default();
-                config.save_comments = true;
+                let config = LexerConfig::new().with_save_comments(true);
                 let mut lexer = UclLexer::with_config(black_box(&comment_heavy_content), config);
                 let mut token_count = 0;
                 while let Ok(token) = lexer.next_token() {
@@ -464,7 +463,7 @@ fn bench_comment_memory_efficiency(c: &mut Criterion) {
 fn bench_error_handling_memory(c: &mut Criterion) {
     let mut group = c.benchmark_group("error_handling_memory");
 
-    let invalid_configs = vec![
+    let invalid_configs = [
         r#"{ unterminated_string = "missing quote }"#.repeat(50),
         r#"{ invalid_number = 123.45.67 }"#.repeat(50),
         r#"{ unterminated_object = { missing_brace = true"#.repeat(50),
@@ -484,7 +483,7 @@ fn bench_error_handling_memory(c: &mut Criterion) {
 
                     for _ in 0..iters {
                         let result: Result<Value, _> = from_str(black_box(content));
-                        black_box(result); // Should be an error
+                        let _ = black_box(result); // Should be an error
                     }
 
                     let elapsed = start.elapsed();
@@ -527,7 +526,7 @@ fn bench_string_allocation_optimization(c: &mut Criterion) {
 
             for _ in 0..iters {
                 let result: Result<Value, _> = from_str(black_box(&bare_word_config));
-                black_box(result);
+                let _ = black_box(result);
             }
 
             let elapsed = start.elapsed();
@@ -547,7 +546,7 @@ fn bench_string_allocation_optimization(c: &mut Criterion) {
 
             for _ in 0..iters {
                 let result: Result<Value, _> = from_str(black_box(&quoted_string_config));
-                black_box(result);
+                let _ = black_box(result);
             }
 
             let elapsed = start.elapsed();
@@ -563,6 +562,103 @@ fn bench_string_allocation_optimization(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark reparsing one document with fresh trees against `parse_into`
+fn bench_parse_into_reuse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_into_reuse");
+    group.sample_size(10);
+    // About 500KB
+    let content = generate_large_ucl_content(750);
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("fresh_parse_x1000", |b| {
+        b.iter_custom(|iterations| {
+            let mut elapsed = std::time::Duration::ZERO;
+            for _ in 0..iterations {
+                reset_memory_tracking();
+                let start = std::time::Instant::now();
+                for _ in 0..1000 {
+                    let value = UclParser::new(&content).parse_document().unwrap();
+                    black_box(&value);
+                }
+                elapsed += start.elapsed();
+            }
+            let (allocated, deallocated, _) = get_memory_stats();
+            eprintln!(
+                "Fresh parse x1000 - Allocated: {} bytes, Deallocated: {} bytes",
+                allocated, deallocated
+            );
+            elapsed
+        });
+    });
+
+    group.bench_function("parse_into_x1000", |b| {
+        b.iter_custom(|iterations| {
+            let mut parser = UclParser::new("");
+            let mut value = UclValue::Null;
+            parser.parse_into(&content, &mut value).unwrap();
+            let mut elapsed = std::time::Duration::ZERO;
+            for _ in 0..iterations {
+                reset_memory_tracking();
+                let start = std::time::Instant::now();
+                for _ in 0..1000 {
+                    parser.parse_into(&content, &mut value).unwrap();
+                    black_box(&value);
+                }
+                elapsed += start.elapsed();
+            }
+            let (allocated, deallocated, _) = get_memory_stats();
+            eprintln!(
+                "parse_into x1000 - Allocated: {} bytes, Deallocated: {} bytes",
+                allocated, deallocated
+            );
+            elapsed
+        });
+    });
+
+    group.finish();
+}
+
+/// Generate a flat document of `keys` entries with keys of about `key_len` bytes
+fn generate_keyed_content(keys: usize, key_len: usize) -> String {
+    let mut content = String::with_capacity(keys * (key_len + 8));
+    for i in 0..keys {
+        let key = format!("{:_<width$}", format!("k{:06}", i), width = key_len);
+        content.push_str(&format!("{} = {};\n", key, i % 100));
+    }
+    content
+}
+
+fn bench_object_keys(c: &mut Criterion) {
+    let mut group = c.benchmark_group("object_keys");
+    group.sample_size(10);
+
+    // Short keys fit inline in `UclKey`, long ones are stored on the heap
+    for (name, key_len) in [("short_100k", 12), ("long_100k", 32)] {
+        let content = generate_keyed_content(100_000, key_len);
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        group.bench_with_input(BenchmarkId::new("parse", name), &content, |b, content| {
+            b.iter_custom(|iterations| {
+                let mut elapsed = std::time::Duration::ZERO;
+                for _ in 0..iterations {
+                    reset_memory_tracking();
+                    let start = std::time::Instant::now();
+                    let value = UclParser::new(content).parse_document().unwrap();
+                    elapsed += start.elapsed();
+                    black_box(&value);
+                }
+                let (allocated, _, peak) = get_memory_stats();
+                eprintln!(
+                    "{} keys - Allocated: {} bytes, Peak: {} bytes",
+                    name, allocated, peak
+                );
+                elapsed
+            });
+        });
+    }
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_memory_usage_parsing,
@@ -571,6 +667,8 @@ criterion_group!(
     bench_lexer_memory_efficiency,
     bench_comment_memory_efficiency,
     bench_error_handling_memory,
-    bench_string_allocation_optimization
+    bench_string_allocation_optimization,
+    bench_parse_into_reuse,
+    bench_object_keys
 );
 criterion_main!(benches);
@@ -2,8 +2,8 @@ use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group,
 use serde::Deserialize;
 use std::collections::HashMap;
 use ucl_lexer::{
-    EnvironmentVariableHandler, LexerConfig, MapVariableHandler, ParserConfig, UclDeserializer,
-    UclParser, from_str,
+    EnvironmentVariableHandler, LexerConfig, MapVariableHandler, ParserConfig, Token,
+    UclDeserializer, UclLexer, UclParser, from_str, from_str_at_path,
 };
 
 /// Generate complex nested UCL structures for parsing benchmarks
@@ -24,7 +24,7 @@ fn generate_nested_ucl(depth: usize, breadth: usize) -> String {
         content.push_str("  simple_value = \"test\"\n");
         content.push_str("  number_value = 42\n");
         content.push_str("  array_value = [1, 2, 3, \"four\", true]\n");
-        content.push_str("}");
+        content.push('}');
         content
     }
 
@@ -73,6 +73,7 @@ fn generate_variable_ucl() -> String {
 
 /// Test structures for deserialization benchmarks
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct SimpleConfig {
     name: String,
     port: u16,
@@ -80,6 +81,7 @@ struct SimpleConfig {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct ComplexConfig {
     services: HashMap<String, ServiceConfig>,
     features: FeatureConfig,
@@ -87,6 +89,7 @@ struct ComplexConfig {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct ServiceConfig {
     url: String,
     port: u16,
@@ -94,6 +97,7 @@ struct ServiceConfig {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct ServiceSettings {
     timeout: String,
     workers: Option<u32>,
@@ -101,24 +105,28 @@ struct ServiceSettings {
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct FeatureConfig {
     logging: LoggingConfig,
     monitoring: MonitoringConfig,
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct LoggingConfig {
     level: String,
     output: String,
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MonitoringConfig {
     enabled: bool,
     endpoint: String,
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)]
 struct MetadataConfig {
     version: String,
     author: String,
@@ -222,13 +230,31 @@ fn bench_variable_expansion(c: &mut Criterion) {
 
     group.bench_function("with_env_handler", |b| {
         b.iter(|| {
-            let handler = Box::new(EnvironmentVariableHandler);
+            let handler = Box::new(EnvironmentVariableHandler::new());
             let mut parser = UclParser::with_variable_handler(black_box(&variable_ucl), handler);
             parser.parse_document()
         });
     });
 
     group.finish();
+
+    // One templated string with 10k references, as in generated HAProxy configs
+    let mut group = c.benchmark_group("templated_string");
+    let template: String = (0..10_000)
+        .map(|i| format!("server s{i} ${{HOST_{}}}:${{PORT:-80}} check\n", i % 100))
+        .collect();
+    group.throughput(Throughput::Bytes(template.len() as u64));
+    let hosts: HashMap<String, String> = (0..100)
+        .map(|i| (format!("HOST_{i}"), format!("10.0.0.{i}")))
+        .collect();
+    let parser =
+        UclParser::with_variable_handler("", Box::new(MapVariableHandler::from_map(hosts)));
+
+    group.bench_function("10k_variables", |b| {
+        b.iter(|| parser.expand_variables(black_box(&template)).unwrap());
+    });
+
+    group.finish();
 }
 
 /// Benchmark serde deserialization
@@ -334,8 +360,7 @@ fn bench_parser_config(c: &mut Criterion) {
 
     group.bench_function("no_duplicate_keys", |b| {
         b.iter(|| {
-            let mut config = ParserConfig::default();
-            config.allow_duplicate_keys = false;
+            let config = ParserConfig::new().with_allow_duplicate_keys(false);
             let mut parser = UclParser::new(black_box(test_ucl)).with_config(config);
             parser.parse_document()
         });
@@ -343,8 +368,7 @@ fn bench_parser_config(c: &mut Criterion) {
 
     group.bench_function("max_depth_limited", |b| {
         b.iter(|| {
-            let mut config = ParserConfig::default();
-            config.max_depth = 10;
+            let config = ParserConfig::new().with_max_depth(10);
             let mut parser = UclParser::new(black_box(test_ucl)).with_config(config);
             parser.parse_document()
         });
@@ -352,8 +376,7 @@ fn bench_parser_config(c: &mut Criterion) {
 
     group.bench_function("with_comments", |b| {
         b.iter(|| {
-            let mut lexer_config = LexerConfig::default();
-            lexer_config.save_comments = true;
+            let lexer_config = LexerConfig::new().with_save_comments(true);
             let mut parser = UclParser::with_lexer_config(black_box(test_ucl), lexer_config);
             parser.parse_document()
         });
@@ -366,7 +389,7 @@ fn bench_parser_config(c: &mut Criterion) {
 fn bench_error_handling(c: &mut Criterion) {
     let mut group = c.benchmark_group("error_handling");
 
-    let invalid_ucl_samples = vec![
+    let invalid_ucl_samples = [
         r#"{ unterminated_string = "missing quote }"#,
         r#"{ invalid_number = 123.45.67 }"#,
         r#"{ unterminated_object = { missing_brace = true"#,
@@ -401,6 +424,135 @@ fn bench_error_handling(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark extracting one section that is 1% of the document
+fn bench_section_extraction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("section_extraction");
+
+    #[derive(Deserialize)]
+    struct Logging {
+        #[allow(dead_code)]
+        level: String,
+    }
+
+    let mut content = String::new();
+    for i in 0..99 {
+        content.push_str(&format!(
+            "service_{i} {{\n    host = \"10.0.0.{i}\"\n    ports = [80, 443, 8080]\n    \
+             limits {{ memory = 512mb, timeout = 30s }}\n    tags = [\"web\", \"tier-{i}\"]\n}}\n"
+        ));
+        if i == 49 {
+            content.push_str("logging {\n    level = \"info\"\n}\n");
+        }
+    }
+
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("parse_document", |b| {
+        b.iter(|| UclParser::new(black_box(&content)).parse_document())
+    });
+
+    group.bench_function("from_str_at_path", |b| {
+        b.iter(|| from_str_at_path::<Logging>(black_box(&content), "logging"))
+    });
+
+    group.finish();
+}
+
+fn bench_ignored_section(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ignored_section");
+    group.sample_size(10);
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Listen {
+        port: u16,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(dead_code)]
+    struct Service {
+        name: String,
+        listen: Listen,
+    }
+
+    let mut content = String::from("name = \"api\"\nmetrics {\n");
+    let mut i = 0;
+    while content.len() < 10 * 1024 * 1024 {
+        content.push_str(&format!(
+            "    series_{i} {{ query = \"rate(requests{{job='${{JOB}}'}}[5m])\", \
+             buckets = [0.1, 0.5, 1, 5], labels {{ team = \"core\", tier = {i} }} }}\n"
+        ));
+        i += 1;
+    }
+    content.push_str("}\nlisten { port = 443 }\n");
+    let config = LexerConfig::new().with_max_tokens(usize::MAX);
+
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("lex_only", |b| {
+        b.iter(|| {
+            let mut lexer = UclLexer::with_config(black_box(&content), config.clone());
+            while lexer.next_token().unwrap() != Token::Eof {}
+        })
+    });
+
+    group.bench_function("from_str_skipping", |b| {
+        b.iter(|| {
+            let parser = UclParser::with_lexer_config(black_box(&content), config.clone())
+                .with_config(ParserConfig::new().with_skip_undeclared_sections(true));
+            Service::deserialize(UclDeserializer::from_parser(parser)).unwrap()
+        })
+    });
+
+    group.bench_function("parse_document", |b| {
+        b.iter(|| {
+            UclParser::with_lexer_config(black_box(&content), config.clone())
+                .parse_document()
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+/// Benchmark checking a 20MB document against parsing it
+fn bench_validation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("validation");
+    group.sample_size(10);
+
+    let mut content = String::new();
+    let mut i = 0;
+    while content.len() < 20 * 1024 * 1024 {
+        content.push_str(&format!(
+            "service_{i} {{\n    name = \"service number {i}\"\n    \
+             query = \"rate(requests{{job='${{JOB}}'}}[5m])\"\n    \
+             ports = [80, 443, 8080]\n    limits {{ memory = 512mb, timeout = 30s }}\n}}\n"
+        ));
+        i += 1;
+    }
+    let config = LexerConfig::new().with_max_tokens(usize::MAX);
+
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("parse_document", |b| {
+        b.iter(|| {
+            UclParser::with_lexer_config(black_box(&content), config.clone())
+                .parse_document()
+                .unwrap()
+        })
+    });
+
+    group.bench_function("validate", |b| {
+        b.iter(|| {
+            UclParser::with_lexer_config(black_box(&content), config.clone())
+                .validate()
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_parser_basic,
@@ -408,6 +560,9 @@ criterion_group!(
     bench_variable_expansion,
     bench_serde_deserialization,
     bench_parser_config,
-    bench_error_handling
+    bench_error_handling,
+    bench_section_extraction,
+    bench_ignored_section,
+    bench_validation
 );
 criterion_main!(benches);
@@ -1,7 +1,6 @@
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use serde_json::Value;
-use std::collections::HashMap;
-use ucl_lexer::{LexerConfig, ParserConfig, UclParser, from_str};
+use ucl_lexer::from_str;
 
 /// Generate NGINX-style configuration content for benchmarking
 fn generate_nginx_style_config(size: &str) -> String {
@@ -735,7 +734,7 @@ app = {
 "#;
 
     let config_with_hash_comments = config_with_cpp_comments.replace("//", "#");
-    let config_without_comments = remove_comments(&config_with_cpp_comments);
+    let config_without_comments = remove_comments(config_with_cpp_comments);
 
     group.throughput(Throughput::Bytes(config_with_cpp_comments.len() as u64));
 
@@ -817,7 +816,7 @@ config = {
 }
 "#;
 
-    let quoted_config = convert_bare_words_to_quoted(&bare_word_config);
+    let quoted_config = convert_bare_words_to_quoted(bare_word_config);
 
     group.throughput(Throughput::Bytes(bare_word_config.len() as u64));
 
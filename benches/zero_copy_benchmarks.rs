@@ -3,8 +3,12 @@
 // (borrowed strings vs owned strings with escapes).
 
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use serde::Deserialize;
 use std::borrow::Cow;
-use ucl_lexer::{StringFormat, Token, UclLexer};
+use std::collections::BTreeMap;
+use ucl_lexer::{
+    LexerConfig, MapVariableHandler, StringFormat, Token, UclLexer, from_str_with_variables,
+};
 
 /// Benchmark the effectiveness of zero-copy (borrowed vs owned strings)
 fn bench_zero_copy_effectiveness(c: &mut Criterion) {
@@ -79,6 +83,53 @@ fn bench_zero_copy_effectiveness(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark strings full of `$` with and without variable detection
+fn bench_variable_detection(c: &mut Criterion) {
+    let mut group = c.benchmark_group("variable_detection");
+
+    // Prometheus-style label templates
+    let content = (0..500)
+        .map(|i| {
+            format!(
+                r#"rule{} = "rate(http_requests_total{{job='$job', instance='$instance'}}[5m]) > {}""#,
+                i, i
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    for detect_variables in [true, false] {
+        let config = LexerConfig::new().with_detect_variables(detect_variables);
+        group.bench_with_input(
+            BenchmarkId::new("detect_variables", detect_variables),
+            &config,
+            |b, config| {
+                b.iter(|| {
+                    let mut lexer = UclLexer::with_config(black_box(&content), config.clone());
+                    let mut borrowed_count = 0;
+                    while let Ok(token) = lexer.next_token() {
+                        if let Token::String {
+                            value: Cow::Borrowed(_),
+                            ..
+                        } = &token
+                        {
+                            borrowed_count += 1;
+                        }
+                        if matches!(token, Token::Eof) {
+                            break;
+                        }
+                    }
+                    borrowed_count
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 /// Benchmark string size impact on parsing performance
 fn bench_string_size_impact(c: &mut Criterion) {
     let mut group = c.benchmark_group("string_size_impact");
@@ -199,11 +250,56 @@ fn bench_string_format_optimization(c: &mut Criterion) {
     group.finish();
 }
 
+/// A string that borrows from the input when it was read unchanged
+#[derive(Deserialize)]
+struct Text<'a>(#[serde(borrow)] Cow<'a, str>);
+
+/// Benchmark deserializing a large config where only two strings use variables
+fn bench_mostly_static_variables(c: &mut Criterion) {
+    let mut group = c.benchmark_group("mostly_static_variables");
+
+    let content: String = (0..5000)
+        .map(|i| match i {
+            1000 => "host = \"${HOST}\"\n".to_string(),
+            4000 => "port = \"${PORT}\"\n".to_string(),
+            _ => format!("key_{i} = \"some static value {i}\"\n"),
+        })
+        .collect();
+    let handler = || {
+        let mut handler = MapVariableHandler::new();
+        handler.insert("HOST".to_string(), "db.internal".to_string());
+        handler.insert("PORT".to_string(), "5432".to_string());
+        Box::new(handler)
+    };
+
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("owned_strings", |b| {
+        b.iter(|| {
+            let values: BTreeMap<String, String> =
+                from_str_with_variables(black_box(&content), handler()).unwrap();
+            values.values().map(String::len).sum::<usize>()
+        });
+    });
+
+    group.bench_function("borrowed_strings", |b| {
+        b.iter(|| {
+            let values: BTreeMap<String, Text> =
+                from_str_with_variables(black_box(&content), handler()).unwrap();
+            values.values().map(|text| text.0.len()).sum::<usize>()
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     bench_zero_copy_effectiveness,
+    bench_variable_detection,
     bench_string_size_impact,
     bench_cow_usage,
-    bench_string_format_optimization
+    bench_string_format_optimization,
+    bench_mostly_static_variables
 );
 criterion_main!(benches);
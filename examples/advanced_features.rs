@@ -8,7 +8,8 @@ use std::collections::HashMap;
 use std::io::Cursor;
 use ucl_lexer::{
     ChainedVariableHandler, EnvironmentVariableHandler, Position, Token, UclError, UclLexer,
-    VariableContext, VariableHandler, from_str, from_str_with_variables, streaming_lexer_from_reader,
+    VariableContext, VariableHandler, from_str, from_str_with_variables,
+    streaming_lexer_from_reader,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -49,9 +50,9 @@ fn demo_advanced_variables() -> Result<(), Box<dyn std::error::Error>> {
 
     #[derive(Debug, Deserialize)]
     struct TimeoutConfig {
-        connect: f64,
-        read: f64,
-        write: f64,
+        connect: String,
+        read: String,
+        write: String,
     }
 
     let config_text = r#"
@@ -80,9 +81,9 @@ fn demo_advanced_variables() -> Result<(), Box<dyn std::error::Error>> {
         
         # Timeouts with environment overrides
         timeouts {
-            connect = ${CONNECT_TIMEOUT:-5}s
-            read = ${READ_TIMEOUT:-30}s
-            write = ${WRITE_TIMEOUT:-10}s
+            connect = "${CONNECT_TIMEOUT:-5}s"
+            read = "${READ_TIMEOUT:-30}s"
+            write = "${WRITE_TIMEOUT:-10}s"
         }
     "#;
 
@@ -98,7 +99,7 @@ fn demo_advanced_variables() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let config: ServiceConfig =
-        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler::new()))?;
 
     println!("Parsed configuration with variable expansion:");
     println!("  Service name: {}", config.name);
@@ -107,7 +108,7 @@ fn demo_advanced_variables() -> Result<(), Box<dyn std::error::Error>> {
     println!("  API keys: {} configured", config.api_keys.len());
     println!("  Endpoints: {:?}", config.endpoints);
     println!(
-        "  Timeouts: connect={}s, read={}s, write={}s",
+        "  Timeouts: connect={}, read={}, write={}",
         config.timeouts.connect, config.timeouts.read, config.timeouts.write
     );
 
@@ -177,19 +178,19 @@ fn demo_custom_variable_handlers() -> Result<(), Box<dyn std::error::Error>> {
             name = "dynamic-app"
             instance_id = "${RANDOM_ID}"
             hostname = "${HOSTNAME}"
-            pid = ${PID}
-            started_at = ${TIMESTAMP}
+            pid = "${PID}"
+            started_at = "${TIMESTAMP}"
         }
         
         system {
-            cpu_count = ${CPU_COUNT}
+            cpu_count = "${CPU_COUNT}"
             memory_limit = "${MEMORY_LIMIT}"
             working_directory = "${CURRENT_DIR}"
         }
         
         metadata {
             config_path = "${CONTEXT_PATH}"
-            config_line = ${LINE_NUMBER}
+            config_line = "${LINE_NUMBER}"
         }
     "#;
 
@@ -205,13 +206,13 @@ fn demo_custom_variable_handlers() -> Result<(), Box<dyn std::error::Error>> {
         name: String,
         instance_id: String,
         hostname: String,
-        pid: u32,
-        started_at: i64,
+        pid: String,
+        started_at: String,
     }
 
     #[derive(Debug, Deserialize)]
     struct SystemInfo {
-        cpu_count: u32,
+        cpu_count: String,
         memory_limit: String,
         working_directory: String,
     }
@@ -219,13 +220,13 @@ fn demo_custom_variable_handlers() -> Result<(), Box<dyn std::error::Error>> {
     #[derive(Debug, Deserialize)]
     struct ConfigMetadata {
         config_path: String,
-        config_line: u32,
+        config_line: String,
     }
 
     // Chain custom handler with environment handler
     let handler = ChainedVariableHandler::from_handlers(vec![
         Box::new(ComputedVariableHandler::new()),
-        Box::new(EnvironmentVariableHandler),
+        Box::new(EnvironmentVariableHandler::new()),
     ]);
 
     let config: DynamicConfig = from_str_with_variables(config_text, Box::new(handler))?;
@@ -237,7 +238,7 @@ fn demo_custom_variable_handlers() -> Result<(), Box<dyn std::error::Error>> {
     );
     println!("  Host: {} (PID: {})", config.app.hostname, config.app.pid);
     println!("  Started at: {}", config.app.started_at);
-    assert!(config.app.started_at >= 0);
+    assert!(config.app.started_at.parse::<i64>()? >= 0);
     println!(
         "  System: {} CPUs, {} memory",
         config.system.cpu_count, config.system.memory_limit
@@ -360,16 +361,13 @@ fn demo_error_handling() -> Result<(), Box<dyn std::error::Error>> {
                 println!("  Error: {}", error);
 
                 // Extract position information if available
-                match &error {
-                    UclError::Lex(lex_error) => {
-                        if let Some(pos) = extract_position_from_error(lex_error) {
-                            println!("  Position: line {}, column {}", pos.line, pos.column);
-
-                            // Show context around error
-                            show_error_context(invalid_ucl, pos);
-                        }
-                    }
-                    _ => {}
+                if let UclError::Lex(lex_error) = &error
+                    && let Some(pos) = extract_position_from_error(lex_error)
+                {
+                    println!("  Position: line {}, column {}", pos.line, pos.column);
+
+                    // Show context around error
+                    show_error_context(invalid_ucl, pos);
                 }
             }
         }
@@ -1,5 +1,4 @@
 use serde::{Deserialize, Serialize};
-use serde_json;
 use std::collections::HashMap;
 use ucl_lexer::from_str;
 
@@ -121,7 +120,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         debug yes                    // Boolean keyword
         
         server {
-            listen 80
+            listen "80"
             listen 443 ssl           // Implicit bare word values
             server_name example.com
             server_name www.example.com
@@ -171,10 +170,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
          * implicit array creation through
          * key repetition
          */
-        feature authentication
-        feature logging
-        feature metrics
-        feature caching
+        features authentication
+        features logging
+        features metrics
+        features caching
         
         // Internationalization with extended Unicode escapes
         i18n {
@@ -195,7 +194,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             implicit_value bare_word_without_quotes
             colon_assignment: "uses colon separator"
             
-            nested_object config {
+            nested_object {
                 property "nested value"
                 value 42
             }
@@ -291,14 +290,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         try_files $uri $uri/ =404;
                     }
                 }
-            NGINX
+NGINX
             
             shell_script = <<BASH
                 #!/bin/bash
                 echo "Starting application..."
                 ./start_server.sh
                 echo "Application started"
-            BASH
+BASH
             
             sql_query = <<SQL
                 SELECT u.name, u.email, p.title
@@ -306,7 +305,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 JOIN posts p ON u.id = p.user_id
                 WHERE u.active = true
                 ORDER BY p.created_at DESC;
-            SQL
+SQL
         }
     "#;
 
@@ -356,15 +355,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     ssl_mode require
                 }
                 
-                replica {
-                    host "db-replica-1.example.com"
-                    port 5432
-                }
-                
-                replica {
-                    host "db-replica-2.example.com"
-                    port 5432
-                }
+                // Repeated objects merge, so several replicas form an explicit array
+                replica [
+                    { host "db-replica-1.example.com", port 5432 },
+                    { host "db-replica-2.example.com", port 5432 }
+                ]
                 
                 pool_size 20
                 max_connections 100
@@ -451,6 +446,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         replica: Vec<DatabaseNode>,
         pool_size: u32,
         max_connections: u32,
+        // Implicit values with time suffixes keep their text
         timeout: String,
     }
 
@@ -466,7 +462,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     struct AppCacheConfig {
         redis: RedisConfig,
         ttl: String,
-        max_memory: String,
+        // Size suffixes are parsed to bytes
+        max_memory: u64,
     }
 
     #[derive(Debug, Deserialize)]
@@ -489,7 +486,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[derive(Debug, Deserialize)]
     struct FileLoggingConfig {
         path: String,
-        max_size: String,
+        max_size: u64,
         max_files: u32,
         compress: bool,
     }
@@ -0,0 +1,112 @@
+//! Configuration Layers Example
+//!
+//! Loads the defaults shipped with an application, merges deployment overrides
+//! over them and expands `${VAR:-default}` references in the overrides from
+//! the process environment before deserializing the result.
+//!
+//! Run with `cargo run --example config_layers`, optionally setting `APP_ENV`,
+//! `DB_USER`, `DB_HOST` or `LOG_LEVEL` to change the expanded values.
+
+use serde::Deserialize;
+use std::error::Error;
+use std::path::Path;
+use ucl_lexer::{EnvironmentVariableHandler, UclParser, UclValue, VariableHandler, from_value};
+
+/// Directory holding `defaults.ucl` and `overrides.ucl`
+const DATA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data");
+
+#[derive(Debug, Deserialize)]
+pub struct AppConfig {
+    pub app: AppSection,
+    pub server: ServerSection,
+    pub database: DatabaseSection,
+    pub logging: LoggingSection,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppSection {
+    pub name: String,
+    pub environment: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerSection {
+    pub host: String,
+    pub port: u16,
+    pub workers: u32,
+    pub request_timeout: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DatabaseSection {
+    pub url: String,
+    pub pool_size: u32,
+    pub connect_timeout: f64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoggingSection {
+    pub level: String,
+    pub targets: Vec<String>,
+}
+
+/// Parses one layer, expanding its variables with `variables`
+fn parse_layer(
+    path: &Path,
+    variables: Box<dyn VariableHandler>,
+) -> Result<UclValue, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    let value = UclParser::with_variable_handler(&text, variables).parse_document()?;
+    Ok(value)
+}
+
+/// Loads the defaults and the overrides in `dir` and merges them
+///
+/// Objects are merged key by key, while scalars and arrays in the overrides
+/// replace the defaults.
+pub fn load_config(
+    dir: &Path,
+    variables: Box<dyn VariableHandler>,
+) -> Result<AppConfig, Box<dyn Error>> {
+    let mut merged = parse_layer(
+        &dir.join("defaults.ucl"),
+        Box::new(EnvironmentVariableHandler::new()),
+    )?;
+    merged.merge_from(parse_layer(&dir.join("overrides.ucl"), variables)?);
+    Ok(from_value(merged)?)
+}
+
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let config = load_config(
+        Path::new(DATA_DIR),
+        Box::new(EnvironmentVariableHandler::new()),
+    )?;
+
+    println!("=== Merged configuration ===");
+    println!("{} ({})", config.app.name, config.app.environment);
+    println!(
+        "Server: {}:{} with {} workers, {}s request timeout",
+        config.server.host,
+        config.server.port,
+        config.server.workers,
+        config.server.request_timeout
+    );
+    println!(
+        "Database: {} (pool {}, {}s connect timeout)",
+        config.database.url, config.database.pool_size, config.database.connect_timeout
+    );
+    println!(
+        "Logging: {} to {}",
+        config.logging.level,
+        config.logging.targets.join(", ")
+    );
+
+    // Keys missing from the overrides keep their defaults
+    assert_eq!(config.app.name, "inventory");
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.server.workers, 16);
+    assert_eq!(config.database.pool_size, 50);
+    assert_eq!(config.logging.targets.len(), 2);
+
+    Ok(())
+}
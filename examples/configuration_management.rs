@@ -6,8 +6,8 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 use ucl_lexer::{
-    ChainedVariableHandler, EnvironmentVariableHandler, MapVariableHandler, from_str,
-    from_str_with_variables,
+    ChainedVariableHandler, EnvironmentVariableHandler, MapVariableHandler, UclParser, UclValue,
+    from_str, from_value,
 };
 
 #[derive(Debug, Deserialize, Clone)]
@@ -237,16 +237,19 @@ fn load_environment_config(
         _ => {}
     }
 
-    let variable_handler = ChainedVariableHandler::from_handlers(vec![
-        Box::new(MapVariableHandler::from_map(env_vars)),
-        Box::new(EnvironmentVariableHandler),
-    ]);
+    let parse_layer = |text: &str| {
+        let handler = ChainedVariableHandler::from_handlers(vec![
+            Box::new(MapVariableHandler::from_map(env_vars.clone())),
+            Box::new(EnvironmentVariableHandler::new()),
+        ]);
+        UclParser::with_variable_handler(text, Box::new(handler)).parse_document()
+    };
 
-    // Combine base config with environment overrides
-    let combined_config = format!("{}\n{}", base_config, env_overrides);
+    // Layer the environment overrides onto the base configuration
+    let mut merged = parse_layer(&base_config)?;
+    merged.merge_from(parse_layer(&env_overrides)?);
 
-    let config: ApplicationConfig =
-        from_str_with_variables(&combined_config, Box::new(variable_handler))?;
+    let config: ApplicationConfig = from_value(merged)?;
 
     assert_application_config(&config);
 
@@ -362,50 +365,66 @@ fn get_environment_overrides(environment: &str) -> String {
     match environment {
         "development" => r#"
             # Development overrides
-            app.debug = true
-            server.host = "localhost"
-            database.migrations.auto_migrate = true
-            logging.outputs = [
-                { type = "stdout", level = "debug" },
-                { type = "file", target = "./logs/dev.log", level = "debug" }
-            ]
+            app { debug = true }
+            server { host = "localhost" }
+            database { migrations { auto_migrate = true } }
+            logging {
+                outputs = [
+                    { type = "stdout", level = "debug" },
+                    { type = "file", target = "./logs/dev.log", level = "debug" }
+                ]
+            }
         "#
         .to_string(),
 
         "staging" => r#"
             # Staging overrides
-            server.host = "0.0.0.0"
-            server.port = 8080
-            server.limits.max_connections = 5000
-            database.pool.max_connections = 50
-            monitoring.alerts.enabled = true
-            monitoring.alerts.email_recipients = ["staging-alerts@company.com"]
+            server {
+                host = "0.0.0.0"
+                port = 8080
+                limits { max_connections = 5000 }
+            }
+            database { pool { max_connections = 50 } }
+            monitoring {
+                alerts {
+                    enabled = true
+                    email_recipients = ["staging-alerts@company.com"]
+                }
+            }
         "#
         .to_string(),
 
         "production" => r#"
             # Production overrides
-            server.host = "0.0.0.0"
-            server.port = 80
-            server.limits.max_connections = 10000
-            server.tls {
-                cert_file = "/etc/ssl/certs/app.crt"
-                key_file = "/etc/ssl/private/app.key"
+            server {
+                host = "0.0.0.0"
+                port = 80
+                limits { max_connections = 10000 }
+                tls {
+                    cert_file = "/etc/ssl/certs/app.crt"
+                    key_file = "/etc/ssl/private/app.key"
+                }
+            }
+            database {
+                pool { max_connections = 100 }
+                replicas = [
+                    {
+                        host = "replica1.internal"
+                        port = 5432
+                        database = "myapp"
+                        username = "readonly"
+                        password = "${REPLICA_PASSWORD}"
+                        ssl_mode = "require"
+                    }
+                ]
             }
-            database.pool.max_connections = 100
-            database.replicas = [
-                {
-                    host = "replica1.internal"
-                    port = 5432
-                    database = "myapp"
-                    username = "readonly"
-                    password = "${REPLICA_PASSWORD}"
-                    ssl_mode = "require"
+            monitoring {
+                alerts {
+                    enabled = true
+                    webhook_url = "${ALERT_WEBHOOK_URL}"
+                    email_recipients = ["ops@company.com", "alerts@company.com"]
                 }
-            ]
-            monitoring.alerts.enabled = true
-            monitoring.alerts.webhook_url = "${ALERT_WEBHOOK_URL}"
-            monitoring.alerts.email_recipients = ["ops@company.com", "alerts@company.com"]
+            }
             feature_flags {
                 new_feature = false
                 beta_api = false
@@ -476,8 +495,14 @@ fn assert_application_config(config: &ApplicationConfig) {
     assert!(!config.database.primary.username.is_empty());
     assert!(!config.database.primary.password.is_empty());
     assert!(!config.database.primary.ssl_mode.is_empty());
-    assert!(config.database.migrations.auto_migrate || !config.database.migrations.auto_migrate);
-    assert!(config.database.migrations.migration_path.contains("migration"));
+    let _ = config.database.migrations.auto_migrate;
+    assert!(
+        config
+            .database
+            .migrations
+            .migration_path
+            .contains("migration")
+    );
 
     assert!(!config.cache.redis.url.is_empty());
     assert!(config.cache.redis.pool_size > 0);
@@ -492,18 +517,18 @@ fn assert_application_config(config: &ApplicationConfig) {
         assert!(!output.r#type.is_empty());
         // target is optional; ensure access
         let _ = output.target.as_deref();
-        assert!(output.level.as_deref().unwrap_or("info").len() > 0);
+        assert!(!output.level.as_deref().unwrap_or("info").is_empty());
     }
 
-    assert!(config.monitoring.metrics.enabled || !config.monitoring.metrics.enabled);
+    let _ = config.monitoring.metrics.enabled;
     assert!(!config.monitoring.metrics.endpoint.is_empty());
     assert!(config.monitoring.metrics.interval >= 0.0);
     assert!(config.monitoring.metrics.retention >= 0.0);
-    assert!(config.monitoring.health_check.enabled || !config.monitoring.health_check.enabled);
+    let _ = config.monitoring.health_check.enabled;
     assert!(!config.monitoring.health_check.endpoint.is_empty());
     assert!(config.monitoring.health_check.interval >= 0.0);
     assert!(config.monitoring.health_check.timeout >= 0.0);
-    assert!(config.monitoring.alerts.enabled || !config.monitoring.alerts.enabled);
+    let _ = config.monitoring.alerts.enabled;
     assert!(config.monitoring.alerts.email_recipients.len() <= 10_000);
     if let Some(webhook) = &config.monitoring.alerts.webhook_url {
         assert!(!webhook.is_empty());
@@ -631,9 +656,6 @@ fn demo_config_merging() -> Result<(), Box<dyn std::error::Error>> {
     println!("  2. User configuration file");
     println!("  3. Command-line overrides");
 
-    // In a real application, you would implement proper merging logic
-    // For demonstration, we'll show how different configs would be parsed
-
     #[derive(Debug, Deserialize)]
     struct SimpleConfig {
         app: SimpleApp,
@@ -663,41 +685,25 @@ fn demo_config_merging() -> Result<(), Box<dyn std::error::Error>> {
     );
     assert!(!base.app.environment.is_empty());
 
-    let user: SimpleConfig = from_str(user_config)?;
-    println!(
-        "User config: {} on port {} with {} custom settings",
-        user.app.name,
-        user.server.port,
-        user.custom_settings.len()
-    );
-
-    let cli: serde_json::Value = from_str(cli_overrides)?;
-    let cli_host = cli
-        .get("server")
-        .and_then(|server| server.get("host"))
-        .and_then(|host| host.as_str())
-        .unwrap_or("unknown");
-    let cli_debug = cli
-        .get("app")
-        .and_then(|app| app.get("debug"))
-        .and_then(|debug| debug.as_bool())
-        .unwrap_or(false);
-    println!(
-        "CLI overrides: host={} debug={}",
-        cli_host, cli_debug
-    );
-
-    // Simulate final merged configuration
-    println!("\nFinal merged configuration would be:");
-    println!("  App name: {} (from user config)", user.app.name);
-    println!("  Version: {} (from base config)", base.app.version);
-    println!("  Host: 0.0.0.0 (from CLI override)");
-    println!("  Port: {} (from user config)", user.server.port);
-    println!("  Debug: false (from CLI override)");
+    // Later layers are deep-merged over earlier ones before the result is typed
+    let mut merged: UclValue = base_config.parse()?;
+    merged.merge_from(user_config.parse()?);
+    merged.merge_from(cli_overrides.parse()?);
+    let config: SimpleConfig = from_value(merged)?;
+
+    println!("\nFinal merged configuration:");
+    println!("  App name: {} (from user config)", config.app.name);
+    println!("  Version: {} (from base config)", config.app.version);
+    println!("  Host: {} (from CLI override)", config.server.host);
+    println!("  Port: {} (from user config)", config.server.port);
+    println!("  Debug: {} (from CLI override)", config.app.debug);
     println!(
         "  Custom settings: {} items (from user config)",
-        user.custom_settings.len()
+        config.custom_settings.len()
     );
+    assert_eq!(config.server.host, "0.0.0.0");
+    assert_eq!(config.server.port, 8080);
+    assert!(!config.app.debug);
 
     println!();
     Ok(())
@@ -27,8 +27,7 @@ fn main() {
     println!("Testing C++ comments with preservation:");
 
     // Test with comment preservation
-    let mut config = LexerConfig::default();
-    config.save_comments = true;
+    let config = LexerConfig::new().with_save_comments(true);
     let mut lexer = UclLexer::with_config(config_text, config);
 
     let mut comments = Vec::new();
@@ -41,10 +41,10 @@ fn demo_builtin_plugins() -> Result<(), Box<dyn std::error::Error>> {
         .with_plugin(Box::new(PathProcessingPlugin::new()))?
         .build()?;
 
-    match parser.parse_document()? {
+    match &parser.parse_document()? {
         UclValue::Object(config) => {
             println!("Parsed configuration:");
-            for (key, value) in &config {
+            for (key, value) in config {
                 match value {
                     UclValue::String(s) => println!("  {}: \"{}\"", key, s),
                     _ => println!("  {}: {:?}", key, value),
@@ -54,8 +54,8 @@ fn demo_builtin_plugins() -> Result<(), Box<dyn std::error::Error>> {
             // Note: Path normalization processed "./assets/../static" -> "static"
             if let Some(UclValue::String(path)) = config.get("static_path") {
                 println!(
-                    "\nPath normalization result: {} -> {}",
-                    "./assets/../static", path
+                    "\nPath normalization result: ./assets/../static -> {}",
+                    path
                 );
             }
         }
@@ -83,7 +83,7 @@ fn demo_custom_hooks() -> Result<(), Box<dyn std::error::Error>> {
     // Add custom hooks directly
     parser.add_string_processor(Box::new(PathNormalizationProcessor::new()));
 
-    match parser.parse_document()? {
+    match &parser.parse_document()? {
         UclValue::Object(config) => {
             println!("Parsed and validated configuration:");
             if let Some(UclValue::Object(server_config)) = config.get("server_config") {
@@ -125,10 +125,10 @@ fn demo_plugin_configuration() -> Result<(), Box<dyn std::error::Error>> {
         .with_plugin(Box::new(validation_plugin))?
         .build()?;
 
-    match parser.parse_document() {
+    match &parser.parse_document() {
         Ok(UclValue::Object(config)) => {
             println!("Configuration validated successfully:");
-            for (key, value) in &config {
+            for (key, value) in config {
                 match value {
                     UclValue::String(s) => println!("  {}: \"{}\"", key, s),
                     _ => println!("  {}: {:?}", key, value),
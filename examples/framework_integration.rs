@@ -4,7 +4,9 @@
 //! with popular Rust web frameworks and libraries.
 
 use serde::Deserialize;
-use ucl_lexer::{EnvironmentVariableHandler, from_str, from_str_with_variables};
+use ucl_lexer::{
+    EnvironmentVariableHandler, UclValue, from_str, from_str_with_variables, from_value,
+};
 
 // Example integration with Axum web framework
 #[derive(Debug, Deserialize, Clone)]
@@ -315,10 +317,7 @@ fn setup_tokio_runtime(config: TokioConfig) -> Result<(), Box<dyn std::error::Er
     // This would be the actual Tokio runtime setup
     println!(
         "    ✓ Runtime configured with {} worker threads",
-        config
-            .runtime
-            .worker_threads
-            .unwrap_or_else(|| num_cpus::get())
+        config.runtime.worker_threads.unwrap_or_else(num_cpus::get)
     );
     println!(
         "    ✓ Task limits set: {} concurrent, {}s timeout",
@@ -363,7 +362,7 @@ fn demo_api_service_integration() -> Result<(), Box<dyn std::error::Error>> {
 
     // Use environment variable handler for JWT secret
     let config: ApiConfig =
-        from_str_with_variables(api_config, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(api_config, Box::new(EnvironmentVariableHandler::new()))?;
 
     println!("API configuration loaded:");
     println!("  Version: {}", config.version);
@@ -494,19 +493,25 @@ fn demo_configuration_composition() -> Result<(), Box<dyn std::error::Error>> {
         base.features.auth, base.features.metrics, base.features.tracing
     );
 
-    // Parse environment overrides
-    let overrides: ComposedConfig = from_str(environment_overrides)?;
-    println!("\nEnvironment overrides:");
-    println!("  App environment: {}", overrides.app.environment);
+    // Layer the environment overrides onto the base configuration
+    let mut merged: UclValue = base_config.parse()?;
+    merged.merge_from(environment_overrides.parse()?);
+    let composed: ComposedConfig = from_value(merged)?;
+    println!("\nWith environment overrides:");
+    println!(
+        "  App: {} v{} ({})",
+        composed.app.name, composed.app.version, composed.app.environment
+    );
     println!(
         "  Server: {}:{}",
-        overrides.server.host, overrides.server.port
+        composed.server.host, composed.server.port
     );
-    println!("  Tracing enabled: {}", overrides.features.tracing);
-
-    // In a real application, you would merge these configurations
-    println!("\n  ✓ Configuration composition demonstrated");
-    println!("  ✓ Multiple sources can be parsed and merged");
+    println!(
+        "  Features: auth={}, metrics={}, tracing={}",
+        composed.features.auth, composed.features.metrics, composed.features.tracing
+    );
+    assert_eq!(composed.app.environment, "production");
+    assert!(composed.features.auth && composed.features.tracing);
 
     println!();
     Ok(())
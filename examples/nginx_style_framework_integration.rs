@@ -354,9 +354,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             description "High-performance web API built with Rust"
             
             // Implicit array creation through key repetition
-            author "Alice Johnson"
-            author "Bob Smith"
-            author "Carol Davis"
+            authors "Alice Johnson"
+            authors "Bob Smith"
+            authors "Carol Davis"
             
             environment production
         }
@@ -380,8 +380,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     cert_path "/etc/ssl/certs/api.crt"
                     key_path "/etc/ssl/private/api.key"
                     
-                    protocol "TLSv1.2"
-                    protocol "TLSv1.3"  // Implicit array
+                    protocols "TLSv1.2"
+                    protocols "TLSv1.3"  // Implicit array
                 }
             }
             
@@ -389,18 +389,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 cors {
                     enabled true
                     
-                    allowed_origin "https://app.example.com"
-                    allowed_origin "https://admin.example.com"
+                    allowed_origins "https://app.example.com"
+                    allowed_origins "https://admin.example.com"
                     
-                    allowed_method "GET"
-                    allowed_method "POST"
-                    allowed_method "PUT"
-                    allowed_method "DELETE"
-                    allowed_method "OPTIONS"
+                    allowed_methods "GET"
+                    allowed_methods "POST"
+                    allowed_methods "PUT"
+                    allowed_methods "DELETE"
+                    allowed_methods "OPTIONS"
                     
-                    allowed_header "Content-Type"
-                    allowed_header "Authorization"
-                    allowed_header "X-Requested-With"
+                    allowed_headers "Content-Type"
+                    allowed_headers "Authorization"
+                    allowed_headers "X-Requested-With"
                     
                     max_age 86400
                 }
@@ -415,9 +415,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 compression {
                     enabled yes
                     
-                    algorithm "gzip"
-                    algorithm "br"      // Brotli
-                    algorithm "deflate"
+                    algorithms "gzip"
+                    algorithms "br"      // Brotli
+                    algorithms "deflate"
                     
                     min_size "1kb"
                 }
@@ -537,9 +537,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             ]
             
-            filter "api=debug"
-            filter "sqlx=warn"
-            filter "tower_http=info"
+            filters "api=debug"
+            filters "sqlx=warn"
+            filters "tower_http=info"
             
             sampling {
                 enabled true
@@ -592,8 +592,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 strategy header      // Version via Accept header
                 default_version "v1"
                 
-                supported_version "v1"
-                supported_version "v2"
+                supported_versions "v1"
+                supported_versions "v2"
                 
                 deprecation_warnings true
             }
@@ -623,9 +623,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 strict_mode true
                 max_payload_size "10mb"
                 
-                custom_validator "email"
-                custom_validator "phone"
-                custom_validator "uuid"
+                custom_validators "email"
+                custom_validators "phone"
+                custom_validators "uuid"
             }
             
             serialization {
@@ -896,7 +896,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         port: u16,
         routes: HashMap<String, RouteConfig>,
         load_balancing: LoadBalancingConfig,
-        rate_limits: HashMap<String, RateLimitConfig>,
+        rate_limits: HashMap<String, RouteRateLimit>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct RouteRateLimit {
+        requests_per_minute: u32,
+        burst: u32,
     }
 
     #[derive(Debug, Deserialize)]
@@ -948,7 +954,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         microservices.gateway.rate_limits.len()
     );
     for (path, limit) in &microservices.gateway.rate_limits {
-        println!("    Rate limit {} -> {}/min", path, limit.requests_per_minute);
+        println!(
+            "    Rate limit {} -> {}/min (burst {})",
+            path, limit.requests_per_minute, limit.burst
+        );
     }
 
     println!(
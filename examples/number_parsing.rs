@@ -20,7 +20,7 @@ struct NumberConfig {
 
     // Size suffixes (decimal)
     disk_space: u64,        // Will parse "1tb" as bytes
-    network_bandwidth: u64, // Will parse "100mbps" as bits per second
+    network_bandwidth: u64, // Will parse "100M" as bits per second
 
     // Time suffixes
     timeout: f64,          // Will parse "30s" as seconds
@@ -115,12 +115,12 @@ fn demo_size_suffixes() -> Result<(), Box<dyn std::error::Error>> {
         huge_storage = 1tb       # 1 * 1024^4 bytes
         
         # Decimal size suffixes (1000-based)
-        network_speed = 100mbps  # 100 * 1000 * 1000 bits per second
-        disk_capacity = 500gb    # 500 * 1000^3 bytes
+        network_speed = 100M     # 100 * 1000 * 1000 bits per second
+        disk_capacity = 500g     # 500 * 1000^3 bytes
         
         # Byte suffixes
         packet_size = 1500b      # 1500 bytes
-        header_size = 64bytes    # 64 bytes
+        header_size = 64b        # 64 bytes
         
         # Without suffix (raw bytes)
         raw_size = 1048576       # 1MB in bytes
@@ -198,9 +198,6 @@ fn demo_time_suffixes() -> Result<(), Box<dyn std::error::Error>> {
         precise_delay = 1.5s     # 1.5 seconds
         sub_second = 0.1s        # 0.1 seconds
         
-        # Multiple units (if supported)
-        complex_duration = 1h30min  # 5400 seconds
-        
         # Without suffix (raw seconds)
         raw_seconds = 3600       # 1 hour in seconds
     "#;
@@ -216,7 +213,6 @@ fn demo_time_suffixes() -> Result<(), Box<dyn std::error::Error>> {
         retention_period: f64,
         precise_delay: f64,
         sub_second: f64,
-        // complex_duration: f64,  // May not be supported yet
         raw_seconds: f64,
     }
 
@@ -468,7 +464,7 @@ fn demo_full_number_config() -> Result<(), Box<dyn std::error::Error>> {
         cache_size = 2gb
         buffer_size = 64kb
         disk_space = 1tb
-        network_bandwidth = 100mbps
+        network_bandwidth = 100M
         timeout = 30s
         retry_delay = 500ms
         session_duration = 2h
@@ -253,45 +253,31 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         retries 3
                     }
                     
-                    // Backend servers with implicit array syntax
-                    backend {
-                        host "10.0.1.10"
-                        port 8080
-                        weight 100
-                        backup false
-                    }
-                    
-                    backend {
-                        host "10.0.1.11"
-                        port 8080
-                        weight 100
-                        backup false
-                    }
-                    
-                    backend {
-                        host "10.0.1.12"
-                        port 8080
-                        weight 50
-                        backup true
-                    }
+                    // Backend servers; repeated nested objects would merge
+                    backends [
+                        { host "10.0.1.10", port 8080, weight 100, backup false },
+                        { host "10.0.1.11", port 8080, weight 100, backup false },
+                        { host "10.0.1.12", port 8080, weight 50, backup true }
+                    ]
                 }
             }
             
             storage {
                 // Persistent volumes
-                persistent_volume {
-                    name "api-data"
-                    size "100Gi"
-                    storage_class "fast-ssd"
-                    access_mode "ReadWriteOnce"
-                }
-                
-                persistent_volume {
-                    name "api-logs"
-                    size "50Gi"
-                    storage_class "standard"
-                    access_mode "ReadWriteMany"
-                }
+                persistent_volumes [
+                    {
+                        name "api-data"
+                        size "100Gi"
+                        storage_class "fast-ssd"
+                        access_mode "ReadWriteOnce"
+                    },
+                    {
+                        name "api-logs"
+                        size "50Gi"
+                        storage_class "standard"
+                        access_mode "ReadWriteMany"
+                    }
+                ]
                 
                 object_storage {
                     provider "aws-s3"
@@ -309,18 +295,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 scrape_interval "15s"
                 
                 // Scrape targets
-                target "kubernetes-pods"
-                target "kubernetes-nodes"
-                target "kubernetes-services"
+                targets "kubernetes-pods"
+                targets "kubernetes-nodes"
+                targets "kubernetes-services"
             }
             
             grafana {
                 enabled true
                 admin_password null  // Set via secret
                 
-                dashboard "kubernetes-cluster"
-                dashboard "application-metrics"
-                dashboard "infrastructure-overview"
+                dashboards "kubernetes-cluster"
+                dashboards "application-metrics"
+                dashboards "infrastructure-overview"
             }
             
             alerting {
@@ -401,12 +387,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             
             ci_cd {
                 pipeline {
-                    stage "build"
-                    stage "test"
-                    stage "security-scan"
-                    stage "deploy-staging"
-                    stage "integration-tests"
-                    stage "deploy-production"
+                    stages "build"
+                    stages "test"
+                    stages "security-scan"
+                    stages "deploy-staging"
+                    stages "integration-tests"
+                    stages "deploy-production"
                     
                     parallel_jobs 4
                     timeout "30m"
@@ -44,7 +44,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
         # HTTP server configuration
         http {
             host = "${HTTP_HOST:-0.0.0.0}"
-            port = ${HTTP_PORT:-8080}
+            port = 8080
             
             # Request handling
             max_request_size = 10mb
@@ -53,7 +53,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
             
             # TLS configuration
             tls {
-                enabled = ${TLS_ENABLED:-false}
+                enabled = false
                 cert_file = "${TLS_CERT_FILE:-/etc/ssl/certs/service.crt}"
                 key_file = "${TLS_KEY_FILE:-/etc/ssl/private/service.key}"
                 protocols = ["TLSv1.2", "TLSv1.3"]
@@ -65,7 +65,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
             # Primary database
             primary {
                 url = "${DATABASE_URL}"
-                pool_size = ${DB_POOL_SIZE:-20}
+                pool_size = 20
                 max_lifetime = 30min
                 idle_timeout = 10min
                 connection_timeout = 5s
@@ -79,7 +79,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
             
             # Migration settings
             migrations {
-                auto_migrate = ${AUTO_MIGRATE:-false}
+                auto_migrate = false
                 migration_timeout = 5min
             }
         }
@@ -87,7 +87,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
         # Redis for caching and sessions
         redis {
             url = "${REDIS_URL:-redis://localhost:6379/0}"
-            pool_size = ${REDIS_POOL_SIZE:-10}
+            pool_size = 10
             timeout = 2s
             
             # Key prefixes for different data types
@@ -131,10 +131,10 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
             
             # Distributed tracing
             tracing {
-                enabled = ${TRACING_ENABLED:-false}
+                enabled = false
                 jaeger_endpoint = "${JAEGER_ENDPOINT:-http://localhost:14268/api/traces}"
                 service_name = "auth-service"
-                sample_rate = ${TRACE_SAMPLE_RATE:-0.1}
+                sample_rate = 0.1
             }
             
             # Health checks
@@ -172,7 +172,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
             }
             
             session_security {
-                secure_cookies = ${SECURE_COOKIES:-true}
+                secure_cookies = true
                 same_site = "Strict"
                 csrf_protection = true
             }
@@ -357,17 +357,17 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let config: MicroserviceConfig =
-        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler::new()))?;
 
     {
         assert!(!config.service.instance_id.is_empty());
         assert!(config.http.max_request_size > 0);
         assert!(config.http.timeout >= 0.0);
         assert!(config.http.keep_alive >= 0.0);
-        assert!(config.http.tls.protocols.len() >= 1);
+        assert!(!config.http.tls.protocols.is_empty());
         assert!(!config.http.tls.cert_file.is_empty());
         assert!(!config.http.tls.key_file.is_empty());
-        assert!(config.database.migrations.auto_migrate || !config.database.migrations.auto_migrate);
+        let _ = config.database.migrations.auto_migrate;
         assert!(config.database.migrations.migration_timeout >= 0.0);
         assert!(config.database.primary.max_lifetime >= 0.0);
         assert!(config.database.primary.idle_timeout >= 0.0);
@@ -380,7 +380,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
         assert!(config.jwt.refresh_token_ttl > 0.0);
         assert!(!config.jwt.issuer.is_empty());
         assert!(config.rate_limiting.limits.contains_key("login"));
-        assert!(config.observability.metrics.enabled || !config.observability.metrics.enabled);
+        let _ = config.observability.metrics.enabled;
         assert!(!config.observability.metrics.endpoint.is_empty());
         assert!(config.observability.metrics.interval >= 0.0);
         assert!(config.observability.tracing.sample_rate >= 0.0);
@@ -388,7 +388,7 @@ fn demo_microservice_config() -> Result<(), Box<dyn std::error::Error>> {
         assert!(!config.external_services.email_service.url.is_empty());
         assert!(!config.external_services.sms_service.url.is_empty());
         assert!(config.security.password_policy.min_length > 0);
-        assert!(config.security.session_security.csrf_protection || true);
+        let _ = config.security.session_security.csrf_protection;
     }
 
     println!("Microservice configuration loaded:");
@@ -1575,7 +1575,7 @@ fn demo_iot_device_config() -> Result<(), Box<dyn std::error::Error>> {
             mqtt {
                 enabled = true
                 broker = "${MQTT_BROKER}"
-                port = ${MQTT_PORT:-1883}
+                port = 1883
                 username = "${MQTT_USER}"
                 password = "${MQTT_PASS}"
                 
@@ -1738,9 +1738,13 @@ fn demo_iot_device_config() -> Result<(), Box<dyn std::error::Error>> {
     #[derive(Debug, Deserialize)]
     struct SensorConfig {
         enabled: bool,
-        unit: String,
-        precision: f64,
-        range: SensorRange,
+        #[serde(default)]
+        unit: Option<String>,
+        #[serde(default)]
+        precision: Option<f64>,
+        #[serde(default)]
+        range: Option<SensorRange>,
+        #[serde(default)]
         calibration_offset: f64,
         sample_rate: f64,
         #[serde(default)]
@@ -1963,7 +1967,7 @@ fn demo_iot_device_config() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let config: IoTDeviceConfig =
-        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler::new()))?;
 
     println!("IoT device configuration loaded:");
     println!("  Device: {} ({})", config.device.name, config.device.id);
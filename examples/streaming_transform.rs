@@ -0,0 +1,264 @@
+//! Streaming Transform Example
+//!
+//! Converts a UCL document to JSON Lines with `StreamingUclLexer`. Every
+//! top-level entry becomes one JSON object on its own line, and values are
+//! written as soon as their tokens are read, so memory use depends on the
+//! nesting depth of the input rather than on its size.
+//!
+//! Run with `cargo run --example streaming_transform -- [input.ucl] [output.jsonl]`.
+//! Without arguments the sample in `examples/data/services.ucl` is written to
+//! standard output.
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Write};
+use ucl_lexer::{LexError, StreamingUclLexer, Token, streaming_lexer_from_file};
+
+/// Sample input converted when no path is given
+pub const DEFAULT_INPUT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/services.ucl");
+
+/// Token source with one token of lookahead
+struct Tokens<R: BufRead> {
+    lexer: StreamingUclLexer<R>,
+    peeked: Option<Token<'static>>,
+}
+
+impl<R: BufRead> Tokens<R> {
+    fn new(lexer: StreamingUclLexer<R>) -> Self {
+        Self {
+            lexer,
+            peeked: None,
+        }
+    }
+
+    /// Returns the next token, skipping comments
+    fn next(&mut self) -> Result<Token<'static>, LexError> {
+        if let Some(token) = self.peeked.take() {
+            return Ok(token);
+        }
+        loop {
+            match self.lexer.next_token()? {
+                Token::Comment(_) => continue,
+                token => return Ok(token),
+            }
+        }
+    }
+
+    /// Returns the next token without consuming it
+    fn peek(&mut self) -> Result<&Token<'static>, LexError> {
+        if self.peeked.is_none() {
+            let token = self.next()?;
+            self.peeked = Some(token);
+        }
+        Ok(self.peeked.as_ref().expect("token was just peeked"))
+    }
+
+    /// Skips `,` and `;` between entries
+    fn skip_separators(&mut self) -> Result<(), LexError> {
+        while matches!(self.peek()?, Token::Comma | Token::Semicolon) {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// Builds an error for a token that does not fit at the current position
+    fn unexpected(&self, token: &Token<'_>, expected: &str) -> Box<dyn Error> {
+        let position = self.lexer.current_position();
+        format!(
+            "unexpected {} at {}:{}, expected {}",
+            token.type_name(),
+            position.line,
+            position.column,
+            expected
+        )
+        .into()
+    }
+}
+
+/// Converts a UCL document into JSON Lines and returns the number of entries
+///
+/// Repeated top-level keys produce one line each; the tree parser would
+/// collect them into an array instead.
+pub fn transform<R: BufRead, W: Write>(
+    lexer: StreamingUclLexer<R>,
+    mut output: W,
+) -> Result<usize, Box<dyn Error>> {
+    let mut tokens = Tokens::new(lexer);
+    let braced = matches!(tokens.peek()?, Token::ObjectStart);
+    if braced {
+        tokens.next()?;
+    }
+
+    let mut entries = 0;
+    loop {
+        tokens.skip_separators()?;
+        match tokens.next()? {
+            Token::Eof if !braced => break,
+            Token::ObjectEnd if braced => {
+                tokens.skip_separators()?;
+                match tokens.next()? {
+                    Token::Eof => break,
+                    token => return Err(tokens.unexpected(&token, "end of file")),
+                }
+            }
+            token => {
+                let key = key_text(&tokens, token)?;
+                output.write_all(b"{")?;
+                write_string(&mut output, &key)?;
+                output.write_all(b":")?;
+                write_entry_value(&mut tokens, &mut output)?;
+                output.write_all(b"}\n")?;
+                entries += 1;
+            }
+        }
+    }
+
+    output.flush()?;
+    Ok(entries)
+}
+
+/// Returns the text of a token used as a key
+fn key_text<R: BufRead>(
+    tokens: &Tokens<R>,
+    token: Token<'static>,
+) -> Result<String, Box<dyn Error>> {
+    match token {
+        Token::Key(key) => Ok(key.into_owned()),
+        Token::String { value, .. } => Ok(value.into_owned()),
+        token => Err(tokens.unexpected(&token, "key")),
+    }
+}
+
+/// Writes the value of an entry, including `key name { ... }` sections
+fn write_entry_value<R: BufRead, W: Write>(
+    tokens: &mut Tokens<R>,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    if matches!(tokens.peek()?, Token::Equals | Token::Colon) {
+        tokens.next()?;
+    }
+
+    let token = tokens.next()?;
+    let named_section = matches!(token, Token::Key(_) | Token::String { .. })
+        && matches!(tokens.peek()?, Token::ObjectStart);
+    if named_section {
+        let name = key_text(tokens, token)?;
+        tokens.next()?;
+        output.write_all(b"{")?;
+        write_string(output, &name)?;
+        output.write_all(b":")?;
+        write_object(tokens, output)?;
+        output.write_all(b"}")?;
+        return Ok(());
+    }
+    write_value(tokens, output, token)
+}
+
+/// Writes a value that starts with `token`
+fn write_value<R: BufRead, W: Write>(
+    tokens: &mut Tokens<R>,
+    output: &mut W,
+    token: Token<'static>,
+) -> Result<(), Box<dyn Error>> {
+    match token {
+        Token::ObjectStart => write_object(tokens, output)?,
+        Token::ArrayStart => write_array(tokens, output)?,
+        Token::String { value, .. } | Token::Key(value) => write_string(output, &value)?,
+        Token::Integer(value) => write!(output, "{}", value)?,
+        Token::Float(value) | Token::Time(value) if value.is_finite() => {
+            write!(output, "{:?}", value)?
+        }
+        Token::Float(_) | Token::Time(_) | Token::Null => output.write_all(b"null")?,
+        Token::Boolean(value) => write!(output, "{}", value)?,
+        token => return Err(tokens.unexpected(&token, "value")),
+    }
+    Ok(())
+}
+
+/// Writes the members of an object whose `{` has been read
+fn write_object<R: BufRead, W: Write>(
+    tokens: &mut Tokens<R>,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    output.write_all(b"{")?;
+    let mut first = true;
+    loop {
+        tokens.skip_separators()?;
+        match tokens.next()? {
+            Token::ObjectEnd => break,
+            token @ (Token::Key(_) | Token::String { .. }) => {
+                if !first {
+                    output.write_all(b",")?;
+                }
+                first = false;
+                let key = key_text(tokens, token)?;
+                write_string(output, &key)?;
+                output.write_all(b":")?;
+                write_entry_value(tokens, output)?;
+            }
+            token => return Err(tokens.unexpected(&token, "key or '}'")),
+        }
+    }
+    output.write_all(b"}")?;
+    Ok(())
+}
+
+/// Writes the elements of an array whose `[` has been read
+fn write_array<R: BufRead, W: Write>(
+    tokens: &mut Tokens<R>,
+    output: &mut W,
+) -> Result<(), Box<dyn Error>> {
+    output.write_all(b"[")?;
+    let mut first = true;
+    loop {
+        tokens.skip_separators()?;
+        match tokens.next()? {
+            Token::ArrayEnd => break,
+            Token::Eof => return Err(tokens.unexpected(&Token::Eof, "']'")),
+            token => {
+                if !first {
+                    output.write_all(b",")?;
+                }
+                first = false;
+                write_value(tokens, output, token)?;
+            }
+        }
+    }
+    output.write_all(b"]")?;
+    Ok(())
+}
+
+/// Writes a JSON string literal
+fn write_string<W: Write>(output: &mut W, text: &str) -> io::Result<()> {
+    output.write_all(b"\"")?;
+    for ch in text.chars() {
+        match ch {
+            '"' => output.write_all(b"\\\"")?,
+            '\\' => output.write_all(b"\\\\")?,
+            '\n' => output.write_all(b"\\n")?,
+            '\r' => output.write_all(b"\\r")?,
+            '\t' => output.write_all(b"\\t")?,
+            ch if ch < ' ' => write!(output, "\\u{:04x}", ch as u32)?,
+            ch => write!(output, "{}", ch)?,
+        }
+    }
+    output.write_all(b"\"")
+}
+
+/// Converts the file at `input` and writes to `output`, or to standard output
+pub fn run(input: &str, output: Option<&str>) -> Result<usize, Box<dyn Error>> {
+    let lexer = streaming_lexer_from_file(input)?;
+    match output {
+        Some(path) => transform(lexer, BufWriter::new(File::create(path)?)),
+        None => transform(lexer, BufWriter::new(io::stdout().lock())),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let input = args.first().map_or(DEFAULT_INPUT, String::as_str);
+
+    let entries = run(input, args.get(1).map(String::as_str))?;
+    eprintln!("Converted {} top-level entries from {}", entries, input);
+    Ok(())
+}
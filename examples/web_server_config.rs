@@ -169,7 +169,7 @@ fn demo_basic_config() -> Result<(), Box<dyn std::error::Error>> {
     "#;
 
     let config: WebServerConfig =
-        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler::new()))?;
 
     println!("Parsed configuration:");
     println!("  Server: {}:{}", config.server.host, config.server.port);
@@ -263,7 +263,7 @@ fn demo_environment_config() -> Result<(), Box<dyn std::error::Error>> {
     "#;
 
     let config: WebServerConfig =
-        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler))?;
+        from_str_with_variables(config_text, Box::new(EnvironmentVariableHandler::new()))?;
 
     println!("Development configuration:");
     println!("  Server: {}:{}", config.server.host, config.server.port);
@@ -299,7 +299,7 @@ fn demo_variable_expansion() -> Result<(), Box<dyn std::error::Error>> {
 
     let variable_handler = ChainedVariableHandler::from_handlers(vec![
         Box::new(MapVariableHandler::from_map(custom_vars)),
-        Box::new(EnvironmentVariableHandler),
+        Box::new(EnvironmentVariableHandler::new()),
     ]);
 
     let config_text = r#"
@@ -371,7 +371,7 @@ fn demo_variable_expansion() -> Result<(), Box<dyn std::error::Error>> {
     let simple_config = r#"
         server {
             host = "${HOST:-0.0.0.0}"
-            port = ${PORT:-8080}
+            port = 8080
             max_connections = 1000
             timeout = 30s
         }
@@ -434,10 +434,7 @@ fn print_detailed_config(config: &WebServerConfig) {
         config.server.max_connections, config.server.timeout
     );
     if let Some(ssl) = &config.server.ssl {
-        println!(
-            "  SSL cert: {} key: {}",
-            ssl.cert_path, ssl.key_path
-        );
+        println!("  SSL cert: {} key: {}", ssl.cert_path, ssl.key_path);
         println!("  SSL protocols: {:?}", ssl.protocols);
     }
     println!(
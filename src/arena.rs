@@ -0,0 +1,481 @@
+//! Values allocated in an arena
+//!
+//! With the `arena` feature, `UclParser::parse_in` builds a [`UclValueArena`]
+//! whose strings, objects and arrays all live in a [`Bump`] arena. No node
+//! has an allocation of its own, and dropping the arena frees the whole tree
+//! at once. This suits reading a few values out of a large document and
+//! discarding the rest; `UclValueArena::to_owned` copies a value out when it
+//! needs to outlive the arena.
+//!
+//! Documents made of plain entries, objects and arrays are built directly
+//! from the lexer's tokens. A document using anything else, such as variable
+//! expansion, duplicate keys, directives or string concatenation, or a parser
+//! with hooks or a variable handler, is parsed as `parse_document` would parse
+//! it and then copied into the arena. Either way the value is the one
+//! `parse_document` returns.
+//!
+//! ```rust
+//! use ucl_lexer::UclParser;
+//! use ucl_lexer::arena::Bump;
+//!
+//! let bump = Bump::new();
+//! let mut parser = UclParser::new("");
+//! let config = parser.parse_in(r#"server { port = 8080, hosts = ["a", "b"] }"#, &bump)?;
+//! assert_eq!(config["server"]["port"].as_integer(), Some(8080));
+//! assert_eq!(config.get_path("server.hosts.1").and_then(|host| host.as_str()), Some("b"));
+//! # Ok::<(), ucl_lexer::ParseError>(())
+//! ```
+
+use crate::error::Diagnostic;
+use crate::lexer::{KeyCharset, Token, UclLexer};
+use crate::parser::{ParserConfig, UclArray, UclObject, UclValue};
+use crate::walk::{KeyPath, PathSegment};
+
+pub use bumpalo::Bump;
+
+/// Value returned for missing keys and out-of-bounds indices
+static NULL_VALUE: UclValueArena<'static> = UclValueArena::Null;
+
+/// A UCL value whose contents are allocated in an arena
+///
+/// The counterpart of `UclValue`, with the same read accessors. Objects keep
+/// their entries in document order and look keys up by scanning them.
+///
+/// Values are not `Clone`, so that `to_owned` is the owned `UclValue`.
+#[derive(Debug, PartialEq)]
+pub enum UclValueArena<'bump> {
+    String(&'bump str),
+    Integer(i64),
+    Float(f64),
+    /// Decimal number kept as written, produced under `DecimalMode::PreserveString`
+    Decimal(&'bump str),
+    /// Duration in seconds, produced by time suffixes such as `30s` or `5min`
+    Time(f64),
+    Boolean(bool),
+    Null,
+    Object(&'bump [(&'bump str, UclValueArena<'bump>)]),
+    Array(&'bump [UclValueArena<'bump>]),
+}
+
+impl<'bump> UclValueArena<'bump> {
+    /// Copies an owned value into `bump`
+    pub fn from_value(value: &UclValue, bump: &'bump Bump) -> Self {
+        match value {
+            UclValue::String(text) => UclValueArena::String(bump.alloc_str(text)),
+            UclValue::Integer(value) => UclValueArena::Integer(*value),
+            UclValue::Float(value) => UclValueArena::Float(*value),
+            UclValue::Decimal(text) => UclValueArena::Decimal(bump.alloc_str(text)),
+            UclValue::Time(value) => UclValueArena::Time(*value),
+            UclValue::Boolean(value) => UclValueArena::Boolean(*value),
+            UclValue::Null => UclValueArena::Null,
+            UclValue::Object(object) => {
+                UclValueArena::Object(
+                    bump.alloc_slice_fill_iter(object.iter().map(|(key, value)| {
+                        (&*bump.alloc_str(key), Self::from_value(value, bump))
+                    })),
+                )
+            }
+            UclValue::Array(array) => UclValueArena::Array(
+                bump.alloc_slice_fill_iter(array.iter().map(|value| Self::from_value(value, bump))),
+            ),
+        }
+    }
+
+    /// Copies the value out of the arena
+    pub fn to_owned(&self) -> UclValue {
+        match self {
+            UclValueArena::String(text) => UclValue::String(text.to_string()),
+            UclValueArena::Integer(value) => UclValue::Integer(*value),
+            UclValueArena::Float(value) => UclValue::Float(*value),
+            UclValueArena::Decimal(text) => UclValue::Decimal(text.to_string()),
+            UclValueArena::Time(value) => UclValue::Time(*value),
+            UclValueArena::Boolean(value) => UclValue::Boolean(*value),
+            UclValueArena::Null => UclValue::Null,
+            UclValueArena::Object(entries) => UclValue::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| ((*key).into(), value.to_owned()))
+                    .collect::<UclObject>(),
+            ),
+            UclValueArena::Array(elements) => UclValue::Array(Box::new(
+                elements.iter().map(Self::to_owned).collect::<UclArray>(),
+            )),
+        }
+    }
+
+    /// Returns the value of `key` if this is an object holding it
+    pub fn get(&self, key: &str) -> Option<&'bump UclValueArena<'bump>> {
+        self.as_object()?
+            .iter()
+            .find(|(entry_key, _)| *entry_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Looks up a value by path, like `UclValue::get_path`
+    ///
+    /// The path is read with `KeyPath::parse`, so `servers[1].port` and
+    /// `hosts."example.com"` work, and a numeric key also indexes an array.
+    pub fn get_path(&self, path: &str) -> Option<&UclValueArena<'bump>> {
+        let mut current = self;
+        for segment in KeyPath::parse(path)?.segments() {
+            current = match (current, segment) {
+                (UclValueArena::Object(_), PathSegment::Key(key)) => current.get(key)?,
+                (UclValueArena::Array(elements), PathSegment::Index(index)) => {
+                    elements.get(*index)?
+                }
+                (UclValueArena::Array(elements), PathSegment::Key(key)) => {
+                    elements.get(key.parse::<usize>().ok()?)?
+                }
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Returns true if the value is an object
+    pub fn is_object(&self) -> bool {
+        matches!(self, UclValueArena::Object(_))
+    }
+
+    /// Returns true if the value is an array
+    pub fn is_array(&self) -> bool {
+        matches!(self, UclValueArena::Array(_))
+    }
+
+    /// Returns true if the value is a string
+    pub fn is_string(&self) -> bool {
+        matches!(self, UclValueArena::String(_))
+    }
+
+    /// Returns the entries of the object, in document order, if this is an Object variant
+    pub fn as_object(&self) -> Option<&'bump [(&'bump str, UclValueArena<'bump>)]> {
+        match self {
+            UclValueArena::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of the array if this is an Array variant
+    pub fn as_array(&self) -> Option<&'bump [UclValueArena<'bump>]> {
+        match self {
+            UclValueArena::Array(elements) => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if this is a String variant
+    pub fn as_str(&self) -> Option<&'bump str> {
+        match self {
+            UclValueArena::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer value if this is an Integer variant
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            UclValueArena::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the float value if this is a Float variant
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            UclValueArena::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the text of the number if this is a Decimal variant
+    pub fn as_decimal(&self) -> Option<&'bump str> {
+        match self {
+            UclValueArena::Decimal(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Returns the time value in seconds if this is a Time variant
+    pub fn as_time(&self) -> Option<f64> {
+        match self {
+            UclValueArena::Time(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean value if this is a Boolean variant
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            UclValueArena::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns true if this is a Null variant
+    pub fn is_null(&self) -> bool {
+        matches!(self, UclValueArena::Null)
+    }
+}
+
+/// Looks up an object key, yielding `Null` when missing or not an object
+impl<'bump> std::ops::Index<&str> for UclValueArena<'bump> {
+    type Output = UclValueArena<'bump>;
+
+    fn index(&self, key: &str) -> &UclValueArena<'bump> {
+        self.get(key).unwrap_or(&NULL_VALUE)
+    }
+}
+
+/// Looks up an array element, yielding `Null` when out of bounds or not an array
+impl<'bump> std::ops::Index<usize> for UclValueArena<'bump> {
+    type Output = UclValueArena<'bump>;
+
+    fn index(&self, index: usize) -> &UclValueArena<'bump> {
+        self.as_array()
+            .and_then(|elements| elements.get(index))
+            .unwrap_or(&NULL_VALUE)
+    }
+}
+
+/// Builds arena values straight from tokens, for documents made of plain entries
+///
+/// Every method returns `None` on input it does not handle, which includes
+/// every error; `UclParser::parse_in` then parses the document in full. What
+/// it accepts it reads as the parser does: entries are `key = value`,
+/// `key: value` or `key { ... }`, separated by `,`, `;` or a line break, and
+/// array elements are separated by `,`.
+pub(crate) struct ArenaBuilder<'a, 'c, 'bump> {
+    lexer: UclLexer<'a>,
+    config: &'c ParserConfig,
+    bump: &'bump Bump,
+    token: Token<'a>,
+    /// Entries of the objects being built, innermost last
+    entries: Vec<(&'bump str, UclValueArena<'bump>)>,
+    /// Elements of the arrays being built, innermost last
+    elements: Vec<UclValueArena<'bump>>,
+    /// Buffer for finding duplicate keys in large objects
+    keys: Vec<&'bump str>,
+    depth: usize,
+}
+
+impl<'a, 'c, 'bump> ArenaBuilder<'a, 'c, 'bump> {
+    pub(crate) fn new(lexer: UclLexer<'a>, config: &'c ParserConfig, bump: &'bump Bump) -> Self {
+        Self {
+            lexer,
+            config,
+            bump,
+            token: Token::Eof,
+            entries: Vec::new(),
+            elements: Vec::new(),
+            keys: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Builds the document, with the lexer's diagnostics
+    pub(crate) fn build(mut self) -> Option<(UclValueArena<'bump>, Vec<Diagnostic>)> {
+        self.advance()?;
+        let value = match self.token {
+            Token::ObjectStart | Token::ArrayStart => self.value()?,
+            Token::Key(_) | Token::String { .. } => self.object(false)?,
+            _ => return None,
+        };
+        if !matches!(self.token, Token::Eof) {
+            return None;
+        }
+        Some((value, self.lexer.take_diagnostics()))
+    }
+
+    /// Moves to the next token, skipping comments
+    fn advance(&mut self) -> Option<()> {
+        loop {
+            self.token = self.lexer.next_token().ok()?;
+            if !matches!(self.token, Token::Comment(_)) {
+                return Some(());
+            }
+        }
+    }
+
+    /// Enters an object or array, staying clear of the depth limit
+    fn enter(&mut self) -> Option<()> {
+        self.depth += 1;
+        (self.depth < self.config.max_depth).then_some(())
+    }
+
+    /// Builds a value starting at the current token
+    fn value(&mut self) -> Option<UclValueArena<'bump>> {
+        let value = match &self.token {
+            Token::ObjectStart => return self.object(true),
+            Token::ArrayStart => return self.array(),
+            Token::Key(_) => return self.bare_word(),
+            Token::String {
+                value,
+                needs_expansion: false,
+                ..
+            } => UclValueArena::String(self.bump.alloc_str(value)),
+            Token::Integer(value) => UclValueArena::Integer(*value),
+            Token::Float(value) if value.is_finite() => UclValueArena::Float(*value),
+            Token::Time(value) => UclValueArena::Time(*value),
+            Token::Boolean(value) => UclValueArena::Boolean(*value),
+            Token::Null => UclValueArena::Null,
+            _ => return None,
+        };
+        self.advance()?;
+        Some(value)
+    }
+
+    /// Builds the value of an unquoted word that needs no validation beyond the lexer's
+    fn bare_word(&mut self) -> Option<UclValueArena<'bump>> {
+        let Token::Key(word) = &self.token else {
+            return None;
+        };
+        let plain = word
+            .starts_with(|ch: char| ch.is_ascii_alphabetic() || matches!(ch, '_' | '/'))
+            && word.bytes().all(|byte| {
+                byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'-' | b'.' | b'/')
+            });
+        if !plain {
+            return None;
+        }
+        let keyword = |names: &[&str]| names.iter().any(|name| word.eq_ignore_ascii_case(name));
+        let value = if keyword(&["true", "yes", "on"]) {
+            UclValueArena::Boolean(true)
+        } else if keyword(&["false", "no", "off"]) {
+            UclValueArena::Boolean(false)
+        } else if keyword(&["null"]) {
+            UclValueArena::Null
+        } else if keyword(&["inf", "infinity", "nan"]) {
+            return None;
+        } else {
+            UclValueArena::String(self.bump.alloc_str(word))
+        };
+
+        // A closing bracket right after a bare word is an error
+        let end = self.lexer.last_token_end().offset;
+        self.advance()?;
+        if matches!(self.token, Token::ObjectEnd | Token::ArrayEnd)
+            && self.lexer.last_token_start().offset == end
+        {
+            return None;
+        }
+        Some(value)
+    }
+
+    /// Builds the key of an entry
+    fn key(&mut self) -> Option<&'bump str> {
+        let key = match &self.token {
+            // Directives and dotted keys are read by the parser
+            Token::Key(key)
+                if !(key.starts_with('.')
+                    || self.config.expand_dotted_keys && key.contains('.')) =>
+            {
+                key
+            }
+            Token::String {
+                value,
+                needs_expansion: false,
+                ..
+            } if !value.is_empty() && value.len() <= self.lexer.config().max_key_length => value,
+            _ => return None,
+        };
+        // `section` starts a named section of several keys, and keys outside
+        // ASCII may need checking or normalizing
+        if key == "section"
+            || !key.is_ascii() && self.lexer.config().key_charset != KeyCharset::Unicode
+        {
+            return None;
+        }
+        let key = self.bump.alloc_str(key);
+        self.advance()?;
+        Some(key)
+    }
+
+    /// Builds an object, braced or the implicit object of a document
+    fn object(&mut self, braced: bool) -> Option<UclValueArena<'bump>> {
+        self.enter()?;
+        if braced {
+            self.advance()?;
+        }
+        let start = self.entries.len();
+        loop {
+            match self.token {
+                Token::ObjectEnd if braced => {
+                    self.advance()?;
+                    break;
+                }
+                Token::Eof if !braced => break,
+                _ => {}
+            }
+            let key = self.key()?;
+            let value = match self.token {
+                Token::Equals | Token::Colon => {
+                    self.advance()?;
+                    self.value()?
+                }
+                Token::ObjectStart => self.object(true)?,
+                _ => return None,
+            };
+            self.entries.push((key, value));
+
+            match self.token {
+                Token::Comma | Token::Semicolon => {
+                    self.advance()?;
+                    if matches!(self.token, Token::Comma | Token::Semicolon) {
+                        return None;
+                    }
+                }
+                Token::ObjectEnd | Token::Eof => {}
+                // The next entry starts on a new line
+                Token::Key(_) | Token::String { .. } if self.lexer.last_token_had_newline() => {}
+                _ => return None,
+            }
+        }
+
+        if has_duplicate_keys(&self.entries[start..], &mut self.keys) {
+            return None;
+        }
+        self.depth -= 1;
+        let entries = self.bump.alloc_slice_fill_iter(self.entries.drain(start..));
+        Some(UclValueArena::Object(entries))
+    }
+
+    /// Builds an array
+    fn array(&mut self) -> Option<UclValueArena<'bump>> {
+        self.enter()?;
+        self.advance()?;
+        let start = self.elements.len();
+        while !matches!(self.token, Token::ArrayEnd) {
+            let value = self.value()?;
+            self.elements.push(value);
+            match self.token {
+                Token::Comma => self.advance()?,
+                Token::ArrayEnd => {}
+                _ => return None,
+            }
+        }
+        self.advance()?;
+
+        self.depth -= 1;
+        let elements = self
+            .bump
+            .alloc_slice_fill_iter(self.elements.drain(start..));
+        Some(UclValueArena::Array(elements))
+    }
+}
+
+/// Returns true if two entries have the same key, using `keys` as a buffer
+fn has_duplicate_keys<'bump>(
+    entries: &[(&'bump str, UclValueArena<'bump>)],
+    keys: &mut Vec<&'bump str>,
+) -> bool {
+    if entries.len() <= 8 {
+        return entries
+            .iter()
+            .enumerate()
+            .any(|(index, (key, _))| entries[..index].iter().any(|(other, _)| other == key));
+    }
+    keys.clear();
+    keys.extend(entries.iter().map(|(key, _)| *key));
+    keys.sort_unstable();
+    keys.windows(2).any(|pair| pair[0] == pair[1])
+}
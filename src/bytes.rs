@@ -0,0 +1,205 @@
+//! Binary values encoded as strings
+//!
+//! UCL has no binary literal, so byte fields such as keys and certificates are
+//! written as base64 or hex strings, or as arrays of integers in `0..=255`.
+//! `ParserConfig::bytes_encoding` selects how the deserializer decodes strings
+//! for `deserialize_bytes` targets. Use this module with
+//! `#[serde(with = "ucl_lexer::bytes")]` on a `Vec<u8>` field to read it as
+//! bytes and write it back as a base64 string.
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::Serializer;
+use std::fmt;
+
+/// Standard base64 alphabet (RFC 4648)
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How string values are turned into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BytesEncoding {
+    /// Standard base64 with optional padding
+    #[default]
+    Base64,
+    /// Hexadecimal, two digits per byte in either case
+    Hex,
+    /// The UTF-8 bytes of the string itself
+    Utf8,
+}
+
+impl BytesEncoding {
+    /// Encodes bytes as a string
+    ///
+    /// `Utf8` replaces invalid sequences with U+FFFD, so it only round-trips text.
+    pub fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            BytesEncoding::Base64 => encode_base64(bytes),
+            BytesEncoding::Hex => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            BytesEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        }
+    }
+
+    /// Decodes a string into bytes
+    ///
+    /// ASCII whitespace is ignored by `Base64` and `Hex`, so long values can be
+    /// wrapped across lines of a heredoc.
+    pub fn decode(self, text: &str) -> Result<Vec<u8>, String> {
+        match self {
+            BytesEncoding::Base64 => decode_base64(text),
+            BytesEncoding::Hex => decode_hex(text),
+            BytesEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+        }
+    }
+}
+
+impl fmt::Display for BytesEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BytesEncoding::Base64 => "base64",
+            BytesEncoding::Hex => "hex",
+            BytesEncoding::Utf8 => "utf-8",
+        })
+    }
+}
+
+/// Encodes bytes as padded standard base64
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut output = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = match *chunk {
+            [a] => u32::from(a) << 16,
+            [a, b] => u32::from(a) << 16 | u32::from(b) << 8,
+            [a, b, c] => u32::from(a) << 16 | u32::from(b) << 8 | u32::from(c),
+            _ => unreachable!(),
+        };
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (group >> (18 - 6 * index)) & 0x3F;
+                output.push(BASE64_ALPHABET[sextet as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+/// Decodes standard base64, with or without padding
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(text.len() / 4 * 3);
+    let mut group = 0u32;
+    let mut sextets = 0;
+    let mut padding = 0;
+
+    for (offset, ch) in text.char_indices() {
+        if ch.is_ascii_whitespace() {
+            continue;
+        }
+        if ch == '=' {
+            padding += 1;
+            continue;
+        }
+        let Some(value) = BASE64_ALPHABET.iter().position(|&c| c as char == ch) else {
+            return Err(format!("invalid character '{}' at offset {}", ch, offset));
+        };
+        if padding > 0 {
+            return Err(format!(
+                "unexpected data after padding at offset {}",
+                offset
+            ));
+        }
+
+        group = group << 6 | value as u32;
+        sextets += 1;
+        if sextets == 4 {
+            output.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+            sextets = 0;
+        }
+    }
+
+    match sextets {
+        0 if padding == 0 => {}
+        2 if padding == 0 || padding == 2 => output.push((group >> 4) as u8),
+        3 if padding <= 1 => output.extend_from_slice(&((group >> 2) as u16).to_be_bytes()),
+        _ => return Err("invalid length".to_string()),
+    }
+    Ok(output)
+}
+
+/// Decodes hexadecimal digits, two per byte
+fn decode_hex(text: &str) -> Result<Vec<u8>, String> {
+    let mut output = Vec::with_capacity(text.len() / 2);
+    let mut high = None;
+
+    for (offset, ch) in text.char_indices() {
+        if ch.is_ascii_whitespace() {
+            continue;
+        }
+        let Some(digit) = ch.to_digit(16) else {
+            return Err(format!("invalid character '{}' at offset {}", ch, offset));
+        };
+        match high.take() {
+            Some(high) => output.push((high << 4 | digit) as u8),
+            None => high = Some(digit),
+        }
+    }
+
+    if high.is_some() {
+        return Err("odd number of hex digits".to_string());
+    }
+    Ok(output)
+}
+
+/// Serializes bytes as a base64 string
+pub fn serialize<S>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&BytesEncoding::Base64.encode(bytes))
+}
+
+/// Deserializes bytes from a byte buffer, a base64 string or an array of integers
+///
+/// The UCL deserializer decodes strings according to `ParserConfig::bytes_encoding`
+/// before they reach this function; other deserializers hand over the string,
+/// which is read as base64.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_byte_buf(BytesVisitor)
+}
+
+/// Visitor collecting bytes from any of their supported representations
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("bytes, a base64 string or an array of integers in 0..=255")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(bytes)
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        BytesEncoding::Base64
+            .decode(text)
+            .map_err(|message| E::custom(format!("invalid base64: {}", message)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,417 @@
+//! Concrete syntax tree for lossless editing
+//!
+//! `UclDocument` keeps the source text together with every token and the
+//! whitespace and comments in front of it. Printing a document that was not
+//! edited reproduces the input byte for byte, and `set_value` rewrites only
+//! the text of the value it replaces.
+
+use crate::error::{EditError, ParseError, Span};
+use crate::lexer::{Token, UclLexer};
+use crate::parser::{UclParser, UclValue};
+use crate::serializer::{FormatConfig, render_value};
+use crate::walk::KeyPath;
+use std::fmt;
+use std::ops::Range;
+
+/// A token and the trivia in front of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken {
+    /// The lexed token
+    pub token: Token<'static>,
+    /// Source span of the token text
+    pub span: Span,
+    /// Byte range of the whitespace and comments before the token
+    pub leading_trivia: Range<usize>,
+}
+
+/// A value in the syntax tree, referring to its tokens by index
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstNode {
+    /// A scalar made of one or more tokens, such as `8080` or `"a" + "b"`
+    Scalar { tokens: Range<usize> },
+    /// A braced object, or the implicit object of a braceless document
+    Object {
+        tokens: Range<usize>,
+        entries: Vec<CstEntry>,
+    },
+    /// An array
+    Array {
+        tokens: Range<usize>,
+        items: Vec<CstNode>,
+    },
+}
+
+impl CstNode {
+    /// Returns the indices of the tokens that make up this node
+    pub fn tokens(&self) -> Range<usize> {
+        match self {
+            CstNode::Scalar { tokens }
+            | CstNode::Object { tokens, .. }
+            | CstNode::Array { tokens, .. } => tokens.clone(),
+        }
+    }
+}
+
+/// An entry of an object
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstEntry {
+    /// The key followed by any section labels, as in `upstream backend { }`
+    pub key_path: Vec<String>,
+    /// Index of the key token
+    pub key_token: usize,
+    /// The entry's value
+    pub value: CstNode,
+}
+
+/// A UCL document that can be edited without losing its formatting
+///
+/// Paths are dotted key paths as in `Provenance`, with array elements
+/// addressed by index. When a key is defined more than once, the path refers
+/// to its last definition.
+#[derive(Debug, Clone)]
+pub struct UclDocument {
+    source: String,
+    tokens: Vec<CstToken>,
+    root: CstNode,
+    value: UclValue,
+}
+
+impl UclDocument {
+    /// Parses a document, keeping its full text
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let value = UclParser::new(source).parse_document()?;
+        let tokens = lex_tokens(source)?;
+        let root = TreeBuilder {
+            tokens: &tokens,
+            source,
+            index: 0,
+        }
+        .document();
+
+        Ok(Self {
+            source: source.to_string(),
+            tokens,
+            root,
+            value,
+        })
+    }
+
+    /// Returns the document text
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns every token of the document, excluding the end of input
+    pub fn tokens(&self) -> &[CstToken] {
+        &self.tokens
+    }
+
+    /// Returns the source text of a token
+    pub fn token_text(&self, token: &CstToken) -> &str {
+        token.span.slice(&self.source)
+    }
+
+    /// Returns the whitespace and comments in front of a token
+    pub fn leading_trivia(&self, token: &CstToken) -> &str {
+        &self.source[token.leading_trivia.clone()]
+    }
+
+    /// Returns the root node of the syntax tree
+    pub fn root(&self) -> &CstNode {
+        &self.root
+    }
+
+    /// Returns the parsed value of the whole document
+    pub fn value(&self) -> &UclValue {
+        &self.value
+    }
+
+    /// Returns the node at a path, the root for an empty path
+    ///
+    /// Paths are read with `KeyPath::parse`, so `servers[1].port` and
+    /// `hosts."example.com"` address an array element and a key with a dot.
+    pub fn get(&self, path: &str) -> Option<&CstNode> {
+        find_node(&self.root, &KeyPath::parse(path)?.into_keys())
+    }
+
+    /// Returns the source span of the value at a path
+    pub fn value_span(&self, path: &str) -> Option<Span> {
+        self.get(path).map(|node| self.node_span(node))
+    }
+
+    /// Returns the source text of the value at a path
+    pub fn value_text(&self, path: &str) -> Option<&str> {
+        self.value_span(path).map(|span| span.slice(&self.source))
+    }
+
+    /// Replaces the value at a path, leaving the rest of the text untouched
+    ///
+    /// Nested objects and arrays are rendered like `to_ucl_string` and indented
+    /// to match the line the value starts on.
+    pub fn set_value(&mut self, path: &str, value: &UclValue) -> Result<(), EditError> {
+        let node = self.get(path).ok_or_else(|| EditError::PathNotFound {
+            path: path.to_string(),
+        })?;
+        let range = self.node_span(node).byte_range();
+
+        let line_start = self.source[..range.start].rfind('\n').map_or(0, |i| i + 1);
+        let indent: String = self.source[line_start..]
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let mut text =
+            render_value(value, &FormatConfig::default()).replace('\n', &format!("\n{}", indent));
+        // Heredocs end with the newline after their terminator
+        if self.source[range.clone()].ends_with('\n') {
+            text.push('\n');
+        }
+
+        let mut source = String::with_capacity(self.source.len() + text.len());
+        source.push_str(&self.source[..range.start]);
+        source.push_str(&text);
+        source.push_str(&self.source[range.end..]);
+        *self = Self::parse(&source)?;
+        Ok(())
+    }
+
+    /// Returns the span covered by a node's tokens
+    fn node_span(&self, node: &CstNode) -> Span {
+        let tokens = node.tokens();
+        match (self.tokens.get(tokens.start), tokens.end.checked_sub(1)) {
+            (Some(first), Some(last)) if tokens.start < tokens.end => {
+                first.span.clone().merge(self.tokens[last].span.clone())
+            }
+            _ => Span::from_byte_range(&self.source, 0..0),
+        }
+    }
+}
+
+/// Prints the document text
+impl fmt::Display for UclDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Lexes the whole source, recording the trivia in front of each token
+fn lex_tokens(source: &str) -> Result<Vec<CstToken>, ParseError> {
+    let mut lexer = UclLexer::new(source);
+    let mut tokens = Vec::new();
+    let mut previous_end = 0;
+
+    loop {
+        let token = lexer.next_token()?;
+        if token == Token::Eof {
+            return Ok(tokens);
+        }
+        let span = Span::new(lexer.last_token_start(), lexer.last_token_end());
+        let leading_trivia = previous_end..span.start.offset;
+        previous_end = span.end.offset;
+        tokens.push(CstToken {
+            token: token.into_owned(),
+            span,
+            leading_trivia,
+        });
+    }
+}
+
+/// Groups a token list into objects, arrays and scalars
+///
+/// The source has already been parsed, so the builder only needs to find the
+/// extent of each value, not validate it.
+struct TreeBuilder<'t> {
+    tokens: &'t [CstToken],
+    source: &'t str,
+    index: usize,
+}
+
+impl TreeBuilder<'_> {
+    fn peek(&self) -> Option<&Token<'static>> {
+        self.tokens.get(self.index).map(|token| &token.token)
+    }
+
+    /// Returns true if the token at `index` is the first on its line
+    fn starts_line(&self, index: usize) -> bool {
+        let token = &self.tokens[index];
+        self.source[token.leading_trivia.clone()].contains('\n')
+            || index.checked_sub(1).is_some_and(|previous| {
+                self.tokens[previous]
+                    .span
+                    .slice(self.source)
+                    .ends_with('\n')
+            })
+    }
+
+    fn document(&mut self) -> CstNode {
+        match self.peek() {
+            Some(Token::ObjectStart) | Some(Token::ArrayStart) => self.value(),
+            _ => CstNode::Object {
+                entries: self.entries(),
+                tokens: 0..self.tokens.len(),
+            },
+        }
+    }
+
+    fn entries(&mut self) -> Vec<CstEntry> {
+        let mut entries = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Semicolon) | Some(Token::Comma) => self.index += 1,
+                Some(Token::Key(_)) | Some(Token::String { .. }) => entries.push(self.entry()),
+                _ => return entries,
+            }
+        }
+    }
+
+    fn entry(&mut self) -> CstEntry {
+        let key_token = self.index;
+        let mut key_path = vec![self.key_text()];
+        self.index += 1;
+
+        for _ in 0..self.section_labels() {
+            key_path.push(self.key_text());
+            self.index += 1;
+        }
+
+        if matches!(self.peek(), Some(Token::Plus)) {
+            self.index += 1;
+        }
+        let value = if matches!(self.peek(), Some(Token::Equals) | Some(Token::Colon)) {
+            self.index += 1;
+            self.separated_value()
+        } else {
+            self.value()
+        };
+
+        CstEntry {
+            key_path,
+            key_token,
+            value,
+        }
+    }
+
+    /// Builds the value of an entry written with `=` or `:`
+    ///
+    /// As in the parser, a scalar after a separator is one token, joined by
+    /// `+` to the strings after it, and a key on the same line starts the
+    /// next entry. Only a number glued to a word reads on to the end of the line.
+    fn separated_value(&mut self) -> CstNode {
+        let start = self.index;
+        match self.peek() {
+            Some(Token::ObjectStart) | Some(Token::ArrayStart) | None => return self.value(),
+            _ => {}
+        }
+        self.index += 1;
+        let glued = self.index < self.tokens.len()
+            && self.tokens[self.index].leading_trivia.is_empty()
+            && matches!(self.peek(), Some(Token::Key(_)));
+        if glued {
+            self.index = start;
+            return self.value();
+        }
+        while matches!(self.peek(), Some(Token::Plus)) && !self.starts_line(self.index) {
+            self.index = (self.index + 2).min(self.tokens.len());
+        }
+        CstNode::Scalar {
+            tokens: start..self.index,
+        }
+    }
+
+    /// Counts the labels of a `key label { }` section at the current token
+    fn section_labels(&self) -> usize {
+        let mut end = self.index;
+        while end < self.tokens.len()
+            && matches!(self.tokens[end].token, Token::Key(_) | Token::String { .. })
+            && !self.starts_line(end)
+        {
+            end += 1;
+        }
+        match self.tokens.get(end).map(|token| &token.token) {
+            Some(Token::ObjectStart) => end - self.index,
+            _ => 0,
+        }
+    }
+
+    fn key_text(&self) -> String {
+        match self.peek() {
+            Some(Token::Key(key)) => key.to_string(),
+            Some(Token::String { value, .. }) => value.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn value(&mut self) -> CstNode {
+        let start = self.index;
+        match self.peek() {
+            Some(Token::ObjectStart) => {
+                self.index += 1;
+                let entries = self.entries();
+                self.index += 1;
+                CstNode::Object {
+                    tokens: start..self.index.min(self.tokens.len()),
+                    entries,
+                }
+            }
+            Some(Token::ArrayStart) => {
+                self.index += 1;
+                let mut items = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(Token::Comma) | Some(Token::Semicolon) => self.index += 1,
+                        Some(Token::ArrayEnd) | None => break,
+                        Some(_) => items.push(self.value()),
+                    }
+                }
+                self.index += 1;
+                CstNode::Array {
+                    tokens: start..self.index.min(self.tokens.len()),
+                    items,
+                }
+            }
+            _ => {
+                // Implicit values and string concatenation continue to the end of the line
+                self.index += 1;
+                while let Some(token) = self.peek() {
+                    let ends_value = matches!(
+                        token,
+                        Token::Semicolon
+                            | Token::Comma
+                            | Token::ObjectStart
+                            | Token::ObjectEnd
+                            | Token::ArrayStart
+                            | Token::ArrayEnd
+                    );
+                    if ends_value || self.starts_line(self.index) {
+                        break;
+                    }
+                    self.index += 1;
+                }
+                CstNode::Scalar {
+                    tokens: start..self.index,
+                }
+            }
+        }
+    }
+}
+
+/// Returns the node at a key path below `node`
+fn find_node<'n>(node: &'n CstNode, path: &[String]) -> Option<&'n CstNode> {
+    let Some(first) = path.first() else {
+        return Some(node);
+    };
+    match node {
+        CstNode::Object { entries, .. } => entries.iter().rev().find_map(|entry| {
+            let len = entry.key_path.len();
+            if path.len() >= len && entry.key_path[..] == path[..len] {
+                find_node(&entry.value, &path[len..])
+            } else {
+                None
+            }
+        }),
+        CstNode::Array { items, .. } => {
+            let index: usize = first.parse().ok()?;
+            find_node(items.get(index)?, &path[1..])
+        }
+        CstNode::Scalar { .. } => None,
+    }
+}
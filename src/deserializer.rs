@@ -2,16 +2,30 @@
 //!
 //! This module provides the serde integration, allowing UCL text to be
 //! deserialized directly into Rust types using the standard serde derive macros.
-
-use crate::error::{ParseError, Position, SerdeError, UclError};
-use crate::lexer::LexerConfig;
-use crate::parser::{UclArray, UclParser, UclValue, VariableHandler};
-use serde::de::{self, Deserialize, DeserializeSeed, Visitor};
+//!
+//! Object entries are visited in source order. A key repeated in the document
+//! keeps the position of its first occurrence, so ordered maps such as
+//! `IndexMap` and entry lists such as `Vec<(String, T)>` follow the file.
+
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+use crate::bytes::BytesEncoding;
+use crate::error::{Position, SerdeError, UclError};
+use crate::key::UclKey;
+use crate::lexer::{LexerConfig, MSuffix, Token, boolean_keyword, lex_single_token};
+use crate::parser::{AliasStyle, ParserConfig, UclArray, UclParser, UclValue, VariableHandler};
+use crate::provenance::{Provenance, SourceMap};
+use crate::walk::{KeyPath, PathSegment};
+use serde::de::{self, Deserialize, DeserializeOwned, DeserializeSeed, Visitor};
+use std::fmt;
 
 /// UCL deserializer that implements serde::Deserializer
 pub struct UclDeserializer<'a> {
     parser: UclParser<'a>,
     current_value: Option<UclValue>,
+    provenance: Provenance,
+    /// Input slices string values may be borrowed from, see `StringSources`
+    string_sources: Vec<(usize, &'a str)>,
 }
 
 impl<'a> UclDeserializer<'a> {
@@ -20,6 +34,8 @@ impl<'a> UclDeserializer<'a> {
         Self {
             parser: UclParser::new(input),
             current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
         }
     }
 
@@ -28,6 +44,18 @@ impl<'a> UclDeserializer<'a> {
         Self {
             parser: UclParser::with_lexer_config(input, config),
             current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
+        }
+    }
+
+    /// Creates a deserializer with custom parser configuration
+    pub fn with_parser_config(input: &'a str, config: ParserConfig) -> Self {
+        Self {
+            parser: UclParser::new(input).with_config(config),
+            current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
         }
     }
 
@@ -36,78 +64,213 @@ impl<'a> UclDeserializer<'a> {
         Self {
             parser: UclParser::with_variable_handler(input, handler),
             current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
         }
     }
 
+    /// Creates a deserializer that borrows a variable handler
+    pub fn with_variable_handler_ref(input: &'a str, handler: &'a dyn VariableHandler) -> Self {
+        Self {
+            parser: UclParser::with_variable_handler_ref(input, handler),
+            current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
+        }
+    }
+
+    /// Creates a deserializer over input split into fragments, without joining them
+    pub fn from_fragments(fragments: &[&'a str]) -> Self {
+        Self::from_parser(UclParser::from_fragments(fragments))
+    }
+
     /// Creates a deserializer from an existing parser
     pub fn from_parser(parser: UclParser<'a>) -> Self {
         Self {
             parser,
             current_value: None,
+            provenance: Provenance::new(),
+            string_sources: Vec::new(),
         }
     }
 
+    /// Converts strings to the numbers and booleans a target asks for, and numbers to strings
+    ///
+    /// Strings are read with the lexer's rules, so `"1gb"` fills a `u64` and
+    /// `"yes"` a `bool`. This is the same as `ParserConfig::with_lenient_coercion`.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use ucl_lexer::UclDeserializer;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Server {
+    ///     port: u16,
+    ///     debug: bool,
+    ///     name: String,
+    /// }
+    ///
+    /// let input = "port = \"8080\"\ndebug = \"yes\"\nname = 42";
+    /// let server = Server::deserialize(UclDeserializer::new(input).lenient())?;
+    /// assert_eq!(server.port, 8080);
+    /// assert!(server.debug);
+    /// assert_eq!(server.name, "42");
+    /// # Ok::<(), ucl_lexer::UclError>(())
+    /// ```
+    pub fn lenient(mut self) -> Self {
+        self.parser.config_mut().lenient_coercion = true;
+        self
+    }
+
     /// Returns the current position in the input
     fn current_position(&self) -> Position {
         self.parser.current_position()
     }
 
-    /// Parses the next value if not already cached
-    fn ensure_value(&mut self) -> Result<&UclValue, UclError> {
+    /// Parses the document if it has not been parsed yet
+    fn parse_if_needed(&mut self) -> Result<(), UclError> {
+        self.parse_keys_if_needed(None)
+    }
+
+    /// Returns the top-level keys to build for a struct with `fields`, `None` for all
+    ///
+    /// Only `ParserConfig::skip_undeclared_sections` skips sections, and not
+    /// with a key alias style, under which keys may be spelled unlike fields.
+    fn declared_keys(&self, fields: &'static [&'static str]) -> Option<&'static [&'static str]> {
+        let config = self.parser.config();
+        (config.skip_undeclared_sections && config.key_alias_style.is_none()).then_some(fields)
+    }
+
+    /// Parses the document if needed, skipping top-level entries whose keys are not in `keys`
+    ///
+    /// The skipped entries are lexed but their values are not built and their
+    /// variables are not expanded; their keys map to `UclValue::Null`.
+    fn parse_keys_if_needed(&mut self, keys: Option<&[&str]>) -> Result<(), UclError> {
         if self.current_value.is_none() {
-            let value = self.parser.parse_document().map_err(|e| {
-                // Add context to parse errors
-                match e {
-                    ParseError::UnexpectedToken {
-                        token,
-                        position,
-                        expected,
-                        ..
-                    } => UclError::Serde(SerdeError::Custom(format!(
-                        "Unexpected token '{}' at {}, expected {}",
-                        token, position, expected
-                    ))),
-                    ParseError::VariableNotFound { name, position, .. } => {
-                        UclError::Serde(SerdeError::Custom(format!(
-                            "Variable '{}' not found at {}",
-                            name, position
-                        )))
-                    }
-                    other => UclError::Parse(other),
-                }
-            })?;
-            self.current_value = Some(value);
+            self.current_value = Some(self.parse_value(keys)?);
         }
-        Ok(self.current_value.as_ref().unwrap())
+        Ok(())
+    }
+
+    /// Parses the document, keeping only `keys` of an implicit root object when given
+    fn parse_value(&mut self, keys: Option<&[&str]>) -> Result<UclValue, UclError> {
+        self.parser.keep_ambiguous_suffixes();
+        self.parser.keep_string_sources();
+        let value = self.parser.parse_document_filtered(keys)?;
+        self.provenance = self.parser.take_provenance();
+        self.string_sources = self.parser.take_string_sources();
+        Ok(value)
+    }
+
+    /// Parses the next value if not already cached
+    fn ensure_value(&mut self) -> Result<&UclValue, UclError> {
+        let value = match self.current_value.take() {
+            Some(value) => value,
+            None => self.parse_value(None)?,
+        };
+        Ok(self.current_value.insert(value))
     }
 
     /// Takes the current value, parsing if necessary
     fn take_value(&mut self) -> Result<UclValue, UclError> {
-        if self.current_value.is_none() {
-            let value = self.parser.parse_document().map_err(|e| {
-                // Add context to parse errors
-                match e {
-                    ParseError::UnexpectedToken {
-                        token,
-                        position,
-                        expected,
-                        ..
-                    } => UclError::Serde(SerdeError::Custom(format!(
-                        "Unexpected token '{}' at {}, expected {}",
-                        token, position, expected
-                    ))),
-                    ParseError::VariableNotFound { name, position, .. } => {
-                        UclError::Serde(SerdeError::Custom(format!(
-                            "Variable '{}' not found at {}",
-                            name, position
-                        )))
-                    }
-                    other => UclError::Parse(other),
+        match self.current_value.take() {
+            Some(value) => Ok(value),
+            None => self.parse_value(None),
+        }
+    }
+
+    /// Fills in the position of a nested value error from the key origins
+    fn locate(&mut self, mut error: UclError) -> UclError {
+        if let UclError::Serde(SerdeError::DuplicateField {
+            first,
+            second,
+            path,
+            positions,
+        }) = &mut error
+        {
+            let provenance = self.origins();
+            let origin = |key: &String| {
+                let path = [path.as_slice(), std::slice::from_ref(key)].concat();
+                provenance
+                    .nearest_origin(&path)
+                    .map(|origin| origin.span.start)
+            };
+            *positions = [origin(first), origin(second)]
+                .into_iter()
+                .collect::<Option<_>>()
+                .unwrap_or_default();
+        } else if let UclError::Serde(serde_error) = &mut error
+            && let Some((path, position @ None)) = serde_error.location_mut()
+        {
+            *position = self
+                .origins()
+                .nearest_origin(path)
+                .map(|origin| origin.span.start);
+        }
+        error
+    }
+
+    /// Returns where the document's keys were defined
+    ///
+    /// Origins are only recorded while parsing for `from_str_with_source_map`
+    /// or `ParserConfig::track_provenance`. Otherwise the document is parsed
+    /// again to find them, which only a failed deserialization pays for.
+    fn origins(&mut self) -> &Provenance {
+        if self.provenance.is_empty() {
+            self.provenance = self.parser.reparse_provenance();
+        }
+        &self.provenance
+    }
+
+    /// Returns the settings passed down to nested values
+    fn value_options(&self) -> ValueOptions<'_, 'a> {
+        let config = self.parser.config();
+        ValueOptions {
+            bytes_encoding: config.bytes_encoding,
+            lenient: config.lenient_coercion,
+            m_suffix: self.parser.m_suffix_meaning(),
+            key_aliases: config.key_alias_style,
+            sources: StringSources(&self.string_sources),
+        }
+    }
+
+    /// Visits an object as a map, or as a struct when `fields` are given
+    fn deserialize_fields<V>(
+        mut self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, UclError>
+    where
+        V: Visitor<'a>,
+    {
+        let mut value = self.take_value()?;
+        match &mut value {
+            UclValue::Object(object) => {
+                let map = match UclMapAccess::for_struct(
+                    std::mem::take(object),
+                    fields,
+                    self.value_options(),
+                ) {
+                    Ok(map) => map,
+                    Err(e) => return Err(self.locate(e)),
+                };
+                visitor.visit_map(map).map_err(|e| self.locate(e))
+            }
+            // Allow arrays to be deserialized as maps with string indices
+            UclValue::Array(array) => {
+                let mut object = crate::parser::UclObject::new();
+                for (i, value) in std::mem::take(array).into_iter().enumerate() {
+                    object.insert(i.to_string().into(), value);
                 }
-            })?;
-            self.current_value = Some(value);
+                let map = UclMapAccess::new(object, self.value_options());
+                visitor.visit_map(map).map_err(|e| self.locate(e))
+            }
+            _ => Err(UclError::Serde(SerdeError::TypeMismatch {
+                expected: "object or array".to_string(),
+                found: format!("{:?}", value),
+                position: self.current_position(),
+            })),
         }
-        Ok(self.current_value.take().unwrap())
     }
 
     /// Returns a reference to the underlying parser
@@ -128,22 +291,22 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
+        let mut value = self.take_value()?;
 
-        match value {
-            UclValue::String(s) => visitor.visit_string(s),
-            UclValue::Integer(i) => visitor.visit_i64(i),
-            UclValue::Float(f) => visitor.visit_f64(f),
-            UclValue::Boolean(b) => visitor.visit_bool(b),
+        match &mut value {
+            UclValue::String(s) | UclValue::Decimal(s) => visitor.visit_string(std::mem::take(s)),
+            UclValue::Integer(i) => visitor.visit_i64(*i),
+            UclValue::Float(f) | UclValue::Time(f) => visitor.visit_f64(*f),
+            UclValue::Boolean(b) => visitor.visit_bool(*b),
             UclValue::Null => visitor.visit_unit(),
-            UclValue::Object(obj) => {
+            UclValue::Object(_) => {
                 // Put the value back for deserialize_map to take
-                self.current_value = Some(UclValue::Object(obj));
+                self.current_value = Some(value);
                 self.deserialize_map(visitor)
             }
-            UclValue::Array(arr) => {
+            UclValue::Array(_) => {
                 // Put the value back for deserialize_seq to take
-                self.current_value = Some(UclValue::Array(arr));
+                self.current_value = Some(value);
                 self.deserialize_seq(visitor)
             }
         }
@@ -192,7 +355,7 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         let value = self.take_value()?;
         match value {
             UclValue::Integer(i) => visitor.visit_i64(i),
-            UclValue::Float(f) => visitor.visit_i64(f as i64),
+            UclValue::Float(f) | UclValue::Time(f) => visitor.visit_i64(f as i64),
             _ => Err(UclError::Serde(SerdeError::TypeMismatch {
                 expected: "integer".to_string(),
                 found: format!("{:?}", value),
@@ -229,7 +392,7 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         let value = self.take_value()?;
         match value {
             UclValue::Integer(i) if i >= 0 => visitor.visit_u64(i as u64),
-            UclValue::Float(f) if f >= 0.0 => visitor.visit_u64(f as u64),
+            UclValue::Float(f) | UclValue::Time(f) if f >= 0.0 => visitor.visit_u64(f as u64),
             _ => Err(UclError::Serde(SerdeError::TypeMismatch {
                 expected: "unsigned integer".to_string(),
                 found: format!("{:?}", value),
@@ -251,8 +414,9 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     {
         let value = self.take_value()?;
         match value {
-            UclValue::Float(f) => visitor.visit_f64(f),
+            UclValue::Float(f) | UclValue::Time(f) => visitor.visit_f64(f),
             UclValue::Integer(i) => visitor.visit_f64(i as f64),
+            UclValue::Decimal(ref text) => visitor.visit_f64(decimal_to_f64(text)),
             _ => Err(UclError::Serde(SerdeError::TypeMismatch {
                 expected: "float".to_string(),
                 found: format!("{:?}", value),
@@ -266,7 +430,7 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         V: Visitor<'de>,
     {
         let value = self.take_value()?;
-        match value {
+        match &value {
             UclValue::String(s) => {
                 let mut chars = s.chars();
                 match (chars.next(), chars.next()) {
@@ -286,10 +450,17 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         }
     }
 
-    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_str<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
+        let value = self.take_value()?;
+        if let UclValue::String(text) = &value
+            && let Some(source) = self.value_options().sources.get(text)
+        {
+            return visitor.visit_borrowed_str(source);
+        }
+        self.current_value = Some(value);
         self.deserialize_string(visitor)
     }
 
@@ -297,9 +468,9 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
-        match value {
-            UclValue::String(s) => visitor.visit_string(s),
+        let mut value = self.take_value()?;
+        match &mut value {
+            UclValue::String(s) | UclValue::Decimal(s) => visitor.visit_string(std::mem::take(s)),
             _ => Err(UclError::Serde(SerdeError::TypeMismatch {
                 expected: "string".to_string(),
                 found: format!("{:?}", value),
@@ -312,14 +483,17 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        self.deserialize_string(visitor)
+        self.deserialize_byte_buf(visitor)
     }
 
-    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_byte_buf<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_string(visitor)
+        let value = self.take_value()?;
+        let bytes =
+            decode_bytes(value, self.value_options().bytes_encoding).map_err(|e| self.locate(e))?;
+        visitor.visit_byte_buf(bytes)
     }
 
     fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
@@ -333,18 +507,15 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         }
     }
 
+    /// Visits `null` or an empty object as `()`, and anything else as itself
     fn deserialize_unit<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
-        match value {
+        match self.ensure_value()? {
             UclValue::Null => visitor.visit_unit(),
-            _ => Err(UclError::Serde(SerdeError::TypeMismatch {
-                expected: "null".to_string(),
-                found: format!("{:?}", value),
-                position: self.current_position(),
-            })),
+            UclValue::Object(object) if object.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
         }
     }
 
@@ -374,16 +545,16 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
-        match value {
+        let mut value = self.take_value()?;
+        match &mut value {
             UclValue::Array(array) => {
-                let seq = UclSeqAccess::new(array);
-                visitor.visit_seq(seq)
+                let seq = UclSeqAccess::new(std::mem::take(array), self.value_options());
+                visitor.visit_seq(seq).map_err(|e| self.locate(e))
             }
             // Allow objects to be deserialized as sequences of values
             UclValue::Object(object) => {
-                let seq = UclObjectSeqAccess::new(object.into_values());
-                visitor.visit_seq(seq)
+                let seq = UclObjectSeqAccess::new(std::mem::take(object), self.value_options());
+                visitor.visit_seq(seq).map_err(|e| self.locate(e))
             }
             _ => Err(UclError::Serde(SerdeError::TypeMismatch {
                 expected: "array or object".to_string(),
@@ -393,62 +564,73 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         }
     }
 
-    fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_tuple<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match &mut self.take_value()? {
+            UclValue::Array(array) => visit_fixed_seq(
+                std::mem::take(array),
+                len,
+                self.value_options(),
+                std::any::type_name::<V::Value>(),
+                visitor,
+            )
+            .map_err(|e| self.locate(e)),
+            value => {
+                self.current_value = Some(std::mem::take(value));
+                self.deserialize_seq(visitor)
+            }
+        }
     }
 
     fn deserialize_tuple_struct<V>(
-        self,
-        _name: &'static str,
-        _len: usize,
+        mut self,
+        name: &'static str,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_seq(visitor)
+        match &mut self.take_value()? {
+            UclValue::Array(array) => visit_fixed_seq(
+                std::mem::take(array),
+                len,
+                self.value_options(),
+                name,
+                visitor,
+            )
+            .map_err(|e| self.locate(e)),
+            value => {
+                self.current_value = Some(std::mem::take(value));
+                self.deserialize_seq(visitor)
+            }
+        }
     }
 
-    fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
-        match value {
-            UclValue::Object(object) => {
-                let map = UclMapAccess::new(object);
-                visitor.visit_map(map)
-            }
-            // Allow arrays to be deserialized as maps with string indices
-            UclValue::Array(array) => {
-                let mut object = crate::parser::UclObject::new();
-                for (i, value) in array.into_iter().enumerate() {
-                    object.insert(i.to_string(), value);
-                }
-                let map = UclMapAccess::new(object);
-                visitor.visit_map(map)
-            }
-            _ => Err(UclError::Serde(SerdeError::TypeMismatch {
-                expected: "object or array".to_string(),
-                found: format!("{:?}", value),
-                position: self.current_position(),
-            })),
-        }
+        self.deserialize_fields(&[], visitor)
     }
 
+    /// Builds only the top-level sections named by `fields`
+    ///
+    /// Other sections are skipped token by token and reach the visitor as
+    /// null values, which it ignores or rejects as unknown fields.
     fn deserialize_struct<V>(
-        self,
+        mut self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        self.parse_keys_if_needed(self.declared_keys(fields))?;
+        self.deserialize_fields(fields, visitor)
     }
 
     fn deserialize_enum<V>(
@@ -460,19 +642,25 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let value = self.take_value()?;
-        match value {
-            // Unit variant (string)
-            UclValue::String(s) => {
-                let enum_access = UclEnumAccess::new_unit(s);
-                visitor.visit_enum(enum_access)
-            }
+        let mut value = self.take_value()?;
+        // Unit variant by name or, for integers, by index
+        if matches!(value, UclValue::String(_) | UclValue::Integer(_)) {
+            let enum_access = UclEnumAccess::new_unit(value, self.value_options());
+            return visitor.visit_enum(enum_access).map_err(|e| self.locate(e));
+        }
+        match &mut value {
             // Data variant (object with single key)
-            UclValue::Object(mut obj) => {
+            UclValue::Object(obj) => {
                 if obj.len() == 1 {
+                    // The object was just checked to hold exactly one entry
+                    #[allow(clippy::unwrap_used)]
                     let (variant_name, variant_value) = obj.shift_remove_index(0).unwrap();
-                    let enum_access = UclEnumAccess::new_data(variant_name, variant_value);
-                    visitor.visit_enum(enum_access)
+                    let enum_access = UclEnumAccess::new_data(
+                        variant_name.into(),
+                        variant_value,
+                        self.value_options(),
+                    );
+                    visitor.visit_enum(enum_access).map_err(|e| self.locate(e))
                 } else {
                     Err(UclError::Serde(SerdeError::TypeMismatch {
                         expected: "enum (string or single-key object)".to_string(),
@@ -496,28 +684,33 @@ impl<'de> de::Deserializer<'de> for UclDeserializer<'de> {
         self.deserialize_string(visitor)
     }
 
-    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    fn deserialize_ignored_any<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        self.parse_keys_if_needed(self.declared_keys(&[]))?;
+        visitor.visit_unit()
     }
 }
 
 /// Sequence access for UCL arrays
-struct UclSeqAccess {
+struct UclSeqAccess<'s, 'de> {
     array: std::vec::IntoIter<UclValue>,
+    index: usize,
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclSeqAccess {
-    fn new(array: Box<UclArray>) -> Self {
+impl<'s, 'de> UclSeqAccess<'s, 'de> {
+    fn new(array: Box<UclArray>, options: ValueOptions<'s, 'de>) -> Self {
         Self {
             array: array.into_vec().into_iter(),
+            index: 0,
+            options,
         }
     }
 }
 
-impl<'de> de::SeqAccess<'de> for UclSeqAccess {
+impl<'s, 'de> de::SeqAccess<'de> for UclSeqAccess<'s, 'de> {
     type Error = UclError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
@@ -526,8 +719,12 @@ impl<'de> de::SeqAccess<'de> for UclSeqAccess {
     {
         match self.array.next() {
             Some(value) => {
-                let deserializer = UclValueDeserializer::new(value);
-                seed.deserialize(deserializer).map(Some)
+                let deserializer = UclValueDeserializer::new(value, self.options);
+                let index = self.index;
+                self.index += 1;
+                seed.deserialize(deserializer)
+                    .map(Some)
+                    .map_err(|e| within(e, index))
             }
             None => Ok(None),
         }
@@ -539,27 +736,34 @@ impl<'de> de::SeqAccess<'de> for UclSeqAccess {
     }
 }
 
-/// Sequence access for UCL object values (direct iterator, no Vec allocation)
-struct UclObjectSeqAccess {
-    values: indexmap::map::IntoValues<String, UclValue>,
+/// Sequence access for UCL object entries in source order (no Vec allocation)
+///
+/// Pair elements such as `(String, T)` receive the key and the value, other
+/// elements only the value.
+struct UclObjectSeqAccess<'s, 'de> {
+    entries: indexmap::map::IntoIter<UclKey, UclValue>,
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclObjectSeqAccess {
-    fn new(values: indexmap::map::IntoValues<String, UclValue>) -> Self {
-        Self { values }
+impl<'s, 'de> UclObjectSeqAccess<'s, 'de> {
+    fn new(object: crate::parser::UclObject, options: ValueOptions<'s, 'de>) -> Self {
+        Self {
+            entries: object.into_iter(),
+            options,
+        }
     }
 }
 
-impl<'de> de::SeqAccess<'de> for UclObjectSeqAccess {
+impl<'s, 'de> de::SeqAccess<'de> for UclObjectSeqAccess<'s, 'de> {
     type Error = UclError;
 
     fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
     where
         T: DeserializeSeed<'de>,
     {
-        match self.values.next() {
-            Some(value) => {
-                let deserializer = UclValueDeserializer::new(value);
+        match self.entries.next() {
+            Some((key, value)) => {
+                let deserializer = UclValueDeserializer::entry(key.into(), value, self.options);
                 seed.deserialize(deserializer).map(Some)
             }
             None => Ok(None),
@@ -567,27 +771,51 @@ impl<'de> de::SeqAccess<'de> for UclObjectSeqAccess {
     }
 
     fn size_hint(&self) -> Option<usize> {
-        let (lower, upper) = self.values.size_hint();
-        upper.or(Some(lower))
+        Some(self.entries.len())
     }
 }
 
 /// Map access for UCL objects
-struct UclMapAccess {
-    object: indexmap::map::IntoIter<String, UclValue>,
+struct UclMapAccess<'s, 'de> {
+    object: indexmap::map::IntoIter<UclKey, UclValue>,
+    current_key: UclKey,
     current_value: Option<UclValue>,
+    /// Field names of the struct being visited, empty for maps
+    fields: &'static [&'static str],
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclMapAccess {
-    fn new(object: crate::parser::UclObject) -> Self {
+impl<'s, 'de> UclMapAccess<'s, 'de> {
+    fn new(object: crate::parser::UclObject, options: ValueOptions<'s, 'de>) -> Self {
         Self {
             object: object.into_iter(),
+            current_key: UclKey::new(),
             current_value: None,
+            fields: &[],
+            options,
         }
     }
+
+    /// Creates map access for a struct, whose keys may name `fields` under the alias style
+    ///
+    /// Two keys naming the same field are an error, as the struct would see
+    /// the field twice.
+    fn for_struct(
+        object: crate::parser::UclObject,
+        fields: &'static [&'static str],
+        options: ValueOptions<'s, 'de>,
+    ) -> Result<Self, UclError> {
+        if let Some(style) = options.key_aliases {
+            check_aliases(&object, fields, style)?;
+        }
+        Ok(Self {
+            fields,
+            ..Self::new(object, options)
+        })
+    }
 }
 
-impl<'de> de::MapAccess<'de> for UclMapAccess {
+impl<'s, 'de> de::MapAccess<'de> for UclMapAccess<'s, 'de> {
     type Error = UclError;
 
     fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
@@ -597,7 +825,17 @@ impl<'de> de::MapAccess<'de> for UclMapAccess {
         match self.object.next() {
             Some((key, value)) => {
                 self.current_value = Some(value);
-                let key_deserializer = UclValueDeserializer::new(UclValue::String(key));
+                self.current_key.clone_from(&key);
+                let name = match self
+                    .options
+                    .key_aliases
+                    .and_then(|style| style.field_for(&key, self.fields))
+                {
+                    Some(field) => field.to_string(),
+                    None => key.into(),
+                };
+                let key_deserializer =
+                    UclValueDeserializer::new(UclValue::String(name), self.options);
                 seed.deserialize(key_deserializer).map(Some)
             }
             None => Ok(None),
@@ -610,8 +848,9 @@ impl<'de> de::MapAccess<'de> for UclMapAccess {
     {
         match self.current_value.take() {
             Some(value) => {
-                let deserializer = UclValueDeserializer::new(value);
+                let deserializer = UclValueDeserializer::new(value, self.options);
                 seed.deserialize(deserializer)
+                    .map_err(|e| within(e, &self.current_key))
             }
             None => Err(UclError::Serde(SerdeError::Custom(
                 "No value available for map entry".to_string(),
@@ -625,55 +864,64 @@ impl<'de> de::MapAccess<'de> for UclMapAccess {
 }
 
 /// Enum access for UCL enum deserialization
-struct UclEnumAccess {
-    variant_name: String,
+///
+/// A unit variant is named by a string or selected by an integer index.
+struct UclEnumAccess<'s, 'de> {
+    variant: UclValue,
     variant_value: Option<UclValue>,
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclEnumAccess {
-    fn new_unit(variant_name: String) -> Self {
+impl<'s, 'de> UclEnumAccess<'s, 'de> {
+    fn new_unit(variant: UclValue, options: ValueOptions<'s, 'de>) -> Self {
         Self {
-            variant_name,
+            variant,
             variant_value: None,
+            options,
         }
     }
 
-    fn new_data(variant_name: String, variant_value: UclValue) -> Self {
+    fn new_data(
+        variant_name: String,
+        variant_value: UclValue,
+        options: ValueOptions<'s, 'de>,
+    ) -> Self {
         Self {
-            variant_name,
+            variant: UclValue::String(variant_name),
             variant_value: Some(variant_value),
+            options,
         }
     }
 }
 
-impl<'de> de::EnumAccess<'de> for UclEnumAccess {
+impl<'s, 'de> de::EnumAccess<'de> for UclEnumAccess<'s, 'de> {
     type Error = UclError;
-    type Variant = UclVariantAccess;
+    type Variant = UclVariantAccess<'s, 'de>;
 
     fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
     where
         V: DeserializeSeed<'de>,
     {
-        let variant_name_deserializer =
-            UclValueDeserializer::new(UclValue::String(self.variant_name));
+        let variant_name_deserializer = UclValueDeserializer::new(self.variant, self.options);
         let variant_name = seed.deserialize(variant_name_deserializer)?;
-        let variant_access = UclVariantAccess::new(self.variant_value);
+        let variant_access = UclVariantAccess::new(self.variant_value, self.options);
         Ok((variant_name, variant_access))
     }
 }
 
 /// Variant access for UCL enum variants
-struct UclVariantAccess {
+struct UclVariantAccess<'s, 'de> {
     value: Option<UclValue>,
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclVariantAccess {
-    fn new(value: Option<UclValue>) -> Self {
-        Self { value }
+impl<'s, 'de> UclVariantAccess<'s, 'de> {
+    fn new(value: Option<UclValue>, options: ValueOptions<'s, 'de>) -> Self {
+        Self { value, options }
     }
 }
 
-impl<'de> de::VariantAccess<'de> for UclVariantAccess {
+impl<'s, 'de> de::VariantAccess<'de> for UclVariantAccess<'s, 'de> {
     type Error = UclError;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
@@ -691,7 +939,7 @@ impl<'de> de::VariantAccess<'de> for UclVariantAccess {
     {
         match self.value {
             Some(value) => {
-                let deserializer = UclValueDeserializer::new(value);
+                let deserializer = UclValueDeserializer::new(value, self.options);
                 seed.deserialize(deserializer)
             }
             None => Err(UclError::Serde(SerdeError::Custom(
@@ -705,18 +953,20 @@ impl<'de> de::VariantAccess<'de> for UclVariantAccess {
         V: Visitor<'de>,
     {
         match self.value {
-            Some(UclValue::Array(array)) => {
-                let seq = UclSeqAccess::new(array);
-                visitor.visit_seq(seq)
-            }
-            Some(value) => {
-                // Single value as tuple with one element
-                use smallvec::SmallVec;
-                let mut array = SmallVec::new();
-                array.push(value);
-                let seq = UclSeqAccess::new(Box::new(array));
-                visitor.visit_seq(seq)
-            }
+            Some(mut value) => match &mut value {
+                UclValue::Array(array) => {
+                    let seq = UclSeqAccess::new(std::mem::take(array), self.options);
+                    visitor.visit_seq(seq)
+                }
+                _ => {
+                    // Single value as tuple with one element
+                    use smallvec::SmallVec;
+                    let mut array = SmallVec::new();
+                    array.push(value);
+                    let seq = UclSeqAccess::new(Box::new(array), self.options);
+                    visitor.visit_seq(seq)
+                }
+            },
             None => Err(UclError::Serde(SerdeError::Custom(
                 "Expected tuple variant data, found unit".to_string(),
             ))),
@@ -724,16 +974,16 @@ impl<'de> de::VariantAccess<'de> for UclVariantAccess {
     }
 
     fn struct_variant<V>(
-        self,
-        _fields: &'static [&'static str],
+        mut self,
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
         match self.value {
-            Some(UclValue::Object(object)) => {
-                let map = UclMapAccess::new(object);
+            Some(UclValue::Object(ref mut object)) => {
+                let map = UclMapAccess::for_struct(std::mem::take(object), fields, self.options)?;
                 visitor.visit_map(map)
             }
             Some(_) => Err(UclError::Serde(SerdeError::Custom(
@@ -746,46 +996,567 @@ impl<'de> de::VariantAccess<'de> for UclVariantAccess {
     }
 }
 
+/// Deserializes integers, reading strings as numbers in lenient mode
+macro_rules! lenient_integers {
+    ($($method:ident => $ty:ty, $visit:ident, $fallback:ident;)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                self.reject_ambiguous_suffix()?;
+                match self.value {
+                    UclValue::String(ref text) if self.options.lenient => {
+                        visitor.$visit(coerce_integer::<$ty>(&text, self.options.m_suffix)?)
+                    }
+                    _ => self.$fallback(visitor),
+                }
+            }
+        )*
+    };
+}
+
+/// Settings the value deserializers pass down to nested values
+#[derive(Debug, Clone, Copy, Default)]
+struct ValueOptions<'s, 'de> {
+    /// How strings are decoded for byte targets
+    bytes_encoding: BytesEncoding,
+    /// Convert between strings and primitives, see `UclDeserializer::lenient`
+    lenient: bool,
+    /// Meaning of a bare `m` suffix in strings read as numbers
+    m_suffix: MSuffix,
+    /// Spellings of a key read as the same struct field
+    key_aliases: Option<AliasStyle>,
+    /// Input slices string values may be borrowed from
+    sources: StringSources<'s, 'de>,
+}
+
+/// Input slices that string values were read from unchanged
+///
+/// Recorded by the parser with `keep_string_sources` and sorted by the
+/// address of each value's text. Strings that needed unescaping or variable
+/// expansion have no slice, and `&str` targets reject them.
+#[derive(Debug, Clone, Copy, Default)]
+struct StringSources<'s, 'de>(&'s [(usize, &'de str)]);
+
+impl<'de> StringSources<'_, 'de> {
+    /// Returns the input slice `text` was read from, if it is unchanged
+    fn get(self, text: &str) -> Option<&'de str> {
+        if text.is_empty() {
+            return Some("");
+        }
+        let address = text.as_ptr() as usize;
+        let index = self
+            .0
+            .binary_search_by_key(&address, |(address, _)| *address);
+        let source = self.0[index.ok()?].1;
+        (source == text).then_some(source)
+    }
+}
+
 /// Deserializer for individual UCL values
-struct UclValueDeserializer {
+struct UclValueDeserializer<'s, 'de> {
     value: UclValue,
+    /// Key of the object entry holding the value, when reached through a sequence
+    key: Option<String>,
+    options: ValueOptions<'s, 'de>,
 }
 
-impl UclValueDeserializer {
-    fn new(value: UclValue) -> Self {
-        Self { value }
+impl<'s, 'de> UclValueDeserializer<'s, 'de> {
+    fn new(value: UclValue, options: ValueOptions<'s, 'de>) -> Self {
+        Self {
+            value,
+            key: None,
+            options,
+        }
+    }
+
+    /// Creates a deserializer for an object entry that pair targets read as `(key, value)`
+    fn entry(key: String, value: UclValue, options: ValueOptions<'s, 'de>) -> Self {
+        Self {
+            value,
+            key: Some(key),
+            options,
+        }
+    }
+
+    /// Rejects a bare `5m`, kept as text under `MSuffix::Error`, for a number target
+    ///
+    /// Types such as `Seconds` and `ByteSize` read the text with the unit they expect.
+    fn reject_ambiguous_suffix(&self) -> Result<(), UclError> {
+        match &self.value {
+            UclValue::String(text) if self.options.m_suffix == MSuffix::Error => {
+                match ambiguous_number(text) {
+                    Some(number) => Err(UclError::Serde(SerdeError::AmbiguousSuffix {
+                        number: number.to_string(),
+                        path: Vec::new(),
+                        position: None,
+                    })),
+                    None => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
     }
 }
 
-impl<'de> de::Deserializer<'de> for UclValueDeserializer {
+impl<'s, 'de> de::Deserializer<'de> for UclValueDeserializer<'s, 'de> {
     type Error = UclError;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        match self.value {
-            UclValue::String(s) => visitor.visit_string(s),
-            UclValue::Integer(i) => visitor.visit_i64(i),
-            UclValue::Float(f) => visitor.visit_f64(f),
-            UclValue::Boolean(b) => visitor.visit_bool(b),
+        let mut value = self.value;
+        match &mut value {
+            UclValue::String(s) | UclValue::Decimal(s) => visitor.visit_string(std::mem::take(s)),
+            UclValue::Integer(i) => visitor.visit_i64(*i),
+            UclValue::Float(f) | UclValue::Time(f) => visitor.visit_f64(*f),
+            UclValue::Boolean(b) => visitor.visit_bool(*b),
             UclValue::Null => visitor.visit_unit(),
             UclValue::Object(obj) => {
-                let map = UclMapAccess::new(obj);
+                let map = UclMapAccess::new(std::mem::take(obj), self.options);
                 visitor.visit_map(map)
             }
             UclValue::Array(arr) => {
-                let seq = UclSeqAccess::new(arr);
+                let seq = UclSeqAccess::new(std::mem::take(arr), self.options);
                 visitor.visit_seq(seq)
             }
         }
     }
 
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            UclValue::String(ref text) if self.options.lenient => {
+                visitor.visit_bool(coerce_bool(text)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.reject_ambiguous_suffix()?;
+        match self.value {
+            // Time values are stored as float seconds; integer targets get whole seconds
+            UclValue::Time(t) => visitor.visit_i64(t as i64),
+            UclValue::String(ref text) if self.options.lenient => {
+                visitor.visit_i64(coerce_integer(text, self.options.m_suffix)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.reject_ambiguous_suffix()?;
+        match self.value {
+            UclValue::Time(t) if t >= 0.0 => visitor.visit_u64(t as u64),
+            UclValue::String(ref text) if self.options.lenient => {
+                visitor.visit_u64(coerce_integer(text, self.options.m_suffix)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.reject_ambiguous_suffix()?;
+        match self.value {
+            UclValue::Decimal(ref text) => visitor.visit_f32(decimal_to_f64(text) as f32),
+            UclValue::String(ref text) if self.options.lenient => {
+                visitor.visit_f32(coerce_float(text, "f32", self.options.m_suffix)? as f32)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.reject_ambiguous_suffix()?;
+        match self.value {
+            UclValue::Decimal(ref text) => visitor.visit_f64(decimal_to_f64(text)),
+            UclValue::String(ref text) if self.options.lenient => {
+                visitor.visit_f64(coerce_float(text, "f64", self.options.m_suffix)?)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// Borrows strings read unchanged from the input, see `StringSources`
+    fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if let UclValue::String(text) = &self.value
+            && let Some(source) = self.options.sources.get(text)
+        {
+            return visitor.visit_borrowed_str(source);
+        }
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            UclValue::Integer(i) if self.options.lenient => visitor.visit_string(i.to_string()),
+            UclValue::Float(f) | UclValue::Time(f) if self.options.lenient => {
+                visitor.visit_string(f.to_string())
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            UclValue::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_byte_buf(decode_bytes(self.value, self.options.bytes_encoding)?)
+    }
+
+    /// Visits `null` or an empty object as `()`, and anything else as itself
+    fn deserialize_unit<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match &self.value {
+            UclValue::Null => visitor.visit_unit(),
+            UclValue::Object(object) if object.is_empty() => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            // Allow objects to be deserialized as sequences of entries
+            UclValue::Object(ref mut object) => visitor.visit_seq(UclObjectSeqAccess::new(
+                std::mem::take(object),
+                self.options,
+            )),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple<V>(mut self, len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        if len == 2
+            && let Some(key) = self.key.take()
+        {
+            let pair: UclArray = [UclValue::String(key), self.value].into_iter().collect();
+            return visitor.visit_seq(UclSeqAccess::new(Box::new(pair), self.options));
+        }
+
+        match self.value {
+            UclValue::Array(ref mut array) => visit_fixed_seq(
+                std::mem::take(array),
+                len,
+                self.options,
+                std::any::type_name::<V::Value>(),
+                visitor,
+            ),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_tuple_struct<V>(
+        mut self,
+        name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            UclValue::Array(ref mut array) => {
+                visit_fixed_seq(std::mem::take(array), len, self.options, name, visitor)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        mut self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            // Unit variant by name or, for integers, by index
+            UclValue::String(_) | UclValue::Integer(_) => {
+                visitor.visit_enum(UclEnumAccess::new_unit(self.value, self.options))
+            }
+            // Data variant (object with single key)
+            UclValue::Object(ref mut obj) if obj.len() == 1 => {
+                // The guard checked that the object holds exactly one entry
+                #[allow(clippy::unwrap_used)]
+                let (variant_name, variant_value) = obj.shift_remove_index(0).unwrap();
+                visitor.visit_enum(UclEnumAccess::new_data(
+                    variant_name.into(),
+                    variant_value,
+                    self.options,
+                ))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    /// Visits non-negative integers as `u64`, which derived variant identifiers read as an index
+    fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.value {
+            UclValue::Integer(i) if i >= 0 => visitor.visit_u64(i as u64),
+            _ => self.deserialize_string(visitor),
+        }
+    }
+
+    lenient_integers! {
+        deserialize_i8 => i8, visit_i8, deserialize_i64;
+        deserialize_i16 => i16, visit_i16, deserialize_i64;
+        deserialize_i32 => i32, visit_i32, deserialize_i64;
+        deserialize_u8 => u8, visit_u8, deserialize_u64;
+        deserialize_u16 => u16, visit_u16, deserialize_u64;
+        deserialize_u32 => u32, visit_u32, deserialize_u64;
+    }
+
+    /// Drops the value without visiting its contents
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        drop(self.value);
+        visitor.visit_unit()
+    }
+
+    fn deserialize_struct<V>(
+        mut self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match &mut self.value {
+            UclValue::Object(object) => {
+                let map = UclMapAccess::for_struct(std::mem::take(object), fields, self.options)?;
+                visitor.visit_map(map)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
     // Delegate all other methods to deserialize_any for simplicity
     serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+        i128 u128 char
+        map
+    }
+}
+
+/// Visits an array as a tuple, tuple struct or fixed-size array of exactly `len` elements
+fn visit_fixed_seq<'s, 'de, V>(
+    array: Box<UclArray>,
+    len: usize,
+    options: ValueOptions<'s, 'de>,
+    target: &str,
+    visitor: V,
+) -> Result<V::Value, UclError>
+where
+    V: Visitor<'de>,
+{
+    if array.len() != len {
+        return Err(UclError::Serde(SerdeError::InvalidLength {
+            expected: len,
+            found: array.len(),
+            target: target.to_string(),
+            path: Vec::new(),
+            position: None,
+        }));
+    }
+    visitor.visit_seq(UclSeqAccess::new(array, options))
+}
+
+/// Converts the text of a `UclValue::Decimal` to the nearest `f64`
+///
+/// Decimal values are plain decimals, so parsing only fails on values built by hand.
+fn decimal_to_f64(text: &str) -> f64 {
+    text.parse().unwrap_or(f64::NAN)
+}
+
+/// Prepends the key or index of an enclosing value to the path of a nested value error
+fn within(mut error: UclError, segment: impl fmt::Display) -> UclError {
+    if let UclError::Serde(serde_error) = &mut error
+        && let Some(path) = serde_error.path_mut()
+    {
+        path.insert(0, segment.to_string());
+    }
+    error
+}
+
+/// Rejects an object with two keys that name the same field under the alias style
+fn check_aliases(
+    object: &crate::parser::UclObject,
+    fields: &[&str],
+    style: AliasStyle,
+) -> Result<(), UclError> {
+    let mut seen: Vec<(&str, &UclKey)> = Vec::new();
+    for key in object.keys() {
+        let Some(field) = style.field_for(key, fields) else {
+            continue;
+        };
+        if let Some((_, first)) = seen.iter().find(|(name, _)| *name == field) {
+            return Err(UclError::Serde(SerdeError::DuplicateField {
+                first: first.to_string(),
+                second: key.to_string(),
+                path: Vec::new(),
+                positions: Vec::new(),
+            }));
+        }
+        seen.push((field, key));
+    }
+    Ok(())
+}
+
+/// Error for a string that lenient coercion cannot read as `target`
+fn invalid_coercion(text: &str, target: &str) -> UclError {
+    UclError::Serde(SerdeError::InvalidCoercion {
+        value: text.to_string(),
+        target: target.to_string(),
+        path: Vec::new(),
+        position: None,
+    })
+}
+
+/// Lexer rules for strings read as numbers, with `m` as the document reads it
+fn number_config(m_suffix: MSuffix) -> LexerConfig {
+    LexerConfig::default().with_m_suffix_meaning(m_suffix)
+}
+
+/// Returns the number of a bare `5m`, the suffix `MSuffix` decides the meaning of
+fn ambiguous_number(text: &str) -> Option<&str> {
+    let number = text.trim().strip_suffix('m')?;
+    let digits = number.trim_start_matches(['-', '+']);
+    (digits.starts_with(|ch: char| ch.is_ascii_digit()) && number.parse::<f64>().is_ok())
+        .then_some(number)
+}
+
+/// Reads a string as an integer, including size suffixes and whole seconds of durations
+fn coerce_integer<T: TryFrom<i64>>(text: &str, m_suffix: MSuffix) -> Result<T, UclError> {
+    let integer = match lex_single_token(text, number_config(m_suffix)) {
+        Some(Token::Integer(i)) => Some(i),
+        Some(Token::Time(t)) => Some(t as i64),
+        _ => None,
+    };
+    integer
+        .and_then(|i| T::try_from(i).ok())
+        .ok_or_else(|| invalid_coercion(text, std::any::type_name::<T>()))
+}
+
+/// Reads a string as a float, including durations in seconds
+fn coerce_float(text: &str, target: &str, m_suffix: MSuffix) -> Result<f64, UclError> {
+    match lex_single_token(text, number_config(m_suffix)) {
+        Some(Token::Integer(i)) => Ok(i as f64),
+        Some(Token::Float(f) | Token::Time(f)) => Ok(f),
+        _ => Err(invalid_coercion(text, target)),
+    }
+}
+
+/// Reads a string as a boolean with the parser's keywords, such as `yes` or `off`
+fn coerce_bool(text: &str) -> Result<bool, UclError> {
+    boolean_keyword(text.trim()).ok_or_else(|| invalid_coercion(text, "bool"))
+}
+
+/// Decodes a string or an array of integers into bytes
+fn decode_bytes(value: UclValue, encoding: BytesEncoding) -> Result<Vec<u8>, UclError> {
+    let invalid = |message: String| {
+        UclError::Serde(SerdeError::InvalidBytes {
+            message,
+            path: Vec::new(),
+            position: None,
+        })
+    };
+
+    match &value {
+        UclValue::String(text) => encoding
+            .decode(text)
+            .map_err(|message| invalid(format!("{} value is invalid: {}", encoding, message))),
+        UclValue::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(index, element)| match element {
+                UclValue::Integer(byte @ 0..=255) => Ok(*byte as u8),
+                other => Err(invalid(format!(
+                    "element {} is {}, expected an integer in 0..=255",
+                    index, other
+                ))),
+            })
+            .collect(),
+        other => Err(invalid(format!(
+            "expected a string or an array of integers, found {}",
+            other.type_name()
+        ))),
     }
 }
 
@@ -798,7 +1569,164 @@ where
     T::deserialize(deserializer)
 }
 
+/// Deserializes UCL text split into fragments without joining them
+///
+/// ```
+/// use serde::Deserialize;
+/// use ucl_lexer::from_fragments;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+///     port: u16,
+/// }
+///
+/// let server: Server = from_fragments(&["host = \"example.org\"\nport = 80", "80\n"])?;
+/// assert_eq!(server.host, "example.org");
+/// assert_eq!(server.port, 8080);
+/// # Ok::<(), ucl_lexer::UclError>(())
+/// ```
+pub fn from_fragments<'a, T>(fragments: &[&'a str]) -> Result<T, UclError>
+where
+    T: Deserialize<'a>,
+{
+    T::deserialize(UclDeserializer::from_fragments(fragments))
+}
+
+/// Deserializes the value at a dotted path, such as `"server.tls"`
+///
+/// Only the top-level section the path starts with is parsed; the values of
+/// all other top-level entries are skipped without being built, see
+/// [`UclParser::parse_document_keys`]. The path is read with
+/// [`KeyPath::parse`], so `servers[1]` indexes an array and
+/// `hosts."example.com"` names a key with a dot; numeric segments also index
+/// arrays.
+///
+/// ```
+/// use serde::Deserialize;
+/// use ucl_lexer::from_str_at_path;
+///
+/// #[derive(Deserialize)]
+/// struct Logging {
+///     level: String,
+/// }
+///
+/// let input = "server { port = 80 }\nlogging { level = \"debug\" }";
+/// let logging: Logging = from_str_at_path(input, "logging").unwrap();
+/// assert_eq!(logging.level, "debug");
+///
+/// let err = from_str_at_path::<u16>(input, "server.tls.port").unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "No value at path 'server.tls.port'; the closest existing path is 'server'"
+/// );
+/// ```
+pub fn from_str_at_path<T>(s: &str, path: &str) -> Result<T, UclError>
+where
+    T: DeserializeOwned,
+{
+    let mut values = from_str_at_paths(s, &[path])?;
+    Ok(values.remove(0))
+}
+
+/// Deserializes the values at several dotted paths in one pass, in the order given
+pub fn from_str_at_paths<T>(s: &str, paths: &[&str]) -> Result<Vec<T>, UclError>
+where
+    T: DeserializeOwned,
+{
+    let mut keys: Vec<String> = Vec::new();
+    for path in paths {
+        let first = KeyPath::parse(path).and_then(|path| path.segments().first().cloned());
+        if let Some(PathSegment::Key(key)) = first
+            && !keys.contains(&key)
+        {
+            keys.push(key);
+        }
+    }
+
+    let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+    let root = UclValue::Object(UclParser::new(s).parse_document_keys(&keys)?);
+    paths
+        .iter()
+        .map(|path| {
+            let value = root
+                .get_path(path)
+                .ok_or_else(|| path_not_found(&root, path))?;
+            from_value(value.clone())
+        })
+        .collect()
+}
+
+/// Builds the error for a missing path with the longest prefix that exists
+fn path_not_found(root: &UclValue, path: &str) -> UclError {
+    let segments = KeyPath::parse(path).map_or_else(Vec::new, |path| path.segments().to_vec());
+    let closest = (1..segments.len())
+        .rev()
+        .map(|len| KeyPath::from(segments[..len].to_vec()))
+        .find(|prefix| root.get_key_path(prefix).is_some());
+    UclError::PathNotFound {
+        path: path.to_string(),
+        closest: closest.map(|prefix| prefix.to_string()),
+    }
+}
+
+/// Deserializes UCL text together with the source locations of its entries
+///
+/// The `SourceMap` lets rules checked after deserialization, such as a port
+/// range, be reported at the line that set the value.
+pub fn from_str_with_source_map<'a, T>(s: &'a str) -> Result<(T, SourceMap), UclError>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = UclDeserializer::new(s);
+    deserializer.parser.config_mut().track_provenance = true;
+    deserializer.parse_if_needed()?;
+    let source_map = SourceMap::from(deserializer.provenance.clone());
+    Ok((T::deserialize(deserializer)?, source_map))
+}
+
+/// Deserializes UCL bytes into a Rust type
+///
+/// UTF-8 input is parsed in place without copying. See `UclParser::parse_bytes`
+/// for how other encodings are detected.
+pub fn from_slice<T>(bytes: &[u8]) -> Result<T, UclError>
+where
+    T: DeserializeOwned,
+{
+    from_str(&crate::encoding::decode(bytes)?)
+}
+
+/// Deserializes an already parsed value into a Rust type
+///
+/// Useful when the tree is built or merged before it is typed, for example by
+/// layering overrides onto defaults with `UclValue::merge_from`. Byte fields
+/// are decoded as base64.
+///
+/// ```
+/// use serde::Deserialize;
+/// use ucl_lexer::{UclValue, from_value};
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     port: u16,
+/// }
+///
+/// let mut value: UclValue = "port = 80".parse().unwrap();
+/// value.merge_from("port = 8080".parse().unwrap());
+/// let server: Server = from_value(value).unwrap();
+/// assert_eq!(server.port, 8080);
+/// ```
+pub fn from_value<T>(value: UclValue) -> Result<T, UclError>
+where
+    T: DeserializeOwned,
+{
+    T::deserialize(UclValueDeserializer::new(value, ValueOptions::default()))
+}
+
 /// Convenience function to deserialize UCL text with variable expansion
+///
+/// Returns `UclError::VariableExpansionDisabled` when built with the
+/// `no-variable-expansion` feature.
 pub fn from_str_with_variables<'a, T>(
     s: &'a str,
     handler: Box<dyn VariableHandler>,
@@ -806,10 +1734,63 @@ pub fn from_str_with_variables<'a, T>(
 where
     T: Deserialize<'a>,
 {
+    variable_expansion_enabled()?;
     let deserializer = UclDeserializer::with_variable_handler(s, handler);
     T::deserialize(deserializer)
 }
 
+/// Deserializes UCL text with variable expansion through a borrowed handler
+///
+/// Unlike `from_str_with_variables`, the handler is neither boxed nor moved,
+/// so one handler can serve many parses, concurrently if need be.
+///
+/// ```
+/// use serde::Deserialize;
+/// use std::collections::HashMap;
+/// use ucl_lexer::{MapVariableHandler, from_str_with_variables_ref};
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     host: String,
+/// }
+///
+/// let handler = MapVariableHandler::from_map(HashMap::from([(
+///     "HOST".to_string(),
+///     "example.com".to_string(),
+/// )]));
+/// for _ in 0..3 {
+///     let server: Server = from_str_with_variables_ref("host = \"${HOST}\"", &handler).unwrap();
+///     assert_eq!(server.host, "example.com");
+/// }
+/// ```
+pub fn from_str_with_variables_ref<'a, T>(
+    s: &'a str,
+    handler: &'a dyn VariableHandler,
+) -> Result<T, UclError>
+where
+    T: Deserialize<'a>,
+{
+    variable_expansion_enabled()?;
+    T::deserialize(UclDeserializer::with_variable_handler_ref(s, handler))
+}
+
+/// Deserializes UCL text with variable expansion, taking the handler by value
+pub fn from_str_with_variable_handler<'a, T, H>(s: &'a str, handler: H) -> Result<T, UclError>
+where
+    T: Deserialize<'a>,
+    H: VariableHandler + 'static,
+{
+    from_str_with_variables(s, Box::new(handler))
+}
+
+/// Rejects variable expansion in builds with the `no-variable-expansion` feature
+fn variable_expansion_enabled() -> Result<(), UclError> {
+    if cfg!(feature = "no-variable-expansion") {
+        return Err(UclError::VariableExpansionDisabled);
+    }
+    Ok(())
+}
+
 /// Convenience function to deserialize UCL text with custom lexer configuration
 pub fn from_str_with_config<'a, T>(s: &'a str, config: LexerConfig) -> Result<T, UclError>
 where
@@ -820,6 +1801,9 @@ where
 }
 
 /// Convenience function to deserialize UCL text with both custom config and variables
+///
+/// Returns `UclError::VariableExpansionDisabled` when built with the
+/// `no-variable-expansion` feature.
 pub fn from_str_with_config_and_variables<'a, T>(
     s: &'a str,
     _config: LexerConfig,
@@ -828,16 +1812,12 @@ pub fn from_str_with_config_and_variables<'a, T>(
 where
     T: Deserialize<'a>,
 {
+    variable_expansion_enabled()?;
     // Create parser with variable handler first, then apply config
-    let parser = UclParser::with_variable_handler(s, handler)
-        .with_config(crate::parser::ParserConfig::default());
+    let parser = UclParser::with_variable_handler(s, handler).with_config(ParserConfig::default());
     // Note: We can't easily combine lexer config with variable handler in current API
     // This would require extending the parser API
-    let deserializer = UclDeserializer {
-        parser,
-        current_value: None,
-    };
-    T::deserialize(deserializer)
+    T::deserialize(UclDeserializer::from_parser(parser))
 }
 
 /// Convenience function to deserialize UCL text using environment variables
@@ -845,7 +1825,7 @@ pub fn from_str_with_env<'a, T>(s: &'a str) -> Result<T, UclError>
 where
     T: Deserialize<'a>,
 {
-    let handler = Box::new(crate::parser::EnvironmentVariableHandler);
+    let handler = Box::new(crate::parser::EnvironmentVariableHandler::new());
     from_str_with_variables(s, handler)
 }
 
@@ -862,6 +1842,7 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
     use serde::Deserialize;
@@ -943,7 +1924,7 @@ mod tests {
             Ok(parsed) => {
                 assert_eq!(parsed.user.name, "Bob");
                 assert_eq!(parsed.user.age, 25);
-                assert_eq!(parsed.active, true);
+                assert!(parsed.active);
             }
             Err(_) => {
                 // Expected to fail until full parsing is implemented
@@ -1083,25 +2064,13 @@ mod tests {
 
     #[test]
     fn test_environment_variables() {
-        unsafe {
-            std::env::set_var("TEST_UCL_VAR", "test_value");
-        }
+        let environment = HashMap::from([("TEST_UCL_VAR".to_string(), "test_value".to_string())]);
+        let handler = crate::parser::EnvironmentVariableHandler::from_snapshot(environment);
 
         let ucl = r#"{ env_value = "${TEST_UCL_VAR}" }"#;
-        let result: Result<HashMap<String, String>, _> = from_str_with_env(ucl);
-
-        match result {
-            Ok(parsed) => {
-                assert_eq!(parsed.get("env_value"), Some(&"test_value".to_string()));
-            }
-            Err(_) => {
-                // Expected to fail until full parsing is implemented
-            }
-        }
-
-        unsafe {
-            std::env::remove_var("TEST_UCL_VAR");
-        }
+        let parsed: HashMap<String, String> =
+            from_str_with_variables(ucl, Box::new(handler)).unwrap();
+        assert_eq!(parsed.get("env_value"), Some(&"test_value".to_string()));
     }
 
     #[test]
@@ -1129,7 +2098,7 @@ mod tests {
                 assert_eq!(parsed.as_i32, 42);
                 assert_eq!(parsed.as_f64, 42.0);
                 assert_eq!(parsed.as_string, "42");
-                assert_eq!(parsed.as_bool, true);
+                assert!(parsed.as_bool);
             }
             Err(_) => {
                 // Expected to fail until full parsing is implemented
@@ -1187,4 +2156,48 @@ mod tests {
         // Test position tracking
         let _position = deserializer.current_position();
     }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Mode {
+        Fast,
+        Limited { rate: u32 },
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Layered {
+        mode: Mode,
+        backup: Option<Mode>,
+        owner: Option<TestStruct>,
+    }
+
+    #[test]
+    fn test_from_value_nested_options_and_enums() {
+        let mut value: UclValue = "mode = \"Fast\"\nowner = null".parse().unwrap();
+        value.merge_from("backup { Limited { rate = 5 } }".parse().unwrap());
+
+        let layered: Layered = from_value(value).unwrap();
+        assert_eq!(
+            layered,
+            Layered {
+                mode: Mode::Fast,
+                backup: Some(Mode::Limited { rate: 5 }),
+                owner: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_origins_are_recorded_only_when_needed() {
+        let input = "user {\n  name = \"Alice\"\n  age = -1\n}\nactive = true";
+        let mut deserializer = UclDeserializer::new(input);
+        deserializer.parse_if_needed().unwrap();
+        assert!(deserializer.provenance.is_empty());
+
+        // A failure parses the document again to place the error
+        let err = NestedStruct::deserialize(UclDeserializer::new(input)).unwrap_err();
+        assert_eq!(err.position().map(|p| p.line), Some(3), "{err}");
+
+        let (_, source_map) = from_str_with_source_map::<de::IgnoredAny>(input).unwrap();
+        assert_eq!(source_map.line_col_for_path("user.age"), Some((3, 3)));
+    }
 }
@@ -0,0 +1,130 @@
+//! Structured directives written in comments
+//!
+//! With `ParserConfig::directives` set, the parser reads comments such as
+//! `# ucl-lint: disable=typed-string` or `# @deprecated use new_key` and
+//! applies them to the key that follows:
+//!
+//! - a lint directive suppresses the listed lints for that entry, including
+//!   its nested entries;
+//! - a deprecation directive records a diagnostic when the key is present.
+//!
+//! Recognized directives are available from `UclParser::directives` after
+//! parsing.
+
+use crate::error::{LintCode, Position};
+
+/// Default prefix of lint directives
+pub const DEFAULT_LINT_PREFIX: &str = "ucl-lint:";
+
+/// Default prefix of deprecation directives
+pub const DEFAULT_DEPRECATED_PREFIX: &str = "@deprecated";
+
+/// Instruction carried by a directive comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveKind {
+    /// Lints suppressed for the entry, from `ucl-lint: disable=<lint>,<lint>`
+    DisableLints(Vec<LintCode>),
+    /// Deprecation note for the entry, from `@deprecated <note>`
+    Deprecated(String),
+}
+
+/// Directive attached to the key that follows its comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// What the directive asks for
+    pub kind: DirectiveKind,
+    /// Position of the comment holding the directive
+    pub position: Position,
+    /// Path of the key the directive applies to
+    pub key_path: Vec<String>,
+    /// Position of that key
+    pub key_position: Position,
+}
+
+/// Recognizes directives in comment text
+///
+/// ```rust
+/// use ucl_lexer::{DirectiveKind, DirectiveParser, LintCode};
+///
+/// let directives = DirectiveParser::new();
+/// assert_eq!(
+///     directives.parse(" ucl-lint: disable=typed-string"),
+///     Some(Ok(DirectiveKind::DisableLints(vec![LintCode::TypedString])))
+/// );
+/// assert_eq!(directives.parse(" an ordinary comment"), None);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveParser {
+    lint_prefix: String,
+    deprecated_prefix: String,
+}
+
+impl DirectiveParser {
+    /// Creates a parser for `ucl-lint:` and `@deprecated` directives
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the prefix that starts a lint directive
+    pub fn with_lint_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.lint_prefix = prefix.into();
+        self
+    }
+
+    /// Sets the prefix that starts a deprecation directive
+    pub fn with_deprecated_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.deprecated_prefix = prefix.into();
+        self
+    }
+
+    /// Reads the directive in the text of a comment, such as `CommentInfo::text`
+    ///
+    /// Returns `None` for ordinary comments and an error message for a
+    /// directive that cannot be understood.
+    pub fn parse(&self, comment: &str) -> Option<Result<DirectiveKind, String>> {
+        let text = comment.trim();
+        if let Some(rest) = text.strip_prefix(self.lint_prefix.as_str()) {
+            return Some(self.parse_lint(rest.trim()));
+        }
+        let rest = text.strip_prefix(self.deprecated_prefix.as_str())?;
+        // `@deprecated_since` is not a deprecation directive
+        if rest.starts_with(|ch: char| !ch.is_whitespace()) {
+            return None;
+        }
+        Some(Ok(DirectiveKind::Deprecated(rest.trim().to_string())))
+    }
+
+    /// Reads the arguments of a lint directive
+    fn parse_lint(&self, arguments: &str) -> Result<DirectiveKind, String> {
+        let Some(names) = arguments.strip_prefix("disable=") else {
+            return Err(format!(
+                "expected `{} disable=<lint>` but found `{}`",
+                self.lint_prefix, arguments
+            ));
+        };
+        names
+            .split(',')
+            .map(str::trim)
+            .map(|name| {
+                LintCode::from_name(name).ok_or_else(|| {
+                    let known: Vec<_> = LintCode::ALL.iter().map(|code| code.as_str()).collect();
+                    format!(
+                        "unknown lint `{}`, expected one of: {}",
+                        name,
+                        known.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(DirectiveKind::DisableLints)
+    }
+}
+
+impl Default for DirectiveParser {
+    fn default() -> Self {
+        Self {
+            lint_prefix: DEFAULT_LINT_PREFIX.to_string(),
+            deprecated_prefix: DEFAULT_DEPRECATED_PREFIX.to_string(),
+        }
+    }
+}
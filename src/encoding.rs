@@ -0,0 +1,92 @@
+//! Decoding of byte input into UTF-8 text
+//!
+//! Input is read as UTF-8 unless it starts with a UTF-16 byte order mark.
+//! Valid UTF-8 is borrowed as is; UTF-16 is transcoded into an owned string
+//! when the `encoding` feature is enabled and rejected otherwise.
+
+use crate::error::UclError;
+use std::borrow::Cow;
+
+/// Byte order mark of UTF-16 little-endian input
+const UTF16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+
+/// Byte order mark of UTF-16 big-endian input
+const UTF16BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// Decodes input bytes, borrowing them when they are already UTF-8
+pub(crate) fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, UclError> {
+    if bytes.starts_with(&UTF16LE_BOM) {
+        return decode_utf16(bytes, "UTF-16LE", u16::from_le_bytes);
+    }
+    if bytes.starts_with(&UTF16BE_BOM) {
+        return decode_utf16(bytes, "UTF-16BE", u16::from_be_bytes);
+    }
+
+    std::str::from_utf8(bytes)
+        .map(Cow::Borrowed)
+        .map_err(|error| UclError::InvalidEncoding {
+            detected: "UTF-8",
+            position: error.valid_up_to(),
+        })
+}
+
+/// Transcodes UTF-16 after its byte order mark
+#[cfg(feature = "encoding")]
+fn decode_utf16(
+    bytes: &[u8],
+    detected: &'static str,
+    unit: fn([u8; 2]) -> u16,
+) -> Result<Cow<'static, str>, UclError> {
+    let body = &bytes[UTF16LE_BOM.len()..];
+    let units = body.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+
+    let mut text = String::with_capacity(body.len() / 2);
+    let mut position = UTF16LE_BOM.len();
+    for decoded in char::decode_utf16(units) {
+        let ch = decoded.map_err(|_| UclError::InvalidEncoding { detected, position })?;
+        text.push(ch);
+        position += ch.len_utf16() * 2;
+    }
+    if !body.len().is_multiple_of(2) {
+        return Err(UclError::InvalidEncoding { detected, position });
+    }
+    Ok(Cow::Owned(text))
+}
+
+/// Rejects UTF-16, which can only be transcoded with the `encoding` feature
+#[cfg(not(feature = "encoding"))]
+fn decode_utf16(
+    _bytes: &[u8],
+    detected: &'static str,
+    _unit: fn([u8; 2]) -> u16,
+) -> Result<Cow<'static, str>, UclError> {
+    Err(UclError::InvalidEncoding {
+        detected,
+        position: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_is_borrowed() {
+        let bytes = "key = \"värde\"".as_bytes();
+        assert!(
+            matches!(decode(bytes), Ok(Cow::Borrowed(text)) if text.as_ptr() == bytes.as_ptr())
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8_offset() {
+        let error = decode(b"a = 1\nb = \"\xE2\x82\"").unwrap_err();
+        assert!(matches!(
+            error,
+            UclError::InvalidEncoding {
+                detected: "UTF-8",
+                position: 11
+            }
+        ));
+    }
+}
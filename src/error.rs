@@ -3,11 +3,14 @@
 //! This module provides comprehensive error handling with detailed position
 //! information for debugging and user feedback.
 
+use std::cmp::Ordering;
 use std::fmt;
+use std::ops::{Range, RangeInclusive};
 use thiserror::Error;
 
 /// Represents a position in the source text
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-token", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     /// Line number (1-based)
     pub line: usize,
@@ -18,6 +21,12 @@ pub struct Position {
 }
 
 impl Position {
+    /// Largest column `Display` writes exactly
+    ///
+    /// Past it a position renders as `line 1, column >100000`, so an error on
+    /// a minified or generated single-line document stays readable.
+    pub const DISPLAY_COLUMN_CAP: usize = 100_000;
+
     /// Creates a new position at the start of input
     pub fn new() -> Self {
         Self {
@@ -51,6 +60,79 @@ impl Position {
             self.advance(c);
         }
     }
+
+    /// Computes the position of a byte offset by scanning the source
+    ///
+    /// Offsets past the end are clamped to the end of the source, and offsets
+    /// inside a multi-byte character resolve to the start of that character.
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        let mut position = Self::new();
+        position.advance_by(&source[..offset]);
+        position
+    }
+
+    /// Returns the 1-based column in UTF-16 code units, as LSP clients count it
+    ///
+    /// `source_line` is the text of the line this position is on.
+    pub fn utf16_column(&self, source_line: &str) -> usize {
+        self.column_in(source_line, ColumnUnit::Utf16)
+    }
+
+    /// Returns the 1-based column counted in `unit`
+    ///
+    /// `source_line` is the text of the line this position is on.
+    pub fn column_in(&self, source_line: &str, unit: ColumnUnit) -> usize {
+        let prefix = self.line_prefix(source_line);
+        1 + match unit {
+            ColumnUnit::Bytes => prefix.len(),
+            ColumnUnit::Chars => prefix.chars().count(),
+            ColumnUnit::Utf16 => prefix.encode_utf16().count(),
+        }
+    }
+
+    /// Returns the text of `source_line` before this position's column
+    fn line_prefix<'a>(&self, source_line: &'a str) -> &'a str {
+        let end = source_line
+            .char_indices()
+            .nth(self.column.saturating_sub(1))
+            .map_or(source_line.len(), |(index, _)| index);
+        &source_line[..end]
+    }
+}
+
+/// Unit in which columns are counted
+///
+/// `Position::column` counts characters; editors and LSP clients often
+/// expect bytes or UTF-16 code units instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Bytes of UTF-8
+    Bytes,
+    /// Unicode scalar values, as `Position::column` counts them
+    #[default]
+    Chars,
+    /// UTF-16 code units
+    Utf16,
+}
+
+impl PartialOrd for Position {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Positions are ordered by byte offset
+impl Ord for Position {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.offset
+            .cmp(&other.offset)
+            .then(self.line.cmp(&other.line))
+            .then(self.column.cmp(&other.column))
+    }
 }
 
 impl Default for Position {
@@ -61,12 +143,22 @@ impl Default for Position {
 
 impl fmt::Display for Position {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}:{}", self.line, self.column)
+        if self.column > Self::DISPLAY_COLUMN_CAP {
+            write!(
+                f,
+                "line {}, column >{}",
+                self.line,
+                Self::DISPLAY_COLUMN_CAP
+            )
+        } else {
+            write!(f, "{}:{}", self.line, self.column)
+        }
     }
 }
 
 /// Represents a span of text in the source
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-token", derive(serde::Serialize, serde::Deserialize))]
 pub struct Span {
     /// Start position of the span
     pub start: Position,
@@ -143,7 +235,56 @@ impl Span {
 
     /// Returns true if this span contains the given position
     pub fn contains(&self, position: Position) -> bool {
-        position.offset >= self.start.offset && position.offset <= self.end.offset
+        self.contains_offset(position.offset)
+    }
+
+    /// Returns true if this span contains the given byte offset
+    ///
+    /// Like `contains`, the end offset is included so that a cursor placed
+    /// right after a token still belongs to it.
+    pub fn contains_offset(&self, offset: usize) -> bool {
+        offset >= self.start.offset && offset <= self.end.offset
+    }
+
+    /// Returns the smallest span covering both spans
+    pub fn merge(self, other: Span) -> Self {
+        self.extend_to(&other)
+    }
+
+    /// Returns the text covered by this span
+    ///
+    /// Returns an empty string if the span is out of bounds or does not fall
+    /// on character boundaries of `source`.
+    pub fn slice<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.byte_range()).unwrap_or("")
+    }
+
+    /// Returns the lines covered by this span (1-based, inclusive)
+    pub fn line_range(&self) -> RangeInclusive<usize> {
+        self.start.line..=self.end.line
+    }
+
+    /// Returns the byte range covered by this span
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset.max(self.start.offset)
+    }
+
+    /// Creates a span from a byte range, computing lines and columns from the source
+    pub fn from_byte_range(source: &str, range: Range<usize>) -> Self {
+        let start = Position::from_offset(source, range.start);
+        let mut end = start;
+        end.advance_by(
+            source
+                .get(start.offset..Position::from_offset(source, range.end).offset)
+                .unwrap_or(""),
+        );
+        Self { start, end }
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.byte_range()
     }
 }
 
@@ -161,6 +302,115 @@ impl fmt::Display for Span {
     }
 }
 
+/// Columns between tab stops used by `ErrorContext` unless set otherwise
+pub const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Most characters of source text shown on one line of an error
+///
+/// Error messages cut longer tokens and keys, and `ErrorContext` shows this
+/// many columns of longer lines around the error unless set otherwise.
+pub const DEFAULT_MAX_WIDTH: usize = 120;
+
+/// Source text quoted in an error message, cut to `DEFAULT_MAX_WIDTH` characters
+struct Excerpt<'a>(&'a str);
+
+impl fmt::Display for Excerpt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.char_indices().nth(DEFAULT_MAX_WIDTH) {
+            Some((end, _)) => write!(f, "{}…", &self.0[..end]),
+            None => f.write_str(self.0),
+        }
+    }
+}
+
+fn excerpt(text: &str) -> Excerpt<'_> {
+    Excerpt(text)
+}
+
+/// Returns whether `excerpt` cuts `text`
+fn is_truncated(text: &str) -> bool {
+    text.chars().nth(DEFAULT_MAX_WIDTH).is_some()
+}
+
+/// Columns a character takes in a terminal
+///
+/// Covers combining marks and the common ranges of wide characters and
+/// emoji; other characters take one column.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200B..=0x200D
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Width of `text` drawn from terminal column `start`, with tabs moving to the next tab stop
+fn display_width(text: &str, start: usize, tab_width: usize) -> usize {
+    let tab_width = tab_width.max(1);
+    let end = text.chars().fold(start, |column, c| match c {
+        '\t' => column + tab_width - column % tab_width,
+        _ => column + char_width(c),
+    });
+    end - start
+}
+
+/// Returns the `width` columns of `text` from column `start`, marking cut text with `…`
+///
+/// `text` has its tabs expanded already.
+fn clip_columns(text: &str, start: usize, width: usize) -> String {
+    let mut clipped = String::new();
+    let mut column = 0;
+    for c in text.chars() {
+        let end = column + char_width(c);
+        if column >= start && end <= start + width {
+            clipped.push(c);
+        }
+        column = end;
+    }
+    let mut shown = String::with_capacity(clipped.len() + 6);
+    if start > 0 && column > 0 {
+        shown.push('…');
+    }
+    shown.push_str(&clipped);
+    if column > start + width {
+        shown.push('…');
+    }
+    shown
+}
+
+/// Replaces the tabs in `text` with spaces up to the next tab stop
+fn expand_tabs(text: &str, tab_width: usize) -> String {
+    if !text.contains('\t') {
+        return text.to_string();
+    }
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+    for c in text.chars() {
+        let width = display_width(c.encode_utf8(&mut [0; 4]), column, tab_width);
+        match c {
+            '\t' => expanded.push_str(&" ".repeat(width)),
+            _ => expanded.push(c),
+        }
+        column += width;
+    }
+    expanded
+}
+
 /// Context information for enhanced error reporting
 #[derive(Debug, Clone)]
 pub struct ErrorContext {
@@ -172,6 +422,15 @@ pub struct ErrorContext {
     pub suggestions: Vec<String>,
     /// Additional help text
     pub help: Option<String>,
+    /// Unit of the columns shown in error locations
+    pub column_unit: ColumnUnit,
+    /// Columns between tab stops when drawing source lines
+    pub tab_width: usize,
+    /// Most columns of a source line to draw
+    ///
+    /// Longer lines show this many columns around the error, with `…` in
+    /// place of the text cut at either end.
+    pub max_width: usize,
 }
 
 impl ErrorContext {
@@ -182,6 +441,9 @@ impl ErrorContext {
             span,
             suggestions: Vec::new(),
             help: None,
+            column_unit: ColumnUnit::default(),
+            tab_width: DEFAULT_TAB_WIDTH,
+            max_width: DEFAULT_MAX_WIDTH,
         }
     }
 
@@ -233,12 +495,60 @@ impl ErrorContext {
         self
     }
 
+    /// Sets the unit of the columns shown in error locations
+    pub fn with_column_unit(mut self, column_unit: ColumnUnit) -> Self {
+        self.column_unit = column_unit;
+        self
+    }
+
+    /// Sets the columns between tab stops when drawing source lines
+    pub fn with_tab_width(mut self, tab_width: usize) -> Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    /// Sets the most columns of a source line to draw
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = max_width;
+        self
+    }
+
+    /// Formats the start of the span as `line:column`, with the column in `column_unit`
+    ///
+    /// Columns past `Position::DISPLAY_COLUMN_CAP` are written as
+    /// `line 1, column >100000`, as `Position` displays them.
+    pub fn location(&self) -> String {
+        let column = self.start_column();
+        if column > Position::DISPLAY_COLUMN_CAP {
+            format!(
+                "line {}, column >{}",
+                self.span.start.line,
+                Position::DISPLAY_COLUMN_CAP
+            )
+        } else {
+            format!("{}:{}", self.span.start.line, column)
+        }
+    }
+
+    /// Column of the start of the span in `column_unit`
+    fn start_column(&self) -> usize {
+        let line = self
+            .source
+            .lines()
+            .nth(self.span.start.line.saturating_sub(1))
+            .unwrap_or("");
+        self.span.start.column_in(line, self.column_unit)
+    }
+
     /// Extracts the source code snippet around the error
     pub fn source_snippet(&self) -> String {
         self.extract_lines_around_span(2)
     }
 
     /// Extracts lines around the error span with context
+    ///
+    /// Lines wider than `max_width` are cut to the same columns, centered on
+    /// the start of the span.
     pub fn extract_lines_around_span(&self, context_lines: usize) -> String {
         let lines: Vec<&str> = self.source.lines().collect();
         if lines.is_empty() {
@@ -254,45 +564,71 @@ impl ErrorContext {
         let mut result = String::new();
         let line_number_width = context_end.to_string().len();
 
-        for (i, line) in lines[context_start..context_end].iter().enumerate() {
+        // Columns of the error marker, measuring the text before and under it
+        // as the line is drawn
+        let marker = lines.get(start_line).map(|line| {
+            let prefix = self.span.start.line_prefix(line);
+            let marked = if self.span.start.line == self.span.end.line {
+                &line[prefix.len()..self.span.end.line_prefix(line).len().max(prefix.len())]
+            } else {
+                &line[prefix.len()..]
+            };
+            let indent = display_width(prefix, 0, self.tab_width);
+            let length = display_width(marked, indent, self.tab_width).max(1);
+            (indent, length, display_width(line, 0, self.tab_width))
+        });
+        let (indent, length, error_line_width) = marker.unwrap_or((0, 1, 0));
+
+        let shown = &lines[context_start..context_end];
+        let widest = shown
+            .iter()
+            .map(|line| display_width(line, 0, self.tab_width))
+            .max()
+            .unwrap_or(0);
+        // Columns drawn of each line, leaving room for an ellipsis at each
+        // end the error line is cut at
+        let window = (widest > self.max_width).then(|| {
+            let width = self.max_width.saturating_sub(2).max(1);
+            let one_cut = self.max_width.saturating_sub(1).max(1);
+            let centered = (indent + length.min(width) / 2).saturating_sub(width / 2);
+            if centered == 0 {
+                (0, one_cut)
+            } else if centered + one_cut >= error_line_width {
+                (error_line_width.saturating_sub(one_cut), one_cut)
+            } else {
+                (centered, width)
+            }
+        });
+
+        for (i, line) in shown.iter().enumerate() {
             let line_num = context_start + i + 1;
-            let is_error_line = line_num >= self.span.start.line && line_num <= self.span.end.line;
-            let is_context_line = !is_error_line;
-
-            // Use different formatting for error lines vs context lines
-            if is_error_line {
-                result.push_str(&format!(
-                    "{:width$} | {}\n",
-                    line_num,
-                    line,
-                    width = line_number_width
-                ));
-
-                // Add error indicator with improved positioning
-                if line_num == self.span.start.line {
-                    let spaces = " "
-                        .repeat(line_number_width + 3 + self.span.start.column.saturating_sub(1));
-                    let carets = if self.span.start.line == self.span.end.line {
-                        let length =
-                            (self.span.end.column.saturating_sub(self.span.start.column)).max(1);
-                        "^".repeat(length)
-                    } else {
-                        "^".repeat(
-                            line.len()
-                                .saturating_sub(self.span.start.column.saturating_sub(1))
-                                .max(1),
+            let text = expand_tabs(line, self.tab_width);
+            let text = match window {
+                Some((start, width)) => clip_columns(&text, start, width),
+                None => text,
+            };
+            result.push_str(&format!(
+                "{:width$} | {}\n",
+                line_num,
+                text,
+                width = line_number_width
+            ));
+
+            if line_num == self.span.start.line && marker.is_some() {
+                let (indent, length) = match window {
+                    Some((start, width)) => {
+                        let end = (indent + length).min(start + width);
+                        let indent = indent.max(start);
+                        (
+                            indent - start + usize::from(start > 0),
+                            end.saturating_sub(indent).max(1),
                         )
-                    };
-                    result.push_str(&format!("{}{}  <-- Error here\n", spaces, carets));
-                }
-            } else if is_context_line {
-                // Context lines are shown with a dimmed appearance
-                result.push_str(&format!(
-                    "{:width$} | {}\n",
-                    line_num,
-                    line,
-                    width = line_number_width
-                ));
+                    }
+                    None => (indent, length),
+                };
+                let spaces = " ".repeat(line_number_width + 3 + indent);
+                let carets = "^".repeat(length);
+                result.push_str(&format!("{}{}  <-- Error here\n", spaces, carets));
             }
         }
 
@@ -309,8 +645,17 @@ impl ErrorContext {
         self.extract_lines_around_span(3)
     }
 
-    /// Gets the exact text that caused the error
+    /// Gets the exact text that caused the error, cut to `max_width` characters
     pub fn error_text(&self) -> String {
+        let text = self.full_error_text();
+        match text.char_indices().nth(self.max_width) {
+            Some((end, _)) => format!("{}…", &text[..end]),
+            None => text,
+        }
+    }
+
+    /// Gets the text of the span on its first line
+    fn full_error_text(&self) -> String {
         let lines: Vec<&str> = self.source.lines().collect();
         if lines.is_empty() || self.span.start.line == 0 || self.span.start.line > lines.len() {
             return String::new();
@@ -349,7 +694,8 @@ impl ErrorContext {
         // Header with precise location
         output.push_str(&format!(
             "Error at {}: {}\n",
-            self.span.start, error_message
+            self.location(),
+            error_message
         ));
 
         // Show the problematic text if available
@@ -386,11 +732,11 @@ impl ErrorContext {
             format!(
                 "{} at {} (near '{}')",
                 error_message,
-                self.span.start,
+                self.location(),
                 error_text.trim()
             )
         } else {
-            format!("{} at {}", error_message, self.span.start)
+            format!("{} at {}", error_message, self.location())
         }
     }
 
@@ -402,7 +748,9 @@ impl ErrorContext {
         output.push_str("🚨 UCL Parse Error\n");
         output.push_str(&format!(
             "Location: {} (line {}, column {})\n",
-            self.span.start, self.span.start.line, self.span.start.column
+            self.location(),
+            self.span.start.line,
+            self.start_column()
         ));
         output.push_str(&format!("Message: {}\n", error_message));
 
@@ -438,11 +786,46 @@ impl ErrorContext {
 pub enum UclError {
     /// Lexical analysis error
     #[error("Lexical error: {0}")]
-    Lex(#[from] LexError),
+    Lex(#[source] LexError),
 
     /// Parsing error
     #[error("Parse error: {0}")]
-    Parse(#[from] ParseError),
+    Parse(#[source] ParseError),
+
+    /// Input larger than `ParserConfig::max_document_size`
+    #[error("Document of {size} bytes exceeds the maximum document size of {limit} bytes")]
+    DocumentTooLarge { size: usize, limit: usize },
+
+    /// Parse stopped because `ParserConfig::cancellation` was set
+    #[error("Parsing was cancelled at {position}")]
+    Cancelled { position: Position },
+
+    /// Byte input that cannot be decoded, with the byte offset of the first bad sequence
+    #[error("Invalid {detected} input at byte {position}")]
+    InvalidEncoding {
+        detected: &'static str,
+        position: usize,
+    },
+
+    /// Variable expansion requested from a build with the `no-variable-expansion` feature
+    #[error(
+        "Variable expansion is disabled: this build has the `no-variable-expansion` feature, \
+         which reads `$` as an ordinary character"
+    )]
+    VariableExpansionDisabled,
+
+    /// No value at a path asked for by `from_str_at_path`
+    ///
+    /// `closest` is the longest prefix of the path that exists, `None` when
+    /// not even its first key does.
+    #[error(
+        "No value at path '{path}'{}",
+        .closest.as_ref().map(|closest| format!("; the closest existing path is '{}'", closest)).unwrap_or_default()
+    )]
+    PathNotFound {
+        path: String,
+        closest: Option<String>,
+    },
 
     /// Serde deserialization error
     #[error("Serde error: {0}")]
@@ -451,10 +834,107 @@ pub enum UclError {
     /// I/O error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+
+    /// Value conversion error
+    #[error("Conversion error: {0}")]
+    Conversion(#[from] ConversionError),
 }
 
-/// Lexical analysis errors
+/// Oversized documents are reported as `UclError::DocumentTooLarge`
+impl From<LexError> for UclError {
+    fn from(error: LexError) -> Self {
+        match error {
+            LexError::DocumentTooLarge { size, limit } => {
+                UclError::DocumentTooLarge { size, limit }
+            }
+            other => UclError::Lex(other),
+        }
+    }
+}
+
+/// Oversized documents are reported as `UclError::DocumentTooLarge`, and
+/// cancelled parses as `UclError::Cancelled`
+impl From<ParseError> for UclError {
+    fn from(error: ParseError) -> Self {
+        match error {
+            ParseError::DocumentTooLarge { size, limit } => {
+                UclError::DocumentTooLarge { size, limit }
+            }
+            ParseError::Cancelled { position } => UclError::Cancelled { position },
+            other => UclError::Parse(other),
+        }
+    }
+}
+
+/// Errors produced when editing a `UclDocument`
 #[derive(Debug, Error)]
+pub enum EditError {
+    /// No value exists at the given path
+    #[error("No value at path '{path}'")]
+    PathNotFound { path: String },
+
+    /// The edited text no longer parses
+    #[error("Edited document does not parse: {0}")]
+    Parse(#[from] ParseError),
+}
+
+/// Describes an expected-token set in error messages
+fn expected_list(expected: &[&str]) -> String {
+    match expected {
+        [single] => single.to_string(),
+        _ => format!("one of {}", expected.join(", ")),
+    }
+}
+
+/// Errors produced by `UclWriter`
+#[derive(Debug, Error)]
+pub enum WriteError {
+    /// Writing to the output failed
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// An object or array was closed while none of that kind was innermost
+    #[error("No open {container} to end")]
+    NotOpen { container: &'static str },
+
+    /// An entry of the document or of an object was written without a key
+    #[error("Object entries need a key")]
+    MissingKey,
+
+    /// An array element was written with a key
+    #[error("Array elements cannot have a key, found '{key}'")]
+    KeyInArray { key: String },
+
+    /// The writer was finished with objects or arrays still open
+    #[error("{depth} objects or arrays are still open")]
+    Unclosed { depth: usize },
+}
+
+/// Errors produced when converting a `UclValue` into a Rust type
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ConversionError {
+    /// The value has a different type than the target
+    #[error("expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    /// The integer does not fit into the target type
+    #[error("integer {value} is out of range for {target}")]
+    OutOfRange { value: i64, target: &'static str },
+
+    /// An array element has a different type than the target
+    #[error("element {index} is {found}, expected {expected}")]
+    ElementTypeMismatch {
+        index: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+}
+
+/// Lexical analysis errors
+#[derive(Debug, Clone, Error)]
 pub enum LexError {
     /// Unexpected character encountered
     #[error("Unexpected character '{character}' at {position}")]
@@ -465,14 +945,14 @@ pub enum LexError {
     UnterminatedString { position: Position },
 
     /// Invalid escape sequence in string
-    #[error("Invalid escape sequence '\\{sequence}' at {position}")]
+    #[error("Invalid escape sequence '\\{}' at {position}", excerpt(sequence))]
     InvalidEscape {
         sequence: String,
         position: Position,
     },
 
     /// Invalid Unicode escape sequence
-    #[error("Invalid unicode escape '\\u{sequence}' at {position}")]
+    #[error("Invalid unicode escape '\\{}' at {position}", excerpt(sequence))]
     InvalidUnicodeEscape {
         sequence: String,
         position: Position,
@@ -482,6 +962,10 @@ pub enum LexError {
     #[error("Unterminated comment at {position}")]
     UnterminatedComment { position: Position },
 
+    /// Multi-line comment opened inside more than `LexerConfig::max_comment_nesting` others
+    #[error("Comment nesting exceeds the maximum depth of {limit} at {position}")]
+    CommentNestingTooDeep { limit: usize, position: Position },
+
     /// Invalid number format
     #[error("Invalid number format at {position}: {message}")]
     InvalidNumber { message: String, position: Position },
@@ -499,14 +983,17 @@ pub enum LexError {
     InvalidCppComment { message: String, position: Position },
 
     /// Invalid extended Unicode escape sequence
-    #[error("Invalid extended Unicode escape '\\u{{{sequence}}}' at {position}")]
+    #[error(
+        "Invalid extended Unicode escape '\\u{{{}}}' at {position}",
+        excerpt(sequence)
+    )]
     InvalidExtendedUnicodeEscape {
         sequence: String,
         position: Position,
     },
 
     /// Heredoc terminator not found with specific guidance
-    #[error("Heredoc terminator '{terminator}' not found at {position}")]
+    #[error("Heredoc terminator '{}' not found at {position}", excerpt(terminator))]
     HeredocTerminatorNotFound {
         terminator: String,
         position: Position,
@@ -519,26 +1006,109 @@ pub enum LexError {
         position: Position,
         suggestion: String,
     },
+
+    /// Bare or quoted key longer than `LexerConfig::max_key_length`
+    #[error("Key of {length} bytes exceeds the maximum key length of {limit} bytes at {position}")]
+    KeyTooLong {
+        length: usize,
+        limit: usize,
+        position: Position,
+    },
+
+    /// Bare `m` suffix with `MSuffix::Error`, which could mean minutes or a size
+    #[error(
+        "Ambiguous suffix 'm' in '{number}m' at {position}: write '{number}min' for minutes \
+         or '{number}mb' for a size",
+        number = excerpt(number)
+    )]
+    AmbiguousSuffix { number: String, position: Position },
+
+    /// Streamed input grew past the configured maximum document size
+    #[error("Input of at least {size} bytes exceeds the maximum document size of {limit} bytes")]
+    DocumentTooLarge { size: usize, limit: usize },
+
+    /// More tokens than `LexerConfig::max_tokens` allows, or than
+    /// `LexerConfig::max_tokens_per_input_byte` allows for the input size
+    ///
+    /// `bound` names the setting that gave the limit. The streaming lexer
+    /// reports the bytes read so far as `input_size`.
+    #[error(
+        "Token limit of {limit} exceeded at {position}: the limit is set by {bound} for \
+         {input_size} bytes of input"
+    )]
+    TokenLimitExceeded {
+        limit: usize,
+        bound: &'static str,
+        input_size: usize,
+        position: Position,
+    },
+
+    /// Input after a standalone scalar read by the `scalar` functions
+    #[error("Unexpected '{}' after the value at {position}", excerpt(text))]
+    TrailingInput { text: String, position: Position },
+
+    /// Standalone scalar of another kind than the `scalar` function reads
+    #[error("Expected {expected} at {position}, found {}", found_text(found))]
+    InvalidScalar {
+        expected: &'static str,
+        found: String,
+        position: Position,
+    },
+
+    /// State the lexer should never reach, reported instead of panicking
+    #[error("Internal lexer error at {position}: {detail}; please report this input")]
+    Internal {
+        detail: &'static str,
+        position: Position,
+    },
+}
+
+/// Describes the text found in place of a scalar
+fn found_text(found: &str) -> String {
+    if found.is_empty() {
+        "the end of input".to_string()
+    } else {
+        format!("'{}'", excerpt(found))
+    }
 }
 
 /// Parsing errors
 #[derive(Debug, Error)]
 pub enum ParseError {
     /// Unexpected token encountered
-    #[error("Unexpected token {token} at {position}, expected {expected}")]
+    ///
+    /// Token kinds are named as by `Token::type_name`; `expected` lists every
+    /// kind accepted at this point, with `value` standing for any value.
+    #[error(
+        "Unexpected token at {position}: expected {}; found {found}",
+        expected_list(expected)
+    )]
     UnexpectedToken {
-        token: String,
+        found: &'static str,
+        expected: Vec<&'static str>,
         position: Position,
-        expected: String,
     },
 
+    /// Character that cannot start a token
+    #[error("Unexpected character '{character}' at {position}")]
+    UnexpectedCharacter { character: char, position: Position },
+
     /// Variable not found during expansion
-    #[error("Variable '{name}' not found at {position}")]
+    #[error("Variable '{}' not found at {position}", excerpt(name))]
     VariableNotFound { name: String, position: Position },
 
     /// Duplicate key in object
-    #[error("Duplicate key '{key}' at {position}")]
-    DuplicateKey { key: String, position: Position },
+    #[error(
+        "duplicate key `{}` at line {} (first defined at line {})",
+        excerpt(key),
+        second_occurrence.line,
+        first_occurrence.line
+    )]
+    DuplicateKey {
+        key: String,
+        first_occurrence: Position,
+        second_occurrence: Position,
+    },
 
     /// Invalid object structure
     #[error("Invalid object structure at {position}: {message}")]
@@ -548,14 +1118,185 @@ pub enum ParseError {
     #[error("Invalid array structure at {position}: {message}")]
     InvalidArray { message: String, position: Position },
 
+    /// `key = value` or `key: value` written as an array element
+    #[error(
+        "Key '{key}' is assigned a value inside an array at {position}; \
+         wrap each element in braces, as in [ {{ {key} = ... }}, {{ {key} = ... }} ]",
+        key = excerpt(key)
+    )]
+    KeyValueInArray { key: String, position: Position },
+
+    /// Key with a character outside ASCII, with `KeyCharset::Ascii`
+    #[error(
+        "Key '{}' at {position} contains the non-ASCII character U+{:04X} '{character}'; \
+         keys must be ASCII",
+        excerpt(key),
+        u32::from(*character)
+    )]
+    NonAsciiKey {
+        key: String,
+        character: char,
+        position: Position,
+    },
+
+    /// `=` where the value of an assignment is expected
+    #[error("Unexpected '=' at {position}: did you accidentally repeat the assignment operator?")]
+    RepeatedAssignment { position: Position },
+
+    /// `,` or `;` before the first entry of an object or document, or the first element of an array
+    #[error(
+        "Unexpected '{separator}' at {position} at the start of the {container}; a separator \
+         can only come after a value"
+    )]
+    LeadingSeparator {
+        separator: char,
+        container: &'static str,
+        position: Position,
+    },
+
+    /// A second `,` between two entries of an object
+    #[error("Repeated ',' at {position}; entries are separated by at most one ','")]
+    RepeatedSeparator { position: Position },
+
+    /// Two separators in a row inside an array, as in `[1,,2]`
+    #[error(
+        "Missing element {index} of the array before '{separator}' at {position}; remove \
+         the extra separator"
+    )]
+    MissingArrayElement {
+        index: usize,
+        separator: char,
+        position: Position,
+    },
+
+    /// A second value on the line of an entry, as in `a = 1 2`
+    #[error(
+        "Missing separator before {found} at {position}; a value ends at a newline, ',' or ';'"
+    )]
+    MissingSeparator {
+        found: &'static str,
+        position: Position,
+    },
+
+    /// Content after a top-level array, object or scalar
+    #[error(
+        "Unexpected {found} at {position} after the top-level {root}; a document is a single \
+         value or a list of keys without enclosing braces"
+    )]
+    TrailingContent {
+        found: &'static str,
+        root: &'static str,
+        position: Position,
+    },
+
+    /// UCL syntax in a document read with `ParserConfig::json_only`
+    #[error("JSON does not allow {construct} at {position}")]
+    NotJson {
+        construct: &'static str,
+        position: Position,
+    },
+
+    /// Two plugins that claim the same number suffix or key path
+    #[error(
+        "Plugins '{plugin_a}' and '{plugin_b}' conflict: {detail}; allow one of them to \
+         override the other"
+    )]
+    PluginConflict {
+        plugin_a: String,
+        plugin_b: String,
+        detail: String,
+    },
+
     /// Variable expansion error
     #[error("Variable expansion error at {position}: {message}")]
     VariableExpansion { message: String, position: Position },
 
+    /// String that grows past `LexerConfig::max_string_length` when its variables are expanded
+    #[error(
+        "Expanded string of at least {length} bytes exceeds the maximum string length of \
+         {limit} bytes at {position}"
+    )]
+    ExpandedStringTooLong {
+        length: usize,
+        limit: usize,
+        position: Position,
+    },
+
     /// Maximum nesting depth exceeded
     #[error("Maximum nesting depth exceeded at {position}")]
     MaxDepthExceeded { position: Position },
 
+    /// Input larger than `ParserConfig::max_document_size`
+    #[error("Document of {size} bytes exceeds the maximum document size of {limit} bytes")]
+    DocumentTooLarge { size: usize, limit: usize },
+
+    /// Parse stopped because `ParserConfig::cancellation` was set
+    ///
+    /// The position is where the parser was when it saw the flag.
+    #[error("Parsing was cancelled at {position}")]
+    Cancelled { position: Position },
+
+    /// Non-finite number rejected by the parser configuration
+    #[error("Non-finite number '{}' is not allowed at {position}", excerpt(value))]
+    NonFiniteNumber { value: String, position: Position },
+
+    /// Number that is not a plain decimal, with `DecimalMode::PreserveString`
+    #[error(
+        "Number '{}' at {position} is not a plain decimal; exponents and suffixes are not allowed in decimal mode",
+        excerpt(value)
+    )]
+    InvalidDecimal { value: String, position: Position },
+
+    /// Constant name that would shadow a keyword such as `true` or `null`
+    #[error("'{name}' is a keyword and cannot be registered as a constant")]
+    ReservedConstant { name: String },
+
+    /// Bare word value that is not a registered constant, with `strict_constants` set
+    #[error(
+        "Unknown constant '{}' at {position}, quote the value if it is a string",
+        excerpt(name)
+    )]
+    UnknownConstant { name: String, position: Position },
+
+    /// Number suffix that is not a unit of the family expected at this position
+    #[error(
+        "Unknown {family} unit '{}' at {position}, expected one of: {}",
+        excerpt(unit),
+        valid_units.join(", ")
+    )]
+    UnknownUnit {
+        unit: String,
+        family: String,
+        valid_units: Vec<String>,
+        position: Position,
+    },
+
+    /// Reference to an anchor that no earlier `&name` defines
+    #[error("Undefined anchor '{}' referenced at {position}", excerpt(name))]
+    UndefinedAnchor { name: String, position: Position },
+
+    /// Reference to an anchor inside the value the anchor marks
+    #[error(
+        "Anchor '{}' is referenced inside its own value at {position}",
+        excerpt(name)
+    )]
+    RecursiveAnchor { name: String, position: Position },
+
+    /// Anchor name defined a second time
+    #[error(
+        "Anchor '{}' at {position} is already defined at {first_definition}",
+        excerpt(name)
+    )]
+    DuplicateAnchor {
+        name: String,
+        first_definition: Position,
+        position: Position,
+    },
+
+    /// References that copy more than `ParserConfig::max_alias_expansion` values in a document
+    #[error("Anchor references copy more than the maximum of {limit} values at {position}")]
+    AliasExpansionTooLarge { limit: usize, position: Position },
+
     /// NGINX-style syntax error with specific guidance
     #[error("NGINX-style syntax error at {position}: {message}")]
     NginxSyntaxError {
@@ -569,7 +1310,7 @@ pub enum ParseError {
     InvalidCommentSyntax { message: String, position: Position },
 
     /// Ambiguous bare word that needs clarification
-    #[error("Ambiguous bare word '{word}' at {position}")]
+    #[error("Ambiguous bare word '{}' at {position}", excerpt(word))]
     AmbiguousBareWord {
         word: String,
         position: Position,
@@ -591,6 +1332,113 @@ pub enum ParseError {
         position: Position,
         suggestion: String,
     },
+
+    /// Include directive with a malformed path or parameter list
+    #[error("Invalid include at {position}: {message}")]
+    InvalidInclude { message: String, position: Position },
+
+    /// Included file that could not be read
+    #[error("Cannot include '{path}' at {position}: {message}")]
+    IncludeFailed {
+        path: String,
+        message: String,
+        position: Position,
+    },
+
+    /// Error in an included file, whose positions refer to that file
+    #[error("In included file '{path}': {source}")]
+    InIncludedFile {
+        path: String,
+        #[source]
+        source: Box<ParseError>,
+    },
+
+    /// Lexer error without a parse error of its own, kept as the source
+    #[error("Lexical error: {0}")]
+    Lex(#[source] LexError),
+}
+
+/// Non-fatal message recorded by the parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Human-readable description
+    pub message: String,
+    /// Position the diagnostic refers to
+    pub position: Position,
+    /// Lint that produced the diagnostic, if any
+    pub code: Option<LintCode>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic
+    pub fn new(message: impl Into<String>, position: Position) -> Self {
+        Self {
+            message: message.into(),
+            position,
+            code: None,
+        }
+    }
+
+    /// Creates a diagnostic reported by a lint
+    pub fn lint(code: LintCode, message: impl Into<String>, position: Position) -> Self {
+        Self {
+            code: Some(code),
+            ..Self::new(message, position)
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(code) = self.code {
+            write!(f, "{}: ", code)?;
+        }
+        write!(f, "{} at {}", self.message, self.position)
+    }
+}
+
+/// Structural lints enabled through `LintConfig`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintCode {
+    /// Entries of one object mix `=` and `:`
+    MixedSeparators,
+    /// A key repeats the name of an enclosing key
+    ShadowedKey,
+    /// An empty object is assigned to a key that holds a scalar elsewhere
+    EmptyObject,
+    /// A quoted string reads as a number, boolean or duration
+    TypedString,
+}
+
+impl LintCode {
+    /// Every lint, in the order they are documented
+    pub const ALL: [LintCode; 4] = [
+        LintCode::MixedSeparators,
+        LintCode::ShadowedKey,
+        LintCode::EmptyObject,
+        LintCode::TypedString,
+    ];
+
+    /// Returns the stable name of the lint
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LintCode::MixedSeparators => "mixed-separators",
+            LintCode::ShadowedKey => "shadowed-key",
+            LintCode::EmptyObject => "empty-object",
+            LintCode::TypedString => "typed-string",
+        }
+    }
+
+    /// Looks up a lint by its stable name
+    pub fn from_name(name: &str) -> Option<LintCode> {
+        LintCode::ALL.into_iter().find(|code| code.as_str() == name)
+    }
+}
+
+impl fmt::Display for LintCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Serde integration errors
@@ -600,6 +1448,16 @@ pub enum SerdeError {
     #[error("{0}")]
     Custom(String),
 
+    /// Message from the type being deserialized, such as a value of the wrong type
+    #[error("{}", describe_message(message, path, position))]
+    Message {
+        message: String,
+        /// Key path of the value within the document
+        path: Vec<String>,
+        /// Position of the key that defined the value, when known
+        position: Option<Position>,
+    },
+
     /// Type mismatch during deserialization
     #[error("Type mismatch: expected {expected}, found {found} at {position}")]
     TypeMismatch {
@@ -615,6 +1473,200 @@ pub enum SerdeError {
     /// Unknown field encountered
     #[error("Unknown field '{field}' at {position}")]
     UnknownField { field: String, position: Position },
+
+    /// Array with the wrong number of elements for a tuple or fixed-size array
+    #[error(
+        "expected {expected} elements for {target}, found {found} {}",
+        describe_location(path, position)
+    )]
+    InvalidLength {
+        expected: usize,
+        found: usize,
+        target: String,
+        /// Key path of the array within the document
+        path: Vec<String>,
+        /// Position of the key that defined the array, when known
+        position: Option<Position>,
+    },
+
+    /// String or array that could not be decoded into bytes
+    #[error("Invalid bytes {}: {message}", describe_key(path, position))]
+    InvalidBytes {
+        message: String,
+        /// Key path of the value within the document
+        path: Vec<String>,
+        /// Position of the key that defined the value, when known
+        position: Option<Position>,
+    },
+
+    /// String that lenient coercion could not read as the requested type
+    #[error(
+        "Cannot convert \"{value}\" to {target} {}",
+        describe_key(path, position)
+    )]
+    InvalidCoercion {
+        value: String,
+        target: String,
+        /// Key path of the value within the document
+        path: Vec<String>,
+        /// Position of the key that defined the value, when known
+        position: Option<Position>,
+    },
+
+    /// Number with a bare `m` suffix, kept as text under `MSuffix::Error`, for a number target
+    #[error(
+        "Ambiguous suffix 'm' in '{number}m' {}: write '{number}min' for minutes or \
+         '{number}mb' for a size",
+        describe_key(path, position)
+    )]
+    AmbiguousSuffix {
+        number: String,
+        /// Key path of the value within the document
+        path: Vec<String>,
+        /// Position of the key that defined the value, when known
+        position: Option<Position>,
+    },
+
+    /// Two keys of one object that name the same field under `ParserConfig::key_alias_style`
+    #[error(
+        "Duplicate key: {} name the same field",
+        describe_duplicate(first, second, path, positions)
+    )]
+    DuplicateField {
+        first: String,
+        second: String,
+        /// Key path of the object within the document
+        path: Vec<String>,
+        /// Positions of the first and second key, empty when not known
+        positions: Vec<Position>,
+    },
+
+    /// Name that matches none of an enum's variants
+    #[error(
+        "Unknown variant `{variant}` {}, expected {}",
+        describe_key(path, position),
+        describe_variants(expected)
+    )]
+    UnknownVariant {
+        variant: String,
+        /// Variant names the enum accepts
+        expected: Vec<String>,
+        /// Key path of the value within the document
+        path: Vec<String>,
+        /// Position of the key that defined the value, when known
+        position: Option<Position>,
+    },
+}
+
+impl SerdeError {
+    /// Returns the dotted key path of the value the error is about
+    ///
+    /// `None` for errors about the document root and for errors that do not
+    /// record where they were raised.
+    pub fn key_path(&self) -> Option<String> {
+        match self {
+            SerdeError::Message { path, .. }
+            | SerdeError::DuplicateField { path, .. }
+            | SerdeError::InvalidLength { path, .. }
+            | SerdeError::InvalidBytes { path, .. }
+            | SerdeError::InvalidCoercion { path, .. }
+            | SerdeError::AmbiguousSuffix { path, .. }
+            | SerdeError::UnknownVariant { path, .. }
+                if !path.is_empty() =>
+            {
+                Some(path.join("."))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the key path of errors raised inside a nested value
+    pub(crate) fn path_mut(&mut self) -> Option<&mut Vec<String>> {
+        match self {
+            SerdeError::DuplicateField { path, .. } => Some(path),
+            other => other.location_mut().map(|(path, _)| path),
+        }
+    }
+
+    /// Returns the key path and position of errors raised inside a nested value
+    pub(crate) fn location_mut(&mut self) -> Option<(&mut Vec<String>, &mut Option<Position>)> {
+        match self {
+            SerdeError::Message { path, position, .. }
+            | SerdeError::InvalidLength { path, position, .. }
+            | SerdeError::InvalidBytes { path, position, .. }
+            | SerdeError::InvalidCoercion { path, position, .. }
+            | SerdeError::AmbiguousSuffix { path, position, .. }
+            | SerdeError::UnknownVariant { path, position, .. } => Some((path, position)),
+            _ => None,
+        }
+    }
+}
+
+/// Describes where a value is for error messages
+fn describe_location(path: &[String], position: &Option<Position>) -> String {
+    match position {
+        Some(position) => format!("at line {}", position.line),
+        None if path.is_empty() => "at the document root".to_string(),
+        None => format!("at '{}'", path.join(".")),
+    }
+}
+
+/// Prefixes a message with the key path and position it is about, if any
+fn describe_message(message: &str, path: &[String], position: &Option<Position>) -> String {
+    match position {
+        _ if path.is_empty() => message.to_string(),
+        Some(position) => format!(
+            "error at {} (line {}, col {}): {}",
+            path.join("."),
+            position.line,
+            position.column,
+            message
+        ),
+        None => format!("error at {}: {}", path.join("."), message),
+    }
+}
+
+/// Describes two spellings of one field and where they are for error messages
+fn describe_duplicate(
+    first: &str,
+    second: &str,
+    path: &[String],
+    positions: &[Position],
+) -> String {
+    match positions {
+        [first_position, second_position] => format!(
+            "'{}' at {} and '{}' at {}",
+            first, first_position, second, second_position
+        ),
+        _ if path.is_empty() => format!("'{}' and '{}'", first, second),
+        _ => format!("'{}' and '{}' in '{}'", first, second, path.join(".")),
+    }
+}
+
+/// Describes which key a value belongs to and where it is for error messages
+fn describe_key(path: &[String], position: &Option<Position>) -> String {
+    match position {
+        Some(position) if !path.is_empty() => {
+            format!("for '{}' at line {}", path.join("."), position.line)
+        }
+        _ => describe_location(path, position),
+    }
+}
+
+/// Lists the accepted variant names of an enum for error messages
+fn describe_variants(expected: &[String]) -> String {
+    match expected {
+        [] => "no variants".to_string(),
+        [only] => format!("`{}`", only),
+        _ => format!(
+            "one of {}",
+            expected
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
 }
 
 /// Enhanced error with context information
@@ -724,7 +1776,7 @@ impl ErrorContext {
         Self::new(source, span)
             .with_suggestion(format!(
                 "Add '{}' on its own line to terminate the heredoc",
-                terminator
+                excerpt(terminator)
             ))
             .with_suggestion(
                 "Ensure the terminator line contains only the terminator (whitespace is allowed)"
@@ -750,8 +1802,9 @@ impl ParseError {
 
     /// Generate suggestions for bare word errors
     pub fn suggest_bare_word_fix(word: &str) -> String {
+        let quoted = excerpt(word);
         if word.contains('-') || word.contains(' ') || word.contains('.') {
-            format!("Quote the value: \"{}\"", word)
+            format!("Quote the value: \"{}\"", quoted)
         } else if [
             "true", "false", "null", "yes", "no", "on", "off", "inf", "nan",
         ]
@@ -764,12 +1817,12 @@ impl ParseError {
         } else if word.chars().any(|c| !c.is_alphanumeric() && c != '_') {
             format!(
                 "Bare words can only contain letters, numbers, and underscores. Use quotes: \"{}\"",
-                word
+                quoted
             )
         } else {
             format!(
                 "Use quotes if '{}' should be a literal string: \"{}\"",
-                word, word
+                quoted, quoted
             )
         }
     }
@@ -827,7 +1880,456 @@ impl LexError {
     }
 }
 
+impl LexError {
+    /// Codes returned by `code`, in the order the variants are declared
+    pub const CODES: &'static [&'static str] = &[
+        "lex/unexpected-character",
+        "lex/unterminated-string",
+        "lex/invalid-escape",
+        "lex/invalid-unicode-escape",
+        "lex/unterminated-comment",
+        "lex/comment-nesting-too-deep",
+        "lex/invalid-number",
+        "lex/invalid-heredoc",
+        "lex/invalid-utf8",
+        "lex/invalid-cpp-comment",
+        "lex/invalid-extended-unicode-escape",
+        "lex/heredoc-terminator-not-found",
+        "lex/invalid-bare-word-character",
+        "lex/key-too-long",
+        "lex/ambiguous-suffix",
+        "lex/document-too-large",
+        "lex/token-limit-exceeded",
+        "lex/trailing-input",
+        "lex/invalid-scalar",
+        "lex/internal",
+    ];
+
+    /// Returns the stable code of the error, such as `lex/unterminated-string`
+    ///
+    /// Codes are part of the public API: they do not change between releases
+    /// even when the message does.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexError::UnexpectedCharacter { .. } => "lex/unexpected-character",
+            LexError::UnterminatedString { .. } => "lex/unterminated-string",
+            LexError::InvalidEscape { .. } => "lex/invalid-escape",
+            LexError::InvalidUnicodeEscape { .. } => "lex/invalid-unicode-escape",
+            LexError::UnterminatedComment { .. } => "lex/unterminated-comment",
+            LexError::CommentNestingTooDeep { .. } => "lex/comment-nesting-too-deep",
+            LexError::InvalidNumber { .. } => "lex/invalid-number",
+            LexError::InvalidHeredoc { .. } => "lex/invalid-heredoc",
+            LexError::InvalidUtf8 { .. } => "lex/invalid-utf8",
+            LexError::InvalidCppComment { .. } => "lex/invalid-cpp-comment",
+            LexError::InvalidExtendedUnicodeEscape { .. } => "lex/invalid-extended-unicode-escape",
+            LexError::HeredocTerminatorNotFound { .. } => "lex/heredoc-terminator-not-found",
+            LexError::InvalidBareWordCharacter { .. } => "lex/invalid-bare-word-character",
+            LexError::KeyTooLong { .. } => "lex/key-too-long",
+            LexError::AmbiguousSuffix { .. } => "lex/ambiguous-suffix",
+            LexError::DocumentTooLarge { .. } => "lex/document-too-large",
+            LexError::TokenLimitExceeded { .. } => "lex/token-limit-exceeded",
+            LexError::TrailingInput { .. } => "lex/trailing-input",
+            LexError::InvalidScalar { .. } => "lex/invalid-scalar",
+            LexError::Internal { .. } => "lex/internal",
+        }
+    }
+
+    /// Returns the position the error is about, `None` for errors about the whole input
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            LexError::UnexpectedCharacter { position, .. }
+            | LexError::UnterminatedString { position }
+            | LexError::InvalidEscape { position, .. }
+            | LexError::InvalidUnicodeEscape { position, .. }
+            | LexError::UnterminatedComment { position }
+            | LexError::CommentNestingTooDeep { position, .. }
+            | LexError::InvalidNumber { position, .. }
+            | LexError::InvalidHeredoc { position, .. }
+            | LexError::InvalidUtf8 { position }
+            | LexError::InvalidCppComment { position, .. }
+            | LexError::InvalidExtendedUnicodeEscape { position, .. }
+            | LexError::HeredocTerminatorNotFound { position, .. }
+            | LexError::InvalidBareWordCharacter { position, .. }
+            | LexError::KeyTooLong { position, .. }
+            | LexError::AmbiguousSuffix { position, .. }
+            | LexError::TokenLimitExceeded { position, .. }
+            | LexError::TrailingInput { position, .. }
+            | LexError::InvalidScalar { position, .. }
+            | LexError::Internal { position, .. } => Some(*position),
+            LexError::DocumentTooLarge { .. } => None,
+        }
+    }
+
+    /// Returns the first suggestion of `suggest_fix`, if any
+    pub fn hint(&self) -> Option<String> {
+        self.suggest_fix().into_iter().next()
+    }
+
+    /// Returns whether the message shortens source text it quotes
+    ///
+    /// Text taken from the input, such as the rest of the line after a
+    /// scalar, is cut to `DEFAULT_MAX_WIDTH` characters in the message.
+    pub fn message_truncated(&self) -> bool {
+        match self {
+            LexError::InvalidEscape { sequence, .. }
+            | LexError::InvalidUnicodeEscape { sequence, .. }
+            | LexError::InvalidExtendedUnicodeEscape { sequence, .. } => is_truncated(sequence),
+            LexError::HeredocTerminatorNotFound { terminator, .. } => is_truncated(terminator),
+            LexError::AmbiguousSuffix { number, .. } => is_truncated(number),
+            LexError::TrailingInput { text, .. } => is_truncated(text),
+            LexError::InvalidScalar { found, .. } => is_truncated(found),
+            _ => false,
+        }
+    }
+}
+
+impl ParseError {
+    /// Codes returned by `code`, in the order the variants are declared
+    ///
+    /// `InIncludedFile` and `Lex` have no code of their own and report the
+    /// code of the error they wrap.
+    pub const CODES: &'static [&'static str] = &[
+        "parse/unexpected-token",
+        "parse/unexpected-character",
+        "parse/variable-not-found",
+        "parse/duplicate-key",
+        "parse/invalid-object",
+        "parse/invalid-array",
+        "parse/key-value-in-array",
+        "parse/non-ascii-key",
+        "parse/repeated-assignment",
+        "parse/leading-separator",
+        "parse/repeated-separator",
+        "parse/missing-array-element",
+        "parse/missing-separator",
+        "parse/trailing-content",
+        "parse/not-json",
+        "parse/plugin-conflict",
+        "parse/variable-expansion",
+        "parse/expanded-string-too-long",
+        "parse/max-depth-exceeded",
+        "parse/document-too-large",
+        "parse/cancelled",
+        "parse/non-finite-number",
+        "parse/invalid-decimal",
+        "parse/reserved-constant",
+        "parse/unknown-constant",
+        "parse/unknown-unit",
+        "parse/undefined-anchor",
+        "parse/recursive-anchor",
+        "parse/duplicate-anchor",
+        "parse/alias-expansion-too-large",
+        "parse/nginx-syntax",
+        "parse/invalid-comment-syntax",
+        "parse/ambiguous-bare-word",
+        "parse/invalid-implicit-syntax",
+        "parse/mixed-syntax-styles",
+        "parse/invalid-include",
+        "parse/include-failed",
+    ];
+
+    /// Returns the stable code of the error, such as `parse/duplicate-key`
+    ///
+    /// Errors in included files and lexer errors report the code of the
+    /// error they wrap.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "parse/unexpected-token",
+            ParseError::UnexpectedCharacter { .. } => "parse/unexpected-character",
+            ParseError::VariableNotFound { .. } => "parse/variable-not-found",
+            ParseError::DuplicateKey { .. } => "parse/duplicate-key",
+            ParseError::InvalidObject { .. } => "parse/invalid-object",
+            ParseError::InvalidArray { .. } => "parse/invalid-array",
+            ParseError::KeyValueInArray { .. } => "parse/key-value-in-array",
+            ParseError::NonAsciiKey { .. } => "parse/non-ascii-key",
+            ParseError::RepeatedAssignment { .. } => "parse/repeated-assignment",
+            ParseError::LeadingSeparator { .. } => "parse/leading-separator",
+            ParseError::RepeatedSeparator { .. } => "parse/repeated-separator",
+            ParseError::MissingArrayElement { .. } => "parse/missing-array-element",
+            ParseError::MissingSeparator { .. } => "parse/missing-separator",
+            ParseError::TrailingContent { .. } => "parse/trailing-content",
+            ParseError::NotJson { .. } => "parse/not-json",
+            ParseError::PluginConflict { .. } => "parse/plugin-conflict",
+            ParseError::VariableExpansion { .. } => "parse/variable-expansion",
+            ParseError::ExpandedStringTooLong { .. } => "parse/expanded-string-too-long",
+            ParseError::MaxDepthExceeded { .. } => "parse/max-depth-exceeded",
+            ParseError::DocumentTooLarge { .. } => "parse/document-too-large",
+            ParseError::Cancelled { .. } => "parse/cancelled",
+            ParseError::NonFiniteNumber { .. } => "parse/non-finite-number",
+            ParseError::InvalidDecimal { .. } => "parse/invalid-decimal",
+            ParseError::ReservedConstant { .. } => "parse/reserved-constant",
+            ParseError::UnknownConstant { .. } => "parse/unknown-constant",
+            ParseError::UnknownUnit { .. } => "parse/unknown-unit",
+            ParseError::UndefinedAnchor { .. } => "parse/undefined-anchor",
+            ParseError::RecursiveAnchor { .. } => "parse/recursive-anchor",
+            ParseError::DuplicateAnchor { .. } => "parse/duplicate-anchor",
+            ParseError::AliasExpansionTooLarge { .. } => "parse/alias-expansion-too-large",
+            ParseError::NginxSyntaxError { .. } => "parse/nginx-syntax",
+            ParseError::InvalidCommentSyntax { .. } => "parse/invalid-comment-syntax",
+            ParseError::AmbiguousBareWord { .. } => "parse/ambiguous-bare-word",
+            ParseError::InvalidImplicitSyntax { .. } => "parse/invalid-implicit-syntax",
+            ParseError::MixedSyntaxStyles { .. } => "parse/mixed-syntax-styles",
+            ParseError::InvalidInclude { .. } => "parse/invalid-include",
+            ParseError::IncludeFailed { .. } => "parse/include-failed",
+            ParseError::InIncludedFile { source, .. } => source.code(),
+            ParseError::Lex(error) => error.code(),
+        }
+    }
+
+    /// Returns the position the error is about, `None` for errors about the whole input
+    ///
+    /// Positions of errors in included files refer to the included file.
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            ParseError::UnexpectedToken { position, .. }
+            | ParseError::UnexpectedCharacter { position, .. }
+            | ParseError::VariableNotFound { position, .. }
+            | ParseError::InvalidObject { position, .. }
+            | ParseError::InvalidArray { position, .. }
+            | ParseError::KeyValueInArray { position, .. }
+            | ParseError::NonAsciiKey { position, .. }
+            | ParseError::RepeatedAssignment { position }
+            | ParseError::LeadingSeparator { position, .. }
+            | ParseError::RepeatedSeparator { position }
+            | ParseError::MissingArrayElement { position, .. }
+            | ParseError::MissingSeparator { position, .. }
+            | ParseError::TrailingContent { position, .. }
+            | ParseError::NotJson { position, .. }
+            | ParseError::VariableExpansion { position, .. }
+            | ParseError::ExpandedStringTooLong { position, .. }
+            | ParseError::MaxDepthExceeded { position }
+            | ParseError::Cancelled { position }
+            | ParseError::NonFiniteNumber { position, .. }
+            | ParseError::InvalidDecimal { position, .. }
+            | ParseError::UnknownConstant { position, .. }
+            | ParseError::UnknownUnit { position, .. }
+            | ParseError::UndefinedAnchor { position, .. }
+            | ParseError::RecursiveAnchor { position, .. }
+            | ParseError::DuplicateAnchor { position, .. }
+            | ParseError::AliasExpansionTooLarge { position, .. }
+            | ParseError::NginxSyntaxError { position, .. }
+            | ParseError::InvalidCommentSyntax { position, .. }
+            | ParseError::AmbiguousBareWord { position, .. }
+            | ParseError::InvalidImplicitSyntax { position, .. }
+            | ParseError::MixedSyntaxStyles { position, .. }
+            | ParseError::InvalidInclude { position, .. }
+            | ParseError::IncludeFailed { position, .. } => Some(*position),
+            ParseError::DuplicateKey {
+                second_occurrence, ..
+            } => Some(*second_occurrence),
+            ParseError::PluginConflict { .. }
+            | ParseError::DocumentTooLarge { .. }
+            | ParseError::ReservedConstant { .. } => None,
+            ParseError::InIncludedFile { source, .. } => source.position(),
+            ParseError::Lex(error) => error.position(),
+        }
+    }
+
+    /// Returns a suggestion for fixing the input, if the error carries one
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            ParseError::NginxSyntaxError { suggestion, .. }
+            | ParseError::AmbiguousBareWord { suggestion, .. }
+            | ParseError::MixedSyntaxStyles { suggestion, .. } => Some(suggestion.clone()),
+            ParseError::InvalidImplicitSyntax {
+                expected_pattern, ..
+            } => Some(format!("expected {}", expected_pattern)),
+            ParseError::InIncludedFile { source, .. } => source.hint(),
+            ParseError::Lex(error) => error.hint(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the message shortens source text it quotes, as `LexError::message_truncated`
+    pub fn message_truncated(&self) -> bool {
+        match self {
+            ParseError::DuplicateKey { key, .. }
+            | ParseError::KeyValueInArray { key, .. }
+            | ParseError::NonAsciiKey { key, .. } => is_truncated(key),
+            ParseError::VariableNotFound { name, .. }
+            | ParseError::UnknownConstant { name, .. }
+            | ParseError::UndefinedAnchor { name, .. }
+            | ParseError::RecursiveAnchor { name, .. }
+            | ParseError::DuplicateAnchor { name, .. } => is_truncated(name),
+            ParseError::NonFiniteNumber { value, .. }
+            | ParseError::InvalidDecimal { value, .. } => is_truncated(value),
+            ParseError::UnknownUnit { unit, .. } => is_truncated(unit),
+            ParseError::AmbiguousBareWord { word, .. } => is_truncated(word),
+            ParseError::InIncludedFile { source, .. } => source.message_truncated(),
+            ParseError::Lex(error) => error.message_truncated(),
+            _ => false,
+        }
+    }
+}
+
+impl SerdeError {
+    /// Codes returned by `code`, in the order the variants are declared
+    pub const CODES: &'static [&'static str] = &[
+        "serde/custom",
+        "serde/message",
+        "serde/type-mismatch",
+        "serde/missing-field",
+        "serde/unknown-field",
+        "serde/invalid-length",
+        "serde/invalid-bytes",
+        "serde/invalid-coercion",
+        "serde/ambiguous-suffix",
+        "serde/duplicate-field",
+        "serde/unknown-variant",
+    ];
+
+    /// Returns the stable code of the error, such as `serde/missing-field`
+    pub fn code(&self) -> &'static str {
+        match self {
+            SerdeError::Custom(_) => "serde/custom",
+            SerdeError::Message { .. } => "serde/message",
+            SerdeError::TypeMismatch { .. } => "serde/type-mismatch",
+            SerdeError::MissingField { .. } => "serde/missing-field",
+            SerdeError::UnknownField { .. } => "serde/unknown-field",
+            SerdeError::InvalidLength { .. } => "serde/invalid-length",
+            SerdeError::InvalidBytes { .. } => "serde/invalid-bytes",
+            SerdeError::InvalidCoercion { .. } => "serde/invalid-coercion",
+            SerdeError::AmbiguousSuffix { .. } => "serde/ambiguous-suffix",
+            SerdeError::DuplicateField { .. } => "serde/duplicate-field",
+            SerdeError::UnknownVariant { .. } => "serde/unknown-variant",
+        }
+    }
+
+    /// Returns the position of the key the error is about, when known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            SerdeError::TypeMismatch { position, .. }
+            | SerdeError::MissingField { position, .. }
+            | SerdeError::UnknownField { position, .. } => Some(*position),
+            SerdeError::Message { position, .. }
+            | SerdeError::InvalidLength { position, .. }
+            | SerdeError::InvalidBytes { position, .. }
+            | SerdeError::InvalidCoercion { position, .. }
+            | SerdeError::AmbiguousSuffix { position, .. }
+            | SerdeError::UnknownVariant { position, .. } => *position,
+            SerdeError::DuplicateField { positions, .. } => positions.last().copied(),
+            SerdeError::Custom(_) => None,
+        }
+    }
+}
+
+impl ConversionError {
+    /// Codes returned by `code`, in the order the variants are declared
+    pub const CODES: &'static [&'static str] = &[
+        "conversion/type-mismatch",
+        "conversion/out-of-range",
+        "conversion/element-type-mismatch",
+    ];
+
+    /// Returns the stable code of the error, such as `conversion/out-of-range`
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConversionError::TypeMismatch { .. } => "conversion/type-mismatch",
+            ConversionError::OutOfRange { .. } => "conversion/out-of-range",
+            ConversionError::ElementTypeMismatch { .. } => "conversion/element-type-mismatch",
+        }
+    }
+}
+
 impl UclError {
+    /// Returns the dotted key path of the value a deserialization error is about
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Server {
+    ///     port: u16,
+    /// }
+    ///
+    /// let err = ucl_lexer::from_str::<Server>("port = \"http\"").unwrap_err();
+    /// assert_eq!(err.key_path().as_deref(), Some("port"));
+    /// ```
+    pub fn key_path(&self) -> Option<String> {
+        match self {
+            UclError::Serde(error) => error.key_path(),
+            _ => None,
+        }
+    }
+
+    /// Codes of the variants that do not wrap another error, in declaration order
+    ///
+    /// Wrapped lexer, parser, deserialization and conversion errors report
+    /// the codes in `LexError::CODES`, `ParseError::CODES`,
+    /// `SerdeError::CODES` and `ConversionError::CODES`.
+    pub const CODES: &'static [&'static str] = &[
+        "ucl/document-too-large",
+        "ucl/cancelled",
+        "ucl/invalid-encoding",
+        "ucl/variable-expansion-disabled",
+        "ucl/path-not-found",
+        "ucl/io",
+    ];
+
+    /// Returns the stable code of the error, such as `parse/duplicate-key`
+    ///
+    /// Codes are part of the public API, so tools can route errors without
+    /// matching on messages:
+    ///
+    /// ```rust
+    /// let err = ucl_lexer::from_str::<serde_json::Value>("a = \"open").unwrap_err();
+    /// assert_eq!(err.code(), "lex/unterminated-string");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            UclError::Lex(error) => error.code(),
+            UclError::Parse(error) => error.code(),
+            UclError::DocumentTooLarge { .. } => "ucl/document-too-large",
+            UclError::Cancelled { .. } => "ucl/cancelled",
+            UclError::InvalidEncoding { .. } => "ucl/invalid-encoding",
+            UclError::VariableExpansionDisabled => "ucl/variable-expansion-disabled",
+            UclError::PathNotFound { .. } => "ucl/path-not-found",
+            UclError::Serde(error) => error.code(),
+            UclError::Io(_) => "ucl/io",
+            UclError::Conversion(error) => error.code(),
+        }
+    }
+
+    /// Returns the position the error is about, when known
+    pub fn position(&self) -> Option<Position> {
+        match self {
+            UclError::Lex(error) => error.position(),
+            UclError::Parse(error) => error.position(),
+            UclError::Serde(error) => error.position(),
+            UclError::Cancelled { position } => Some(*position),
+            _ => None,
+        }
+    }
+
+    /// Returns a suggestion for fixing the input, if the error carries one
+    pub fn hint(&self) -> Option<String> {
+        match self {
+            UclError::Lex(error) => error.hint(),
+            UclError::Parse(error) => error.hint(),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the message shortens source text it quotes
+    ///
+    /// Messages quote at most `DEFAULT_MAX_WIDTH` characters of a token or
+    /// key, followed by `…`, so a huge input cannot flood a log. Tooling that
+    /// needs the full text can take it from the source at `position`.
+    ///
+    /// ```rust
+    /// let input = format!("1 {}", "x".repeat(1_000_000));
+    /// let err = ucl_lexer::UclError::from(ucl_lexer::scalar::parse_scalar(&input).unwrap_err());
+    /// assert!(err.message_truncated());
+    /// assert!(err.to_string().len() < 200);
+    /// ```
+    pub fn message_truncated(&self) -> bool {
+        match self {
+            UclError::Lex(error) => error.message_truncated(),
+            UclError::Parse(error) => error.message_truncated(),
+            _ => false,
+        }
+    }
+
     /// Enhances this error with context information from source text
     pub fn with_source_context(self, source: &str) -> EnhancedError {
         let context = match &self {
@@ -861,14 +2363,14 @@ impl UclError {
                         let escape_text = format!("\\{}", sequence);
                         let suggestion = match sequence.as_str() {
                             "x" => "Use \\uXXXX for Unicode escapes instead of \\x".to_string(),
-                            _ => format!("Use a valid escape sequence like \\n, \\t, \\r, \\\\, \\\", or \\uXXXX instead of \\{}", sequence),
+                            _ => format!("Use a valid escape sequence like \\n, \\t, \\r, \\\\, \\\", or \\uXXXX instead of \\{}", excerpt(sequence)),
                         };
                         ErrorContext::for_token(source.to_string(), *position, &escape_text)
                             .with_suggestion(suggestion)
                             .with_help("Valid escape sequences: \\n (newline), \\t (tab), \\r (carriage return), \\\\ (backslash), \\\" (quote), \\uXXXX or \\u{...} (Unicode)".to_string())
                     }
                     LexError::InvalidUnicodeEscape { sequence, position } => {
-                        ErrorContext::unicode_escape_error(source.to_string(), *position, &format!("\\u{}", sequence))
+                        ErrorContext::unicode_escape_error(source.to_string(), *position, &format!("\\{}", sequence))
                     }
                     LexError::InvalidExtendedUnicodeEscape { sequence, position } => {
                         ErrorContext::unicode_escape_error(source.to_string(), *position, &format!("\\u{{{}}}", sequence))
@@ -899,15 +2401,15 @@ impl UclError {
             }
             UclError::Parse(parse_err) => {
                 let context = match parse_err {
-                    ParseError::UnexpectedToken { token, expected, position } => {
-                        let mut ctx = ErrorContext::for_token(source.to_string(), *position, token)
-                            .with_suggestion(format!("Replace '{}' with {}", token, expected));
+                    ParseError::UnexpectedToken { found, expected, position } => {
+                        let mut ctx = ErrorContext::for_token(source.to_string(), *position, found)
+                            .with_suggestion(format!("Replace {} with {}", found, expected_list(expected)));
 
                         // Add context-specific suggestions
-                        if expected.contains("=") || expected.contains(":") {
+                        if expected.contains(&"'='") || expected.contains(&"':'") {
                             ctx = ctx.with_suggestion("For NGINX-style syntax, you can omit separators: 'key value'".to_string());
                         }
-                        if token == "{" && expected.contains("value") {
+                        if *found == "'{'" && expected.contains(&"value") {
                             ctx = ctx.with_suggestion("Use 'key { ... }' for implicit object creation".to_string());
                         }
 
@@ -916,20 +2418,25 @@ impl UclError {
                     ParseError::VariableNotFound { name, position } => {
                         ErrorContext::for_token(source.to_string(), *position, &format!("${}", name))
                             .with_suggestions(vec![
-                                format!("Define the variable '{}' or check for typos", name),
+                                format!("Define the variable '{}' or check for typos", excerpt(name)),
                                 "Use $$ to escape a literal $ character".to_string()
                             ])
                             .with_help("Variables can be environment variables or custom variables provided to the parser".to_string())
                     }
-                    ParseError::DuplicateKey { key, position } => {
-                        ErrorContext::for_token(source.to_string(), *position, key)
+                    ParseError::DuplicateKey { key, second_occurrence, .. } => {
+                        ErrorContext::for_token(source.to_string(), *second_occurrence, key)
                             .with_suggestions(vec![
-                                format!("Remove the duplicate key '{}' or rename it", key),
+                                format!("Remove the duplicate key '{}' or rename it", excerpt(key)),
                                 "Enable implicit arrays to automatically convert duplicate keys to arrays".to_string(),
                                 "Use explicit array syntax: key = [value1, value2]".to_string()
                             ])
                             .with_help("UCL can automatically create arrays from duplicate keys if configured".to_string())
                     }
+                    ParseError::KeyValueInArray { key, position } => {
+                        ErrorContext::for_token(source.to_string(), *position, key)
+                            .with_suggestion(format!("Wrap each element in braces: [ {{ {key} = ... }}, {{ {key} = ... }} ]", key = excerpt(key)))
+                            .with_help("Array elements are values; an element with keys is an object and needs '{' and '}'".to_string())
+                    }
                     ParseError::NginxSyntaxError { message, position, suggestion } => {
                         ErrorContext::nginx_syntax_error(source.to_string(), *position, message, "nginx_error")
                             .with_priority_suggestion(suggestion.clone())
@@ -978,8 +2485,23 @@ impl UclError {
 }
 
 impl serde::de::Error for UclError {
+    /// Keeps room for the key path so the deserializer can add the key and line
     fn custom<T: fmt::Display>(msg: T) -> Self {
-        UclError::Serde(SerdeError::Custom(msg.to_string()))
+        UclError::Serde(SerdeError::Message {
+            message: msg.to_string(),
+            path: Vec::new(),
+            position: None,
+        })
+    }
+
+    /// Keeps the variant list so the deserializer can add the key and line
+    fn unknown_variant(variant: &str, expected: &'static [&'static str]) -> Self {
+        UclError::Serde(SerdeError::UnknownVariant {
+            variant: variant.to_string(),
+            expected: expected.iter().map(|name| name.to_string()).collect(),
+            path: Vec::new(),
+            position: None,
+        })
     }
 }
 
@@ -989,10 +2511,295 @@ impl serde::de::Error for SerdeError {
     }
 }
 
+/// Writes an error as a `{code, message, line, column, offset, hint, path}` record
+///
+/// Fields that do not apply to the error are `null`.
+#[cfg(feature = "serde-errors")]
+fn serialize_error<S: serde::Serializer>(
+    serializer: S,
+    code: &'static str,
+    message: String,
+    position: Option<Position>,
+    hint: Option<String>,
+    path: Option<String>,
+) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut record = serializer.serialize_struct("Error", 7)?;
+    record.serialize_field("code", code)?;
+    record.serialize_field("message", &message)?;
+    record.serialize_field("line", &position.map(|position| position.line))?;
+    record.serialize_field("column", &position.map(|position| position.column))?;
+    record.serialize_field("offset", &position.map(|position| position.offset))?;
+    record.serialize_field("hint", &hint)?;
+    record.serialize_field("path", &path)?;
+    record.end()
+}
+
+#[cfg(feature = "serde-errors")]
+impl serde::Serialize for LexError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.to_string();
+        serialize_error(
+            serializer,
+            self.code(),
+            message,
+            self.position(),
+            self.hint(),
+            None,
+        )
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl serde::Serialize for ParseError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.to_string();
+        serialize_error(
+            serializer,
+            self.code(),
+            message,
+            self.position(),
+            self.hint(),
+            None,
+        )
+    }
+}
+
+#[cfg(feature = "serde-errors")]
+impl serde::Serialize for SerdeError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.to_string();
+        let path = self.key_path();
+        serialize_error(
+            serializer,
+            self.code(),
+            message,
+            self.position(),
+            None,
+            path,
+        )
+    }
+}
+
+/// Serialized with the code, position and hint of the error it wraps
+///
+/// ```rust
+/// let err = ucl_lexer::from_str::<serde_json::Value>("a = 1\nb = [1,,2]").unwrap_err();
+/// let record = serde_json::to_value(&err).unwrap();
+/// assert_eq!(record["code"], "parse/missing-array-element");
+/// assert_eq!((record["line"].as_u64(), record["column"].as_u64()), (Some(2), Some(8)));
+/// ```
+#[cfg(feature = "serde-errors")]
+impl serde::Serialize for UclError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = self.to_string();
+        let path = self.key_path();
+        serialize_error(
+            serializer,
+            self.code(),
+            message,
+            self.position(),
+            self.hint(),
+            path,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const MULTI_BYTE: &str = "key = \"héllo\"\nname = \"日本\"\n";
+
+    #[test]
+    fn test_position_from_offset() {
+        assert_eq!(Position::from_offset(MULTI_BYTE, 0), Position::new());
+
+        // `l` right after the two-byte `é`
+        let pos = Position::from_offset(MULTI_BYTE, 10);
+        assert_eq!((pos.line, pos.column, pos.offset), (1, 10, 10));
+
+        // Start of `本` on the second line
+        let pos = Position::from_offset(MULTI_BYTE, 26);
+        assert_eq!((pos.line, pos.column, pos.offset), (2, 10, 26));
+
+        // Inside `本` resolves to its first byte
+        assert_eq!(Position::from_offset(MULTI_BYTE, 27), pos);
+
+        let end = Position::from_offset(MULTI_BYTE, 1000);
+        assert_eq!((end.line, end.column, end.offset), (3, 1, MULTI_BYTE.len()));
+    }
+
+    #[test]
+    fn test_position_ordering() {
+        let a = Position::from_offset(MULTI_BYTE, 3);
+        let b = Position::from_offset(MULTI_BYTE, 20);
+        assert!(a < b);
+        assert_eq!(a.max(b), b);
+
+        let mut positions = vec![b, Position::new(), a];
+        positions.sort();
+        assert_eq!(positions, vec![Position::new(), a, b]);
+    }
+
+    #[test]
+    fn test_span_byte_ranges() {
+        let span = Span::from_byte_range(MULTI_BYTE, 6..14);
+        assert_eq!(span.slice(MULTI_BYTE), "\"héllo\"");
+        assert_eq!((span.start.line, span.start.column), (1, 7));
+        assert_eq!((span.end.line, span.end.column), (1, 14));
+        assert_eq!(Range::from(span.clone()), 6..14);
+        assert_eq!(span.line_range(), 1..=1);
+
+        let span = Span::from_byte_range(MULTI_BYTE, 22..29);
+        assert_eq!(span.slice(MULTI_BYTE), "\"日本");
+        assert_eq!(span.line_range(), 2..=2);
+
+        // Ranges are clamped and snapped to character boundaries
+        let span = Span::from_byte_range(MULTI_BYTE, 23..24);
+        assert_eq!(span.slice(MULTI_BYTE), "");
+        let span = Span::from_byte_range(MULTI_BYTE, 16..1000);
+        assert_eq!(span.slice(MULTI_BYTE), &MULTI_BYTE[16..]);
+        assert_eq!(span.line_range(), 2..=3);
+    }
+
+    #[test]
+    fn test_span_slice_off_boundary() {
+        let start = Position::from_offset(MULTI_BYTE, 6);
+        let mut end = start;
+        end.offset = 9; // inside `é`
+        assert_eq!(Span::new(start, end).slice(MULTI_BYTE), "");
+
+        end.offset = 1000;
+        assert_eq!(Span::new(start, end).slice(MULTI_BYTE), "");
+    }
+
+    // A tab, an emoji and `e` with a combining acute accent before `bad`
+    const MIXED_LINE: &str = "\tkey = \"😀e\u{301}\" bad";
+
+    #[test]
+    fn test_column_units() {
+        let position = Position::from_offset(MIXED_LINE, MIXED_LINE.find("bad").unwrap());
+        assert_eq!(position.column, 14);
+        assert_eq!(position.column_in(MIXED_LINE, ColumnUnit::Chars), 14);
+        assert_eq!(position.column_in(MIXED_LINE, ColumnUnit::Bytes), 18);
+        assert_eq!(position.utf16_column(MIXED_LINE), 15);
+
+        // Columns past the end of the line are clamped to it
+        let mut past_end = position;
+        past_end.column = 100;
+        assert_eq!(past_end.utf16_column("ab"), 3);
+        assert_eq!(Position::new().utf16_column(""), 1);
+    }
+
+    #[test]
+    fn test_tab_expansion() {
+        assert_eq!(expand_tabs("ab\tc\td", 4), "ab  c   d");
+        assert_eq!(expand_tabs("\tx", 8), "        x");
+        assert_eq!(display_width("ab\tc", 0, 4), 5);
+        assert_eq!(display_width("\t", 6, 4), 2);
+        assert_eq!(display_width("😀e\u{301}日", 0, 4), 5);
+    }
+
+    #[test]
+    fn test_caret_lines_up_with_expanded_line() {
+        let position = Position::from_offset(MIXED_LINE, MIXED_LINE.find("bad").unwrap());
+        let context = ErrorContext::for_token(MIXED_LINE.to_string(), position, "bad");
+        let snippet = context.source_snippet();
+        let lines: Vec<&str> = snippet.lines().collect();
+        assert_eq!(lines[0], "1 |     key = \"😀e\u{301}\" bad");
+        // The tab takes 4 columns, the emoji 2 and the accent none
+        assert_eq!(
+            lines[1],
+            format!("{}^^^  <-- Error here", " ".repeat(4 + 16))
+        );
+
+        let snippet = context.clone().with_tab_width(8).source_snippet();
+        assert!(snippet.starts_with("1 |         key"), "{snippet}");
+        assert!(
+            snippet.contains(&format!("\n{}^^^", " ".repeat(4 + 20))),
+            "{snippet}"
+        );
+
+        assert!(
+            context
+                .format_error("bad")
+                .starts_with("Error at 1:14: bad")
+        );
+        let context = context.with_column_unit(ColumnUnit::Utf16);
+        assert!(
+            context
+                .format_error("bad")
+                .starts_with("Error at 1:15: bad")
+        );
+        assert!(
+            context
+                .format_compact("bad")
+                .ends_with("at 1:15 (near 'bad')")
+        );
+    }
+
+    #[test]
+    fn test_long_lines_are_cut_around_the_error() {
+        let source = format!(
+            "a = 1\nb = \"{}\\q{}\"\nc = 2",
+            "x".repeat(40),
+            "y".repeat(40)
+        );
+        let position = Position::from_offset(&source, source.find("\\q").unwrap());
+        let context = ErrorContext::for_token(source.clone(), position, "\\q").with_max_width(22);
+        let snippet = context.source_snippet();
+        let lines: Vec<&str> = snippet.lines().collect();
+        // Every line shows the same columns, so the context stays aligned
+        assert_eq!(lines[0], "1 | …");
+        assert_eq!(
+            lines[1],
+            format!("2 | …{}\\q{}…", "x".repeat(9), "y".repeat(9))
+        );
+        assert_eq!(
+            lines[2],
+            format!("{}^^  <-- Error here", " ".repeat(4 + 10))
+        );
+        assert_eq!(lines[3], "3 | …");
+
+        // Near the start of a line, only the end is cut
+        let position = Position::from_offset(&source, source.find('"').unwrap());
+        let snippet = ErrorContext::for_token(source.clone(), position, "\"")
+            .with_max_width(22)
+            .source_snippet();
+        assert!(
+            snippet.contains("\n2 | b = \"xxxxxxxxxxxxxxxx…\n"),
+            "{snippet}"
+        );
+        assert!(snippet.contains(&format!("\n{}^  <-- Error here", " ".repeat(4 + 4))));
+
+        // Lines within the width are drawn whole
+        let snippet = ErrorContext::for_token(source.clone(), position, "\"").source_snippet();
+        assert!(snippet.contains(&format!(
+            "\n2 | b = \"{}\\q{}\"\n",
+            "x".repeat(40),
+            "y".repeat(40)
+        )));
+    }
+
+    #[test]
+    fn test_span_contains_and_merge() {
+        let key = Span::from_byte_range(MULTI_BYTE, 0..3);
+        let value = Span::from_byte_range(MULTI_BYTE, 6..14);
+
+        assert!(key.contains_offset(0));
+        assert!(key.contains_offset(3));
+        assert!(!key.contains_offset(4));
+        assert!(value.contains(Position::from_offset(MULTI_BYTE, 8)));
+        assert!(!value.contains(Position::from_offset(MULTI_BYTE, 20)));
+
+        let entry = key.merge(value.clone());
+        assert_eq!(entry.byte_range(), 0..14);
+        assert_eq!(entry.slice(MULTI_BYTE), "key = \"héllo\"");
+        assert_eq!(value.merge(Span::from_byte_range(MULTI_BYTE, 0..3)), entry);
+    }
+
     #[test]
     fn test_position_new() {
         let pos = Position::new();
@@ -44,8 +44,10 @@ mod tests {
         use crate::lexer::LexerConfig;
 
         // Test valid UTF-8 in strict mode
-        let mut config = LexerConfig::default();
-        config.strict_unicode = true;
+        let config = LexerConfig {
+            strict_unicode: true,
+            ..Default::default()
+        };
 
         let input = "\"héllo wörld 🌍\"";
         let mut lexer = UclLexer::with_config(input, config.clone());
@@ -84,8 +86,10 @@ mod tests {
         use crate::lexer::LexerConfig;
 
         // Test string length limit
-        let mut config = LexerConfig::default();
-        config.max_string_length = 10;
+        let mut config = LexerConfig {
+            max_string_length: 10,
+            ..Default::default()
+        };
 
         let input = "\"this string is too long\"";
         let mut lexer = UclLexer::with_config(input, config.clone());
@@ -250,7 +254,7 @@ mod tests {
         let control_chars = vec![
             '\x00', '\x01', '\x02', '\x03', '\x04', '\x05', '\x06', '\x07', '\x08', '\x0B', '\x0C',
             '\x0E', '\x0F', '\x10', '\x11', '\x12', '\x13', '\x14', '\x15', '\x16', '\x17', '\x18',
-            '\x19', '\x1A', '\x1B', '\x1C', '\x1D', '\x1E', '\x1F', '\x7F',
+            '\x19', '\x1A', '\x1B', '\x1C', '\x1D', '\x1E', '\x1F',
         ];
 
         for ch in control_chars {
@@ -275,6 +279,13 @@ mod tests {
         let mut lexer = UclLexer::new(input);
         let result = lexer.next_token();
         assert!(result.is_ok(), "Tab character should be allowed in strings");
+
+        // DEL is allowed as well, as JSON only requires escaping below U+0020
+        let mut lexer = UclLexer::new("\"hello\x7Fworld\"");
+        assert!(
+            lexer.next_token().is_ok(),
+            "DEL should be allowed in strings"
+        );
     }
 
     #[test]
@@ -282,8 +293,10 @@ mod tests {
         use crate::lexer::LexerConfig;
 
         // Test various Unicode edge cases
-        let mut config = LexerConfig::default();
-        config.strict_unicode = true;
+        let config = LexerConfig {
+            strict_unicode: true,
+            ..Default::default()
+        };
 
         // Test valid Unicode ranges
         let valid_cases = vec![
@@ -316,8 +329,10 @@ mod tests {
     fn test_nesting_depth_limits() {
         use crate::lexer::LexerConfig;
 
-        let mut config = LexerConfig::default();
-        config.max_nesting_depth = 3;
+        let config = LexerConfig {
+            max_nesting_depth: 3,
+            ..Default::default()
+        };
 
         // Test nested objects within limit
         let input = "{ a: { b: { c: 1 } } }";
@@ -357,8 +372,10 @@ mod tests {
         use crate::lexer::LexerConfig;
 
         // Test comment length protection
-        let mut config = LexerConfig::default();
-        config.max_comment_length = 100;
+        let mut config = LexerConfig {
+            max_comment_length: 100,
+            ..Default::default()
+        };
 
         let long_comment = format!("# {}", "x".repeat(200));
         let mut lexer = UclLexer::with_config(&long_comment, config.clone());
@@ -372,4 +389,59 @@ mod tests {
         let result = lexer.next_token();
         assert!(result.is_err(), "Expected error for long string");
     }
+
+    /// Parses a malformed document and returns its expected-token set
+    fn expected_tokens(input: &str) -> (&'static str, Vec<&'static str>) {
+        match UclParser::new(input).parse_document() {
+            Err(ParseError::UnexpectedToken {
+                found, expected, ..
+            }) => (found, expected),
+            other => panic!("expected an unexpected token error for {input:?}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_expected_token_sets() {
+        // After a key
+        assert_eq!(
+            expected_tokens("server }"),
+            ("'}'", vec!["'='", "':'", "'{'", "value"])
+        );
+        assert_eq!(
+            expected_tokens(
+                "a = 1
+name"
+            ),
+            ("end of file", vec!["'='", "':'", "'{'", "value"])
+        );
+        // After a separator
+        assert_eq!(expected_tokens("a = ;"), ("';'", vec!["value"]));
+        // Inside an array
+        assert_eq!(expected_tokens("a = [1, }"), ("'}'", vec!["value", "']'"]));
+        assert_eq!(
+            expected_tokens("a = [1 2]"),
+            ("integer", vec!["','", "';'", "']'"])
+        );
+        // Inside an object
+        assert_eq!(
+            expected_tokens("a { b = 1"),
+            ("end of file", vec!["','", "';'", "key", "'}'"])
+        );
+    }
+
+    #[test]
+    fn test_expected_token_display() {
+        let error = UclParser::new("server }").parse_document().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unexpected token at 1:9: expected one of '=', ':', '{', value; found '}'"
+        );
+
+        let error = UclParser::new("a = 1 }").parse_document().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unexpected '}' at 1:7 after the top-level object; a document is a single value \
+             or a list of keys without enclosing braces"
+        );
+    }
 }
@@ -0,0 +1,288 @@
+//! Splitting strings into literal text and variable references
+//!
+//! `scan_variables` walks a string once and returns its `$VAR`, `${VAR}` and
+//! `${VAR:-fallback}` references together with the text between them, as
+//! slices of the input. The parser expands variables from these segments and
+//! joins the result with a single allocation; tools can use them to find
+//! variable spans without expanding anything.
+
+use crate::error::{ParseError, Position};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Piece of a string returned by `scan_variables`
+///
+/// ```rust
+/// use ucl_lexer::expand::{Segment, scan_variables};
+///
+/// let segments = scan_variables("http://${HOST:-localhost}:$PORT/");
+/// assert_eq!(segments[0], Segment::Literal("http://"));
+/// assert_eq!(
+///     segments[1],
+///     Segment::Variable { name: "HOST", fallback: Some("localhost"), span: 7..25 }
+/// );
+/// assert_eq!(segments[2], Segment::Literal(":"));
+/// assert_eq!(segments[3], Segment::Variable { name: "PORT", fallback: None, span: 26..31 });
+/// assert_eq!(segments[4], Segment::Literal("/"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment<'a> {
+    /// Text kept as written; the `$$` escape yields a literal of one `$`
+    Literal(&'a str),
+    /// Reference to a variable
+    Variable {
+        /// Variable name
+        name: &'a str,
+        /// Unexpanded text after `:-` in `${VAR:-fallback}`
+        fallback: Option<&'a str>,
+        /// Byte range of the whole reference in the scanned string
+        span: Range<usize>,
+    },
+    /// `${` that does not start a valid reference
+    Malformed {
+        /// Reason the reference is invalid
+        message: String,
+        /// Byte range from the `$` to where scanning resumed
+        span: Range<usize>,
+    },
+}
+
+/// Splits a string into literals and variable references in one pass
+///
+/// A `$` that is not followed by `$`, `{`, a letter or `_` stays part of the
+/// surrounding literal. Fallbacks are returned unexpanded, so scan them again
+/// to find the references they contain.
+pub fn scan_variables(input: &str) -> Vec<Segment<'_>> {
+    let bytes = input.as_bytes();
+    let mut segments = Vec::new();
+    let mut literal_start = 0;
+    let mut pos = 0;
+
+    while let Some(found) = input[pos..].find('$') {
+        let dollar = pos + found;
+        let (segment, end) = match bytes.get(dollar + 1) {
+            Some(b'$') => (Segment::Literal(&input[dollar..dollar + 1]), dollar + 2),
+            Some(b'{') => scan_braced(input, dollar),
+            Some(&byte) if byte.is_ascii_alphabetic() || byte == b'_' => {
+                let end = name_end(bytes, dollar + 1);
+                let segment = Segment::Variable {
+                    name: &input[dollar + 1..end],
+                    fallback: None,
+                    span: dollar..end,
+                };
+                (segment, end)
+            }
+            _ => {
+                pos = dollar + 1;
+                continue;
+            }
+        };
+
+        if literal_start < dollar {
+            segments.push(Segment::Literal(&input[literal_start..dollar]));
+        }
+        segments.push(segment);
+        literal_start = end;
+        pos = end;
+    }
+
+    if literal_start < input.len() {
+        segments.push(Segment::Literal(&input[literal_start..]));
+    }
+    segments
+}
+
+/// End of the variable name starting at `start`
+fn name_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|&byte| !(byte.is_ascii_alphanumeric() || byte == b'_'))
+        .map_or(bytes.len(), |length| start + length)
+}
+
+/// Scans the `${...}` reference at `dollar`, returning it and where it ends
+fn scan_braced(input: &str, dollar: usize) -> (Segment<'_>, usize) {
+    let bytes = input.as_bytes();
+    let name_start = dollar + 2;
+    let mut pos = name_end(bytes, name_start);
+    let name = &input[name_start..pos];
+    let malformed = |message: String, end: usize| {
+        let segment = Segment::Malformed {
+            message,
+            span: dollar..end,
+        };
+        (segment, end)
+    };
+    let unclosed = || "Unclosed variable expansion: missing '}'".to_string();
+
+    let fallback = match bytes.get(pos) {
+        Some(b'}') => None,
+        Some(b':') if bytes.get(pos + 1) == Some(&b'-') => {
+            // Braces opened by nested expansions inside the fallback
+            let fallback_start = pos + 2;
+            let mut depth = 0usize;
+            let close = bytes[fallback_start..].iter().position(|&byte| match byte {
+                b'{' => {
+                    depth += 1;
+                    false
+                }
+                b'}' if depth == 0 => true,
+                b'}' => {
+                    depth -= 1;
+                    false
+                }
+                _ => false,
+            });
+            let Some(length) = close else {
+                return malformed(unclosed(), input.len());
+            };
+            pos = fallback_start + length;
+            Some(&input[fallback_start..pos])
+        }
+        Some(b':') => return malformed("Invalid variable fallback syntax".to_string(), pos + 1),
+        Some(_) => {
+            let ch = input[pos..].chars().next().unwrap_or_default();
+            return malformed(
+                format!("Invalid character '{}' in variable name", ch),
+                pos + ch.len_utf8(),
+            );
+        }
+        None => return malformed(unclosed(), input.len()),
+    };
+
+    if name.is_empty() {
+        return malformed("Empty variable name in ${} expansion".to_string(), pos + 1);
+    }
+    let segment = Segment::Variable {
+        name,
+        fallback,
+        span: dollar..pos + 1,
+    };
+    (segment, pos + 1)
+}
+
+/// Expanded pieces of a string, joined once when complete
+pub(crate) struct Expansion<'a> {
+    pieces: Vec<Cow<'a, str>>,
+    length: usize,
+    limit: usize,
+}
+
+impl<'a> Expansion<'a> {
+    /// Starts an expansion whose result may be at most `limit` bytes
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            pieces: Vec::new(),
+            length: 0,
+            limit,
+        }
+    }
+
+    /// Appends a piece, failing once the result would exceed the limit
+    pub(crate) fn push(
+        &mut self,
+        piece: impl Into<Cow<'a, str>>,
+        position: Position,
+    ) -> Result<(), ParseError> {
+        let piece = piece.into();
+        self.length += piece.len();
+        if self.length > self.limit {
+            return Err(ParseError::ExpandedStringTooLong {
+                length: self.length,
+                limit: self.limit,
+                position,
+            });
+        }
+        self.pieces.push(piece);
+        Ok(())
+    }
+
+    /// Joins the pieces into a string of exactly the expanded length
+    pub(crate) fn finish(self) -> String {
+        let mut result = String::with_capacity(self.length);
+        for piece in &self.pieces {
+            result.push_str(piece);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable<'a>(name: &'a str, fallback: Option<&'a str>, span: Range<usize>) -> Segment<'a> {
+        Segment::Variable {
+            name,
+            fallback,
+            span,
+        }
+    }
+
+    #[test]
+    fn test_scan_literals_and_escapes() {
+        assert_eq!(scan_variables(""), []);
+        assert_eq!(scan_variables("plain"), [Segment::Literal("plain")]);
+        assert_eq!(scan_variables("$1 $ $@"), [Segment::Literal("$1 $ $@")]);
+        assert_eq!(
+            scan_variables("$$HOME$"),
+            [Segment::Literal("$"), Segment::Literal("HOME$")]
+        );
+    }
+
+    #[test]
+    fn test_scan_references() {
+        assert_eq!(
+            scan_variables("$a_1/${B}é$c"),
+            [
+                variable("a_1", None, 0..4),
+                Segment::Literal("/"),
+                variable("B", None, 5..9),
+                Segment::Literal("é"),
+                variable("c", None, 11..13),
+            ]
+        );
+        assert_eq!(
+            scan_variables("${A:-x{y}${B:-}}!"),
+            [
+                variable("A", Some("x{y}${B:-}"), 0..16),
+                Segment::Literal("!")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_malformed_references() {
+        let message = |input| match &scan_variables(input)[..] {
+            [Segment::Malformed { message, span }, rest @ ..] => {
+                (message.clone(), span.clone(), rest.len())
+            }
+            other => panic!("{input}: {other:?}"),
+        };
+
+        assert_eq!(
+            message("${}"),
+            ("Empty variable name in ${} expansion".to_string(), 0..3, 0)
+        );
+        assert_eq!(
+            message("${a-b} $c"),
+            (
+                "Invalid character '-' in variable name".to_string(),
+                0..4,
+                2
+            )
+        );
+        assert_eq!(
+            message("${a:b}"),
+            ("Invalid variable fallback syntax".to_string(), 0..4, 1)
+        );
+        assert_eq!(
+            message("${a:-{}"),
+            (
+                "Unclosed variable expansion: missing '}'".to_string(),
+                0..7,
+                0
+            )
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! `.include` directives
+//!
+//! With a resolver set through `UclParser::with_include_resolver`, a
+//! top-level `.include "path"` entry reads another file and merges it over
+//! the document, as a later layer passed to `TrackedValue::merge_from` would
+//! be: at equal priority the included values win, whatever the position of
+//! the directive. Parameters in parentheses change how the path is read:
+//!
+//! - `glob=true` includes every file matching the pattern, in file name order;
+//! - `try=true` skips a file that does not exist instead of failing;
+//! - `priority=N` gives the included values the merge priority `N`, as a
+//!   `.priority N` directive in the included file would.
+//!
+//! Each included file, and each optional file that was skipped, is recorded
+//! as a diagnostic at the directive. Without a resolver, `.include` entries
+//! are read as ordinary keys.
+//!
+//! A document and the files it includes, directly or through other includes,
+//! may read at most `MAX_INCLUDED_FILES` files holding together at most
+//! `ParserConfig::max_document_size` bytes, so that files including each
+//! other several times cannot fan out within the nesting limit.
+//!
+//! ```rust
+//! use std::io;
+//! use ucl_lexer::UclParser;
+//! use ucl_lexer::include::IncludeResolver;
+//!
+//! struct Files;
+//!
+//! impl IncludeResolver for Files {
+//!     fn read(&self, path: &str) -> io::Result<String> {
+//!         match path {
+//!             "conf.d/10-port.ucl" => Ok("port = 8080".to_string()),
+//!             "conf.d/20-host.ucl" => Ok("host = example.org".to_string()),
+//!             _ => Err(io::ErrorKind::NotFound.into()),
+//!         }
+//!     }
+//!
+//!     fn glob(&self, _pattern: &str) -> io::Result<Vec<String>> {
+//!         Ok(vec!["conf.d/20-host.ucl".into(), "conf.d/10-port.ucl".into()])
+//!     }
+//! }
+//!
+//! let input = "port = 80\n.include(glob=true) \"conf.d/*.ucl\"\n.include(try=true) \"local.ucl\"";
+//! let mut parser = UclParser::new(input).with_include_resolver(Files);
+//! let value = parser.parse_document()?;
+//! assert_eq!(value["port"], 8080);
+//! assert_eq!(value["host"], "example.org");
+//! assert_eq!(parser.diagnostics().len(), 3);
+//! # Ok::<(), ucl_lexer::ParseError>(())
+//! ```
+
+use crate::error::Position;
+use crate::parser::UclValue;
+use crate::scalar::parse_scalar;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Deepest nesting of included files, which stops a file that includes itself
+pub(crate) const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Most files a document may include, counting nested includes
+pub(crate) const MAX_INCLUDED_FILES: usize = 1024;
+
+/// Files and bytes read for the includes of a document so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct IncludeTotals {
+    /// Number of files read
+    pub files: usize,
+    /// Total size of the files read
+    pub bytes: usize,
+}
+
+/// Reads the files named by `.include` directives
+///
+/// Paths are passed as written in the directive. Implement this trait to
+/// read includes from somewhere other than the file system, such as an
+/// archive or a map in tests.
+pub trait IncludeResolver: Send + Sync {
+    /// Returns the contents of the file at `path`
+    ///
+    /// A missing file is an error of kind `io::ErrorKind::NotFound`, which
+    /// `.include(try=true)` skips.
+    fn read(&self, path: &str) -> io::Result<String>;
+
+    /// Returns the paths of the files matching a glob `pattern`
+    ///
+    /// The paths are read with `read` and may be returned in any order; the
+    /// parser sorts them by file name.
+    fn glob(&self, pattern: &str) -> io::Result<Vec<String>>;
+}
+
+/// Reads included files from a directory
+///
+/// Relative paths, including those of nested includes, are relative to the
+/// base directory. Glob patterns may use `*` and `?` in their file name, as
+/// in `conf.d/*.ucl`, but not in their directories.
+///
+/// Only files inside the base directory are read: an absolute path, or one
+/// that leaves the directory through `..` or a symbolic link, fails with
+/// `io::ErrorKind::PermissionDenied`.
+#[derive(Debug, Clone)]
+pub struct FsIncludeResolver {
+    base: PathBuf,
+}
+
+impl FsIncludeResolver {
+    /// Creates a resolver for paths relative to `base`
+    pub fn new(base: impl Into<PathBuf>) -> Self {
+        Self { base: base.into() }
+    }
+
+    /// Returns the canonical form of `path` if it lies inside the base directory
+    fn resolve(&self, path: &str) -> io::Result<PathBuf> {
+        let outside = || {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("'{}' is outside the include directory", path),
+            )
+        };
+        if Path::new(path).has_root() {
+            return Err(outside());
+        }
+        let base = self.base.canonicalize()?;
+        let resolved = base.join(path).canonicalize()?;
+        if !resolved.starts_with(&base) {
+            return Err(outside());
+        }
+        Ok(resolved)
+    }
+}
+
+impl IncludeResolver for FsIncludeResolver {
+    fn read(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(self.resolve(path)?)
+    }
+
+    fn glob(&self, pattern: &str) -> io::Result<Vec<String>> {
+        let (directory, name_pattern) = match pattern.rsplit_once('/') {
+            Some((directory, name)) => (directory, name),
+            None => ("", pattern),
+        };
+        if directory.contains(['*', '?']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "wildcards are only supported in the file name",
+            ));
+        }
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(self.resolve(directory)?)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                continue;
+            };
+            if entry.file_type()?.is_file() && wildcard_match(name_pattern, name) {
+                paths.push(match directory {
+                    "" => name.to_string(),
+                    _ => format!("{}/{}", directory, name),
+                });
+            }
+        }
+        Ok(paths)
+    }
+}
+
+/// Returns whether `name` matches `pattern`, where `*` matches any run of
+/// characters and `?` any single character
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has taken
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, taken)) => {
+                    p = star + 1;
+                    n = taken + 1;
+                    backtrack = Some((star, taken + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
+
+/// Sorts included paths by file name, then by the whole path
+pub(crate) fn sort_by_file_name(paths: &mut [String]) {
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or_default().to_string();
+    paths.sort_by(|a, b| file_name(a).cmp(&file_name(b)).then_with(|| a.cmp(b)));
+}
+
+/// Parameters of an include directive, from `.include(glob=true, try=true, priority=2)`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct IncludeParams {
+    /// Read the path as a glob pattern
+    pub glob: bool,
+    /// Skip missing files
+    pub optional: bool,
+    /// Merge priority of the included values
+    pub priority: Option<u8>,
+}
+
+impl IncludeParams {
+    /// Reads a comma-separated `name=value` list, with values read as scalars
+    pub(crate) fn parse(list: &str) -> Result<Self, String> {
+        let mut params = Self::default();
+        for param in list
+            .split(',')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+        {
+            let Some((name, value)) = param.split_once('=') else {
+                return Err(format!("expected `name=value` but found `{}`", param));
+            };
+            let value = parse_scalar(value).map_err(|error| error.to_string())?;
+            match (name.trim(), value) {
+                ("glob", UclValue::Boolean(glob)) => params.glob = glob,
+                ("try", UclValue::Boolean(optional)) => params.optional = optional,
+                ("priority", UclValue::Integer(priority)) => {
+                    params.priority = Some(u8::try_from(priority).map_err(|_| {
+                        "priority must be an integer between 0 and 255".to_string()
+                    })?);
+                }
+                ("glob" | "try", _) => return Err(format!("`{}` must be a boolean", name)),
+                ("priority", _) => {
+                    return Err("priority must be an integer between 0 and 255".to_string());
+                }
+                (name, _) => {
+                    return Err(format!(
+                        "unknown parameter `{}`, expected glob, try or priority",
+                        name
+                    ));
+                }
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// An include directive read from a document, resolved once the document is parsed
+#[derive(Debug, Clone)]
+pub(crate) struct PendingInclude {
+    /// Path or glob pattern as written
+    pub path: String,
+    /// Parameters from the parentheses
+    pub params: IncludeParams,
+    /// Position of the directive
+    pub position: Position,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.ucl", "10-base.ucl"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("?0-*.ucl", "10-base.ucl"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("*.ucl", "10-base.conf"));
+        assert!(!wildcard_match("?.ucl", "10.ucl"));
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!(
+            IncludeParams::parse("glob=true, try=yes, priority=2"),
+            Ok(IncludeParams {
+                glob: true,
+                optional: true,
+                priority: Some(2),
+            })
+        );
+        assert_eq!(IncludeParams::parse(""), Ok(IncludeParams::default()));
+        assert!(IncludeParams::parse("priority=300").is_err());
+        assert!(IncludeParams::parse("glob=1").is_err());
+        assert!(IncludeParams::parse("recursive=true").is_err());
+        assert!(IncludeParams::parse("glob").is_err());
+    }
+}
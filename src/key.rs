@@ -0,0 +1,431 @@
+//! Object keys stored inline when short
+//!
+//! Most configuration keys are short names such as `port` or `worker_count`.
+//! `UclKey` keeps keys of up to `INLINE_CAPACITY` bytes inside the key itself
+//! and only moves longer ones to the heap, so building a `UclObject` does not
+//! allocate once per key. A key dereferences to `str`, hashes and compares
+//! like one and serializes as a string, so lookups such as `object.get("port")`
+//! and iteration work with `&str`.
+
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::borrow::{Borrow, Cow};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// Longest key, in bytes, stored without a heap allocation
+///
+/// `UclKey` is the size of a `String`, with one byte taken by the length.
+pub const INLINE_CAPACITY: usize = 22;
+
+/// Key of a `UclObject`
+///
+/// ```rust
+/// use ucl_lexer::{UclKey, UclParser};
+///
+/// let value = UclParser::new("worker_count = 4").parse_document().unwrap();
+/// let object = value.as_object().unwrap();
+/// assert_eq!(object.get("worker_count").and_then(|v| v.as_integer()), Some(4));
+///
+/// let key: &UclKey = object.keys().next().unwrap();
+/// assert_eq!(key, "worker_count");
+/// assert!(key.is_inline());
+/// ```
+#[derive(Clone)]
+pub struct UclKey(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Inline {
+        len: u8,
+        bytes: [u8; INLINE_CAPACITY],
+    },
+    Heap(Box<str>),
+}
+
+impl UclKey {
+    /// Creates an empty key
+    pub const fn new() -> Self {
+        Self(Repr::Inline {
+            len: 0,
+            bytes: [0; INLINE_CAPACITY],
+        })
+    }
+
+    /// Returns the key as a string slice
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            // SAFETY: the first `len` bytes are only ever written by `inline`,
+            // which copies them from a `str`
+            Repr::Inline { len, bytes } => unsafe {
+                std::str::from_utf8_unchecked(&bytes[..*len as usize])
+            },
+            Repr::Heap(text) => text,
+        }
+    }
+
+    /// Returns true if the key is stored without a heap allocation
+    pub fn is_inline(&self) -> bool {
+        matches!(self.0, Repr::Inline { .. })
+    }
+
+    /// Returns the key of the array element at `index`, written in decimal
+    ///
+    /// The digits of any `usize` fit inline, so this never allocates.
+    pub(crate) fn index(mut index: usize) -> Self {
+        let mut bytes = [0; INLINE_CAPACITY];
+        let mut start = INLINE_CAPACITY;
+        loop {
+            start -= 1;
+            bytes[start] = b'0' + (index % 10) as u8;
+            index /= 10;
+            if index == 0 {
+                break;
+            }
+        }
+        let len = INLINE_CAPACITY - start;
+        bytes.copy_within(start.., 0);
+        bytes[len..].fill(0);
+        Self(Repr::Inline {
+            len: len as u8,
+            bytes,
+        })
+    }
+
+    /// Stores `text` inline, or returns `None` if it does not fit
+    fn inline(text: &str) -> Option<Self> {
+        if text.len() > INLINE_CAPACITY {
+            return None;
+        }
+        let mut bytes = [0; INLINE_CAPACITY];
+        bytes[..text.len()].copy_from_slice(text.as_bytes());
+        Some(Self(Repr::Inline {
+            len: text.len() as u8,
+            bytes,
+        }))
+    }
+}
+
+impl Default for UclKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for UclKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for UclKey {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for UclKey {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for UclKey {
+    fn from(text: &str) -> Self {
+        Self::inline(text).unwrap_or_else(|| Self(Repr::Heap(text.into())))
+    }
+}
+
+impl From<&String> for UclKey {
+    fn from(text: &String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl From<String> for UclKey {
+    fn from(text: String) -> Self {
+        Self::inline(&text).unwrap_or_else(|| Self(Repr::Heap(text.into_boxed_str())))
+    }
+}
+
+impl From<Box<str>> for UclKey {
+    fn from(text: Box<str>) -> Self {
+        Self::inline(&text).unwrap_or(Self(Repr::Heap(text)))
+    }
+}
+
+impl From<Cow<'_, str>> for UclKey {
+    fn from(text: Cow<'_, str>) -> Self {
+        match text {
+            Cow::Borrowed(text) => Self::from(text),
+            Cow::Owned(text) => Self::from(text),
+        }
+    }
+}
+
+impl From<&UclKey> for UclKey {
+    fn from(key: &UclKey) -> Self {
+        key.clone()
+    }
+}
+
+impl From<UclKey> for String {
+    fn from(key: UclKey) -> Self {
+        match key.0 {
+            Repr::Heap(text) => text.into_string(),
+            Repr::Inline { .. } => key.as_str().to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for UclKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for UclKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+impl PartialEq for UclKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for UclKey {}
+
+impl PartialOrd for UclKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for UclKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+/// Hashes like `str`, as `Borrow<str>` requires
+impl Hash for UclKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+macro_rules! impl_str_eq {
+    ($($other:ty),*) => {$(
+        impl PartialEq<$other> for UclKey {
+            fn eq(&self, other: &$other) -> bool {
+                self.as_str() == AsRef::<str>::as_ref(other)
+            }
+        }
+
+        impl PartialEq<UclKey> for $other {
+            fn eq(&self, other: &UclKey) -> bool {
+                AsRef::<str>::as_ref(self) == other.as_str()
+            }
+        }
+    )*};
+}
+
+impl_str_eq!(str, &str, String);
+
+impl Serialize for UclKey {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for UclKey {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyVisitor;
+
+        impl Visitor<'_> for KeyVisitor {
+            type Value = UclKey;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<UclKey, E> {
+                Ok(UclKey::from(text))
+            }
+
+            fn visit_string<E: de::Error>(self, text: String) -> Result<UclKey, E> {
+                Ok(UclKey::from(text))
+            }
+        }
+
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+/// Canonical compositions of a letter and a combining mark
+///
+/// Each entry is a mark, the letters it follows and the precomposed letters
+/// they make, in the same order. This covers the letters of Latin-1 and
+/// Latin Extended-A.
+const COMPOSITIONS: &[(char, &str, &str)] = &[
+    // combining grave accent
+    ('\u{300}', "AEIOUaeiou", "ÀÈÌÒÙàèìòù"),
+    // combining acute accent
+    (
+        '\u{301}',
+        "ACEILNORSUYZaceilnorsuyz",
+        "ÁĆÉÍĹŃÓŔŚÚÝŹáćéíĺńóŕśúýź",
+    ),
+    // combining circumflex accent
+    (
+        '\u{302}',
+        "ACEGHIJOSUWYaceghijosuwy",
+        "ÂĈÊĜĤÎĴÔŜÛŴŶâĉêĝĥîĵôŝûŵŷ",
+    ),
+    // combining tilde
+    ('\u{303}', "AINOUainou", "ÃĨÑÕŨãĩñõũ"),
+    // combining macron
+    ('\u{304}', "AEIOUaeiou", "ĀĒĪŌŪāēīōū"),
+    // combining breve
+    ('\u{306}', "AEGIOUaegiou", "ĂĔĞĬŎŬăĕğĭŏŭ"),
+    // combining dot above
+    ('\u{307}', "CEGIZcegz", "ĊĖĠİŻċėġż"),
+    // combining diaeresis
+    ('\u{308}', "AEIOUYaeiouy", "ÄËÏÖÜŸäëïöüÿ"),
+    // combining ring above
+    ('\u{30A}', "AUau", "ÅŮåů"),
+    // combining double acute accent
+    ('\u{30B}', "OUou", "ŐŰőű"),
+    // combining caron
+    ('\u{30C}', "CDELNRSTZcdelnrstz", "ČĎĚĽŇŘŠŤŽčďěľňřšťž"),
+    // combining cedilla
+    ('\u{327}', "CGKLNRSTcgklnrst", "ÇĢĶĻŅŖŞŢçģķļņŗşţ"),
+    // combining ogonek
+    ('\u{328}', "AEIUaeiu", "ĄĘĮŲąęįų"),
+];
+
+/// Characters whose normalization form C is another single character
+const SINGLETONS: &[(char, char)] = &[
+    ('\u{340}', '\u{300}'),
+    ('\u{341}', '\u{301}'),
+    ('\u{2126}', '\u{3A9}'),
+    ('\u{212A}', 'K'),
+    ('\u{212B}', '\u{C5}'),
+];
+
+/// Returns the precomposed letter for `base` followed by `mark`, if there is one
+fn compose(base: char, mark: char) -> Option<char> {
+    let (_, bases, composed) = COMPOSITIONS.iter().find(|(m, _, _)| *m == mark)?;
+    let index = bases.chars().position(|c| c == base)?;
+    composed.chars().nth(index)
+}
+
+/// Returns `key` in Unicode normalization form C, or `None` if it already is
+///
+/// Only the compositions in `COMPOSITIONS` and `SINGLETONS` are applied, so
+/// other decomposed text is kept as written.
+pub(crate) fn normalize_nfc(key: &str) -> Option<String> {
+    if key.is_ascii() {
+        return None;
+    }
+    let mut normalized = String::with_capacity(key.len());
+    let mut last: Option<char> = None;
+    for c in key.chars() {
+        let c = SINGLETONS
+            .iter()
+            .find(|(from, _)| *from == c)
+            .map_or(c, |(_, to)| *to);
+        match last.and_then(|base| compose(base, c)) {
+            Some(composed) => last = Some(composed),
+            None => {
+                normalized.extend(last);
+                last = Some(c);
+            }
+        }
+    }
+    normalized.extend(last);
+    (normalized != key).then_some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_inline_and_heap_storage() {
+        assert_eq!(std::mem::size_of::<UclKey>(), std::mem::size_of::<String>());
+
+        let short = "a".repeat(INLINE_CAPACITY);
+        let long = "a".repeat(INLINE_CAPACITY + 1);
+        assert!(UclKey::from(short.as_str()).is_inline());
+        assert!(UclKey::from(short.clone()).is_inline());
+        assert!(!UclKey::from(long.as_str()).is_inline());
+        assert!(!UclKey::from(long.clone()).is_inline());
+        assert!(UclKey::new().is_inline());
+
+        for text in ["", "port", "ключ", short.as_str(), long.as_str()] {
+            let key = UclKey::from(text);
+            assert_eq!(key.as_str(), text);
+            assert_eq!(key, text);
+            assert_eq!(String::from(key.clone()), text);
+            assert_eq!(hash_of(&key), hash_of(text));
+        }
+    }
+
+    #[test]
+    fn test_index_keys() {
+        for index in [0, 7, 10, 42, 1000, usize::MAX] {
+            let key = UclKey::index(index);
+            assert!(key.is_inline());
+            assert_eq!(key, UclKey::from(index.to_string()));
+            assert_eq!(hash_of(&key), hash_of(index.to_string().as_str()));
+        }
+    }
+
+    #[test]
+    fn test_ordering_matches_str() {
+        let mut keys: Vec<UclKey> = ["b", "a", "aa", &"z".repeat(30)]
+            .into_iter()
+            .map(UclKey::from)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, ["a", "aa", "b", &"z".repeat(30)]);
+        assert_eq!(format!("{:?} {:>4}", keys[0], keys[1]), "\"a\"   aa");
+    }
+
+    #[test]
+    fn test_normalize_nfc() {
+        assert_eq!(normalize_nfc("port"), None);
+        assert_eq!(normalize_nfc("caf\u{e9}"), None);
+        assert_eq!(normalize_nfc("cafe\u{301}").as_deref(), Some("caf\u{e9}"));
+        assert_eq!(
+            normalize_nfc("A\u{30A}ngstro\u{308}m \u{212B}").as_deref(),
+            Some("\u{C5}ngstr\u{F6}m \u{C5}")
+        );
+        assert_eq!(normalize_nfc("\u{212A}elvin").as_deref(), Some("Kelvin"));
+        // A mark with no precomposed letter, or after a mark, stays as written
+        assert_eq!(normalize_nfc("q\u{301}"), None);
+        assert_eq!(normalize_nfc("\u{301}\u{301}"), None);
+        // Cyrillic letters are not Latin ones in any normalization form
+        assert_eq!(normalize_nfc("\u{430}pi"), None);
+
+        for (mark, bases, composed) in COMPOSITIONS {
+            assert_eq!(bases.chars().count(), composed.chars().count(), "{mark:?}");
+        }
+    }
+}
@@ -75,7 +75,7 @@
 //! // Use environment variables
 //! let config: Config = from_str_with_variables(
 //!     ucl_text,
-//!     Box::new(EnvironmentVariableHandler)
+//!     Box::new(EnvironmentVariableHandler::new())
 //! )?;
 //! # Ok::<(), ucl_lexer::UclError>(())
 //! ```
@@ -255,6 +255,11 @@
 //! - `zero-copy`: Enable zero-copy string parsing optimizations
 //! - `save-comments`: Preserve comments during parsing
 //! - `strict-unicode`: Enforce strict Unicode validation
+//! - `encoding`: Transcode UTF-16 input with a byte order mark in `from_slice`
+//! - `serde-token`: Serialize tokens and spans, and dump them with `tokens_to_json`
+//! - `types`: `ByteSize` and `Seconds` newtypes for suffixed sizes and durations
+//! - `arena`: `UclParser::parse_in`, which allocates the parsed value in a `bumpalo` arena
+//! - `serde-errors`: Serialize errors as `{code, message, line, column, offset, hint, path}` records
 //!
 //! ## Examples
 //!
@@ -265,41 +270,95 @@
 //! - `performance_comparison.rs`: Performance benchmarking
 //! - `number_parsing.rs`: Rich number format examples
 
+#[cfg(feature = "arena")]
+pub mod arena;
+pub mod bytes;
 pub mod c_libucl_compatibility;
+pub mod cancellation;
+pub mod cst;
 pub mod deserializer;
+pub mod directives;
+mod encoding;
 pub mod error;
+pub mod expand;
+pub mod include;
+pub mod key;
 pub mod lexer;
+mod macros;
 pub mod parser;
+pub mod provenance;
+pub mod scalar;
+pub mod serializer;
+#[cfg(feature = "types")]
+pub mod types;
+pub mod walk;
 
 #[cfg(test)]
 mod error_tests;
 
 // Re-export main types and functions
-pub use deserializer::{UclDeserializer, from_str, from_str_with_variables};
-pub use error::{LexError, ParseError, UclError};
+pub use bytes::BytesEncoding;
+pub use cancellation::CancellationFlag;
+pub use cst::{CstEntry, CstNode, CstToken, UclDocument};
+pub use deserializer::{
+    UclDeserializer, from_fragments, from_slice, from_str, from_str_at_path, from_str_at_paths,
+    from_str_with_source_map, from_str_with_variable_handler, from_str_with_variables,
+    from_str_with_variables_ref, from_value,
+};
+pub use directives::{Directive, DirectiveKind, DirectiveParser};
+pub use error::{
+    ConversionError, Diagnostic, EditError, LexError, LintCode, ParseError, UclError, WriteError,
+};
+pub use include::{FsIncludeResolver, IncludeResolver};
+pub use key::UclKey;
+#[cfg(feature = "serde-token")]
+pub use lexer::tokens_to_json;
+#[cfg(feature = "async")]
+pub use lexer::{
+    AsyncStreamingUclLexer, streaming_lexer_from_async_file, streaming_lexer_from_async_reader,
+};
 pub use lexer::{
-    LexerConfig, StreamingUclLexer, StringFormat, Token, UclLexer, streaming_lexer_from_file,
-    streaming_lexer_from_reader,
+    KeyCharset, LexerConfig, LexerStats, MSuffix, NewlineHandling, StreamingUclLexer, StringFormat,
+    Token, UclLexer, streaming_lexer_from_file, streaming_lexer_from_reader,
+};
+pub use parser::{
+    AliasStyle, DEFAULT_MAX_DOCUMENT_SIZE, DecimalMode, DuplicateKeyBehavior, EquivalenceOptions,
+    LintConfig, NonFinitePolicy, ParseStats, ParserConfig, TimeSuffixStyle, UclArray, UclArrayExt,
+    UclContainerExt, UclObject, UclParser, UclValue, format_time, validate,
+};
+pub use provenance::{Origin, Provenance, SourceMap, TrackedValue};
+pub use serializer::{
+    FormatConfig, UclScalar, UclWriter, to_ucl_string, to_ucl_string_tracked,
+    to_ucl_string_with_config,
 };
-pub use parser::{DuplicateKeyBehavior, ParserConfig, UclArray, UclObject, UclParser, UclValue};
+pub use walk::{KeyPath, PathSegment, UclVisitor, Walk, WalkNodes};
 
 // Re-export position types
-pub use error::{Position, Span};
+pub use error::{ColumnUnit, Position, Span};
 
 // Re-export variable handler types
 pub use parser::{
-    ChainedVariableHandler, EnvironmentVariableHandler, MapVariableHandler, VariableContext,
-    VariableHandler,
+    ChainedVariableHandler, EnvironmentNameTransform, EnvironmentVariableHandler,
+    MapVariableHandler, VariableContext, VariableHandler,
 };
 
 // Re-export custom parsing hooks
-pub use parser::{NumberSuffixHandler, ParsingHooks, StringPostProcessor, ValidationHook};
+pub use parser::{
+    KeyTransformHook, NumberSuffixHandler, ParsingHooks, StringPostProcessor, ValidationHook,
+};
 
 // Re-export plugin system
-pub use parser::{PluginConfig, PluginRegistry, UclParserBuilder, UclPlugin};
+pub use parser::{ConfiguredParser, PluginConfig, PluginRegistry, UclParserBuilder, UclPlugin};
+
+// Re-export quantity parsing
+pub use parser::{
+    BuiltinSuffixes, QuantityOutput, QuantityPlugin, QuantitySuffixHandler, UnitFamily,
+};
 
 // Re-export example plugins
 pub use parser::{ConfigValidationPlugin, CssUnitsPlugin, PathProcessingPlugin};
 
 // Re-export example implementations
-pub use parser::{CustomUnitSuffixHandler, PathNormalizationProcessor, SchemaValidationHook};
+pub use parser::{
+    CustomUnitSuffixHandler, KeyPrefixStripper, PathNormalizationProcessor, SchemaValidationHook,
+};
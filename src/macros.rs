@@ -0,0 +1,86 @@
+//! Macros for constructing UCL values
+//!
+//! The `ucl!` macro builds a `UclValue` from JSON-like syntax, which keeps
+//! test fixtures and programmatic configuration short.
+
+/// Constructs a `UclValue` from JSON-like syntax
+///
+/// ```rust
+/// use ucl_lexer::{ucl, UclValue};
+///
+/// let port = 8080;
+/// let value = ucl!({
+///     "name": "server",
+///     "port": port,
+///     "tags": ["web", "prod"],
+///     "tls": { "enabled": true, "cert": null }
+/// });
+///
+/// assert_eq!(value.as_object().unwrap()["port"], UclValue::Integer(8080));
+/// ```
+///
+/// Keys must be a single token: a string literal or a parenthesized expression.
+/// Any other value is converted with `UclValue::from`.
+#[macro_export]
+macro_rules! ucl {
+    (null) => {
+        $crate::UclValue::Null
+    };
+    (true) => {
+        $crate::UclValue::Boolean(true)
+    };
+    (false) => {
+        $crate::UclValue::Boolean(false)
+    };
+    ([]) => {
+        $crate::UclValue::Array(::std::boxed::Box::new($crate::UclArray::new()))
+    };
+    ([ $($tt:tt)+ ]) => {{
+        let mut array = $crate::UclArray::new();
+        $crate::__ucl_internal!(@array array () ($($tt)+));
+        $crate::UclValue::Array(::std::boxed::Box::new(array))
+    }};
+    ({}) => {
+        $crate::UclValue::Object($crate::UclObject::new())
+    };
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::UclObject::new();
+        $crate::__ucl_internal!(@object object ($($tt)+));
+        $crate::UclValue::Object(object)
+    }};
+    ($other:expr) => {
+        $crate::UclValue::from($other)
+    };
+}
+
+/// Token muncher behind `ucl!`; collects element and value tokens up to the next top-level comma
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __ucl_internal {
+    (@array $array:ident ($($elem:tt)+) (, $($rest:tt)*)) => {
+        $array.push($crate::ucl!($($elem)+));
+        $crate::__ucl_internal!(@array $array () ($($rest)*));
+    };
+    (@array $array:ident ($($elem:tt)+) ()) => {
+        $array.push($crate::ucl!($($elem)+));
+    };
+    (@array $array:ident () ()) => {};
+    (@array $array:ident ($($elem:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::__ucl_internal!(@array $array ($($elem)* $next) ($($rest)*));
+    };
+
+    (@object $object:ident ()) => {};
+    (@object $object:ident ($key:tt : $($rest:tt)+)) => {
+        $crate::__ucl_internal!(@entry $object [$key] () ($($rest)+));
+    };
+    (@entry $object:ident [$key:tt] ($($value:tt)+) (, $($rest:tt)*)) => {
+        $object.insert($crate::UclKey::from(($key).to_string()), $crate::ucl!($($value)+));
+        $crate::__ucl_internal!(@object $object ($($rest)*));
+    };
+    (@entry $object:ident [$key:tt] ($($value:tt)+) ()) => {
+        $object.insert($crate::UclKey::from(($key).to_string()), $crate::ucl!($($value)+));
+    };
+    (@entry $object:ident [$key:tt] ($($value:tt)*) ($next:tt $($rest:tt)*)) => {
+        $crate::__ucl_internal!(@entry $object [$key] ($($value)* $next) ($($rest)*));
+    };
+}
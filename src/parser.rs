@@ -3,26 +3,129 @@
 //! This module provides the parser that consumes tokens from the lexer
 //! and builds structured UCL values with variable expansion support.
 
-use crate::error::{ParseError, Position};
-use crate::lexer::{LexerConfig, Token, UclLexer};
+#![deny(clippy::unwrap_used, clippy::expect_used)]
+
+#[cfg(feature = "arena")]
+use crate::arena::{ArenaBuilder, Bump, UclValueArena};
+use crate::bytes::BytesEncoding;
+use crate::cancellation::{self, CancellationFlag};
+use crate::directives::{Directive, DirectiveKind, DirectiveParser};
+use crate::error::{
+    ConversionError, Diagnostic, LexError, LintCode, ParseError, Position, Span, UclError,
+};
+use crate::expand::{Expansion, Segment, scan_variables};
+use crate::include::{
+    IncludeParams, IncludeResolver, IncludeTotals, MAX_INCLUDE_DEPTH, MAX_INCLUDED_FILES,
+    PendingInclude, sort_by_file_name,
+};
+use crate::key::{UclKey, normalize_nfc};
+use crate::lexer::{CommentInfo, KeyCharset, LexerConfig, MSuffix, StringFormat, Token, UclLexer};
+use crate::provenance::{Origin, Provenance, TrackedValue};
 use indexmap::IndexMap;
+use indexmap::map::Entry;
 use smallvec::SmallVec;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
 use std::cmp::Reverse;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+pub use crate::lexer::DEFAULT_MAX_DOCUMENT_SIZE;
 
 /// Behavior when duplicate keys are encountered
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DuplicateKeyBehavior {
     /// Return an error when duplicate keys are found
+    ///
+    /// Each object has its own keys, so objects in an array may repeat them.
+    /// Named sections with distinct labels, such as `server a { }` and
+    /// `server b { }`, are merged; repeating a label is a duplicate.
     Error,
     /// Create implicit arrays when keys are repeated
     ImplicitArray,
     /// Use the last value (override previous values)
     Override,
+    /// Keep the first value and ignore later ones
+    KeepFirst,
+}
+
+/// Handling of non-finite floats (`inf`, `-infinity`, `nan`, overflowing literals)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    /// Keep non-finite values as floats
+    #[default]
+    Allow,
+    /// Reject non-finite values with a parse error
+    Error,
+    /// Replace non-finite values with null and record a diagnostic
+    Null,
+}
+
+/// How numbers with a fractional part are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecimalMode {
+    /// Read them as `f64`
+    #[default]
+    Float,
+    /// Keep the text as written in `UclValue::Decimal`, for values that must stay exact
+    ///
+    /// Only plain decimals such as `0.1` or `-12.50` are accepted; exponents
+    /// and suffixes are rejected with `ParseError::InvalidDecimal`.
+    PreserveString,
+}
+
+/// Spellings of a key that the deserializer reads as the same struct field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AliasStyle {
+    /// `max-connections` and `max_connections` name the same field
+    KebabSnakeEquivalent,
+}
+
+impl AliasStyle {
+    /// Returns whether two keys are spellings of the same name
+    ///
+    /// ```rust
+    /// use ucl_lexer::AliasStyle;
+    ///
+    /// let style = AliasStyle::KebabSnakeEquivalent;
+    /// assert!(style.same_key("max-connections", "max_connections"));
+    /// assert!(!style.same_key("max-connections", "maxconnections"));
+    /// ```
+    pub fn same_key(self, a: &str, b: &str) -> bool {
+        match self {
+            AliasStyle::KebabSnakeEquivalent => {
+                a.len() == b.len()
+                    && a.bytes().zip(b.bytes()).all(|(a, b)| {
+                        a == b || (matches!(a, b'-' | b'_') && matches!(b, b'-' | b'_'))
+                    })
+            }
+        }
+    }
+
+    /// Returns the field among `fields` that `key` names, preferring an exact match
+    pub(crate) fn field_for<'f>(self, key: &str, fields: &[&'f str]) -> Option<&'f str> {
+        fields
+            .iter()
+            .find(|field| **field == key)
+            .or_else(|| fields.iter().find(|field| self.same_key(field, key)))
+            .copied()
+    }
 }
 
 /// Configuration options for the parser
+///
+/// Build a configuration from `ParserConfig::new()` or a preset such as
+/// `ParserConfig::untrusted()` with the `with_*` methods, and read it back
+/// with the accessor of the same name. The fields are still public for one
+/// release; accessing them directly is deprecated, as they will become private.
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct ParserConfig {
     /// Maximum nesting depth to prevent stack overflow
     pub max_depth: usize,
@@ -33,6 +136,50 @@ pub struct ParserConfig {
     pub duplicate_key_behavior: DuplicateKeyBehavior,
     /// Preserve key order in objects
     pub preserve_key_order: bool,
+    /// How non-finite float values are handled
+    pub non_finite_numbers: NonFinitePolicy,
+    /// How numbers with a fractional part are read
+    pub decimal_mode: DecimalMode,
+    /// Record the origin of each object entry while parsing
+    pub track_provenance: bool,
+    /// Concatenate strings with `key += "..."` instead of rejecting the append
+    pub append_concatenates_strings: bool,
+    /// How the deserializer decodes strings into byte fields
+    pub bytes_encoding: BytesEncoding,
+    /// Let the deserializer read strings as numbers or booleans, and numbers as strings
+    pub lenient_coercion: bool,
+    /// Accept `key value;` without ':' or '=' for scalar values, as libucl does
+    pub relaxed_assignment: bool,
+    /// Read a key with no value, as in `enabled;`, as `enabled = true`
+    pub bare_key_as_true: bool,
+    /// Largest accepted input in bytes, checked before parsing starts
+    pub max_document_size: usize,
+    /// Structural lints reported as diagnostics
+    pub lints: LintConfig,
+    /// Values substituted for bare words in value position
+    pub constants: HashMap<String, UclValue>,
+    /// Match constant names case-sensitively
+    pub constants_case_sensitive: bool,
+    /// Reject bare words in value position that are neither keywords nor constants
+    pub strict_constants: bool,
+    /// Read lint and deprecation directives from comments
+    pub directives: Option<DirectiveParser>,
+    /// Register values marked `&name` and copy them where `*name` appears
+    pub enable_anchors: bool,
+    /// Most values, counting every nested one, that `*name` references may copy into a document
+    pub max_alias_expansion: usize,
+    /// Read a bare key such as `a.b.c = 1` as `a { b { c = 1 } }`
+    pub expand_dotted_keys: bool,
+    /// Accept only JSON, rejecting UCL extensions such as unquoted keys, `=` and comments
+    pub json_only: bool,
+    /// Stop at content after a complete document instead of rejecting it
+    pub allow_trailing_content: bool,
+    /// Let the deserializer match struct fields under other spellings of their names
+    pub key_alias_style: Option<AliasStyle>,
+    /// Let the deserializer skip top-level sections a struct does not declare
+    pub skip_undeclared_sections: bool,
+    /// Flag that stops the parse with `ParseError::Cancelled` when set
+    pub cancellation: Option<CancellationFlag>,
 }
 
 impl ParserConfig {
@@ -41,6 +188,53 @@ impl ParserConfig {
         Self::default()
     }
 
+    /// Creates a strict configuration that requires ':' or '=' before scalar values
+    ///
+    /// Anchor references may copy at most 10,000 values into a document.
+    pub fn strict() -> Self {
+        Self::default()
+            .with_relaxed_assignment(false)
+            .with_bare_key_as_true(false)
+            .with_max_alias_expansion(10_000)
+    }
+
+    /// Creates a configuration that also accepts libucl shorthands such as `enabled;`
+    pub fn libucl_compat() -> Self {
+        Self::default()
+            .with_relaxed_assignment(true)
+            .with_bare_key_as_true(true)
+    }
+
+    /// Creates a configuration that accepts exactly the JSON grammar
+    ///
+    /// UCL extensions such as unquoted keys, `=`, `;`, comments, trailing
+    /// commas and number suffixes are errors. A repeated key keeps its last
+    /// value, as most JSON parsers do, and `${VAR}` is not expanded.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{ParserConfig, UclParser};
+    ///
+    /// let parse = |input| UclParser::new(input).with_config(ParserConfig::json_strict()).parse_document();
+    /// assert!(parse(r#"{"port": 80, "hosts": ["a", "b"]}"#).is_ok());
+    /// assert!(parse("port = 80").is_err());
+    /// ```
+    pub fn json_strict() -> Self {
+        Self::strict()
+            .with_json_only(true)
+            .with_duplicate_key_behavior(DuplicateKeyBehavior::Override)
+    }
+
+    /// Creates a strict configuration with tight limits for input from untrusted sources
+    ///
+    /// Inputs are limited to 1 MiB and 32 levels of nesting, and anchor
+    /// references to 10,000 copied values as in `strict`. Pair it with
+    /// `LexerConfig::untrusted()`.
+    pub fn untrusted() -> Self {
+        Self::strict()
+            .with_max_depth(32)
+            .with_max_document_size(1024 * 1024)
+    }
+
     /// Sets the duplicate key behavior
     pub fn with_duplicate_key_behavior(mut self, behavior: DuplicateKeyBehavior) -> Self {
         self.duplicate_key_behavior = behavior;
@@ -73,1709 +267,6240 @@ impl ParserConfig {
         self.preserve_key_order = preserve;
         self
     }
-}
 
-impl Default for ParserConfig {
-    fn default() -> Self {
-        Self {
-            max_depth: 128,
-            allow_duplicate_keys: true, // Kept for backward compatibility
-            duplicate_key_behavior: DuplicateKeyBehavior::ImplicitArray,
-            preserve_key_order: true,
-        }
+    /// Sets the policy for non-finite float values
+    pub fn with_non_finite_numbers(mut self, policy: NonFinitePolicy) -> Self {
+        self.non_finite_numbers = policy;
+        self
     }
-}
 
-/// UCL value types
-#[derive(Debug, Clone, PartialEq)]
-pub enum UclValue {
-    String(String),
-    Integer(i64),
-    Float(f64),
-    Boolean(bool),
-    Null,
-    Object(UclObject),
-    /// Arrays use Box<SmallVec> to avoid infinite size recursion
-    /// SmallVec stores ≤4 elements inline without heap allocation
-    Array(Box<UclArray>),
-}
+    /// Sets how numbers with a fractional part are read
+    ///
+    /// ```rust
+    /// use ucl_lexer::{DecimalMode, ParserConfig, UclParser};
+    ///
+    /// let config = ParserConfig::new().with_decimal_mode(DecimalMode::PreserveString);
+    /// let value = UclParser::new("rate = 0.10").with_config(config).parse_document()?;
+    /// assert_eq!(value["rate"].as_decimal(), Some("0.10"));
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn with_decimal_mode(mut self, mode: DecimalMode) -> Self {
+        self.decimal_mode = mode;
+        self
+    }
 
-impl UclValue {
-    /// Returns true if the value is an object
-    pub fn is_object(&self) -> bool {
-        matches!(self, UclValue::Object(_))
+    /// Sets whether the origin of each object entry is recorded
+    pub fn with_track_provenance(mut self, track: bool) -> Self {
+        self.track_provenance = track;
+        self
     }
 
-    /// Returns true if the value is an array
-    pub fn is_array(&self) -> bool {
-        matches!(self, UclValue::Array(_))
+    /// Sets whether `key += "..."` concatenates onto an existing string
+    pub fn with_append_concatenates_strings(mut self, concatenate: bool) -> Self {
+        self.append_concatenates_strings = concatenate;
+        self
     }
 
-    /// Returns true if the value is a string
-    pub fn is_string(&self) -> bool {
-        matches!(self, UclValue::String(_))
+    /// Sets how the deserializer decodes strings into byte fields
+    pub fn with_bytes_encoding(mut self, encoding: BytesEncoding) -> Self {
+        self.bytes_encoding = encoding;
+        self
     }
 
-    /// Returns a reference to the object if this is an Object variant
-    pub fn as_object(&self) -> Option<&UclObject> {
-        if let UclValue::Object(obj) = self {
-            Some(obj)
-        } else {
-            None
-        }
+    /// Sets whether the deserializer converts between strings and primitives
+    pub fn with_lenient_coercion(mut self, lenient: bool) -> Self {
+        self.lenient_coercion = lenient;
+        self
     }
 
-    /// Returns a reference to the array if this is an Array variant
-    pub fn as_array(&self) -> Option<&UclArray> {
-        if let UclValue::Array(arr) = self {
-            Some(arr)
-        } else {
-            None
-        }
+    /// Enables directives in comments, recognized by the given parser
+    pub fn with_directives(mut self, directives: DirectiveParser) -> Self {
+        self.directives = Some(directives);
+        self
     }
 
-    /// Returns a reference to the string if this is a String variant
-    pub fn as_str(&self) -> Option<&str> {
-        if let UclValue::String(s) = self {
-            Some(s.as_str())
-        } else {
-            None
-        }
+    /// Sets whether scalar values may follow a key without ':' or '='
+    pub fn with_relaxed_assignment(mut self, relaxed: bool) -> Self {
+        self.relaxed_assignment = relaxed;
+        self
     }
 
-    /// Returns the integer value if this is an Integer variant
-    pub fn as_integer(&self) -> Option<i64> {
-        if let UclValue::Integer(i) = self {
-            Some(*i)
-        } else {
-            None
-        }
+    /// Sets whether a key with no value is read as `true`
+    pub fn with_bare_key_as_true(mut self, enabled: bool) -> Self {
+        self.bare_key_as_true = enabled;
+        self
     }
 
-    /// Returns the float value if this is a Float variant
-    pub fn as_float(&self) -> Option<f64> {
-        if let UclValue::Float(f) = self {
-            Some(*f)
-        } else {
-            None
-        }
+    /// Sets whether `&name` anchors and `*name` references are read
+    pub fn with_enable_anchors(mut self, enabled: bool) -> Self {
+        self.enable_anchors = enabled;
+        self
     }
 
-    /// Returns the time value if this is a Float variant (time values are stored as floats)
-    pub fn as_time(&self) -> Option<f64> {
-        if let UclValue::Float(f) = self {
-            Some(*f)
-        } else {
-            None
-        }
+    /// Sets the most values `*name` references may copy into a document
+    ///
+    /// Each reference counts the anchored value and every value nested in
+    /// it, so references to anchors that themselves hold references cannot
+    /// grow a small document into an enormous tree. Going past the limit is
+    /// `ParseError::AliasExpansionTooLarge`.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{ParseError, ParserConfig, UclParser};
+    ///
+    /// let config = ParserConfig::new()
+    ///     .with_enable_anchors(true)
+    ///     .with_max_alias_expansion(4);
+    /// let input = "a = &a [1, 2]\nb = [*a, *a]";
+    /// let err = UclParser::new(input).with_config(config).parse_document().unwrap_err();
+    /// assert!(matches!(err, ParseError::AliasExpansionTooLarge { limit: 4, .. }));
+    /// ```
+    pub fn with_max_alias_expansion(mut self, limit: usize) -> Self {
+        self.max_alias_expansion = limit;
+        self
     }
 
-    /// Returns the boolean value if this is a Boolean variant
-    pub fn as_bool(&self) -> Option<bool> {
-        if let UclValue::Boolean(b) = self {
-            Some(*b)
-        } else {
-            None
-        }
+    /// Sets whether bare keys such as `a.b.c` are expanded into nested objects
+    pub fn with_expand_dotted_keys(mut self, enabled: bool) -> Self {
+        self.expand_dotted_keys = enabled;
+        self
     }
 
-    /// Returns true if this is a Null variant
-    pub fn is_null(&self) -> bool {
-        matches!(self, UclValue::Null)
+    /// Sets whether only JSON is accepted
+    pub fn with_json_only(mut self, enabled: bool) -> Self {
+        self.json_only = enabled;
+        self
     }
-}
 
-/// UCL object type (preserves insertion order)
-pub type UclObject = IndexMap<String, UclValue>;
+    /// Sets whether content after a complete document ends the parse instead of failing it
+    ///
+    /// By default anything but whitespace, comments and separators after the
+    /// document is a `ParseError::TrailingContent`: tokens after an explicit
+    /// root value, or a `}` or `]` that closes nothing in a document without
+    /// braces. When allowed, the parse stops at that token and
+    /// `UclParser::trailing_content_offset` returns its byte offset, so a UCL
+    /// block can be read from the start of a larger file.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{ParserConfig, UclParser};
+    ///
+    /// let input = "{ port = 80 }\n--- end of config ---\n";
+    /// let config = ParserConfig::new().with_allow_trailing_content(true);
+    /// let mut parser = UclParser::new(input).with_config(config);
+    /// let value = parser.parse_document()?;
+    /// assert_eq!(value["port"], 80);
+    /// assert_eq!(parser.trailing_content_offset(), Some(14));
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn with_allow_trailing_content(mut self, enabled: bool) -> Self {
+        self.allow_trailing_content = enabled;
+        self
+    }
 
-/// UCL array type - uses SmallVec to avoid heap allocation for small arrays (≤4 elements)
-/// Most UCL arrays in practice have ≤4 elements (tags, options, etc.)
-pub type UclArray = SmallVec<[UclValue; 4]>;
+    /// Sets which spellings of a key the deserializer reads as the same struct field
+    ///
+    /// Keys that match a field exactly are unaffected, as are maps. An object
+    /// with two spellings of one field, such as `max-connections` and
+    /// `max_connections`, is an error naming both keys.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use ucl_lexer::{AliasStyle, ParserConfig, UclDeserializer};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Server {
+    ///     max_connections: u32,
+    /// }
+    ///
+    /// let config = ParserConfig::new().with_key_alias_style(Some(AliasStyle::KebabSnakeEquivalent));
+    /// let server = Server::deserialize(UclDeserializer::with_parser_config(
+    ///     "max-connections = 10",
+    ///     config,
+    /// ))?;
+    /// assert_eq!(server.max_connections, 10);
+    /// # Ok::<(), ucl_lexer::UclError>(())
+    /// ```
+    pub fn with_key_alias_style(mut self, style: Option<AliasStyle>) -> Self {
+        self.key_alias_style = style;
+        self
+    }
 
-/// Context information for variable expansion
-#[derive(Debug, Clone)]
-pub struct VariableContext {
-    /// Current position in the source
-    pub position: Position,
-    /// Path to current object (for nested variable resolution)
-    pub current_object_path: Vec<String>,
-    /// Stack of variables currently being expanded (for circular reference detection)
-    pub expansion_stack: Vec<String>,
-}
+    /// Sets whether deserializing a struct skips the top-level sections it does not declare
+    ///
+    /// When enabled, only the top-level sections named by the struct's fields
+    /// and aliases are built. The others are skipped token by token, without
+    /// building values or expanding variables, so reading a few sections of a
+    /// large file runs close to lexing speed. Skipped sections are lexed but
+    /// not otherwise checked, so syntax errors inside them go unreported; by
+    /// default the whole document is parsed and validated.
+    ///
+    /// ```rust
+    /// use serde::Deserialize;
+    /// use ucl_lexer::{ParserConfig, UclDeserializer};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Service {
+    ///     port: u16,
+    /// }
+    ///
+    /// let input = "port = 80\nmetrics { a = = 1 }\n";
+    /// assert!(Service::deserialize(UclDeserializer::new(input)).is_err());
+    ///
+    /// let config = ParserConfig::new().with_skip_undeclared_sections(true);
+    /// let service = Service::deserialize(UclDeserializer::with_parser_config(input, config))?;
+    /// assert_eq!(service.port, 80);
+    /// # Ok::<(), ucl_lexer::UclError>(())
+    /// ```
+    pub fn with_skip_undeclared_sections(mut self, enabled: bool) -> Self {
+        self.skip_undeclared_sections = enabled;
+        self
+    }
 
-impl VariableContext {
-    /// Creates a new variable context
-    pub fn new(position: Position) -> Self {
-        Self {
-            position,
-            current_object_path: Vec::new(),
-            expansion_stack: Vec::new(),
-        }
+    /// Sets a flag that cancels the parse from another thread, `None` for none
+    ///
+    /// The parser checks the flag every `cancellation::CHECK_INTERVAL` tokens
+    /// and at the start of each object, and stops with
+    /// `ParseError::Cancelled` once it is set. Included files are parsed
+    /// with the same flag.
+    pub fn with_cancellation(mut self, flag: Option<CancellationFlag>) -> Self {
+        self.cancellation = flag;
+        self
     }
 
-    /// Pushes a new key onto the object path
-    pub fn push_key(&mut self, key: String) {
-        self.current_object_path.push(key);
+    /// Sets the largest accepted input in bytes, `usize::MAX` for no limit
+    pub fn with_max_document_size(mut self, limit: usize) -> Self {
+        self.max_document_size = limit;
+        self
     }
 
-    /// Pops the last key from the object path
-    pub fn pop_key(&mut self) {
-        self.current_object_path.pop();
+    /// Sets the structural lints reported as diagnostics
+    pub fn with_lints(mut self, lints: LintConfig) -> Self {
+        self.lints = lints;
+        self
     }
 
-    /// Pushes a variable onto the expansion stack for circular reference detection
-    pub fn push_expansion(&mut self, var_name: String) -> Result<(), String> {
-        if self.expansion_stack.contains(&var_name) {
-            return Err(format!(
-                "Circular reference detected: {} -> {}",
-                self.expansion_stack.join(" -> "),
-                var_name
-            ));
-        }
-        self.expansion_stack.push(var_name);
-        Ok(())
+    /// Sets whether constant names are matched case-sensitively
+    pub fn with_constants_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.constants_case_sensitive = case_sensitive;
+        self
     }
 
-    /// Pops a variable from the expansion stack
-    pub fn pop_expansion(&mut self) {
-        self.expansion_stack.pop();
+    /// Sets whether bare words that are not registered constants are rejected
+    pub fn with_strict_constants(mut self, strict: bool) -> Self {
+        self.strict_constants = strict;
+        self
     }
 
-    /// Returns the current expansion depth
-    pub fn expansion_depth(&self) -> usize {
-        self.expansion_stack.len()
+    /// Registers a value substituted for the bare word `name` in value position
+    ///
+    /// Keys are never replaced. Names of keywords such as `true`, `off` or
+    /// `null` are rejected in any case.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{ParserConfig, UclParser, UclValue};
+    ///
+    /// let mut config = ParserConfig::new();
+    /// config.register_constant("DEBUG", UclValue::Integer(10))?;
+    ///
+    /// let value = UclParser::new("log_level = DEBUG\nDEBUG = WARN")
+    ///     .with_config(config)
+    ///     .parse_document()?;
+    /// assert_eq!(value["log_level"], 10);
+    /// assert_eq!(value["DEBUG"], "WARN");
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn register_constant(&mut self, name: &str, value: UclValue) -> Result<(), ParseError> {
+        Self::check_constant_name(name)?;
+        self.constants.insert(name.to_string(), value);
+        Ok(())
     }
 
-    /// Returns a copy of the context with updated position
-    pub fn with_position(&self, position: Position) -> Self {
-        Self {
-            position,
-            current_object_path: self.current_object_path.clone(),
-            expansion_stack: self.expansion_stack.clone(),
+    /// Registers several constants, or none if any name is a keyword
+    pub fn register_constants(
+        &mut self,
+        constants: HashMap<String, UclValue>,
+    ) -> Result<(), ParseError> {
+        for name in constants.keys() {
+            Self::check_constant_name(name)?;
         }
+        self.constants.extend(constants);
+        Ok(())
     }
-}
 
-/// Trait for handling variable expansion
-pub trait VariableHandler {
-    /// Resolves a variable by name
-    fn resolve_variable(&self, name: &str) -> Option<String>;
+    /// Returns the constant registered under `name`
+    ///
+    /// An exact match wins over one that differs only in case.
+    pub fn constant(&self, name: &str) -> Option<&UclValue> {
+        if let Some(value) = self.constants.get(name) {
+            return Some(value);
+        }
+        if self.constants_case_sensitive {
+            return None;
+        }
+        self.constants
+            .iter()
+            .find(|(constant, _)| constant.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value)
+    }
 
-    /// Resolves a variable with additional context
-    fn resolve_variable_with_context(
-        &self,
-        name: &str,
-        _context: &VariableContext,
-    ) -> Option<String> {
-        // Default implementation ignores context
-        self.resolve_variable(name)
+    /// Returns the maximum nesting depth
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
     }
-}
 
-/// Environment variable handler
-pub struct EnvironmentVariableHandler;
+    /// Returns whether duplicate keys are allowed (legacy setting)
+    pub fn allow_duplicate_keys(&self) -> bool {
+        self.allow_duplicate_keys
+    }
 
-impl VariableHandler for EnvironmentVariableHandler {
-    fn resolve_variable(&self, name: &str) -> Option<String> {
-        std::env::var(name).ok()
+    /// Returns the duplicate key behavior
+    pub fn duplicate_key_behavior(&self) -> DuplicateKeyBehavior {
+        self.duplicate_key_behavior
     }
-}
 
-/// Map-based variable handler
-pub struct MapVariableHandler {
-    variables: HashMap<String, String>,
-}
+    /// Returns whether key order is preserved
+    pub fn preserve_key_order(&self) -> bool {
+        self.preserve_key_order
+    }
 
-impl MapVariableHandler {
-    /// Creates a new map variable handler
-    pub fn new() -> Self {
-        Self {
-            variables: HashMap::new(),
-        }
+    /// Returns the policy for non-finite float values
+    pub fn non_finite_numbers(&self) -> NonFinitePolicy {
+        self.non_finite_numbers
     }
 
-    /// Creates a handler from an existing map
-    pub fn from_map(variables: HashMap<String, String>) -> Self {
-        Self { variables }
+    /// Returns how numbers with a fractional part are read
+    pub fn decimal_mode(&self) -> DecimalMode {
+        self.decimal_mode
     }
 
-    /// Inserts a variable
-    pub fn insert(&mut self, name: String, value: String) {
-        self.variables.insert(name, value);
+    /// Returns whether the origin of each object entry is recorded
+    pub fn track_provenance(&self) -> bool {
+        self.track_provenance
     }
 
-    /// Gets a reference to the internal map
-    pub fn variables(&self) -> &HashMap<String, String> {
-        &self.variables
+    /// Returns whether `key += "..."` concatenates onto an existing string
+    pub fn append_concatenates_strings(&self) -> bool {
+        self.append_concatenates_strings
     }
-}
 
-impl Default for MapVariableHandler {
-    fn default() -> Self {
-        Self::new()
+    /// Returns how the deserializer decodes strings into byte fields
+    pub fn bytes_encoding(&self) -> BytesEncoding {
+        self.bytes_encoding
     }
-}
 
-impl VariableHandler for MapVariableHandler {
-    fn resolve_variable(&self, name: &str) -> Option<String> {
-        self.variables.get(name).cloned()
+    /// Returns whether the deserializer converts between strings and primitives
+    pub fn lenient_coercion(&self) -> bool {
+        self.lenient_coercion
     }
-}
 
-/// Chained variable handler that tries multiple handlers in order
-pub struct ChainedVariableHandler {
-    handlers: Vec<Box<dyn VariableHandler>>,
-}
+    /// Returns whether scalar values may follow a key without ':' or '='
+    pub fn relaxed_assignment(&self) -> bool {
+        self.relaxed_assignment
+    }
 
-impl ChainedVariableHandler {
-    /// Creates a new chained handler
-    pub fn new() -> Self {
-        Self {
-            handlers: Vec::new(),
-        }
+    /// Returns whether a key with no value is read as `true`
+    pub fn bare_key_as_true(&self) -> bool {
+        self.bare_key_as_true
     }
 
-    /// Adds a handler to the chain
-    pub fn add_handler(&mut self, handler: Box<dyn VariableHandler>) {
-        self.handlers.push(handler);
+    /// Returns the largest accepted input in bytes
+    pub fn max_document_size(&self) -> usize {
+        self.max_document_size
     }
 
-    /// Creates a chained handler from a vector of handlers
-    pub fn from_handlers(handlers: Vec<Box<dyn VariableHandler>>) -> Self {
-        Self { handlers }
+    /// Returns the structural lints reported as diagnostics
+    pub fn lints(&self) -> LintConfig {
+        self.lints
     }
-}
 
-impl Default for ChainedVariableHandler {
-    fn default() -> Self {
-        Self::new()
+    /// Returns the registered constants
+    pub fn constants(&self) -> &HashMap<String, UclValue> {
+        &self.constants
     }
-}
 
-impl VariableHandler for ChainedVariableHandler {
-    fn resolve_variable(&self, name: &str) -> Option<String> {
-        for handler in &self.handlers {
-            if let Some(value) = handler.resolve_variable(name) {
-                return Some(value);
-            }
-        }
-        None
+    /// Returns whether constant names are matched case-sensitively
+    pub fn constants_case_sensitive(&self) -> bool {
+        self.constants_case_sensitive
     }
 
-    fn resolve_variable_with_context(
-        &self,
-        name: &str,
-        context: &VariableContext,
-    ) -> Option<String> {
-        for handler in &self.handlers {
-            if let Some(value) = handler.resolve_variable_with_context(name, context) {
-                return Some(value);
-            }
-        }
-        None
+    /// Returns whether bare words that are not registered constants are rejected
+    pub fn strict_constants(&self) -> bool {
+        self.strict_constants
     }
-}
 
-/// Trait for custom number suffix handling
-pub trait NumberSuffixHandler {
-    /// Attempts to parse a custom suffix and return the multiplier
-    /// Returns None if the suffix is not recognized by this handler
-    fn parse_suffix(&self, suffix: &str) -> Option<f64>;
+    /// Returns whether `&name` anchors and `*name` references are read
+    pub fn enable_anchors(&self) -> bool {
+        self.enable_anchors
+    }
 
-    /// Returns the priority of this handler (higher priority handlers are tried first)
-    fn priority(&self) -> u32 {
-        0
+    /// Returns the most values `*name` references may copy into a document
+    pub fn max_alias_expansion(&self) -> usize {
+        self.max_alias_expansion
     }
 
-    /// Returns a description of the suffixes this handler supports
-    fn description(&self) -> &str {
-        "Custom number suffix handler"
+    /// Returns whether bare keys such as `a.b.c` are expanded into nested objects
+    pub fn expand_dotted_keys(&self) -> bool {
+        self.expand_dotted_keys
     }
-}
 
-/// Trait for custom string post-processing
-pub trait StringPostProcessor {
-    /// Processes a string value after parsing and variable expansion
-    /// Can modify the string or return an error
-    fn process_string(&self, value: &str, context: &VariableContext) -> Result<String, ParseError>;
+    /// Returns whether only JSON is accepted
+    pub fn json_only(&self) -> bool {
+        self.json_only
+    }
 
-    /// Returns the priority of this processor (higher priority processors are applied first)
-    fn priority(&self) -> u32 {
-        0
+    /// Returns whether content after a complete document ends the parse instead of failing it
+    pub fn allow_trailing_content(&self) -> bool {
+        self.allow_trailing_content
     }
 
-    /// Returns a description of what this processor does
-    fn description(&self) -> &str {
-        "Custom string post-processor"
+    /// Returns which spellings of a key the deserializer reads as the same struct field
+    pub fn key_alias_style(&self) -> Option<AliasStyle> {
+        self.key_alias_style
     }
-}
 
-/// Trait for custom validation during parsing
-pub trait ValidationHook {
-    /// Validates a parsed value before it's added to the result
-    /// Can modify the value or return an error
-    fn validate_value(
-        &self,
-        value: &UclValue,
-        context: &VariableContext,
-    ) -> Result<Option<UclValue>, ParseError>;
+    /// Returns whether deserializing a struct skips the top-level sections it does not declare
+    pub fn skip_undeclared_sections(&self) -> bool {
+        self.skip_undeclared_sections
+    }
 
-    /// Validates an object key before it's used
-    fn validate_key(
-        &self,
-        _key: &str,
-        _context: &VariableContext,
-    ) -> Result<Option<String>, ParseError> {
-        // Default implementation accepts all keys
-        Ok(None)
+    /// Returns the directive parser, if directives are enabled
+    pub fn directives(&self) -> Option<&DirectiveParser> {
+        self.directives.as_ref()
     }
 
-    /// Returns the priority of this hook (higher priority hooks are called first)
-    fn priority(&self) -> u32 {
-        0
+    /// Returns the flag that cancels the parse, if one is set
+    pub fn cancellation(&self) -> Option<&CancellationFlag> {
+        self.cancellation.as_ref()
     }
 
-    /// Returns a description of what this hook validates
-    fn description(&self) -> &str {
-        "Custom validation hook"
+    /// Rejects names that the parser reads as keywords
+    fn check_constant_name(name: &str) -> Result<(), ParseError> {
+        const KEYWORDS: [&str; 10] = [
+            "true", "yes", "on", "false", "no", "off", "null", "inf", "infinity", "nan",
+        ];
+        if KEYWORDS
+            .iter()
+            .any(|keyword| keyword.eq_ignore_ascii_case(name))
+        {
+            return Err(ParseError::ReservedConstant {
+                name: name.to_string(),
+            });
+        }
+        Ok(())
     }
 }
 
-/// Container for all custom parsing hooks
-#[derive(Default)]
-pub struct ParsingHooks {
-    /// Custom number suffix handlers
-    pub number_suffix_handlers: Vec<Box<dyn NumberSuffixHandler>>,
-    /// Custom string post-processors
-    pub string_processors: Vec<Box<dyn StringPostProcessor>>,
-    /// Custom validation hooks
-    pub validation_hooks: Vec<Box<dyn ValidationHook>>,
+impl Default for ParserConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 128,
+            allow_duplicate_keys: true, // Kept for backward compatibility
+            duplicate_key_behavior: DuplicateKeyBehavior::ImplicitArray,
+            preserve_key_order: true,
+            non_finite_numbers: NonFinitePolicy::Allow,
+            decimal_mode: DecimalMode::Float,
+            track_provenance: false,
+            append_concatenates_strings: false,
+            bytes_encoding: BytesEncoding::Base64,
+            lenient_coercion: false,
+            relaxed_assignment: true,
+            bare_key_as_true: false,
+            max_document_size: DEFAULT_MAX_DOCUMENT_SIZE,
+            lints: LintConfig::default(),
+            constants: HashMap::new(),
+            constants_case_sensitive: true,
+            strict_constants: false,
+            directives: None,
+            enable_anchors: false,
+            max_alias_expansion: 1_000_000,
+            expand_dotted_keys: false,
+            json_only: false,
+            allow_trailing_content: false,
+            key_alias_style: None,
+            skip_undeclared_sections: false,
+            cancellation: None,
+        }
+    }
 }
 
-impl ParsingHooks {
-    /// Creates a new empty set of parsing hooks
+/// Selects the structural lints recorded in `UclParser::diagnostics`
+///
+/// Lints never change the parsed value. All of them are disabled by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    /// Report objects whose entries mix `=` and `:`
+    pub mixed_separators: bool,
+    /// Report keys that repeat the name of an enclosing key
+    pub shadowed_keys: bool,
+    /// Report `{}` assigned to a key that holds a scalar elsewhere in the document
+    pub empty_objects: bool,
+    /// Report quoted strings that read as a number, boolean or duration
+    pub typed_strings: bool,
+}
+
+impl LintConfig {
+    /// Creates a configuration with every lint disabled
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Adds a number suffix handler
-    pub fn add_number_suffix_handler(&mut self, handler: Box<dyn NumberSuffixHandler>) {
-        self.number_suffix_handlers.push(handler);
-        // Sort by priority (highest first)
-        self.number_suffix_handlers
-            .sort_by_key(|handler| Reverse(handler.priority()));
+    /// Creates a configuration with every lint enabled
+    pub fn all() -> Self {
+        LintCode::ALL
+            .into_iter()
+            .fold(Self::new(), |config, code| config.with_lint(code, true))
     }
 
-    /// Adds a string post-processor
-    pub fn add_string_processor(&mut self, processor: Box<dyn StringPostProcessor>) {
-        self.string_processors.push(processor);
-        // Sort by priority (highest first)
-        self.string_processors
-            .sort_by_key(|processor| Reverse(processor.priority()));
+    /// Enables or disables a single lint
+    pub fn with_lint(mut self, code: LintCode, enabled: bool) -> Self {
+        *self.flag_mut(code) = enabled;
+        self
     }
 
-    /// Adds a validation hook
-    pub fn add_validation_hook(&mut self, hook: Box<dyn ValidationHook>) {
-        self.validation_hooks.push(hook);
-        // Sort by priority (highest first)
-        self.validation_hooks
-            .sort_by_key(|hook| Reverse(hook.priority()));
+    /// Returns true if the lint is enabled
+    pub fn is_enabled(&self, code: LintCode) -> bool {
+        match code {
+            LintCode::MixedSeparators => self.mixed_separators,
+            LintCode::ShadowedKey => self.shadowed_keys,
+            LintCode::EmptyObject => self.empty_objects,
+            LintCode::TypedString => self.typed_strings,
+        }
     }
 
-    /// Tries to parse a number suffix using registered handlers
-    pub fn parse_number_suffix(&self, suffix: &str) -> Option<f64> {
-        for handler in &self.number_suffix_handlers {
-            if let Some(multiplier) = handler.parse_suffix(suffix) {
-                return Some(multiplier);
-            }
+    /// Returns the flag controlling a lint
+    fn flag_mut(&mut self, code: LintCode) -> &mut bool {
+        match code {
+            LintCode::MixedSeparators => &mut self.mixed_separators,
+            LintCode::ShadowedKey => &mut self.shadowed_keys,
+            LintCode::EmptyObject => &mut self.empty_objects,
+            LintCode::TypedString => &mut self.typed_strings,
         }
-        None
     }
+}
 
-    /// Processes a string using registered processors
-    pub fn process_string(
-        &self,
-        value: &str,
-        context: &VariableContext,
-    ) -> Result<String, ParseError> {
-        if self.string_processors.is_empty() {
-            return Ok(value.to_string());
+/// UCL value types
+///
+/// Dropping, cloning and comparing values walk nested objects and arrays
+/// with a worklist rather than recursion, so they work at any depth. Because
+/// of the `Drop` impl, the contents of a value cannot be moved out with a
+/// pattern; take them with `std::mem::take` on a binding such as
+/// `UclValue::String(text)` matched through `&mut`.
+///
+/// Values carry no metadata. The origin and merge priority of each entry
+/// are kept beside the value by `TrackedValue`, from `UclParser::parse_tracked`,
+/// so equality and serialization only see the data.
+#[derive(Debug, Default)]
+pub enum UclValue {
+    String(String),
+    Integer(i64),
+    /// Compares as `f64` does, so `-0.0 == 0.0`, but is written with the
+    /// shortest digits that round-trip and keeps the sign of zero
+    Float(f64),
+    /// Decimal number kept as written, produced under `DecimalMode::PreserveString`
+    Decimal(String),
+    /// Duration in seconds, produced by time suffixes such as `30s` or `5min`
+    Time(f64),
+    Boolean(bool),
+    #[default]
+    Null,
+    Object(UclObject),
+    /// Arrays use Box<SmallVec> to avoid infinite size recursion
+    /// SmallVec stores ≤4 elements inline without heap allocation
+    Array(Box<UclArray>),
+}
+
+impl Drop for UclValue {
+    fn drop(&mut self) {
+        let mut pending = Vec::new();
+        self.take_nested_containers(&mut pending);
+        while let Some(mut value) = pending.pop() {
+            // Emptied of containers, `value` drops without recursing
+            value.take_nested_containers(&mut pending);
         }
-        let mut result = value.to_string();
-        for processor in &self.string_processors {
-            result = processor.process_string(&result, context)?;
+    }
+}
+
+/// An object or array being cloned, with the index of its next element
+enum CloneFrame<'a> {
+    Object {
+        source: &'a UclObject,
+        next: usize,
+        clone: UclObject,
+    },
+    Array {
+        source: &'a UclArray,
+        next: usize,
+        clone: Box<UclArray>,
+    },
+}
+
+impl<'a> CloneFrame<'a> {
+    /// Starts cloning `value`, or returns `None` for a scalar
+    fn new(value: &'a UclValue) -> Option<Self> {
+        match value {
+            UclValue::Object(source) => Some(CloneFrame::Object {
+                source,
+                next: 0,
+                clone: UclObject::with_capacity(source.len()),
+            }),
+            UclValue::Array(source) => Some(CloneFrame::Array {
+                source,
+                next: 0,
+                clone: Box::new(UclArray::with_capacity(source.len())),
+            }),
+            _ => None,
         }
-        Ok(result)
     }
 
-    /// Validates a value using registered hooks
-    pub fn validate_value(
-        &self,
-        value: &UclValue,
-        context: &VariableContext,
-    ) -> Result<UclValue, ParseError> {
-        let mut result = value.clone();
-        for hook in &self.validation_hooks {
-            if let Some(modified_value) = hook.validate_value(&result, context)? {
-                result = modified_value;
+    /// Returns the next element to clone
+    fn next_element(&mut self) -> Option<&'a UclValue> {
+        match self {
+            CloneFrame::Object { source, next, .. } => {
+                let (_, value) = source.get_index(*next)?;
+                *next += 1;
+                Some(value)
+            }
+            CloneFrame::Array { source, next, .. } => {
+                let value = source.get(*next)?;
+                *next += 1;
+                Some(value)
             }
         }
-        Ok(result)
     }
 
-    /// Validates a key using registered hooks
-    pub fn validate_key(&self, key: &str, context: &VariableContext) -> Result<String, ParseError> {
-        let mut result = key.to_string();
-        for hook in &self.validation_hooks {
-            if let Some(modified_key) = hook.validate_key(&result, context)? {
-                result = modified_key;
+    /// Adds the clone of the element last returned by `next_element`
+    fn push(&mut self, value: UclValue) {
+        match self {
+            CloneFrame::Object {
+                source,
+                next,
+                clone,
+            } => {
+                // `next_element` returned the entry just before `next`
+                #[allow(clippy::expect_used)]
+                let (key, _) = source.get_index(*next - 1).expect("element was returned");
+                clone.insert(key.clone(), value);
             }
+            CloneFrame::Array { clone, .. } => clone.push(value),
         }
-        Ok(result)
     }
-}
-
-/// Plugin system for extensible parsing
-/// Trait for UCL parser plugins
-pub trait UclPlugin {
-    /// Returns the name of the plugin
-    fn name(&self) -> &str;
 
-    /// Returns the version of the plugin
-    fn version(&self) -> &str {
-        "1.0.0"
+    fn finish(self) -> UclValue {
+        match self {
+            CloneFrame::Object { clone, .. } => UclValue::Object(clone),
+            CloneFrame::Array { clone, .. } => UclValue::Array(clone),
+        }
     }
+}
 
-    /// Returns a description of what the plugin does
-    fn description(&self) -> &str {
-        "UCL parser plugin"
+impl Clone for UclValue {
+    fn clone(&self) -> Self {
+        let Some(root) = CloneFrame::new(self) else {
+            return self.clone_scalar();
+        };
+        let mut frame = root;
+        let mut parents = Vec::new();
+        loop {
+            match frame.next_element() {
+                Some(element) => match CloneFrame::new(element) {
+                    Some(nested) => parents.push(std::mem::replace(&mut frame, nested)),
+                    None => frame.push(element.clone_scalar()),
+                },
+                None => {
+                    let value = frame.finish();
+                    match parents.pop() {
+                        Some(parent) => {
+                            frame = parent;
+                            frame.push(value);
+                        }
+                        None => return value,
+                    }
+                }
+            }
+        }
     }
+}
 
-    /// Returns the priority of the plugin (higher priority plugins are loaded first)
-    fn priority(&self) -> u32 {
-        0
+/// Objects are equal when they hold the same entries, in any order
+impl PartialEq for UclValue {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some(pair) = pending.pop() {
+            match pair {
+                (UclValue::Object(left), UclValue::Object(right)) => {
+                    if left.len() != right.len() {
+                        return false;
+                    }
+                    for (key, value) in left {
+                        match right.get(key.as_str()) {
+                            Some(other) => pending.push((value, other)),
+                            None => return false,
+                        }
+                    }
+                }
+                (UclValue::Array(left), UclValue::Array(right)) => {
+                    if left.len() != right.len() {
+                        return false;
+                    }
+                    pending.extend(left.iter().zip(right.iter()));
+                }
+                (left, right) => {
+                    if !left.scalar_eq(right) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
     }
+}
 
-    /// Called when the plugin is loaded
-    fn on_load(&mut self) -> Result<(), ParseError> {
-        Ok(())
+impl UclValue {
+    /// Moves the objects and arrays directly inside this value to `pending`
+    fn take_nested_containers(&mut self, pending: &mut Vec<UclValue>) {
+        let elements: &mut dyn Iterator<Item = &mut UclValue> = match self {
+            UclValue::Object(object) => &mut object.values_mut(),
+            UclValue::Array(array) => &mut array.iter_mut(),
+            _ => return,
+        };
+        for element in elements {
+            if matches!(element, UclValue::Object(_) | UclValue::Array(_)) {
+                pending.push(std::mem::take(element));
+            }
+        }
     }
 
-    /// Called when the plugin is unloaded
-    fn on_unload(&mut self) -> Result<(), ParseError> {
-        Ok(())
+    /// Clones a value that is not an object or array
+    fn clone_scalar(&self) -> UclValue {
+        match self {
+            UclValue::String(text) => UclValue::String(text.clone()),
+            UclValue::Integer(value) => UclValue::Integer(*value),
+            UclValue::Float(value) => UclValue::Float(*value),
+            UclValue::Decimal(text) => UclValue::Decimal(text.clone()),
+            UclValue::Time(value) => UclValue::Time(*value),
+            UclValue::Boolean(value) => UclValue::Boolean(*value),
+            UclValue::Null => UclValue::Null,
+            UclValue::Object(_) | UclValue::Array(_) => {
+                unreachable!("containers are cloned by frames")
+            }
+        }
     }
 
-    /// Configures the plugin with the given configuration
-    fn configure(&mut self, _config: &PluginConfig) -> Result<(), ParseError> {
-        Ok(())
+    /// Compares two values that are not both objects or both arrays
+    fn scalar_eq(&self, other: &UclValue) -> bool {
+        match (self, other) {
+            (UclValue::String(left), UclValue::String(right)) => left == right,
+            (UclValue::Integer(left), UclValue::Integer(right)) => left == right,
+            (UclValue::Float(left), UclValue::Float(right)) => left == right,
+            (UclValue::Decimal(left), UclValue::Decimal(right)) => left == right,
+            (UclValue::Time(left), UclValue::Time(right)) => left == right,
+            (UclValue::Boolean(left), UclValue::Boolean(right)) => left == right,
+            (UclValue::Null, UclValue::Null) => true,
+            _ => false,
+        }
     }
 
-    /// Returns the number suffix handlers provided by this plugin
-    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
-        Vec::new()
+    /// Returns true if the value is an object
+    pub fn is_object(&self) -> bool {
+        matches!(self, UclValue::Object(_))
     }
 
-    /// Returns the string post-processors provided by this plugin
-    fn string_processors(&self) -> Vec<Box<dyn StringPostProcessor>> {
-        Vec::new()
+    /// Returns true if the value is an array
+    pub fn is_array(&self) -> bool {
+        matches!(self, UclValue::Array(_))
     }
 
-    /// Returns the validation hooks provided by this plugin
-    fn validation_hooks(&self) -> Vec<Box<dyn ValidationHook>> {
-        Vec::new()
+    /// Returns true if the value is a string
+    pub fn is_string(&self) -> bool {
+        matches!(self, UclValue::String(_))
     }
 
-    /// Returns the variable handlers provided by this plugin
-    fn variable_handlers(&self) -> Vec<Box<dyn VariableHandler>> {
-        Vec::new()
+    /// Returns a reference to the object if this is an Object variant
+    pub fn as_object(&self) -> Option<&UclObject> {
+        if let UclValue::Object(obj) = self {
+            Some(obj)
+        } else {
+            None
+        }
     }
-}
-
-/// Configuration for plugins
-#[derive(Debug, Clone)]
-pub struct PluginConfig {
-    /// Plugin-specific configuration as key-value pairs
-    pub settings: HashMap<String, String>,
-    /// Whether the plugin is enabled
-    pub enabled: bool,
-    /// Plugin priority override
-    pub priority_override: Option<u32>,
-}
 
-impl PluginConfig {
-    /// Creates a new plugin configuration
-    pub fn new() -> Self {
-        Self {
-            settings: HashMap::new(),
-            enabled: true,
-            priority_override: None,
+    /// Returns a reference to the array if this is an Array variant
+    pub fn as_array(&self) -> Option<&UclArray> {
+        if let UclValue::Array(arr) = self {
+            Some(arr)
+        } else {
+            None
         }
     }
 
-    /// Creates a configuration with settings
-    pub fn with_settings(settings: HashMap<String, String>) -> Self {
-        Self {
-            settings,
-            enabled: true,
-            priority_override: None,
+    /// Returns a reference to the string if this is a String variant
+    pub fn as_str(&self) -> Option<&str> {
+        if let UclValue::String(s) = self {
+            Some(s.as_str())
+        } else {
+            None
         }
     }
 
-    /// Sets a configuration value
-    pub fn set(&mut self, key: String, value: String) {
-        self.settings.insert(key, value);
+    /// Returns the integer value if this is an Integer variant
+    pub fn as_integer(&self) -> Option<i64> {
+        if let UclValue::Integer(i) = self {
+            Some(*i)
+        } else {
+            None
+        }
     }
 
-    /// Gets a configuration value
-    pub fn get(&self, key: &str) -> Option<&String> {
-        self.settings.get(key)
+    /// Returns the float value if this is a Float variant
+    pub fn as_float(&self) -> Option<f64> {
+        if let UclValue::Float(f) = self {
+            Some(*f)
+        } else {
+            None
+        }
     }
 
-    /// Gets a configuration value with a default
-    pub fn get_or(&self, key: &str, default: &str) -> String {
-        self.settings
-            .get(key)
-            .cloned()
-            .unwrap_or_else(|| default.to_string())
+    /// Returns the text of the number if this is a Decimal variant
+    pub fn as_decimal(&self) -> Option<&str> {
+        if let UclValue::Decimal(text) = self {
+            Some(text.as_str())
+        } else {
+            None
+        }
     }
 
-    /// Enables or disables the plugin
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
+    /// Returns the time value in seconds if this is a Time variant
+    pub fn as_time(&self) -> Option<f64> {
+        if let UclValue::Time(t) = self {
+            Some(*t)
+        } else {
+            None
+        }
     }
 
-    /// Sets the priority override
-    pub fn set_priority_override(&mut self, priority: Option<u32>) {
-        self.priority_override = priority;
+    /// Returns the time value as a `Duration` if this is a non-negative Time variant
+    pub fn as_duration(&self) -> Option<std::time::Duration> {
+        self.as_time()
+            .and_then(|seconds| std::time::Duration::try_from_secs_f64(seconds).ok())
     }
-}
 
-impl Default for PluginConfig {
-    fn default() -> Self {
-        Self::new()
+    /// Returns true if the value is a time value
+    pub fn is_time(&self) -> bool {
+        matches!(self, UclValue::Time(_))
     }
-}
-
-/// Plugin registry for managing loaded plugins
-pub struct PluginRegistry {
-    /// Loaded plugins
-    plugins: Vec<Box<dyn UclPlugin>>,
-    /// Plugin configurations
-    configs: HashMap<String, PluginConfig>,
-    /// Whether the registry is initialized
-    initialized: bool,
-}
 
-impl PluginRegistry {
-    /// Creates a new plugin registry
-    pub fn new() -> Self {
-        Self {
-            plugins: Vec::new(),
-            configs: HashMap::new(),
-            initialized: false,
+    /// Returns the boolean value if this is a Boolean variant
+    pub fn as_bool(&self) -> Option<bool> {
+        if let UclValue::Boolean(b) = self {
+            Some(*b)
+        } else {
+            None
         }
     }
 
-    /// Registers a plugin
-    pub fn register_plugin(&mut self, mut plugin: Box<dyn UclPlugin>) -> Result<(), ParseError> {
-        let name = plugin.name().to_string();
+    /// Returns true if this is a Null variant
+    pub fn is_null(&self) -> bool {
+        matches!(self, UclValue::Null)
+    }
 
-        // Configure the plugin if we have a configuration for it
-        if let Some(config) = self.configs.get(&name) {
-            plugin.configure(config)?;
+    /// Recursively sorts object keys and normalizes floats (`-0.0` to `0.0`, one NaN)
+    ///
+    /// Canonical values always render to the same text, which makes the output
+    /// suitable for hashing.
+    pub fn canonicalize(&mut self) {
+        match self {
+            UclValue::Float(value) | UclValue::Time(value) => {
+                if value.is_nan() {
+                    *value = f64::NAN;
+                } else if *value == 0.0 {
+                    *value = 0.0;
+                }
+            }
+            UclValue::Object(obj) => {
+                obj.sort_unstable_keys();
+                obj.values_mut().for_each(UclValue::canonicalize);
+            }
+            UclValue::Array(arr) => arr.iter_mut().for_each(UclValue::canonicalize),
+            _ => {}
         }
-
-        // Load the plugin
-        plugin.on_load()?;
-
-        self.plugins.push(plugin);
-
-        // Sort plugins by priority (highest first)
-        self.plugins.sort_by(|a, b| {
-            let a_priority = self
-                .configs
-                .get(a.name())
-                .and_then(|c| c.priority_override)
-                .unwrap_or_else(|| a.priority());
-            let b_priority = self
-                .configs
-                .get(b.name())
-                .and_then(|c| c.priority_override)
-                .unwrap_or_else(|| b.priority());
-            b_priority.cmp(&a_priority)
-        });
-
-        Ok(())
     }
 
-    /// Unregisters a plugin by name
-    pub fn unregister_plugin(&mut self, name: &str) -> Result<(), ParseError> {
-        if let Some(pos) = self.plugins.iter().position(|p| p.name() == name) {
-            let mut plugin = self.plugins.remove(pos);
-            plugin.on_unload()?;
+    /// Deep merges another value into this one
+    ///
+    /// Objects are merged key by key; any other value is replaced by `other`.
+    /// To keep origins and let `.priority` decide, merge `TrackedValue`s instead.
+    pub fn merge_from(&mut self, mut other: UclValue) {
+        match (&mut *self, &mut other) {
+            (UclValue::Object(target), UclValue::Object(source)) => {
+                for (key, value) in std::mem::take(source) {
+                    match target.get_mut(&key) {
+                        Some(existing) => existing.merge_from(value),
+                        None => {
+                            target.insert(key, value);
+                        }
+                    }
+                }
+            }
+            _ => *self = other,
         }
-        Ok(())
     }
 
-    /// Sets the configuration for a plugin
-    pub fn set_plugin_config(&mut self, name: String, config: PluginConfig) {
-        self.configs.insert(name, config);
+    /// Looks up a value by dotted path, indexing arrays with numeric segments
+    ///
+    /// Arrays may also be indexed as `servers[0]`, and a key containing `.`
+    /// may be quoted, as in `hosts."example.com"`; see `KeyPath::parse`. The
+    /// empty path is the value itself.
+    pub fn get_path(&self, path: &str) -> Option<&UclValue> {
+        self.get_key_path(&crate::walk::KeyPath::parse(path)?)
+    }
+
+    /// Looks up a value by path segments
+    pub(crate) fn get_segments<I>(&self, segments: I) -> Option<&UclValue>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut current = self;
+        for segment in segments {
+            let segment = segment.as_ref();
+            current = match current {
+                UclValue::Object(obj) => obj.get(segment)?,
+                UclValue::Array(arr) => arr.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Returns a human-readable name for the value's type
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            UclValue::String(_) => "string",
+            UclValue::Integer(_) => "integer",
+            UclValue::Float(_) => "float",
+            UclValue::Decimal(_) => "decimal",
+            UclValue::Time(_) => "time",
+            UclValue::Boolean(_) => "boolean",
+            UclValue::Null => "null",
+            UclValue::Object(_) => "object",
+            UclValue::Array(_) => "array",
+        }
     }
+}
 
-    /// Gets the configuration for a plugin
-    pub fn get_plugin_config(&self, name: &str) -> Option<&PluginConfig> {
-        self.configs.get(name)
+/// Writes a string as a double-quoted JSON-style literal
+pub(crate) fn write_quoted_str(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for ch in s.chars() {
+        match ch {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
     }
+    f.write_str("\"")
+}
 
-    /// Lists all registered plugins
-    pub fn list_plugins(&self) -> Vec<&dyn UclPlugin> {
-        self.plugins.iter().map(|p| p.as_ref()).collect()
+/// Magnitudes from which floats are written with an exponent
+const PLAIN_FLOAT_RANGE: Range<f64> = 1e-5..1e16;
+
+/// Writes a float so that it is read back as the same float by the lexer
+///
+/// The digits are the shortest that round-trip, so parsing the text gives the
+/// same bits back, including the sign of `-0.0`. Integral values keep a `.0`
+/// so that they stay floats.
+pub(crate) fn write_float(f: &mut impl fmt::Write, value: f64) -> fmt::Result {
+    if value.is_nan() {
+        f.write_str("nan")
+    } else if value.is_infinite() {
+        f.write_str(if value > 0.0 { "inf" } else { "-inf" })
+    } else if value.fract() == 0.0 && value.abs() < PLAIN_FLOAT_RANGE.end {
+        write!(f, "{:.1}", value)
+    } else {
+        write_float_digits(f, value)
     }
+}
 
-    /// Gets a plugin by name
-    pub fn get_plugin(&self, name: &str) -> Option<&dyn UclPlugin> {
-        self.plugins
-            .iter()
-            .find(|p| p.name() == name)
-            .map(|p| p.as_ref())
+/// Writes the shortest round-trip digits of a finite float
+///
+/// Magnitudes outside `PLAIN_FLOAT_RANGE` use an exponent, as in `1e300` or
+/// `5e-324`, instead of hundreds of zeros.
+fn write_float_digits(f: &mut impl fmt::Write, value: f64) -> fmt::Result {
+    if value == 0.0 || PLAIN_FLOAT_RANGE.contains(&value.abs()) {
+        write!(f, "{}", value)
+    } else {
+        write!(f, "{:e}", value)
     }
+}
 
-    /// Initializes the registry and applies all plugin hooks
-    pub fn initialize(&mut self) -> Result<ParsingHooks, ParseError> {
-        if self.initialized {
-            return Err(ParseError::InvalidObject {
-                message: "Plugin registry already initialized".to_string(),
-                position: Position::new(),
-            });
-        }
+/// How time values are written back to UCL text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimeSuffixStyle {
+    /// Largest suffix that yields an integral amount (`90.0` becomes `90s`)
+    #[default]
+    Integral,
+    /// Largest suffix that yields at most two decimals (`90.0` becomes `1.5min`)
+    ShortDecimal,
+}
 
-        let mut hooks = ParsingHooks::new();
+/// Time suffixes recognized by the lexer, largest first
+const TIME_SUFFIXES: [(&str, f64); 7] = [
+    ("y", 31_536_000.0),
+    ("w", 604_800.0),
+    ("d", 86_400.0),
+    ("h", 3_600.0),
+    ("min", 60.0),
+    ("s", 1.0),
+    ("ms", 0.001),
+];
+
+/// Formats a duration in seconds with the most readable time suffix
+pub fn format_time(seconds: f64, style: TimeSuffixStyle) -> String {
+    if !seconds.is_finite() {
+        return format!(
+            "{}s",
+            if seconds.is_nan() {
+                "nan"
+            } else if seconds > 0.0 {
+                "inf"
+            } else {
+                "-inf"
+            }
+        );
+    }
+    if seconds == 0.0 {
+        return "0s".to_string();
+    }
 
-        // Collect hooks from all enabled plugins
-        for plugin in &self.plugins {
-            let config = self.configs.get(plugin.name());
-            let enabled = config.map(|c| c.enabled).unwrap_or(true);
+    let decimals = match style {
+        TimeSuffixStyle::Integral => 0,
+        TimeSuffixStyle::ShortDecimal => 2,
+    };
+    let scale = 10f64.powi(decimals);
+    for (suffix, multiplier) in TIME_SUFFIXES {
+        let amount = seconds / multiplier;
+        if amount.abs() < 1.0 {
+            continue;
+        }
+        let scaled = amount * scale;
+        if (scaled - scaled.round()).abs() <= 1e-9 * scaled.abs().max(1.0) {
+            let mut text = String::new();
+            // Writing to a `String` cannot fail
+            let _ = write_float_digits(&mut text, scaled.round() / scale);
+            text.push_str(suffix);
+            return text;
+        }
+    }
 
-            if enabled {
-                // Add number suffix handlers
-                for handler in plugin.number_suffix_handlers() {
-                    hooks.add_number_suffix_handler(handler);
-                }
+    let mut text = String::new();
+    let _ = write_float_digits(&mut text, seconds);
+    text.push('s');
+    text
+}
 
-                // Add string processors
-                for processor in plugin.string_processors() {
-                    hooks.add_string_processor(processor);
+/// Renders the value as compact, single-line JSON-compatible UCL
+impl fmt::Display for UclValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UclValue::String(s) => write_quoted_str(f, s),
+            UclValue::Integer(i) => write!(f, "{}", i),
+            UclValue::Float(value) => write_float(f, *value),
+            UclValue::Decimal(text) => f.write_str(text),
+            UclValue::Time(seconds) => {
+                f.write_str(&format_time(*seconds, TimeSuffixStyle::default()))
+            }
+            UclValue::Boolean(b) => write!(f, "{}", b),
+            UclValue::Null => f.write_str("null"),
+            UclValue::Object(obj) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in obj.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_quoted_str(f, key)?;
+                    write!(f, ": {}", value)?;
                 }
-
-                // Add validation hooks
-                for hook in plugin.validation_hooks() {
-                    hooks.add_validation_hook(hook);
+                f.write_str("}")
+            }
+            UclValue::Array(arr) => {
+                f.write_str("[")?;
+                for (i, value) in arr.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
                 }
+                f.write_str("]")
             }
         }
-
-        self.initialized = true;
-        Ok(hooks)
     }
+}
 
-    /// Resets the registry (unloads all plugins)
-    pub fn reset(&mut self) -> Result<(), ParseError> {
-        for plugin in &mut self.plugins {
-            plugin.on_unload()?;
+/// Parses either a standalone scalar (`42`, `"text"`, `true`) or a whole UCL document
+impl FromStr for UclValue {
+    type Err = UclError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scalar_parser = UclParser::new(s);
+        if scalar_parser.skip_whitespace_and_comments().is_ok()
+            && matches!(
+                scalar_parser.current_token(),
+                Some(
+                    Token::String { .. }
+                        | Token::Integer(_)
+                        | Token::Float(_)
+                        | Token::Time(_)
+                        | Token::Boolean(_)
+                        | Token::Null
+                )
+            )
+            && let Ok(value) = scalar_parser.parse_value()
+            && scalar_parser.skip_whitespace_and_comments().is_ok()
+            && matches!(scalar_parser.current_token(), Some(Token::Eof))
+        {
+            return Ok(value);
         }
-        self.plugins.clear();
-        self.initialized = false;
-        Ok(())
-    }
 
-    /// Returns whether the registry is initialized
-    pub fn is_initialized(&self) -> bool {
-        self.initialized
+        Ok(UclParser::new(s).parse_document()?)
     }
+}
 
-    /// Gets all variable handlers from enabled plugins
-    pub fn get_variable_handlers(&self) -> Vec<Box<dyn VariableHandler>> {
-        let mut handlers = Vec::new();
-
-        for plugin in &self.plugins {
-            let config = self.configs.get(plugin.name());
-            let enabled = config.map(|c| c.enabled).unwrap_or(true);
-
-            if enabled {
-                handlers.extend(plugin.variable_handlers());
+macro_rules! impl_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for UclValue {
+                fn from(value: $ty) -> Self {
+                    UclValue::Integer(i64::from(value))
+                }
             }
-        }
+        )*
+    };
+}
 
-        handlers
+impl_from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+impl From<f32> for UclValue {
+    fn from(value: f32) -> Self {
+        UclValue::Float(f64::from(value))
     }
 }
 
-impl Default for PluginRegistry {
-    fn default() -> Self {
-        Self::new()
+impl From<f64> for UclValue {
+    fn from(value: f64) -> Self {
+        UclValue::Float(value)
     }
 }
 
-/// Builder for creating a parser with plugins
-pub struct UclParserBuilder<'a> {
-    input: &'a str,
-    lexer_config: Option<LexerConfig>,
-    parser_config: Option<ParserConfig>,
-    variable_handler: Option<Box<dyn VariableHandler>>,
-    plugin_registry: Option<PluginRegistry>,
+impl From<bool> for UclValue {
+    fn from(value: bool) -> Self {
+        UclValue::Boolean(value)
+    }
 }
 
-impl<'a> UclParserBuilder<'a> {
-    /// Creates a new parser builder
-    pub fn new(input: &'a str) -> Self {
-        Self {
-            input,
-            lexer_config: None,
-            parser_config: None,
-            variable_handler: None,
-            plugin_registry: None,
-        }
+impl From<&str> for UclValue {
+    fn from(value: &str) -> Self {
+        UclValue::String(value.to_string())
     }
+}
 
-    /// Sets the lexer configuration
-    pub fn with_lexer_config(mut self, config: LexerConfig) -> Self {
-        self.lexer_config = Some(config);
-        self
+impl From<String> for UclValue {
+    fn from(value: String) -> Self {
+        UclValue::String(value)
     }
+}
 
-    /// Sets the parser configuration
-    pub fn with_parser_config(mut self, config: ParserConfig) -> Self {
-        self.parser_config = Some(config);
-        self
+impl<T: Into<UclValue>> From<Option<T>> for UclValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(UclValue::Null, Into::into)
     }
+}
 
-    /// Sets the variable handler
-    pub fn with_variable_handler(mut self, handler: Box<dyn VariableHandler>) -> Self {
-        self.variable_handler = Some(handler);
-        self
+impl<T: Into<UclValue>> From<Vec<T>> for UclValue {
+    fn from(values: Vec<T>) -> Self {
+        UclValue::Array(Box::new(values.into_iter().map(Into::into).collect()))
     }
+}
 
-    /// Sets the plugin registry
-    pub fn with_plugin_registry(mut self, registry: PluginRegistry) -> Self {
-        self.plugin_registry = Some(registry);
-        self
+impl From<UclArray> for UclValue {
+    fn from(array: UclArray) -> Self {
+        UclValue::Array(Box::new(array))
     }
+}
 
-    /// Adds a plugin to the registry
-    pub fn with_plugin(mut self, plugin: Box<dyn UclPlugin>) -> Result<Self, ParseError> {
-        if self.plugin_registry.is_none() {
-            self.plugin_registry = Some(PluginRegistry::new());
-        }
-
-        if let Some(ref mut registry) = self.plugin_registry {
-            registry.register_plugin(plugin)?;
-        }
-
-        Ok(self)
+impl From<UclObject> for UclValue {
+    fn from(object: UclObject) -> Self {
+        UclValue::Object(object)
     }
+}
 
-    /// Builds the parser
-    pub fn build(self) -> Result<UclParser<'a>, ParseError> {
-        let lexer_config = self.lexer_config.unwrap_or_default();
-        let parser_config = self.parser_config.unwrap_or_default();
-
-        let mut parser = if let Some(lexer_config) = Some(lexer_config) {
-            UclParser::with_lexer_config(self.input, lexer_config).with_config(parser_config)
-        } else {
-            UclParser::new(self.input).with_config(parser_config)
-        };
-
-        // Set up variable handler (potentially from plugins)
-        if let Some(mut registry) = self.plugin_registry {
-            // Initialize the registry and get parsing hooks
-            let hooks = registry.initialize()?;
-            parser.set_parsing_hooks(hooks);
-
-            // Set up chained variable handler if we have plugin handlers
-            let plugin_handlers = registry.get_variable_handlers();
-            if !plugin_handlers.is_empty() {
-                let mut chained = ChainedVariableHandler::new();
+/// Keys are sorted, since `HashMap` iteration order differs between runs
+impl<V: Into<UclValue>> From<HashMap<String, V>> for UclValue {
+    fn from(map: HashMap<String, V>) -> Self {
+        let mut object: UclObject = map.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+        object.sort_unstable_keys();
+        UclValue::Object(object)
+    }
+}
 
-                // Add plugin handlers first
-                for handler in plugin_handlers {
-                    chained.add_handler(handler);
+macro_rules! impl_try_from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl TryFrom<UclValue> for $ty {
+                type Error = ConversionError;
+
+                fn try_from(value: UclValue) -> Result<Self, Self::Error> {
+                    match value {
+                        UclValue::Integer(i) => {
+                            <$ty>::try_from(i).map_err(|_| ConversionError::OutOfRange {
+                                value: i,
+                                target: stringify!($ty),
+                            })
+                        }
+                        other => Err(ConversionError::TypeMismatch {
+                            expected: "integer",
+                            found: other.type_name(),
+                        }),
+                    }
                 }
+            }
+        )*
+    };
+}
 
-                // Add user-provided handler last (highest priority)
-                if let Some(user_handler) = self.variable_handler {
-                    chained.add_handler(user_handler);
-                }
+impl_try_from_integer!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
 
-                parser.variable_handler = Some(Box::new(chained));
-            } else if let Some(handler) = self.variable_handler {
-                parser.variable_handler = Some(handler);
+impl TryFrom<UclValue> for f64 {
+    type Error = ConversionError;
+
+    fn try_from(value: UclValue) -> Result<Self, Self::Error> {
+        match value {
+            UclValue::Float(f) | UclValue::Time(f) => Ok(f),
+            UclValue::Integer(i) => Ok(i as f64),
+            // The nearest float, as exact decimals are only kept by `DecimalMode::PreserveString`
+            UclValue::Decimal(ref text) => {
+                text.parse().map_err(|_| ConversionError::TypeMismatch {
+                    expected: "float",
+                    found: "decimal",
+                })
             }
-        } else if let Some(handler) = self.variable_handler {
-            parser.variable_handler = Some(handler);
+            other => Err(ConversionError::TypeMismatch {
+                expected: "float",
+                found: other.type_name(),
+            }),
         }
-
-        Ok(parser)
     }
 }
 
-/// Built-in example implementations
-/// Example number suffix handler for custom units
-pub struct CustomUnitSuffixHandler {
-    units: HashMap<String, f64>,
-    priority: u32,
+impl TryFrom<UclValue> for bool {
+    type Error = ConversionError;
+
+    fn try_from(value: UclValue) -> Result<Self, Self::Error> {
+        match value {
+            UclValue::Boolean(b) => Ok(b),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "boolean",
+                found: other.type_name(),
+            }),
+        }
+    }
 }
 
-impl CustomUnitSuffixHandler {
-    /// Creates a new custom unit suffix handler
-    pub fn new() -> Self {
-        let mut units = HashMap::new();
-        // Example: custom units for data processing
-        units.insert("px".to_string(), 1.0); // pixels
-        units.insert("pt".to_string(), 1.333); // points to pixels
-        units.insert("em".to_string(), 16.0); // em to pixels (assuming 16px base)
-        units.insert("rem".to_string(), 16.0); // rem to pixels
+impl TryFrom<UclValue> for String {
+    type Error = ConversionError;
 
-        Self {
-            units,
-            priority: 100,
+    fn try_from(mut value: UclValue) -> Result<Self, Self::Error> {
+        match &mut value {
+            UclValue::String(s) => Ok(std::mem::take(s)),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "string",
+                found: other.type_name(),
+            }),
         }
     }
+}
 
-    /// Adds a custom unit
-    pub fn add_unit(&mut self, suffix: String, multiplier: f64) {
-        self.units.insert(suffix, multiplier);
+impl TryFrom<UclValue> for UclObject {
+    type Error = ConversionError;
+
+    fn try_from(mut value: UclValue) -> Result<Self, Self::Error> {
+        match &mut value {
+            UclValue::Object(obj) => Ok(std::mem::take(obj)),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "object",
+                found: other.type_name(),
+            }),
+        }
     }
+}
 
-    /// Sets the priority of this handler
-    pub fn with_priority(mut self, priority: u32) -> Self {
-        self.priority = priority;
-        self
+impl TryFrom<UclValue> for Vec<UclValue> {
+    type Error = ConversionError;
+
+    fn try_from(mut value: UclValue) -> Result<Self, Self::Error> {
+        match &mut value {
+            UclValue::Array(array) => Ok(std::mem::take(&mut **array).into_vec()),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "array",
+                found: other.type_name(),
+            }),
+        }
     }
 }
 
-impl Default for CustomUnitSuffixHandler {
-    fn default() -> Self {
-        Self::new()
+macro_rules! impl_partial_eq_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl PartialEq<$ty> for UclValue {
+                fn eq(&self, other: &$ty) -> bool {
+                    match self {
+                        UclValue::Integer(i) => i64::try_from(*other).is_ok_and(|o| *i == o),
+                        _ => false,
+                    }
+                }
+            }
+
+            impl PartialEq<UclValue> for $ty {
+                fn eq(&self, other: &UclValue) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+
+impl_partial_eq_integer!(i8, i16, i32, i64, u8, u16, u32, u64, usize);
+
+impl PartialEq<f64> for UclValue {
+    fn eq(&self, other: &f64) -> bool {
+        matches!(self, UclValue::Float(f) if f == other)
     }
 }
 
-impl NumberSuffixHandler for CustomUnitSuffixHandler {
-    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
-        self.units.get(suffix).copied()
+impl PartialEq<UclValue> for f64 {
+    fn eq(&self, other: &UclValue) -> bool {
+        other == self
     }
+}
 
-    fn priority(&self) -> u32 {
-        self.priority
+impl PartialEq<bool> for UclValue {
+    fn eq(&self, other: &bool) -> bool {
+        matches!(self, UclValue::Boolean(b) if b == other)
     }
+}
 
-    fn description(&self) -> &str {
-        "Custom unit suffix handler (px, pt, em, rem)"
+impl PartialEq<UclValue> for bool {
+    fn eq(&self, other: &UclValue) -> bool {
+        other == self
     }
 }
 
-/// Example string post-processor for path normalization
-pub struct PathNormalizationProcessor {
-    priority: u32,
+impl PartialEq<str> for UclValue {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == Some(other)
+    }
 }
 
-impl PathNormalizationProcessor {
-    /// Creates a new path normalization processor
-    pub fn new() -> Self {
-        Self { priority: 50 }
+impl PartialEq<&str> for UclValue {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == Some(*other)
     }
+}
 
-    /// Sets the priority of this processor
-    pub fn with_priority(mut self, priority: u32) -> Self {
-        self.priority = priority;
-        self
+impl PartialEq<String> for UclValue {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == Some(other.as_str())
     }
 }
 
-impl Default for PathNormalizationProcessor {
-    fn default() -> Self {
-        Self::new()
+impl PartialEq<UclValue> for &str {
+    fn eq(&self, other: &UclValue) -> bool {
+        other == self
     }
 }
 
-impl StringPostProcessor for PathNormalizationProcessor {
-    fn process_string(
-        &self,
-        value: &str,
-        _context: &VariableContext,
-    ) -> Result<String, ParseError> {
-        // Normalize path separators and resolve relative paths
-        let normalized = value
-            .replace('\\', "/") // Normalize to forward slashes
-            .replace("//", "/"); // Remove double slashes
+impl PartialEq<UclValue> for String {
+    fn eq(&self, other: &UclValue) -> bool {
+        other == self
+    }
+}
 
-        // Basic relative path resolution
-        let parts: Vec<&str> = normalized.split('/').collect();
-        let mut resolved = Vec::new();
+static NULL_VALUE: UclValue = UclValue::Null;
 
-        for part in parts {
-            match part {
-                "." | "" => continue, // Skip current directory and empty parts
-                ".." => {
-                    if !resolved.is_empty() && resolved.last() != Some(&"..") {
-                        resolved.pop();
-                    } else {
-                        resolved.push(part);
-                    }
-                }
-                _ => resolved.push(part),
-            }
-        }
+/// Looks up an object key, yielding `Null` for missing keys and non-objects
+impl std::ops::Index<&str> for UclValue {
+    type Output = UclValue;
 
-        Ok(resolved.join("/"))
+    fn index(&self, key: &str) -> &UclValue {
+        self.as_object()
+            .and_then(|obj| obj.get(key))
+            .unwrap_or(&NULL_VALUE)
     }
+}
 
-    fn priority(&self) -> u32 {
-        self.priority
-    }
+/// Looks up an array element, yielding `Null` when out of bounds or not an array
+impl std::ops::Index<usize> for UclValue {
+    type Output = UclValue;
 
-    fn description(&self) -> &str {
-        "Path normalization processor (resolves ./ and ../, normalizes separators)"
+    fn index(&self, index: usize) -> &UclValue {
+        self.as_array()
+            .and_then(|arr| arr.get(index))
+            .unwrap_or(&NULL_VALUE)
     }
 }
 
-/// Example validation hook for schema validation
-pub struct SchemaValidationHook {
-    required_keys: Vec<String>,
-    allowed_keys: Option<Vec<String>>,
-    priority: u32,
+/// Options controlling semantic comparison in `UclValue::equivalent`
+#[derive(Debug, Clone, Default)]
+pub struct EquivalenceOptions {
+    /// Compare objects as unordered maps instead of requiring the same key order
+    pub ignore_key_order: bool,
+    /// Treat integers and floats with the same numeric value as equal
+    pub numeric_equivalence: bool,
+    /// Treat two NaN floats as equal
+    pub nan_equal: bool,
+    /// Dotted key paths to skip; `*` matches any single key or array index
+    pub ignored_paths: Vec<String>,
 }
 
-impl SchemaValidationHook {
-    /// Creates a new schema validation hook
+impl EquivalenceOptions {
+    /// Creates strict comparison options
     pub fn new() -> Self {
-        Self {
-            required_keys: Vec::new(),
-            allowed_keys: None,
-            priority: 100,
-        }
+        Self::default()
     }
 
-    /// Adds a required key
-    pub fn require_key(mut self, key: String) -> Self {
-        self.required_keys.push(key);
+    /// Sets whether key order is ignored
+    pub fn with_ignore_key_order(mut self, ignore: bool) -> Self {
+        self.ignore_key_order = ignore;
         self
     }
 
-    /// Sets the allowed keys (if None, all keys are allowed)
-    pub fn allow_keys(mut self, keys: Vec<String>) -> Self {
-        self.allowed_keys = Some(keys);
+    /// Sets whether integers and floats compare by numeric value
+    pub fn with_numeric_equivalence(mut self, enabled: bool) -> Self {
+        self.numeric_equivalence = enabled;
         self
     }
 
-    /// Sets the priority of this hook
-    pub fn with_priority(mut self, priority: u32) -> Self {
-        self.priority = priority;
+    /// Sets whether NaN equals NaN
+    pub fn with_nan_equal(mut self, enabled: bool) -> Self {
+        self.nan_equal = enabled;
         self
     }
-}
 
-impl Default for SchemaValidationHook {
-    fn default() -> Self {
-        Self::new()
+    /// Adds a dotted key path (e.g. `server.port` or `servers.*.id`) to ignore
+    pub fn with_ignored_path(mut self, path: impl Into<String>) -> Self {
+        self.ignored_paths.push(path.into());
+        self
+    }
+
+    /// Returns true if the given path matches one of the ignored paths
+    fn is_ignored(&self, path: &[String]) -> bool {
+        self.ignored_paths.iter().any(|pattern| {
+            let segments: Vec<&str> = pattern.split('.').collect();
+            segments.len() == path.len()
+                && segments
+                    .iter()
+                    .zip(path)
+                    .all(|(segment, key)| *segment == "*" || segment == key)
+        })
     }
 }
 
-impl ValidationHook for SchemaValidationHook {
-    fn validate_value(
+impl UclValue {
+    /// Compares two values semantically according to the given options
+    pub fn equivalent(&self, other: &UclValue, options: &EquivalenceOptions) -> bool {
+        let mut path = Vec::new();
+        self.equivalent_at(other, options, &mut path)
+    }
+
+    fn equivalent_at(
         &self,
-        value: &UclValue,
-        context: &VariableContext,
-    ) -> Result<Option<UclValue>, ParseError> {
-        match value {
-            UclValue::Object(obj) => {
-                // Check required keys
-                for required_key in &self.required_keys {
-                    if !obj.contains_key(required_key) {
-                        return Err(ParseError::InvalidObject {
-                            message: format!("Missing required key '{}'", required_key),
-                            position: context.position,
-                        });
-                    }
+        other: &UclValue,
+        options: &EquivalenceOptions,
+        path: &mut Vec<String>,
+    ) -> bool {
+        match (self, other) {
+            (UclValue::Float(a), UclValue::Float(b)) | (UclValue::Time(a), UclValue::Time(b)) => {
+                a == b || (options.nan_equal && a.is_nan() && b.is_nan())
+            }
+            (UclValue::Integer(i), UclValue::Float(f))
+            | (UclValue::Float(f), UclValue::Integer(i))
+                if options.numeric_equivalence =>
+            {
+                *i as f64 == *f
+            }
+            (UclValue::Object(a), UclValue::Object(b)) => {
+                let visible = |obj: &UclObject, path: &mut Vec<String>| -> Vec<UclKey> {
+                    obj.keys()
+                        .filter(|key| {
+                            path.push(key.to_string());
+                            let ignored = options.is_ignored(path);
+                            path.pop();
+                            !ignored
+                        })
+                        .cloned()
+                        .collect()
+                };
+                let keys_a = visible(a, path);
+                let keys_b = visible(b, path);
+                if keys_a.len() != keys_b.len() {
+                    return false;
                 }
-
-                // Check allowed keys
-                if let Some(allowed) = &self.allowed_keys {
-                    for key in obj.keys() {
-                        if !allowed.contains(key) {
-                            return Err(ParseError::InvalidObject {
-                                message: format!("Key '{}' is not allowed", key),
-                                position: context.position,
-                            });
-                        }
-                    }
+                if !options.ignore_key_order && keys_a != keys_b {
+                    return false;
                 }
-
-                Ok(None) // No modification needed
+                keys_a.iter().all(|key| {
+                    let Some(value_b) = b.get(key) else {
+                        return false;
+                    };
+                    path.push(key.to_string());
+                    let equal = a[key].equivalent_at(value_b, options, path);
+                    path.pop();
+                    equal
+                })
             }
-            _ => Ok(None), // Only validate objects
+            (UclValue::Array(a), UclValue::Array(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b.iter()).enumerate().all(|(i, (x, y))| {
+                        path.push(i.to_string());
+                        let equal = options.is_ignored(path) || x.equivalent_at(y, options, path);
+                        path.pop();
+                        equal
+                    })
+            }
+            _ => self == other,
         }
     }
+}
 
-    fn validate_key(
-        &self,
-        key: &str,
-        context: &VariableContext,
-    ) -> Result<Option<String>, ParseError> {
-        if let Some(allowed) = &self.allowed_keys
-            && !allowed.contains(&key.to_string())
-        {
-            return Err(ParseError::InvalidObject {
-                message: format!("Key '{}' is not allowed", key),
-                position: context.position,
-            });
-        }
-        Ok(None) // No modification needed
+/// UCL object type (preserves insertion order)
+///
+/// Keys of up to `key::INLINE_CAPACITY` bytes are stored without a heap allocation.
+pub type UclObject = IndexMap<UclKey, UclValue>;
+
+/// UCL array type - uses SmallVec to avoid heap allocation for small arrays (≤4 elements)
+/// Most UCL arrays in practice have ≤4 elements (tags, options, etc.)
+pub type UclArray = SmallVec<[UclValue; 4]>;
+
+/// Typed extraction of array elements
+///
+/// `UclArray` is a `SmallVec`, so iteration, indexing, `push`, `insert`,
+/// `remove` and `collect()` come with it. These helpers read every element as
+/// one type and report the index of the first element that does not match.
+///
+/// ```rust
+/// use ucl_lexer::{UclArrayExt, ucl};
+///
+/// let value = ucl!({ "ports": [80, 443], "mixed": [1, "two"] });
+/// let ports = value["ports"].as_array().unwrap().as_vec_of_i64().unwrap();
+/// assert_eq!(ports, [80, 443]);
+///
+/// let err = value["mixed"].as_array().unwrap().as_vec_of_i64().unwrap_err();
+/// assert_eq!(err.to_string(), "element 1 is string, expected integer");
+/// ```
+pub trait UclArrayExt {
+    /// Returns every element as a string slice
+    fn as_vec_of_str(&self) -> Result<Vec<&str>, ConversionError>;
+
+    /// Returns every element as an integer
+    fn as_vec_of_i64(&self) -> Result<Vec<i64>, ConversionError>;
+
+    /// Returns every element as a float, accepting integers and times as well
+    fn as_vec_of_f64(&self) -> Result<Vec<f64>, ConversionError>;
+
+    /// Returns every element as a boolean
+    fn as_vec_of_bool(&self) -> Result<Vec<bool>, ConversionError>;
+}
+
+impl UclArrayExt for [UclValue] {
+    fn as_vec_of_str(&self) -> Result<Vec<&str>, ConversionError> {
+        extract_elements(self, "string", UclValue::as_str)
     }
 
-    fn priority(&self) -> u32 {
-        self.priority
+    fn as_vec_of_i64(&self) -> Result<Vec<i64>, ConversionError> {
+        extract_elements(self, "integer", UclValue::as_integer)
     }
 
-    fn description(&self) -> &str {
-        "Schema validation hook (checks required and allowed keys)"
+    fn as_vec_of_f64(&self) -> Result<Vec<f64>, ConversionError> {
+        extract_elements(self, "float", |value| match value {
+            UclValue::Float(f) | UclValue::Time(f) => Some(*f),
+            UclValue::Integer(i) => Some(*i as f64),
+            UclValue::Decimal(text) => text.parse().ok(),
+            _ => None,
+        })
+    }
+
+    fn as_vec_of_bool(&self) -> Result<Vec<bool>, ConversionError> {
+        extract_elements(self, "boolean", UclValue::as_bool)
     }
 }
 
-/// Example plugin implementations
-/// Example plugin for CSS-like units
-pub struct CssUnitsPlugin {
-    name: String,
-    version: String,
-    priority: u32,
+/// Applies `extract` to every element, failing on the first element it rejects
+fn extract_elements<'a, T>(
+    items: &'a [UclValue],
+    expected: &'static str,
+    extract: impl Fn(&'a UclValue) -> Option<T>,
+) -> Result<Vec<T>, ConversionError> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            extract(item).ok_or(ConversionError::ElementTypeMismatch {
+                index,
+                expected,
+                found: item.type_name(),
+            })
+        })
+        .collect()
 }
 
-impl CssUnitsPlugin {
-    /// Creates a new CSS units plugin
-    pub fn new() -> Self {
-        Self {
-            name: "css-units".to_string(),
-            version: "1.0.0".to_string(),
-            priority: 100,
-        }
+/// Emptying objects and arrays for reuse
+///
+/// Removing the elements keeps the container's allocation, so refilling it up
+/// to its old length does not allocate again. `UclParser::parse_into` does
+/// this for a whole tree.
+///
+/// ```rust
+/// use ucl_lexer::{UclContainerExt, UclObject, UclValue};
+///
+/// let mut object = UclObject::with_capacity(16);
+/// object.insert("a".into(), UclValue::from(1));
+/// object.clear_retaining_capacity();
+/// assert!(object.is_empty());
+/// assert!(object.capacity() >= 16);
+/// ```
+pub trait UclContainerExt {
+    /// Removes every element, keeping the allocated capacity
+    fn clear_retaining_capacity(&mut self);
+}
+
+impl UclContainerExt for UclObject {
+    fn clear_retaining_capacity(&mut self) {
+        self.clear();
     }
 }
 
-impl Default for CssUnitsPlugin {
-    fn default() -> Self {
-        Self::new()
+impl UclContainerExt for UclArray {
+    fn clear_retaining_capacity(&mut self) {
+        self.clear();
     }
 }
 
-impl UclPlugin for CssUnitsPlugin {
-    fn name(&self) -> &str {
-        &self.name
+/// Context information for variable expansion
+#[derive(Debug, Clone)]
+pub struct VariableContext {
+    /// Current position in the source
+    pub position: Position,
+    /// Path to current object (for nested variable resolution)
+    pub current_object_path: Vec<UclKey>,
+    /// Stack of variables currently being expanded (for circular reference detection)
+    pub expansion_stack: Vec<String>,
+}
+
+impl VariableContext {
+    /// Creates a new variable context
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            current_object_path: Vec::new(),
+            expansion_stack: Vec::new(),
+        }
     }
 
-    fn version(&self) -> &str {
-        &self.version
+    /// Pushes a new key onto the object path
+    pub fn push_key(&mut self, key: impl Into<UclKey>) {
+        self.current_object_path.push(key.into());
     }
 
-    fn description(&self) -> &str {
-        "Adds support for CSS-like units (px, pt, em, rem, %, vh, vw)"
+    /// Pops the last key from the object path, returning it
+    pub fn pop_key(&mut self) -> Option<UclKey> {
+        self.current_object_path.pop()
     }
 
-    fn priority(&self) -> u32 {
-        self.priority
+    /// Returns the object path followed by `key`
+    pub(crate) fn path_to(&self, key: &str) -> Vec<String> {
+        let path = self.current_object_path.iter().map(|k| k.to_string());
+        path.chain(std::iter::once(key.to_string())).collect()
     }
 
-    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
-        vec![Box::new(
-            CustomUnitSuffixHandler::new().with_priority(self.priority),
-        )]
+    /// Pushes a variable onto the expansion stack for circular reference detection
+    pub fn push_expansion(&mut self, var_name: String) -> Result<(), String> {
+        if self.expansion_stack.contains(&var_name) {
+            return Err(format!(
+                "Circular reference detected: {} -> {}",
+                self.expansion_stack.join(" -> "),
+                var_name
+            ));
+        }
+        self.expansion_stack.push(var_name);
+        Ok(())
     }
-}
 
-/// Example plugin for path processing
-pub struct PathProcessingPlugin {
-    name: String,
-    version: String,
-    priority: u32,
-}
+    /// Pops a variable from the expansion stack
+    pub fn pop_expansion(&mut self) {
+        self.expansion_stack.pop();
+    }
 
-impl PathProcessingPlugin {
-    /// Creates a new path processing plugin
-    pub fn new() -> Self {
+    /// Returns the current expansion depth
+    pub fn expansion_depth(&self) -> usize {
+        self.expansion_stack.len()
+    }
+
+    /// Returns a copy of the context with updated position
+    pub fn with_position(&self, position: Position) -> Self {
         Self {
-            name: "path-processing".to_string(),
-            version: "1.0.0".to_string(),
-            priority: 50,
+            position,
+            current_object_path: self.current_object_path.clone(),
+            expansion_stack: self.expansion_stack.clone(),
         }
     }
 }
 
-impl Default for PathProcessingPlugin {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+/// Trait for handling variable expansion
+pub trait VariableHandler: Send + Sync {
+    /// Resolves a variable by name
+    fn resolve_variable(&self, name: &str) -> Option<String>;
 
-impl UclPlugin for PathProcessingPlugin {
-    fn name(&self) -> &str {
-        &self.name
+    /// Resolves a variable with additional context
+    fn resolve_variable_with_context(
+        &self,
+        name: &str,
+        _context: &VariableContext,
+    ) -> Option<String> {
+        // Default implementation ignores context
+        self.resolve_variable(name)
     }
 
-    fn version(&self) -> &str {
-        &self.version
+    /// Names of variables the handler provides that were never resolved
+    ///
+    /// The parser reports them as a diagnostic after a document is parsed.
+    /// Handlers that do not track usage report none.
+    fn unused_variables(&self) -> Vec<String> {
+        Vec::new()
     }
+}
 
-    fn description(&self) -> &str {
-        "Provides path normalization and processing for string values"
+impl<T: VariableHandler + ?Sized> VariableHandler for Arc<T> {
+    fn resolve_variable(&self, name: &str) -> Option<String> {
+        (**self).resolve_variable(name)
     }
 
-    fn priority(&self) -> u32 {
-        self.priority
+    fn resolve_variable_with_context(
+        &self,
+        name: &str,
+        context: &VariableContext,
+    ) -> Option<String> {
+        (**self).resolve_variable_with_context(name, context)
     }
 
-    fn string_processors(&self) -> Vec<Box<dyn StringPostProcessor>> {
-        vec![Box::new(
-            PathNormalizationProcessor::new().with_priority(self.priority),
-        )]
+    fn unused_variables(&self) -> Vec<String> {
+        (**self).unused_variables()
     }
 }
 
-/// Example plugin for configuration validation
-pub struct ConfigValidationPlugin {
-    name: String,
-    version: String,
-    priority: u32,
-    required_keys: Vec<String>,
-    allowed_keys: Option<Vec<String>>,
+/// Maps a UCL variable name to the name of the environment variable it is read from
+pub type EnvironmentNameTransform = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Environment variable handler
+///
+/// By default variables are read from the live process environment. Use
+/// `snapshot` or `from_snapshot` to resolve against a fixed set of variables,
+/// and the allowlist and denylist to limit which variables a config can read.
+#[derive(Clone, Default)]
+pub struct EnvironmentVariableHandler {
+    snapshot: Option<HashMap<String, String>>,
+    allowlist: Vec<String>,
+    denylist: Vec<String>,
+    name_transform: Option<EnvironmentNameTransform>,
 }
 
-impl ConfigValidationPlugin {
-    /// Creates a new configuration validation plugin
+impl EnvironmentVariableHandler {
+    /// Creates a handler that reads the live process environment
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a handler that captures the current process environment once
+    pub fn snapshot() -> Self {
+        Self::from_snapshot(std::env::vars().collect())
+    }
+
+    /// Creates a handler that resolves against a fixed set of variables
+    pub fn from_snapshot(variables: HashMap<String, String>) -> Self {
         Self {
-            name: "config-validation".to_string(),
-            version: "1.0.0".to_string(),
-            priority: 200,
-            required_keys: Vec::new(),
-            allowed_keys: None,
+            snapshot: Some(variables),
+            ..Self::default()
         }
     }
 
-    /// Adds a required key
-    pub fn require_key(mut self, key: String) -> Self {
-        self.required_keys.push(key);
+    /// Only allows variables whose environment name matches one of the glob patterns
+    ///
+    /// Patterns support `*` for any run of characters and `?` for a single character.
+    pub fn with_allowlist<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowlist = patterns.into_iter().map(Into::into).collect();
         self
     }
 
-    /// Sets allowed keys
-    pub fn allow_keys(mut self, keys: Vec<String>) -> Self {
-        self.allowed_keys = Some(keys);
+    /// Rejects variables whose environment name matches one of the glob patterns
+    ///
+    /// The denylist takes precedence over the allowlist.
+    pub fn with_denylist<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.denylist = patterns.into_iter().map(Into::into).collect();
         self
     }
-}
 
-impl Default for ConfigValidationPlugin {
-    fn default() -> Self {
-        Self::new()
+    /// Sets the naming convention that maps variable names to environment names
+    ///
+    /// Allowlist and denylist patterns are matched against the transformed name.
+    pub fn with_name_transform<F>(mut self, transform: F) -> Self
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        self.name_transform = Some(Arc::new(transform));
+        self
     }
-}
 
-impl UclPlugin for ConfigValidationPlugin {
-    fn name(&self) -> &str {
-        &self.name
+    /// Naming convention that maps `db.password` to `DB_PASSWORD`
+    pub fn screaming_snake_case(name: &str) -> String {
+        name.chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() {
+                    ch.to_ascii_uppercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect()
     }
 
-    fn version(&self) -> &str {
-        &self.version
-    }
+    /// Returns true if the environment variable may be read
+    fn is_permitted(&self, env_name: &str) -> bool {
+        if self
+            .denylist
+            .iter()
+            .any(|pattern| glob_matches(pattern, env_name))
+        {
+            return false;
+        }
 
-    fn description(&self) -> &str {
-        "Validates configuration structure with required and allowed keys"
+        self.allowlist.is_empty()
+            || self
+                .allowlist
+                .iter()
+                .any(|pattern| glob_matches(pattern, env_name))
     }
+}
 
-    fn priority(&self) -> u32 {
-        self.priority
-    }
+impl VariableHandler for EnvironmentVariableHandler {
+    fn resolve_variable(&self, name: &str) -> Option<String> {
+        let env_name = match &self.name_transform {
+            Some(transform) => transform(name),
+            None => name.to_string(),
+        };
 
-    fn configure(&mut self, config: &PluginConfig) -> Result<(), ParseError> {
-        // Configure required keys from plugin config
-        if let Some(required) = config.get("required_keys") {
-            self.required_keys = required.split(',').map(|s| s.trim().to_string()).collect();
+        if !self.is_permitted(&env_name) {
+            return None;
         }
 
-        // Configure allowed keys from plugin config
-        if let Some(allowed) = config.get("allowed_keys") {
-            self.allowed_keys = Some(allowed.split(',').map(|s| s.trim().to_string()).collect());
+        match &self.snapshot {
+            Some(variables) => variables.get(&env_name).cloned(),
+            None => std::env::var(&env_name).ok(),
         }
+    }
+}
 
-        Ok(())
+/// Matches a name against a glob pattern with `*` and `?` wildcards
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&ch) if ch == '?' || ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
     }
 
-    fn validation_hooks(&self) -> Vec<Box<dyn ValidationHook>> {
-        let mut hook = SchemaValidationHook::new().with_priority(self.priority);
+    pattern[p..].iter().all(|&ch| ch == '*')
+}
 
-        for key in &self.required_keys {
-            hook = hook.require_key(key.clone());
-        }
+/// Map-based variable handler
+///
+/// ```rust
+/// use ucl_lexer::{MapVariableHandler, UclParser};
+///
+/// let mut handler = MapVariableHandler::new()
+///     .case_insensitive(true)
+///     .trim_values(true)
+///     .require_all_used(true);
+/// handler.insert("DATABASE_URL".to_string(), "postgres://db\n".to_string());
+/// handler.insert("CACHE_URL".to_string(), "redis://cache".to_string());
+///
+/// let mut parser = UclParser::with_variable_handler("db = \"${database_url}\"", Box::new(handler));
+/// let value = parser.parse_document()?;
+/// assert_eq!(value["db"], "postgres://db");
+/// assert_eq!(
+///     parser.diagnostics()[0].message,
+///     "variables provided but never used: CACHE_URL"
+/// );
+/// # Ok::<(), ucl_lexer::ParseError>(())
+/// ```
+#[derive(Default)]
+pub struct MapVariableHandler {
+    variables: HashMap<String, String>,
+    /// Names by their lowercase form, with `case_insensitive`
+    folded: Option<HashMap<String, String>>,
+    trim_values: bool,
+    /// Names resolved so far, with `require_all_used`
+    used: Option<Mutex<HashSet<String>>>,
+}
 
-        if let Some(ref allowed) = self.allowed_keys {
-            hook = hook.allow_keys(allowed.clone());
+impl MapVariableHandler {
+    /// Creates a new map variable handler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a handler from an existing map
+    pub fn from_map(variables: HashMap<String, String>) -> Self {
+        Self {
+            variables,
+            ..Self::default()
         }
+    }
 
-        vec![Box::new(hook)]
+    /// Matches variable names regardless of case, so `${database_url}` reads `DATABASE_URL`
+    ///
+    /// Names keep the case they were inserted with in `variables`. Of names
+    /// that differ only in case, the one inserted last with `insert` is read.
+    pub fn case_insensitive(mut self, enabled: bool) -> Self {
+        self.folded = enabled.then(|| {
+            self.variables
+                .keys()
+                .map(|name| (name.to_lowercase(), name.clone()))
+                .collect()
+        });
+        self
     }
-}
 
-/// Syntax style detection for NGINX-style implicit syntax
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum SyntaxStyle {
-    /// Explicit syntax with separators (key = value or key: value)
-    Explicit,
-    /// Implicit syntax without separators (key value or key { ... })
-    Implicit,
-    /// NGINX-style nested syntax (key identifier { ... })
-    NginxNested,
-}
+    /// Strips leading and trailing whitespace, such as a final newline, from resolved values
+    pub fn trim_values(mut self, enabled: bool) -> Self {
+        self.trim_values = enabled;
+        self
+    }
 
-/// UCL parser that consumes tokens and builds structured data
-pub struct UclParser<'a> {
-    lexer: UclLexer<'a>,
-    variable_handler: Option<Box<dyn VariableHandler>>,
-    current_token: Option<Token<'a>>,
-    current_token_start: Option<Position>,
-    current_token_end: Option<Position>,
-    config: ParserConfig,
-    current_depth: usize,
-    parsing_hooks: ParsingHooks,
+    /// Reports variables that were provided but never referenced
+    ///
+    /// Once a document is parsed, the names of the unused variables are
+    /// listed in a diagnostic of the parser. Usage is counted from the time
+    /// this is enabled, across every document the handler resolves for.
+    pub fn require_all_used(mut self, enabled: bool) -> Self {
+        self.used = enabled.then(Mutex::default);
+        self
+    }
+
+    /// Inserts a variable
+    pub fn insert(&mut self, name: String, value: String) {
+        if let Some(folded) = &mut self.folded {
+            folded.insert(name.to_lowercase(), name.clone());
+        }
+        self.variables.insert(name, value);
+    }
+
+    /// Gets a reference to the internal map
+    pub fn variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
 }
 
-impl<'a> UclParser<'a> {
-    /// Creates a new parser with default configuration
-    pub fn new(input: &'a str) -> Self {
-        let mut parser = Self {
-            lexer: UclLexer::new(input),
-            variable_handler: None,
-            current_token: None,
-            current_token_start: None,
-            current_token_end: None,
-            config: ParserConfig::default(),
-            current_depth: 0,
-            parsing_hooks: ParsingHooks::new(),
+impl VariableHandler for MapVariableHandler {
+    fn resolve_variable(&self, name: &str) -> Option<String> {
+        let (name, value) = match &self.folded {
+            Some(folded) => {
+                let name = folded.get(&name.to_lowercase())?;
+                self.variables.get_key_value(name)?
+            }
+            None => self.variables.get_key_value(name)?,
         };
-
-        // Load the first token
-        parser.advance_token().ok();
-        parser
+        if let Some(used) = &self.used {
+            used.lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(name.clone());
+        }
+        Some(match self.trim_values {
+            true => value.trim().to_string(),
+            false => value.clone(),
+        })
     }
 
-    /// Creates a parser with custom lexer configuration
-    pub fn with_lexer_config(input: &'a str, lexer_config: LexerConfig) -> Self {
-        let mut parser = Self {
-            lexer: UclLexer::with_config(input, lexer_config),
-            variable_handler: None,
-            current_token: None,
-            current_token_start: None,
-            current_token_end: None,
-            config: ParserConfig::default(),
-            current_depth: 0,
-            parsing_hooks: ParsingHooks::new(),
+    fn unused_variables(&self) -> Vec<String> {
+        let Some(used) = &self.used else {
+            return Vec::new();
         };
+        let used = used.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut unused: Vec<String> = self
+            .variables
+            .keys()
+            .filter(|name| !used.contains(*name))
+            .cloned()
+            .collect();
+        unused.sort();
+        unused
+    }
+}
 
-        // Load the first token
-        parser.advance_token().ok();
-        parser
+/// Chained variable handler that tries multiple handlers in order
+pub struct ChainedVariableHandler {
+    handlers: Vec<Box<dyn VariableHandler>>,
+}
+
+impl ChainedVariableHandler {
+    /// Creates a new chained handler
+    pub fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+        }
     }
 
-    /// Creates a parser with a variable handler
-    pub fn with_variable_handler(input: &'a str, handler: Box<dyn VariableHandler>) -> Self {
-        let mut parser = Self::new(input);
-        parser.variable_handler = Some(handler);
-        parser
+    /// Adds a handler to the chain
+    pub fn add_handler(&mut self, handler: Box<dyn VariableHandler>) {
+        self.handlers.push(handler);
     }
 
-    /// Creates a parser with custom parsing hooks
-    pub fn with_parsing_hooks(input: &'a str, hooks: ParsingHooks) -> Self {
-        let mut parser = Self::new(input);
-        parser.parsing_hooks = hooks;
-        parser
+    /// Creates a chained handler from a vector of handlers
+    pub fn from_handlers(handlers: Vec<Box<dyn VariableHandler>>) -> Self {
+        Self { handlers }
     }
+}
 
-    /// Creates a parser with both variable handler and parsing hooks
-    pub fn with_variable_handler_and_hooks(
-        input: &'a str,
-        handler: Box<dyn VariableHandler>,
-        hooks: ParsingHooks,
-    ) -> Self {
-        let mut parser = Self::new(input);
-        parser.variable_handler = Some(handler);
-        parser.parsing_hooks = hooks;
-        parser
+impl Default for ChainedVariableHandler {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    /// Sets the parser configuration
-    pub fn with_config(mut self, config: ParserConfig) -> Self {
-        self.config = config;
-        self
+impl VariableHandler for ChainedVariableHandler {
+    fn unused_variables(&self) -> Vec<String> {
+        self.handlers
+            .iter()
+            .flat_map(|handler| handler.unused_variables())
+            .collect()
     }
 
-    /// Gets a reference to the parsing hooks
-    pub fn parsing_hooks(&self) -> &ParsingHooks {
-        &self.parsing_hooks
+    fn resolve_variable(&self, name: &str) -> Option<String> {
+        for handler in &self.handlers {
+            if let Some(value) = handler.resolve_variable(name) {
+                return Some(value);
+            }
+        }
+        None
     }
 
-    /// Gets a mutable reference to the parsing hooks
-    pub fn parsing_hooks_mut(&mut self) -> &mut ParsingHooks {
-        &mut self.parsing_hooks
+    fn resolve_variable_with_context(
+        &self,
+        name: &str,
+        context: &VariableContext,
+    ) -> Option<String> {
+        for handler in &self.handlers {
+            if let Some(value) = handler.resolve_variable_with_context(name, context) {
+                return Some(value);
+            }
+        }
+        None
     }
+}
 
-    /// Sets the parsing hooks
-    pub fn set_parsing_hooks(&mut self, hooks: ParsingHooks) {
-        self.parsing_hooks = hooks;
+/// Trait for custom number suffix handling
+pub trait NumberSuffixHandler: Send + Sync {
+    /// Attempts to parse a custom suffix and return the multiplier
+    /// Returns None if the suffix is not recognized by this handler
+    fn parse_suffix(&self, suffix: &str) -> Option<f64>;
+
+    /// Converts a number written directly before a suffix, as in `10px`, into a value
+    ///
+    /// Returns `Ok(None)` if the suffix is not recognized. The default
+    /// implementation scales the number by the multiplier from `parse_suffix`.
+    fn apply_suffix(
+        &self,
+        number: f64,
+        suffix: &str,
+        _context: &VariableContext,
+        _position: Position,
+    ) -> Result<Option<UclValue>, ParseError> {
+        Ok(self
+            .parse_suffix(suffix)
+            .map(|multiplier| UclValue::Float(number * multiplier)))
     }
 
-    /// Adds a number suffix handler
-    pub fn add_number_suffix_handler(&mut self, handler: Box<dyn NumberSuffixHandler>) {
-        self.parsing_hooks.add_number_suffix_handler(handler);
+    /// Returns the built-in time and size suffixes this handler takes over from the lexer
+    fn shadowed_suffixes(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    /// Adds a string post-processor
-    pub fn add_string_processor(&mut self, processor: Box<dyn StringPostProcessor>) {
-        self.parsing_hooks.add_string_processor(processor);
+    /// Returns the suffixes this handler reads, checked for conflicts between plugins
+    ///
+    /// Suffixes that are not listed are never reported as conflicting.
+    fn suffixes(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    /// Adds a validation hook
-    pub fn add_validation_hook(&mut self, hook: Box<dyn ValidationHook>) {
-        self.parsing_hooks.add_validation_hook(hook);
+    /// Returns the priority of this handler (higher priority handlers are tried first)
+    fn priority(&self) -> u32 {
+        0
     }
 
-    /// Advances to the next token
-    fn advance_token(&mut self) -> Result<(), ParseError> {
-        match self.lexer.next_token() {
-            Ok(token) => {
-                self.current_token = Some(token);
-                self.current_token_start = Some(self.lexer.last_token_start());
-                self.current_token_end = Some(self.lexer.last_token_end());
-                Ok(())
-            }
-            Err(lex_error) => Err(ParseError::from(lex_error)),
-        }
+    /// Returns a description of the suffixes this handler supports
+    fn description(&self) -> &str {
+        "Custom number suffix handler"
     }
+}
 
-    /// Returns the current token
-    pub fn current_token(&self) -> Option<&Token<'a>> {
-        self.current_token.as_ref()
+impl<T: NumberSuffixHandler + ?Sized> NumberSuffixHandler for Arc<T> {
+    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
+        (**self).parse_suffix(suffix)
     }
 
-    /// Returns the start position of the current token
-    pub fn current_token_start(&self) -> Option<Position> {
-        self.current_token_start
+    fn apply_suffix(
+        &self,
+        number: f64,
+        suffix: &str,
+        context: &VariableContext,
+        position: Position,
+    ) -> Result<Option<UclValue>, ParseError> {
+        (**self).apply_suffix(number, suffix, context, position)
     }
 
-    /// Returns the end position of the current token
-    pub fn current_token_end(&self) -> Option<Position> {
-        self.current_token_end
+    fn shadowed_suffixes(&self) -> Vec<String> {
+        (**self).shadowed_suffixes()
     }
 
-    /// Returns the current position
-    pub fn current_position(&self) -> Position {
-        self.lexer.current_position()
+    fn suffixes(&self) -> Vec<String> {
+        (**self).suffixes()
     }
 
-    /// Peeks at the next token without consuming it
-    pub fn peek_token(&mut self) -> Result<Option<&Token<'a>>, ParseError> {
-        Ok(self.current_token())
+    fn priority(&self) -> u32 {
+        (**self).priority()
     }
 
-    /// Expects a specific token and consumes it
-    pub fn expect_token(&mut self, expected: &Token<'a>) -> Result<Token<'a>, ParseError> {
-        match self.current_token() {
-            Some(token) if std::mem::discriminant(token) == std::mem::discriminant(expected) => {
-                let consumed_token = token.clone();
-                self.advance_token()?;
-                Ok(consumed_token)
-            }
-            Some(token) => Err(ParseError::UnexpectedToken {
-                token: token.type_name().to_string(),
-                position: self.current_position(),
-                expected: expected.type_name().to_string(),
-            }),
-            None => Err(ParseError::UnexpectedToken {
-                token: "end of file".to_string(),
-                position: self.current_position(),
-                expected: expected.type_name().to_string(),
-            }),
-        }
+    fn description(&self) -> &str {
+        (**self).description()
     }
+}
 
-    /// Consumes the current token if it matches the expected type
-    pub fn consume_if_matches(
-        &mut self,
-        expected: &Token<'a>,
-    ) -> Result<Option<Token<'a>>, ParseError> {
-        match self.current_token() {
-            Some(token) if std::mem::discriminant(token) == std::mem::discriminant(expected) => {
-                let consumed_token = token.clone();
-                self.advance_token()?;
-                Ok(Some(consumed_token))
-            }
-            _ => Ok(None),
-        }
+/// Trait for custom string post-processing
+pub trait StringPostProcessor: Send + Sync {
+    /// Processes a string value after parsing and variable expansion
+    /// Can modify the string or return an error
+    fn process_string(&self, value: &str, context: &VariableContext) -> Result<String, ParseError>;
+
+    /// Returns the key paths, such as `server.root`, whose strings this processor rewrites
+    ///
+    /// Two plugins listing the same path conflict. Processors that apply to
+    /// every string list none.
+    fn paths(&self) -> Vec<String> {
+        Vec::new()
     }
 
-    /// Checks if the current token is of a specific type
-    pub fn is_current_token(&self, expected: &Token<'a>) -> bool {
-        match self.current_token() {
-            Some(token) => std::mem::discriminant(token) == std::mem::discriminant(expected),
-            None => false,
-        }
+    /// Returns the priority of this processor (higher priority processors are applied first)
+    fn priority(&self) -> u32 {
+        0
     }
 
-    /// Skips whitespace and comments
-    pub fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
-        while let Some(token) = self.current_token() {
-            match token {
-                Token::Comment(_) => {
-                    self.advance_token()?;
-                }
-                _ => break,
-            }
-        }
-        Ok(())
+    /// Returns a description of what this processor does
+    fn description(&self) -> &str {
+        "Custom string post-processor"
     }
+}
 
-    /// Detects the syntax style for the current key-value pair using lookahead
-    fn detect_syntax_style(&mut self) -> Result<SyntaxStyle, ParseError> {
-        match self.current_token() {
-            Some(Token::Colon) | Some(Token::Equals) => Ok(SyntaxStyle::Explicit),
-            Some(Token::ObjectStart) => Ok(SyntaxStyle::Implicit),
-            Some(Token::Key(_)) | Some(Token::String { .. }) => {
-                let snapshot = self.lexer.snapshot();
-                let saved_token = self.current_token.clone();
-
-                let lookahead_result = (|| -> Result<bool, ParseError> {
-                    self.advance_token()?;
-                    self.skip_whitespace_and_comments()?;
-                    Ok(matches!(self.current_token(), Some(Token::ObjectStart)))
-                })();
-
-                self.lexer.restore(snapshot);
-                self.current_token = saved_token;
+impl<T: StringPostProcessor + ?Sized> StringPostProcessor for Arc<T> {
+    fn process_string(&self, value: &str, context: &VariableContext) -> Result<String, ParseError> {
+        (**self).process_string(value, context)
+    }
 
-                match lookahead_result? {
-                    true => Ok(SyntaxStyle::NginxNested),
-                    false => Ok(SyntaxStyle::Implicit),
-                }
-            }
-            _ => Ok(SyntaxStyle::Implicit),
-        }
+    fn paths(&self) -> Vec<String> {
+        (**self).paths()
     }
 
-    /// Checks if additional tokens should be treated as part of the current value
-    fn has_inline_value_continuation(&self) -> bool {
-        match self.current_token() {
-            Some(Token::Semicolon) | Some(Token::Comma) | Some(Token::ObjectEnd) => false,
-            Some(Token::Eof) | None => false,
-            Some(_) => !self.lexer.last_token_had_newline(),
-        }
+    fn priority(&self) -> u32 {
+        (**self).priority()
     }
 
-    /// Returns the raw text for the current token between the given positions
-    fn token_text_from_positions(&self, start: Position, end: Position) -> String {
-        self.lexer
-            .source()
-            .get(start.offset..end.offset)
-            .unwrap_or("")
-            .to_string()
+    fn description(&self) -> &str {
+        (**self).description()
     }
+}
 
-    /// Collects inline tokens that belong to the same value (same line)
-    fn collect_inline_value(
-        &mut self,
-        mut current: String,
+/// Trait for custom validation during parsing
+pub trait ValidationHook: Send + Sync {
+    /// Validates a parsed value before it's added to the result
+    /// Can modify the value or return an error
+    fn validate_value(
+        &self,
+        value: &UclValue,
         context: &VariableContext,
-    ) -> Result<String, ParseError> {
-        let mut _first_token_is_key = false;
-        let mut _saw_separator = false;
-        let mut parts_appended = 0usize;
-
-        while self.has_inline_value_continuation() {
-            let token_start = match self.current_token_start() {
-                Some(pos) => pos,
-                None => break,
-            };
+    ) -> Result<Option<UclValue>, ParseError>;
 
-            let gap_text = self.lexer.last_token_leading_whitespace();
-            if !gap_text.is_empty() {
-                current.push_str(gap_text);
-            }
+    /// Validates an object key before it's used
+    fn validate_key(
+        &self,
+        _key: &str,
+        _context: &VariableContext,
+    ) -> Result<Option<String>, ParseError> {
+        // Default implementation accepts all keys
+        Ok(None)
+    }
 
-            let part_end = self.current_token_end().unwrap_or(token_start);
-            let token_snapshot = self.current_token().cloned();
+    /// Returns the priority of this hook (higher priority hooks are called first)
+    fn priority(&self) -> u32 {
+        0
+    }
 
-            let part = match token_snapshot.as_ref() {
-                Some(Token::Key(text)) => {
-                    if parts_appended == 0 {
-                        _first_token_is_key = true;
-                    }
-                    let part_str = text.to_string();
-                    self.validate_bare_word(&part_str, token_start)?;
-                    part_str
-                }
-                Some(Token::String {
-                    value,
-                    needs_expansion,
-                    ..
-                }) => {
-                    if *needs_expansion {
-                        self.expand_variables_with_context_safe(value, context)?
-                    } else {
-                        value.to_string()
-                    }
-                }
-                Some(Token::Integer(val)) => val.to_string(),
-                Some(Token::Float(val)) => {
-                    let mut text = val.to_string();
-                    if text.contains('.') {
-                        while text.ends_with('0') {
-                            text.pop();
-                        }
-                        if text.ends_with('.') {
-                            text.push('0');
-                        }
-                    }
-                    text
-                }
-                Some(Token::Time(_)) => self.token_text_from_positions(token_start, part_end),
-                Some(Token::Boolean(true)) => "true".to_string(),
-                Some(Token::Boolean(false)) => "false".to_string(),
-                Some(Token::Null) => "null".to_string(),
-                Some(Token::Colon) => {
-                    _saw_separator = true;
-                    ":".to_string()
-                }
-                Some(Token::Equals) => {
-                    _saw_separator = true;
-                    "=".to_string()
-                }
-                _ => break,
-            };
+    /// Returns a description of what this hook validates
+    fn description(&self) -> &str {
+        "Custom validation hook"
+    }
+}
 
-            current.push_str(&part);
-            parts_appended += 1;
+impl<T: ValidationHook + ?Sized> ValidationHook for Arc<T> {
+    fn validate_value(
+        &self,
+        value: &UclValue,
+        context: &VariableContext,
+    ) -> Result<Option<UclValue>, ParseError> {
+        (**self).validate_value(value, context)
+    }
 
-            self.advance_token()?;
-            self.skip_whitespace_and_comments()?;
-        }
+    fn validate_key(
+        &self,
+        key: &str,
+        context: &VariableContext,
+    ) -> Result<Option<String>, ParseError> {
+        (**self).validate_key(key, context)
+    }
 
-        Ok(current)
+    fn priority(&self) -> u32 {
+        (**self).priority()
     }
 
-    /// Handles inline string concatenation using '+' on the same line
-    fn concatenate_inline_strings(
-        &mut self,
-        mut current: String,
-        context: Option<&VariableContext>,
-    ) -> Result<String, ParseError> {
-        while matches!(self.current_token(), Some(Token::Plus))
-            && !self.lexer.last_token_had_newline()
-        {
-            let plus_position = self
-                .current_token_start()
-                .unwrap_or_else(|| self.current_position());
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+}
 
-            self.advance_token()?;
-            self.skip_whitespace_and_comments()?;
+/// Trait for rewriting or rejecting object keys before they are inserted
+///
+/// Transforms run on every key, after `LexerConfig::key_charset` is applied
+/// and before the validation hooks, duplicate detection and insertion, so a
+/// rewritten key collides with an existing one as if it had been written that
+/// way. `path` holds the keys of the enclosing objects, as already
+/// transformed. With `ParserConfig::expand_dotted_keys`, each segment of a
+/// dotted key such as `a.b.c` is transformed on its own, after splitting.
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// use ucl_lexer::{KeyTransformHook, ParseError, Span, UclParser};
+///
+/// struct StripLegacyPrefix;
+///
+/// impl KeyTransformHook for StripLegacyPrefix {
+///     fn transform_key<'k>(
+///         &self,
+///         key: &'k str,
+///         _path: &[&str],
+///         _span: Span,
+///     ) -> Result<Cow<'k, str>, ParseError> {
+///         Ok(key.strip_prefix("x-").map_or(Cow::Borrowed(key), Cow::Borrowed))
+///     }
+/// }
+///
+/// let mut parser = UclParser::new("x-timeout = 30; server { x-port = 80 }");
+/// parser.add_key_transform(Box::new(StripLegacyPrefix));
+/// let value = parser.parse_document()?;
+/// assert_eq!(value["timeout"], 30);
+/// assert_eq!(value["server"]["port"], 80);
+/// # Ok::<(), ParseError>(())
+/// ```
+pub trait KeyTransformHook: Send + Sync {
+    /// Returns the key to insert in place of `key`, or an error rejecting it
+    fn transform_key<'k>(
+        &self,
+        key: &'k str,
+        path: &[&str],
+        span: Span,
+    ) -> Result<Cow<'k, str>, ParseError>;
 
-            let appended = match self.current_token() {
-                Some(Token::String {
-                    value: next_value,
-                    needs_expansion: next_needs_expansion,
-                    ..
-                }) => {
-                    if let Some(ctx) = context {
-                        if *next_needs_expansion {
-                            self.expand_variables_with_context_safe(next_value, ctx)?
-                        } else {
-                            next_value.to_string()
-                        }
-                    } else if *next_needs_expansion {
-                        self.expand_variables(next_value)?
-                    } else {
-                        next_value.to_string()
-                    }
-                }
-                Some(_) => {
-                    return Err(ParseError::InvalidObject {
-                        message:
-                            "String concatenation requires quoted strings on both sides of '+'"
-                                .to_string(),
-                        position: self
-                            .current_token_start()
-                            .unwrap_or_else(|| self.current_position()),
-                    });
-                }
-                None => {
-                    return Err(ParseError::InvalidObject {
-                        message:
-                            "String concatenation terminates unexpectedly; missing right-hand string"
-                                .to_string(),
-                        position: plus_position,
-                    });
-                }
-            };
+    /// Returns the priority of this hook (higher priority hooks are applied first)
+    fn priority(&self) -> u32 {
+        0
+    }
 
-            current.push_str(&appended);
-            self.advance_token()?;
-            self.skip_whitespace_and_comments()?;
-        }
+    /// Returns a description of what this hook does
+    fn description(&self) -> &str {
+        "Custom key transform hook"
+    }
+}
 
-        Ok(current)
+impl<T: KeyTransformHook + ?Sized> KeyTransformHook for Arc<T> {
+    fn transform_key<'k>(
+        &self,
+        key: &'k str,
+        path: &[&str],
+        span: Span,
+    ) -> Result<Cow<'k, str>, ParseError> {
+        (**self).transform_key(key, path, span)
     }
 
-    /// Parses a bare word value (unquoted identifier)
-    fn parse_bare_word_value(&mut self) -> Result<UclValue, ParseError> {
-        let context = VariableContext::new(self.current_position());
-        self.parse_bare_word_value_with_context(&context, false)
+    fn priority(&self) -> u32 {
+        (**self).priority()
     }
 
-    /// Parses a bare word value with explicit context (unquoted identifier)
-    fn parse_bare_word_value_with_context(
-        &mut self,
-        context: &VariableContext,
-        explicit_separator: bool,
-    ) -> Result<UclValue, ParseError> {
-        match self.current_token() {
-            Some(Token::Key(word)) => {
-                let word_str = word.to_string();
-                let start_position = self
-                    .current_token_start()
-                    .unwrap_or_else(|| self.current_position());
-                let bare_word_end = self
-                    .current_token_end()
-                    .unwrap_or_else(|| self.current_position());
-                self.advance_token()?;
+    fn description(&self) -> &str {
+        (**self).description()
+    }
+}
 
-                if let Some(next_char) = self
-                    .lexer
-                    .source()
-                    .get(bare_word_end.offset..)
-                    .and_then(|s| s.chars().next())
-                    && next_char == '#'
-                {
-                    return Err(ParseError::InvalidObject {
-                        message: format!(
-                            "Bare word '{}' cannot be immediately followed by '#'. quote the value if you need a literal hash.",
-                            word_str
-                        ),
-                        position: bare_word_end,
-                    });
-                }
+/// Container for all custom parsing hooks
+#[derive(Default)]
+pub struct ParsingHooks {
+    /// Custom number suffix handlers
+    pub number_suffix_handlers: Vec<Box<dyn NumberSuffixHandler>>,
+    /// Custom string post-processors
+    pub string_processors: Vec<Box<dyn StringPostProcessor>>,
+    /// Custom validation hooks
+    pub validation_hooks: Vec<Box<dyn ValidationHook>>,
+    /// Custom key transforms
+    pub key_transforms: Vec<Box<dyn KeyTransformHook>>,
+}
 
-                // Validate bare word before processing
-                self.validate_bare_word(&word_str, start_position)?;
+impl ParsingHooks {
+    /// Creates a new empty set of parsing hooks
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-                self.skip_whitespace_and_comments()?;
+    /// Returns true if no hook of any kind is registered
+    #[cfg(feature = "arena")]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.number_suffix_handlers.is_empty()
+            && self.string_processors.is_empty()
+            && self.validation_hooks.is_empty()
+            && self.key_transforms.is_empty()
+    }
 
-                // Detect structural delimiters that are directly attached to the bare word (e.g. hello{world)
+    /// Adds a number suffix handler
+    pub fn add_number_suffix_handler(&mut self, handler: Box<dyn NumberSuffixHandler>) {
+        self.number_suffix_handlers.push(handler);
+        // Sort by priority (highest first)
+        self.number_suffix_handlers
+            .sort_by_key(|handler| Reverse(handler.priority()));
+    }
+
+    /// Adds a string post-processor
+    pub fn add_string_processor(&mut self, processor: Box<dyn StringPostProcessor>) {
+        self.string_processors.push(processor);
+        // Sort by priority (highest first)
+        self.string_processors
+            .sort_by_key(|processor| Reverse(processor.priority()));
+    }
+
+    /// Adds a validation hook
+    pub fn add_validation_hook(&mut self, hook: Box<dyn ValidationHook>) {
+        self.validation_hooks.push(hook);
+        // Sort by priority (highest first)
+        self.validation_hooks
+            .sort_by_key(|hook| Reverse(hook.priority()));
+    }
+
+    /// Adds a key transform
+    pub fn add_key_transform(&mut self, hook: Box<dyn KeyTransformHook>) {
+        self.key_transforms.push(hook);
+        // Sort by priority (highest first)
+        self.key_transforms
+            .sort_by_key(|hook| Reverse(hook.priority()));
+    }
+
+    /// Tries to parse a number suffix using registered handlers
+    pub fn parse_number_suffix(&self, suffix: &str) -> Option<f64> {
+        for handler in &self.number_suffix_handlers {
+            if let Some(multiplier) = handler.parse_suffix(suffix) {
+                return Some(multiplier);
+            }
+        }
+        None
+    }
+
+    /// Converts a number and its suffix using the first handler that recognizes the suffix
+    pub fn apply_number_suffix(
+        &self,
+        number: f64,
+        suffix: &str,
+        context: &VariableContext,
+        position: Position,
+    ) -> Result<Option<UclValue>, ParseError> {
+        for handler in &self.number_suffix_handlers {
+            if let Some(value) = handler.apply_suffix(number, suffix, context, position)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the built-in suffixes claimed by registered handlers
+    pub fn shadowed_suffixes(&self) -> Vec<String> {
+        self.number_suffix_handlers
+            .iter()
+            .flat_map(|handler| handler.shadowed_suffixes())
+            .collect()
+    }
+
+    /// Processes a string using registered processors
+    pub fn process_string(
+        &self,
+        value: &str,
+        context: &VariableContext,
+    ) -> Result<String, ParseError> {
+        self.process_owned_string(value.to_string(), context)
+    }
+
+    /// Validates a value using registered hooks
+    pub fn validate_value(
+        &self,
+        value: &UclValue,
+        context: &VariableContext,
+    ) -> Result<UclValue, ParseError> {
+        self.validate_owned_value(value.clone(), context)
+    }
+
+    /// Validates a key using registered hooks
+    pub fn validate_key(&self, key: &str, context: &VariableContext) -> Result<String, ParseError> {
+        self.validate_owned_key(key.into(), context)
+            .map(String::from)
+    }
+
+    /// Processes a string the parser owns, without copying it when no processor is registered
+    pub(crate) fn process_owned_string(
+        &self,
+        mut value: String,
+        context: &VariableContext,
+    ) -> Result<String, ParseError> {
+        for processor in &self.string_processors {
+            value = processor.process_string(&value, context)?;
+        }
+        Ok(value)
+    }
+
+    /// Validates a value the parser owns, without cloning it when no hook replaces it
+    pub(crate) fn validate_owned_value(
+        &self,
+        mut value: UclValue,
+        context: &VariableContext,
+    ) -> Result<UclValue, ParseError> {
+        for hook in &self.validation_hooks {
+            if let Some(modified_value) = hook.validate_value(&value, context)? {
+                value = modified_value;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Transforms a key written at `span` inside the objects at `path`
+    pub fn transform_key(
+        &self,
+        key: &str,
+        path: &[&str],
+        span: Span,
+    ) -> Result<String, ParseError> {
+        self.transform_owned_key(key.into(), path, span)
+            .map(String::from)
+    }
+
+    /// Transforms a key the parser owns, without copying it when no hook changes it
+    pub(crate) fn transform_owned_key(
+        &self,
+        mut key: UclKey,
+        path: &[&str],
+        span: Span,
+    ) -> Result<UclKey, ParseError> {
+        for hook in &self.key_transforms {
+            let transformed = match hook.transform_key(&key, path, span.clone())? {
+                Cow::Borrowed(text) if text == key.as_str() => continue,
+                Cow::Borrowed(text) => UclKey::from(text),
+                Cow::Owned(text) => UclKey::from(text),
+            };
+            key = transformed;
+        }
+        Ok(key)
+    }
+
+    /// Validates a key the parser owns, without copying it when no hook replaces it
+    pub(crate) fn validate_owned_key(
+        &self,
+        mut key: UclKey,
+        context: &VariableContext,
+    ) -> Result<UclKey, ParseError> {
+        for hook in &self.validation_hooks {
+            if let Some(modified_key) = hook.validate_key(&key, context)? {
+                key = modified_key.into();
+            }
+        }
+        Ok(key)
+    }
+}
+
+/// Plugin system for extensible parsing
+/// Trait for UCL parser plugins
+pub trait UclPlugin: Send + Sync {
+    /// Returns the name of the plugin
+    fn name(&self) -> &str;
+
+    /// Returns the version of the plugin
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    /// Returns a description of what the plugin does
+    fn description(&self) -> &str {
+        "UCL parser plugin"
+    }
+
+    /// Returns the priority of the plugin (higher priority plugins are loaded first)
+    fn priority(&self) -> u32 {
+        0
+    }
+
+    /// Called when the plugin is loaded
+    fn on_load(&mut self) -> Result<(), ParseError> {
+        Ok(())
+    }
+
+    /// Called when the plugin is unloaded
+    fn on_unload(&mut self) -> Result<(), ParseError> {
+        Ok(())
+    }
+
+    /// Configures the plugin with the given configuration
+    fn configure(&mut self, _config: &PluginConfig) -> Result<(), ParseError> {
+        Ok(())
+    }
+
+    /// Returns the number suffix handlers provided by this plugin
+    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
+        Vec::new()
+    }
+
+    /// Returns the string post-processors provided by this plugin
+    fn string_processors(&self) -> Vec<Box<dyn StringPostProcessor>> {
+        Vec::new()
+    }
+
+    /// Returns the validation hooks provided by this plugin
+    fn validation_hooks(&self) -> Vec<Box<dyn ValidationHook>> {
+        Vec::new()
+    }
+
+    /// Returns the key transforms provided by this plugin
+    fn key_transforms(&self) -> Vec<Box<dyn KeyTransformHook>> {
+        Vec::new()
+    }
+
+    /// Returns the variable handlers provided by this plugin
+    fn variable_handlers(&self) -> Vec<Box<dyn VariableHandler>> {
+        Vec::new()
+    }
+}
+
+/// Configuration for plugins
+#[derive(Debug, Clone)]
+pub struct PluginConfig {
+    /// Plugin-specific configuration as key-value pairs
+    pub settings: HashMap<String, String>,
+    /// Whether the plugin is enabled
+    pub enabled: bool,
+    /// Plugin priority override
+    ///
+    /// Also decides which of two plugins that are both allowed to override
+    /// each other takes precedence.
+    pub priority_override: Option<u32>,
+}
+
+impl PluginConfig {
+    /// Creates a new plugin configuration
+    pub fn new() -> Self {
+        Self {
+            settings: HashMap::new(),
+            enabled: true,
+            priority_override: None,
+        }
+    }
+
+    /// Creates a configuration with settings
+    pub fn with_settings(settings: HashMap<String, String>) -> Self {
+        Self {
+            settings,
+            enabled: true,
+            priority_override: None,
+        }
+    }
+
+    /// Sets a configuration value
+    pub fn set(&mut self, key: String, value: String) {
+        self.settings.insert(key, value);
+    }
+
+    /// Gets a configuration value
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.settings.get(key)
+    }
+
+    /// Gets a configuration value with a default
+    pub fn get_or(&self, key: &str, default: &str) -> String {
+        self.settings
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| default.to_string())
+    }
+
+    /// Enables or disables the plugin
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Sets the priority override
+    pub fn set_priority_override(&mut self, priority: Option<u32>) {
+        self.priority_override = priority;
+    }
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Plugin registry for managing loaded plugins
+pub struct PluginRegistry {
+    /// Loaded plugins
+    plugins: Vec<Box<dyn UclPlugin>>,
+    /// Plugin configurations
+    configs: HashMap<String, PluginConfig>,
+    /// Plugins allowed to take over suffixes and paths claimed by other plugins
+    overrides: HashSet<String>,
+    /// Plugin reading each number suffix, filled in by `initialize`
+    suffix_owners: HashMap<String, String>,
+    /// Plugin rewriting the strings at each key path, filled in by `initialize`
+    path_owners: HashMap<String, String>,
+    /// Whether the registry is initialized
+    initialized: bool,
+}
+
+impl PluginRegistry {
+    /// Creates a new plugin registry
+    pub fn new() -> Self {
+        Self {
+            plugins: Vec::new(),
+            configs: HashMap::new(),
+            overrides: HashSet::new(),
+            suffix_owners: HashMap::new(),
+            path_owners: HashMap::new(),
+            initialized: false,
+        }
+    }
+
+    /// Returns the priority of a plugin, as overridden by its configuration
+    fn plugin_priority(configs: &HashMap<String, PluginConfig>, plugin: &dyn UclPlugin) -> u32 {
+        configs
+            .get(plugin.name())
+            .and_then(|config| config.priority_override)
+            .unwrap_or_else(|| plugin.priority())
+    }
+
+    /// Registers a plugin
+    pub fn register_plugin(&mut self, mut plugin: Box<dyn UclPlugin>) -> Result<(), ParseError> {
+        let name = plugin.name().to_string();
+
+        // Configure the plugin if we have a configuration for it
+        if let Some(config) = self.configs.get(&name) {
+            plugin.configure(config)?;
+        }
+
+        // Load the plugin
+        plugin.on_load()?;
+
+        self.plugins.push(plugin);
+
+        // Sort plugins by priority (highest first)
+        let configs = &self.configs;
+        self.plugins
+            .sort_by_key(|plugin| Reverse(Self::plugin_priority(configs, plugin.as_ref())));
+
+        Ok(())
+    }
+
+    /// Unregisters a plugin by name
+    pub fn unregister_plugin(&mut self, name: &str) -> Result<(), ParseError> {
+        if let Some(pos) = self.plugins.iter().position(|p| p.name() == name) {
+            let mut plugin = self.plugins.remove(pos);
+            plugin.on_unload()?;
+        }
+        Ok(())
+    }
+
+    /// Sets the configuration for a plugin
+    pub fn set_plugin_config(&mut self, name: String, config: PluginConfig) {
+        self.configs.insert(name, config);
+    }
+
+    /// Gets the configuration for a plugin
+    pub fn get_plugin_config(&self, name: &str) -> Option<&PluginConfig> {
+        self.configs.get(name)
+    }
+
+    /// Lets a plugin take over the suffixes and paths it shares with other plugins
+    ///
+    /// Without it, two enabled plugins that read the same number suffix or
+    /// rewrite strings at the same key path make `initialize` fail with
+    /// `ParseError::PluginConflict`. If both plugins are allowed to override,
+    /// the one with the higher priority, from `PluginConfig::priority_override`
+    /// or the plugin itself, takes precedence.
+    pub fn allow_override(&mut self, name: impl Into<String>) {
+        self.overrides.insert(name.into());
+    }
+
+    /// Returns the plugin that reads a number suffix, once the registry is initialized
+    pub fn suffix_owner(&self, suffix: &str) -> Option<&str> {
+        self.suffix_owners.get(suffix).map(String::as_str)
+    }
+
+    /// Returns the plugin that rewrites strings at a key path, once the registry is initialized
+    pub fn path_owner(&self, path: &str) -> Option<&str> {
+        self.path_owners.get(path).map(String::as_str)
+    }
+
+    /// Lists all registered plugins
+    pub fn list_plugins(&self) -> Vec<&dyn UclPlugin> {
+        self.plugins.iter().map(|p| p.as_ref()).collect()
+    }
+
+    /// Gets a plugin by name
+    pub fn get_plugin(&self, name: &str) -> Option<&dyn UclPlugin> {
+        self.plugins
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+
+    /// Initializes the registry and applies all plugin hooks
+    pub fn initialize(&mut self) -> Result<ParsingHooks, ParseError> {
+        if self.initialized {
+            return Err(ParseError::InvalidObject {
+                message: "Plugin registry already initialized".to_string(),
+                position: Position::new(),
+            });
+        }
+
+        let mut hooks = ParsingHooks::new();
+        let plugins: Vec<&dyn UclPlugin> = self
+            .plugins
+            .iter()
+            .map(|plugin| plugin.as_ref())
+            .filter(|plugin| {
+                self.configs
+                    .get(plugin.name())
+                    .is_none_or(|config| config.enabled)
+            })
+            .collect();
+        let mut handlers: Vec<_> = plugins
+            .iter()
+            .map(|plugin| plugin.number_suffix_handlers())
+            .collect();
+        let mut processors: Vec<_> = plugins
+            .iter()
+            .map(|plugin| plugin.string_processors())
+            .collect();
+
+        // Find the plugin reading each suffix and rewriting each path
+        let mut suffix_owners = HashMap::new();
+        let mut path_owners = HashMap::new();
+        let mut lost_suffixes = vec![HashSet::new(); plugins.len()];
+        let mut lost_paths = vec![HashSet::new(); plugins.len()];
+        for index in 0..plugins.len() {
+            for suffix in handlers[index]
+                .iter()
+                .flat_map(|handler| handler.suffixes())
+            {
+                let detail = || format!("both read the number suffix '{}'", suffix);
+                let claim = self.claim(&plugins, &mut suffix_owners, index, &suffix, detail)?;
+                if let Some(loser) = claim {
+                    lost_suffixes[loser].insert(suffix);
+                }
+            }
+            for path in processors[index]
+                .iter()
+                .flat_map(|processor| processor.paths())
+            {
+                let detail = || format!("both rewrite strings at '{}'", path);
+                let claim = self.claim(&plugins, &mut path_owners, index, &path, detail)?;
+                if let Some(loser) = claim {
+                    lost_paths[loser].insert(path);
+                }
+            }
+        }
+
+        // Collect hooks from all enabled plugins, without the claims they lost
+        for (index, plugin) in plugins.iter().enumerate() {
+            for handler in std::mem::take(&mut handlers[index]) {
+                if lost_suffixes[index].is_empty() {
+                    hooks.add_number_suffix_handler(handler);
+                } else {
+                    hooks.add_number_suffix_handler(Box::new(OverriddenSuffixes {
+                        handler,
+                        suffixes: lost_suffixes[index].clone(),
+                    }));
+                }
+            }
+            for processor in std::mem::take(&mut processors[index]) {
+                if lost_paths[index].is_empty() {
+                    hooks.add_string_processor(processor);
+                } else {
+                    hooks.add_string_processor(Box::new(OverriddenPaths {
+                        processor,
+                        paths: lost_paths[index].clone(),
+                    }));
+                }
+            }
+            for hook in plugin.validation_hooks() {
+                hooks.add_validation_hook(hook);
+            }
+            for hook in plugin.key_transforms() {
+                hooks.add_key_transform(hook);
+            }
+        }
+
+        let owner = |index: usize| plugins[index].name().to_string();
+        let suffix_owners = suffix_owners
+            .into_iter()
+            .map(|(suffix, index)| (suffix, owner(index)))
+            .collect();
+        let path_owners = path_owners
+            .into_iter()
+            .map(|(path, index)| (path, owner(index)))
+            .collect();
+        self.suffix_owners = suffix_owners;
+        self.path_owners = path_owners;
+        self.initialized = true;
+        Ok(hooks)
+    }
+
+    /// Records that the plugin at `index` claims `claim`, which `owners` maps to plugin indices
+    ///
+    /// Returns the plugin that lost the claim to the other one, if two plugins
+    /// make it and one of them may override the other.
+    fn claim(
+        &self,
+        plugins: &[&dyn UclPlugin],
+        owners: &mut HashMap<String, usize>,
+        index: usize,
+        claim: &str,
+        detail: impl FnOnce() -> String,
+    ) -> Result<Option<usize>, ParseError> {
+        let owner = match owners.get(claim) {
+            Some(&owner) if owner != index => owner,
+            Some(_) => return Ok(None),
+            None => {
+                owners.insert(claim.to_string(), index);
+                return Ok(None);
+            }
+        };
+
+        let (first, second) = (plugins[owner], plugins[index]);
+        let first_wins = match (
+            self.overrides.contains(first.name()),
+            self.overrides.contains(second.name()),
+        ) {
+            (true, false) => Some(true),
+            (false, true) => Some(false),
+            (true, true) => {
+                let first_priority = Self::plugin_priority(&self.configs, first);
+                let second_priority = Self::plugin_priority(&self.configs, second);
+                (first_priority != second_priority).then_some(first_priority > second_priority)
+            }
+            (false, false) => None,
+        };
+        match first_wins {
+            Some(true) => Ok(Some(index)),
+            Some(false) => {
+                owners.insert(claim.to_string(), index);
+                Ok(Some(owner))
+            }
+            None => Err(ParseError::PluginConflict {
+                plugin_a: first.name().to_string(),
+                plugin_b: second.name().to_string(),
+                detail: detail(),
+            }),
+        }
+    }
+
+    /// Resets the registry (unloads all plugins)
+    pub fn reset(&mut self) -> Result<(), ParseError> {
+        for plugin in &mut self.plugins {
+            plugin.on_unload()?;
+        }
+        self.plugins.clear();
+        self.suffix_owners.clear();
+        self.path_owners.clear();
+        self.initialized = false;
+        Ok(())
+    }
+
+    /// Returns whether the registry is initialized
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Gets all variable handlers from enabled plugins
+    pub fn get_variable_handlers(&self) -> Vec<Box<dyn VariableHandler>> {
+        let mut handlers = Vec::new();
+
+        for plugin in &self.plugins {
+            let config = self.configs.get(plugin.name());
+            let enabled = config.map(|c| c.enabled).unwrap_or(true);
+
+            if enabled {
+                handlers.extend(plugin.variable_handlers());
+            }
+        }
+
+        handlers
+    }
+}
+
+impl Default for PluginRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Suffix handler of a plugin without the suffixes another plugin took over
+struct OverriddenSuffixes {
+    handler: Box<dyn NumberSuffixHandler>,
+    suffixes: HashSet<String>,
+}
+
+impl NumberSuffixHandler for OverriddenSuffixes {
+    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
+        if self.suffixes.contains(suffix) {
+            return None;
+        }
+        self.handler.parse_suffix(suffix)
+    }
+
+    fn apply_suffix(
+        &self,
+        number: f64,
+        suffix: &str,
+        context: &VariableContext,
+        position: Position,
+    ) -> Result<Option<UclValue>, ParseError> {
+        if self.suffixes.contains(suffix) {
+            return Ok(None);
+        }
+        self.handler.apply_suffix(number, suffix, context, position)
+    }
+
+    fn shadowed_suffixes(&self) -> Vec<String> {
+        let mut shadowed = self.handler.shadowed_suffixes();
+        shadowed.retain(|suffix| !self.suffixes.contains(suffix));
+        shadowed
+    }
+
+    fn suffixes(&self) -> Vec<String> {
+        let mut suffixes = self.handler.suffixes();
+        suffixes.retain(|suffix| !self.suffixes.contains(suffix));
+        suffixes
+    }
+
+    fn priority(&self) -> u32 {
+        self.handler.priority()
+    }
+
+    fn description(&self) -> &str {
+        self.handler.description()
+    }
+}
+
+/// String processor of a plugin that leaves the paths another plugin took over alone
+struct OverriddenPaths {
+    processor: Box<dyn StringPostProcessor>,
+    paths: HashSet<String>,
+}
+
+impl StringPostProcessor for OverriddenPaths {
+    fn process_string(&self, value: &str, context: &VariableContext) -> Result<String, ParseError> {
+        let path = context
+            .current_object_path
+            .iter()
+            .map(UclKey::as_str)
+            .collect::<Vec<_>>()
+            .join(".");
+        if self.paths.contains(&path) {
+            return Ok(value.to_string());
+        }
+        self.processor.process_string(value, context)
+    }
+
+    fn paths(&self) -> Vec<String> {
+        let mut paths = self.processor.paths();
+        paths.retain(|path| !self.paths.contains(path));
+        paths
+    }
+
+    fn priority(&self) -> u32 {
+        self.processor.priority()
+    }
+
+    fn description(&self) -> &str {
+        self.processor.description()
+    }
+}
+
+/// Builder for creating a parser with plugins
+pub struct UclParserBuilder<'a> {
+    input: &'a str,
+    lexer_config: Option<LexerConfig>,
+    parser_config: Option<ParserConfig>,
+    variable_handler: Option<Box<dyn VariableHandler>>,
+    plugin_registry: Option<PluginRegistry>,
+    overrides: Vec<String>,
+    key_transforms: Vec<Box<dyn KeyTransformHook>>,
+}
+
+impl<'a> UclParserBuilder<'a> {
+    /// Creates a new parser builder
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            lexer_config: None,
+            parser_config: None,
+            variable_handler: None,
+            plugin_registry: None,
+            overrides: Vec::new(),
+            key_transforms: Vec::new(),
+        }
+    }
+
+    /// Sets the lexer configuration
+    pub fn with_lexer_config(mut self, config: LexerConfig) -> Self {
+        self.lexer_config = Some(config);
+        self
+    }
+
+    /// Sets the parser configuration
+    pub fn with_parser_config(mut self, config: ParserConfig) -> Self {
+        self.parser_config = Some(config);
+        self
+    }
+
+    /// Sets the variable handler
+    pub fn with_variable_handler(mut self, handler: Box<dyn VariableHandler>) -> Self {
+        self.variable_handler = Some(handler);
+        self
+    }
+
+    /// Sets the plugin registry
+    pub fn with_plugin_registry(mut self, registry: PluginRegistry) -> Self {
+        self.plugin_registry = Some(registry);
+        self
+    }
+
+    /// Adds a plugin to the registry
+    pub fn with_plugin(mut self, plugin: Box<dyn UclPlugin>) -> Result<Self, ParseError> {
+        if self.plugin_registry.is_none() {
+            self.plugin_registry = Some(PluginRegistry::new());
+        }
+
+        if let Some(ref mut registry) = self.plugin_registry {
+            registry.register_plugin(plugin)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Adds a key transform, applied along with those of the plugins in priority order
+    pub fn with_key_transform(mut self, hook: Box<dyn KeyTransformHook>) -> Self {
+        self.key_transforms.push(hook);
+        self
+    }
+
+    /// Lets the named plugin take over suffixes and paths it shares with other plugins
+    ///
+    /// See `PluginRegistry::allow_override`.
+    pub fn allow_override(mut self, plugin: impl Into<String>) -> Self {
+        self.overrides.push(plugin.into());
+        self
+    }
+
+    /// Builds the parser
+    ///
+    /// Fails with `ParseError::PluginConflict` if two plugins read the same
+    /// number suffix or rewrite strings at the same key path, and neither is
+    /// allowed to override the other.
+    pub fn build(self) -> Result<UclParser<'a>, ParseError> {
+        let input = self.input;
+        Ok(self.build_configured()?.parser(input))
+    }
+
+    /// Builds a parser configuration that can be shared across threads
+    ///
+    /// The builder's input is ignored; pass each document to
+    /// [`ConfiguredParser::parse`] instead.
+    pub fn build_shared(self) -> Result<Arc<ConfiguredParser>, ParseError> {
+        self.build_configured().map(Arc::new)
+    }
+
+    /// Initializes plugins and resolves the variable handler chain
+    fn build_configured(self) -> Result<ConfiguredParser, ParseError> {
+        let mut hooks = ParsingHooks::new();
+        let mut variable_handler = self.variable_handler;
+
+        if let Some(mut registry) = self.plugin_registry {
+            for plugin in self.overrides {
+                registry.allow_override(plugin);
+            }
+            // Initialize the registry and get parsing hooks
+            hooks = registry.initialize()?;
+
+            // Set up chained variable handler if we have plugin handlers
+            let plugin_handlers = registry.get_variable_handlers();
+            if !plugin_handlers.is_empty() {
+                let mut chained = ChainedVariableHandler::new();
+
+                // Add plugin handlers first
+                for handler in plugin_handlers {
+                    chained.add_handler(handler);
+                }
+
+                // Add user-provided handler last (highest priority)
+                if let Some(user_handler) = variable_handler {
+                    chained.add_handler(user_handler);
+                }
+
+                variable_handler = Some(Box::new(chained));
+            }
+        }
+        for hook in self.key_transforms {
+            hooks.add_key_transform(hook);
+        }
+
+        let mut lexer_config = self.lexer_config.unwrap_or_default();
+        lexer_config
+            .shadowed_suffixes
+            .extend(hooks.shadowed_suffixes());
+
+        Ok(ConfiguredParser {
+            lexer_config,
+            parser_config: self.parser_config.unwrap_or_default(),
+            variable_handler: variable_handler.map(Arc::from),
+            number_suffix_handlers: hooks
+                .number_suffix_handlers
+                .into_iter()
+                .map(Arc::from)
+                .collect(),
+            string_processors: hooks.string_processors.into_iter().map(Arc::from).collect(),
+            validation_hooks: hooks.validation_hooks.into_iter().map(Arc::from).collect(),
+            key_transforms: hooks.key_transforms.into_iter().map(Arc::from).collect(),
+        })
+    }
+}
+
+/// Parser configuration with initialized plugins, shareable across threads
+///
+/// Each call to [`ConfiguredParser::parse`] creates a fresh parser that
+/// borrows the shared handlers, so a single instance can parse many documents
+/// concurrently.
+pub struct ConfiguredParser {
+    lexer_config: LexerConfig,
+    parser_config: ParserConfig,
+    variable_handler: Option<Arc<dyn VariableHandler>>,
+    number_suffix_handlers: Vec<Arc<dyn NumberSuffixHandler>>,
+    string_processors: Vec<Arc<dyn StringPostProcessor>>,
+    validation_hooks: Vec<Arc<dyn ValidationHook>>,
+    key_transforms: Vec<Arc<dyn KeyTransformHook>>,
+}
+
+impl ConfiguredParser {
+    /// Parses a complete document
+    pub fn parse(&self, input: &str) -> Result<UclValue, ParseError> {
+        self.parser(input).parse_document()
+    }
+
+    /// Creates a parser for the given input using the shared configuration
+    pub fn parser<'a>(&self, input: &'a str) -> UclParser<'a> {
+        let mut parser = UclParser::with_lexer_config(input, self.lexer_config.clone())
+            .with_config(self.parser_config.clone());
+
+        parser.variable_handler = self
+            .variable_handler
+            .clone()
+            .map(|handler| VariableHandlerSlot::Owned(Box::new(handler)));
+        parser.set_parsing_hooks(ParsingHooks {
+            number_suffix_handlers: self
+                .number_suffix_handlers
+                .iter()
+                .map(|handler| Box::new(Arc::clone(handler)) as Box<dyn NumberSuffixHandler>)
+                .collect(),
+            string_processors: self
+                .string_processors
+                .iter()
+                .map(|processor| Box::new(Arc::clone(processor)) as Box<dyn StringPostProcessor>)
+                .collect(),
+            validation_hooks: self
+                .validation_hooks
+                .iter()
+                .map(|hook| Box::new(Arc::clone(hook)) as Box<dyn ValidationHook>)
+                .collect(),
+            key_transforms: self
+                .key_transforms
+                .iter()
+                .map(|hook| Box::new(Arc::clone(hook)) as Box<dyn KeyTransformHook>)
+                .collect(),
+        });
+        parser
+    }
+
+    /// Returns the lexer configuration
+    pub fn lexer_config(&self) -> &LexerConfig {
+        &self.lexer_config
+    }
+
+    /// Returns the parser configuration
+    pub fn parser_config(&self) -> &ParserConfig {
+        &self.parser_config
+    }
+}
+
+/// Built-in example implementations
+/// Example number suffix handler for custom units
+pub struct CustomUnitSuffixHandler {
+    units: HashMap<String, f64>,
+    priority: u32,
+}
+
+impl CustomUnitSuffixHandler {
+    /// Creates a new custom unit suffix handler
+    pub fn new() -> Self {
+        let mut units = HashMap::new();
+        // Example: custom units for data processing
+        units.insert("px".to_string(), 1.0); // pixels
+        units.insert("pt".to_string(), 1.333); // points to pixels
+        units.insert("em".to_string(), 16.0); // em to pixels (assuming 16px base)
+        units.insert("rem".to_string(), 16.0); // rem to pixels
+
+        Self {
+            units,
+            priority: 100,
+        }
+    }
+
+    /// Adds a custom unit
+    pub fn add_unit(&mut self, suffix: String, multiplier: f64) {
+        self.units.insert(suffix, multiplier);
+    }
+
+    /// Sets the priority of this handler
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for CustomUnitSuffixHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumberSuffixHandler for CustomUnitSuffixHandler {
+    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
+        self.units.get(suffix).copied()
+    }
+
+    fn suffixes(&self) -> Vec<String> {
+        self.units.keys().cloned().collect()
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn description(&self) -> &str {
+        "Custom unit suffix handler (px, pt, em, rem)"
+    }
+}
+
+/// Example string post-processor for path normalization
+pub struct PathNormalizationProcessor {
+    priority: u32,
+}
+
+impl PathNormalizationProcessor {
+    /// Creates a new path normalization processor
+    pub fn new() -> Self {
+        Self { priority: 50 }
+    }
+
+    /// Sets the priority of this processor
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for PathNormalizationProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringPostProcessor for PathNormalizationProcessor {
+    fn process_string(
+        &self,
+        value: &str,
+        _context: &VariableContext,
+    ) -> Result<String, ParseError> {
+        // Normalize path separators and resolve relative paths
+        let normalized = value
+            .replace('\\', "/") // Normalize to forward slashes
+            .replace("//", "/"); // Remove double slashes
+
+        // Basic relative path resolution
+        let parts: Vec<&str> = normalized.split('/').collect();
+        let mut resolved = Vec::new();
+
+        for part in parts {
+            match part {
+                "." | "" => continue, // Skip current directory and empty parts
+                ".." => {
+                    if !resolved.is_empty() && resolved.last() != Some(&"..") {
+                        resolved.pop();
+                    } else {
+                        resolved.push(part);
+                    }
+                }
+                _ => resolved.push(part),
+            }
+        }
+
+        Ok(resolved.join("/"))
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn description(&self) -> &str {
+        "Path normalization processor (resolves ./ and ../, normalizes separators)"
+    }
+}
+
+/// Example validation hook for schema validation
+pub struct SchemaValidationHook {
+    required_keys: Vec<String>,
+    allowed_keys: Option<Vec<String>>,
+    priority: u32,
+}
+
+impl SchemaValidationHook {
+    /// Creates a new schema validation hook
+    pub fn new() -> Self {
+        Self {
+            required_keys: Vec::new(),
+            allowed_keys: None,
+            priority: 100,
+        }
+    }
+
+    /// Adds a required key
+    pub fn require_key(mut self, key: String) -> Self {
+        self.required_keys.push(key);
+        self
+    }
+
+    /// Sets the allowed keys (if None, all keys are allowed)
+    pub fn allow_keys(mut self, keys: Vec<String>) -> Self {
+        self.allowed_keys = Some(keys);
+        self
+    }
+
+    /// Sets the priority of this hook
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl Default for SchemaValidationHook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ValidationHook for SchemaValidationHook {
+    fn validate_value(
+        &self,
+        value: &UclValue,
+        context: &VariableContext,
+    ) -> Result<Option<UclValue>, ParseError> {
+        match value {
+            UclValue::Object(obj) => {
+                // Check required keys
+                for required_key in &self.required_keys {
+                    if !obj.contains_key(required_key.as_str()) {
+                        return Err(ParseError::InvalidObject {
+                            message: format!("Missing required key '{}'", required_key),
+                            position: context.position,
+                        });
+                    }
+                }
+
+                // Check allowed keys
+                if let Some(allowed) = &self.allowed_keys {
+                    for key in obj.keys() {
+                        if !allowed.iter().any(|allowed_key| key == allowed_key) {
+                            return Err(ParseError::InvalidObject {
+                                message: format!("Key '{}' is not allowed", key),
+                                position: context.position,
+                            });
+                        }
+                    }
+                }
+
+                Ok(None) // No modification needed
+            }
+            _ => Ok(None), // Only validate objects
+        }
+    }
+
+    fn validate_key(
+        &self,
+        key: &str,
+        context: &VariableContext,
+    ) -> Result<Option<String>, ParseError> {
+        if let Some(allowed) = &self.allowed_keys
+            && !allowed.contains(&key.to_string())
+        {
+            return Err(ParseError::InvalidObject {
+                message: format!("Key '{}' is not allowed", key),
+                position: context.position,
+            });
+        }
+        Ok(None) // No modification needed
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn description(&self) -> &str {
+        "Schema validation hook (checks required and allowed keys)"
+    }
+}
+
+/// Example key transform that strips a legacy prefix, such as `x-`, from keys
+pub struct KeyPrefixStripper {
+    prefix: String,
+    priority: u32,
+}
+
+impl KeyPrefixStripper {
+    /// Creates a key transform stripping `prefix`
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            priority: 100,
+        }
+    }
+
+    /// Sets the priority of this hook
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+impl KeyTransformHook for KeyPrefixStripper {
+    fn transform_key<'k>(
+        &self,
+        key: &'k str,
+        _path: &[&str],
+        _span: Span,
+    ) -> Result<Cow<'k, str>, ParseError> {
+        Ok(Cow::Borrowed(
+            key.strip_prefix(self.prefix.as_str()).unwrap_or(key),
+        ))
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn description(&self) -> &str {
+        "Key prefix stripper (renames legacy keys)"
+    }
+}
+
+/// Example plugin implementations
+/// Example plugin for CSS-like units
+pub struct CssUnitsPlugin {
+    name: String,
+    version: String,
+    priority: u32,
+}
+
+impl CssUnitsPlugin {
+    /// Creates a new CSS units plugin
+    pub fn new() -> Self {
+        Self {
+            name: "css-units".to_string(),
+            version: "1.0.0".to_string(),
+            priority: 100,
+        }
+    }
+}
+
+impl Default for CssUnitsPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UclPlugin for CssUnitsPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn description(&self) -> &str {
+        "Adds support for CSS-like units (px, pt, em, rem, %, vh, vw)"
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
+        vec![Box::new(
+            CustomUnitSuffixHandler::new().with_priority(self.priority),
+        )]
+    }
+}
+
+/// A family of interchangeable units, each with a conversion factor to the base unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitFamily {
+    name: String,
+    units: IndexMap<String, f64>,
+}
+
+impl UnitFamily {
+    /// Creates an empty unit family
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            units: IndexMap::new(),
+        }
+    }
+
+    /// Adds a unit worth `factor` base units
+    pub fn with_unit(mut self, unit: impl Into<String>, factor: f64) -> Self {
+        self.units.insert(unit.into(), factor);
+        self
+    }
+
+    /// CSS lengths in pixels: `px`, `pt`, `em` and `rem` (16px)
+    pub fn length() -> Self {
+        Self::new("length")
+            .with_unit("px", 1.0)
+            .with_unit("pt", 4.0 / 3.0)
+            .with_unit("em", 16.0)
+            .with_unit("rem", 16.0)
+    }
+
+    /// Frequencies in hertz: `hz`, `khz`, `mhz` and `ghz`
+    pub fn frequency() -> Self {
+        Self::new("frequency")
+            .with_unit("hz", 1.0)
+            .with_unit("khz", 1e3)
+            .with_unit("mhz", 1e6)
+            .with_unit("ghz", 1e9)
+    }
+
+    /// Percentages as fractions: `50%` is `0.5`
+    pub fn percentage() -> Self {
+        Self::new("percentage").with_unit("%", 0.01)
+    }
+
+    /// Returns the family name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the conversion factor of a unit
+    pub fn factor(&self, unit: &str) -> Option<f64> {
+        self.units.get(unit).copied()
+    }
+
+    /// Returns the units of this family in registration order
+    pub fn units(&self) -> impl Iterator<Item = &str> {
+        self.units.keys().map(String::as_str)
+    }
+}
+
+/// How a unit family treats units that are also built-in time or size suffixes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinSuffixes {
+    /// The lexer keeps reading built-in suffixes, so `10m` stays ten million
+    Defer,
+    /// The family's units replace built-in suffixes, so `10m` reaches the family
+    Shadow,
+}
+
+/// How parsed quantities are represented
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuantityOutput {
+    /// A float in the family's base unit, so `2em` becomes `32.0`
+    #[default]
+    Normalized,
+    /// An object with the number and unit as written, so `2em` becomes `{ value = 2.0, unit = "em" }`
+    Structured,
+}
+
+/// Number suffix handler that converts quantities of the registered unit families
+#[derive(Debug, Clone)]
+pub struct QuantitySuffixHandler {
+    families: Vec<(UnitFamily, BuiltinSuffixes)>,
+    key_families: HashMap<String, String>,
+    output: QuantityOutput,
+    priority: u32,
+}
+
+impl QuantitySuffixHandler {
+    /// Creates a handler without any unit families
+    pub fn new() -> Self {
+        Self {
+            families: Vec::new(),
+            key_families: HashMap::new(),
+            output: QuantityOutput::default(),
+            priority: 100,
+        }
+    }
+
+    /// Returns the registered family with the given name
+    fn family(&self, name: &str) -> Option<&UnitFamily> {
+        self.families
+            .iter()
+            .map(|(family, _)| family)
+            .find(|family| family.name() == name)
+    }
+
+    /// Builds the value of a quantity
+    fn quantity(&self, number: f64, unit: &str, factor: f64) -> UclValue {
+        match self.output {
+            QuantityOutput::Normalized => UclValue::Float(number * factor),
+            QuantityOutput::Structured => {
+                let mut object = UclObject::new();
+                object.insert("value".into(), UclValue::Float(number));
+                object.insert("unit".into(), UclValue::String(unit.to_string()));
+                UclValue::Object(object)
+            }
+        }
+    }
+}
+
+impl Default for QuantitySuffixHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NumberSuffixHandler for QuantitySuffixHandler {
+    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
+        self.families
+            .iter()
+            .find_map(|(family, _)| family.factor(suffix))
+    }
+
+    fn apply_suffix(
+        &self,
+        number: f64,
+        suffix: &str,
+        context: &VariableContext,
+        position: Position,
+    ) -> Result<Option<UclValue>, ParseError> {
+        let expected = context
+            .current_object_path
+            .last()
+            .and_then(|key| self.key_families.get(key.as_str()))
+            .and_then(|name| self.family(name));
+
+        match expected {
+            Some(family) => match family.factor(suffix) {
+                Some(factor) => Ok(Some(self.quantity(number, suffix, factor))),
+                None => Err(ParseError::UnknownUnit {
+                    unit: suffix.to_string(),
+                    family: family.name().to_string(),
+                    valid_units: family.units().map(str::to_string).collect(),
+                    position,
+                }),
+            },
+            None => Ok(self
+                .parse_suffix(suffix)
+                .map(|factor| self.quantity(number, suffix, factor))),
+        }
+    }
+
+    fn shadowed_suffixes(&self) -> Vec<String> {
+        self.families
+            .iter()
+            .filter(|(_, builtins)| *builtins == BuiltinSuffixes::Shadow)
+            .flat_map(|(family, _)| family.units().map(str::to_string))
+            .collect()
+    }
+
+    fn suffixes(&self) -> Vec<String> {
+        self.families
+            .iter()
+            .flat_map(|(family, _)| family.units().map(str::to_string))
+            .collect()
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn description(&self) -> &str {
+        "Quantity handler for registered unit families"
+    }
+}
+
+/// Plugin that parses numbers with units, such as `12px` or `2.4ghz`
+///
+/// Units are grouped into families with a conversion factor to the family's
+/// base unit. Binding a key to a family makes any other unit on that key a
+/// positioned error that lists the family's units.
+///
+/// ```rust
+/// use ucl_lexer::{BuiltinSuffixes, QuantityPlugin, UclParserBuilder, UnitFamily};
+///
+/// let plugin = QuantityPlugin::new()
+///     .with_family(UnitFamily::length(), BuiltinSuffixes::Defer)
+///     .with_key_family("width", "length");
+///
+/// let value = UclParserBuilder::new("width = 2em")
+///     .with_plugin(Box::new(plugin))?
+///     .build()?
+///     .parse_document()?;
+/// assert_eq!(value["width"], 32.0);
+/// # Ok::<(), ucl_lexer::ParseError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QuantityPlugin {
+    handler: QuantitySuffixHandler,
+}
+
+impl QuantityPlugin {
+    /// Creates a plugin without any unit families
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a unit family
+    ///
+    /// `builtins` decides whether units that collide with built-in time or size
+    /// suffixes, such as `m`, are read by this family or by the lexer.
+    pub fn with_family(mut self, family: UnitFamily, builtins: BuiltinSuffixes) -> Self {
+        self.handler
+            .families
+            .retain(|(existing, _)| existing.name() != family.name());
+        self.handler.families.push((family, builtins));
+        self
+    }
+
+    /// Requires values of `key` to use units of the named family
+    pub fn with_key_family(mut self, key: impl Into<String>, family: impl Into<String>) -> Self {
+        self.handler.key_families.insert(key.into(), family.into());
+        self
+    }
+
+    /// Sets how parsed quantities are represented
+    pub fn with_output(mut self, output: QuantityOutput) -> Self {
+        self.handler.output = output;
+        self
+    }
+
+    /// Sets the priority of the plugin and its suffix handler
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.handler.priority = priority;
+        self
+    }
+
+    /// Returns the suffix handler configured by this plugin
+    pub fn handler(&self) -> &QuantitySuffixHandler {
+        &self.handler
+    }
+}
+
+impl UclPlugin for QuantityPlugin {
+    fn name(&self) -> &str {
+        "quantity"
+    }
+
+    fn description(&self) -> &str {
+        "Parses numbers with units from registered unit families"
+    }
+
+    fn priority(&self) -> u32 {
+        self.handler.priority
+    }
+
+    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
+        vec![Box::new(self.handler.clone())]
+    }
+}
+
+/// Example plugin for path processing
+pub struct PathProcessingPlugin {
+    name: String,
+    version: String,
+    priority: u32,
+}
+
+impl PathProcessingPlugin {
+    /// Creates a new path processing plugin
+    pub fn new() -> Self {
+        Self {
+            name: "path-processing".to_string(),
+            version: "1.0.0".to_string(),
+            priority: 50,
+        }
+    }
+}
+
+impl Default for PathProcessingPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UclPlugin for PathProcessingPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn description(&self) -> &str {
+        "Provides path normalization and processing for string values"
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn string_processors(&self) -> Vec<Box<dyn StringPostProcessor>> {
+        vec![Box::new(
+            PathNormalizationProcessor::new().with_priority(self.priority),
+        )]
+    }
+}
+
+/// Example plugin for configuration validation
+pub struct ConfigValidationPlugin {
+    name: String,
+    version: String,
+    priority: u32,
+    required_keys: Vec<String>,
+    allowed_keys: Option<Vec<String>>,
+    legacy_prefix: Option<String>,
+}
+
+impl ConfigValidationPlugin {
+    /// Creates a new configuration validation plugin
+    pub fn new() -> Self {
+        Self {
+            name: "config-validation".to_string(),
+            version: "1.0.0".to_string(),
+            priority: 200,
+            required_keys: Vec::new(),
+            allowed_keys: None,
+            legacy_prefix: None,
+        }
+    }
+
+    /// Adds a required key
+    pub fn require_key(mut self, key: String) -> Self {
+        self.required_keys.push(key);
+        self
+    }
+
+    /// Sets allowed keys
+    pub fn allow_keys(mut self, keys: Vec<String>) -> Self {
+        self.allowed_keys = Some(keys);
+        self
+    }
+
+    /// Strips a legacy prefix from keys before they are checked and inserted
+    pub fn strip_key_prefix(mut self, prefix: String) -> Self {
+        self.legacy_prefix = Some(prefix);
+        self
+    }
+}
+
+impl Default for ConfigValidationPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UclPlugin for ConfigValidationPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn description(&self) -> &str {
+        "Validates configuration structure with required and allowed keys"
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn configure(&mut self, config: &PluginConfig) -> Result<(), ParseError> {
+        // Configure required keys from plugin config
+        if let Some(required) = config.get("required_keys") {
+            self.required_keys = required.split(',').map(|s| s.trim().to_string()).collect();
+        }
+
+        // Configure allowed keys from plugin config
+        if let Some(allowed) = config.get("allowed_keys") {
+            self.allowed_keys = Some(allowed.split(',').map(|s| s.trim().to_string()).collect());
+        }
+
+        // Configure the legacy key prefix from plugin config
+        if let Some(prefix) = config.get("legacy_key_prefix") {
+            self.legacy_prefix = Some(prefix.trim().to_string());
+        }
+
+        Ok(())
+    }
+
+    fn validation_hooks(&self) -> Vec<Box<dyn ValidationHook>> {
+        let mut hook = SchemaValidationHook::new().with_priority(self.priority);
+
+        for key in &self.required_keys {
+            hook = hook.require_key(key.clone());
+        }
+
+        if let Some(ref allowed) = self.allowed_keys {
+            hook = hook.allow_keys(allowed.clone());
+        }
+
+        vec![Box::new(hook)]
+    }
+
+    fn key_transforms(&self) -> Vec<Box<dyn KeyTransformHook>> {
+        self.legacy_prefix
+            .iter()
+            .map(|prefix| {
+                Box::new(KeyPrefixStripper::new(prefix.as_str()).with_priority(self.priority))
+                    as Box<dyn KeyTransformHook>
+            })
+            .collect()
+    }
+}
+
+/// Syntax style detection for NGINX-style implicit syntax
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntaxStyle {
+    /// Explicit syntax with separators (key = value or key: value)
+    Explicit,
+    /// Implicit syntax without separators (key value or key { ... })
+    Implicit,
+    /// NGINX-style nested syntax (key identifier { ... })
+    NginxNested,
+}
+
+/// Source name recorded in origins when none is set
+const DEFAULT_SOURCE_NAME: &str = "<input>";
+
+/// Directive setting the merge priority of a document, as in libucl
+const PRIORITY_DIRECTIVE: &str = ".priority";
+
+/// Directive including other files, as in libucl
+const INCLUDE_DIRECTIVE: &str = ".include";
+
+/// How an entry's value combines with an earlier value for the same key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assignment {
+    /// `key = value`, subject to the duplicate key behavior
+    Replace,
+    /// `key += value`, appending to arrays and merging objects
+    Append,
+}
+
+/// An explicit `=` or `:` after a key, with its position
+type Separator = (char, Position);
+
+/// Where a run of `,` and `;` between two entries or elements appears
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeparatorContext {
+    /// Between the entries of a document without enclosing braces
+    Document,
+    /// Between the entries of an object in braces
+    Object,
+    /// After element `index - 1` of an array
+    Array { index: usize },
+}
+
+impl SeparatorContext {
+    /// Returns whether `separator` may follow the separators already read in its run
+    ///
+    /// As in libucl, `;` ends an entry, so any number of them is ignored,
+    /// while a `,` only separates two entries. Array elements are separated
+    /// by exactly one of either. A separator before the first entry or
+    /// element is rejected before this applies.
+    ///
+    /// | context          | first in run | after `;` only | after a `,` |
+    /// |------------------|--------------|----------------|-------------|
+    /// | document, object | `,` `;`      | `,` `;`        | `;`         |
+    /// | array            | `,` `;`      | none           | none        |
+    fn accepts(self, separator: char, run: &[char]) -> bool {
+        match self {
+            SeparatorContext::Document | SeparatorContext::Object => {
+                separator == ';' || !run.contains(&',')
+            }
+            SeparatorContext::Array { .. } => run.is_empty(),
+        }
+    }
+
+    /// Name of the container, for errors
+    fn container(self) -> &'static str {
+        match self {
+            SeparatorContext::Document => "document",
+            SeparatorContext::Object => "object",
+            SeparatorContext::Array { .. } => "array",
+        }
+    }
+}
+
+/// A variable handler owned by a parser or borrowed from the caller
+enum VariableHandlerSlot<'a> {
+    Owned(Box<dyn VariableHandler>),
+    Borrowed(&'a dyn VariableHandler),
+}
+
+impl VariableHandlerSlot<'_> {
+    fn get(&self) -> &dyn VariableHandler {
+        match self {
+            VariableHandlerSlot::Owned(handler) => handler.as_ref(),
+            VariableHandlerSlot::Borrowed(handler) => *handler,
+        }
+    }
+}
+
+/// UCL parser that consumes tokens and builds structured data
+pub struct UclParser<'a> {
+    lexer: UclLexer<'a>,
+    variable_handler: Option<VariableHandlerSlot<'a>>,
+    current_token: Option<Token<'a>>,
+    current_token_start: Option<Position>,
+    current_token_end: Option<Position>,
+    /// End of the token before the current one
+    previous_token_end: Option<Position>,
+    config: ParserConfig,
+    current_depth: usize,
+    parsing_hooks: ParsingHooks,
+    diagnostics: Vec<Diagnostic>,
+    source_name: String,
+    provenance: Provenance,
+    /// Positions of the dotted keys that created object levels, by full key path
+    dotted_keys: HashMap<Vec<String>, Position>,
+    /// Positions of the named sections that created object levels, by full key path
+    section_labels: HashMap<Vec<String>, Position>,
+    lenient_errors: Option<Vec<UclError>>,
+    /// Tokens left to read before the cancellation flag is checked again
+    cancellation_countdown: usize,
+    counters: ParseCounters,
+    priority: Option<u8>,
+    /// Position of the `.priority` directive while no merge has used it
+    unused_priority: Option<Position>,
+    /// Reads the files of `.include` directives, which are ordinary keys without one
+    include_resolver: Option<Arc<dyn IncludeResolver>>,
+    /// Include directives of the document, resolved once it is parsed
+    includes: Vec<PendingInclude>,
+    /// Number of files that include this one, directly or through other includes
+    include_depth: usize,
+    /// Files read for the includes of the whole document, shared with included files
+    included: IncludeTotals,
+    lint_state: LintState,
+    directive_state: DirectiveState,
+    anchors: AnchorState,
+    /// Buffers of a previous tree, reused by `parse_into`
+    pool: RefCell<ValuePool>,
+    /// Error from lexing the first token, reported when parsing starts
+    initial_error: Option<ParseError>,
+    /// Set by `validate`, which checks the grammar without keeping string
+    /// contents or array elements
+    validating: bool,
+    /// Input slices of the string values read unchanged, with the address of
+    /// the value's text; recorded for `UclDeserializer` only
+    string_sources: Option<Vec<(usize, &'a str)>>,
+    /// Byte offset of the content the last parse stopped at, with
+    /// `ParserConfig::allow_trailing_content`
+    trailing_content_offset: Option<usize>,
+    /// Set while a dotted key is read, whose segments are transformed after splitting
+    reading_dotted_key: bool,
+    /// Set while a bracketed root is parsed with `ParserConfig::allow_trailing_content`,
+    /// so that input after it that does not lex ends the document
+    root_ends_at_lex_error: bool,
+}
+
+/// Counters collected while parsing a document
+///
+/// Token, comment, string and nesting counts come from the lexer; the rest
+/// are counted by the parser. All counters are plain integers and are always
+/// collected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseStats {
+    /// Number of tokens produced by the lexer, including comments and end of file
+    pub tokens: usize,
+    /// Deepest nesting of objects and arrays reached
+    pub peak_nesting_depth: usize,
+    /// Number of strings that needed variable expansion
+    pub strings_expanded: usize,
+    /// Number of variable references replaced by a value
+    pub variables_expanded: usize,
+    /// Number of string and key tokens borrowed from the input
+    pub zero_copy_strings: usize,
+    /// Number of string and key tokens that needed an owned copy
+    pub owned_strings: usize,
+    /// Number of comments skipped
+    pub comments_skipped: usize,
+    /// Number of keys defined more than once in the same object
+    pub duplicate_keys_encountered: usize,
+    /// Number of input bytes consumed by the lexer
+    pub bytes_processed: usize,
+    /// Time spent in `parse_document`
+    pub duration: Duration,
+}
+
+/// Parser-side counters behind `ParseStats`
+///
+/// Expansion counters are cells because variable expansion only borrows the parser.
+#[derive(Debug, Default)]
+struct ParseCounters {
+    strings_expanded: Cell<usize>,
+    variables_expanded: Cell<usize>,
+    duplicate_keys: usize,
+    duration: Duration,
+}
+
+impl ParseCounters {
+    /// Adds one to a counter
+    fn increment(cell: &Cell<usize>) {
+        cell.set(cell.get() + 1);
+    }
+}
+
+/// Keys of an entry; more than one only for named sections such as `section foo bar {}`
+type KeyPath = SmallVec<[UclKey; 1]>;
+
+/// Positions of the keys of an object being parsed, by entry index
+type KeyPositions = SmallVec<[Position; 8]>;
+
+/// Strings, objects and arrays taken from a previous tree, handed out while parsing
+///
+/// Buffers are returned in the order the tree held them, which is the order the
+/// parser asks for them, so reparsing a document of the same shape finds a
+/// buffer of the right capacity at each step.
+#[derive(Debug, Default)]
+struct ValuePool {
+    strings: Vec<String>,
+    objects: Vec<UclObject>,
+    /// Emptied `UclValue::Array` values, kept whole so their box is reused too
+    arrays: Vec<UclValue>,
+}
+
+impl ValuePool {
+    /// Takes a tree apart, keeping the allocations of its containers and strings
+    fn recycle(&mut self, value: UclValue) {
+        self.collect(value);
+        self.strings.reverse();
+        self.objects.reverse();
+        self.arrays.reverse();
+    }
+
+    /// Collects the buffers of a value, containers before their contents
+    fn collect(&mut self, mut value: UclValue) {
+        match &mut value {
+            UclValue::Array(array) => {
+                let mut items = std::mem::take(&mut **array);
+                let slot = self.arrays.len();
+                self.arrays.push(value);
+                for item in items.drain(..) {
+                    self.collect(item);
+                }
+                if let UclValue::Array(array) = &mut self.arrays[slot] {
+                    **array = items;
+                }
+            }
+            UclValue::String(text) => self.strings.push(std::mem::take(text)),
+            UclValue::Object(object) => {
+                let mut object = std::mem::take(object);
+                let slot = self.objects.len();
+                self.objects.push(UclObject::new());
+                for (key, value) in object.drain(..) {
+                    // Short keys are stored inline and have no buffer to keep
+                    if !key.is_inline() {
+                        self.strings.push(key.into());
+                    }
+                    self.collect(value);
+                }
+                self.objects[slot] = object;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns a copy of `text`, written into a recycled buffer when there is one
+    fn string(&mut self, text: &str) -> String {
+        match self.strings.pop() {
+            Some(mut buffer) => {
+                buffer.clear();
+                buffer.push_str(text);
+                buffer
+            }
+            None => text.to_string(),
+        }
+    }
+
+    /// Returns an empty object, keeping the capacity of a recycled one
+    fn object(&mut self) -> UclObject {
+        self.objects.pop().unwrap_or_default()
+    }
+
+    /// Returns an empty `UclValue::Array`, keeping the capacity of a recycled one
+    fn array(&mut self) -> UclValue {
+        self.arrays
+            .pop()
+            .unwrap_or_else(|| UclValue::Array(Box::default()))
+    }
+
+    /// Frees the buffers the last document did not need
+    fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Document-wide facts collected for lints that are reported after parsing
+#[derive(Debug, Default)]
+struct LintState {
+    /// First position at which each key name held a scalar
+    scalar_keys: HashMap<String, Position>,
+    /// Keys assigned an empty object, with the position of the key
+    empty_objects: Vec<(String, Position)>,
+}
+
+/// Directives read from comments, waiting for or attached to their keys
+#[derive(Debug, Default)]
+struct DirectiveState {
+    /// Directives read since the last key, with the position of their comment
+    pending: Vec<(Position, DirectiveKind)>,
+    /// Offset of the last comment read, so lookahead does not read it twice
+    last_comment_offset: Option<usize>,
+    /// Directives attached to a key
+    attached: Vec<Directive>,
+    /// Suppressed lints with the byte range of the entry they apply to
+    suppressions: Vec<(Vec<LintCode>, Range<usize>)>,
+}
+
+/// Values registered with `&name` while parsing a document
+#[derive(Debug, Default)]
+struct AnchorState {
+    /// Anchored values by name, with the position of their `&name` and
+    /// the number of values a reference copies
+    defined: HashMap<String, (UclValue, Position, usize)>,
+    /// Names of anchors whose value is still being parsed, innermost last
+    open: Vec<String>,
+    /// Values copied by references so far, checked against `ParserConfig::max_alias_expansion`
+    copied: usize,
+}
+
+/// Returns the number of values in `value`, counting it and every value nested in it
+fn count_values(value: &UclValue) -> usize {
+    1 + match value {
+        UclValue::Object(object) => object.values().map(count_values).sum(),
+        UclValue::Array(array) => array.iter().map(count_values).sum(),
+        _ => 0,
+    }
+}
+
+impl<'a> UclParser<'a> {
+    /// Creates a new parser with default configuration
+    pub fn new(input: &'a str) -> Self {
+        Self::from_lexer(UclLexer::new(input))
+    }
+
+    /// Creates a parser with custom lexer configuration
+    pub fn with_lexer_config(input: &'a str, lexer_config: LexerConfig) -> Self {
+        Self::from_lexer(UclLexer::with_config(input, lexer_config))
+    }
+
+    /// Creates a parser over input split into fragments, without joining them
+    ///
+    /// See [`UclLexer::from_fragments`] for how tokens across fragment
+    /// boundaries are read.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{UclParser, UclValue};
+    ///
+    /// let header = "server {\n  port = 80";
+    /// let body = "80\n}\n";
+    /// let value = UclParser::from_fragments(&[header, body]).parse_document()?;
+    /// assert_eq!(value["server"]["port"], UclValue::Integer(8080));
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn from_fragments(fragments: &[&'a str]) -> Self {
+        Self::from_lexer(UclLexer::from_fragments(fragments))
+    }
+
+    /// Creates a parser over input split into fragments with custom lexer configuration
+    pub fn from_fragments_with_lexer_config(
+        fragments: &[&'a str],
+        lexer_config: LexerConfig,
+    ) -> Self {
+        Self::from_lexer(UclLexer::from_fragments_with_config(
+            fragments,
+            lexer_config,
+        ))
+    }
+
+    /// Creates a parser that reads tokens from `lexer`
+    fn from_lexer(lexer: UclLexer<'a>) -> Self {
+        let mut parser = Self {
+            lexer,
+            variable_handler: None,
+            current_token: None,
+            current_token_start: None,
+            current_token_end: None,
+            previous_token_end: None,
+            config: ParserConfig::default(),
+            current_depth: 0,
+            parsing_hooks: ParsingHooks::new(),
+            diagnostics: Vec::new(),
+            source_name: DEFAULT_SOURCE_NAME.to_string(),
+            provenance: Provenance::new(),
+            dotted_keys: HashMap::new(),
+            section_labels: HashMap::new(),
+            lenient_errors: None,
+            cancellation_countdown: cancellation::CHECK_INTERVAL,
+            counters: ParseCounters::default(),
+            priority: None,
+            unused_priority: None,
+            include_resolver: None,
+            includes: Vec::new(),
+            include_depth: 0,
+            included: IncludeTotals::default(),
+            lint_state: LintState::default(),
+            directive_state: DirectiveState::default(),
+            anchors: AnchorState::default(),
+            pool: RefCell::default(),
+            initial_error: None,
+            validating: false,
+            string_sources: None,
+            trailing_content_offset: None,
+            reading_dotted_key: false,
+            root_ends_at_lex_error: false,
+        };
+
+        // Load the first token
+        parser.initial_error = parser.advance_token().err();
+        parser
+    }
+
+    /// Creates a parser with a variable handler
+    pub fn with_variable_handler(input: &'a str, handler: Box<dyn VariableHandler>) -> Self {
+        let mut parser = Self::new(input);
+        parser.variable_handler = Some(VariableHandlerSlot::Owned(handler));
+        parser
+    }
+
+    /// Creates a parser that borrows a variable handler
+    ///
+    /// One handler can serve any number of parsers, including parsers on other
+    /// threads, without being boxed or cloned for each.
+    pub fn with_variable_handler_ref(input: &'a str, handler: &'a dyn VariableHandler) -> Self {
+        let mut parser = Self::new(input);
+        parser.variable_handler = Some(VariableHandlerSlot::Borrowed(handler));
+        parser
+    }
+
+    /// Creates a parser with custom parsing hooks
+    pub fn with_parsing_hooks(input: &'a str, hooks: ParsingHooks) -> Self {
+        let mut parser = Self::new(input);
+        parser.set_parsing_hooks(hooks);
+        parser
+    }
+
+    /// Creates a parser with both variable handler and parsing hooks
+    pub fn with_variable_handler_and_hooks(
+        input: &'a str,
+        handler: Box<dyn VariableHandler>,
+        hooks: ParsingHooks,
+    ) -> Self {
+        let mut parser = Self::new(input);
+        parser.variable_handler = Some(VariableHandlerSlot::Owned(handler));
+        parser.set_parsing_hooks(hooks);
+        parser
+    }
+
+    /// Sets the parser configuration
+    pub fn with_config(mut self, config: ParserConfig) -> Self {
+        self.config = config;
+        // Directives are read from comment tokens, which the lexer only keeps on
+        // request, and anchors from tokens it only produces on request. JSON
+        // input needs comments as tokens to reject them, and its first token
+        // checked again.
+        let lexer = self.lexer.config();
+        let save_comments =
+            lexer.save_comments || self.config.directives.is_some() || self.config.json_only;
+        let anchors = lexer.anchors || self.config.enable_anchors;
+        if save_comments != lexer.save_comments || anchors != lexer.anchors || self.config.json_only
+        {
+            let lexer_config = LexerConfig {
+                save_comments,
+                anchors,
+                ..lexer.clone()
+            };
+            self.lexer = self.lexer.restarted(lexer_config);
+            self.initial_error = self.advance_token().err();
+        }
+        self
+    }
+
+    /// Returns the parser configuration
+    pub fn config(&self) -> &ParserConfig {
+        &self.config
+    }
+
+    /// Returns a mutable reference to the parser configuration
+    pub(crate) fn config_mut(&mut self) -> &mut ParserConfig {
+        &mut self.config
+    }
+
+    /// Returns what a bare `m` suffix means to the lexer
+    pub(crate) fn m_suffix_meaning(&self) -> MSuffix {
+        self.lexer.config().m_suffix_meaning
+    }
+
+    /// Reads a bare `5m` as the word `5m` instead of rejecting it with `MSuffix::Error`
+    ///
+    /// Deserializers leave the suffix to the target type this way, so a
+    /// duration field reads minutes and a number field reports the ambiguity.
+    pub(crate) fn keep_ambiguous_suffixes(&mut self) {
+        let lexer = self.lexer.config();
+        if lexer.m_suffix_meaning == MSuffix::Error && !lexer.keep_ambiguous_suffixes {
+            let lexer_config = LexerConfig {
+                keep_ambiguous_suffixes: true,
+                ..lexer.clone()
+            };
+            self.lexer = self.lexer.restarted(lexer_config);
+            self.initial_error = self.advance_token().err();
+        }
+    }
+
+    /// Records where string values read unchanged from the input came from
+    ///
+    /// Lets a deserializer lend `&'a str` for strings that needed neither
+    /// unescaping nor variable expansion.
+    pub(crate) fn keep_string_sources(&mut self) {
+        self.string_sources.get_or_insert_with(Vec::new);
+    }
+
+    /// Takes the slices recorded since `keep_string_sources`, sorted by the address of the value's text
+    ///
+    /// An address recorded twice, after the first value was dropped, keeps
+    /// the later slice.
+    pub(crate) fn take_string_sources(&mut self) -> Vec<(usize, &'a str)> {
+        let mut sources = self
+            .string_sources
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+        sources.reverse();
+        sources.sort_by_key(|(address, _)| *address);
+        sources.dedup_by_key(|(address, _)| *address);
+        sources
+    }
+
+    /// Sets the source name recorded in value origins, such as a file name
+    pub fn with_source_name(mut self, name: impl Into<String>) -> Self {
+        self.source_name = name.into();
+        self
+    }
+
+    /// Reads `.include` directives, with their files read through `resolver`
+    ///
+    /// See the [`include`](crate::include) module for the directive and its
+    /// parameters. Included files are parsed with this parser's configuration
+    /// and variable handler.
+    pub fn with_include_resolver(mut self, resolver: impl IncludeResolver + 'static) -> Self {
+        self.include_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Returns the byte offset where the last parse stopped before the end of its input
+    ///
+    /// This is set only with `ParserConfig::allow_trailing_content`, when
+    /// content followed the document; it is `None` when the whole input was
+    /// read.
+    pub fn trailing_content_offset(&self) -> Option<usize> {
+        self.trailing_content_offset
+    }
+
+    /// Returns the origins recorded while parsing
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+
+    /// Takes the recorded origins, leaving an empty table behind
+    pub fn take_provenance(&mut self) -> Provenance {
+        std::mem::take(&mut self.provenance)
+    }
+
+    /// Parses a complete document together with the origins of its entries
+    ///
+    /// Origins are only recorded when `ParserConfig::track_provenance` is enabled.
+    /// A `.priority` directive becomes the priority of the document root.
+    pub fn parse_tracked(&mut self) -> Result<TrackedValue, ParseError> {
+        let value = self.parse_document_filtered(None)?;
+        self.unused_priority = None;
+        let mut tracked = TrackedValue::new(value, self.take_provenance());
+        if let Some(priority) = self.priority {
+            tracked.set_priority("", priority);
+        }
+        Ok(tracked)
+    }
+
+    /// Returns the priority set by a `.priority N` directive, if any
+    pub fn priority(&self) -> Option<u8> {
+        self.priority
+    }
+
+    /// Parses a top-level `.priority N` directive, returning false for any other entry
+    fn parse_priority_directive(&mut self) -> Result<bool, ParseError> {
+        if !matches!(self.current_token(), Some(Token::Key(key)) if key == PRIORITY_DIRECTIVE) {
+            return Ok(false);
+        }
+        let directive = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        self.advance_token()?;
+        self.skip_whitespace_and_comments()?;
+
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let priority = match self.current_token() {
+            Some(Token::Integer(value)) => u8::try_from(*value).ok(),
+            _ => None,
+        };
+        let Some(priority) = priority else {
+            return Err(ParseError::InvalidObject {
+                message: "Priority must be an integer between 0 and 255".to_string(),
+                position,
+            });
+        };
+        self.priority = Some(priority);
+        self.unused_priority = Some(directive);
+
+        self.advance_token()?;
+        self.skip_whitespace_and_comments()?;
+        if matches!(self.current_token(), Some(Token::Semicolon)) {
+            self.advance_token()?;
+        }
+        Ok(true)
+    }
+
+    /// Parses a top-level `.include "path"` directive, returning false for any other entry
+    ///
+    /// The directive is only recorded here; `resolve_includes` reads its files
+    /// once the document is parsed.
+    fn parse_include_directive(&mut self) -> Result<bool, ParseError> {
+        if self.include_resolver.is_none() {
+            return Ok(false);
+        }
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let params = match self.current_token() {
+            Some(Token::Key(key)) => match key.strip_prefix(INCLUDE_DIRECTIVE) {
+                Some("") => IncludeParams::default(),
+                Some(rest) => match rest
+                    .strip_prefix('(')
+                    .and_then(|rest| rest.strip_suffix(')'))
+                {
+                    Some(list) => IncludeParams::parse(list)
+                        .map_err(|message| ParseError::InvalidInclude { message, position })?,
+                    None => return Ok(false),
+                },
+                None => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+        self.advance_token()?;
+        self.skip_whitespace_and_comments()?;
+
+        let path = match self.current_token() {
+            Some(Token::String {
+                value,
+                needs_expansion: true,
+                ..
+            }) => {
+                let value = value.to_string();
+                self.expand_variables(&value)?
+            }
+            Some(Token::String { value, .. } | Token::Key(value)) => value.to_string(),
+            _ => {
+                return Err(ParseError::InvalidInclude {
+                    message: "expected the path of the file to include".to_string(),
+                    position,
+                });
+            }
+        };
+        self.includes.push(PendingInclude {
+            path,
+            params,
+            position,
+        });
+
+        self.advance_token()?;
+        self.skip_whitespace_and_comments()?;
+        if matches!(self.current_token(), Some(Token::Semicolon)) {
+            self.advance_token()?;
+        }
+        Ok(true)
+    }
+
+    /// Merges the files of the document's include directives over `value`, in order
+    ///
+    /// Each file is layered over the value as by `TrackedValue::merge_from`,
+    /// so priorities from `.priority` directives and `priority=` parameters
+    /// decide which value of a key is kept.
+    fn resolve_includes(&mut self, value: UclValue) -> Result<UclValue, ParseError> {
+        let Some(resolver) = self.include_resolver.clone() else {
+            return Ok(value);
+        };
+        let includes = std::mem::take(&mut self.includes);
+        if includes.is_empty() {
+            return Ok(value);
+        }
+
+        let mut tracked = TrackedValue::new(value, self.take_provenance());
+        if let Some(priority) = self.priority {
+            tracked.set_priority("", priority);
+        }
+        self.unused_priority = None;
+        for include in includes {
+            if self.include_depth >= MAX_INCLUDE_DEPTH {
+                return Err(ParseError::IncludeFailed {
+                    path: include.path,
+                    message: format!("includes are nested more than {} deep", MAX_INCLUDE_DEPTH),
+                    position: include.position,
+                });
+            }
+            for path in self.include_paths(resolver.as_ref(), &include)? {
+                if self.included.files >= MAX_INCLUDED_FILES {
+                    return Err(ParseError::IncludeFailed {
+                        path,
+                        message: format!("more than {} files are included", MAX_INCLUDED_FILES),
+                        position: include.position,
+                    });
+                }
+                let text = match resolver.read(&path) {
+                    Ok(text) => text,
+                    Err(error)
+                        if include.params.optional && error.kind() == io::ErrorKind::NotFound =>
+                    {
+                        self.diagnostics.push(Diagnostic::new(
+                            format!("skipped missing optional include '{}'", path),
+                            include.position,
+                        ));
+                        continue;
+                    }
+                    Err(error) => {
+                        return Err(ParseError::IncludeFailed {
+                            path,
+                            message: error.to_string(),
+                            position: include.position,
+                        });
+                    }
+                };
+                self.included.files += 1;
+                self.included.bytes = self.included.bytes.saturating_add(text.len());
+                if self.included.bytes > self.config.max_document_size {
+                    return Err(ParseError::IncludeFailed {
+                        path,
+                        message: format!(
+                            "included files hold more than the maximum document size of {} bytes",
+                            self.config.max_document_size
+                        ),
+                        position: include.position,
+                    });
+                }
+                let mut layer = self
+                    .parse_included(&text, &path, &resolver)
+                    .map_err(|error| match error {
+                        ParseError::Cancelled { .. } => error,
+                        _ => ParseError::InIncludedFile {
+                            path: path.clone(),
+                            source: Box::new(error),
+                        },
+                    })?;
+                if let Some(priority) = include.params.priority {
+                    layer.set_priority("", priority);
+                }
+                tracked.merge_from(layer);
+                self.diagnostics.push(Diagnostic::new(
+                    format!("included '{}'", path),
+                    include.position,
+                ));
+            }
+        }
+        self.provenance = tracked.provenance;
+        Ok(tracked.value)
+    }
+
+    /// Returns the paths an include directive names, expanding a glob pattern
+    fn include_paths(
+        &mut self,
+        resolver: &dyn IncludeResolver,
+        include: &PendingInclude,
+    ) -> Result<Vec<String>, ParseError> {
+        if !include.params.glob {
+            return Ok(vec![include.path.clone()]);
+        }
+        let mut paths = match resolver.glob(&include.path) {
+            Ok(paths) => paths,
+            Err(error) if include.params.optional && error.kind() == io::ErrorKind::NotFound => {
+                Vec::new()
+            }
+            Err(error) => {
+                return Err(ParseError::IncludeFailed {
+                    path: include.path.clone(),
+                    message: error.to_string(),
+                    position: include.position,
+                });
+            }
+        };
+        if paths.is_empty() {
+            if !include.params.optional {
+                return Err(ParseError::IncludeFailed {
+                    path: include.path.clone(),
+                    message: "no files match the pattern".to_string(),
+                    position: include.position,
+                });
+            }
+            self.diagnostics.push(Diagnostic::new(
+                format!("no files match optional include '{}'", include.path),
+                include.position,
+            ));
+        }
+        sort_by_file_name(&mut paths);
+        Ok(paths)
+    }
+
+    /// Parses an included file with this parser's configuration, variable handler and hooks
+    ///
+    /// Diagnostics of the file are kept, prefixed with its path.
+    fn parse_included(
+        &mut self,
+        text: &str,
+        path: &str,
+        resolver: &Arc<dyn IncludeResolver>,
+    ) -> Result<TrackedValue, ParseError> {
+        let mut parser = UclParser::with_lexer_config(text, self.lexer.config().clone())
+            .with_config(self.config.clone())
+            .with_source_name(path);
+        parser.variable_handler = self
+            .variable_handler
+            .as_ref()
+            .map(|handler| VariableHandlerSlot::Borrowed(handler.get()));
+        parser.parsing_hooks = std::mem::take(&mut self.parsing_hooks);
+        parser.include_resolver = Some(Arc::clone(resolver));
+        parser.include_depth = self.include_depth + 1;
+        parser.included = self.included;
+
+        let result = parser.parse_tracked();
+
+        self.parsing_hooks = std::mem::take(&mut parser.parsing_hooks);
+        self.included = parser.included;
+        self.diagnostics
+            .extend(parser.diagnostics.drain(..).map(|mut diagnostic| {
+                diagnostic.message = format!("in '{}': {}", path, diagnostic.message);
+                diagnostic
+            }));
+        result
+    }
+
+    /// Returns the statistics collected while parsing so far
+    pub fn stats(&self) -> ParseStats {
+        let lexer_stats = self.lexer.stats();
+        ParseStats {
+            tokens: self.lexer.token_count(),
+            peak_nesting_depth: lexer_stats.peak_nesting_depth,
+            strings_expanded: self.counters.strings_expanded.get(),
+            variables_expanded: self.counters.variables_expanded.get(),
+            zero_copy_strings: lexer_stats.borrowed_strings,
+            owned_strings: lexer_stats.owned_strings,
+            comments_skipped: lexer_stats.comments,
+            duplicate_keys_encountered: self.counters.duplicate_keys,
+            bytes_processed: self.lexer.current_position().offset,
+            duration: self.counters.duration,
+        }
+    }
+
+    /// Parses a complete document and returns it with the collected statistics
+    pub fn parse_with_stats(&mut self) -> Result<(UclValue, ParseStats), ParseError> {
+        let value = self.parse_document()?;
+        Ok((value, self.stats()))
+    }
+
+    /// Parses a document from bytes, detecting its encoding
+    ///
+    /// Input is UTF-8 unless it starts with a UTF-16 byte order mark. UTF-16 is
+    /// transcoded with the `encoding` feature; without it, and for bytes that do
+    /// not decode, `UclError::InvalidEncoding` gives the offending byte offset.
+    pub fn parse_bytes(bytes: &[u8]) -> Result<UclValue, UclError> {
+        Self::parse_bytes_with_config(bytes, &ParserConfig::default())
+    }
+
+    /// Parses a document from bytes like `parse_bytes`, with a parser configuration
+    ///
+    /// ```rust
+    /// use ucl_lexer::{ParserConfig, UclParser};
+    ///
+    /// let config = ParserConfig::untrusted().with_max_depth(1);
+    /// assert!(UclParser::parse_bytes_with_config(b"a = 1", &config).is_ok());
+    /// assert!(UclParser::parse_bytes_with_config(b"a { b { c = 1 } }", &config).is_err());
+    /// ```
+    pub fn parse_bytes_with_config(
+        bytes: &[u8],
+        config: &ParserConfig,
+    ) -> Result<UclValue, UclError> {
+        let text = crate::encoding::decode(bytes)?;
+        Ok(UclParser::new(&text)
+            .with_config(config.clone())
+            .parse_document()?)
+    }
+
+    /// Parses a document, collecting every error instead of stopping at the first
+    ///
+    /// See `parse_document_lenient` for how errors are recovered from.
+    pub fn parse_lenient(input: &str) -> (Option<UclValue>, Vec<UclError>) {
+        UclParser::new(input).parse_document_lenient()
+    }
+
+    /// Checks the document against the full grammar without keeping its contents
+    ///
+    /// Reports every error `parse_document_lenient` would, including
+    /// duplicate keys, nesting limits and malformed variable references.
+    /// Variables are not resolved, and only the keys and structure needed for
+    /// these checks are kept: strings are left empty and arrays keep no
+    /// elements. Variable references are checked even without a variable
+    /// handler. Parsing hooks are not run, as they would see the empty strings.
+    ///
+    /// ```rust
+    /// use ucl_lexer::UclParser;
+    ///
+    /// assert!(UclParser::new("port = 80\nname = \"${NAME}\"").validate().is_ok());
+    ///
+    /// let errors = UclParser::new("a = [1,,2]\nb = \"${}\"").validate().unwrap_err();
+    /// let errors = UclParser::new("a = 1,, b = 2\nc = \"${}\"").validate().unwrap_err();
+    /// ```
+    pub fn validate(&mut self) -> Result<(), Vec<UclError>> {
+        let hooks = std::mem::take(&mut self.parsing_hooks);
+        self.validating = true;
+        let errors = if self.check_plain_document() {
+            Vec::new()
+        } else {
+            self.rewind();
+            self.parse_document_lenient().1
+        };
+        self.validating = false;
+        self.parsing_hooks = hooks;
+        self.diagnostics.clear();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns to the start of the input, forgetting what was read so far
+    fn rewind(&mut self) {
+        self.lexer = self.lexer.restarted(self.lexer.config().clone());
+        self.current_depth = 0;
+        self.diagnostics.clear();
+        self.counters = ParseCounters::default();
+        self.lint_state = LintState::default();
+        self.dotted_keys.clear();
+        self.section_labels.clear();
+        self.anchors = AnchorState::default();
+        self.initial_error = self.advance_token().err();
+    }
+
+    /// Parses the input again from the start, recording where its entries were defined
+    ///
+    /// `UclDeserializer` calls this once deserializing has failed, to place
+    /// the error, so documents that deserialize are parsed without tracking.
+    pub(crate) fn reparse_provenance(&mut self) -> Provenance {
+        let track = std::mem::replace(&mut self.config.track_provenance, true);
+        self.rewind();
+        // An error leaves the origins recorded up to it, which are still useful
+        let _ = self.parse_document_filtered(None);
+        self.config.track_provenance = track;
+        self.take_provenance()
+    }
+
+    /// Checks a document written in the common subset of the grammar, building nothing
+    ///
+    /// The subset is an implicit or braced root object whose entries are
+    /// `key = value`, `key: value` or `key { ... }` with a plain key, values
+    /// are objects, arrays or scalars, and separators follow
+    /// `SeparatorContext`. Scalars are read by `parse_value_with_context` as
+    /// in parsing. Returns true once the whole document is known to be valid.
+    /// Anything outside the subset, and any error, returns false, and
+    /// `validate` then parses the document, which decides and reports.
+    fn check_plain_document(&mut self) -> bool {
+        if self.initial_error.is_some()
+            || self.lexer.source_len() > self.config.max_document_size
+            || self.config.directives.is_some()
+            || self.config.enable_anchors
+            || self.config.json_only
+            || self.skip_whitespace_and_comments().is_err()
+        {
+            return false;
+        }
+
+        let mut context = VariableContext::new(self.current_position());
+        let checked = match self.current_token() {
+            Some(Token::ObjectStart) => {
+                self.check_plain_object(&mut context)
+                    && self.skip_whitespace_and_comments().is_ok()
+                    && self
+                        .skip_separators(SeparatorContext::Document, false)
+                        .is_ok()
+            }
+            Some(Token::Key(_) | Token::String { .. }) => {
+                self.check_plain_entries(SeparatorContext::Document, &mut context)
+            }
+            _ => false,
+        };
+        checked && self.at_end_of_input()
+    }
+
+    /// Checks the entries of the root up to the end of input, or of an object up to its `}`
+    ///
+    /// The closing token is left for the caller. In `DuplicateKeyBehavior::Error`
+    /// mode a repeated key is left to the parser, which merges repeated sections.
+    fn check_plain_entries(
+        &mut self,
+        separators: SeparatorContext,
+        context: &mut VariableContext,
+    ) -> bool {
+        let closing = match separators {
+            SeparatorContext::Object => Token::ObjectEnd,
+            _ => Token::Eof,
+        };
+        let unique_keys = self.config.duplicate_key_behavior == DuplicateKeyBehavior::Error;
+        let mut keys = HashSet::new();
+        let mut after_entry = false;
+
+        loop {
+            if self.skip_whitespace_and_comments().is_err()
+                || self.skip_separators(separators, !after_entry).is_err()
+            {
+                return false;
+            }
+            after_entry = true;
+
+            let key = match self.current_token() {
+                Some(token) if *token == closing => return true,
+                Some(Token::Key(key)) if !self.at_dotted_key() => key,
+                Some(Token::String {
+                    value,
+                    format: StringFormat::Json | StringFormat::Single,
+                    needs_expansion: false,
+                }) => value,
+                _ => return false,
+            };
+            // Keys such as `.priority` and `.include` are directives, and the
+            // lexer only limits the length of bare keys
+            if key.is_empty()
+                || key.starts_with('.')
+                || key.len() > self.lexer.config().max_key_length
+                || unique_keys && !keys.insert(key.clone())
+            {
+                return false;
+            }
+
+            if self.advance_token().is_err() || self.skip_whitespace_and_comments().is_err() {
+                return false;
+            }
+            let checked = match self.current_token() {
+                Some(Token::ObjectStart) => self.check_plain_object(context),
+                Some(Token::Equals | Token::Colon) => {
+                    self.advance_token().is_ok()
+                        && self.skip_whitespace_and_comments().is_ok()
+                        && self.check_plain_value(context, true)
+                }
+                _ => false,
+            };
+            if !checked || self.skip_whitespace_and_comments().is_err() {
+                return false;
+            }
+            match self.current_token() {
+                Some(Token::Comma | Token::Semicolon | Token::Key(_) | Token::String { .. }) => {}
+                Some(token) if *token == closing => {}
+                _ => return false,
+            }
+        }
+    }
+
+    /// Checks a value in the subset read by `check_plain_document`
+    fn check_plain_value(
+        &mut self,
+        context: &mut VariableContext,
+        explicit_separator: bool,
+    ) -> bool {
+        match self.current_token() {
+            Some(Token::ObjectStart) => self.check_plain_object(context),
+            Some(Token::ArrayStart) => self.check_plain_array(context),
+            Some(Token::Anchor(_) | Token::Reference(_)) | None => false,
+            Some(_) => self
+                .parse_value_with_context(context, explicit_separator)
+                .is_ok(),
+        }
+    }
+
+    /// Checks an object in the subset read by `check_plain_document`, with its braces
+    fn check_plain_object(&mut self, context: &mut VariableContext) -> bool {
+        if self.current_depth >= self.config.max_depth {
+            return false;
+        }
+        self.current_depth += 1;
+        let checked = self.advance_token().is_ok()
+            && self.check_plain_entries(SeparatorContext::Object, context)
+            && self.advance_token().is_ok();
+        self.current_depth -= 1;
+        checked
+    }
+
+    /// Checks an array in the subset read by `check_plain_document`, with its brackets
+    fn check_plain_array(&mut self, context: &mut VariableContext) -> bool {
+        if self.current_depth >= self.config.max_depth || self.advance_token().is_err() {
+            return false;
+        }
+        self.current_depth += 1;
+        let mut index = 0;
+        let checked = loop {
+            if self.skip_whitespace_and_comments().is_err() {
+                break false;
+            }
+            match self.current_token() {
+                Some(Token::ArrayEnd) => break self.advance_token().is_ok(),
+                // `[ host = "a" ]` is an error the parser reports
+                Some(Token::Key(_)) => {
+                    let assigned = self.next_token_matches(|token| {
+                        matches!(token, Some(Token::Equals | Token::Colon))
+                    });
+                    if !matches!(assigned, Ok(false)) {
+                        break false;
+                    }
+                }
+                Some(Token::Comma | Token::Semicolon | Token::ObjectEnd | Token::Eof) | None => {
+                    break false;
+                }
+                _ => {}
+            }
+            if !self.check_plain_value(context, false)
+                || self.skip_whitespace_and_comments().is_err()
+            {
+                break false;
+            }
+            index += 1;
+            match self.current_token() {
+                Some(Token::Comma | Token::Semicolon) => {
+                    if self
+                        .skip_separators(SeparatorContext::Array { index }, false)
+                        .is_err()
+                    {
+                        break false;
+                    }
+                }
+                Some(Token::ArrayEnd) => {}
+                _ => break false,
+            }
+        };
+        self.current_depth -= 1;
+        checked
+    }
+
+    /// Parses a complete document, recovering from errors instead of stopping at the first
+    ///
+    /// Each broken entry is reported and replaced by `UclValue::Null`, and parsing
+    /// resumes at the next key on the same nesting level. The value is `None` only
+    /// when nothing could be recovered.
+    pub fn parse_document_lenient(&mut self) -> (Option<UclValue>, Vec<UclError>) {
+        self.lenient_errors = Some(Vec::new());
+        let result = self.parse_document();
+        let mut errors = self.lenient_errors.take().unwrap_or_default();
+
+        match result {
+            Ok(value) => (Some(value), errors),
+            Err(error) => {
+                errors.push(UclError::from(error));
+                (None, errors)
+            }
+        }
+    }
+
+    /// Gets a reference to the parsing hooks
+    pub fn parsing_hooks(&self) -> &ParsingHooks {
+        &self.parsing_hooks
+    }
+
+    /// Gets a mutable reference to the parsing hooks
+    pub fn parsing_hooks_mut(&mut self) -> &mut ParsingHooks {
+        &mut self.parsing_hooks
+    }
+
+    /// Sets the parsing hooks
+    pub fn set_parsing_hooks(&mut self, hooks: ParsingHooks) {
+        self.parsing_hooks = hooks;
+        self.sync_shadowed_suffixes();
+    }
+
+    /// Adds a number suffix handler
+    pub fn add_number_suffix_handler(&mut self, handler: Box<dyn NumberSuffixHandler>) {
+        self.parsing_hooks.add_number_suffix_handler(handler);
+        self.sync_shadowed_suffixes();
+    }
+
+    /// Hands the suffixes claimed by number suffix handlers over from the lexer
+    fn sync_shadowed_suffixes(&mut self) {
+        let config = self.lexer.config_mut();
+        for suffix in self.parsing_hooks.shadowed_suffixes() {
+            if !config.shadowed_suffixes.contains(&suffix) {
+                config.shadowed_suffixes.push(suffix);
+            }
+        }
+    }
+
+    /// Applies number suffix handlers to a number followed directly by a suffix, as in `10px`
+    ///
+    /// Consumes the suffix and returns the converted value if a handler recognized it.
+    fn apply_number_suffix(
+        &mut self,
+        number: f64,
+        number_start: Position,
+        number_end: Position,
+        context: &VariableContext,
+    ) -> Result<Option<UclValue>, ParseError> {
+        if self.parsing_hooks.number_suffix_handlers.is_empty() {
+            return Ok(None);
+        }
+
+        let suffix = match (self.current_token_start(), self.current_token()) {
+            (Some(start), Some(Token::Key(suffix))) if start.offset == number_end.offset => {
+                suffix.to_string()
+            }
+            _ => return Ok(None),
+        };
+
+        let value =
+            self.parsing_hooks
+                .apply_number_suffix(number, &suffix, context, number_start)?;
+        if value.is_some() {
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+        }
+        Ok(value)
+    }
+
+    /// Adds a string post-processor
+    pub fn add_string_processor(&mut self, processor: Box<dyn StringPostProcessor>) {
+        self.parsing_hooks.add_string_processor(processor);
+    }
+
+    /// Adds a validation hook
+    pub fn add_validation_hook(&mut self, hook: Box<dyn ValidationHook>) {
+        self.parsing_hooks.add_validation_hook(hook);
+    }
+
+    /// Adds a key transform
+    pub fn add_key_transform(&mut self, hook: Box<dyn KeyTransformHook>) {
+        self.parsing_hooks.add_key_transform(hook);
+    }
+
+    /// Returns the non-fatal diagnostics recorded while parsing
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Returns the directives read from comments, in source order
+    ///
+    /// Empty unless `ParserConfig::directives` is set.
+    pub fn directives(&self) -> &[Directive] {
+        &self.directive_state.attached
+    }
+
+    /// Returns the comments saved by the lexer, in source order
+    ///
+    /// Empty unless `LexerConfig::save_comments` is set.
+    pub fn comments(&self) -> &[CommentInfo<'a>] {
+        self.lexer.comments()
+    }
+
+    /// Takes the recorded diagnostics, leaving the list empty
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Builds the value of a float token under the configured decimal mode
+    ///
+    /// `DecimalMode::PreserveString` keeps the number as written between
+    /// `start` and `end`, which must be a plain decimal; otherwise the
+    /// non-finite policy applies.
+    fn float_value(
+        &mut self,
+        value: f64,
+        start: Position,
+        end: Position,
+    ) -> Result<UclValue, ParseError> {
+        if self.config.decimal_mode == DecimalMode::Float {
+            return self.apply_non_finite_policy(value, start, end);
+        }
+        let text = self.token_text_from_positions(start, end);
+        if is_plain_decimal(&text) {
+            Ok(UclValue::Decimal(text))
+        } else {
+            Err(ParseError::InvalidDecimal {
+                value: text,
+                position: start,
+            })
+        }
+    }
+
+    /// Applies the configured non-finite policy to a float value
+    ///
+    /// `start` and `end` delimit the number as written, such as `inf` or an
+    /// overflowing `1e400`, which errors and diagnostics quote.
+    fn apply_non_finite_policy(
+        &mut self,
+        value: f64,
+        start: Position,
+        end: Position,
+    ) -> Result<UclValue, ParseError> {
+        if value.is_finite() {
+            return Ok(UclValue::Float(value));
+        }
+
+        match self.config.non_finite_numbers {
+            NonFinitePolicy::Allow => Ok(UclValue::Float(value)),
+            NonFinitePolicy::Error => Err(ParseError::NonFiniteNumber {
+                value: self.token_text_from_positions(start, end),
+                position: start,
+            }),
+            NonFinitePolicy::Null => {
+                self.diagnostics.push(Diagnostic::new(
+                    format!(
+                        "Non-finite number '{}' replaced with null",
+                        self.token_text_from_positions(start, end)
+                    ),
+                    start,
+                ));
+                Ok(UclValue::Null)
+            }
+        }
+    }
+
+    /// Records where an object entry was defined when provenance tracking is enabled
+    fn record_origin(&mut self, context: &VariableContext, key: &str, span: Span) {
+        if !self.config.track_provenance {
+            return;
+        }
+        self.provenance.record(
+            context.path_to(key),
+            Origin::new(self.source_name.clone(), span),
+        );
+    }
+
+    /// Records whether an entry was written with `+=`, so merged layers can append it
+    fn record_assignment(&mut self, context: &VariableContext, key: &str, assignment: Assignment) {
+        if !self.config.track_provenance {
+            return;
+        }
+        self.provenance
+            .record_assignment(context.path_to(key), assignment == Assignment::Append);
+    }
+
+    /// Returns how a string starting at the current token would be written
+    ///
+    /// Bare words and numbers followed by letters read as unquoted strings.
+    fn value_string_format(&self) -> Option<StringFormat> {
+        match self.current_token() {
+            Some(Token::String { format, .. }) => Some(*format),
+            Some(Token::Key(_) | Token::Integer(_) | Token::Float(_)) => {
+                Some(StringFormat::Unquoted)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the input slice of a string starting at the current token, when borrowed
+    fn value_source(&self) -> Option<&'a str> {
+        self.string_sources.as_ref()?;
+        match self.current_token() {
+            Some(
+                Token::String {
+                    value: Cow::Borrowed(text),
+                    ..
+                }
+                | Token::Key(Cow::Borrowed(text)),
+            ) => Some(*text),
+            _ => None,
+        }
+    }
+
+    /// Records that a string value is the input slice it was read from
+    ///
+    /// Expansion, concatenation and hooks give the value other contents, so
+    /// only values still equal to the slice are recorded.
+    fn record_string_source(&mut self, source: Option<&'a str>, value: &UclValue) {
+        if let (Some(sources), Some(source), UclValue::String(text)) =
+            (&mut self.string_sources, source, value)
+            && !text.is_empty()
+            && text == source
+        {
+            sources.push((text.as_ptr() as usize, source));
+        }
+    }
+
+    /// Records how a string value was written when provenance tracking is enabled
+    fn record_string_format(
+        &mut self,
+        context: &VariableContext,
+        format: Option<StringFormat>,
+        value: &UclValue,
+    ) {
+        if !self.config.track_provenance || !value.is_string() {
+            return;
+        }
+        if let Some(format) = format {
+            let path = context.current_object_path.iter().map(UclKey::to_string);
+            self.provenance.record_string_format(path.collect(), format);
+        }
+    }
+
+    /// Records a diagnostic for a duplicate key that was resolved without an error
+    fn report_duplicate_key(
+        &mut self,
+        key: &str,
+        first_occurrence: Position,
+        second_occurrence: Position,
+        resolution: &str,
+    ) {
+        self.diagnostics.push(Diagnostic::new(
+            format!(
+                "duplicate key `{}` {} the value first defined at line {}",
+                key, resolution, first_occurrence.line
+            ),
+            second_occurrence,
+        ));
+    }
+
+    /// Applies the per-entry lints to a parsed object entry
+    fn lint_entry(
+        &mut self,
+        context: &VariableContext,
+        key: &str,
+        position: Position,
+        value: &UclValue,
+    ) {
+        let lints = self.config.lints;
+        if lints.shadowed_keys
+            && context
+                .current_object_path
+                .iter()
+                .any(|parent| parent == key)
+        {
+            self.diagnostics.push(Diagnostic::lint(
+                LintCode::ShadowedKey,
+                format!("key `{}` shadows an enclosing key of the same name", key),
+                position,
+            ));
+        }
+        if lints.empty_objects {
+            match value {
+                UclValue::Object(object) if object.is_empty() => {
+                    self.lint_state
+                        .empty_objects
+                        .push((key.to_string(), position));
+                }
+                UclValue::Object(_) | UclValue::Array(_) => {}
+                _ => {
+                    self.lint_state
+                        .scalar_keys
+                        .entry(key.to_string())
+                        .or_insert(position);
+                }
+            }
+        }
+    }
+
+    /// Reports an entry whose separator differs from the first one used in its object
+    fn lint_separator(
+        &mut self,
+        first_separator: &mut Option<char>,
+        separator: Option<Separator>,
+        key: &str,
+    ) {
+        let Some((separator, position)) = separator else {
+            return;
+        };
+        match *first_separator {
+            None => *first_separator = Some(separator),
+            Some(first) if first != separator && self.config.lints.mixed_separators => {
+                self.diagnostics.push(Diagnostic::lint(
+                    LintCode::MixedSeparators,
+                    format!(
+                        "`{}` is assigned with '{}' but earlier entries of this object use '{}'",
+                        key, separator, first
+                    ),
+                    position,
+                ));
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Reports a quoted string that reads as a number, boolean or duration
+    fn lint_typed_string(&mut self, text: &str, format: StringFormat, position: Position) {
+        if !self.config.lints.typed_strings
+            || !matches!(format, StringFormat::Json | StringFormat::Single)
+        {
+            return;
+        }
+
+        let mut lexer = UclLexer::new(text);
+        let kind = match lexer.next_token() {
+            Ok(Token::Integer(_)) => "number",
+            Ok(Token::Float(value)) if value.is_finite() => "number",
+            Ok(Token::Boolean(_)) => "boolean",
+            Ok(Token::Key(word))
+                if matches!(
+                    word.to_ascii_lowercase().as_str(),
+                    "yes" | "no" | "on" | "off"
+                ) =>
+            {
+                "boolean"
+            }
+            Ok(Token::Time(_)) => "duration",
+            _ => return,
+        };
+        if lexer.last_token_start().offset != 0 || lexer.last_token_end().offset != text.len() {
+            return;
+        }
+        self.diagnostics.push(Diagnostic::lint(
+            LintCode::TypedString,
+            format!(
+                "string \"{}\" looks like a {}; remove the quotes if it is meant to be one",
+                text, kind
+            ),
+            position,
+        ));
+    }
+
+    /// Reports empty objects assigned to keys that hold scalars elsewhere in the document
+    fn report_empty_objects(&mut self) {
+        let state = std::mem::take(&mut self.lint_state);
+        for (key, position) in state.empty_objects {
+            if let Some(scalar) = state.scalar_keys.get(&key) {
+                self.diagnostics.push(Diagnostic::lint(
+                    LintCode::EmptyObject,
+                    format!(
+                        "empty object assigned to `{}`, which holds a scalar at line {}",
+                        key, scalar.line
+                    ),
+                    position,
+                ));
+            }
+        }
+    }
+
+    /// Records an error in lenient mode, or returns it otherwise
+    fn tolerate(&mut self, error: ParseError) -> Result<(), ParseError> {
+        match self.lenient_errors.as_mut() {
+            // A cancelled parse stops, however many errors are collected
+            Some(errors) if !matches!(error, ParseError::Cancelled { .. }) => {
+                errors.push(UclError::from(error));
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Records an error inside an object entry and skips to the next entry
+    ///
+    /// `in_key` marks errors raised while reading the key itself, where the
+    /// current token has to be skipped even if it looks like a key.
+    fn recover_entry(
+        &mut self,
+        error: ParseError,
+        depth: usize,
+        in_braces: bool,
+        in_key: bool,
+    ) -> Result<(), ParseError> {
+        self.tolerate(error)?;
+        self.current_depth = depth;
+        self.skip_to_entry_boundary(in_braces, in_key);
+        Ok(())
+    }
+
+    /// Returns the current token as a separator character, if it is `,` or `;`
+    fn current_separator(&self) -> Option<char> {
+        match self.current_token() {
+            Some(Token::Comma) => Some(','),
+            Some(Token::Semicolon) => Some(';'),
+            _ => None,
+        }
+    }
+
+    /// Consumes the run of `,` and `;` at the current token
+    ///
+    /// Before the first entry or element (`leading`), any separator is a
+    /// `ParseError::LeadingSeparator`. Otherwise the run is checked against
+    /// `SeparatorContext::accepts`, and the first separator it rejects is a
+    /// `ParseError::RepeatedSeparator`, or a `ParseError::MissingArrayElement`
+    /// inside an array. Comments may come between the separators of a run.
+    fn skip_separators(
+        &mut self,
+        context: SeparatorContext,
+        leading: bool,
+    ) -> Result<(), ParseError> {
+        let mut run = SmallVec::<[char; 4]>::new();
+        let mut last_position = None;
+        while let Some(separator) = self.current_separator() {
+            let position = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+            last_position = Some(position);
+            if leading {
+                return Err(ParseError::LeadingSeparator {
+                    separator,
+                    container: context.container(),
+                    position,
+                });
+            }
+            if !context.accepts(separator, &run) {
+                return Err(match context {
+                    SeparatorContext::Array { index } => ParseError::MissingArrayElement {
+                        index,
+                        separator,
+                        position,
+                    },
+                    _ => ParseError::RepeatedSeparator { position },
+                });
+            }
+            run.push(separator);
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+        }
+        if self.config.json_only
+            && let Some(position) = last_position
+            && matches!(
+                self.current_token(),
+                Some(Token::ObjectEnd | Token::ArrayEnd | Token::Eof) | None
+            )
+        {
+            return Err(ParseError::NotJson {
+                construct: "a trailing ','",
+                position,
+            });
+        }
+        Ok(())
+    }
+
+    /// Copies a string value, which is left empty when only validating
+    fn string_value(&self, text: &str) -> String {
+        if self.validating {
+            String::new()
+        } else {
+            self.pool.borrow_mut().string(text)
+        }
+    }
+
+    /// Returns true once all input has been consumed
+    fn at_end_of_input(&self) -> bool {
+        matches!(self.current_token(), Some(Token::Eof) | None)
+    }
+
+    /// Skips the value of an entry without building it
+    ///
+    /// Stops at the next key on the current nesting level, at a `;` or `,`
+    /// that ends the entry, or at a closing bracket that does not belong to
+    /// the value. A key on a new line, after a closed object, array or heredoc
+    /// starts the next entry unless an operator comes before it.
+    fn skip_entry_value(&mut self) -> Result<(), ParseError> {
+        let mut depth = 0usize;
+        let mut first = true;
+        let mut boundary = false;
+        let mut after_operator = false;
+
+        loop {
+            let newline = self.lexer.last_token_had_newline() && !after_operator;
+            match self.current_token() {
+                Some(Token::Eof) | None => return Ok(()),
+                Some(Token::ObjectEnd | Token::ArrayEnd) if depth == 0 => return Ok(()),
+                Some(Token::Semicolon | Token::Comma) if depth == 0 => return Ok(()),
+                // An opening brace or separator on the next line still belongs to the key
+                Some(Token::ObjectStart | Token::Equals | Token::Colon) if first => {}
+                Some(Token::Comment(_)) => {}
+                Some(_) if depth == 0 && (boundary || newline) => return Ok(()),
+                _ => {}
+            }
+
+            boundary = match self.current_token() {
+                Some(Token::ObjectStart | Token::ArrayStart) => {
+                    depth += 1;
+                    false
+                }
+                Some(Token::ObjectEnd | Token::ArrayEnd) => {
+                    depth -= 1;
+                    depth == 0
+                }
+                Some(Token::Comment(_)) => boundary,
+                // A heredoc takes the line break after its terminator
+                Some(Token::String {
+                    format: StringFormat::Heredoc,
+                    ..
+                }) => depth == 0,
+                _ => false,
+            };
+            after_operator = match self.current_token() {
+                Some(Token::Equals | Token::Colon | Token::Plus) => true,
+                Some(Token::Comment(_)) => after_operator,
+                _ => false,
+            };
+            first = false;
+            self.advance_token()?;
+        }
+    }
+
+    /// Skips tokens until the next key on the current nesting level
+    ///
+    /// A key is only accepted at the start of a line or after a separator, so
+    /// the remains of a broken value are not mistaken for the next entry. Stops
+    /// at the closing brace of the current object when `in_braces` is set.
+    fn skip_to_entry_boundary(&mut self, in_braces: bool, skip_current: bool) {
+        let start = self.current_token_start;
+        let mut depth = 0usize;
+        let mut after_separator = false;
+
+        loop {
+            let skipped = self.current_token_start == start && skip_current;
+            match self.current_token() {
+                Some(Token::Eof) | None => return,
+                Some(Token::ObjectEnd) if depth == 0 && in_braces => return,
+                Some(Token::Key(_))
+                | Some(Token::String { .. })
+                | Some(Token::Boolean(_))
+                | Some(Token::Integer(_))
+                    if depth == 0
+                        && !skipped
+                        && (after_separator || self.lexer.last_token_had_newline()) =>
+                {
+                    return;
+                }
+                Some(Token::ObjectStart) | Some(Token::ArrayStart) => depth += 1,
+                Some(Token::ObjectEnd) | Some(Token::ArrayEnd) => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+
+            after_separator = matches!(
+                self.current_token(),
+                Some(Token::Semicolon) | Some(Token::Comma)
+            );
+
+            while self.advance_token().is_err() {
+                // Skip the offending input so the lexer always makes progress
+                let offset = self.lexer.current_position().offset;
+                let _ = self.lexer.recover_from_error();
+                if self.lexer.current_position().offset == offset && self.lexer.advance().is_none()
+                {
+                    self.current_token = Some(Token::Eof);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Advances to the next token
+    fn advance_token(&mut self) -> Result<(), ParseError> {
+        if self.config.cancellation.is_some() {
+            self.cancellation_countdown -= 1;
+            if self.cancellation_countdown == 0 {
+                self.cancellation_countdown = cancellation::CHECK_INTERVAL;
+                self.check_cancelled()?;
+            }
+        }
+        // Directives apply to the next key only, not to a value or a closing brace
+        if !self.directive_state.pending.is_empty()
+            && !matches!(self.current_token, Some(Token::Comment(_)))
+        {
+            self.directive_state.pending.clear();
+        }
+        match self.lexer.next_token() {
+            Ok(token) => {
+                self.previous_token_end = self.current_token_end;
+                self.current_token = Some(token);
+                self.current_token_start = Some(self.lexer.last_token_start());
+                self.current_token_end = Some(self.lexer.last_token_end());
+                if self.config.json_only {
+                    self.check_json_token()?;
+                }
+                Ok(())
+            }
+            // The root has been closed, so the error is in trailing content
+            Err(lex_error) if self.root_ends_at_lex_error && self.lexer.nesting_depth() == 0 => {
+                let position = lex_error
+                    .position()
+                    .unwrap_or_else(|| self.current_position());
+                self.trailing_content_offset = Some(position.offset);
+                self.previous_token_end = self.current_token_end;
+                self.current_token = Some(Token::Eof);
+                self.current_token_start = Some(position);
+                self.current_token_end = Some(position);
+                Ok(())
+            }
+            Err(lex_error) => Err(ParseError::from(lex_error)),
+        }
+    }
+
+    /// Returns `ParseError::Cancelled` if the cancellation flag is set
+    fn check_cancelled(&self) -> Result<(), ParseError> {
+        match &self.config.cancellation {
+            Some(flag) if flag.is_cancelled() => Err(ParseError::Cancelled {
+                position: self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position()),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects the current token if only UCL allows it, with `ParserConfig::json_only`
+    fn check_json_token(&self) -> Result<(), ParseError> {
+        let start = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let end = self.current_token_end().unwrap_or(start);
+        let text = || {
+            self.lexer
+                .source_text(start.offset..end.offset)
+                .unwrap_or_default()
+        };
+        let construct = match self.current_token() {
+            Some(Token::Key(_)) => "an unquoted key or value",
+            Some(Token::String { format, .. }) => match format {
+                StringFormat::Json => match json_string_extension(&text()) {
+                    Some(construct) => construct,
+                    None => return Ok(()),
+                },
+                StringFormat::Single => "a single-quoted string",
+                StringFormat::Heredoc => "a heredoc",
+                StringFormat::Unquoted => "an unquoted value",
+            },
+            Some(Token::Integer(_) | Token::Float(_)) if !is_json_number(&text()) => {
+                "a number outside JSON syntax"
+            }
+            Some(Token::Time(_)) => "a number with a suffix",
+            Some(Token::Boolean(_) | Token::Null)
+                if !matches!(text().as_ref(), "true" | "false" | "null") =>
+            {
+                "a keyword other than true, false or null"
+            }
+            Some(Token::Semicolon) => "';'",
+            Some(Token::Equals) => "'='",
+            Some(Token::Plus) => "'+'",
+            Some(Token::Anchor(_) | Token::Reference(_)) => "an anchor",
+            Some(Token::Comment(_)) => "a comment",
+            _ => return Ok(()),
+        };
+        Err(ParseError::NotJson {
+            construct,
+            position: start,
+        })
+    }
+
+    /// Returns the current token
+    pub fn current_token(&self) -> Option<&Token<'a>> {
+        self.current_token.as_ref()
+    }
+
+    /// Returns the start position of the current token
+    pub fn current_token_start(&self) -> Option<Position> {
+        self.current_token_start
+    }
+
+    /// Returns the end position of the current token
+    pub fn current_token_end(&self) -> Option<Position> {
+        self.current_token_end
+    }
+
+    /// Returns the current position
+    pub fn current_position(&self) -> Position {
+        self.lexer.current_position()
+    }
+
+    /// Peeks at the next token without consuming it
+    pub fn peek_token(&mut self) -> Result<Option<&Token<'a>>, ParseError> {
+        Ok(self.current_token())
+    }
+
+    /// Expects a specific token and consumes it
+    pub fn expect_token(&mut self, expected: &Token<'a>) -> Result<Token<'a>, ParseError> {
+        match self.current_token() {
+            Some(token) if std::mem::discriminant(token) == std::mem::discriminant(expected) => {
+                let consumed_token = token.clone();
+                self.advance_token()?;
+                Ok(consumed_token)
+            }
+            _ => Err(self.unexpected_token(&[expected.type_name()])),
+        }
+    }
+
+    /// Consumes the current token if it matches the expected type
+    pub fn consume_if_matches(
+        &mut self,
+        expected: &Token<'a>,
+    ) -> Result<Option<Token<'a>>, ParseError> {
+        match self.current_token() {
+            Some(token) if std::mem::discriminant(token) == std::mem::discriminant(expected) => {
+                let consumed_token = token.clone();
+                self.advance_token()?;
+                Ok(Some(consumed_token))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks if the current token is of a specific type
+    pub fn is_current_token(&self, expected: &Token<'a>) -> bool {
+        match self.current_token() {
+            Some(token) => std::mem::discriminant(token) == std::mem::discriminant(expected),
+            None => false,
+        }
+    }
+
+    /// Error for the current token, listing the tokens accepted in its place
+    fn unexpected_token(&self, expected: &[&'static str]) -> ParseError {
+        ParseError::UnexpectedToken {
+            found: self.current_token().map_or("end of file", Token::type_name),
+            expected: expected.to_vec(),
+            position: self.current_position(),
+        }
+    }
+
+    /// Skips whitespace and comments
+    pub fn skip_whitespace_and_comments(&mut self) -> Result<(), ParseError> {
+        while let Some(Token::Comment(text)) = self.current_token() {
+            if let Some(directives) = &self.config.directives
+                && let Some(directive) = directives.parse(text)
+            {
+                self.read_directive(directive);
+            }
+            self.advance_token()?;
+        }
+        Ok(())
+    }
+
+    /// Keeps a directive from the current comment until the next key
+    fn read_directive(&mut self, directive: Result<DirectiveKind, String>) {
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let state = &mut self.directive_state;
+        if state
+            .last_comment_offset
+            .is_some_and(|offset| offset >= position.offset)
+        {
+            return;
+        }
+        state.last_comment_offset = Some(position.offset);
+        match directive {
+            Ok(kind) => state.pending.push((position, kind)),
+            Err(message) => self.diagnostics.push(Diagnostic::new(
+                format!("invalid directive: {}", message),
+                position,
+            )),
+        }
+    }
+
+    /// Attaches the directives read before a key to its entry
+    fn attach_directives(
+        &mut self,
+        directives: Vec<(Position, DirectiveKind)>,
+        context: &VariableContext,
+        key: &str,
+        key_position: Position,
+    ) {
+        if directives.is_empty() {
+            return;
+        }
+        let key_path = context.path_to(key);
+        let end = self
+            .current_token_start()
+            .map_or(self.lexer.source_len(), |position| position.offset);
+
+        for (position, kind) in directives {
+            match &kind {
+                DirectiveKind::DisableLints(codes) => self
+                    .directive_state
+                    .suppressions
+                    .push((codes.clone(), key_position.offset..end)),
+                DirectiveKind::Deprecated(note) => {
+                    let mut message = format!("`{}` is deprecated", key_path.join("."));
+                    if !note.is_empty() {
+                        message.push_str(": ");
+                        message.push_str(note);
+                    }
+                    self.diagnostics
+                        .push(Diagnostic::new(message, key_position));
+                }
+            }
+            self.directive_state.attached.push(Directive {
+                kind,
+                position,
+                key_path: key_path.clone(),
+                key_position,
+            });
+        }
+    }
+
+    /// Orders attached directives by source and drops suppressed lint diagnostics
+    fn finish_directives(&mut self) {
+        // Nested entries finish, and attach their directives, before their parents
+        self.directive_state
+            .attached
+            .sort_by_key(|directive| directive.position.offset);
+        let suppressions = &self.directive_state.suppressions;
+        if suppressions.is_empty() {
+            return;
+        }
+        self.diagnostics.retain(|diagnostic| {
+            let Some(code) = diagnostic.code else {
+                return true;
+            };
+            !suppressions.iter().any(|(codes, range)| {
+                codes.contains(&code) && range.contains(&diagnostic.position.offset)
+            })
+        });
+    }
+
+    /// Detects the syntax style for the current key-value pair using lookahead
+    fn detect_syntax_style(&mut self) -> Result<SyntaxStyle, ParseError> {
+        match self.current_token() {
+            Some(Token::Colon) | Some(Token::Equals) => Ok(SyntaxStyle::Explicit),
+            Some(Token::ObjectStart) => Ok(SyntaxStyle::Implicit),
+            Some(Token::Key(_)) | Some(Token::String { .. }) => {
+                match self.next_token_matches(|token| matches!(token, Some(Token::ObjectStart)))? {
+                    true => Ok(SyntaxStyle::NginxNested),
+                    false => Ok(SyntaxStyle::Implicit),
+                }
+            }
+            _ => Ok(SyntaxStyle::Implicit),
+        }
+    }
+
+    /// Checks the token after the current one with `predicate`, without consuming either
+    ///
+    /// Comments are peeked past; their directives are read once they are consumed.
+    fn next_token_matches(
+        &mut self,
+        predicate: impl FnOnce(Option<&Token<'a>>) -> bool,
+    ) -> Result<bool, ParseError> {
+        let mut n = 1;
+        while let Token::Comment(_) = self.lexer.peek_token_n(n)? {
+            n += 1;
+        }
+        Ok(predicate(Some(self.lexer.peek_token_n(n)?)))
+    }
+
+    /// Returns true if the current token is the first of its line
+    ///
+    /// A heredoc takes the line break after its terminator, so a token right
+    /// after one starts a line too.
+    fn at_line_start(&self) -> bool {
+        self.lexer.last_token_had_newline()
+            || self
+                .previous_token_end
+                .is_some_and(|end| end.column == 1 && end.offset > 0)
+    }
+
+    /// Reports an entry that follows a value on the same line as a missing separator
+    ///
+    /// A value ends at a newline, `,`, `;` or closing brace. Another entry may
+    /// follow on the same line, but when its key turns out to have no `=`,
+    /// `:`, `{` or relaxed value, the key was most likely a second value,
+    /// and the error points at it.
+    fn missing_separator(error: ParseError, found: &'static str, position: Position) -> ParseError {
+        match error {
+            ParseError::UnexpectedToken { expected, .. } if expected.first() == Some(&"'='") => {
+                ParseError::MissingSeparator { found, position }
+            }
+            error => error,
+        }
+    }
+
+    /// Checks if additional tokens should be treated as part of the current value
+    fn has_inline_value_continuation(&self) -> bool {
+        match self.current_token() {
+            Some(Token::Semicolon)
+            | Some(Token::Comma)
+            | Some(Token::ObjectEnd)
+            | Some(Token::ArrayEnd) => false,
+            Some(Token::Eof) | None => false,
+            Some(_) => !self.lexer.last_token_had_newline(),
+        }
+    }
+
+    /// Returns the raw text for the current token between the given positions
+    fn token_text_from_positions(&self, start: Position, end: Position) -> String {
+        self.lexer
+            .source_text(start.offset..end.offset)
+            .map(Cow::into_owned)
+            .unwrap_or_default()
+    }
+
+    /// Collects inline tokens that belong to the same value (same line)
+    fn collect_inline_value(
+        &mut self,
+        mut current: String,
+        context: &VariableContext,
+    ) -> Result<String, ParseError> {
+        let mut _first_token_is_key = false;
+        let mut _saw_separator = false;
+        let mut parts_appended = 0usize;
+
+        while self.has_inline_value_continuation() {
+            let token_start = match self.current_token_start() {
+                Some(pos) => pos,
+                None => break,
+            };
+
+            let gap_text = self.lexer.last_token_leading_whitespace();
+            if !gap_text.is_empty() {
+                current.push_str(gap_text);
+            }
+
+            let part_end = self.current_token_end().unwrap_or(token_start);
+            let token_snapshot = self.current_token().cloned();
+
+            let part = match token_snapshot.as_ref() {
+                Some(Token::Key(text)) => {
+                    if parts_appended == 0 {
+                        _first_token_is_key = true;
+                    }
+                    let part_str = text.to_string();
+                    self.validate_bare_word(&part_str, token_start)?;
+                    part_str
+                }
+                Some(Token::String {
+                    value,
+                    needs_expansion,
+                    ..
+                }) => {
+                    if *needs_expansion {
+                        self.expand_variables_with_context_safe(value, context)?
+                    } else {
+                        value.to_string()
+                    }
+                }
+                Some(Token::Integer(val)) => val.to_string(),
+                Some(Token::Float(val)) => {
+                    let mut text = val.to_string();
+                    if text.contains('.') {
+                        while text.ends_with('0') {
+                            text.pop();
+                        }
+                        if text.ends_with('.') {
+                            text.push('0');
+                        }
+                    }
+                    text
+                }
+                Some(Token::Time(_)) => self.token_text_from_positions(token_start, part_end),
+                Some(Token::Boolean(true)) => "true".to_string(),
+                Some(Token::Boolean(false)) => "false".to_string(),
+                Some(Token::Null) => "null".to_string(),
+                Some(Token::Colon) => {
+                    _saw_separator = true;
+                    ":".to_string()
+                }
+                Some(Token::Equals) => {
+                    _saw_separator = true;
+                    "=".to_string()
+                }
+                _ => break,
+            };
+
+            current.push_str(&part);
+            parts_appended += 1;
+
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+        }
+
+        Ok(current)
+    }
+
+    /// Handles inline string concatenation using '+' on the same line
+    fn concatenate_inline_strings(
+        &mut self,
+        mut current: String,
+        context: Option<&VariableContext>,
+    ) -> Result<String, ParseError> {
+        while matches!(self.current_token(), Some(Token::Plus))
+            && !self.lexer.last_token_had_newline()
+        {
+            let plus_position = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+
+            let appended = match self.current_token() {
+                Some(Token::String {
+                    value: next_value,
+                    needs_expansion: next_needs_expansion,
+                    ..
+                }) => {
+                    if let Some(ctx) = context {
+                        if *next_needs_expansion {
+                            self.expand_variables_with_context_safe(next_value, ctx)?
+                        } else {
+                            next_value.to_string()
+                        }
+                    } else if *next_needs_expansion {
+                        self.expand_variables(next_value)?
+                    } else {
+                        next_value.to_string()
+                    }
+                }
+                Some(_) => {
+                    return Err(ParseError::InvalidObject {
+                        message:
+                            "String concatenation requires quoted strings on both sides of '+'"
+                                .to_string(),
+                        position: self
+                            .current_token_start()
+                            .unwrap_or_else(|| self.current_position()),
+                    });
+                }
+                None => {
+                    return Err(ParseError::InvalidObject {
+                        message:
+                            "String concatenation terminates unexpectedly; missing right-hand string"
+                                .to_string(),
+                        position: plus_position,
+                    });
+                }
+            };
+
+            current.push_str(&appended);
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+        }
+
+        Ok(current)
+    }
+
+    /// Parses a bare word value (unquoted identifier)
+    fn parse_bare_word_value(&mut self) -> Result<UclValue, ParseError> {
+        let context = VariableContext::new(self.current_position());
+        self.parse_bare_word_value_with_context(&context, false)
+    }
+
+    /// Parses a bare word value with explicit context (unquoted identifier)
+    fn parse_bare_word_value_with_context(
+        &mut self,
+        context: &VariableContext,
+        explicit_separator: bool,
+    ) -> Result<UclValue, ParseError> {
+        match self.current_token() {
+            Some(Token::Key(word)) => {
+                let word_str = word.to_string();
+                let start_position = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let bare_word_end = self
+                    .current_token_end()
+                    .unwrap_or_else(|| self.current_position());
+                self.advance_token()?;
+
+                // Validate bare word before processing
+                self.validate_bare_word(&word_str, start_position)?;
+
+                self.skip_whitespace_and_comments()?;
+
+                // Detect structural delimiters that are directly attached to the bare word (e.g. hello{world)
                 // Exception: ${ is allowed for variable expansion
                 let delimiter_error = if let (Some(next_start), Some(token)) =
                     (self.current_token_start(), self.current_token())
@@ -1792,825 +6517,1896 @@ impl<'a> UclParser<'a> {
                         None
                     }
                 } else {
-                    None
-                };
+                    None
+                };
+
+                if let Some((delimiter, position)) = delimiter_error {
+                    return Err(ParseError::InvalidObject {
+                        message: format!(
+                            "Bare word '{}' cannot be adjacent to '{}'. quote the value if you need this character literally.",
+                            word_str, delimiter
+                        ),
+                        position,
+                    });
+                }
+
+                let inline_continuation =
+                    self.has_inline_value_continuation() && !explicit_separator;
+
+                if inline_continuation {
+                    let combined = self.collect_inline_value(word_str, context)?;
+                    let processed = self.parsing_hooks.process_owned_string(combined, context)?;
+                    let ucl_value = UclValue::String(processed);
+                    let validated = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated)
+                } else {
+                    // Check for special keywords when no continuation is present
+                    let normalized = word_str.to_ascii_lowercase();
+                    let non_finite_keywords = self.lexer.config().non_finite_keywords;
+                    let ucl_value = match normalized.as_str() {
+                        "true" | "yes" | "on" => UclValue::Boolean(true),
+                        "false" | "no" | "off" => UclValue::Boolean(false),
+                        "null" => UclValue::Null,
+                        "inf" | "infinity" if non_finite_keywords => self.apply_non_finite_policy(
+                            f64::INFINITY,
+                            start_position,
+                            bare_word_end,
+                        )?,
+                        "-inf" | "-infinity" if non_finite_keywords => self
+                            .apply_non_finite_policy(
+                                f64::NEG_INFINITY,
+                                start_position,
+                                bare_word_end,
+                            )?,
+                        "nan" if non_finite_keywords => {
+                            self.apply_non_finite_policy(f64::NAN, start_position, bare_word_end)?
+                        }
+                        _ => match self.config.constant(&word_str) {
+                            Some(value) => value.clone(),
+                            None if self.config.strict_constants => {
+                                return Err(ParseError::UnknownConstant {
+                                    name: word_str,
+                                    position: start_position,
+                                });
+                            }
+                            None => {
+                                let processed =
+                                    self.parsing_hooks.process_owned_string(word_str, context)?;
+                                UclValue::String(processed)
+                            }
+                        },
+                    };
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated_value)
+                }
+            }
+            Some(Token::String {
+                value,
+                format,
+                needs_expansion,
+            }) => {
+                let format = *format;
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let mut combined_value = if *needs_expansion {
+                    self.expand_variables_with_context_safe(value, context)?
+                } else {
+                    self.string_value(value)
+                };
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
+
+                while self.has_inline_value_continuation() {
+                    match self.current_token() {
+                        Some(Token::Plus) => {
+                            self.advance_token()?;
+                            self.skip_whitespace_and_comments()?;
+
+                            match self.current_token() {
+                                Some(Token::String {
+                                    value: next_value,
+                                    needs_expansion: next_needs_expansion,
+                                    ..
+                                }) => {
+                                    let appended = if *next_needs_expansion {
+                                        self.expand_variables_with_context_safe(
+                                            next_value, context,
+                                        )?
+                                    } else {
+                                        next_value.to_string()
+                                    };
+                                    combined_value.push_str(&appended);
+                                    self.advance_token()?;
+                                    self.skip_whitespace_and_comments()?;
+                                }
+                                Some(Token::Key(_)) => {
+                                    return Err(ParseError::InvalidObject {
+                                        message: "String concatenation requires quoted strings on both sides of '+'".to_string(),
+                                        position: self
+                                            .current_token_start()
+                                            .unwrap_or_else(|| self.current_position()),
+                                    });
+                                }
+                                _ => {
+                                    return Err(self.unexpected_token(&["string"]));
+                                }
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+
+                self.lint_typed_string(&combined_value, format, start);
+                let processed = self
+                    .parsing_hooks
+                    .process_string(&combined_value, context)?;
+                let ucl_value = UclValue::String(processed);
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
+                Ok(validated_value)
+            }
+            Some(Token::Integer(val)) => {
+                let int_val = *val;
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let end = self.current_token_end().unwrap_or(start);
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
+
+                if let Some(ucl_value) =
+                    self.apply_number_suffix(int_val as f64, start, end, context)?
+                {
+                    return self.parsing_hooks.validate_owned_value(ucl_value, context);
+                }
+
+                let inline_continuation =
+                    self.has_inline_value_continuation() && !explicit_separator;
+
+                if inline_continuation {
+                    // Collect remaining tokens on the same line
+                    let combined = self.collect_inline_value(int_val.to_string(), context)?;
+                    let processed = self.parsing_hooks.process_owned_string(combined, context)?;
+                    let ucl_value = UclValue::String(processed);
+                    let validated = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated)
+                } else {
+                    let ucl_value = UclValue::Integer(int_val);
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated_value)
+                }
+            }
+            Some(Token::Float(val)) => {
+                let float_val = *val;
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let end = self.current_token_end().unwrap_or(start);
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
 
-                if let Some((delimiter, position)) = delimiter_error {
-                    return Err(ParseError::InvalidObject {
-                        message: format!(
-                            "Bare word '{}' cannot be adjacent to '{}'. quote the value if you need this character literally.",
-                            word_str, delimiter
-                        ),
-                        position,
-                    });
+                if let Some(ucl_value) = self.apply_number_suffix(float_val, start, end, context)? {
+                    return self.parsing_hooks.validate_owned_value(ucl_value, context);
+                }
+
+                let inline_continuation =
+                    self.has_inline_value_continuation() && !explicit_separator;
+
+                if inline_continuation {
+                    // Collect remaining tokens on the same line
+                    let combined = self.collect_inline_value(float_val.to_string(), context)?;
+                    let processed = self.parsing_hooks.process_owned_string(combined, context)?;
+                    let ucl_value = UclValue::String(processed);
+                    let validated = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated)
+                } else {
+                    let ucl_value = self.float_value(float_val, start, end)?;
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    #[cfg(test)]
+                    println!(
+                        "parse_value_with_context float={} is_finite={}",
+                        float_val,
+                        float_val.is_finite()
+                    );
+                    Ok(validated_value)
+                }
+            }
+            Some(Token::Time(_)) => {
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let end = self.current_token_end().unwrap_or(start);
+                let raw = self.token_text_from_positions(start, end);
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
+
+                let inline_continuation =
+                    self.has_inline_value_continuation() && !explicit_separator;
+
+                if inline_continuation {
+                    // Collect remaining tokens on the same line
+                    let combined = self.collect_inline_value(raw.clone(), context)?;
+                    let processed = self.parsing_hooks.process_owned_string(combined, context)?;
+                    let ucl_value = UclValue::String(processed);
+                    let validated = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated)
+                } else {
+                    let processed = self.parsing_hooks.process_owned_string(raw, context)?;
+                    let ucl_value = UclValue::String(processed);
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
+                    Ok(validated_value)
                 }
+            }
+            Some(Token::Boolean(val)) => {
+                let bool_val = *val;
+                self.advance_token()?;
+                let ucl_value = UclValue::Boolean(bool_val);
+
+                // Apply validation hooks
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
+                Ok(validated_value)
+            }
+            Some(Token::Null) => {
+                self.advance_token()?;
+                let ucl_value = UclValue::Null;
+
+                // Apply validation hooks
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
+                Ok(validated_value)
+            }
+            _ => Err(self.unexpected_token(&["value"])),
+        }
+    }
+
+    /// Validates a bare word value and provides helpful error messages for invalid cases
+    fn validate_bare_word(&self, word: &str, position: Position) -> Result<(), ParseError> {
+        // Check for empty word
+        if word.is_empty() {
+            return Err(ParseError::InvalidObject {
+                message: "Empty bare word is not allowed".to_string(),
+                position,
+            });
+        }
+
+        // Check for special characters that require quoting
+        let invalid_chars = [
+            ' ', '\t', '\n', '\r', '{', '}', '[', ']', '=', ':', ',', ';', '"', '\'', '@',
+        ];
+        if let Some(invalid_char) = word.chars().find(|&c| invalid_chars.contains(&c)) {
+            let suggestion = match invalid_char {
+                ' ' => format!("Quote the value: \"{}\"", word),
+                '\t' | '\n' | '\r' => "Remove whitespace characters or quote the value".to_string(),
+                '{' | '}' | '[' | ']' => "These characters are reserved for objects and arrays. Quote the value if you need them literally".to_string(),
+                '=' | ':' => "These characters are reserved for key-value separators. Quote the value if you need them literally".to_string(),
+                ',' | ';' => "These characters are reserved for separators. Quote the value if you need them literally".to_string(),
+                '"' | '\'' => "Quote characters must be escaped or the entire value must be quoted".to_string(),
+                _ => format!("Quote the value: \"{}\"", word),
+            };
+
+            return Err(ParseError::InvalidObject {
+                message: format!(
+                    "Bare word '{}' contains invalid character '{}'. {}",
+                    word, invalid_char, suggestion
+                ),
+                position,
+            });
+        }
+
+        // Check for ambiguous cases with reserved keywords
+        if self.is_ambiguous_bare_word(word) {
+            let suggestion = self.suggest_bare_word_fix(word);
+            return Err(ParseError::InvalidObject {
+                message: format!("Ambiguous bare word '{}'. {}", word, suggestion),
+                position,
+            });
+        }
+
+        // Check for words that look like numbers but aren't valid
+        if self.looks_like_invalid_number(word) {
+            return Err(ParseError::InvalidObject {
+                message: format!(
+                    "Bare word '{}' looks like a number but is invalid. Quote it if you want a literal string, or fix the number format",
+                    word
+                ),
+                position,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks if a bare word is ambiguous and might cause confusion
+    fn is_ambiguous_bare_word(&self, word: &str) -> bool {
+        // Check for words that might be confused with reserved keywords
+        match word.to_ascii_lowercase().as_str() {
+            // Words that might be confused with numbers
+            _ if word.starts_with('-') && word.len() > 1 => {
+                // Negative-looking words that aren't valid numbers
+                !word[1..].chars().all(|c| c.is_ascii_digit() || c == '.')
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if a word looks like it should be a number but isn't valid
+    fn looks_like_invalid_number(&self, word: &str) -> bool {
+        // Check for common invalid number patterns
+        if word.is_empty() {
+            return false;
+        }
+
+        if word.contains(':') || word.matches('.').count() > 1 {
+            return false;
+        }
+
+        // Starts with digit or sign but isn't a valid number
+        if word.starts_with(|ch: char| ch.is_ascii_digit() || ch == '-' || ch == '+') {
+            // Try to parse as number - if it fails but looks numeric, it's invalid
+            if word.parse::<i64>().is_err() && word.parse::<f64>().is_err() {
+                // Check if it contains mostly digits and number-like characters
+                let numeric_chars = word
+                    .chars()
+                    .filter(|c| {
+                        c.is_ascii_digit()
+                            || *c == '.'
+                            || *c == '-'
+                            || *c == '+'
+                            || *c == 'e'
+                            || *c == 'E'
+                    })
+                    .count();
+
+                // If more than half the characters look numeric, it's probably an invalid number
+                return numeric_chars > word.len() / 2;
+            }
+        }
+
+        false
+    }
+
+    /// Provides suggestions for fixing bare word issues
+    fn suggest_bare_word_fix(&self, word: &str) -> String {
+        if word.contains(' ') || word.contains('\t') {
+            format!("Quote the value: \"{}\"", word)
+        } else if ["true", "false", "null", "yes", "no", "on", "off"]
+            .contains(&word.to_lowercase().as_str())
+        {
+            format!(
+                "'{}' is a reserved keyword. Use \"{}\" for literal string",
+                word, word
+            )
+        } else {
+            format!(
+                "Use quotes if '{}' should be a literal string: \"{}\"",
+                word, word
+            )
+        }
+    }
+
+    /// Parses NGINX-style nested object: key identifier { ... }
+    fn parse_nginx_nested_object(
+        &mut self,
+        context: &mut VariableContext,
+    ) -> Result<UclValue, ParseError> {
+        // Current token should be the nested key identifier
+        let start = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let raw_nested_key = match self.current_token() {
+            Some(Token::Key(k)) => UclKey::from(k.as_ref()),
+            Some(Token::String { value, .. }) => UclKey::from(value.as_ref()),
+            _ => {
+                return Err(self.unexpected_token(&["key"]));
+            }
+        };
+        let nested_key = self.finish_key(raw_nested_key, start, context)?;
+
+        self.advance_token()?;
+        self.skip_whitespace_and_comments()?;
+
+        // Next must be an object
+        match self.current_token() {
+            Some(Token::ObjectStart) => {
+                context.push_key(nested_key.clone());
+                let nested_result = self.parse_object_with_context(context);
+                context.pop_key();
+
+                let nested_obj = nested_result?;
+
+                // Wrap it in a parent object with the nested_key
+                let mut wrapper = UclObject::new();
+                wrapper.insert(nested_key, nested_obj);
+                Ok(UclValue::Object(wrapper))
+            }
+            _ => Err(self.unexpected_token(&["'{'"])),
+        }
+    }
+
+    /// Parses a complete UCL value
+    pub fn parse_value(&mut self) -> Result<UclValue, ParseError> {
+        if self.current_depth >= self.config.max_depth {
+            return Err(ParseError::MaxDepthExceeded {
+                position: self.current_position(),
+            });
+        }
 
-                let inline_continuation =
-                    self.has_inline_value_continuation() && !explicit_separator;
+        self.skip_whitespace_and_comments()?;
 
-                if inline_continuation {
-                    let combined = self.collect_inline_value(word_str, context)?;
-                    let processed = self.parsing_hooks.process_string(&combined, context)?;
-                    let ucl_value = UclValue::String(processed);
-                    let validated = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated)
-                } else {
-                    // Check for special keywords when no continuation is present
-                    let normalized = word_str.to_ascii_lowercase();
-                    let ucl_value = match normalized.as_str() {
-                        "true" | "yes" | "on" => UclValue::Boolean(true),
-                        "false" | "no" | "off" => UclValue::Boolean(false),
-                        "null" => UclValue::Null,
-                        "inf" | "infinity" => UclValue::Float(f64::INFINITY),
-                        "-inf" | "-infinity" => UclValue::Float(f64::NEG_INFINITY),
-                        "nan" => UclValue::Float(f64::NAN),
-                        _ => {
-                            let processed =
-                                self.parsing_hooks.process_string(&word_str, context)?;
-                            UclValue::String(processed)
-                        }
-                    };
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated_value)
-                }
-            }
+        match self.current_token() {
+            Some(Token::Eof) | None => Err(self.unexpected_token(&["value"])),
             Some(Token::String {
                 value,
                 needs_expansion,
                 ..
             }) => {
-                let mut combined_value = if *needs_expansion {
-                    self.expand_variables_with_context_safe(value, context)?
+                let mut combined = if *needs_expansion {
+                    self.expand_variables(value)?
                 } else {
-                    value.to_string()
+                    self.string_value(value)
                 };
+
                 self.advance_token()?;
                 self.skip_whitespace_and_comments()?;
 
-                while self.has_inline_value_continuation() {
-                    match self.current_token() {
-                        Some(Token::Plus) => {
-                            self.advance_token()?;
-                            self.skip_whitespace_and_comments()?;
-
-                            match self.current_token() {
-                                Some(Token::String {
-                                    value: next_value,
-                                    needs_expansion: next_needs_expansion,
-                                    ..
-                                }) => {
-                                    let appended = if *next_needs_expansion {
-                                        self.expand_variables_with_context_safe(
-                                            next_value, context,
-                                        )?
-                                    } else {
-                                        next_value.to_string()
-                                    };
-                                    combined_value.push_str(&appended);
-                                    self.advance_token()?;
-                                    self.skip_whitespace_and_comments()?;
-                                }
-                                Some(Token::Key(_)) => {
-                                    return Err(ParseError::InvalidObject {
-                                        message: "String concatenation requires quoted strings on both sides of '+'".to_string(),
-                                        position: self
-                                            .current_token_start()
-                                            .unwrap_or_else(|| self.current_position()),
-                                    });
-                                }
-                                Some(token) => {
-                                    return Err(ParseError::UnexpectedToken {
-                                        token: token.type_name().to_string(),
-                                        position: self.current_position(),
-                                        expected: "string".to_string(),
-                                    });
-                                }
-                                None => {
-                                    return Err(ParseError::UnexpectedToken {
-                                        token: "end of file".to_string(),
-                                        position: self.current_position(),
-                                        expected: "string".to_string(),
-                                    });
-                                }
-                            }
-                        }
-                        _ => break,
-                    }
-                }
+                combined = self.concatenate_inline_strings(combined, None)?;
 
+                // Apply custom string post-processing
+                let context = VariableContext::new(self.current_position());
                 let processed = self
                     .parsing_hooks
-                    .process_string(&combined_value, context)?;
+                    .process_owned_string(combined, &context)?;
                 let ucl_value = UclValue::String(processed);
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+
+                // Apply validation hooks
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
                 Ok(validated_value)
             }
             Some(Token::Integer(val)) => {
                 let int_val = *val;
                 self.advance_token()?;
-                self.skip_whitespace_and_comments()?;
-
-                let inline_continuation =
-                    self.has_inline_value_continuation() && !explicit_separator;
+                let ucl_value = UclValue::Integer(int_val);
 
-                if inline_continuation {
-                    // Collect remaining tokens on the same line
-                    let combined = self.collect_inline_value(int_val.to_string(), context)?;
-                    let processed = self.parsing_hooks.process_string(&combined, context)?;
-                    let ucl_value = UclValue::String(processed);
-                    let validated = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated)
-                } else {
-                    let ucl_value = UclValue::Integer(int_val);
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated_value)
-                }
+                // Apply validation hooks
+                let context = VariableContext::new(self.current_position());
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
+                Ok(validated_value)
             }
             Some(Token::Float(val)) => {
                 let float_val = *val;
-                self.advance_token()?;
-                self.skip_whitespace_and_comments()?;
-
-                let inline_continuation =
-                    self.has_inline_value_continuation() && !explicit_separator;
-
-                if inline_continuation {
-                    // Collect remaining tokens on the same line
-                    let combined = self.collect_inline_value(float_val.to_string(), context)?;
-                    let processed = self.parsing_hooks.process_string(&combined, context)?;
-                    let ucl_value = UclValue::String(processed);
-                    let validated = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated)
-                } else {
-                    let ucl_value = UclValue::Float(float_val);
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    #[cfg(test)]
-                    println!(
-                        "parse_value_with_context float={} is_finite={}",
-                        float_val,
-                        float_val.is_finite()
-                    );
-                    Ok(validated_value)
-                }
-            }
-            Some(Token::Time(_)) => {
                 let start = self
                     .current_token_start()
                     .unwrap_or_else(|| self.current_position());
                 let end = self.current_token_end().unwrap_or(start);
-                let raw = self.token_text_from_positions(start, end);
                 self.advance_token()?;
-                self.skip_whitespace_and_comments()?;
+                let ucl_value = self.float_value(float_val, start, end)?;
 
-                let inline_continuation =
-                    self.has_inline_value_continuation() && !explicit_separator;
+                // Apply validation hooks
+                let context = VariableContext::new(self.current_position());
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
+                Ok(validated_value)
+            }
+            Some(Token::Time(val)) => {
+                let time_val = *val;
+                self.advance_token()?;
+                let ucl_value = UclValue::Time(time_val);
 
-                if inline_continuation {
-                    // Collect remaining tokens on the same line
-                    let combined = self.collect_inline_value(raw.clone(), context)?;
-                    let processed = self.parsing_hooks.process_string(&combined, context)?;
-                    let ucl_value = UclValue::String(processed);
-                    let validated = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated)
-                } else {
-                    let processed = self.parsing_hooks.process_string(&raw, context)?;
-                    let ucl_value = UclValue::String(processed);
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                    Ok(validated_value)
+                // Apply validation hooks
+                let context = VariableContext::new(self.current_position());
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
+                Ok(validated_value)
+            }
+            Some(Token::Boolean(val)) => {
+                let bool_val = *val;
+                self.advance_token()?;
+                let ucl_value = UclValue::Boolean(bool_val);
+
+                // Apply validation hooks
+                let context = VariableContext::new(self.current_position());
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
+                Ok(validated_value)
+            }
+            Some(Token::Null) => {
+                self.advance_token()?;
+                let ucl_value = UclValue::Null;
+
+                // Apply validation hooks
+                let context = VariableContext::new(self.current_position());
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, &context)?;
+                Ok(validated_value)
+            }
+            Some(Token::ObjectStart) => self.parse_object(),
+            Some(Token::ArrayStart) => self.parse_array(),
+            Some(Token::Key(_)) => {
+                // Handle bare word values (unquoted identifiers)
+                self.parse_bare_word_value()
+            }
+            Some(_) => Err(self.unexpected_token(&["value"])),
+        }
+    }
+
+    /// Parses a UCL object with variable expansion context
+    pub fn parse_object(&mut self) -> Result<UclValue, ParseError> {
+        self.parse_object_with_context(&mut VariableContext::new(self.current_position()))
+    }
+
+    /// Parses a UCL object with explicit variable context
+    pub fn parse_object_with_context(
+        &mut self,
+        context: &mut VariableContext,
+    ) -> Result<UclValue, ParseError> {
+        self.current_depth += 1;
+        if self.current_depth > self.config.max_depth {
+            return Err(ParseError::MaxDepthExceeded {
+                position: self.current_position(),
+            });
+        }
+        self.check_cancelled()?;
+
+        // Consume the opening brace, after any comments saved before it
+        self.skip_whitespace_and_comments()?;
+        self.expect_token(&Token::ObjectStart)?;
+
+        let mut object = self.pool.borrow_mut().object();
+
+        // Handle empty object
+        self.skip_whitespace_and_comments()?;
+        if let Some(Token::ObjectEnd) = self.current_token() {
+            self.advance_token()?;
+            self.current_depth -= 1;
+            return Ok(UclValue::Object(object));
+        }
+
+        let mut key_positions = KeyPositions::new();
+        // Entries merged from `*name` references, until a key of this object replaces them
+        let mut inherited = self.parse_merge_references(&mut object, &mut key_positions)?;
+        let mut first_separator = None;
+        let depth = self.current_depth;
+        let mut after_entry = !object.is_empty();
+
+        loop {
+            self.skip_whitespace_and_comments()?;
+            let same_line =
+                after_entry && self.current_separator().is_none() && !self.at_line_start();
+            if let Err(error) = self.skip_separators(SeparatorContext::Object, !after_entry) {
+                self.recover_entry(error, depth, true, true)?;
+                continue;
+            }
+            after_entry = true;
+
+            let key_token = self.current_token().map_or("end of file", Token::type_name);
+            let key_position = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+            let key_span = Span::new(
+                key_position,
+                self.current_token_end().unwrap_or(key_position),
+            );
+
+            let directives = std::mem::take(&mut self.directive_state.pending);
+
+            // Parse key - support various key formats
+            let dotted = self.at_dotted_key();
+            self.reading_dotted_key = dotted;
+            let key = self.parse_object_key(context);
+            self.reading_dotted_key = false;
+            let key = match key {
+                Ok(Some(key)) => key,
+                Ok(None) => break,
+                Err(error) => {
+                    self.recover_entry(error, depth, true, true)?;
+                    if self.at_end_of_input() {
+                        self.current_depth = depth - 1;
+                        return Ok(UclValue::Object(object));
+                    }
+                    continue;
+                }
+            };
+
+            // The value of `a.b.c` is read under `a.b`, with `c` as its key
+            let dotted_path = match dotted
+                .then(|| self.split_dotted_key(&key, &key_span, context))
+                .transpose()
+            {
+                Ok(dotted_path) => dotted_path,
+                Err(error) => {
+                    self.recover_entry(error, depth, true, false)?;
+                    continue;
+                }
+            };
+            let (key, sections) = match dotted_path.as_deref() {
+                Some([sections @ .., leaf]) => (leaf.clone(), sections),
+                _ => (key, &[][..]),
+            };
+            let path_len = context.current_object_path.len();
+            context.current_object_path.extend_from_slice(sections);
+            context.push_key(key);
+            let entry = self.parse_entry_value(context);
+            let key = context.pop_key().unwrap_or_default();
+            let (assignment, separator, value, labelled) = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    context.current_object_path.truncate(path_len);
+                    let error = if same_line {
+                        Self::missing_separator(error, key_token, key_position)
+                    } else {
+                        error
+                    };
+                    self.recover_entry(error, depth, true, false)?;
+                    if dotted_path.is_none()
+                        && let Entry::Vacant(entry) = object.entry(key)
+                    {
+                        entry.insert(UclValue::Null);
+                        key_positions.push(key_position);
+                    }
+                    continue;
+                }
+            };
+            self.attach_directives(directives, context, &key, key_position);
+            self.record_assignment(context, &key, assignment);
+            self.lint_entry(context, &key, key_position, &value);
+            context.current_object_path.truncate(path_len);
+            self.lint_separator(&mut first_separator, separator, &key);
+
+            // Handle duplicate keys based on configuration
+            if let Some(path) = &dotted_path {
+                let index = object.get_index_of(&path[0]);
+                let first_occurrence = index
+                    .and_then(|index| key_positions.get(index).copied())
+                    .unwrap_or(key_position);
+                let result = self.insert_dotted_entry(
+                    &mut object,
+                    context,
+                    path,
+                    (assignment, value),
+                    key_span,
+                    first_occurrence,
+                );
+                if index.is_none() {
+                    key_positions.push(key_position);
+                }
+                if let Err(error) = result {
+                    self.recover_entry(error, depth, true, false)?;
+                    continue;
+                }
+            } else if assignment == Assignment::Append
+                && let Some(existing_value) = object.get_mut(&key)
+            {
+                if let Err(error) = self.append_value(existing_value, value, &key, key_position) {
+                    self.recover_entry(error, depth, true, false)?;
+                    continue;
+                }
+                self.record_origin(context, &key, key_span);
+            } else if let Some(index) = object
+                .get_index_of(&key)
+                .filter(|&index| inherited.get(index) == Some(&true))
+            {
+                // Keys written in the object replace merged ones, as YAML merge keys do
+                inherited[index] = false;
+                key_positions[index] = key_position;
+                self.record_origin(context, &key, key_span);
+                object[index] = value;
+            } else if let Some((index, _, existing_value)) = object.get_full_mut(&key) {
+                self.counters.duplicate_keys += 1;
+                let first_occurrence = key_positions.get(index).copied().unwrap_or(key_position);
+                // Repeated sections are merged, but a repeated JSON key is one value replacing another
+                if let UclValue::Object(existing_map) = existing_value
+                    && let UclValue::Object(ref new_map) = value
+                    && !self.config.json_only
+                    && self.config.duplicate_key_behavior != DuplicateKeyBehavior::Error
+                {
+                    for (nested_key, nested_value) in new_map.iter() {
+                        existing_map.insert(nested_key.clone(), nested_value.clone());
+                    }
+                    continue;
+                }
+
+                match self.config.duplicate_key_behavior {
+                    DuplicateKeyBehavior::Error => {
+                        let labels = Self::section_labels(&value, labelled, &[]);
+                        self.add_repeated_section(
+                            &mut object[index],
+                            &value,
+                            (&key, &labels),
+                            context,
+                            (first_occurrence, key_position),
+                        )?;
+                    }
+                    DuplicateKeyBehavior::ImplicitArray => {
+                        // Replace the value in place so the key keeps its first position
+                        let slot = &mut object[index];
+                        match slot {
+                            UclValue::Array(arr) => arr.push(value),
+                            other => {
+                                let mut arr = SmallVec::new();
+                                arr.push(std::mem::take(other));
+                                arr.push(value);
+                                *other = UclValue::Array(Box::new(arr));
+                            }
+                        }
+                    }
+                    DuplicateKeyBehavior::Override => {
+                        self.report_duplicate_key(
+                            &key,
+                            first_occurrence,
+                            key_position,
+                            "overrides",
+                        );
+                        self.record_origin(context, &key, key_span);
+                        object.insert(key, value);
+                    }
+                    DuplicateKeyBehavior::KeepFirst => {
+                        self.report_duplicate_key(
+                            &key,
+                            first_occurrence,
+                            key_position,
+                            "is ignored in favor of",
+                        );
+                    }
+                }
+            } else {
+                key_positions.push(key_position);
+                self.record_origin(context, &key, key_span);
+                if labelled {
+                    let labels = Self::section_labels(&value, labelled, &[]);
+                    self.record_section_levels(context, &key, &labels, key_position);
                 }
+                object.insert(key, value);
             }
-            Some(Token::Boolean(val)) => {
-                let bool_val = *val;
-                self.advance_token()?;
-                let ucl_value = UclValue::Boolean(bool_val);
 
-                // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                Ok(validated_value)
-            }
-            Some(Token::Null) => {
-                self.advance_token()?;
-                let ucl_value = UclValue::Null;
+            self.skip_whitespace_and_comments()?;
 
-                // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
-                Ok(validated_value)
+            // Check for separator or end (separators are optional in implicit syntax)
+            match self.current_token() {
+                Some(Token::Comma) | Some(Token::Semicolon) => {
+                    // The whole run is read before the next key
+                }
+                Some(Token::ObjectEnd) => {
+                    // End of object
+                    break;
+                }
+                Some(Token::Key(_))
+                | Some(Token::String { .. })
+                | Some(Token::Boolean(_))
+                | Some(Token::Null)
+                | Some(Token::Integer(_))
+                | Some(Token::Float(_))
+                    if !self.config.json_only =>
+                {
+                    // Next key without separator (implicit syntax)
+                    // Continue to next key-value pair
+                }
+                _ => {
+                    let error = if self.config.json_only {
+                        self.unexpected_token(&["','", "'}'"])
+                    } else {
+                        self.unexpected_token(&["','", "';'", "key", "'}'"])
+                    };
+                    self.recover_entry(error, depth, true, false)?;
+                }
             }
-            Some(token) => Err(ParseError::UnexpectedToken {
-                token: token.type_name().to_string(),
-                position: self.current_position(),
-                expected: "bare word value".to_string(),
-            }),
-            None => Err(ParseError::UnexpectedToken {
-                token: "EOF".to_string(),
-                position: self.current_position(),
-                expected: "bare word value".to_string(),
-            }),
-        }
-    }
-
-    /// Validates a bare word value and provides helpful error messages for invalid cases
-    fn validate_bare_word(&self, word: &str, position: Position) -> Result<(), ParseError> {
-        // Check for empty word
-        if word.is_empty() {
-            return Err(ParseError::InvalidObject {
-                message: "Empty bare word is not allowed".to_string(),
-                position,
-            });
         }
 
-        // Check for special characters that require quoting
-        let invalid_chars = [
-            ' ', '\t', '\n', '\r', '{', '}', '[', ']', '=', ':', ',', ';', '#', '"', '\'', '@',
-        ];
-        if let Some(invalid_char) = word.chars().find(|&c| invalid_chars.contains(&c)) {
-            let suggestion = match invalid_char {
-                ' ' => format!("Quote the value: \"{}\"", word),
-                '\t' | '\n' | '\r' => "Remove whitespace characters or quote the value".to_string(),
-                '{' | '}' | '[' | ']' => "These characters are reserved for objects and arrays. Quote the value if you need them literally".to_string(),
-                '=' | ':' => "These characters are reserved for key-value separators. Quote the value if you need them literally".to_string(),
-                ',' | ';' => "These characters are reserved for separators. Quote the value if you need them literally".to_string(),
-                '#' => "This character starts a comment. Quote the value if you need it literally".to_string(),
-                '"' | '\'' => "Quote characters must be escaped or the entire value must be quoted".to_string(),
-                _ => format!("Quote the value: \"{}\"", word),
-            };
+        // Consume the closing brace
+        self.expect_token(&Token::ObjectEnd)?;
+        self.current_depth -= 1;
 
-            return Err(ParseError::InvalidObject {
-                message: format!(
-                    "Bare word '{}' contains invalid character '{}'. {}",
-                    word, invalid_char, suggestion
-                ),
-                position,
-            });
-        }
+        Ok(UclValue::Object(object))
+    }
 
-        // Check for ambiguous cases with reserved keywords
-        if self.is_ambiguous_bare_word(word) {
-            let suggestion = self.suggest_bare_word_fix(word);
-            return Err(ParseError::InvalidObject {
-                message: format!("Ambiguous bare word '{}'. {}", word, suggestion),
-                position,
-            });
-        }
+    /// Parses a UCL array
+    pub fn parse_array(&mut self) -> Result<UclValue, ParseError> {
+        self.parse_array_with_context(&mut VariableContext::new(self.current_position()))
+    }
 
-        // Check for words that look like numbers but aren't valid
-        if self.looks_like_invalid_number(word) {
-            return Err(ParseError::InvalidObject {
-                message: format!(
-                    "Bare word '{}' looks like a number but is invalid. Quote it if you want a literal string, or fix the number format",
-                    word
+    /// Parses a top-level UCL document (may be an implicit object)
+    ///
+    /// As in JSON, the whole document may also be a single array, object or
+    /// scalar. Content after it is a `ParseError::TrailingContent`.
+    pub fn parse_document(&mut self) -> Result<UclValue, ParseError> {
+        let value = self.parse_document_filtered(None)?;
+        if let (Some(priority), Some(position)) = (self.priority, self.unused_priority.take()) {
+            self.diagnostics.push(Diagnostic::new(
+                format!(
+                    "'.priority {}' has no effect on a plain value; use parse_tracked to merge by priority",
+                    priority
                 ),
                 position,
-            });
+            ));
         }
-
-        Ok(())
+        Ok(value)
     }
 
-    /// Checks if a bare word is ambiguous and might cause confusion
-    fn is_ambiguous_bare_word(&self, word: &str) -> bool {
-        // Check for words that might be confused with reserved keywords
-        match word.to_ascii_lowercase().as_str() {
-            // Words that might be confused with numbers
-            _ if word.starts_with('-') && word.len() > 1 => {
-                // Negative-looking words that aren't valid numbers
-                !word[1..].chars().all(|c| c.is_ascii_digit() || c == '.')
+    /// Parses only the top-level entries with the given keys
+    ///
+    /// The values of all other top-level entries are skipped token by token
+    /// without being built, so extracting one section of a large document
+    /// costs little more than lexing it. Entries that are kept are parsed as
+    /// `parse_document` would parse them, repeated keys included. Keys that are
+    /// not in the document are absent from the result, which is empty when
+    /// the document is an array or a scalar.
+    ///
+    /// With `ParserConfig::enable_anchors`, every entry is parsed so that
+    /// references to anchors in skipped sections resolve.
+    ///
+    /// ```rust
+    /// use ucl_lexer::UclParser;
+    ///
+    /// let input = "server { port = 80 }\nlogging { level = debug }\ncache { size = 1gb }";
+    /// let sections = UclParser::new(input).parse_document_keys(&["logging"])?;
+    /// assert_eq!(sections.len(), 1);
+    /// assert_eq!(sections["logging"]["level"], "debug");
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn parse_document_keys(&mut self, keys: &[&str]) -> Result<UclObject, ParseError> {
+        match &mut self.parse_document_filtered(Some(keys))? {
+            UclValue::Object(object) => {
+                let mut object = std::mem::take(object);
+                object.retain(|key, _| keys.contains(&key.as_str()));
+                Ok(object)
             }
-            _ => false,
+            _ => Ok(UclObject::new()),
         }
     }
 
-    /// Checks if a word looks like it should be a number but isn't valid
-    fn looks_like_invalid_number(&self, word: &str) -> bool {
-        // Check for common invalid number patterns
-        if word.is_empty() {
-            return false;
-        }
-
-        if word.contains(':') || word.matches('.').count() > 1 {
-            return false;
+    /// Parses the document, skipping top-level entries whose keys are not in `keys`
+    ///
+    /// The values of skipped entries of an implicit root object are not built;
+    /// their keys map to `UclValue::Null`.
+    pub(crate) fn parse_document_filtered(
+        &mut self,
+        keys: Option<&[&str]>,
+    ) -> Result<UclValue, ParseError> {
+        let size = self.lexer.source_len();
+        if size > self.config.max_document_size {
+            return Err(ParseError::DocumentTooLarge {
+                size,
+                limit: self.config.max_document_size,
+            });
         }
 
-        let first_char = word.chars().next().unwrap();
-
-        // Starts with digit or sign but isn't a valid number
-        if first_char.is_ascii_digit() || first_char == '-' || first_char == '+' {
-            // Try to parse as number - if it fails but looks numeric, it's invalid
-            if word.parse::<i64>().is_err() && word.parse::<f64>().is_err() {
-                // Check if it contains mostly digits and number-like characters
-                let numeric_chars = word
-                    .chars()
-                    .filter(|c| {
-                        c.is_ascii_digit()
-                            || *c == '.'
-                            || *c == '-'
-                            || *c == '+'
-                            || *c == 'e'
-                            || *c == 'E'
-                    })
-                    .count();
-
-                // If more than half the characters look numeric, it's probably an invalid number
-                return numeric_chars > word.len() / 2;
-            }
+        let started = Instant::now();
+        self.trailing_content_offset = None;
+        let keys = keys.filter(|_| !self.config.enable_anchors);
+        let result = self
+            .parse_document_root(keys)
+            .and_then(|value| self.resolve_includes(value));
+        self.counters.duration += started.elapsed();
+        self.report_empty_objects();
+        let lexer_diagnostics = self.lexer.take_diagnostics();
+        self.diagnostics.extend(lexer_diagnostics);
+        if result.is_ok() {
+            self.report_unused_variables();
         }
-
-        false
+        self.finish_directives();
+        result
     }
 
-    /// Provides suggestions for fixing bare word issues
-    fn suggest_bare_word_fix(&self, word: &str) -> String {
-        if word.contains(' ') || word.contains('\t') {
-            format!("Quote the value: \"{}\"", word)
-        } else if ["true", "false", "null", "yes", "no", "on", "off"]
-            .contains(&word.to_lowercase().as_str())
-        {
-            format!(
-                "'{}' is a reserved keyword. Use \"{}\" for literal string",
-                word, word
-            )
-        } else {
-            format!(
-                "Use quotes if '{}' should be a literal string: \"{}\"",
-                word, word
-            )
+    /// Records a diagnostic listing the variables the handler provided but no string used
+    fn report_unused_variables(&mut self) {
+        let Some(handler) = &self.variable_handler else {
+            return;
+        };
+        let unused = handler.get().unused_variables();
+        if !unused.is_empty() {
+            self.diagnostics.push(Diagnostic::new(
+                format!("variables provided but never used: {}", unused.join(", ")),
+                Position::new(),
+            ));
         }
     }
 
-    /// Parses NGINX-style nested object: key identifier { ... }
-    fn parse_nginx_nested_object(
-        &mut self,
-        context: &mut VariableContext,
-    ) -> Result<UclValue, ParseError> {
-        // Current token should be the nested key identifier
-        let raw_nested_key = match self.current_token() {
-            Some(Token::Key(k)) => k.to_string(),
-            Some(Token::String { value, .. }) => value.to_string(),
-            Some(token) => {
-                return Err(ParseError::UnexpectedToken {
-                    token: token.type_name().to_string(),
-                    position: self.current_position(),
-                    expected: "identifier for NGINX-style object".to_string(),
-                });
-            }
-            None => {
-                return Err(ParseError::UnexpectedToken {
-                    token: "EOF".to_string(),
-                    position: self.current_position(),
-                    expected: "identifier for NGINX-style object".to_string(),
-                });
-            }
-        };
-        let nested_key = self.parsing_hooks.validate_key(&raw_nested_key, context)?;
-
-        self.advance_token()?;
-        self.skip_whitespace_and_comments()?;
+    /// Parses `input` into `out`, reusing the allocations of the tree `out` holds
+    ///
+    /// The strings, objects and arrays of the previous value are cleared and
+    /// filled again, so reparsing a document of the same shape allocates far
+    /// less than `parse_document`. The result equals a fresh parse of `input`.
+    ///
+    /// This parser's configuration, variable handler and hooks apply, and its
+    /// diagnostics and directives describe `input` afterwards. Its own input
+    /// is left alone, so `stats` and positions still refer to that. If parsing
+    /// fails, `out` is `UclValue::Null` and the old buffers are kept for the
+    /// next call.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{UclParser, UclValue};
+    ///
+    /// let mut parser = UclParser::new("");
+    /// let mut config = UclValue::Null;
+    /// for port in [80, 8080] {
+    ///     let text = format!("server {{ host = localhost, port = {} }}", port);
+    ///     parser.parse_into(&text, &mut config)?;
+    ///     assert_eq!(config["server"]["port"], port);
+    /// }
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn parse_into(&mut self, input: &str, out: &mut UclValue) -> Result<(), ParseError> {
+        let mut parser = UclParser::with_lexer_config(input, self.lexer.config().clone());
+        parser.config = std::mem::take(&mut self.config);
+        parser.variable_handler = self
+            .variable_handler
+            .as_ref()
+            .map(|handler| VariableHandlerSlot::Borrowed(handler.get()));
+        parser.parsing_hooks = std::mem::take(&mut self.parsing_hooks);
+        parser.source_name = std::mem::take(&mut self.source_name);
+        parser.include_resolver = self.include_resolver.clone();
+        parser.include_depth = self.include_depth;
+        parser.pool = std::mem::take(&mut self.pool);
+        parser
+            .pool
+            .get_mut()
+            .recycle(std::mem::replace(out, UclValue::Null));
 
-        // Next must be an object
-        match self.current_token() {
-            Some(Token::ObjectStart) => {
-                context.push_key(nested_key.clone());
-                let nested_result = self.parse_object_with_context(context);
-                context.pop_key();
+        let result = parser.parse_document();
 
-                let nested_obj = nested_result?;
+        self.config = parser.config;
+        self.parsing_hooks = parser.parsing_hooks;
+        self.source_name = parser.source_name;
+        self.diagnostics = parser.diagnostics;
+        self.provenance = parser.provenance;
+        self.priority = parser.priority;
+        self.directive_state = parser.directive_state;
+        self.trailing_content_offset = parser.trailing_content_offset;
+        self.pool = parser.pool;
+        *out = result?;
+        self.pool.get_mut().clear();
+        Ok(())
+    }
 
-                // Wrap it in a parent object with the nested_key
-                let mut wrapper = UclObject::new();
-                wrapper.insert(nested_key, nested_obj);
-                Ok(UclValue::Object(wrapper))
+    /// Parses `input` into a value allocated in `bump`
+    ///
+    /// See the [`arena`](crate::arena) module. As with `parse_into`, this
+    /// parser's configuration, variable handler and hooks apply, and its
+    /// diagnostics describe `input` afterwards.
+    #[cfg(feature = "arena")]
+    pub fn parse_in<'bump>(
+        &mut self,
+        input: &str,
+        bump: &'bump Bump,
+    ) -> Result<UclValueArena<'bump>, ParseError> {
+        if self.arena_builder_applies(input) {
+            let lexer = UclLexer::with_config(input, self.lexer.config().clone());
+            if let Some((value, diagnostics)) = ArenaBuilder::new(lexer, &self.config, bump).build()
+            {
+                self.diagnostics = diagnostics;
+                self.provenance = Provenance::new();
+                self.priority = None;
+                return Ok(value);
             }
-            _ => Err(ParseError::UnexpectedToken {
-                token: self
-                    .current_token()
-                    .map(|t| t.type_name())
-                    .unwrap_or("EOF")
-                    .to_string(),
-                position: self.current_position(),
-                expected: "'{' for NGINX-style nested object".to_string(),
-            }),
+        }
+        let mut value = UclValue::Null;
+        self.parse_into(input, &mut value)?;
+        Ok(UclValueArena::from_value(&value, bump))
+    }
+
+    /// Returns whether `ArenaBuilder` may build `input`, leaving nothing this
+    /// parser would do to the document undone
+    #[cfg(feature = "arena")]
+    fn arena_builder_applies(&self, input: &str) -> bool {
+        let config = &self.config;
+        input.len() <= config.max_document_size
+            && self.variable_handler.is_none()
+            && self.include_resolver.is_none()
+            && self.parsing_hooks.is_empty()
+            && config.constants.is_empty()
+            && !config.strict_constants
+            && config.directives.is_none()
+            && !config.enable_anchors
+            && !config.json_only
+            && !config.allow_trailing_content
+            && config.decimal_mode == DecimalMode::Float
+            && !config.track_provenance
+            && config.cancellation.is_none()
+            && LintCode::ALL
+                .into_iter()
+                .all(|code| !config.lints.is_enabled(code))
+    }
+
+    /// Returns whether a top-level key is one of `keys`, in any spelling the alias style allows
+    fn is_wanted_key(&self, keys: &[&str], key: &str) -> bool {
+        match self.config.key_alias_style {
+            Some(style) => keys.iter().any(|wanted| style.same_key(wanted, key)),
+            None => keys.contains(&key),
         }
     }
 
-    /// Parses a complete UCL value
-    pub fn parse_value(&mut self) -> Result<UclValue, ParseError> {
-        if self.current_depth >= self.config.max_depth {
-            return Err(ParseError::MaxDepthExceeded {
-                position: self.current_position(),
-            });
+    /// Parses the root value of a document, keeping only `keys` of an implicit root object
+    fn parse_document_root(&mut self, keys: Option<&[&str]>) -> Result<UclValue, ParseError> {
+        if let Some(error) = self.initial_error.take() {
+            return Err(error);
         }
-
+        self.check_cancelled()?;
         self.skip_whitespace_and_comments()?;
 
-        match self.current_token() {
-            Some(Token::Eof) | None => Err(ParseError::UnexpectedToken {
-                token: "end of file".to_string(),
-                position: self.current_position(),
-                expected: "value".to_string(),
-            }),
-            Some(Token::String {
-                value,
-                needs_expansion,
-                ..
-            }) => {
-                let mut combined = if *needs_expansion {
-                    self.expand_variables(value)?
-                } else {
-                    value.to_string()
-                };
-
-                self.advance_token()?;
-                self.skip_whitespace_and_comments()?;
-
-                combined = self.concatenate_inline_strings(combined, None)?;
-
-                // Apply custom string post-processing
-                let context = VariableContext::new(self.current_position());
-                let processed = self.parsing_hooks.process_string(&combined, &context)?;
-                let ucl_value = UclValue::String(processed);
-
-                // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
+        // A single token followed by the end of input is a scalar document, as
+        // in JSON, unless it is a lone key read as `true`
+        let scalar = match self.current_token() {
+            Some(Token::Eof | Token::ObjectStart | Token::ArrayStart) | None => false,
+            Some(Token::Comma | Token::Semicolon) => false,
+            Some(Token::Key(_)) => !self.config.bare_key_as_true,
+            Some(_) => true,
+        } && self
+            .next_token_matches(|token| matches!(token, Some(Token::Eof) | None))?;
+
+        let root = match self.current_token() {
+            Some(Token::Eof) | None if self.config.json_only => {
+                return Err(ParseError::NotJson {
+                    construct: "an empty document",
+                    position: self.current_position(),
+                });
+            }
+            Some(Token::Eof) | None => {
+                // Empty document
+                return Ok(UclValue::Object(UclObject::new()));
+            }
+            Some(Token::ObjectStart) | Some(Token::ArrayStart) => {
+                // Explicit object or array
+                self.root_ends_at_lex_error = self.config.allow_trailing_content;
+                let root = self.parse_value();
+                self.root_ends_at_lex_error = false;
+                root?
+            }
+            _ if scalar => self.parse_value()?,
+            _ if self.config.json_only => {
+                return Err(ParseError::NotJson {
+                    construct: "an object without braces",
+                    position: self
+                        .current_token_start()
+                        .unwrap_or_else(|| self.current_position()),
+                });
+            }
+            _ => {
+                // Implicit object - parse key-value pairs without braces
+                return self.parse_implicit_object_filtered(keys);
             }
-            Some(Token::Integer(val)) => {
-                let int_val = *val;
-                self.advance_token()?;
-                let ucl_value = UclValue::Integer(int_val);
+        };
 
-                // Apply validation hooks
-                let context = VariableContext::new(self.current_position());
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
+        let rest = self
+            .skip_whitespace_and_comments()
+            .and_then(|()| self.skip_separators(SeparatorContext::Document, false));
+        match rest {
+            Err(error) if self.config.allow_trailing_content => {
+                let position = error.position().unwrap_or_else(|| self.current_position());
+                self.trailing_content_offset = Some(position.offset);
+                return Ok(root);
             }
-            Some(Token::Float(val)) => {
-                let float_val = *val;
-                self.advance_token()?;
-                let ucl_value = UclValue::Float(float_val);
-
-                // Apply validation hooks
-                let context = VariableContext::new(self.current_position());
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
+            rest => rest?,
+        }
+        match self.current_token() {
+            Some(Token::Eof) | None => Ok(root),
+            Some(_) if self.config.allow_trailing_content => {
+                self.stop_at_trailing_content();
+                Ok(root)
             }
-            Some(Token::Time(_)) => {
-                let start = self
+            Some(token) => Err(ParseError::TrailingContent {
+                found: token.type_name(),
+                root: root.type_name(),
+                position: self
                     .current_token_start()
-                    .unwrap_or_else(|| self.current_position());
-                let end = self.current_token_end().unwrap_or(start);
-                let raw = self.token_text_from_positions(start, end);
-                self.advance_token()?;
-                let context = VariableContext::new(self.current_position());
-                let processed = self.parsing_hooks.process_string(&raw, &context)?;
-                let ucl_value = UclValue::String(processed);
+                    .unwrap_or_else(|| self.current_position()),
+            }),
+        }
+    }
 
-                // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
-            }
-            Some(Token::Boolean(val)) => {
-                let bool_val = *val;
-                self.advance_token()?;
-                let ucl_value = UclValue::Boolean(bool_val);
+    /// Records the offset of the current token as where the document ends
+    fn stop_at_trailing_content(&mut self) {
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        self.trailing_content_offset = Some(position.offset);
+    }
 
-                // Apply validation hooks
-                let context = VariableContext::new(self.current_position());
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
+    /// Deep merge two objects (for named section hierarchy)
+    fn deep_merge_objects(mut target: UclObject, source: UclObject) -> UclObject {
+        for (key, mut value) in source {
+            if let Some(existing) = target.get_mut(&key) {
+                // If both are objects, merge them recursively
+                if existing.is_object() && value.is_object() {
+                    if let (UclValue::Object(existing_obj), UclValue::Object(new_obj)) =
+                        (&mut *existing, &mut value)
+                    {
+                        let merged = Self::deep_merge_objects(
+                            std::mem::take(existing_obj),
+                            std::mem::take(new_obj),
+                        );
+                        *existing = UclValue::Object(merged);
+                    }
+                } else {
+                    // Otherwise replace
+                    *existing = value;
+                }
+            } else {
+                // Key doesn't exist, insert it
+                target.insert(key, value);
             }
-            Some(Token::Null) => {
-                self.advance_token()?;
-                let ucl_value = UclValue::Null;
+        }
+        target
+    }
 
-                // Apply validation hooks
-                let context = VariableContext::new(self.current_position());
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, &context)?;
-                Ok(validated_value)
+    /// Adds the named section `labels` to the object of an earlier section with the same key
+    ///
+    /// `section` holds one nested object per label, as built for `server a { }`.
+    /// Sections with distinct labels share their outer levels; a section whose
+    /// labels were all used before returns the labels naming it.
+    fn merge_named_section(
+        target: &mut UclObject,
+        labels: &[UclKey],
+        section: &UclValue,
+    ) -> Result<(), Vec<String>> {
+        let Some((label, rest)) = labels.split_first() else {
+            return Err(Vec::new());
+        };
+        let UclValue::Object(section) = section else {
+            return Err(Vec::new());
+        };
+        let Some(value) = section.get(label) else {
+            return Err(Vec::new());
+        };
+        match target.get_mut(label) {
+            None => {
+                target.insert(label.clone(), value.clone());
+                Ok(())
             }
-            Some(Token::ObjectStart) => self.parse_object(),
-            Some(Token::ArrayStart) => self.parse_array(),
-            Some(Token::Key(_)) => {
-                // Handle bare word values (unquoted identifiers)
-                self.parse_bare_word_value()
+            Some(UclValue::Object(existing)) if !rest.is_empty() => {
+                Self::merge_named_section(existing, rest, value).map_err(|mut path| {
+                    path.insert(0, label.to_string());
+                    path
+                })
             }
-            Some(token) => Err(ParseError::UnexpectedToken {
-                token: token.type_name().to_string(),
-                position: self.current_position(),
-                expected: "value".to_string(),
-            }),
+            Some(_) => Err(vec![label.to_string()]),
         }
     }
 
-    /// Parses a UCL object with variable expansion context
-    pub fn parse_object(&mut self) -> Result<UclValue, ParseError> {
-        self.parse_object_with_context(&mut VariableContext::new(self.current_position()))
+    /// Returns true if the current token is a bare key to expand as a dotted path
+    fn at_dotted_key(&self) -> bool {
+        self.config.expand_dotted_keys
+            && matches!(self.current_token(), Some(Token::Key(key))
+                if key.contains('.') && key.split('.').all(|segment| !segment.is_empty()))
     }
 
-    /// Parses a UCL object with explicit variable context
-    pub fn parse_object_with_context(
-        &mut self,
-        context: &mut VariableContext,
-    ) -> Result<UclValue, ParseError> {
-        self.current_depth += 1;
-        if self.current_depth > self.config.max_depth {
-            return Err(ParseError::MaxDepthExceeded {
-                position: self.current_position(),
-            });
+    /// Splits an expanded dotted key written at `key_span` into its segments
+    ///
+    /// Each segment goes through the key transforms with a span of its own and
+    /// the segments before it as part of its path.
+    fn split_dotted_key(
+        &self,
+        key: &str,
+        key_span: &Span,
+        context: &VariableContext,
+    ) -> Result<KeyPath, ParseError> {
+        if self.parsing_hooks.key_transforms.is_empty() {
+            return Ok(key.split('.').map(UclKey::from).collect());
         }
+        let mut segments = KeyPath::new();
+        let mut start = key_span.start;
+        for segment in key.split('.') {
+            let span = Span::token(start, segment.len());
+            start = span.end;
+            start.offset += 1;
+            start.column += 1;
+            let segment = self.transform_key(segment.into(), &segments, span, context)?;
+            segments.push(segment);
+        }
+        Ok(segments)
+    }
 
-        // Consume the opening brace
-        self.expect_token(&Token::ObjectStart)?;
+    /// Returns the position of the dotted key that created the object level at `path`
+    fn dotted_key_position(&self, mut path: Vec<String>, nested: &[String]) -> Option<Position> {
+        if self.dotted_keys.is_empty() {
+            return None;
+        }
+        path.extend_from_slice(nested);
+        self.dotted_keys.get(&path).copied()
+    }
 
-        let mut object = UclObject::new();
+    /// Inserts the value of a dotted key such as `a.b.c` into `object`
+    ///
+    /// Levels that already hold an object are merged into. The last key, or
+    /// the first level holding something else, is a duplicate key handled as
+    /// configured. Each level the key creates records the key's span, so later
+    /// duplicates, provenance and source maps point at the dotted key.
+    fn insert_dotted_entry(
+        &mut self,
+        object: &mut UclObject,
+        context: &VariableContext,
+        path: &[UclKey],
+        (assignment, value): (Assignment, UclValue),
+        key_span: Span,
+        first_occurrence: Position,
+    ) -> Result<(), ParseError> {
+        let key_position = key_span.start;
+        let mut full_path = context.path_to(&path[0]);
+        let mut target = object;
+        let mut level = 0;
+        while level + 1 < path.len()
+            && matches!(target.get(&path[level]), Some(UclValue::Object(_)))
+        {
+            let Some(UclValue::Object(next)) = target.get_mut(&path[level]) else {
+                unreachable!("checked to be an object");
+            };
+            target = next;
+            level += 1;
+            full_path.push(path[level].to_string());
+        }
 
-        // Handle empty object
-        self.skip_whitespace_and_comments()?;
-        if let Some(Token::ObjectEnd) = self.current_token() {
-            self.advance_token()?;
-            self.current_depth -= 1;
-            return Ok(UclValue::Object(object));
+        let key = &path[level];
+        let mut nested = value;
+        for segment in path[level + 1..].iter().rev() {
+            let mut inner = UclObject::new();
+            inner.insert(segment.clone(), nested);
+            nested = UclValue::Object(inner);
         }
 
-        loop {
-            self.skip_whitespace_and_comments()?;
+        let Some(existing) = target.get_mut(key) else {
+            target.insert(key.clone(), nested);
+            self.record_dotted_levels(full_path, &path[level + 1..], &key_span);
+            return Ok(());
+        };
 
-            // Parse key - support various key formats
-            let key = match self.current_token() {
-                Some(Token::Key(k)) => {
-                    let key_str = k.to_string();
-                    self.advance_token()?;
-                    // Apply key validation
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                Some(Token::String {
-                    value,
-                    needs_expansion,
-                    ..
-                }) => {
-                    let key_str = if *needs_expansion {
-                        self.expand_variables_with_context_safe(value, context)?
-                    } else {
-                        value.to_string()
-                    };
-                    self.advance_token()?;
-                    // Apply key validation
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                // Support bare identifiers as keys (common in UCL)
-                Some(Token::Boolean(true)) => {
-                    self.advance_token()?;
-                    let key_str = "true".to_string();
-                    // Apply key validation
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                Some(Token::Boolean(false)) => {
-                    self.advance_token()?;
-                    let key_str = "false".to_string();
-                    // Apply key validation
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                Some(Token::Integer(val)) => {
-                    let key_str = val.to_string();
-                    self.advance_token()?;
-                    // Apply key validation
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                Some(Token::Float(_)) => {
-                    let start = self
-                        .current_token_start()
-                        .unwrap_or_else(|| self.current_position());
-                    let end = self.current_token_end().unwrap_or(start);
-                    let key_str = self.token_text_from_positions(start, end);
-                    self.advance_token()?;
-                    self.parsing_hooks.validate_key(&key_str, context)?
-                }
-                Some(Token::ObjectEnd) => {
-                    // End of object
-                    break;
-                }
-                Some(token) => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: token.type_name().to_string(),
-                        position: self.current_position(),
-                        expected: "key or '}'".to_string(),
-                    });
-                }
-                None => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: "end of file".to_string(),
-                        position: self.current_position(),
-                        expected: "key or '}'".to_string(),
-                    });
+        self.counters.duplicate_keys += 1;
+        let first_occurrence = self
+            .dotted_key_position(full_path.clone(), &[])
+            .unwrap_or(first_occurrence);
+        if assignment == Assignment::Append && level + 1 == path.len() {
+            self.append_value(existing, nested, key, key_position)?;
+            self.record_dotted_levels(full_path, &[], &key_span);
+            return Ok(());
+        }
+        match self.config.duplicate_key_behavior {
+            DuplicateKeyBehavior::Error => {
+                self.tolerate(ParseError::DuplicateKey {
+                    key: key.to_string(),
+                    first_occurrence,
+                    second_occurrence: key_position,
+                })?;
+            }
+            DuplicateKeyBehavior::ImplicitArray => match existing {
+                UclValue::Array(arr) => arr.push(nested),
+                other => {
+                    let mut arr = SmallVec::new();
+                    arr.push(std::mem::take(other));
+                    arr.push(nested);
+                    *other = UclValue::Array(Box::new(arr));
                 }
-            };
+            },
+            DuplicateKeyBehavior::Override => {
+                self.report_duplicate_key(key, first_occurrence, key_position, "overrides");
+                *existing = nested;
+                self.record_dotted_levels(full_path, &path[level + 1..], &key_span);
+            }
+            DuplicateKeyBehavior::KeepFirst => {
+                self.report_duplicate_key(
+                    key,
+                    first_occurrence,
+                    key_position,
+                    "is ignored in favor of",
+                );
+            }
+        }
+        Ok(())
+    }
 
-            self.skip_whitespace_and_comments()?;
+    /// Records the span of a dotted key for the level at `path` and the levels below it
+    fn record_dotted_levels(&mut self, mut path: Vec<String>, below: &[UclKey], key_span: &Span) {
+        let mut below = below.iter();
+        loop {
+            self.dotted_keys.insert(path.clone(), key_span.start);
+            if self.config.track_provenance {
+                self.provenance.record(
+                    path.clone(),
+                    Origin::new(self.source_name.clone(), key_span.clone()),
+                );
+            }
+            match below.next() {
+                Some(segment) => path.push(segment.to_string()),
+                None => break,
+            }
+        }
+    }
 
-            // Detect syntax style for this key-value pair
-            let syntax_style = self.detect_syntax_style()?;
+    /// Returns the labels of a section such as `server a { }` or `section foo bar { }`
+    ///
+    /// `key_labels` are the keys read after `section`; a labelled value adds its own key.
+    fn section_labels(value: &UclValue, labelled: bool, key_labels: &[UclKey]) -> Vec<UclKey> {
+        match value {
+            UclValue::Object(section) if labelled => section.keys().cloned().collect(),
+            _ => key_labels.to_vec(),
+        }
+    }
 
-            // Parse value based on detected syntax style
-            let value = match syntax_style {
-                SyntaxStyle::Explicit => {
-                    // Expect separator (: or =) and parse value normally
-                    match self.current_token() {
-                        Some(Token::Colon) | Some(Token::Equals) => {
-                            self.advance_token()?;
-                            self.skip_whitespace_and_comments()?;
-                            self.parse_value_with_context(context, true)?
-                        }
-                        Some(token) => {
-                            return Err(ParseError::UnexpectedToken {
-                                token: token.type_name().to_string(),
-                                position: self.current_position(),
-                                expected: "':' or '='".to_string(),
-                            });
-                        }
-                        None => {
-                            return Err(ParseError::UnexpectedToken {
-                                token: "end of file".to_string(),
-                                position: self.current_position(),
-                                expected: "':' or '='".to_string(),
-                            });
-                        }
+    /// Records the position of a named section for the levels its labels create
+    fn record_section_levels(
+        &mut self,
+        context: &VariableContext,
+        key: &str,
+        labels: &[UclKey],
+        position: Position,
+    ) {
+        let mut path = context.path_to(key);
+        for label in labels {
+            path.push(label.to_string());
+            self.section_labels.entry(path.clone()).or_insert(position);
+        }
+    }
+
+    /// Adds an entry repeating `key` under `DuplicateKeyBehavior::Error`
+    ///
+    /// An object created by dotted keys is extended by a later definition, and
+    /// a named section whose labels differ from the earlier ones is merged in.
+    /// Any other repeat is a duplicate of `key`, or of the nested key or label
+    /// defined twice, reported with both of its positions.
+    fn add_repeated_section(
+        &mut self,
+        existing: &mut UclValue,
+        section: &UclValue,
+        (key, labels): (&str, &[UclKey]),
+        context: &VariableContext,
+        (first_occurrence, second_occurrence): (Position, Position),
+    ) -> Result<(), ParseError> {
+        let path = context.path_to(key);
+        let duplicate = match (existing, section) {
+            (UclValue::Object(existing), UclValue::Object(section))
+                if self.dotted_keys.contains_key(&path) =>
+            {
+                self.merge_into_dotted(path.clone(), existing, section)
+                    .err()
+            }
+            (UclValue::Object(existing), _) if !labels.is_empty() => {
+                Self::merge_named_section(existing, labels, section).err()
+            }
+            _ => Some(Vec::new()),
+        };
+        let Some(duplicate) = duplicate else {
+            self.record_section_levels(context, key, labels, second_occurrence);
+            return Ok(());
+        };
+        let mut duplicate_path = path.clone();
+        duplicate_path.extend_from_slice(&duplicate);
+        let first_occurrence = self
+            .dotted_key_position(path, &duplicate)
+            .or_else(|| self.section_labels.get(&duplicate_path).copied())
+            .unwrap_or(first_occurrence);
+        self.tolerate(ParseError::DuplicateKey {
+            key: duplicate
+                .last()
+                .map_or_else(|| key.to_string(), Clone::clone),
+            first_occurrence,
+            second_occurrence,
+        })
+    }
+
+    /// Merges `source` into the object a dotted key created at `path`
+    ///
+    /// Only levels created by dotted keys are merged into; returns the path,
+    /// relative to `path`, of the first key both define.
+    fn merge_into_dotted(
+        &self,
+        path: Vec<String>,
+        target: &mut UclObject,
+        source: &UclObject,
+    ) -> Result<(), Vec<String>> {
+        for (key, value) in source.iter() {
+            match (target.get_mut(key), value) {
+                (None, value) => {
+                    target.insert(key.clone(), value.clone());
+                }
+                (Some(UclValue::Object(existing)), UclValue::Object(nested)) => {
+                    let mut nested_path = path.clone();
+                    nested_path.push(key.to_string());
+                    if !self.dotted_keys.contains_key(&nested_path) {
+                        return Err(vec![key.to_string()]);
                     }
+                    self.merge_into_dotted(nested_path, existing, nested)
+                        .map_err(|mut duplicate| {
+                            duplicate.insert(0, key.to_string());
+                            duplicate
+                        })?
                 }
-                SyntaxStyle::Implicit => {
-                    // Direct value or object without separator
-                    match self.current_token() {
-                        Some(Token::ObjectStart) => self.parse_object_with_context(context)?,
-                        _ => {
-                            // Bare word value
-                            self.parse_bare_word_value_with_context(context, false)?
+                (Some(_), _) => return Err(vec![key.to_string()]),
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the entries of the `*name` references that open an object
+    ///
+    /// In `{ *defaults, port = 81 }` the object starts with the entries of the
+    /// `defaults` anchor. Later references replace values of earlier ones.
+    /// Returns a flag per entry marking it as merged.
+    fn parse_merge_references(
+        &mut self,
+        object: &mut UclObject,
+        key_positions: &mut KeyPositions,
+    ) -> Result<Vec<bool>, ParseError> {
+        while let Some(Token::Reference(name)) = self.current_token() {
+            let name = name.to_string();
+            let position = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+            match &mut self.referenced_value(&name)? {
+                UclValue::Object(entries) => {
+                    for (key, value) in std::mem::take(entries) {
+                        if object.insert(key, value).is_none() {
+                            key_positions.push(position);
                         }
                     }
                 }
-                SyntaxStyle::NginxNested => {
-                    // key identifier { ... } pattern
-                    self.parse_nginx_nested_object(context)?
+                other => {
+                    return Err(ParseError::InvalidObject {
+                        message: format!(
+                            "Cannot merge the {} value of anchor '{}' into an object",
+                            other.type_name(),
+                            name
+                        ),
+                        position,
+                    });
                 }
-            };
+            }
+            self.advance_token()?;
+            self.skip_whitespace_and_comments()?;
+            if matches!(self.current_token(), Some(Token::Comma | Token::Semicolon)) {
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
+            }
+        }
+        Ok(vec![true; object.len()])
+    }
 
-            if syntax_style == SyntaxStyle::Implicit
-                && matches!(self.current_token(), Some(Token::ObjectEnd))
-                && !self.lexer.last_token_had_newline()
-            {
+    /// Parses an object key, returning `None` at the closing brace
+    fn parse_object_key(
+        &mut self,
+        context: &VariableContext,
+    ) -> Result<Option<UclKey>, ParseError> {
+        if self.config.json_only
+            && let Some(Token::Boolean(_) | Token::Null | Token::Integer(_) | Token::Float(_)) =
+                self.current_token()
+        {
+            return Err(ParseError::NotJson {
+                construct: "a key that is not a string",
+                position: self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position()),
+            });
+        }
+        let start = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let key = match self.current_token() {
+            Some(Token::Key(k)) => {
+                let key_str = UclKey::from(k.as_ref());
+                self.advance_token()?;
+                // Apply key validation
+                self.finish_key(key_str, start, context)?
+            }
+            Some(Token::String {
+                value,
+                needs_expansion,
+                ..
+            }) => {
+                let key_str = if *needs_expansion {
+                    UclKey::from(self.expand_variables_with_context_safe(value, context)?)
+                } else {
+                    UclKey::from(value.as_ref())
+                };
+                self.check_key_length(&key_str)?;
+                self.advance_token()?;
+                // Apply key validation
+                self.finish_key(key_str, start, context)?
+            }
+            // Support bare identifiers as keys (common in UCL)
+            Some(Token::Boolean(true)) => {
+                self.advance_token()?;
+                let key_str = UclKey::from("true");
+                // Apply key validation
+                self.finish_key(key_str, start, context)?
+            }
+            Some(Token::Boolean(false)) => {
+                self.advance_token()?;
+                let key_str = UclKey::from("false");
+                // Apply key validation
+                self.finish_key(key_str, start, context)?
+            }
+            Some(Token::Null) => {
+                self.advance_token()?;
+                let key_str = UclKey::from("null");
+                // Apply key validation
+                self.finish_key(key_str, start, context)?
+            }
+            Some(Token::Integer(val)) => {
+                let key_str = val.to_string();
+                self.advance_token()?;
+                // Apply key validation
+                self.finish_key(key_str.into(), start, context)?
+            }
+            Some(Token::Float(_)) => {
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let end = self.current_token_end().unwrap_or(start);
+                let key_str = self.token_text_from_positions(start, end);
+                self.advance_token()?;
+                self.finish_key(key_str.into(), start, context)?
+            }
+            Some(Token::ObjectEnd) => {
+                // End of object
+                return Ok(None);
+            }
+            Some(Token::Reference(name)) => {
                 return Err(ParseError::InvalidObject {
                     message: format!(
-                        "Implicit value for '{}' must be terminated by a newline or ';'.",
-                        key
+                        "Reference '*{}' must come before the first key to merge it into the object",
+                        name
                     ),
                     position: self
                         .current_token_start()
                         .unwrap_or_else(|| self.current_position()),
                 });
             }
+            _ => {
+                return Err(self.unexpected_token(&["key", "'}'"]));
+            }
+        };
+        Ok(Some(key))
+    }
 
-            // Handle duplicate keys based on configuration
-            if let Some(existing_value) = object.get_mut(&key) {
-                if let UclValue::Object(existing_map) = existing_value
-                    && let UclValue::Object(ref new_map) = value
-                {
-                    for (nested_key, nested_value) in new_map.iter() {
-                        existing_map.insert(nested_key.clone(), nested_value.clone());
+    /// Parses the separator and value that follow an entry key
+    ///
+    /// The explicit `=` or `:` of a replacing assignment is returned with its position,
+    /// and whether the value is a labelled section such as the `a { }` of `server a { }`.
+    fn parse_entry_value(
+        &mut self,
+        context: &mut VariableContext,
+    ) -> Result<(Assignment, Option<Separator>, UclValue, bool), ParseError> {
+        self.skip_whitespace_and_comments()?;
+        if self.config.json_only && !self.is_current_token(&Token::Colon) {
+            return Err(self.unexpected_token(&["':'"]));
+        }
+
+        // `+=` appends to the existing value instead of replacing it
+        let assignment = if self.at_append_operator() {
+            self.advance_token()?;
+            Assignment::Append
+        } else {
+            Assignment::Replace
+        };
+
+        if self.config.bare_key_as_true
+            && assignment == Assignment::Replace
+            && self.at_key_without_value()
+        {
+            return Ok((assignment, None, UclValue::Boolean(true), false));
+        }
+
+        // Detect syntax style for this key-value pair
+        let syntax_style = self.detect_syntax_style()?;
+        let mut separator = None;
+        let mut format = None;
+        let mut source = None;
+
+        // Parse value based on detected syntax style
+        let value = match syntax_style {
+            SyntaxStyle::Explicit => {
+                // Expect separator (: or =) and parse value normally
+                match self.current_token() {
+                    Some(token @ (Token::Colon | Token::Equals)) => {
+                        if assignment == Assignment::Replace {
+                            let separator_char = if *token == Token::Colon { ':' } else { '=' };
+                            separator = self
+                                .current_token_start()
+                                .map(|position| (separator_char, position));
+                        }
+                        self.advance_token()?;
+                        self.skip_whitespace_and_comments()?;
+                        format = self.value_string_format();
+                        source = self.value_source();
+                        self.parse_value_with_context(context, true)?
+                    }
+                    _ => {
+                        return Err(self.unexpected_token(&["'='", "':'"]));
                     }
-                    continue;
                 }
-
-                match self.config.duplicate_key_behavior {
-                    DuplicateKeyBehavior::Error => {
-                        return Err(ParseError::DuplicateKey {
-                            key,
-                            position: self.current_position(),
+            }
+            SyntaxStyle::Implicit => {
+                // Direct value or object without separator
+                match self.current_token() {
+                    Some(Token::ObjectStart) => self.parse_object_with_context(context)?,
+                    Some(token) if !self.config.relaxed_assignment => {
+                        return Err(ParseError::UnexpectedToken {
+                            found: token.type_name(),
+                            expected: vec!["'='", "':'", "'{'"],
+                            position: self
+                                .current_token_start()
+                                .unwrap_or_else(|| self.current_position()),
                         });
                     }
-                    DuplicateKeyBehavior::ImplicitArray => {
-                        let existing_value = object.shift_remove(&key).unwrap();
-                        let new_array = match existing_value {
-                            UclValue::Array(mut arr) => {
-                                arr.push(value);
-                                UclValue::Array(arr)
-                            }
-                            other => {
-                                let mut arr = SmallVec::new();
-                                arr.push(other);
-                                arr.push(value);
-                                UclValue::Array(Box::new(arr))
-                            }
-                        };
-                        object.insert(key, new_array);
+                    Some(Token::ArrayStart) => self.parse_array_with_context(context)?,
+                    Some(
+                        Token::ObjectEnd
+                        | Token::ArrayEnd
+                        | Token::Comma
+                        | Token::Semicolon
+                        | Token::Eof,
+                    )
+                    | None => {
+                        return Err(self.unexpected_token(&["'='", "':'", "'{'", "value"]));
                     }
-                    DuplicateKeyBehavior::Override => {
-                        object.insert(key, value);
+                    // A relaxed value starts on the line of its key
+                    Some(_) if self.at_line_start() => {
+                        return Err(ParseError::UnexpectedToken {
+                            found: "end of line",
+                            expected: vec!["'='", "':'", "'{'", "value"],
+                            position: self
+                                .previous_token_end
+                                .unwrap_or_else(|| self.current_position()),
+                        });
+                    }
+                    Some(Token::Anchor(_) | Token::Reference(_)) => {
+                        self.parse_value_with_context(context, false)?
+                    }
+                    _ => {
+                        // Bare word value
+                        format = self.value_string_format();
+                        source = self.value_source();
+                        self.parse_bare_word_value_with_context(context, false)?
                     }
                 }
-            } else {
-                object.insert(key, value);
             }
+            SyntaxStyle::NginxNested => {
+                // key identifier { ... } pattern
+                self.parse_nginx_nested_object(context)?
+            }
+        };
+        self.record_string_format(context, format, &value);
+        self.record_string_source(source, &value);
 
-            self.skip_whitespace_and_comments()?;
+        let labelled = syntax_style == SyntaxStyle::NginxNested;
+        Ok((assignment, separator, value, labelled))
+    }
 
-            // Check for separator or end (separators are optional in implicit syntax)
-            match self.current_token() {
-                Some(Token::Comma) | Some(Token::Semicolon) => {
-                    self.advance_token()?;
-                    // Continue to next key-value pair
-                }
-                Some(Token::ObjectEnd) => {
-                    // End of object
-                    break;
-                }
-                Some(Token::Key(_))
-                | Some(Token::String { .. })
-                | Some(Token::Boolean(_))
-                | Some(Token::Integer(_)) => {
-                    // Next key without separator (implicit syntax)
-                    // Continue to next key-value pair
-                }
-                Some(token) => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: token.type_name().to_string(),
-                        position: self.current_position(),
-                        expected: "',', ';', key, or '}'".to_string(),
-                    });
-                }
-                None => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: "end of file".to_string(),
-                        position: self.current_position(),
-                        expected: "',', ';', key, or '}'".to_string(),
+    /// Parses the value after `&name` and registers a copy of it under `name`
+    ///
+    /// Anchor names are unique within a document: defining one again is an
+    /// error whether or not it was referenced, so every `*name` stands for
+    /// the same value.
+    fn parse_anchored_value(
+        &mut self,
+        name: String,
+        context: &mut VariableContext,
+        explicit_separator: bool,
+    ) -> Result<UclValue, ParseError> {
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        let duplicate = self.anchors.defined.get(&name).map(|(_, first, _)| *first);
+        if let Some(first_definition) = duplicate {
+            self.tolerate(ParseError::DuplicateAnchor {
+                name: name.clone(),
+                first_definition,
+                position,
+            })?;
+        }
+        self.advance_token()?;
+
+        self.anchors.open.push(name);
+        let value = self.parse_value_with_context(context, explicit_separator);
+        let name = self.anchors.open.pop().unwrap_or_default();
+        let value = value?;
+        if duplicate.is_none() {
+            let nodes = count_values(&value);
+            self.anchors
+                .defined
+                .insert(name, (value.clone(), position, nodes));
+        }
+        Ok(value)
+    }
+
+    /// Returns a copy of the value registered under the anchor `name`
+    ///
+    /// The copy is counted against `ParserConfig::max_alias_expansion` before it is made.
+    fn referenced_value(&mut self, name: &str) -> Result<UclValue, ParseError> {
+        let position = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
+        if self.anchors.open.iter().any(|open| open == name) {
+            return Err(ParseError::RecursiveAnchor {
+                name: name.to_string(),
+                position,
+            });
+        }
+        match self.anchors.defined.get(name) {
+            Some((value, _, nodes)) => {
+                self.anchors.copied = self.anchors.copied.saturating_add(*nodes);
+                if self.anchors.copied > self.config.max_alias_expansion {
+                    return Err(ParseError::AliasExpansionTooLarge {
+                        limit: self.config.max_alias_expansion,
+                        position,
                     });
                 }
+                Ok(value.clone())
             }
+            None => Err(ParseError::UndefinedAnchor {
+                name: name.to_string(),
+                position,
+            }),
         }
-
-        // Consume the closing brace
-        self.expect_token(&Token::ObjectEnd)?;
-        self.current_depth -= 1;
-
-        Ok(UclValue::Object(object))
     }
 
-    /// Parses a UCL array
-    pub fn parse_array(&mut self) -> Result<UclValue, ParseError> {
-        self.parse_array_with_context(&mut VariableContext::new(self.current_position()))
+    /// Returns true if the key just read has no value, as in `enabled;`
+    ///
+    /// A value on the same line makes the entry an assignment. A token on a
+    /// later line starts the next entry, except for `{`, `=` and `:`, which
+    /// still belong to the key.
+    fn at_key_without_value(&self) -> bool {
+        match self.current_token() {
+            Some(Token::Semicolon | Token::Comma | Token::ObjectEnd | Token::Eof) | None => true,
+            Some(Token::ObjectStart | Token::Colon | Token::Equals | Token::ArrayEnd) => false,
+            Some(_) => self.lexer.last_token_had_newline(),
+        }
     }
 
-    /// Parses a top-level UCL document (may be an implicit object)
-    pub fn parse_document(&mut self) -> Result<UclValue, ParseError> {
-        self.skip_whitespace_and_comments()?;
+    /// Returns true if the current token is the `+` of a `+=` operator
+    fn at_append_operator(&self) -> bool {
+        matches!(self.current_token(), Some(Token::Plus))
+            && self
+                .current_token_end()
+                .is_some_and(|end| self.lexer.source_byte(end.offset) == Some(b'='))
+    }
 
-        match self.current_token() {
-            Some(Token::Eof) | None => {
-                // Empty document
-                Ok(UclValue::Object(UclObject::new()))
+    /// Appends a `+=` value to the existing value of a key
+    ///
+    /// Arrays gain the new elements and objects are deep-merged. Strings are
+    /// concatenated only when `ParserConfig::append_concatenates_strings` is set.
+    fn append_value(
+        &self,
+        existing: &mut UclValue,
+        mut value: UclValue,
+        key: &str,
+        position: Position,
+    ) -> Result<(), ParseError> {
+        match (existing, &mut value) {
+            (UclValue::Array(items), UclValue::Array(new_items)) => {
+                items.extend(std::mem::take(&mut **new_items))
             }
-            Some(Token::ObjectStart) | Some(Token::ArrayStart) => {
-                // Explicit object or array
-                self.parse_value()
+            (UclValue::Array(items), _) => items.push(value),
+            (existing @ UclValue::Object(_), UclValue::Object(_)) => existing.merge_from(value),
+            (UclValue::String(existing), UclValue::String(value))
+                if self.config.append_concatenates_strings =>
+            {
+                existing.push_str(value)
             }
-            _ => {
-                // Implicit object - parse key-value pairs without braces
-                self.parse_implicit_object()
+            (existing, value) => {
+                return Err(ParseError::InvalidObject {
+                    message: format!(
+                        "Cannot append {} to {} value of '{}' with '+='",
+                        value.type_name(),
+                        existing.type_name(),
+                        key
+                    ),
+                    position,
+                });
             }
         }
+        Ok(())
     }
 
-    /// Deep merge two objects (for named section hierarchy)
-    fn deep_merge_objects(mut target: UclObject, source: UclObject) -> UclObject {
-        for (key, value) in source {
-            if let Some(existing) = target.get_mut(&key) {
-                // If both are objects, merge them recursively
-                if existing.is_object() && value.is_object() {
-                    if let (UclValue::Object(existing_obj), UclValue::Object(new_obj)) =
-                        (existing.clone(), value)
-                    {
-                        let merged = Self::deep_merge_objects(existing_obj, new_obj);
-                        *existing = UclValue::Object(merged);
-                    }
-                } else {
-                    // Otherwise replace
-                    *existing = value;
+    /// Applies `LexerConfig::key_charset`, key transforms and key validation hooks to a key at `position`
+    fn finish_key(
+        &self,
+        key: UclKey,
+        position: Position,
+        context: &VariableContext,
+    ) -> Result<UclKey, ParseError> {
+        let key = match self.lexer.config().key_charset {
+            KeyCharset::Unicode => key,
+            KeyCharset::Ascii => {
+                if let Some(character) = key.chars().find(|c| !c.is_ascii()) {
+                    return Err(ParseError::NonAsciiKey {
+                        key: key.into(),
+                        character,
+                        position,
+                    });
                 }
-            } else {
-                // Key doesn't exist, insert it
-                target.insert(key, value);
+                key
             }
+            KeyCharset::UnicodeNfc => normalize_nfc(&key).map_or(key, UclKey::from),
+        };
+        // Dotted keys are transformed segment by segment once split
+        let key = if self.reading_dotted_key {
+            key
+        } else {
+            let end = match self.current_token_start() {
+                Some(start) if start == position => self.current_token_end(),
+                _ => self.previous_token_end,
+            };
+            let span = Span::new(position, end.unwrap_or(position));
+            self.transform_key(key, &[], span, context)?
+        };
+        self.parsing_hooks.validate_owned_key(key, context)
+    }
+
+    /// Applies the key transforms to a key inside the objects at the context's path and `nested`
+    fn transform_key(
+        &self,
+        key: UclKey,
+        nested: &[UclKey],
+        span: Span,
+        context: &VariableContext,
+    ) -> Result<UclKey, ParseError> {
+        if self.parsing_hooks.key_transforms.is_empty() {
+            return Ok(key);
         }
-        target
+        let path: SmallVec<[&str; 8]> = context
+            .current_object_path
+            .iter()
+            .chain(nested)
+            .map(|key| key.as_str())
+            .collect();
+        self.parsing_hooks.transform_owned_key(key, &path, span)
+    }
+
+    /// Rejects a quoted key longer than `LexerConfig::max_key_length`
+    fn check_key_length(&self, key: &str) -> Result<(), ParseError> {
+        let limit = self.lexer.config().max_key_length;
+        if key.len() > limit {
+            return Err(LexError::KeyTooLong {
+                length: key.len(),
+                limit,
+                position: self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position()),
+            }
+            .into());
+        }
+        Ok(())
     }
 
     /// Parses a key path for named sections (e.g., "section foo bar" -> ["section", "foo", "bar"])
     /// Per SPEC.md lines 154-194
-    fn parse_key_path(&mut self, context: &VariableContext) -> Result<Vec<String>, ParseError> {
-        let mut keys = Vec::new();
+    fn parse_key_path(&mut self, context: &VariableContext) -> Result<KeyPath, ParseError> {
+        let mut keys = KeyPath::new();
 
         // Parse first key
+        let start = self
+            .current_token_start()
+            .unwrap_or_else(|| self.current_position());
         let first_key = match self.current_token() {
             Some(Token::Key(k)) => {
-                let key_str = k.to_string();
+                let key_str = UclKey::from(k.as_ref());
                 self.advance_token()?;
-                self.parsing_hooks.validate_key(&key_str, context)?
+                self.finish_key(key_str, start, context)?
             }
             Some(Token::String {
                 value,
@@ -2618,41 +8414,45 @@ impl<'a> UclParser<'a> {
                 ..
             }) => {
                 let key_str = if *needs_expansion {
-                    self.expand_variables_with_context_safe(value, context)?
+                    UclKey::from(self.expand_variables_with_context_safe(value, context)?)
                 } else {
-                    value.to_string()
+                    UclKey::from(value.as_ref())
                 };
+                self.check_key_length(&key_str)?;
                 self.advance_token()?;
-                self.parsing_hooks.validate_key(&key_str, context)?
+                self.finish_key(key_str, start, context)?
             }
-            Some(token) => {
-                return Err(ParseError::UnexpectedToken {
-                    token: token.type_name().to_string(),
-                    position: self.current_position(),
-                    expected: "key".to_string(),
-                });
+            // Keywords and numbers such as `true`, `inf` or `404` are keys as written
+            Some(Token::Boolean(_) | Token::Null | Token::Integer(_) | Token::Float(_)) => {
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
+                let end = self.current_token_end().unwrap_or(start);
+                let key_str = self.token_text_from_positions(start, end);
+                self.advance_token()?;
+                self.finish_key(key_str.into(), start, context)?
             }
-            None => {
-                return Err(ParseError::UnexpectedToken {
-                    token: "end of file".to_string(),
-                    position: self.current_position(),
-                    expected: "key".to_string(),
-                });
+            _ => {
+                return Err(self.unexpected_token(&["key"]));
             }
         };
-        keys.push(first_key.clone());
+        let is_section = first_key == "section";
+        keys.push(first_key);
 
         // Only parse additional keys if first key is "section" (for named sections like "section foo bar {}")
         // This avoids breaking implicit syntax like "worker_processes auto"
-        if first_key == "section" {
+        if is_section {
             loop {
                 self.skip_whitespace_and_comments()?;
 
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
                 match self.current_token() {
                     Some(Token::Key(k)) => {
-                        let key_str = k.to_string();
+                        let key_str = UclKey::from(k.as_ref());
                         self.advance_token()?;
-                        let validated_key = self.parsing_hooks.validate_key(&key_str, context)?;
+                        let validated_key = self.finish_key(key_str, start, context)?;
                         keys.push(validated_key);
                     }
                     Some(Token::String {
@@ -2661,12 +8461,13 @@ impl<'a> UclParser<'a> {
                         ..
                     }) => {
                         let key_str = if *needs_expansion {
-                            self.expand_variables_with_context_safe(value, context)?
+                            UclKey::from(self.expand_variables_with_context_safe(value, context)?)
                         } else {
-                            value.to_string()
+                            UclKey::from(value.as_ref())
                         };
+                        self.check_key_length(&key_str)?;
                         self.advance_token()?;
-                        let validated_key = self.parsing_hooks.validate_key(&key_str, context)?;
+                        let validated_key = self.finish_key(key_str, start, context)?;
                         keys.push(validated_key);
                     }
                     // Stop if we hit a separator or object start
@@ -2685,79 +8486,154 @@ impl<'a> UclParser<'a> {
 
     /// Parses an implicit object (key-value pairs without braces)
     pub fn parse_implicit_object(&mut self) -> Result<UclValue, ParseError> {
-        let mut object = UclObject::new();
+        self.parse_implicit_object_filtered(None)
+    }
+
+    /// Parses an implicit object, skipping the values of entries whose keys are not in `keys`
+    fn parse_implicit_object_filtered(
+        &mut self,
+        keys: Option<&[&str]>,
+    ) -> Result<UclValue, ParseError> {
+        let mut object = self.pool.borrow_mut().object();
         let mut context = VariableContext::new(self.current_position());
+        let mut key_positions = KeyPositions::new();
+        let mut first_separator = None;
+        let depth = self.current_depth;
+        let mut after_entry = false;
 
         while !matches!(self.current_token(), Some(Token::Eof) | None) {
             self.skip_whitespace_and_comments()?;
+            let same_line =
+                after_entry && self.current_separator().is_none() && !self.at_line_start();
+            if let Err(error) = self.skip_separators(SeparatorContext::Document, !after_entry) {
+                self.recover_entry(error, depth, false, true)?;
+                continue;
+            }
+            after_entry = true;
 
             if matches!(self.current_token(), Some(Token::Eof) | None) {
                 break;
             }
 
-            // Parse key path (supports named sections like "section foo bar {}")
-            let key_path = self.parse_key_path(&context)?;
+            // A closing bracket here closes nothing, so the document ended before it
+            if let Some(token @ (Token::ObjectEnd | Token::ArrayEnd)) = self.current_token() {
+                if self.config.allow_trailing_content {
+                    self.stop_at_trailing_content();
+                    break;
+                }
+                let error = ParseError::TrailingContent {
+                    found: token.type_name(),
+                    root: "object",
+                    position: self
+                        .current_token_start()
+                        .unwrap_or_else(|| self.current_position()),
+                };
+                self.recover_entry(error, depth, false, true)?;
+                continue;
+            }
 
-            self.skip_whitespace_and_comments()?;
+            let key_token = self.current_token().map_or("end of file", Token::type_name);
+            let key_position = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+            let key_span = Span::new(
+                key_position,
+                self.current_token_end().unwrap_or(key_position),
+            );
+
+            match self.parse_priority_directive() {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(error) => {
+                    self.recover_entry(error, depth, false, false)?;
+                    continue;
+                }
+            }
+            match self.parse_include_directive() {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(error) => {
+                    self.recover_entry(error, depth, false, false)?;
+                    continue;
+                }
+            }
+
+            let directives = std::mem::take(&mut self.directive_state.pending);
+
+            // Parse key path (supports named sections like "section foo bar {}")
+            let dotted = self.at_dotted_key();
+            self.reading_dotted_key = dotted;
+            let key_path = self.parse_key_path(&context);
+            self.reading_dotted_key = false;
+            let key_path = key_path.and_then(|key_path| match dotted {
+                true => self.split_dotted_key(&key_path[0], &key_span, &context),
+                false => Ok(key_path),
+            });
+            let mut key_path = match key_path {
+                Ok(key_path) => key_path,
+                Err(error) => {
+                    self.recover_entry(error, depth, false, true)?;
+                    continue;
+                }
+            };
 
-            // Detect syntax style for this key-value pair
-            let syntax_style = self.detect_syntax_style()?;
+            // Skipped entries keep their key with a null value, so callers can
+            // still tell which keys the document has
+            if let Some(keys) = keys
+                && !self.is_wanted_key(keys, &key_path[0])
+            {
+                self.skip_entry_value()?;
+                if let Entry::Vacant(entry) = object.entry(key_path.swap_remove(0)) {
+                    entry.insert(UclValue::Null);
+                    key_positions.push(key_position);
+                }
+                continue;
+            }
 
             // Parse value based on detected syntax style
             // For multi-key paths (named sections), push all keys onto context
-            for k in &key_path {
-                context.push_key(k.clone());
-            }
+            let path_start = context.current_object_path.len();
+            context.current_object_path.extend(key_path);
 
-            let value = match syntax_style {
-                SyntaxStyle::Explicit => {
-                    // Expect separator (: or =) and parse value normally
-                    match self.current_token() {
-                        Some(Token::Colon) | Some(Token::Equals) => {
-                            self.advance_token()?;
-                            self.skip_whitespace_and_comments()?;
-                            self.parse_value_with_context(&mut context, true)?
-                        }
-                        Some(token) => {
-                            return Err(ParseError::UnexpectedToken {
-                                token: token.type_name().to_string(),
-                                position: self.current_position(),
-                                expected: "':' or '='".to_string(),
-                            });
-                        }
-                        None => {
-                            return Err(ParseError::UnexpectedToken {
-                                token: "end of file".to_string(),
-                                position: self.current_position(),
-                                expected: "':' or '='".to_string(),
-                            });
-                        }
-                    }
-                }
-                SyntaxStyle::Implicit => {
-                    // Direct value or object without separator
-                    match self.current_token() {
-                        Some(Token::ObjectStart) => self.parse_object_with_context(&mut context)?,
-                        _ => {
-                            // Bare word value
-                            self.parse_bare_word_value_with_context(&context, false)?
-                        }
+            let entry = self.parse_entry_value(&mut context);
+
+            // Pop all keys from context
+            let mut key_path: KeyPath = context.current_object_path.drain(path_start..).collect();
+
+            let (assignment, separator, value, labelled) = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let error = if same_line {
+                        Self::missing_separator(error, key_token, key_position)
+                    } else {
+                        error
+                    };
+                    self.recover_entry(error, depth, false, false)?;
+                    if !dotted && let Entry::Vacant(entry) = object.entry(key_path.swap_remove(0)) {
+                        entry.insert(UclValue::Null);
+                        key_positions.push(key_position);
                     }
-                }
-                SyntaxStyle::NginxNested => {
-                    // key identifier { ... } pattern
-                    self.parse_nginx_nested_object(&mut context)?
+                    continue;
                 }
             };
-
-            // Pop all keys from context
-            for _ in &key_path {
+            // Named sections lint their innermost key against the keys before it
+            // A key path holds at least the key just read
+            #[allow(clippy::unwrap_used)]
+            let (leaf_key, section) = key_path.split_last().unwrap();
+            for k in section {
+                context.push_key(k.clone());
+            }
+            self.attach_directives(directives, &context, leaf_key, key_position);
+            self.record_assignment(&context, leaf_key, assignment);
+            self.lint_entry(&context, leaf_key, key_position, &value);
+            for _ in section {
                 context.pop_key();
             }
+            self.lint_separator(&mut first_separator, separator, leaf_key);
 
             // For named sections (multi-key paths), build nested structure
             // Per SPEC.md lines 154-194: section foo bar { } -> section.foo.bar
-            let final_value = if key_path.len() > 1 {
+            let mut final_value = if key_path.len() > 1 && !dotted {
                 // Build nested structure from right to left
                 // Example: ["section", "foo", "bar"] with value {...} becomes:
                 // section: { foo: { bar: {...} } }
@@ -2776,60 +8652,106 @@ impl<'a> UclParser<'a> {
             let top_key = &key_path[0];
 
             // Handle duplicate keys based on configuration
-            if object.contains_key(top_key) {
+            if dotted {
+                let index = object.get_index_of(top_key);
+                let first_occurrence = index
+                    .and_then(|index| key_positions.get(index).copied())
+                    .unwrap_or(key_position);
+                let result = self.insert_dotted_entry(
+                    &mut object,
+                    &context,
+                    &key_path,
+                    (assignment, final_value),
+                    key_span,
+                    first_occurrence,
+                );
+                if index.is_none() {
+                    key_positions.push(key_position);
+                }
+                if let Err(error) = result {
+                    self.recover_entry(error, depth, false, false)?;
+                    continue;
+                }
+            } else if assignment == Assignment::Append
+                && let Some(existing_value) = object.get_mut(top_key)
+            {
+                if let Err(error) =
+                    self.append_value(existing_value, final_value, top_key, key_position)
+                {
+                    self.recover_entry(error, depth, false, false)?;
+                    continue;
+                }
+                self.record_origin(&context, top_key, key_span);
+            } else if let Some(index) = object.get_index_of(top_key) {
+                self.counters.duplicate_keys += 1;
+                let first_occurrence = key_positions.get(index).copied().unwrap_or(key_position);
                 match self.config.duplicate_key_behavior {
                     DuplicateKeyBehavior::Error => {
-                        return Err(ParseError::DuplicateKey {
-                            key: top_key.clone(),
-                            position: self.current_position(),
-                        });
+                        let labels = Self::section_labels(&final_value, labelled, &key_path[1..]);
+                        self.add_repeated_section(
+                            &mut object[index],
+                            &final_value,
+                            (top_key, &labels),
+                            &context,
+                            (first_occurrence, key_position),
+                        )?;
                     }
                     DuplicateKeyBehavior::ImplicitArray => {
-                        let existing_value = object.shift_remove(top_key).unwrap();
+                        // Replace the value in place so the key keeps its first position
+                        let slot = &mut object[index];
 
                         // If both are objects and we have a nested path, deep merge them
                         if key_path.len() > 1
-                            && existing_value.is_object()
-                            && final_value.is_object()
+                            && let (UclValue::Object(existing_obj), UclValue::Object(new_obj)) =
+                                (&mut *slot, &mut final_value)
                         {
-                            if let (UclValue::Object(existing_obj), UclValue::Object(new_obj)) =
-                                (existing_value, final_value)
-                            {
-                                // Deep merge the objects
-                                let merged = Self::deep_merge_objects(existing_obj, new_obj);
-                                object.insert(top_key.clone(), UclValue::Object(merged));
-                            }
+                            let merged = Self::deep_merge_objects(
+                                std::mem::take(existing_obj),
+                                std::mem::take(new_obj),
+                            );
+                            *existing_obj = merged;
                         } else {
                             // Simple duplicate key -> create array
-                            let new_array = match existing_value {
-                                UclValue::Array(mut arr) => {
-                                    arr.push(final_value);
-                                    UclValue::Array(arr)
-                                }
+                            match slot {
+                                UclValue::Array(arr) => arr.push(final_value),
                                 other => {
                                     let mut arr = SmallVec::new();
-                                    arr.push(other);
+                                    arr.push(std::mem::take(other));
                                     arr.push(final_value);
-                                    UclValue::Array(Box::new(arr))
+                                    *other = UclValue::Array(Box::new(arr));
                                 }
-                            };
-                            object.insert(top_key.clone(), new_array);
+                            }
                         }
                     }
                     DuplicateKeyBehavior::Override => {
+                        self.report_duplicate_key(
+                            top_key,
+                            first_occurrence,
+                            key_position,
+                            "overrides",
+                        );
+                        self.record_origin(&context, top_key, key_span);
                         object.insert(top_key.clone(), final_value);
                     }
+                    DuplicateKeyBehavior::KeepFirst => {
+                        self.report_duplicate_key(
+                            top_key,
+                            first_occurrence,
+                            key_position,
+                            "is ignored in favor of",
+                        );
+                    }
                 }
             } else {
-                object.insert(top_key.clone(), final_value);
+                key_positions.push(key_position);
+                self.record_origin(&context, top_key, key_span);
+                let labels = Self::section_labels(&final_value, labelled, &key_path[1..]);
+                self.record_section_levels(&context, top_key, &labels, key_position);
+                object.insert(key_path.swap_remove(0), final_value);
             }
 
+            // The optional separators are read before the next key
             self.skip_whitespace_and_comments()?;
-
-            // Optional separator
-            if let Some(Token::Comma) | Some(Token::Semicolon) = self.current_token() {
-                self.advance_token()?;
-            }
         }
 
         Ok(UclValue::Object(object))
@@ -2854,33 +8776,52 @@ impl<'a> UclParser<'a> {
         self.skip_whitespace_and_comments()?;
 
         match self.current_token() {
-            Some(Token::Eof) | None => Err(ParseError::UnexpectedToken {
-                token: "end of file".to_string(),
-                position: self.current_position(),
-                expected: "value".to_string(),
+            Some(Token::Eof) | None => Err(self.unexpected_token(&["value"])),
+            Some(Token::Equals) if explicit_separator => Err(ParseError::RepeatedAssignment {
+                position: self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position()),
             }),
+            Some(Token::Anchor(name)) => {
+                let name = name.to_string();
+                self.parse_anchored_value(name, context, explicit_separator)
+            }
+            Some(Token::Reference(name)) => {
+                let name = name.to_string();
+                let value = self.referenced_value(&name)?;
+                self.advance_token()?;
+                self.skip_whitespace_and_comments()?;
+                self.parsing_hooks.validate_owned_value(value, context)
+            }
             Some(Token::String {
                 value,
+                format,
                 needs_expansion,
-                ..
             }) => {
+                let format = *format;
+                let start = self
+                    .current_token_start()
+                    .unwrap_or_else(|| self.current_position());
                 let mut combined = if *needs_expansion {
                     self.expand_variables_with_context_safe(value, context)?
                 } else {
-                    value.to_string()
+                    self.string_value(value)
                 };
 
                 self.advance_token()?;
                 self.skip_whitespace_and_comments()?;
 
                 combined = self.concatenate_inline_strings(combined, Some(&*context))?;
+                self.lint_typed_string(&combined, format, start);
 
                 // Apply custom string post-processing
-                let processed = self.parsing_hooks.process_string(&combined, context)?;
+                let processed = self.parsing_hooks.process_owned_string(combined, context)?;
                 let ucl_value = UclValue::String(processed);
 
                 // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::Integer(val)) => {
@@ -2889,11 +8830,16 @@ impl<'a> UclParser<'a> {
                     .current_token_start()
                     .unwrap_or_else(|| self.current_position());
                 let end = self.current_token_end().unwrap_or(start);
-                let raw_text = self.token_text_from_positions(start, end);
 
                 self.advance_token()?;
                 self.skip_whitespace_and_comments()?;
 
+                if let Some(ucl_value) =
+                    self.apply_number_suffix(int_val as f64, start, end, context)?
+                {
+                    return self.parsing_hooks.validate_owned_value(ucl_value, context);
+                }
+
                 if let (Some(next_start), Some(Token::Key(next_fragment))) =
                     (self.current_token_start(), self.current_token())
                     && next_start.offset == end.offset
@@ -2901,19 +8847,23 @@ impl<'a> UclParser<'a> {
                     && self.has_inline_value_continuation()
                 {
                     // Number immediately followed by identifier - treat as string
-                    let mut combined = raw_text;
+                    let mut combined = self.token_text_from_positions(start, end);
                     combined.push_str(next_fragment);
                     self.advance_token()?;
 
                     // Continue collecting if more tokens follow
                     let final_value = self.collect_inline_value(combined, context)?;
                     let ucl_value = UclValue::String(final_value);
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
                     return Ok(validated_value);
                 }
 
                 let ucl_value = UclValue::Integer(int_val);
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::Float(val)) => {
@@ -2922,11 +8872,14 @@ impl<'a> UclParser<'a> {
                     .current_token_start()
                     .unwrap_or_else(|| self.current_position());
                 let end = self.current_token_end().unwrap_or(start);
-                let raw_text = self.token_text_from_positions(start, end);
 
                 self.advance_token()?;
                 self.skip_whitespace_and_comments()?;
 
+                if let Some(ucl_value) = self.apply_number_suffix(float_val, start, end, context)? {
+                    return self.parsing_hooks.validate_owned_value(ucl_value, context);
+                }
+
                 if let (Some(next_start), Some(Token::Key(next_fragment))) =
                     (self.current_token_start(), self.current_token())
                     && next_start.offset == end.offset
@@ -2934,19 +8887,23 @@ impl<'a> UclParser<'a> {
                     && self.has_inline_value_continuation()
                 {
                     // Number immediately followed by identifier - treat as string
-                    let mut combined = raw_text;
+                    let mut combined = self.token_text_from_positions(start, end);
                     combined.push_str(next_fragment);
                     self.advance_token()?;
 
                     // Continue collecting if more tokens follow
                     let final_value = self.collect_inline_value(combined, context)?;
                     let ucl_value = UclValue::String(final_value);
-                    let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                    let validated_value = self
+                        .parsing_hooks
+                        .validate_owned_value(ucl_value, context)?;
                     return Ok(validated_value);
                 }
 
-                let ucl_value = UclValue::Float(float_val);
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let ucl_value = self.float_value(float_val, start, end)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::Time(_)) => {
@@ -2955,9 +8912,11 @@ impl<'a> UclParser<'a> {
                     _ => unreachable!("Expected time token"),
                 };
                 self.advance_token()?;
-                let ucl_value = UclValue::Float(time_val);
+                let ucl_value = UclValue::Time(time_val);
 
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::Boolean(val)) => {
@@ -2966,7 +8925,9 @@ impl<'a> UclParser<'a> {
                 let ucl_value = UclValue::Boolean(bool_val);
 
                 // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::Null) => {
@@ -2974,7 +8935,9 @@ impl<'a> UclParser<'a> {
                 let ucl_value = UclValue::Null;
 
                 // Apply validation hooks
-                let validated_value = self.parsing_hooks.validate_value(&ucl_value, context)?;
+                let validated_value = self
+                    .parsing_hooks
+                    .validate_owned_value(ucl_value, context)?;
                 Ok(validated_value)
             }
             Some(Token::ObjectStart) => self.parse_object_with_context(context),
@@ -2983,11 +8946,7 @@ impl<'a> UclParser<'a> {
                 // Handle bare word values (unquoted identifiers)
                 self.parse_bare_word_value_with_context(context, explicit_separator)
             }
-            Some(token) => Err(ParseError::UnexpectedToken {
-                token: token.type_name().to_string(),
-                position: self.current_position(),
-                expected: "value".to_string(),
-            }),
+            Some(_) => Err(self.unexpected_token(&["value"])),
         }
     }
 
@@ -3003,17 +8962,21 @@ impl<'a> UclParser<'a> {
             });
         }
 
-        // Consume the opening bracket
+        // Consume the opening bracket, after any comments saved before it
+        self.skip_whitespace_and_comments()?;
         self.expect_token(&Token::ArrayStart)?;
 
-        let mut array = UclArray::new();
+        let mut value = self.pool.borrow_mut().array();
+        let UclValue::Array(array) = &mut value else {
+            unreachable!("the pool only holds arrays");
+        };
 
         // Handle empty array
         self.skip_whitespace_and_comments()?;
         if let Some(Token::ArrayEnd) = self.current_token() {
             self.advance_token()?;
             self.current_depth -= 1;
-            return Ok(UclValue::Array(Box::new(array)));
+            return Ok(value);
         }
 
         let mut index = 0;
@@ -3021,15 +8984,46 @@ impl<'a> UclParser<'a> {
             self.skip_whitespace_and_comments()?;
 
             // Check for end of array
-            if let Some(Token::ArrayEnd) = self.current_token() {
-                break;
+            match self.current_token() {
+                Some(Token::ArrayEnd) => break,
+                // Only reached before the first element, as later runs are read after each element
+                Some(Token::Comma | Token::Semicolon) => {
+                    self.skip_separators(SeparatorContext::Array { index }, true)?;
+                }
+                Some(Token::ObjectEnd | Token::Eof) | None => {
+                    return Err(self.unexpected_token(&["value", "']'"]));
+                }
+                _ => {}
             }
 
             // Parse value with array index context
-            context.push_key(index.to_string());
+            let element_start = self
+                .current_token_start()
+                .unwrap_or_else(|| self.current_position());
+            // A bare key would otherwise read `= value` as more words of its value
+            if let Some(Token::Key(key)) = self.current_token() {
+                let key = key.to_string();
+                if self.next_token_matches(|token| {
+                    matches!(token, Some(Token::Equals | Token::Colon))
+                })? {
+                    return Err(ParseError::KeyValueInArray {
+                        key,
+                        position: element_start,
+                    });
+                }
+            }
+            context.push_key(UclKey::index(index));
+            let format = self.value_string_format();
+            let source = self.value_source();
             let value = self.parse_value_with_context(context, false)?;
+            self.record_string_format(context, format, &value);
+            self.record_string_source(source, &value);
             context.pop_key();
 
+            // Only the last element is kept when validating, for the error above
+            if self.validating {
+                array.clear();
+            }
             array.push(value);
             index += 1;
 
@@ -3038,26 +9032,27 @@ impl<'a> UclParser<'a> {
             // Check for separator or end
             match self.current_token() {
                 Some(Token::Comma) | Some(Token::Semicolon) => {
-                    self.advance_token()?;
-                    // Continue to next value
+                    self.skip_separators(SeparatorContext::Array { index }, false)?;
                 }
                 Some(Token::ArrayEnd) => {
                     // End of array
                     break;
                 }
-                Some(token) => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: token.type_name().to_string(),
-                        position: self.current_position(),
-                        expected: "',', ';', or ']'".to_string(),
-                    });
+                // `[ host = "a" ]`: an object written without its braces
+                Some(Token::Equals | Token::Colon) => {
+                    if let Some(UclValue::String(key)) = array.last() {
+                        return Err(ParseError::KeyValueInArray {
+                            key: key.clone(),
+                            position: element_start,
+                        });
+                    }
+                    return Err(self.unexpected_token(&["','", "';'", "']'"]));
                 }
-                None => {
-                    return Err(ParseError::UnexpectedToken {
-                        token: "end of file".to_string(),
-                        position: self.current_position(),
-                        expected: "',', ';', or ']'".to_string(),
-                    });
+                _ if self.config.json_only => {
+                    return Err(self.unexpected_token(&["','", "']'"]));
+                }
+                _ => {
+                    return Err(self.unexpected_token(&["','", "';'", "']'"]));
                 }
             }
         }
@@ -3066,7 +9061,7 @@ impl<'a> UclParser<'a> {
         self.expect_token(&Token::ArrayEnd)?;
         self.current_depth -= 1;
 
-        Ok(UclValue::Array(Box::new(array)))
+        Ok(value)
     }
 
     /// Safe variable expansion with context that handles missing handlers gracefully
@@ -3075,29 +9070,62 @@ impl<'a> UclParser<'a> {
         input: &str,
         context: &VariableContext,
     ) -> Result<String, ParseError> {
-        if let Some(handler) = &self.variable_handler {
-            self.expand_variables_with_context(input, handler.as_ref(), context)
-        } else {
-            // No variable handler - return input as-is
+        ParseCounters::increment(&self.counters.strings_expanded);
+        if self.config.json_only {
+            // JSON strings have no variables, and `$$` is two dollar signs
             Ok(input.to_string())
+        } else if self.validating {
+            self.check_variable_syntax(input)?;
+            Ok(input.to_string())
+        } else if let Some(handler) = &self.variable_handler {
+            self.expand_variables_with_context(input, handler.get(), context)
+        } else {
+            // No variable handler - only the `$$` escape is applied
+            Ok(input.replace("$$", "$"))
         }
     }
 
     /// Expands variables in a string using a two-pass algorithm
     pub fn expand_variables(&self, input: &str) -> Result<String, ParseError> {
-        if let Some(handler) = &self.variable_handler {
+        if self.validating {
+            self.check_variable_syntax(input)?;
+            Ok(input.to_string())
+        } else if let Some(handler) = &self.variable_handler {
             let context = VariableContext::new(self.current_position());
-            self.expand_variables_with_context(input, handler.as_ref(), &context)
+            self.expand_variables_with_context(input, handler.get(), &context)
         } else {
             Ok(input.to_string())
         }
     }
 
+    /// Checks the syntax of the variable references in a string without resolving them
+    ///
+    /// Fallbacks are checked too, although expansion only reads them when
+    /// their variable is undefined.
+    fn check_variable_syntax(&self, input: &str) -> Result<(), ParseError> {
+        for segment in scan_variables(input) {
+            match segment {
+                Segment::Variable {
+                    fallback: Some(fallback),
+                    ..
+                } => self.check_variable_syntax(fallback)?,
+                Segment::Malformed { message, .. } => {
+                    return Err(ParseError::VariableExpansion {
+                        message,
+                        position: self.current_position(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
     /// Expands variables with recursive expansion and circular reference detection
     pub fn expand_variables_recursive(&self, input: &str) -> Result<String, ParseError> {
         if let Some(handler) = &self.variable_handler {
             let mut context = VariableContext::new(self.current_position());
-            self.expand_variables_recursive_with_context(input, handler.as_ref(), &mut context)
+            self.expand_variables_recursive_with_context(input, handler.get(), &mut context)
         } else {
             Ok(input.to_string())
         }
@@ -3110,93 +9138,53 @@ impl<'a> UclParser<'a> {
         handler: &dyn VariableHandler,
         context: &mut VariableContext,
     ) -> Result<String, ParseError> {
-        // Two-pass expansion: first calculate the final length, then expand
-        let mut result = String::new();
-        let mut chars = input.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' {
-                match chars.peek() {
-                    Some('$') => {
-                        // $$ escape sequence for literal $
-                        chars.next(); // consume second $
-                        result.push('$');
-                    }
-                    Some('{') => {
-                        // ${VAR} format (UCL spec compliant)
-                        chars.next(); // consume '{'
-                        let (var_name, fallback) =
-                            self.parse_braced_variable_expression(&mut chars)?;
-
-                        // Check for circular reference
-                        if let Err(cycle_msg) = context.push_expansion(var_name.clone()) {
-                            return Err(ParseError::VariableExpansion {
-                                message: cycle_msg,
-                                position: context.position,
-                            });
-                        }
-
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            // Recursively expand the value
-                            let expanded_value = self.expand_variables_recursive_with_context(
-                                &value, handler, context,
-                            )?;
-                            result.push_str(&expanded_value);
-                        } else if let Some(default_value) = fallback {
-                            // Expand the fallback expression
-                            let expanded_value = self.expand_variables_recursive_with_context(
-                                &default_value,
-                                handler,
-                                context,
-                            )?;
-                            result.push_str(&expanded_value);
-                        } else {
-                            // Preserve original if not found (per UCL spec)
-                            result.push_str(&format!("${{{}}}", var_name));
-                        }
+        let mut expansion = Expansion::new(self.lexer.config().max_string_length());
 
-                        context.pop_expansion();
-                    }
-                    Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
-                        // $VAR format (greedy matching)
-                        let var_name = self.parse_simple_variable_name(&mut chars);
-
-                        // Check for circular reference
-                        if let Err(cycle_msg) = context.push_expansion(var_name.clone()) {
-                            return Err(ParseError::VariableExpansion {
-                                message: cycle_msg,
-                                position: context.position,
-                            });
-                        }
+        for segment in scan_variables(input) {
+            let (name, fallback, span) = match segment {
+                Segment::Literal(text) => {
+                    expansion.push(text, context.position)?;
+                    continue;
+                }
+                Segment::Variable {
+                    name,
+                    fallback,
+                    span,
+                } => (name, fallback, span),
+                Segment::Malformed { message, .. } => {
+                    return Err(ParseError::VariableExpansion {
+                        message,
+                        position: self.current_position(),
+                    });
+                }
+            };
 
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            // Recursively expand the value
-                            let expanded_value = self.expand_variables_recursive_with_context(
-                                &value, handler, context,
-                            )?;
-                            result.push_str(&expanded_value);
-                        } else {
-                            // Preserve original if not found
-                            result.push_str(&format!("${}", var_name));
-                        }
+            // Check for circular reference
+            if let Err(cycle_msg) = context.push_expansion(name.to_string()) {
+                return Err(ParseError::VariableExpansion {
+                    message: cycle_msg,
+                    position: context.position,
+                });
+            }
 
-                        context.pop_expansion();
-                    }
-                    _ => {
-                        // Just a literal $ followed by something else
-                        result.push('$');
-                    }
-                }
+            if let Some(value) = handler.resolve_variable_with_context(name, context) {
+                // Recursively expand the value
+                let value =
+                    self.expand_variables_recursive_with_context(&value, handler, context)?;
+                expansion.push(value, context.position)?;
+            } else if let Some(fallback) = fallback {
+                let value =
+                    self.expand_variables_recursive_with_context(fallback, handler, context)?;
+                expansion.push(value, context.position)?;
             } else {
-                result.push(ch);
+                // Preserve original if not found (per UCL spec)
+                expansion.push(&input[span], context.position)?;
             }
+
+            context.pop_expansion();
         }
 
-        Ok(result)
+        Ok(expansion.finish())
     }
 
     /// Expands variables with a specific handler and context
@@ -3217,239 +9205,132 @@ impl<'a> UclParser<'a> {
         context: &VariableContext,
         allow_recursion: bool,
     ) -> Result<String, ParseError> {
-        // Two-pass expansion: first calculate the final length, then expand
-        let expanded_length = self.calculate_expanded_length(input, handler, context)?;
-        let mut result = String::with_capacity(expanded_length);
-
-        let mut chars = input.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' {
-                match chars.peek() {
-                    Some('$') => {
-                        // $$ escape sequence for literal $
-                        chars.next(); // consume second $
-                        result.push('$');
-                    }
-                    Some('{') => {
-                        // ${VAR} format (UCL spec compliant)
-                        chars.next(); // consume '{'
-                        let (var_name, fallback) =
-                            self.parse_braced_variable_expression(&mut chars)?;
-
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            if allow_recursion && value.contains('$') {
-                                // Recursively expand the value
-                                let expanded_value = self.expand_variables_with_context_internal(
-                                    &value, handler, context, true,
-                                )?;
-                                result.push_str(&expanded_value);
-                            } else {
-                                result.push_str(&value);
-                            }
-                        } else if let Some(default_value) = fallback {
-                            let expanded_value = self.expand_variables_with_context_internal(
-                                &default_value,
-                                handler,
-                                context,
-                                true,
-                            )?;
-                            result.push_str(&expanded_value);
-                        } else {
-                            // Preserve original if not found (per UCL spec)
-                            result.push_str(&format!("${{{}}}", var_name));
-                        }
-                    }
-                    Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
-                        // $VAR format (greedy matching)
-                        let var_name = self.parse_simple_variable_name(&mut chars);
-
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            if allow_recursion && value.contains('$') {
-                                // Recursively expand the value
-                                let expanded_value = self.expand_variables_with_context_internal(
-                                    &value, handler, context, true,
-                                )?;
-                                result.push_str(&expanded_value);
-                            } else {
-                                result.push_str(&value);
-                            }
-                        } else {
-                            // Preserve original if not found
-                            result.push_str(&format!("${}", var_name));
-                        }
-                    }
-                    _ => {
-                        // Just a literal $ followed by something else
-                        result.push('$');
-                    }
-                }
-            } else {
-                result.push(ch);
-            }
-        }
-
-        Ok(result)
+        let mut expansion = Expansion::new(self.lexer.config().max_string_length());
+        self.expand_segments(input, handler, context, allow_recursion, &mut expansion)?;
+        Ok(expansion.finish())
     }
 
-    /// Calculates the final length after variable expansion
-    fn calculate_expanded_length(
+    /// Appends the expansion of `input` from a single scan of its segments
+    fn expand_segments<'s>(
         &self,
-        input: &str,
+        input: &'s str,
         handler: &dyn VariableHandler,
         context: &VariableContext,
-    ) -> Result<usize, ParseError> {
-        let mut length = 0;
-        let mut chars = input.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '$' {
-                match chars.peek() {
-                    Some('$') => {
-                        // $$ escape sequence
-                        chars.next(); // consume second $
-                        length += 1; // One $ character
-                    }
-                    Some('{') => {
-                        // ${VAR} format (UCL spec compliant)
-                        chars.next(); // consume '{'
-                        let (var_name, fallback) =
-                            self.parse_braced_variable_expression(&mut chars)?;
-
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            length += value.len();
-                        } else {
-                            if let Some(default_value) = fallback {
-                                let expanded_length = self.calculate_expanded_length(
-                                    &default_value,
-                                    handler,
-                                    context,
-                                )?;
-                                length += expanded_length;
-                            } else {
-                                // Preserve original: ${VAR} (per UCL spec)
-                                length += 3 + var_name.len(); // ${ + VAR + }
-                            }
-                        }
-                    }
-                    Some(c) if c.is_ascii_alphabetic() || *c == '_' => {
-                        // $VAR format
-                        let var_name = self.parse_simple_variable_name(&mut chars);
-
-                        if let Some(value) =
-                            handler.resolve_variable_with_context(&var_name, context)
-                        {
-                            length += value.len();
+        allow_recursion: bool,
+        expansion: &mut Expansion<'s>,
+    ) -> Result<(), ParseError> {
+        for segment in scan_variables(input) {
+            match segment {
+                Segment::Literal(text) => expansion.push(text, context.position)?,
+                Segment::Variable {
+                    name,
+                    fallback,
+                    span,
+                } => {
+                    if let Some(value) = handler.resolve_variable_with_context(name, context) {
+                        ParseCounters::increment(&self.counters.variables_expanded);
+                        if allow_recursion && value.contains('$') {
+                            // Recursively expand the value
+                            let value = self.expand_variables_with_context_internal(
+                                &value, handler, context, true,
+                            )?;
+                            expansion.push(value, context.position)?;
                         } else {
-                            // Preserve original: $VAR
-                            length += 1 + var_name.len(); // $ + VAR
+                            expansion.push(value, context.position)?;
                         }
+                    } else if let Some(fallback) = fallback {
+                        ParseCounters::increment(&self.counters.variables_expanded);
+                        self.expand_segments(fallback, handler, context, true, expansion)?;
+                    } else {
+                        // Preserve original if not found (per UCL spec)
+                        expansion.push(&input[span], context.position)?;
                     }
-                    _ => {
-                        // Just a literal $
-                        length += 1;
-                    }
-                }
-            } else {
-                length += ch.len_utf8();
-            }
-        }
-
-        Ok(length)
-    }
-
-    /// Parses a variable expression from `${VAR}` or `${VAR:-default}`
-    fn parse_braced_variable_expression(
-        &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
-    ) -> Result<(String, Option<String>), ParseError> {
-        let mut var_name = String::new();
-        let mut fallback_text = String::new();
-        let mut has_fallback = false;
-        let mut found_closing_brace = false;
-
-        while let Some(&ch) = chars.peek() {
-            if ch == '}' {
-                chars.next(); // consume '}'
-                found_closing_brace = true;
-                break;
-            }
-
-            if !has_fallback {
-                if ch == ':' {
-                    chars.next();
-                    if chars.peek() == Some(&'-') {
-                        chars.next();
-                        has_fallback = true;
-                        continue;
-                    }
-                    return Err(ParseError::VariableExpansion {
-                        message: "Invalid variable fallback syntax".to_string(),
-                        position: self.current_position(),
-                    });
                 }
-                if ch.is_ascii_alphanumeric() || ch == '_' {
-                    var_name.push(ch);
-                    chars.next();
-                } else {
+                Segment::Malformed { message, .. } => {
                     return Err(ParseError::VariableExpansion {
-                        message: format!("Invalid character '{}' in variable name", ch),
+                        message,
                         position: self.current_position(),
                     });
                 }
-            } else {
-                fallback_text.push(ch);
-                chars.next();
             }
         }
+        Ok(())
+    }
+}
 
-        if !found_closing_brace {
-            return Err(ParseError::VariableExpansion {
-                message: "Unclosed variable expansion: missing '}'".to_string(),
-                position: self.current_position(),
-            });
-        }
+/// Returns true if `text` is a number with digits on both sides of an optional point, such as `-12.50`
+fn is_plain_decimal(text: &str) -> bool {
+    let unsigned = text.strip_prefix(['-', '+']).unwrap_or(text);
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, "0"));
+    [integer, fraction]
+        .iter()
+        .all(|digits| !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()))
+}
 
-        if var_name.is_empty() {
-            return Err(ParseError::VariableExpansion {
-                message: "Empty variable name in ${} expansion".to_string(),
-                position: self.current_position(),
-            });
+/// Returns true if `text` is a number written in JSON syntax, such as `-1.5e+3`
+fn is_json_number(text: &str) -> bool {
+    let digits = |text: &str| text.bytes().take_while(u8::is_ascii_digit).count();
+    let unsigned = text.strip_prefix('-').unwrap_or(text);
+    let integer = digits(unsigned);
+    // No leading zeros, as in `01`
+    if integer == 0 || (integer > 1 && unsigned.starts_with('0')) {
+        return false;
+    }
+    let mut rest = &unsigned[integer..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let length = digits(fraction);
+        if length == 0 {
+            return false;
         }
-
-        let fallback = if has_fallback {
-            Some(fallback_text)
-        } else {
-            None
-        };
-
-        Ok((var_name, fallback))
+        rest = &fraction[length..];
     }
-
-    /// Parses a variable name from $VAR format (greedy matching)
-    fn parse_simple_variable_name(
-        &self,
-        chars: &mut std::iter::Peekable<std::str::Chars>,
-    ) -> String {
-        let mut var_name = String::new();
-
-        while let Some(&ch) = chars.peek() {
-            if ch.is_ascii_alphanumeric() || ch == '_' {
-                var_name.push(ch);
-                chars.next();
-            } else {
-                break;
-            }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let length = digits(exponent);
+        if length == 0 {
+            return false;
         }
+        rest = &exponent[length..];
+    }
+    rest.is_empty()
+}
 
-        var_name
+/// Names the UCL extension used in the double-quoted string `text`, if any
+///
+/// JSON strings have no raw tabs and only the escapes `\" \\ \/ \b \f \n \r \t`
+/// and `\uXXXX`, and are never triple-quoted.
+fn json_string_extension(text: &str) -> Option<&'static str> {
+    if text.starts_with("\"\"\"") {
+        return Some("a triple-quoted string");
+    }
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\t' => return Some("a tab that is not escaped"),
+            b'\\' => match bytes.next() {
+                Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {}
+                Some(b'u') if bytes.next() != Some(b'{') => {}
+                _ => return Some("an escape sequence outside JSON"),
+            },
+            _ => {}
+        }
     }
+    None
+}
+
+/// Checks a document against the full grammar without keeping its contents
+///
+/// Returns every error found, as `UclParser::validate` does with `config`.
+///
+/// ```rust
+/// use ucl_lexer::{ParserConfig, validate};
+///
+/// let config = ParserConfig::default();
+/// assert!(validate("server { port = 80 }", &config).is_ok());
+/// assert_eq!(validate("a = 1,,\nb = [,]", &config).unwrap_err().len(), 2);
+/// ```
+pub fn validate(input: &str, config: &ParserConfig) -> Result<(), Vec<UclError>> {
+    UclParser::new(input).with_config(config.clone()).validate()
 }
 
 // Convert LexError to ParseError
@@ -3459,47 +9340,174 @@ impl From<crate::error::LexError> for ParseError {
             crate::error::LexError::UnexpectedCharacter {
                 character,
                 position,
-            } => ParseError::UnexpectedToken {
-                token: character.to_string(),
+            } => ParseError::UnexpectedCharacter {
+                character,
                 position,
-                expected: "valid character".to_string(),
             },
-            _ => {
-                ParseError::InvalidObject {
-                    message: format!("Lexer error: {}", lex_error),
-                    position: Position::new(), // Default position
-                }
+            crate::error::LexError::DocumentTooLarge { size, limit } => {
+                ParseError::DocumentTooLarge { size, limit }
             }
+            other => ParseError::Lex(other),
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used, clippy::expect_used)]
 mod tests {
     use super::*;
     use serde_json::Value;
 
+    /// Returns a variable of the process environment that `${NAME}` can refer to
+    fn live_variable() -> Option<(String, String)> {
+        std::env::vars().find(|(name, _)| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        })
+    }
+
     #[test]
     fn test_environment_variable_handler() {
-        let handler = EnvironmentVariableHandler;
+        let handler = EnvironmentVariableHandler::new();
 
-        // Set a test environment variable
-        unsafe {
-            std::env::set_var("TEST_VAR", "test_value");
+        // The process environment is only read, tests never change it
+        if let Some((name, value)) = live_variable() {
+            assert_eq!(handler.resolve_variable(&name), Some(value));
         }
+        assert_eq!(handler.resolve_variable("NONEXISTENT_VAR"), None);
+    }
+
+    #[test]
+    fn test_legacy_octal_diagnostics_reach_parser() {
+        let config = LexerConfig {
+            legacy_octal: true,
+            ..Default::default()
+        };
+        let mut parser = UclParser::with_lexer_config("mode 0644\numask = 022\n", config);
+        let value = parser.parse_document().unwrap();
+
+        assert_eq!(value["mode"], 0o644);
+        assert_eq!(value["umask"], 0o22);
+        let lines: Vec<usize> = parser
+            .diagnostics()
+            .iter()
+            .map(|d| d.position.line)
+            .collect();
+        assert_eq!(lines, vec![1, 2]);
+
+        let error = UclParser::new("mode = 0644").parse_document().unwrap_err();
+        assert!(error.to_string().contains("write 0o644 (=420) instead"));
+    }
+
+    fn test_environment() -> HashMap<String, String> {
+        [
+            ("HOME", "/home/app"),
+            ("DB_PASSWORD", "hunter2"),
+            ("DB_HOST", "db.internal"),
+            ("APP_PORT", "8080"),
+            ("AWS_SECRET_KEY", "secret"),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn test_environment_handler_snapshot() {
+        let handler = EnvironmentVariableHandler::from_snapshot(test_environment());
 
         assert_eq!(
-            handler.resolve_variable("TEST_VAR"),
-            Some("test_value".to_string())
+            handler.resolve_variable("HOME"),
+            Some("/home/app".to_string())
         );
-        assert_eq!(handler.resolve_variable("NONEXISTENT_VAR"), None);
+        assert_eq!(handler.resolve_variable("PATH"), None);
+
+        let value = UclParser::with_variable_handler("port = \"${APP_PORT}\"", Box::new(handler))
+            .parse_document()
+            .unwrap();
+        assert_eq!(value["port"], "8080");
+    }
+
+    #[test]
+    fn test_environment_handler_snapshot_is_fixed() {
+        // A snapshot never falls back to the live environment
+        let handler = EnvironmentVariableHandler::from_snapshot(HashMap::new());
+        if let Some((name, _)) = live_variable() {
+            assert_eq!(handler.resolve_variable(&name), None);
+        }
 
-        // Clean up
-        unsafe {
-            std::env::remove_var("TEST_VAR");
+        let snapshot = EnvironmentVariableHandler::snapshot();
+        if let Some((name, value)) = live_variable() {
+            assert_eq!(snapshot.resolve_variable(&name), Some(value));
         }
     }
 
+    #[test]
+    fn test_environment_handler_allowlist() {
+        let handler = EnvironmentVariableHandler::from_snapshot(test_environment())
+            .with_allowlist(["DB_*", "APP_PORT"]);
+
+        assert_eq!(
+            handler.resolve_variable("DB_HOST"),
+            Some("db.internal".to_string())
+        );
+        assert_eq!(
+            handler.resolve_variable("APP_PORT"),
+            Some("8080".to_string())
+        );
+        assert_eq!(handler.resolve_variable("HOME"), None);
+        assert_eq!(handler.resolve_variable("AWS_SECRET_KEY"), None);
+    }
+
+    #[test]
+    fn test_environment_handler_denylist() {
+        let handler = EnvironmentVariableHandler::from_snapshot(test_environment())
+            .with_allowlist(["DB_*", "AWS_*"])
+            .with_denylist(["*PASSWORD*", "*SECRET*"]);
+
+        assert_eq!(
+            handler.resolve_variable("DB_HOST"),
+            Some("db.internal".to_string())
+        );
+        assert_eq!(handler.resolve_variable("DB_PASSWORD"), None);
+        assert_eq!(handler.resolve_variable("AWS_SECRET_KEY"), None);
+    }
+
+    #[test]
+    fn test_environment_handler_name_transform() {
+        let handler = EnvironmentVariableHandler::from_snapshot(test_environment())
+            .with_name_transform(EnvironmentVariableHandler::screaming_snake_case)
+            .with_denylist(["DB_PASSWORD"]);
+
+        assert_eq!(
+            handler.resolve_variable("db.host"),
+            Some("db.internal".to_string())
+        );
+        assert_eq!(
+            handler.resolve_variable("app-port"),
+            Some("8080".to_string())
+        );
+        assert_eq!(handler.resolve_variable("db.password"), None);
+
+        let prefixed = EnvironmentVariableHandler::from_snapshot(test_environment())
+            .with_name_transform(|name| format!("APP_{}", name.to_uppercase()));
+        assert_eq!(prefixed.resolve_variable("port"), Some("8080".to_string()));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("DB_*", "DB_HOST"));
+        assert!(glob_matches("*_KEY", "AWS_SECRET_KEY"));
+        assert!(glob_matches("A?C", "ABC"));
+        assert!(glob_matches("*SECRET*", "AWS_SECRET_KEY"));
+        assert!(!glob_matches("DB_*", "XDB_HOST"));
+        assert!(!glob_matches("A?C", "AC"));
+        assert!(!glob_matches("HOME", "HOMEDIR"));
+    }
+
     #[test]
     fn test_map_variable_handler() {
         let mut handler = MapVariableHandler::new();
@@ -3516,14 +9524,12 @@ mod tests {
         let mut map_handler = MapVariableHandler::new();
         map_handler.insert("map_var".to_string(), "map_value".to_string());
 
+        let environment = HashMap::from([("ENV_VAR".to_string(), "env_value".to_string())]);
         let mut chained = ChainedVariableHandler::new();
         chained.add_handler(Box::new(map_handler));
-        chained.add_handler(Box::new(EnvironmentVariableHandler));
-
-        // Set environment variable
-        unsafe {
-            std::env::set_var("ENV_VAR", "env_value");
-        }
+        chained.add_handler(Box::new(EnvironmentVariableHandler::from_snapshot(
+            environment,
+        )));
 
         assert_eq!(
             chained.resolve_variable("map_var"),
@@ -3534,11 +9540,6 @@ mod tests {
             Some("env_value".to_string())
         );
         assert_eq!(chained.resolve_variable("nonexistent"), None);
-
-        // Clean up
-        unsafe {
-            std::env::remove_var("ENV_VAR");
-        }
     }
 
     #[test]
@@ -3647,7 +9648,7 @@ mod tests {
         let mut parser = UclParser::new(r#"{ "key1": "value1", "key2": 42 }"#);
         let result = parser.parse_value().unwrap();
 
-        match result {
+        match &result {
             UclValue::Object(obj) => {
                 assert_eq!(obj.len(), 2);
                 assert_eq!(
@@ -3665,7 +9666,7 @@ mod tests {
         let mut parser = UclParser::new(r#"[1, "two", true, null]"#);
         let result = parser.parse_value().unwrap();
 
-        match result {
+        match &result {
             UclValue::Array(arr) => {
                 assert_eq!(arr.len(), 4);
                 assert_eq!(arr[0], UclValue::Integer(1));
@@ -3682,7 +9683,7 @@ mod tests {
         let mut parser = UclParser::new(r#"{ "nested": { "array": [1, 2, 3] } }"#);
         let result = parser.parse_value().unwrap();
 
-        match result {
+        match &result {
             UclValue::Object(obj) => match obj.get("nested") {
                 Some(UclValue::Object(nested)) => match nested.get("array") {
                     Some(UclValue::Array(arr)) => {
@@ -3704,7 +9705,7 @@ mod tests {
         let mut parser = UclParser::new(r#"{ "key": "value1", "key": "value2" }"#);
         let result = parser.parse_value().unwrap();
 
-        match result {
+        match &result {
             UclValue::Object(obj) => match obj.get("key") {
                 Some(UclValue::Array(arr)) => {
                     assert_eq!(arr.len(), 2);
@@ -3730,7 +9731,7 @@ mod tests {
 
         let result = parser.parse_value().unwrap();
 
-        match result {
+        match &result {
             UclValue::Object(obj) => {
                 assert_eq!(
                     obj.get("greeting"),
@@ -3747,7 +9748,7 @@ mod tests {
         let mut parser = UclParser::new(r#"key1 = "value1"; key2: 42"#);
         let result = parser.parse_implicit_object().unwrap();
 
-        match result {
+        match &result {
             UclValue::Object(obj) => {
                 assert_eq!(obj.len(), 2);
                 assert_eq!(
@@ -3765,7 +9766,7 @@ mod tests {
         // Test empty document
         let mut parser = UclParser::new("");
         let result = parser.parse_document().unwrap();
-        match result {
+        match &result {
             UclValue::Object(obj) => assert!(obj.is_empty()),
             _ => panic!("Expected empty object"),
         }
@@ -3773,7 +9774,7 @@ mod tests {
         // Test explicit object
         let mut parser = UclParser::new(r#"{ "key": "value" }"#);
         let result = parser.parse_document().unwrap();
-        match result {
+        match &result {
             UclValue::Object(obj) => {
                 assert_eq!(obj.get("key"), Some(&UclValue::String("value".to_string())));
             }
@@ -3783,7 +9784,7 @@ mod tests {
         // Test implicit object
         let mut parser = UclParser::new(r#"key = "value""#);
         let result = parser.parse_document().unwrap();
-        match result {
+        match &result {
             UclValue::Object(obj) => {
                 assert_eq!(obj.get("key"), Some(&UclValue::String("value".to_string())));
             }
@@ -4210,8 +10211,8 @@ mod tests {
             .unwrap();
 
         // Verify the parser was created successfully
-        assert!(parser.parsing_hooks().number_suffix_handlers.len() > 0);
-        assert!(parser.parsing_hooks().string_processors.len() > 0);
+        assert!(!parser.parsing_hooks().number_suffix_handlers.is_empty());
+        assert!(!parser.parsing_hooks().string_processors.is_empty());
     }
 
     #[test]
@@ -4233,7 +10234,7 @@ mod tests {
         let result = parser.parse_document();
         assert!(result.is_ok());
 
-        if let Ok(UclValue::Object(obj)) = result {
+        if let Ok(UclValue::Object(obj)) = &result {
             // Check that path was normalized
             if let Some(UclValue::String(path)) = obj.get("path") {
                 assert_eq!(path, "path"); // "../" should be resolved and "./" removed
@@ -4264,4 +10265,42 @@ mod tests {
         // because we're testing the interface, not the specific implementation
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_keywords_and_numbers_as_keys() {
+        let value = UclParser::new("true = 1\nnull = 2\n404 = \"missing\"\ninf = 3")
+            .parse_document()
+            .unwrap();
+
+        assert_eq!(value["true"], 1);
+        assert_eq!(value["null"], 2);
+        assert_eq!(value["404"], "missing");
+        assert_eq!(value["inf"], 3);
+    }
+
+    #[test]
+    fn test_implicit_array_value() {
+        let value = UclParser::new("levels [debug, info ]\nport 80")
+            .parse_document()
+            .unwrap();
+
+        assert_eq!(value["levels"], crate::ucl!(["debug", "info"]));
+        assert_eq!(value["port"], 80);
+    }
+
+    #[test]
+    fn test_nested_variable_fallback() {
+        let mut handler = MapVariableHandler::new();
+        handler.insert("B".to_string(), "inner".to_string());
+
+        let value = UclParser::with_variable_handler(
+            "a = \"${A:-${B}}\"\nb = \"${A:-${C:-last}}\"",
+            Box::new(handler),
+        )
+        .parse_document()
+        .unwrap();
+
+        assert_eq!(value["a"], "inner");
+        assert_eq!(value["b"], "last");
+    }
 }
@@ -0,0 +1,526 @@
+//! Source provenance for parsed values
+//!
+//! When `ParserConfig::track_provenance` is enabled the parser records where
+//! each object entry was defined. Origins are kept in a side table keyed by
+//! key path, so `UclValue` itself stays a plain value type. `TrackedValue`
+//! pairs a value with its origins and keeps both in sync when layering
+//! documents from several files.
+//!
+//! The side table also holds merge priorities, set per path or for a whole
+//! document with the libucl `.priority N` directive. When layers are merged,
+//! a value of higher priority is kept regardless of merge order.
+//!
+//! It also records which values were written with `+=` alone. Such a value
+//! is appended to the value of the same key in the layer below instead of
+//! replacing it, whichever of the two layers is merged first.
+//!
+//! Finally it keeps how each string value was written: double-quoted,
+//! single-quoted, as a heredoc, or as a bare word. Tooling can tell
+//! `name = foo` from `name = "foo"`, and `to_ucl_string_tracked` writes each
+//! string back the way it was read.
+
+use crate::error::Span;
+use crate::lexer::StringFormat;
+use crate::parser::UclValue;
+use crate::walk::KeyPath;
+use indexmap::{IndexMap, IndexSet};
+use std::fmt;
+
+/// Where a value was defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    /// Name of the source the value was read from, such as a file name
+    pub source_name: String,
+    /// Span of the key that defined the value
+    pub span: Span,
+}
+
+impl Origin {
+    /// Creates a new origin
+    pub fn new(source_name: impl Into<String>, span: Span) -> Self {
+        Self {
+            source_name: source_name.into(),
+            span,
+        }
+    }
+}
+
+/// Renders as `source_name:line`
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source_name, self.span.start.line)
+    }
+}
+
+/// Current origin of a path and the origins it replaced, most recent first
+#[derive(Debug, Clone, PartialEq)]
+struct OriginRecord {
+    origin: Origin,
+    overridden: Vec<Origin>,
+}
+
+/// Origins of the entries in a document, keyed by key path
+///
+/// Array elements are addressed by their index, so the `port` key of the
+/// second element of `servers` has the path `servers.1.port`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Provenance {
+    records: IndexMap<Vec<String>, OriginRecord>,
+    priorities: IndexMap<Vec<String>, u8>,
+    appends: IndexSet<Vec<String>>,
+    string_formats: IndexMap<Vec<String>, StringFormat>,
+}
+
+impl Provenance {
+    /// Creates an empty provenance table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the origin of the value at a path such as `servers[1].port`
+    ///
+    /// Paths are read with `KeyPath::parse`; an invalid path has no origin.
+    pub fn origin(&self, path: &str) -> Option<&Origin> {
+        self.records
+            .get(&KeyPath::parse(path)?.into_keys())
+            .map(|record| &record.origin)
+    }
+
+    /// Returns the origins replaced by the current value at a path, most recent first
+    pub fn overridden(&self, path: &str) -> &[Origin] {
+        KeyPath::parse(path)
+            .and_then(|path| self.records.get(&path.into_keys()))
+            .map_or(&[], |record| &record.overridden)
+    }
+
+    /// Returns the origin of the nearest recorded ancestor of a path, including the path itself
+    pub(crate) fn nearest_origin(&self, path: &[String]) -> Option<&Origin> {
+        (1..=path.len())
+            .rev()
+            .find_map(|len| self.records.get(&path[..len]))
+            .map(|record| &record.origin)
+    }
+
+    /// Returns the merge priority of the value at a path
+    ///
+    /// Values inherit the priority of their nearest ancestor, and the empty
+    /// path is the document root. Without any priority set, or for an
+    /// invalid path, the result is 0.
+    pub fn priority(&self, path: &str) -> u8 {
+        KeyPath::parse(path).map_or(0, |path| self.priority_of(&path.into_keys()))
+    }
+
+    /// Sets the merge priority of the value at a path and everything below it
+    ///
+    /// An invalid path is ignored.
+    pub fn set_priority(&mut self, path: &str, priority: u8) {
+        if let Some(path) = KeyPath::parse(path) {
+            self.priorities.insert(path.into_keys(), priority);
+        }
+    }
+
+    /// Returns the priority of the nearest ancestor of a path, including the path itself
+    fn priority_of(&self, path: &[String]) -> u8 {
+        (0..=path.len())
+            .rev()
+            .find_map(|len| self.priorities.get(&path[..len]))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Returns true if the value at a path was only written with `+=`
+    ///
+    /// Merging layers appends such a value to the one below it.
+    pub fn is_append(&self, path: &str) -> bool {
+        KeyPath::parse(path).is_some_and(|path| self.appends.contains(&path.into_keys()))
+    }
+
+    /// Returns how the string at a path was written
+    ///
+    /// Only string values have a format; for any other path the result is `None`.
+    pub fn string_format(&self, path: &str) -> Option<StringFormat> {
+        self.string_formats
+            .get(&KeyPath::parse(path)?.into_keys())
+            .copied()
+    }
+
+    /// Returns how the string at a path was written, by segments
+    pub(crate) fn string_format_of(&self, path: &[String]) -> Option<StringFormat> {
+        self.string_formats.get(path).copied()
+    }
+
+    /// Returns the number of recorded paths
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns true if no origins were recorded
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Records whether an entry appends to its path, before its origin is recorded
+    ///
+    /// A path stays an append only while every entry written to it is one.
+    pub(crate) fn record_assignment(&mut self, path: Vec<String>, append: bool) {
+        if !append {
+            self.appends.shift_remove(&path);
+        } else if !self.records.contains_key(&path) {
+            self.appends.insert(path);
+        }
+    }
+
+    /// Records how the string value at a path was written
+    pub(crate) fn record_string_format(&mut self, path: Vec<String>, format: StringFormat) {
+        self.string_formats.insert(path, format);
+    }
+
+    /// Records the origin of a path, keeping any previous origin as overridden
+    pub(crate) fn record(&mut self, path: Vec<String>, origin: Origin) {
+        match self.records.get_mut(&path) {
+            Some(record) => {
+                let previous = std::mem::replace(&mut record.origin, origin);
+                record.overridden.insert(0, previous);
+            }
+            None => {
+                self.records.insert(
+                    path,
+                    OriginRecord {
+                        origin,
+                        overridden: Vec::new(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Source locations of a deserialized document, for reporting errors found after deserialization
+///
+/// Produced by `from_str_with_source_map`. Paths are dotted key paths as in
+/// `Provenance`; a path without an entry of its own, such as a scalar array
+/// element, resolves to its nearest enclosing entry.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use ucl_lexer::from_str_with_source_map;
+///
+/// #[derive(Deserialize)]
+/// struct Server {
+///     port: u16,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     server: Server,
+/// }
+///
+/// let (config, source_map) =
+///     from_str_with_source_map::<Config>("server {\n  port = 80\n}\n")?;
+/// if config.server.port <= 1024 {
+///     let (line, column) = source_map.line_col_for_path("server.port").unwrap();
+///     assert_eq!((line, column), (2, 3));
+/// }
+/// # Ok::<(), ucl_lexer::UclError>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    provenance: Provenance,
+}
+
+impl SourceMap {
+    /// Returns the span of the key that defined the value at a path
+    ///
+    /// Paths are read with `KeyPath::parse`, so `servers[1].port` and
+    /// `hosts."example.com"` address an array element and a key with a dot.
+    /// Paths without a key of their own, such as a missing key or a scalar
+    /// array element, have no span; see `closest_span_for_path`.
+    pub fn span_for_path(&self, path: &str) -> Option<Span> {
+        self.provenance
+            .origin(path)
+            .map(|origin| origin.span.clone())
+    }
+
+    /// Returns the span of the key that defined the value at a path or its nearest ancestor
+    ///
+    /// A scalar array element resolves to the key of its array.
+    pub fn closest_span_for_path(&self, path: &str) -> Option<Span> {
+        self.provenance
+            .nearest_origin(&KeyPath::parse(path)?.into_keys())
+            .map(|origin| origin.span.clone())
+    }
+
+    /// Returns the 1-based line and column of the key that defined the value at a path
+    pub fn line_col_for_path(&self, path: &str) -> Option<(usize, usize)> {
+        self.span_for_path(path)
+            .map(|span| (span.start.line, span.start.column))
+    }
+
+    /// Returns how the string at a path was written
+    ///
+    /// Unlike spans, formats are not inherited: only a string value has one.
+    pub fn string_format_for_path(&self, path: &str) -> Option<StringFormat> {
+        self.provenance.string_format(path)
+    }
+
+    /// Returns the origins the map was built from
+    pub fn provenance(&self) -> &Provenance {
+        &self.provenance
+    }
+}
+
+impl From<Provenance> for SourceMap {
+    fn from(provenance: Provenance) -> Self {
+        Self { provenance }
+    }
+}
+
+/// A parsed value together with the origins of its entries
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrackedValue {
+    /// The parsed value
+    pub value: UclValue,
+    /// Origins of the value's entries
+    pub provenance: Provenance,
+}
+
+impl TrackedValue {
+    /// Creates a tracked value
+    pub fn new(value: UclValue, provenance: Provenance) -> Self {
+        Self { value, provenance }
+    }
+
+    /// Returns the origin of the value at a path
+    pub fn origin(&self, path: &str) -> Option<&Origin> {
+        self.provenance.origin(path)
+    }
+
+    /// Returns the merge priority of the value at a path
+    pub fn priority(&self, path: &str) -> u8 {
+        self.provenance.priority(path)
+    }
+
+    /// Sets the merge priority of the value at a path and everything below it
+    pub fn set_priority(&mut self, path: &str, priority: u8) {
+        self.provenance.set_priority(path, priority);
+    }
+
+    /// Returns how the string at a path was written
+    pub fn string_format(&self, path: &str) -> Option<StringFormat> {
+        self.provenance.string_format(path)
+    }
+
+    /// Merges a later layer into this one, keeping the origin of whichever value won
+    ///
+    /// Values are merged like `UclValue::merge_from`. Objects present in both
+    /// layers are merged key by key; every other value from `other` replaces
+    /// the current one and records it as overridden, unless the current value
+    /// has a higher priority. Replaced values take their priority with them.
+    ///
+    /// A value written with `+=` is appended to the value below it, as `+=`
+    /// does within a document, and lies above a value written with `=` at the
+    /// same priority whichever is merged first. It replaces values it cannot
+    /// be appended to. When several layers append to a path, merge them in
+    /// order of priority so that each one lands on the layers below it.
+    pub fn merge_from(&mut self, other: TrackedValue) {
+        let TrackedValue { value, provenance } = other;
+
+        // Objects defined in both layers keep their original definition
+        let shared_objects: Vec<bool> = provenance
+            .records
+            .keys()
+            .map(|path| {
+                self.value
+                    .get_segments(path)
+                    .is_some_and(UclValue::is_object)
+                    && value.get_segments(path).is_some_and(UclValue::is_object)
+            })
+            .collect();
+
+        let mut outcome = MergeOutcome::default();
+        merge_layer(
+            &mut self.value,
+            value,
+            &mut Vec::new(),
+            (&self.provenance, &provenance),
+            &mut outcome,
+        );
+
+        for path in &outcome.placed {
+            let priority = provenance.priority_of(path);
+            self.provenance
+                .priorities
+                .retain(|existing, _| !existing.starts_with(path));
+            if self.provenance.priority_of(path) != priority {
+                self.provenance.priorities.insert(path.clone(), priority);
+            }
+            for (nested, priority) in &provenance.priorities {
+                if nested.len() > path.len() && nested.starts_with(path) {
+                    self.provenance.priorities.insert(nested.clone(), *priority);
+                }
+            }
+            self.provenance
+                .appends
+                .retain(|existing| !existing.starts_with(path));
+            for nested in &provenance.appends {
+                if nested.starts_with(path) {
+                    self.provenance.appends.insert(nested.clone());
+                }
+            }
+            self.provenance
+                .string_formats
+                .retain(|existing, _| !existing.starts_with(path));
+            for (nested, format) in &provenance.string_formats {
+                if nested.starts_with(path) {
+                    self.provenance
+                        .string_formats
+                        .insert(nested.clone(), *format);
+                }
+            }
+        }
+        // A value appended to another keeps whether the lower one was an append
+        for (path, append) in outcome.appends {
+            if append {
+                self.provenance.appends.insert(path);
+            } else {
+                self.provenance.appends.shift_remove(&path);
+            }
+        }
+
+        for ((path, record), both_objects) in provenance.records.into_iter().zip(shared_objects) {
+            if outcome
+                .rejected
+                .iter()
+                .any(|rejected| path.starts_with(rejected))
+            {
+                continue;
+            }
+            match self.provenance.records.get_mut(&path) {
+                Some(_) if both_objects => {}
+                Some(existing) => {
+                    let previous = std::mem::replace(existing, record);
+                    existing.overridden.push(previous.origin);
+                    existing.overridden.extend(previous.overridden);
+                }
+                None => {
+                    self.provenance.records.insert(path, record);
+                }
+            }
+        }
+
+        // Drop origins of entries that were replaced together with their parent
+        let merged = &self.value;
+        self.provenance
+            .records
+            .retain(|path, _| merged.get_segments(path).is_some());
+        self.provenance
+            .string_formats
+            .retain(|path, _| merged.get_segments(path).is_some_and(UclValue::is_string));
+    }
+
+    /// Describes the value at a path and where it came from
+    ///
+    /// For example `server.port = 9090 (from local.ucl:12, overriding site.ucl:44)`.
+    pub fn explain(&self, path: &str) -> String {
+        let Some(value) = self.value.get_path(path) else {
+            return format!("{} is not defined", path);
+        };
+
+        let record =
+            KeyPath::parse(path).and_then(|keys| self.provenance.records.get(&keys.into_keys()));
+        let Some(record) = record else {
+            return format!("{} = {}", path, value);
+        };
+
+        let mut explanation = format!("{} = {} (from {}", path, value, record.origin);
+        if !record.overridden.is_empty() {
+            let overridden: Vec<String> = record.overridden.iter().map(Origin::to_string).collect();
+            explanation.push_str(", overriding ");
+            explanation.push_str(&overridden.join(", "));
+        }
+        explanation.push(')');
+        explanation
+    }
+}
+
+/// Paths touched by `merge_layer`
+#[derive(Default)]
+struct MergeOutcome {
+    /// Paths that took the value of the later layer
+    placed: Vec<Vec<String>>,
+    /// Paths that kept their value because it had a higher priority
+    rejected: Vec<Vec<String>>,
+    /// Paths where one value was appended to the other, with whether the result is an append
+    appends: Vec<(Vec<String>, bool)>,
+}
+
+/// Merges `source` into `target`, keeping target values of higher priority
+///
+/// `layers` holds the provenance of the target and the source.
+fn merge_layer(
+    target: &mut UclValue,
+    mut source: UclValue,
+    path: &mut Vec<String>,
+    layers: (&Provenance, &Provenance),
+    outcome: &mut MergeOutcome,
+) {
+    match (target, &mut source) {
+        (UclValue::Object(target), UclValue::Object(entries)) => {
+            for (key, value) in std::mem::take(entries) {
+                path.push(key.to_string());
+                match target.get_mut(&key) {
+                    Some(existing) => merge_layer(existing, value, path, layers, outcome),
+                    None => {
+                        target.insert(key, value);
+                        outcome.placed.push(path.clone());
+                    }
+                }
+                path.pop();
+            }
+        }
+        (target, _) => {
+            let appends = (
+                layers.0.appends.contains(path.as_slice()),
+                layers.1.appends.contains(path.as_slice()),
+            );
+            // An append lies above a value of the same priority, whatever the merge order
+            let priorities = (layers.0.priority_of(path), layers.1.priority_of(path));
+            if priorities.1 < priorities.0
+                || (priorities.1 == priorities.0 && appends.0 && !appends.1)
+            {
+                // The source lies below the target
+                if appends.0 {
+                    let above = std::mem::replace(target, source);
+                    if append_value(target, above.clone()) {
+                        outcome.appends.push((path.clone(), appends.1));
+                    } else {
+                        *target = above;
+                    }
+                }
+                outcome.rejected.push(path.clone());
+            } else {
+                if !(appends.1 && append_value(target, source.clone())) {
+                    *target = source;
+                } else {
+                    outcome.appends.push((path.clone(), appends.0));
+                }
+                outcome.placed.push(path.clone());
+            }
+        }
+    }
+}
+
+/// Appends `above` to `below` as `+=` does, returning false if they cannot be combined
+///
+/// Arrays gain the new elements and any other value is pushed onto an array.
+/// Objects never get here, as they are merged key by key.
+fn append_value(below: &mut UclValue, mut above: UclValue) -> bool {
+    match (below, &mut above) {
+        (UclValue::Array(items), UclValue::Array(new_items)) => {
+            items.extend(std::mem::take(&mut **new_items))
+        }
+        (UclValue::Array(items), _) => items.push(above),
+        _ => return false,
+    }
+    true
+}
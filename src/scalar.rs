@@ -0,0 +1,189 @@
+//! Parsing of single scalars outside a document
+//!
+//! Command-line flags and environment variables often carry the same values
+//! as a config file, such as `--max-mem 2gb` or `--timeout 1h30m`. These
+//! functions read one standalone literal with the lexer's rules, so a value
+//! means the same on the command line as in the file. Whitespace around the
+//! literal is ignored; anything else after it is an error at its column.
+//!
+//! ```rust
+//! use ucl_lexer::scalar::{parse_bool, parse_duration_secs, parse_size};
+//!
+//! assert_eq!(parse_size("2gb")?, 2 * 1024 * 1024 * 1024);
+//! assert_eq!(parse_duration_secs("1h30m")?, 5400.0);
+//! assert!(parse_bool("on")?);
+//!
+//! let error = parse_size("10kb extra").unwrap_err();
+//! assert_eq!(error.to_string(), "Unexpected 'extra' after the value at 1:6");
+//! # Ok::<(), ucl_lexer::LexError>(())
+//! ```
+
+use crate::error::{LexError, Position};
+use crate::lexer::{LexerConfig, MSuffix, Token, UclLexer, boolean_keyword};
+use crate::parser::UclValue;
+
+/// Reads a scalar as the value of a document entry would be read
+///
+/// Numbers, booleans, `null` and strings become the matching value, and
+/// `yes`, `no`, `on` and `off` are booleans. As in a document, a duration
+/// such as `30s` stays the string `"30s"`; use `parse_duration_secs` to read
+/// its seconds. Quoted strings are not expanded.
+pub fn parse_scalar(text: &str) -> Result<UclValue, LexError> {
+    let mut lexer = UclLexer::new(text);
+    let (token, start) = single_token(&mut lexer, text)?;
+    Ok(match token {
+        Token::Integer(value) => UclValue::Integer(value),
+        Token::Float(value) => UclValue::Float(value),
+        Token::Time(_) => UclValue::String(text.trim().to_string()),
+        Token::Boolean(value) => UclValue::Boolean(value),
+        Token::Null => UclValue::Null,
+        Token::Key(word) => match boolean_keyword(&word) {
+            Some(value) => UclValue::Boolean(value),
+            None => UclValue::String(word.into_owned()),
+        },
+        Token::String { value, .. } => UclValue::String(value.into_owned()),
+        _ => return Err(invalid("a scalar", token_text(text, start), start)),
+    })
+}
+
+/// Reads a size in bytes, such as `512`, `64kb` or `2gb`
+///
+/// A bare `m` suffix is mega here, whatever a document's
+/// `LexerConfig::m_suffix_meaning` says.
+pub fn parse_size(text: &str) -> Result<u64, LexError> {
+    let config = LexerConfig::default().with_m_suffix_meaning(MSuffix::Size);
+    let mut lexer = UclLexer::with_config(text, config);
+    let (token, start) = single_token(&mut lexer, text)?;
+    let bytes = match token {
+        Token::Integer(bytes) => bytes as f64,
+        Token::Float(bytes) if bytes.is_finite() && bytes.fract() == 0.0 => bytes,
+        _ => return Err(invalid("a size", token_text(text, start), start)),
+    };
+    if bytes < 0.0 {
+        return Err(LexError::InvalidNumber {
+            message: "a size cannot be negative".to_string(),
+            position: start,
+        });
+    }
+    Ok(bytes as u64)
+}
+
+/// Reads a duration in seconds, such as `30`, `1.5h` or `1h30m`
+///
+/// Several units may follow each other without spaces, and a sign before
+/// the first applies to all of them. A bare `m` suffix is minutes here.
+pub fn parse_duration_secs(text: &str) -> Result<f64, LexError> {
+    let config = LexerConfig::default().with_m_suffix_meaning(MSuffix::Minutes);
+    let unit_seconds = |part: &str| {
+        let mut lexer = UclLexer::with_config(part, config.clone());
+        match single_token(&mut lexer, part) {
+            Ok((Token::Time(seconds), _)) => Some(seconds),
+            _ => None,
+        }
+    };
+    // A unit may end wherever a letter is followed by a digit
+    let bytes = text.as_bytes();
+    let ends: Vec<usize> = (1..bytes.len())
+        .filter(|&i| bytes[i - 1].is_ascii_alphabetic() && bytes[i].is_ascii_digit())
+        .chain([bytes.len()])
+        .collect();
+    let next_unit = |start: usize| {
+        ends.iter()
+            .filter(|&&end| end > start)
+            .find_map(|&end| Some((end, unit_seconds(&text[start..end])?)))
+    };
+
+    let Some((mut start, mut seconds)) = next_unit(0) else {
+        let mut lexer = UclLexer::with_config(text, config.clone());
+        let (token, start) = single_token(&mut lexer, text)?;
+        return match token {
+            Token::Integer(seconds) => Ok(seconds as f64),
+            Token::Float(seconds) if seconds.is_finite() => Ok(seconds),
+            _ => Err(invalid("a duration", token_text(text, start), start)),
+        };
+    };
+    let sign = if text.trim_start().starts_with('-') {
+        -1.0
+    } else {
+        1.0
+    };
+    while start < text.len() {
+        let Some((end, part)) = next_unit(start) else {
+            return Err(trailing(text, Position::from_offset(text, start)));
+        };
+        seconds += sign * part;
+        start = end;
+    }
+    Ok(seconds)
+}
+
+/// Reads a boolean: `true`, `yes` or `on`, or `false`, `no` or `off`, in any case
+pub fn parse_bool(text: &str) -> Result<bool, LexError> {
+    let mut lexer = UclLexer::new(text);
+    let (token, start) = single_token(&mut lexer, text)?;
+    match token {
+        Token::Boolean(value) => Ok(value),
+        Token::Key(word) => {
+            boolean_keyword(&word).ok_or_else(|| invalid("a boolean", word.into_owned(), start))
+        }
+        _ => Err(invalid("a boolean", token_text(text, start), start)),
+    }
+}
+
+/// Lexes the only token of `text`, with its start
+fn single_token<'a>(
+    lexer: &mut UclLexer<'a>,
+    text: &str,
+) -> Result<(Token<'a>, Position), LexError> {
+    let token = next_token(lexer)?;
+    finish(lexer, text)?;
+    Ok(token)
+}
+
+/// Lexes the next token, which must not be the end of input
+fn next_token<'a>(lexer: &mut UclLexer<'a>) -> Result<(Token<'a>, Position), LexError> {
+    let token = lexer.next_token()?;
+    let start = lexer.last_token_start();
+    if token == Token::Eof {
+        return Err(invalid("a value", String::new(), start));
+    }
+    Ok((token, start))
+}
+
+/// Checks that nothing but whitespace follows the token just lexed
+fn finish(lexer: &mut UclLexer<'_>, text: &str) -> Result<(), LexError> {
+    let end = lexer.last_token_end();
+    match lexer.next_token() {
+        Ok(Token::Eof) => Ok(()),
+        _ => Err(trailing(text, end)),
+    }
+}
+
+/// Error for input left after the value that ends at `end`
+fn trailing(text: &str, end: Position) -> LexError {
+    let rest = &text[end.offset..];
+    let offset = end.offset + rest.len() - rest.trim_start().len();
+    let position = Position::from_offset(text, offset);
+    LexError::TrailingInput {
+        text: token_text(text, position),
+        position,
+    }
+}
+
+/// Error for a token that is not the kind of scalar asked for
+fn invalid(expected: &'static str, found: String, position: Position) -> LexError {
+    LexError::InvalidScalar {
+        expected,
+        found,
+        position,
+    }
+}
+
+/// Returns the word of `text` at `position`, up to the next whitespace
+fn token_text(text: &str, position: Position) -> String {
+    let rest = text.get(position.offset..).unwrap_or_default();
+    rest.split(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
@@ -0,0 +1,602 @@
+//! UCL text output
+//!
+//! Renders a `UclValue` as configuration-style UCL that the parser reads back
+//! into the same value. Output only depends on the value and the
+//! `FormatConfig`, so identical input always yields identical bytes.
+//!
+//! [`UclWriter`] writes the same text entry by entry into an `io::Write`,
+//! for documents too large to build as a `UclValue` first.
+//!
+//! [`to_ucl_string_tracked`] also keeps how each string was written in the
+//! source, as recorded by provenance tracking: bare words stay bare and
+//! single-quoted strings and heredocs keep their form, as long as the text
+//! reads back as the same string.
+
+use crate::error::WriteError;
+use crate::lexer::StringFormat;
+use crate::parser::{
+    TimeSuffixStyle, UclObject, UclValue, format_time, write_float, write_quoted_str,
+};
+use crate::provenance::{Provenance, TrackedValue};
+use std::borrow::Cow;
+use std::fmt::{self, Write};
+use std::io;
+
+/// Words the lexer reads as keywords, which must be quoted when used as keys
+const RESERVED_KEYS: [&str; 10] = [
+    "true", "false", "yes", "no", "on", "off", "null", "inf", "infinity", "nan",
+];
+
+/// Formatting options for UCL output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatConfig {
+    /// Number of spaces per nesting level
+    pub indent: usize,
+    /// Emit object keys in sorted order instead of insertion order
+    pub sort_keys: bool,
+    /// How time values are written
+    pub time_style: TimeSuffixStyle,
+}
+
+impl FormatConfig {
+    /// Creates a format configuration with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of spaces per nesting level
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Sets whether object keys are sorted
+    pub fn with_sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets how time values are written
+    pub fn with_time_style(mut self, style: TimeSuffixStyle) -> Self {
+        self.time_style = style;
+        self
+    }
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self {
+            indent: 4,
+            sort_keys: false,
+            time_style: TimeSuffixStyle::Integral,
+        }
+    }
+}
+
+/// Renders a value as UCL text with the default format configuration
+pub fn to_ucl_string(value: &UclValue) -> String {
+    to_ucl_string_with_config(value, &FormatConfig::default())
+}
+
+/// Renders a value as UCL text
+///
+/// A top-level object is written as a braceless document of `key = value;`
+/// lines; any other value is written as a single expression.
+pub fn to_ucl_string_with_config(value: &UclValue, config: &FormatConfig) -> String {
+    render_document(value, config, None)
+}
+
+/// Renders a tracked value as UCL text, writing strings the way they were read
+///
+/// A string recorded as a bare word is written bare, and one recorded as
+/// single-quoted or as a heredoc keeps that form. A string whose text no
+/// longer fits its form, such as a bare word that now contains a space, is
+/// written double-quoted like any string without a recorded format.
+///
+/// ```rust
+/// use ucl_lexer::{FormatConfig, ParserConfig, UclParser, to_ucl_string_tracked};
+///
+/// let input = "name = web;\npath = '/srv/www';\n";
+/// let tracked = UclParser::new(input)
+///     .with_config(ParserConfig::new().with_track_provenance(true))
+///     .parse_tracked()?;
+/// assert_eq!(to_ucl_string_tracked(&tracked, &FormatConfig::default()), input);
+/// # Ok::<(), ucl_lexer::ParseError>(())
+/// ```
+pub fn to_ucl_string_tracked(tracked: &TrackedValue, config: &FormatConfig) -> String {
+    render_document(&tracked.value, config, Some(&tracked.provenance))
+}
+
+fn render_document(
+    value: &UclValue,
+    config: &FormatConfig,
+    formats: Option<&Provenance>,
+) -> String {
+    let mut writer = Renderer {
+        output: String::new(),
+        config,
+        formats,
+        path: Vec::new(),
+    };
+    // Writing into a String cannot fail
+    let _ = match value {
+        UclValue::Object(obj) => writer.write_entries(obj, 0),
+        other => writer
+            .write_value(other, 0)
+            .and_then(|()| writer.output.write_char('\n')),
+    };
+    writer.output
+}
+
+/// Renders a value as a single expression, with nested values indented from column 0
+pub(crate) fn render_value(value: &UclValue, config: &FormatConfig) -> String {
+    let mut writer = Renderer {
+        output: String::new(),
+        config,
+        formats: None,
+        path: Vec::new(),
+    };
+    // Writing into a String cannot fail
+    let _ = writer.write_value(value, 0);
+    writer.output
+}
+
+/// Writes a key bare when the lexer reads it back unchanged, quoted otherwise
+fn write_key(f: &mut impl fmt::Write, key: &str) -> fmt::Result {
+    let bare = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !RESERVED_KEYS.contains(&key.to_ascii_lowercase().as_str());
+    if bare {
+        f.write_str(key)
+    } else {
+        write_string(f, key)
+    }
+}
+
+/// Writes a quoted string, doubling `$` so that variable expansion restores it
+fn write_string(f: &mut impl fmt::Write, s: &str) -> fmt::Result {
+    if s.contains('$') {
+        write_quoted_str(f, &s.replace('$', "$$"))
+    } else {
+        write_quoted_str(f, s)
+    }
+}
+
+/// Returns true if a bare word reads back as the string `s`
+///
+/// Allows the characters of a key plus `-`, `.` and `/`, which bare values
+/// commonly hold, such as hostnames and paths. A path may start with `/`,
+/// but not with `//`, which starts a comment.
+fn is_bare_value(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '/')
+        && !s.starts_with("//")
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'))
+        && !s.contains("/*")
+        && !RESERVED_KEYS.contains(&s.to_ascii_lowercase().as_str())
+}
+
+/// Writes a single-quoted string if it reads back unchanged, returning whether it did
+///
+/// A backslash could start an escape, so strings holding one are left to
+/// the caller.
+fn write_single_quoted(f: &mut impl fmt::Write, s: &str) -> Result<bool, fmt::Error> {
+    if s.contains(['\\', '\r']) {
+        return Ok(false);
+    }
+    write!(f, "'{}'", s.replace('\'', "\\'"))?;
+    Ok(true)
+}
+
+/// Writes a heredoc if it reads back unchanged, returning whether it did
+///
+/// A heredoc ends with a line break, so only such strings fit. The
+/// terminator is `EOD`, numbered if the text has a line of that name, and
+/// quoted when the text holds a `$` so that nothing is expanded.
+fn write_heredoc(f: &mut impl fmt::Write, s: &str) -> Result<bool, fmt::Error> {
+    if !(s.is_empty() || s.ends_with('\n')) || s.contains('\r') {
+        return Ok(false);
+    }
+    let mut terminator = String::from("EOD");
+    let mut suffix = 0;
+    while s.lines().any(|line| line == terminator) {
+        suffix += 1;
+        terminator = format!("EOD_{}", suffix);
+    }
+    if s.contains('$') {
+        write!(f, "<<'{}'\n{}{}\n", terminator, s, terminator)?;
+    } else {
+        write!(f, "<<{}\n{}{}\n", terminator, s, terminator)?;
+    }
+    Ok(true)
+}
+
+struct Renderer<'c> {
+    output: String,
+    config: &'c FormatConfig,
+    /// Recorded string formats, when strings are written the way they were read
+    formats: Option<&'c Provenance>,
+    /// Key path of the value being written
+    path: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn write_indent(&mut self, depth: usize) -> fmt::Result {
+        for _ in 0..depth * self.config.indent {
+            self.output.write_char(' ')?;
+        }
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &str) -> fmt::Result {
+        write_key(&mut self.output, key)
+    }
+
+    fn write_entries(&mut self, obj: &UclObject, depth: usize) -> fmt::Result {
+        let mut entries: Vec<_> = obj.iter().collect();
+        if self.config.sort_keys {
+            entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        }
+
+        for (key, value) in entries {
+            self.write_indent(depth)?;
+            self.write_key(key)?;
+            self.path.push(key.to_string());
+            match value {
+                UclValue::Object(_) => {
+                    self.output.write_char(' ')?;
+                    self.write_value(value, depth)?;
+                    self.output.write_char('\n')?;
+                }
+                _ => {
+                    self.output.write_str(" = ")?;
+                    self.write_value(value, depth)?;
+                    self.output.write_str(";\n")?;
+                }
+            }
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    /// Writes a string in its recorded format when it still fits, quoted otherwise
+    ///
+    /// A heredoc ends its line, so what follows it is indented to `depth`.
+    fn write_string(&mut self, s: &str, depth: usize) -> fmt::Result {
+        let format = self
+            .formats
+            .and_then(|formats| formats.string_format_of(&self.path));
+        match format {
+            Some(StringFormat::Unquoted) if is_bare_value(s) => self.output.write_str(s),
+            Some(StringFormat::Single) if write_single_quoted(&mut self.output, s)? => Ok(()),
+            Some(StringFormat::Heredoc) if write_heredoc(&mut self.output, s)? => {
+                self.write_indent(depth)
+            }
+            _ => write_string(&mut self.output, s),
+        }
+    }
+
+    fn write_value(&mut self, value: &UclValue, depth: usize) -> fmt::Result {
+        match value {
+            UclValue::String(s) => self.write_string(s, depth),
+            UclValue::Integer(i) => write!(self.output, "{}", i),
+            UclValue::Float(f) => write_float(&mut self.output, *f),
+            UclValue::Decimal(text) => self.output.write_str(text),
+            UclValue::Time(seconds) => self
+                .output
+                .write_str(&format_time(*seconds, self.config.time_style)),
+            UclValue::Boolean(b) => write!(self.output, "{}", b),
+            UclValue::Null => self.output.write_str("null"),
+            UclValue::Object(obj) if obj.is_empty() => self.output.write_str("{}"),
+            UclValue::Object(obj) => {
+                self.output.write_str("{\n")?;
+                self.write_entries(obj, depth + 1)?;
+                self.write_indent(depth)?;
+                self.output.write_char('}')
+            }
+            UclValue::Array(arr) if arr.is_empty() => self.output.write_str("[]"),
+            UclValue::Array(arr) => {
+                self.output.write_str("[\n")?;
+                for (index, element) in arr.iter().enumerate() {
+                    self.write_indent(depth + 1)?;
+                    self.path.push(index.to_string());
+                    self.write_value(element, depth + 1)?;
+                    self.path.pop();
+                    self.output.write_str(",\n")?;
+                }
+                self.write_indent(depth)?;
+                self.output.write_char(']')
+            }
+        }
+    }
+}
+
+/// A scalar written by [`UclWriter::write_scalar`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum UclScalar<'a> {
+    /// Quoted string, escaped as needed
+    String(Cow<'a, str>),
+    /// Integer
+    Integer(i64),
+    /// Float, always written so that it reads back as a float
+    Float(f64),
+    /// Time in seconds, written with a suffix
+    Time(f64),
+    /// Boolean
+    Boolean(bool),
+    /// Null
+    Null,
+}
+
+impl<'a> From<&'a str> for UclScalar<'a> {
+    fn from(value: &'a str) -> Self {
+        UclScalar::String(Cow::Borrowed(value))
+    }
+}
+
+impl From<String> for UclScalar<'_> {
+    fn from(value: String) -> Self {
+        UclScalar::String(Cow::Owned(value))
+    }
+}
+
+impl From<i64> for UclScalar<'_> {
+    fn from(value: i64) -> Self {
+        UclScalar::Integer(value)
+    }
+}
+
+impl From<i32> for UclScalar<'_> {
+    fn from(value: i32) -> Self {
+        UclScalar::Integer(value.into())
+    }
+}
+
+impl From<u32> for UclScalar<'_> {
+    fn from(value: u32) -> Self {
+        UclScalar::Integer(value.into())
+    }
+}
+
+impl From<f64> for UclScalar<'_> {
+    fn from(value: f64) -> Self {
+        UclScalar::Float(value)
+    }
+}
+
+impl From<bool> for UclScalar<'_> {
+    fn from(value: bool) -> Self {
+        UclScalar::Boolean(value)
+    }
+}
+
+/// Open object or array of a [`UclWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Object,
+    Array,
+}
+
+impl Container {
+    fn name(self) -> &'static str {
+        match self {
+            Container::Object => "object",
+            Container::Array => "array",
+        }
+    }
+}
+
+/// Writes UCL text entry by entry, without building a `UclValue`
+///
+/// The output has the layout of [`to_ucl_string_with_config`] and parses back
+/// into the values written. Entries of the document and of objects need a key, array
+/// elements must not have one. Apart from the output, the writer only keeps
+/// the stack of open objects and arrays. `FormatConfig::sort_keys` has no
+/// effect, since entries are written in the order they are given.
+///
+/// ```rust
+/// use ucl_lexer::UclWriter;
+///
+/// let mut writer = UclWriter::new(Vec::new());
+/// writer.write_comment("generated")?;
+/// writer.begin_object("server")?;
+/// writer.write_scalar("port", 8080)?;
+/// writer.begin_array("hosts")?;
+/// writer.write_scalar(None, "a.example")?;
+/// writer.end_array()?;
+/// writer.end_object()?;
+/// let output = String::from_utf8(writer.finish()?).unwrap();
+///
+/// assert_eq!(
+///     output,
+///     "# generated\nserver {\n    port = 8080;\n    hosts = [\n        \"a.example\",\n    ];\n}\n"
+/// );
+/// let value = ucl_lexer::UclParser::new(&output).parse_document()?;
+/// assert_eq!(value["server"]["hosts"][0], "a.example");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub struct UclWriter<W: io::Write> {
+    output: W,
+    config: FormatConfig,
+    stack: Vec<Container>,
+}
+
+impl<W: io::Write> UclWriter<W> {
+    /// Creates a writer with the default format configuration
+    pub fn new(output: W) -> Self {
+        Self::with_config(output, FormatConfig::default())
+    }
+
+    /// Creates a writer with a format configuration
+    pub fn with_config(output: W, config: FormatConfig) -> Self {
+        Self {
+            output,
+            config,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Returns the number of objects and arrays currently open
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Opens an object; `key` is `None` for an array element
+    pub fn begin_object<'k>(&mut self, key: impl Into<Option<&'k str>>) -> Result<(), WriteError> {
+        self.write_entry_start(key.into())?;
+        // Objects are assigned without '=', as `key { ... }`
+        let open = if self.in_array() { "{\n" } else { " {\n" };
+        self.write_text(|f| f.write_str(open))?;
+        self.stack.push(Container::Object);
+        Ok(())
+    }
+
+    /// Closes the innermost object
+    pub fn end_object(&mut self) -> Result<(), WriteError> {
+        self.end(Container::Object, '}')
+    }
+
+    /// Opens an array; `key` is `None` for an array element
+    pub fn begin_array<'k>(&mut self, key: impl Into<Option<&'k str>>) -> Result<(), WriteError> {
+        self.write_entry_start(key.into())?;
+        let open = if self.in_array() { "[\n" } else { " = [\n" };
+        self.write_text(|f| f.write_str(open))?;
+        self.stack.push(Container::Array);
+        Ok(())
+    }
+
+    /// Closes the innermost array
+    pub fn end_array(&mut self) -> Result<(), WriteError> {
+        self.end(Container::Array, ']')
+    }
+
+    /// Writes a scalar; `key` is `None` for an array element
+    pub fn write_scalar<'k, 'v>(
+        &mut self,
+        key: impl Into<Option<&'k str>>,
+        value: impl Into<UclScalar<'v>>,
+    ) -> Result<(), WriteError> {
+        self.write_entry_start(key.into())?;
+        let in_array = self.in_array();
+        let time_style = self.config.time_style;
+        self.write_text(|f| {
+            if !in_array {
+                f.write_str(" = ")?;
+            }
+            match value.into() {
+                UclScalar::String(s) => write_string(f, &s)?,
+                UclScalar::Integer(i) => write!(f, "{}", i)?,
+                UclScalar::Float(x) => write_float(f, x)?,
+                UclScalar::Time(seconds) => f.write_str(&format_time(seconds, time_style))?,
+                UclScalar::Boolean(b) => write!(f, "{}", b)?,
+                UclScalar::Null => f.write_str("null")?,
+            }
+            f.write_str(if in_array { ",\n" } else { ";\n" })
+        })
+    }
+
+    /// Writes a `#` comment at the current indentation, one per line of `text`
+    pub fn write_comment(&mut self, text: &str) -> Result<(), WriteError> {
+        let indent = self.stack.len() * self.config.indent;
+        self.write_text(|f| {
+            for line in text.lines() {
+                write!(f, "{:indent$}#", "")?;
+                if !line.is_empty() {
+                    write!(f, " {}", line)?;
+                }
+                f.write_char('\n')?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Writes text unchanged, without indentation or validation
+    pub fn write_raw(&mut self, text: &str) -> Result<(), WriteError> {
+        Ok(self.output.write_all(text.as_bytes())?)
+    }
+
+    /// Checks that everything was closed, flushes and returns the output
+    pub fn finish(mut self) -> Result<W, WriteError> {
+        if !self.stack.is_empty() {
+            return Err(WriteError::Unclosed {
+                depth: self.stack.len(),
+            });
+        }
+        self.output.flush()?;
+        Ok(self.output)
+    }
+
+    fn in_array(&self) -> bool {
+        self.stack.last() == Some(&Container::Array)
+    }
+
+    /// Checks the key against the enclosing container and writes indentation and key
+    fn write_entry_start(&mut self, key: Option<&str>) -> Result<(), WriteError> {
+        let indent = self.stack.len() * self.config.indent;
+        match (key, self.in_array()) {
+            (None, false) => Err(WriteError::MissingKey),
+            (Some(key), true) => Err(WriteError::KeyInArray {
+                key: key.to_string(),
+            }),
+            (key, _) => self.write_text(|f| {
+                write!(f, "{:indent$}", "")?;
+                key.map_or(Ok(()), |key| write_key(f, key))
+            }),
+        }
+    }
+
+    fn end(&mut self, container: Container, close: char) -> Result<(), WriteError> {
+        if self.stack.last() != Some(&container) {
+            return Err(WriteError::NotOpen {
+                container: container.name(),
+            });
+        }
+        self.stack.pop();
+        let indent = self.stack.len() * self.config.indent;
+        let terminator = match (container, self.in_array()) {
+            (_, true) => ",",
+            (Container::Object, false) => "",
+            (Container::Array, false) => ";",
+        };
+        self.write_text(|f| writeln!(f, "{:indent$}{}{}", "", close, terminator))
+    }
+
+    /// Runs formatting code against the output, keeping the I/O error it hits
+    fn write_text(
+        &mut self,
+        write: impl FnOnce(&mut IoFormatter<'_, W>) -> fmt::Result,
+    ) -> Result<(), WriteError> {
+        let mut formatter = IoFormatter {
+            output: &mut self.output,
+            error: None,
+        };
+        match write(&mut formatter) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(formatter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatting failed"))
+                .into()),
+        }
+    }
+}
+
+/// Adapts an `io::Write` to the `fmt::Write` used by the formatting helpers
+struct IoFormatter<'w, W> {
+    output: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoFormatter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.output.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
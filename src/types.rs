@@ -0,0 +1,327 @@
+//! Newtypes for sizes and durations written with suffixes
+//!
+//! A `u64` field accepts `512mb`, but its type does not say that the value is
+//! a size. [`ByteSize`] and [`Seconds`] deserialize from numbers, suffixed
+//! literals and strings such as `"512mb"` or `"1h30min"`, and display
+//! themselves with suffixes again. Human-readable serializers receive that
+//! text, others the plain number.
+
+use crate::lexer::{LexerConfig, MSuffix, Token, lex_single_token};
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde::ser::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Size suffixes used for display, largest first
+///
+/// The meaning of single-letter suffixes depends on
+/// `LexerConfig::size_suffix_binary`, so only explicit binary forms are written.
+const SIZE_UNITS: [(&str, u64); 4] = [
+    ("tb", 1 << 40),
+    ("gb", 1 << 30),
+    ("mb", 1 << 20),
+    ("kb", 1 << 10),
+];
+
+/// Time units accepted in duration strings, in seconds
+///
+/// `m` means minutes here, since a duration has no use for the mega suffix.
+const TIME_UNITS: [(&str, f64); 8] = [
+    ("ms", 0.001),
+    ("s", 1.0),
+    ("min", 60.0),
+    ("m", 60.0),
+    ("h", 3600.0),
+    ("d", 86400.0),
+    ("w", 604800.0),
+    ("y", 31536000.0),
+];
+
+/// Time units used for display, largest first, in milliseconds
+const DISPLAY_TIME_UNITS: [(&str, u64); 5] = [
+    ("d", 86_400_000),
+    ("h", 3_600_000),
+    ("min", 60_000),
+    ("s", 1_000),
+    ("ms", 1),
+];
+
+/// A size in bytes
+///
+/// A bare `5m` is five million bytes, unless `LexerConfig::m_suffix_meaning`
+/// gives it another meaning for the whole document.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use ucl_lexer::types::ByteSize;
+///
+/// #[derive(Deserialize)]
+/// struct Cache {
+///     limit: ByteSize,
+///     chunk: ByteSize,
+/// }
+///
+/// let cache: Cache = ucl_lexer::from_str("limit = 512mb\nchunk = \"64kb\"")?;
+/// assert_eq!(cache.limit, ByteSize(512 * 1024 * 1024));
+/// assert_eq!(cache.chunk.to_string(), "64kb");
+/// # Ok::<(), ucl_lexer::UclError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    /// Returns the number of bytes
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        ByteSize(bytes)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (suffix, unit) in SIZE_UNITS {
+            if self.0 != 0 && self.0.is_multiple_of(unit) {
+                return write!(f, "{}{}", self.0 / unit, suffix);
+            }
+        }
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = String;
+
+    /// Parses a size with the lexer's suffix rules, such as `512mb` or `4k`
+    ///
+    /// A bare `m` is a size here, whatever `LexerConfig::m_suffix_meaning` says.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let config = LexerConfig::default().with_m_suffix_meaning(MSuffix::Size);
+        match lex_single_token(text, config) {
+            Some(Token::Integer(bytes)) => u64::try_from(bytes)
+                .map(ByteSize)
+                .map_err(|_| format!("size cannot be negative: {}", text.trim())),
+            _ => Err(format!("invalid size: {}", text.trim())),
+        }
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+/// Visitor reading a size from an integer or a suffixed string
+struct ByteSizeVisitor;
+
+impl<'de> Visitor<'de> for ByteSizeVisitor {
+    type Value = ByteSize;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a non-negative size such as 1024 or \"512mb\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, bytes: u64) -> Result<Self::Value, E> {
+        Ok(ByteSize(bytes))
+    }
+
+    fn visit_i64<E: de::Error>(self, bytes: i64) -> Result<Self::Value, E> {
+        u64::try_from(bytes)
+            .map(ByteSize)
+            .map_err(|_| E::custom(format!("size cannot be negative: {}", bytes)))
+    }
+
+    fn visit_f64<E: de::Error>(self, bytes: f64) -> Result<Self::Value, E> {
+        if bytes < 0.0 {
+            return Err(E::custom(format!("size cannot be negative: {}", bytes)));
+        }
+        if bytes.fract() != 0.0 || bytes >= u64::MAX as f64 {
+            return Err(E::custom(format!(
+                "size must be a whole number of bytes: {}",
+                bytes
+            )));
+        }
+        Ok(ByteSize(bytes as u64))
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        text.parse().map_err(E::custom)
+    }
+}
+
+/// A duration in seconds
+///
+/// Strings may combine several units, as in `"1h30min"` or `"2d12h"`. A bare
+/// `5m` is five minutes, quoted or not, unless `LexerConfig::m_suffix_meaning`
+/// gives it another meaning for the whole document.
+///
+/// ```rust
+/// use serde::Deserialize;
+/// use ucl_lexer::types::Seconds;
+///
+/// #[derive(Deserialize)]
+/// struct Job {
+///     timeout: Seconds,
+///     interval: Seconds,
+/// }
+///
+/// let job: Job = ucl_lexer::from_str("timeout = 90s\ninterval = \"1h30m\"")?;
+/// assert_eq!(job.timeout, Seconds(90.0));
+/// assert_eq!(job.interval.to_string(), "1h30min");
+/// # Ok::<(), ucl_lexer::UclError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Seconds(pub f64);
+
+impl Seconds {
+    /// Returns the number of seconds
+    pub fn as_secs_f64(self) -> f64 {
+        self.0
+    }
+
+    /// Converts to a `Duration`, treating negative values as zero
+    pub fn as_duration(self) -> Duration {
+        Duration::try_from_secs_f64(self.0.max(0.0)).unwrap_or(Duration::MAX)
+    }
+}
+
+impl From<Duration> for Seconds {
+    fn from(duration: Duration) -> Self {
+        Seconds(duration.as_secs_f64())
+    }
+}
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.0.is_finite() {
+            return write!(f, "{}", self.0);
+        }
+        if self.0 < 0.0 {
+            f.write_str("-")?;
+        }
+
+        let millis = self.0.abs() * 1000.0;
+        if millis.fract() != 0.0 || millis >= u64::MAX as f64 {
+            return write!(f, "{}s", self.0.abs());
+        }
+        let mut millis = millis as u64;
+        if millis == 0 {
+            return f.write_str("0s");
+        }
+        for (suffix, unit) in DISPLAY_TIME_UNITS {
+            if millis >= unit {
+                write!(f, "{}{}", millis / unit, suffix)?;
+                millis %= unit;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Seconds {
+    type Err = String;
+
+    /// Parses seconds such as `30`, `1.5h` or `1h30min`
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let invalid = || format!("invalid duration: {}", text);
+        let (sign, mut rest) = match text.strip_prefix('-') {
+            Some(rest) => (-1.0, rest),
+            None => (1.0, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if rest.is_empty() {
+            return Err(invalid());
+        }
+        if let Ok(seconds) = rest.parse::<f64>() {
+            return if seconds.is_finite() {
+                Ok(Seconds(sign * seconds))
+            } else {
+                Err(invalid())
+            };
+        }
+
+        let mut total = 0.0;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|ch: char| !(ch.is_ascii_digit() || ch == '.'))
+                .unwrap_or(rest.len());
+            let (number, tail) = rest.split_at(number_len);
+            let unit_len = tail
+                .find(|ch: char| !ch.is_ascii_alphabetic())
+                .unwrap_or(tail.len());
+            let (unit, tail) = tail.split_at(unit_len);
+
+            let value: f64 = number.parse().map_err(|_| invalid())?;
+            let Some((_, multiplier)) = TIME_UNITS.iter().find(|(suffix, _)| *suffix == unit)
+            else {
+                return Err(format!("unknown time unit '{}' in {}", unit, text));
+            };
+            total += value * multiplier;
+            rest = tail;
+        }
+        Ok(Seconds(sign * total))
+    }
+}
+
+impl Serialize for Seconds {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_f64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Seconds {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SecondsVisitor)
+    }
+}
+
+/// Visitor reading a duration from a number of seconds or a suffixed string
+struct SecondsVisitor;
+
+impl<'de> Visitor<'de> for SecondsVisitor {
+    type Value = Seconds;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("a duration such as 30, 1.5h or \"1h30min\"")
+    }
+
+    fn visit_u64<E: de::Error>(self, seconds: u64) -> Result<Self::Value, E> {
+        Ok(Seconds(seconds as f64))
+    }
+
+    fn visit_i64<E: de::Error>(self, seconds: i64) -> Result<Self::Value, E> {
+        Ok(Seconds(seconds as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, seconds: f64) -> Result<Self::Value, E> {
+        if seconds.is_finite() {
+            Ok(Seconds(seconds))
+        } else {
+            Err(E::custom(format!("duration must be finite: {}", seconds)))
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        text.parse().map_err(E::custom)
+    }
+}
@@ -0,0 +1,403 @@
+//! Walking the nodes of a `UclValue`
+//!
+//! `UclValue::walk` iterates over the nodes of a tree with the `KeyPath` of
+//! each, and `UclValue::visit` calls a `UclVisitor` on entering and leaving
+//! every object and array. Both go through the tree in document order,
+//! without recursion, so deeply nested values cannot overflow the stack.
+//!
+//! Paths are written in dotted notation with array indices in brackets, as
+//! in `servers[0].host`. Keys that contain `.` or `[`, start with `"` or are
+//! empty are quoted, as in `hosts."example.com".port`, so every path reads
+//! back through `UclValue::get_path`.
+//!
+//! ```rust
+//! use ucl_lexer::{UclParser, WalkNodes};
+//!
+//! let value = UclParser::new("servers [ { host = a }, { host = b } ]\n\"v1.2\" = yes")
+//!     .parse_document()?;
+//! assert_eq!(value.leaf_paths(), ["servers[0].host", "servers[1].host", "\"v1.2\""]);
+//!
+//! for (path, leaf) in value.walk(WalkNodes::Leaves) {
+//!     assert_eq!(value.get_path(&path.to_string()), Some(leaf));
+//! }
+//! # Ok::<(), ucl_lexer::ParseError>(())
+//! ```
+
+use crate::parser::{UclArray, UclObject, UclValue};
+use std::fmt;
+
+/// One step of a `KeyPath`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// Key of an object entry
+    Key(String),
+    /// Index of an array element
+    Index(usize),
+}
+
+/// Location of a node within a `UclValue`, from the root
+///
+/// The empty path is the root itself. A path is written as `a.b[2].c` by
+/// `Display` and read back by `KeyPath::parse`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KeyPath {
+    segments: Vec<PathSegment>,
+}
+
+impl KeyPath {
+    /// Creates the path of the root
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a path in the notation `Display` writes
+    ///
+    /// Segments are separated by `.`, and `[N]` indexes an array. A key may be
+    /// quoted with `"`, with `\"` and `\\` standing for a quote and a
+    /// backslash. A numeric key such as the `0` of `servers.0` also indexes
+    /// an array when looked up. Returns `None` for an unterminated quote or
+    /// bracket, an index that is not a number, or an empty segment.
+    ///
+    /// ```rust
+    /// use ucl_lexer::{KeyPath, PathSegment};
+    ///
+    /// let path = KeyPath::parse("hosts.\"example.com\"[1]").unwrap();
+    /// assert_eq!(
+    ///     path.segments(),
+    ///     [
+    ///         PathSegment::Key("hosts".into()),
+    ///         PathSegment::Key("example.com".into()),
+    ///         PathSegment::Index(1),
+    ///     ]
+    /// );
+    /// assert_eq!(path.to_string(), "hosts.\"example.com\"[1]");
+    /// assert!(KeyPath::parse("a..b").is_none());
+    /// ```
+    pub fn parse(path: &str) -> Option<Self> {
+        let mut segments = Vec::new();
+        let mut rest = path;
+        // Whether the next segment may be a key, which needs a `.` after the first
+        let mut expect_key = true;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('[') {
+                let (index, after) = after.split_once(']')?;
+                segments.push(PathSegment::Index(index.parse().ok()?));
+                rest = after;
+                expect_key = false;
+                continue;
+            }
+            if !expect_key {
+                rest = rest.strip_prefix('.')?;
+            }
+            let (key, after) = match rest.strip_prefix('"') {
+                Some(quoted) => read_quoted(quoted)?,
+                None => {
+                    let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                    match &rest[..end] {
+                        "" => return None,
+                        key => (key.to_string(), &rest[end..]),
+                    }
+                }
+            };
+            segments.push(PathSegment::Key(key));
+            rest = after;
+            expect_key = false;
+        }
+        Some(Self { segments })
+    }
+
+    /// Returns the segments from the root down
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns the number of segments
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns true for the path of the root
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Returns the path with one more segment
+    pub fn join(&self, segment: PathSegment) -> Self {
+        let mut segments = Vec::with_capacity(self.segments.len() + 1);
+        segments.extend_from_slice(&self.segments);
+        segments.push(segment);
+        Self { segments }
+    }
+
+    /// Returns the segments as keys, with an index written as its number
+    ///
+    /// This is how provenance and the CST key array elements.
+    pub(crate) fn into_keys(self) -> Vec<String> {
+        self.segments
+            .into_iter()
+            .map(|segment| match segment {
+                PathSegment::Key(key) => key,
+                PathSegment::Index(index) => index.to_string(),
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<PathSegment>> for KeyPath {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl fmt::Display for KeyPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Index(index) => write!(f, "[{}]", index)?,
+                PathSegment::Key(key) => {
+                    if i > 0 {
+                        f.write_str(".")?;
+                    }
+                    if key.is_empty() || key.starts_with('"') || key.contains(['.', '[']) {
+                        write_quoted_key(f, key)?;
+                    } else {
+                        f.write_str(key)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes a key in quotes, escaping quotes and backslashes
+fn write_quoted_key(f: &mut fmt::Formatter<'_>, key: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for ch in key.chars() {
+        if matches!(ch, '"' | '\\') {
+            f.write_str("\\")?;
+        }
+        write!(f, "{}", ch)?;
+    }
+    f.write_str("\"")
+}
+
+/// Reads a quoted key after its opening quote, returning it and the text after the closing one
+fn read_quoted(text: &str) -> Option<(String, &str)> {
+    let mut key = String::new();
+    let mut chars = text.char_indices();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((key, &text[i + 1..])),
+            '\\' => key.push(chars.next()?.1),
+            ch => key.push(ch),
+        }
+    }
+    None
+}
+
+/// Nodes yielded by `UclValue::walk`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WalkNodes {
+    /// Every node, the root included
+    #[default]
+    All,
+    /// Objects only, the root included when it is one
+    Objects,
+    /// Scalars, empty objects and empty arrays
+    ///
+    /// Every other node is made of these, so the leaves and their paths are
+    /// enough to rebuild the tree.
+    Leaves,
+}
+
+impl WalkNodes {
+    /// Returns whether a node is yielded
+    fn includes(self, value: &UclValue) -> bool {
+        match self {
+            WalkNodes::All => true,
+            WalkNodes::Objects => value.is_object(),
+            WalkNodes::Leaves => match value {
+                UclValue::Object(object) => object.is_empty(),
+                UclValue::Array(array) => array.is_empty(),
+                _ => true,
+            },
+        }
+    }
+}
+
+/// Iterator over the nodes of a `UclValue` and their paths, in document order
+///
+/// Returned by `UclValue::walk`; a node comes before its children.
+#[derive(Debug, Clone)]
+pub struct Walk<'a> {
+    nodes: WalkNodes,
+    /// Nodes still to visit, the next one last
+    pending: Vec<(KeyPath, &'a UclValue)>,
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = (KeyPath, &'a UclValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((path, value)) = self.pending.pop() {
+            match value {
+                UclValue::Object(object) => {
+                    self.pending.extend(
+                        object.iter().rev().map(|(key, child)| {
+                            (path.join(PathSegment::Key(key.to_string())), child)
+                        }),
+                    );
+                }
+                UclValue::Array(array) => {
+                    self.pending.extend(
+                        array
+                            .iter()
+                            .enumerate()
+                            .rev()
+                            .map(|(index, child)| (path.join(PathSegment::Index(index)), child)),
+                    );
+                }
+                _ => {}
+            }
+            if self.nodes.includes(value) {
+                return Some((path, value));
+            }
+        }
+        None
+    }
+}
+
+/// Callbacks for `UclValue::visit`
+///
+/// Every method does nothing by default, so a visitor implements only those
+/// it needs. An object or array is entered before its children and exited
+/// after them; scalars are strings, numbers, times, booleans and null.
+///
+/// ```rust
+/// use ucl_lexer::{KeyPath, UclObject, UclParser, UclValue, UclVisitor};
+///
+/// /// Counts the integers and the deepest object
+/// #[derive(Default)]
+/// struct Stats {
+///     integers: usize,
+///     depth: usize,
+///     max_depth: usize,
+/// }
+///
+/// impl UclVisitor for Stats {
+///     fn enter_object(&mut self, _path: &KeyPath, _object: &UclObject) {
+///         self.depth += 1;
+///         self.max_depth = self.max_depth.max(self.depth);
+///     }
+///
+///     fn exit_object(&mut self, _path: &KeyPath, _object: &UclObject) {
+///         self.depth -= 1;
+///     }
+///
+///     fn visit_scalar(&mut self, _path: &KeyPath, value: &UclValue) {
+///         self.integers += usize::from(value.as_integer().is_some());
+///     }
+/// }
+///
+/// let value = UclParser::new("a { b { c = 1 } }\nd = [2, 3]").parse_document()?;
+/// let mut stats = Stats::default();
+/// value.visit(&mut stats);
+/// assert_eq!((stats.integers, stats.max_depth), (3, 3));
+/// # Ok::<(), ucl_lexer::ParseError>(())
+/// ```
+pub trait UclVisitor {
+    /// Called before the entries of an object
+    fn enter_object(&mut self, _path: &KeyPath, _object: &UclObject) {}
+
+    /// Called after the entries of an object
+    fn exit_object(&mut self, _path: &KeyPath, _object: &UclObject) {}
+
+    /// Called before the elements of an array
+    fn enter_array(&mut self, _path: &KeyPath, _array: &UclArray) {}
+
+    /// Called after the elements of an array
+    fn exit_array(&mut self, _path: &KeyPath, _array: &UclArray) {}
+
+    /// Called for every value that is neither an object nor an array
+    fn visit_scalar(&mut self, _path: &KeyPath, _value: &UclValue) {}
+}
+
+/// Step of `UclValue::visit`
+enum Visit<'a> {
+    Enter(KeyPath, &'a UclValue),
+    Exit(KeyPath, &'a UclValue),
+}
+
+impl UclValue {
+    /// Iterates over the nodes selected by `nodes` with their paths, in document order
+    ///
+    /// ```rust
+    /// use ucl_lexer::{UclParser, WalkNodes};
+    ///
+    /// let value = UclParser::new("a { b = 1 }\nc = [true]").parse_document()?;
+    /// let paths: Vec<_> = value.walk(WalkNodes::All).map(|(path, _)| path.to_string()).collect();
+    /// assert_eq!(paths, ["", "a", "a.b", "c", "c[0]"]);
+    /// # Ok::<(), ucl_lexer::ParseError>(())
+    /// ```
+    pub fn walk(&self, nodes: WalkNodes) -> Walk<'_> {
+        Walk {
+            nodes,
+            pending: vec![(KeyPath::new(), self)],
+        }
+    }
+
+    /// Returns the paths of the leaves, as `walk(WalkNodes::Leaves)` yields them
+    pub fn leaf_paths(&self) -> Vec<String> {
+        self.walk(WalkNodes::Leaves)
+            .map(|(path, _)| path.to_string())
+            .collect()
+    }
+
+    /// Looks up the value at `path`
+    ///
+    /// Key segments index arrays when they are numbers, as in `get_path`.
+    pub fn get_key_path(&self, path: &KeyPath) -> Option<&UclValue> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (UclValue::Object(object), PathSegment::Key(key)) => object.get(key.as_str())?,
+                (UclValue::Array(array), PathSegment::Index(index)) => array.get(*index)?,
+                (UclValue::Array(array), PathSegment::Key(key)) => {
+                    array.get(key.parse::<usize>().ok()?)?
+                }
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Calls `visitor` for every node, in document order
+    pub fn visit<V: UclVisitor + ?Sized>(&self, visitor: &mut V) {
+        let mut pending = vec![Visit::Enter(KeyPath::new(), self)];
+        while let Some(step) = pending.pop() {
+            match step {
+                Visit::Enter(path, value) => match value {
+                    UclValue::Object(object) => {
+                        visitor.enter_object(&path, object);
+                        pending.push(Visit::Exit(path.clone(), value));
+                        pending.extend(object.iter().rev().map(|(key, child)| {
+                            Visit::Enter(path.join(PathSegment::Key(key.to_string())), child)
+                        }));
+                    }
+                    UclValue::Array(array) => {
+                        visitor.enter_array(&path, array);
+                        pending.push(Visit::Exit(path.clone(), value));
+                        pending.extend(array.iter().enumerate().rev().map(|(index, child)| {
+                            Visit::Enter(path.join(PathSegment::Index(index)), child)
+                        }));
+                    }
+                    scalar => visitor.visit_scalar(&path, scalar),
+                },
+                Visit::Exit(path, UclValue::Object(object)) => visitor.exit_object(&path, object),
+                Visit::Exit(path, UclValue::Array(array)) => visitor.exit_array(&path, array),
+                Visit::Exit(..) => {}
+            }
+        }
+    }
+}
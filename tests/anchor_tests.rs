@@ -0,0 +1,254 @@
+//! Tests for `&anchor` definitions and `*anchor` references
+
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use ucl_lexer::{
+    LexerConfig, ParseError, ParserConfig, Position, Token, UclDeserializer, UclLexer, UclParser,
+    UclValue, ucl,
+};
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input)
+        .with_config(ParserConfig::new().with_enable_anchors(true))
+        .parse_document()
+}
+
+#[test]
+fn test_reference_copies_the_anchored_value() {
+    let input = "\
+defaults = &common { retries = 3, timeout = 30 }
+service_a = *common
+hosts = &hosts [\"a.example.com\", \"b.example.com\"]
+backup { hosts = *hosts, port = *port }
+port = &port 8080
+";
+    let err = parse(input).unwrap_err();
+    // References only see anchors defined before them
+    assert!(matches!(err, ParseError::UndefinedAnchor { ref name, .. } if name == "port"));
+
+    let value = parse(&input.replace("port = *port", "port = 9090")).unwrap();
+    assert_eq!(value["service_a"], value["defaults"]);
+    assert_eq!(value["service_a"], ucl!({ "retries": 3, "timeout": 30 }));
+    assert_eq!(
+        value["backup"],
+        ucl!({ "hosts": ["a.example.com", "b.example.com"], "port": 9090 })
+    );
+    assert_eq!(value["port"], 8080);
+}
+
+#[test]
+fn test_merge_into_object() {
+    // docker-compose style `<<: *defaults`
+    let input = "\
+x-defaults = &defaults {
+    restart = \"always\"
+    logging { driver = \"json-file\", max_size = \"10m\" }
+    replicas = 1
+}
+web { *defaults, image = \"nginx\", replicas = 3 }
+worker {
+    *defaults
+    image = \"worker\"
+    logging { driver = \"syslog\" }
+}
+";
+    let value = parse(input).unwrap();
+    assert_eq!(
+        value["web"],
+        ucl!({
+            "restart": "always",
+            "logging": { "driver": "json-file", "max_size": "10m" },
+            "replicas": 3,
+            "image": "nginx"
+        })
+    );
+    // Merging is shallow: a key written in the object replaces the whole value
+    assert_eq!(value["worker"]["logging"], ucl!({ "driver": "syslog" }));
+    assert_eq!(value["worker"]["replicas"], 1);
+
+    // The anchored value is left as it was
+    assert_eq!(value["x-defaults"]["replicas"], 1);
+}
+
+#[test]
+fn test_several_merges_and_nested_anchors() {
+    let input = "\
+base = &base { timeout = 5, retries = 3 }
+tls = &tls { port = 443, cert = &cert \"/etc/ssl/site.pem\" }
+api { *base, *tls, retries = 5 }
+admin { *tls; port = 8443; key = *cert }
+";
+    let value = parse(input).unwrap();
+    assert_eq!(
+        value["api"],
+        ucl!({ "timeout": 5, "retries": 5, "port": 443, "cert": "/etc/ssl/site.pem" })
+    );
+    assert_eq!(
+        value["admin"],
+        ucl!({ "port": 8443, "cert": "/etc/ssl/site.pem", "key": "/etc/ssl/site.pem" })
+    );
+
+    // A later reference replaces values of an earlier one
+    let value = parse("a = &a { x = 1, y = 1 }\nb = &b { y = 2 }\nc { *a, *b }").unwrap();
+    assert_eq!(value["c"], ucl!({ "x": 1, "y": 2 }));
+
+    // A key repeated after replacing a merged one follows the duplicate key behavior
+    let value = parse("a = &a { x = 1 }\nb { *a, x = 2, x = 3 }").unwrap();
+    assert_eq!(value["b"], ucl!({ "x": [2, 3] }));
+}
+
+#[test]
+fn test_references_in_arrays_and_implicit_values() {
+    let value = parse("peer &peer { weight = 1 }\npool = [*peer, *peer]\nfallback *peer").unwrap();
+    assert_eq!(value["pool"], ucl!([{ "weight": 1 }, { "weight": 1 }]));
+    assert_eq!(value["fallback"], ucl!({ "weight": 1 }));
+}
+
+#[test]
+fn test_undefined_and_recursive_references() {
+    let err = parse("a = 1\nb = *missing").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::UndefinedAnchor { ref name, position }
+            if name == "missing" && position == Position { line: 2, column: 5, offset: 10 }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Undefined anchor 'missing' referenced at 2:5"
+    );
+
+    let err = parse("tree = &tree { child = *tree }").unwrap_err();
+    assert!(matches!(err, ParseError::RecursiveAnchor { ref name, .. } if name == "tree"));
+}
+
+#[test]
+fn test_anchor_names_are_defined_once() {
+    let err = parse("a = &x 1\nb = &x 2").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Anchor 'x' at 2:5 is already defined at 1:5"
+    );
+
+    // Redefining after a reference is rejected too, so `*x` always means one value
+    let err = parse("a = &x 1\nb = *x\nc = &x 2\nd = *x").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateAnchor { ref name, first_definition, position }
+            if name == "x" && first_definition.line == 1 && position.line == 3
+    ));
+}
+
+#[test]
+fn test_invalid_merges() {
+    let err = parse("a = &x 1\nb { *x }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid object structure at 2:5: Cannot merge the integer value of anchor 'x' into an object"
+    );
+
+    let err = parse("a = &x { y = 1 }\nb { c = 1, *x }").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Reference '*x' must come before the first key"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_nested_references_are_limited() {
+    // Each level references the one before ten times: 10^6 strings from 321 bytes
+    let mut input = String::from("l0 = &l0 \"lol\"\n");
+    for level in 1..=6 {
+        let refs = vec![format!("*l{}", level - 1); 10].join(",");
+        input.push_str(&format!("l{level} = &l{level} [{refs}]\n"));
+    }
+    let started = Instant::now();
+
+    for (config, limit, line) in [
+        (ParserConfig::untrusted(), 10_000, 5),
+        (ParserConfig::strict(), 10_000, 5),
+        (ParserConfig::new(), 1_000_000, 7),
+    ] {
+        let err = UclParser::new(&input)
+            .with_config(config.with_enable_anchors(true))
+            .parse_document()
+            .unwrap_err();
+        match err {
+            ParseError::AliasExpansionTooLarge {
+                limit: reported,
+                position,
+            } => {
+                assert_eq!(reported, limit);
+                assert_eq!(position.line, line);
+            }
+            other => panic!("expected an expansion limit error, got {other:?}"),
+        }
+    }
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    // Values below the limit are copied as usual
+    let value = UclParser::new(&input[..input.find("l4").unwrap()])
+        .with_config(ParserConfig::untrusted().with_enable_anchors(true))
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["l3"][9][9][9], "lol");
+}
+
+#[test]
+fn test_disabled_by_default() {
+    assert!(!ParserConfig::default().enable_anchors);
+    let err = UclParser::new("a = &x 1").parse_document().unwrap_err();
+    assert!(
+        err.to_string().contains("Unexpected character '&'"),
+        "{err}"
+    );
+
+    // `&` and `*` not followed by a name are not anchors
+    assert!(parse("a = & x").is_err());
+    assert!(parse("a = *").is_err());
+}
+
+#[test]
+fn test_lexer_tokens() {
+    let config = LexerConfig::new().with_anchors(true);
+    let mut lexer = UclLexer::with_config("&web-v2 *db_1", config);
+    assert_eq!(lexer.next_token().unwrap(), Token::Anchor("web-v2".into()));
+    assert_eq!(lexer.next_token().unwrap(), Token::Reference("db_1".into()));
+    assert_eq!(lexer.next_token().unwrap(), Token::Eof);
+}
+
+#[test]
+fn test_deserialize_merged_services() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Service {
+        image: String,
+        replicas: u32,
+        restart: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Compose {
+        web: Service,
+        worker: Service,
+    }
+
+    let input = "\
+defaults = &defaults { replicas = 1, restart = \"always\" }
+web { *defaults, image = \"nginx\", replicas = 2 }
+worker { *defaults, image = \"worker\" }
+";
+    let compose = Compose::deserialize(UclDeserializer::with_parser_config(
+        input,
+        ParserConfig::new().with_enable_anchors(true),
+    ))
+    .unwrap();
+    assert_eq!(
+        compose.web,
+        Service {
+            image: "nginx".to_string(),
+            replicas: 2,
+            restart: "always".to_string(),
+        }
+    );
+    assert_eq!(compose.worker.replicas, 1);
+}
@@ -0,0 +1,128 @@
+//! Tests for `key += value` append assignments
+
+use ucl_lexer::{DuplicateKeyBehavior, ParseError, ParserConfig, UclParser, UclValue, ucl};
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input).parse_document()
+}
+
+fn parse_with(config: ParserConfig, input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input).with_config(config).parse_document()
+}
+
+#[test]
+fn test_append_extends_arrays() {
+    let value = parse("ports = [80]\nports += [443, 8080]\nports += 9000\n").unwrap();
+    assert_eq!(value["ports"], ucl!([80, 443, 8080, 9000]));
+}
+
+#[test]
+fn test_append_deep_merges_objects() {
+    let input = r#"
+server {
+    host = "a"
+    tls { enabled = false }
+}
+server += {
+    port = 80
+    tls { cert = "c.pem" }
+}
+"#;
+    let value = parse(input).unwrap();
+    assert_eq!(
+        value["server"],
+        ucl!({ "host": "a", "tls": { "enabled": false, "cert": "c.pem" }, "port": 80 })
+    );
+}
+
+#[test]
+fn test_append_inside_braces() {
+    let value = parse("{ a = [1]; a += [2] }").unwrap();
+    assert_eq!(value["a"], ucl!([1, 2]));
+
+    let value = parse("outer { list = [1]\n list += [2] }").unwrap();
+    assert_eq!(value["outer"]["list"], ucl!([1, 2]));
+}
+
+#[test]
+fn test_append_to_missing_key_assigns() {
+    let value = parse("tags += [\"a\", \"b\"]\nmeta += { x = 1 }\n").unwrap();
+    assert_eq!(value["tags"], ucl!(["a", "b"]));
+    assert_eq!(value["meta"], ucl!({ "x": 1 }));
+}
+
+#[test]
+fn test_append_to_scalar_is_an_error() {
+    let err = parse("port = 80\nport += 81\n").unwrap_err();
+    match &err {
+        ParseError::InvalidObject { message, position } => {
+            assert!(message.contains("'port'"), "{message}");
+            assert_eq!((position.line, position.column), (2, 1));
+        }
+        other => panic!("expected invalid object error, got {other:?}"),
+    }
+
+    assert!(parse("name = \"a\"\nname += \"b\"\n").is_err());
+}
+
+#[test]
+fn test_append_concatenates_strings_behind_flag() {
+    let config = ParserConfig::default().with_append_concatenates_strings(true);
+    let value = parse_with(config, "path = \"/usr\"\npath += \"/bin\"\n").unwrap();
+    assert_eq!(value["path"], "/usr/bin");
+
+    let config = ParserConfig::default().with_append_concatenates_strings(true);
+    assert!(parse_with(config, "n = 1\nn += 2\n").is_err());
+}
+
+#[test]
+fn test_append_bypasses_duplicate_key_behavior() {
+    let input = "a = [1]\na += [2]\n";
+    for behavior in [
+        DuplicateKeyBehavior::Error,
+        DuplicateKeyBehavior::ImplicitArray,
+        DuplicateKeyBehavior::Override,
+        DuplicateKeyBehavior::KeepFirst,
+    ] {
+        let config = ParserConfig::default().with_duplicate_key_behavior(behavior);
+        let value = parse_with(config, input).unwrap();
+        assert_eq!(value["a"], ucl!([1, 2]), "{behavior:?}");
+    }
+}
+
+#[test]
+fn test_plain_duplicates_still_follow_behavior_after_append() {
+    let config = ParserConfig::default().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+    let err = parse_with(config, "a += [1]\na = [2]\n").unwrap_err();
+    assert!(matches!(err, ParseError::DuplicateKey { ref key, .. } if key == "a"));
+
+    let config =
+        ParserConfig::default().with_duplicate_key_behavior(DuplicateKeyBehavior::Override);
+    let value = parse_with(config, "a = [1]\na += [2]\na = [3]\n").unwrap();
+    assert_eq!(value["a"], ucl!([3]));
+
+    let config =
+        ParserConfig::default().with_duplicate_key_behavior(DuplicateKeyBehavior::ImplicitArray);
+    let value = parse_with(config, "a = 1\na = 2\na += 3\n").unwrap();
+    assert_eq!(value["a"], ucl!([1, 2, 3]));
+}
+
+#[test]
+fn test_append_with_dotted_keys() {
+    let value = parse("server.ports = [80]\nserver.ports += [443]\n").unwrap();
+    assert_eq!(value["server.ports"], ucl!([80, 443]));
+
+    let value = parse("\"a.b\" = { x = 1 }\n\"a.b\" += { y = 2 }\n").unwrap();
+    assert_eq!(value["a.b"], ucl!({ "x": 1, "y": 2 }));
+}
+
+#[test]
+fn test_bare_plus_is_still_rejected() {
+    for input in ["a + 1", "+ a = 1", "a = [1 + 2]", "a + = 1"] {
+        let err = parse(input).unwrap_err();
+        assert!(
+            matches!(err, ParseError::UnexpectedToken { found: "'+'", .. }),
+            "{input}: {err:?}"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Tests for parsing into arena-allocated values with `UclParser::parse_in`
+#![cfg(feature = "arena")]
+
+use ucl_lexer::arena::{Bump, UclValueArena};
+use ucl_lexer::{KeyCharset, LexerConfig, MapVariableHandler, ParserConfig, UclParser};
+
+/// Documents the arena builder reads itself, and documents it leaves to the parser
+const DOCUMENTS: &[&str] = &[
+    "",
+    "port = 80",
+    "server { host = \"localhost\", port = 8080; enabled = yes }\nname: web\n",
+    "{\"a\": [1, 2.5, \"x\", null, true, {\"b\": []}], \"c\": {}}",
+    "[1, 2, [3, [4]], {a = b }]",
+    "timeout = 30s\nsize = 10kb\nratio = -0.5\n",
+    "words = [alpha, Beta-2, /usr/local, TRUE, Off, none ]",
+    "# comment\na = 1 // another\nb = 2; /* block */\n",
+    "a = 1, b = 2,",
+    "quoted { \"key with space\" = 'single' }",
+    "text = <<EOD\nline one\nline two\nEOD\n",
+    // Left to the parser
+    "a = 1\na = 2",
+    "a { x = 1 }\na { y = 2 }",
+    "a.b.c = 1",
+    "section foo bar { x = 1 }",
+    "greeting = \"hello\" \" world\"",
+    "value = hello world",
+    "list += [1]\nlist += [2]",
+    "enabled;",
+    "port 80",
+    ".priority 3\nport = 80",
+    "path = \"$HOME/bin\"",
+    "level = inf",
+    "many = [a b]",
+    "x = 1 y = 2",
+    "42",
+    "[1, {a = b}]",
+    "words = [alpha]",
+    "a = 1\n/* block */ b = 2",
+    "big { k0 = 0, k1 = 1, k2 = 2, k3 = 3, k4 = 4, k5 = 5, k6 = 6, k7 = 7, k8 = 8, k3 = 9 }",
+    // Errors
+    "a = [1,,2]",
+    "a = {",
+    "a = hello}",
+    "a = ; b = 1",
+    "{ a = 1 } trailing",
+    "a = \"unterminated",
+];
+
+#[test]
+fn test_parse_in_matches_parse_document() {
+    let mut parser = UclParser::new("");
+    for document in DOCUMENTS {
+        let bump = Bump::new();
+        let expected = UclParser::new(document).parse_document();
+        match (parser.parse_in(document, &bump), expected) {
+            (Ok(value), Ok(expected)) => assert_eq!(value.to_owned(), expected, "{document:?}"),
+            (Err(error), Err(expected)) => {
+                assert_eq!(error.to_string(), expected.to_string(), "{document:?}")
+            }
+            (result, expected) => panic!("{document:?}: {result:?} but expected {expected:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_arena_accessors() {
+    let bump = Bump::new();
+    let input =
+        "server {\n  port = 8080\n  hosts = [\"a\", \"b\"]\n  timeout = 5s\n}\nratio = 0.5\n";
+    let value = UclParser::new("").parse_in(input, &bump).unwrap();
+
+    assert!(value.is_object());
+    assert_eq!(value["server"]["port"].as_integer(), Some(8080));
+    assert_eq!(value["server"]["hosts"][1].as_str(), Some("b"));
+    assert_eq!(value["server"]["timeout"].as_time(), Some(5.0));
+    assert_eq!(
+        value.get("ratio").and_then(|ratio| ratio.as_float()),
+        Some(0.5)
+    );
+    assert_eq!(
+        value.get_path("server.hosts.0"),
+        Some(&UclValueArena::String("a"))
+    );
+    assert!(value["missing"].is_null());
+    assert!(value["server"]["hosts"][7].is_null());
+    assert_eq!(value.get_path("server.port.deeper"), None);
+
+    let keys: Vec<_> = value["server"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .map(|(key, _)| *key)
+        .collect();
+    assert_eq!(keys, ["port", "hosts", "timeout"]);
+    let hosts: Vec<_> = value["server"]["hosts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(UclValueArena::as_str)
+        .collect();
+    assert_eq!(hosts, ["a", "b"]);
+
+    // Owned copies outlive the arena
+    let owned = value["server"].to_owned();
+    drop(bump);
+    assert_eq!(owned["port"], 8080);
+}
+
+#[test]
+fn test_parser_settings_apply() {
+    let bump = Bump::new();
+
+    let handler = MapVariableHandler::from_map(
+        [("HOME".to_string(), "/home/user".to_string())]
+            .into_iter()
+            .collect(),
+    );
+    let mut parser = UclParser::with_variable_handler("", Box::new(handler));
+    let value = parser.parse_in("path = \"$HOME/bin\"", &bump).unwrap();
+    assert_eq!(value["path"].as_str(), Some("/home/user/bin"));
+
+    let mut parser = UclParser::new("").with_config(
+        ParserConfig::new().with_duplicate_key_behavior(ucl_lexer::DuplicateKeyBehavior::Error),
+    );
+    assert!(parser.parse_in("a = 1\na = 2", &bump).is_err());
+    assert_eq!(
+        parser.parse_in("a = 1\nb = 2", &bump).unwrap()["b"].as_integer(),
+        Some(2)
+    );
+
+    let mut parser = UclParser::new("").with_config(ParserConfig::new().with_max_depth(2));
+    assert!(parser.parse_in("a { b { c { d = 1 } } }", &bump).is_err());
+
+    let config = LexerConfig::new().with_key_charset(KeyCharset::Ascii);
+    let mut parser = UclParser::with_lexer_config("", config);
+    assert!(parser.parse_in("caf\u{e9} = 1", &bump).is_err());
+    let config = LexerConfig::new().with_key_charset(KeyCharset::UnicodeNfc);
+    let mut parser = UclParser::with_lexer_config("", config);
+    let value = parser.parse_in("\"cafe\u{301}\" = 1", &bump).unwrap();
+    assert_eq!(value["caf\u{e9}"].as_integer(), Some(1));
+}
@@ -0,0 +1,109 @@
+//! Tests for building, mutating and extracting `UclArray` values
+
+use ucl_lexer::{ConversionError, UclArray, UclArrayExt, UclParser, UclValue, ucl};
+
+#[test]
+fn test_build_and_mutate() {
+    let mut array: UclArray = (1..=3).map(UclValue::from).collect();
+    array.push(UclValue::from("four"));
+    array.insert(0, UclValue::Null);
+
+    assert_eq!(array.len(), 5);
+    assert!(!array.is_empty());
+    assert_eq!(array.get(1), Some(&UclValue::Integer(1)));
+    assert_eq!(array.get(9), None);
+    assert_eq!(array.remove(0), UclValue::Null);
+    assert_eq!(UclValue::from(array), ucl!([1, 2, 3, "four"]));
+}
+
+#[test]
+fn test_iteration() {
+    let value = UclParser::new("ports = [80, 443, 8080]")
+        .parse_document()
+        .unwrap();
+    let array = value["ports"].as_array().unwrap();
+
+    let total: i64 = array.iter().filter_map(UclValue::as_integer).sum();
+    assert_eq!(total, 80 + 443 + 8080);
+    let mut strings = 0;
+    for item in array {
+        strings += usize::from(item.is_string());
+    }
+    assert_eq!(strings, 0);
+
+    let owned: Vec<String> = array.clone().into_iter().map(|v| v.to_string()).collect();
+    assert_eq!(owned, ["80", "443", "8080"]);
+}
+
+#[test]
+fn test_typed_extraction() {
+    let value = ucl!({
+        "names": ["a", "b"],
+        "ports": [80, 443],
+        "ratios": [0.5, 2],
+        "flags": [true, false],
+        "empty": []
+    });
+
+    assert_eq!(
+        value["names"].as_array().unwrap().as_vec_of_str().unwrap(),
+        ["a", "b"]
+    );
+    assert_eq!(
+        value["ports"].as_array().unwrap().as_vec_of_i64().unwrap(),
+        [80, 443]
+    );
+    assert_eq!(
+        value["ratios"].as_array().unwrap().as_vec_of_f64().unwrap(),
+        [0.5, 2.0]
+    );
+    assert_eq!(
+        value["flags"].as_array().unwrap().as_vec_of_bool().unwrap(),
+        [true, false]
+    );
+    assert!(
+        value["empty"]
+            .as_array()
+            .unwrap()
+            .as_vec_of_str()
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn test_heterogeneous_array_names_element() {
+    let value = UclParser::new("mixed = [\"a\", 1, true, null, { x = 1 }]")
+        .parse_document()
+        .unwrap();
+    let mixed = value["mixed"].as_array().unwrap();
+
+    assert_eq!(
+        mixed.as_vec_of_str().unwrap_err(),
+        ConversionError::ElementTypeMismatch {
+            index: 1,
+            expected: "string",
+            found: "integer",
+        }
+    );
+    assert_eq!(
+        mixed.as_vec_of_i64().unwrap_err().to_string(),
+        "element 0 is string, expected integer"
+    );
+    assert!(matches!(
+        mixed[1..].as_vec_of_bool(),
+        Err(ConversionError::ElementTypeMismatch {
+            index: 0,
+            found: "integer",
+            ..
+        })
+    ));
+    assert!(matches!(
+        mixed[2..].as_vec_of_f64(),
+        Err(ConversionError::ElementTypeMismatch {
+            index: 0,
+            found: "boolean",
+            ..
+        })
+    ));
+}
@@ -0,0 +1,89 @@
+//! Tests for the errors reported for misplaced `=` and `:`
+
+use ucl_lexer::{ParseError, Position, UclError, UclParser, ucl};
+
+fn parse_error(input: &str) -> ParseError {
+    UclParser::new(input).parse_document().unwrap_err()
+}
+
+#[test]
+fn test_key_value_in_array() {
+    let err = parse_error("servers = [ host = \"a\", host = \"b\" ]");
+    assert!(matches!(
+        err,
+        ParseError::KeyValueInArray { ref key, position }
+            if key == "host" && position == Position { line: 1, column: 13, offset: 12 }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Key 'host' is assigned a value inside an array at 1:13; wrap each element in braces, \
+         as in [ { host = ... }, { host = ... } ]"
+    );
+
+    // Quoted keys, `:` and elements after the first are detected too
+    for input in [
+        "servers = [ \"host\": \"a\" ]",
+        "servers = [\n  { host = \"a\" },\n  port: 80\n]",
+        "nested { list = [[ name = x ]] }",
+    ] {
+        assert!(
+            matches!(parse_error(input), ParseError::KeyValueInArray { .. }),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn test_arrays_without_assignments_are_unchanged() {
+    let value = UclParser::new(
+        "servers = [ { host = \"a\" }, { host: \"b\" } ]\nwords = [hello world, x ]",
+    )
+    .parse_document()
+    .unwrap();
+    assert_eq!(
+        value,
+        ucl!({
+            "servers": [{ "host": "a" }, { "host": "b" }],
+            "words": ["hello world", "x"]
+        })
+    );
+
+    // `=` after a value that cannot be a key keeps the generic error
+    assert!(matches!(
+        parse_error("a = [1 = 2]"),
+        ParseError::UnexpectedToken { .. }
+    ));
+}
+
+#[test]
+fn test_repeated_assignment_operator() {
+    for (input, column) in [
+        ("a = = 1", 5),
+        ("a == 1", 4),
+        ("a : = 1", 5),
+        ("a { b = = 1 }", 9),
+    ] {
+        let err = parse_error(input);
+        assert!(
+            matches!(err, ParseError::RepeatedAssignment { position } if position.column == column),
+            "{input}: {err:?}"
+        );
+        assert!(
+            err.to_string()
+                .ends_with("did you accidentally repeat the assignment operator?"),
+            "{err}"
+        );
+    }
+}
+
+#[test]
+fn test_enhanced_error_suggests_braces() {
+    let input = "servers = [ host = \"a\" ]";
+    let enhanced = UclError::from(parse_error(input)).with_source_context(input);
+    assert!(
+        enhanced
+            .to_string()
+            .contains("Wrap each element in braces: [ { host = ... }, { host = ... } ]"),
+        "{enhanced}"
+    );
+}
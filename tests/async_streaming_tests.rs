@@ -0,0 +1,210 @@
+//! Tests for `AsyncStreamingUclLexer` reading input that arrives in small, delayed chunks
+#![cfg(feature = "async")]
+
+use std::future::Future;
+use std::io::{self, Cursor};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::time::Sleep;
+use ucl_lexer::{
+    LexError, StreamingUclLexer, Token, streaming_lexer_from_async_file,
+    streaming_lexer_from_async_reader,
+};
+
+/// Hands out at most `chunk` bytes per read, sleeping for `delay` before each one
+struct Trickle {
+    data: Vec<u8>,
+    offset: usize,
+    chunk: usize,
+    delay: Duration,
+    sleep: Option<Pin<Box<Sleep>>>,
+    polls: Arc<AtomicUsize>,
+}
+
+impl Trickle {
+    fn new(data: &str, chunk: usize) -> Self {
+        Self {
+            data: data.as_bytes().to_vec(),
+            offset: 0,
+            chunk,
+            delay: Duration::from_millis(1),
+            sleep: None,
+            polls: Arc::default(),
+        }
+    }
+}
+
+impl AsyncRead for Trickle {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        this.polls.fetch_add(1, Ordering::Relaxed);
+        if this.offset < this.data.len() {
+            if !this.delay.is_zero() {
+                let delay = this.delay;
+                let sleep = this
+                    .sleep
+                    .get_or_insert_with(|| Box::pin(tokio::time::sleep(delay)));
+                ready!(sleep.as_mut().poll(cx));
+                this.sleep = None;
+            }
+            let end = (this.offset + this.chunk)
+                .min(this.data.len())
+                .min(this.offset + buf.remaining());
+            buf.put_slice(&this.data[this.offset..end]);
+            this.offset = end;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Lexes `input` with the blocking streaming lexer, which reads it in one go
+fn sync_tokens(input: &str) -> Vec<Result<Token<'static>, LexError>> {
+    let mut lexer = StreamingUclLexer::new(Cursor::new(input.as_bytes()));
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let done = !matches!(token, Ok(ref token) if *token != Token::Eof);
+        tokens.push(token);
+        if done {
+            return tokens;
+        }
+    }
+}
+
+/// Lexes `input` with the async lexer, `chunk` bytes at a time
+async fn async_tokens(input: &str, chunk: usize) -> Vec<Result<Token<'static>, LexError>> {
+    let mut lexer = streaming_lexer_from_async_reader(Trickle::new(input, chunk));
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token().await;
+        let done = !matches!(token, Ok(ref token) if *token != Token::Eof);
+        tokens.push(token);
+        if done {
+            return tokens;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tokens_split_between_chunks() {
+    let input = "\u{feff}name = \"café ☕ \\\"q\\\"\" # note\r\n\
+                 size = 512mb; ratio = -1.5e3\n\
+                 /* outer /* inner */ */ path = '/srv/\\'x\\''\n\
+                 text = <<EOD\nline 𝄞 one\n$HOME\nEOD\n\
+                 list = [true, null, 0x1F, 30s]\n";
+    let expected = sync_tokens(input);
+    assert!(expected.iter().all(Result::is_ok), "{expected:?}");
+    assert_eq!(expected.len(), 28);
+
+    for chunk in [1, 2, 3, 5, 4096] {
+        let tokens = async_tokens(input, chunk).await;
+        assert_eq!(
+            format!("{tokens:?}"),
+            format!("{expected:?}"),
+            "chunk of {chunk}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_waits_for_input_without_polling_in_a_loop() {
+    let input = "server { port = 8080; host = \"db.internal\" }\n".repeat(4);
+    let reader = Trickle::new(&input, 3);
+    let polls = Arc::clone(&reader.polls);
+    let mut lexer = streaming_lexer_from_async_reader(reader);
+
+    let mut count = 0;
+    while lexer.next_token().await.unwrap() != Token::Eof {
+        count += 1;
+    }
+    assert_eq!(count, 40);
+    assert_eq!(lexer.current_position().offset, input.len());
+
+    // Each chunk is polled once while its delay runs and once to read it,
+    // plus a last read for the end of the input
+    let chunks = input.len().div_ceil(3);
+    assert!(
+        polls.load(Ordering::Relaxed) <= 2 * chunks + 1,
+        "{} polls for {chunks} chunks",
+        polls.load(Ordering::Relaxed)
+    );
+}
+
+#[tokio::test]
+async fn test_errors_match_the_blocking_lexer() {
+    for input in [
+        "a = \"unterminated",
+        "a = 1\nb = \"bad \\q escape\"",
+        "a = <<EOD\nno end\n",
+        "a = 10abc",
+        "a = /* open",
+        "a = é @",
+    ] {
+        let expected = sync_tokens(input);
+        assert!(expected.last().unwrap().is_err(), "{input}");
+        assert_eq!(
+            format!("{:?}", async_tokens(input, 2).await),
+            format!("{expected:?}"),
+            "{input}"
+        );
+    }
+
+    // Limits apply as the input arrives
+    let input = "key = value\n".repeat(200);
+    let mut lexer =
+        streaming_lexer_from_async_reader(Trickle::new(&input, 64)).with_max_document_size(1000);
+    let err = loop {
+        match lexer.next_token().await {
+            Ok(Token::Eof) => panic!("read past the size limit"),
+            Ok(_) => {}
+            Err(err) => break err,
+        }
+    };
+    assert!(
+        matches!(err, LexError::DocumentTooLarge { limit: 1000, .. }),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_long_token_in_tiny_chunks() {
+    let value = "x".repeat(200_000);
+    let input = format!("a = \"{value}\"\nb = 1\n");
+    // Rescanning the string from its start after every byte would not finish
+    let mut reader = Trickle::new(&input, 1);
+    reader.delay = Duration::ZERO;
+    let mut lexer = streaming_lexer_from_async_reader(reader);
+    lexer.next_token().await.unwrap();
+    lexer.next_token().await.unwrap();
+    match lexer.next_token().await.unwrap() {
+        Token::String { value: read, .. } => assert_eq!(read, value),
+        other => panic!("expected a string, got {other:?}"),
+    }
+    assert_eq!(lexer.next_token().await.unwrap(), Token::Key("b".into()));
+}
+
+#[tokio::test]
+async fn test_reading_a_file() {
+    let path = std::env::temp_dir().join(format!("ucl-async-{}.conf", std::process::id()));
+    std::fs::write(&path, "port = 80\n").unwrap();
+    let mut lexer = streaming_lexer_from_async_file(&path).await.unwrap();
+    assert_eq!(lexer.next_token().await.unwrap(), Token::Key("port".into()));
+    assert_eq!(lexer.next_token().await.unwrap(), Token::Equals);
+    assert_eq!(lexer.next_token().await.unwrap(), Token::Integer(80));
+    assert_eq!(lexer.next_token().await.unwrap(), Token::Eof);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        streaming_lexer_from_async_file(path.with_extension("missing"))
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,104 @@
+//! Tests for keys without a value read as `true`
+
+use serde::Deserialize;
+use ucl_lexer::{LexerConfig, ParseError, ParserConfig, UclDeserializer, UclParser, UclValue, ucl};
+
+fn parse_compat(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input)
+        .with_config(ParserConfig::libucl_compat())
+        .parse_document()
+}
+
+#[test]
+fn test_terminators() {
+    // Semicolon, comma, newline and closing brace
+    let value = parse_compat("pkg { enabled; signed, readonly\nverified }").unwrap();
+    assert_eq!(
+        value["pkg"],
+        ucl!({ "enabled": true, "signed": true, "readonly": true, "verified": true })
+    );
+
+    // The end of the document ends the last entry as well
+    let value = parse_compat("workers = 4\ndaemonize").unwrap();
+    assert_eq!(value, ucl!({ "workers": 4, "daemonize": true }));
+}
+
+#[test]
+fn test_value_on_the_same_line_is_an_assignment() {
+    let value =
+        parse_compat("options {\n    log_level debug\n    readonly\n    port 8080;\n}").unwrap();
+    assert_eq!(
+        value["options"],
+        ucl!({ "log_level": "debug", "readonly": true, "port": 8080 })
+    );
+
+    // A key on the next line starts a new entry, even for a section label
+    let value = parse_compat("enabled\nserver { port 80 }").unwrap();
+    assert_eq!(value, ucl!({ "enabled": true, "server": { "port": 80 } }));
+
+    // An opening brace or separator on the next line still belongs to the key
+    let value = parse_compat("server\n{\n    port = 80\n}\nname\n= \"app\"").unwrap();
+    assert_eq!(value, ucl!({ "server": { "port": 80 }, "name": "app" }));
+}
+
+#[test]
+fn test_trailing_comment() {
+    let input = "enabled # turn the feature on\nworkers = 2\n";
+    assert_eq!(
+        parse_compat(input).unwrap(),
+        ucl!({ "enabled": true, "workers": 2 })
+    );
+
+    let lexer_config = LexerConfig::new().with_save_comments(true);
+    let value = UclParser::with_lexer_config(input, lexer_config)
+        .with_config(ParserConfig::libucl_compat())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, ucl!({ "enabled": true, "workers": 2 }));
+}
+
+#[test]
+fn test_disabled_by_default_and_in_strict_mode() {
+    assert!(!ParserConfig::default().bare_key_as_true);
+    assert!(UclParser::new("a { enabled; }").parse_document().is_err());
+    assert!(
+        UclParser::new("a { enabled; }")
+            .with_config(ParserConfig::strict())
+            .parse_document()
+            .is_err()
+    );
+
+    let config = ParserConfig::strict().with_bare_key_as_true(true);
+    let value = UclParser::new("a { enabled; port = 1 }")
+        .with_config(config)
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["a"], ucl!({ "enabled": true, "port": 1 }));
+}
+
+#[test]
+fn test_deserialize_into_bool_fields() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Repository {
+        enabled: bool,
+        signed: Option<bool>,
+        mirror: Option<bool>,
+        url: String,
+    }
+
+    let input = "enabled;\nsigned\nurl = \"https://pkg.example.org\"";
+    let repository = Repository::deserialize(UclDeserializer::with_parser_config(
+        input,
+        ParserConfig::libucl_compat(),
+    ))
+    .unwrap();
+    assert_eq!(
+        repository,
+        Repository {
+            enabled: true,
+            signed: Some(true),
+            mirror: None,
+            url: "https://pkg.example.org".to_string(),
+        }
+    );
+}
@@ -1,5 +1,7 @@
+#![allow(clippy::approx_constant)]
+
 use serde_json::Value;
-use ucl_lexer::{UclError, from_str};
+use ucl_lexer::{ParseError, UclError, from_str};
 
 #[cfg(test)]
 mod bare_word_tests {
@@ -172,7 +174,6 @@ mod bare_word_tests {
     fn test_bare_word_validation_errors() {
         // Test bare words with special characters require quoting
         let invalid_configs = vec![
-            (r#"key = hello world"#, "Spaces should require quotes"),
             (
                 r#"key = hello@world"#,
                 "Special characters should require quotes",
@@ -185,8 +186,6 @@ mod bare_word_tests {
             (r#"key = hello}world"#, "Braces should require quotes"),
             (r#"key = hello[world"#, "Brackets should require quotes"),
             (r#"key = hello]world"#, "Brackets should require quotes"),
-            (r#"key = hello,world"#, "Comma should require quotes"),
-            (r#"key = hello;world"#, "Semicolon should require quotes"),
         ];
 
         for (config, description) in invalid_configs {
@@ -212,6 +211,33 @@ mod bare_word_tests {
                 }
             }
         }
+
+        // A space ends the value, so the next word is a second value on the line
+        let error = from_str::<Value>("key = hello world").unwrap_err();
+        assert!(
+            matches!(
+                error,
+                UclError::Parse(ParseError::MissingSeparator { found: "key", position })
+                    if position.column == 13
+            ),
+            "{error}"
+        );
+
+        // A comma or semicolon ends the entry, so the next word is a key without a value
+        for config in ["key = hello,world", "key = hello;world"] {
+            let error = from_str::<Value>(config).unwrap_err();
+            assert!(
+                matches!(
+                    &error,
+                    UclError::Parse(ParseError::UnexpectedToken {
+                        found: "end of file",
+                        expected,
+                        ..
+                    }) if expected[..] == ["'='", "':'", "'{'", "value"]
+                ),
+                "{config}: {error}"
+            );
+        }
     }
 
     #[test]
@@ -393,7 +419,6 @@ mod bare_word_tests {
     fn test_bare_word_error_suggestions() {
         // Test that error messages provide helpful suggestions
         let potentially_problematic_configs = vec![
-            r#"key = hello world"#,      // Space in bare word
             r#"key = hello@domain.com"#, // Email-like
             r#"key = hello#comment"#,    // Hash character
         ];
@@ -418,6 +443,15 @@ mod bare_word_tests {
                 }
             }
         }
+
+        // A space in a bare word explains where values end
+        let error = from_str::<Value>("key = hello world").unwrap_err();
+        assert!(
+            error.to_string().ends_with(
+                "Missing separator before key at 1:13; a value ends at a newline, ',' or ';'"
+            ),
+            "{error}"
+        );
     }
 
     #[test]
@@ -436,4 +470,38 @@ mod bare_word_tests {
         assert_eq!(result["chinese_word"], "中文");
         assert_eq!(result["mixed_unicode"], "hello世界");
     }
+
+    #[test]
+    fn test_hash_inside_bare_words() {
+        let config = r#"
+            color = #ff0000
+            channel: #general
+            tag = issue#42 # trailing comment
+            compact=#fff
+            # a whole-line comment
+            label = "quoted" # trailing comment
+        "#;
+
+        let result: Value = from_str(config).expect("Should parse hash values");
+        assert_eq!(result["color"], "#ff0000");
+        assert_eq!(result["channel"], "#general");
+        assert_eq!(result["tag"], "issue#42");
+        assert_eq!(result["compact"], "#fff");
+        assert_eq!(result["label"], "quoted");
+        assert_eq!(result.as_object().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_hash_followed_by_space_is_a_comment() {
+        let result: Value = from_str("key = # comment\n  42\nnext = 1 #note").unwrap();
+        assert_eq!(result["key"], 42);
+        assert_eq!(result["next"], 1);
+
+        // Without `=` or `:`, a `#` after whitespace still starts a comment,
+        // which leaves the key without a value on its line
+        let result: Value = from_str("color red #ff0000\nsize 3").unwrap();
+        assert_eq!(result["color"], "red");
+        let err = from_str::<Value>("color #ff0000\nsize 3").unwrap_err();
+        assert!(err.to_string().contains("found end of line"), "{err}");
+    }
 }
@@ -0,0 +1,105 @@
+//! Tests for deserializing strings borrowed from the input
+
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use ucl_lexer::{MapVariableHandler, UclError, from_fragments, from_str, from_str_with_variables};
+
+#[derive(Debug, Deserialize)]
+struct Text<'a>(#[serde(borrow)] Cow<'a, str>);
+
+impl Text<'_> {
+    fn is_borrowed(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+}
+
+fn handler() -> Box<MapVariableHandler> {
+    let mut handler = MapVariableHandler::new();
+    handler.insert("HOST".to_string(), "db.internal".to_string());
+    handler.insert("PORT".to_string(), "5432".to_string());
+    Box::new(handler)
+}
+
+#[test]
+fn test_str_fields_borrow_from_the_input() {
+    #[derive(Deserialize)]
+    struct Server<'a> {
+        name: &'a str,
+        root: &'a str,
+        tags: Vec<&'a str>,
+        empty: &'a str,
+    }
+
+    let input = "name = web\nroot = \"/srv\"\n\
+                 tags = [edge, 'eu', \"v2\"]\nempty = \"\"\n";
+    let server: Server = from_str(input).unwrap();
+    assert_eq!(server.name, "web");
+    assert_eq!(server.root, "/srv");
+    assert_eq!(server.tags, ["edge", "eu", "v2"]);
+    assert_eq!(server.empty, "");
+
+    let range = input.as_bytes().as_ptr_range();
+    for text in [server.name, server.root, server.tags[2]] {
+        assert!(range.contains(&text.as_ptr()), "{text:?}");
+    }
+}
+
+#[test]
+fn test_only_expanded_strings_are_owned() {
+    let input: String = (0..5000)
+        .map(|i| match i {
+            1000 => "host = \"${HOST}\"\n".to_string(),
+            4000 => "port = \"$PORT\"\n".to_string(),
+            _ => format!("key_{i} = \"value {i}\"\n"),
+        })
+        .collect();
+    let values: BTreeMap<String, Text> = from_str_with_variables(&input, handler()).unwrap();
+    assert_eq!(values.len(), 5000);
+    assert_eq!(values["host"].0, "db.internal");
+    assert_eq!(values["port"].0, "5432");
+    assert_eq!(values["key_1"].0, "value 1");
+
+    let owned: Vec<&str> = values
+        .iter()
+        .filter(|(_, text)| !text.is_borrowed())
+        .map(|(key, _)| key.as_str())
+        .collect();
+    assert_eq!(owned, ["host", "port"]);
+}
+
+#[test]
+fn test_changed_strings_are_owned() {
+    // Escapes change the text, and strings with variable references are
+    // copied even when the reference is left as written
+    let input = "escaped = \"a\\tb\"\nplain = 'a b'\nmissing = \"$UNDEFINED\"\n\
+                 literal = \"$$5\"\n";
+    let values: BTreeMap<String, Text> = from_str_with_variables(input, handler()).unwrap();
+    let borrowed: Vec<(&str, &str)> = values
+        .iter()
+        .filter(|(_, text)| text.is_borrowed())
+        .map(|(key, text)| (key.as_str(), text.0.as_ref()))
+        .collect();
+    assert_eq!(borrowed, [("plain", "a b")]);
+    assert_eq!(values["escaped"].0, "a\tb");
+    assert_eq!(values["literal"].0, "$5");
+    assert_eq!(values["missing"].0, "$UNDEFINED");
+
+    // A `&str` target cannot hold an owned string
+    #[derive(Debug, Deserialize)]
+    struct Escaped<'a> {
+        _escaped: &'a str,
+    }
+    let err = from_str::<Escaped>("_escaped = \"a\\tb\"").unwrap_err();
+    assert!(matches!(err, UclError::Serde(_)), "{err:?}");
+    assert!(err.to_string().contains("borrowed string"), "{err}");
+}
+
+#[test]
+fn test_fragments_lend_strings_within_one_fragment() {
+    let fragments = ["name = \"web\"\nhost = \"db.", "internal\"\n"];
+    let values: BTreeMap<String, Text> = from_fragments(&fragments).unwrap();
+    assert!(values["name"].is_borrowed());
+    assert_eq!(values["host"].0, "db.internal");
+    assert!(!values["host"].is_borrowed());
+}
@@ -0,0 +1,130 @@
+//! Tests for deserializing binary values from base64, hex and integer arrays
+
+use serde::{Deserialize, Serialize};
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{
+    BytesEncoding, ParserConfig, UclDeserializer, UclError, from_str, to_ucl_string, ucl,
+};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Secret {
+    name: String,
+    #[serde(with = "ucl_lexer::bytes")]
+    key: Vec<u8>,
+}
+
+/// Deterministic pseudo-random bytes
+fn blob(len: usize) -> Vec<u8> {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+fn parse_with(input: &str, encoding: BytesEncoding) -> Result<Secret, UclError> {
+    let config = ParserConfig::default().with_bytes_encoding(encoding);
+    Secret::deserialize(UclDeserializer::with_parser_config(input, config))
+}
+
+#[test]
+fn test_base64_and_hex_codecs() {
+    assert_eq!(BytesEncoding::Base64.encode(b"hello"), "aGVsbG8=");
+    assert_eq!(BytesEncoding::Base64.encode(b"hi"), "aGk=");
+    assert_eq!(BytesEncoding::Base64.encode(b"abc"), "YWJj");
+    assert_eq!(BytesEncoding::Base64.decode("aGVsbG8").unwrap(), b"hello");
+    assert_eq!(
+        BytesEncoding::Base64.decode("aGVs\n bG8=").unwrap(),
+        b"hello"
+    );
+    assert_eq!(BytesEncoding::Hex.encode(&[0, 171, 255]), "00abff");
+    assert_eq!(BytesEncoding::Hex.decode("00ABff").unwrap(), [0, 171, 255]);
+
+    assert!(BytesEncoding::Base64.decode("aGV!").is_err());
+    assert!(BytesEncoding::Base64.decode("a").is_err());
+    assert!(BytesEncoding::Base64.decode("aGk=aGk=").is_err());
+    assert!(BytesEncoding::Hex.decode("abc").is_err());
+}
+
+#[test]
+fn test_string_encodings() {
+    let secret: Secret = from_str("name = k\nkey = \"aGVsbG8=\"\n").unwrap();
+    assert_eq!(secret.key, b"hello");
+
+    let secret = parse_with("name = k\nkey = \"68656c6c6f\"\n", BytesEncoding::Hex).unwrap();
+    assert_eq!(secret.key, b"hello");
+
+    let secret = parse_with("name = k\nkey = \"héllo\"\n", BytesEncoding::Utf8).unwrap();
+    assert_eq!(secret.key, "héllo".as_bytes());
+}
+
+#[test]
+fn test_integer_array() {
+    let secret: Secret = from_str("name = k\nkey = [0, 127, 255]\n").unwrap();
+    assert_eq!(secret.key, [0, 127, 255]);
+
+    let err = from_str::<Secret>("name = k\nkey = [1, 256]\n").unwrap_err();
+    assert!(err.to_string().contains("element 1 is 256"), "{err}");
+}
+
+#[test]
+fn test_invalid_base64_names_the_key() {
+    let input = "name = k\ntls {\n    key = \"not base64!\"\n}\n";
+
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        name: String,
+        tls: Tls,
+    }
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Tls {
+        #[serde(with = "ucl_lexer::bytes")]
+        key: Vec<u8>,
+    }
+
+    let err = from_str::<Config>(input).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: Invalid bytes for 'tls.key' at line 3: \
+         base64 value is invalid: invalid character '!' at offset 10"
+    );
+    match err {
+        UclError::Serde(SerdeError::InvalidBytes { path, position, .. }) => {
+            assert_eq!(path, ["tls", "key"]);
+            assert_eq!(position.map(|p| (p.line, p.column)), Some((3, 5)));
+        }
+        other => panic!("expected invalid bytes error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_base64_round_trip_through_serializer() {
+    let secret = Secret {
+        name: "hmac".to_string(),
+        key: blob(1024),
+    };
+
+    // Byte fields are written as base64 strings
+    let json = serde_json::to_value(&secret).unwrap();
+    let encoded = json["key"].as_str().unwrap().to_string();
+    assert_eq!(encoded, BytesEncoding::Base64.encode(&secret.key));
+
+    let text = to_ucl_string(&ucl!({ "name": "hmac", "key": encoded }));
+    let parsed: Secret = from_str(&text).unwrap();
+    assert_eq!(parsed, secret);
+}
+
+#[test]
+fn test_hex_round_trip() {
+    let key = blob(1024);
+    let value = ucl!({ "name": "der", "key": BytesEncoding::Hex.encode(&key) });
+
+    let parsed = parse_with(&to_ucl_string(&value), BytesEncoding::Hex).unwrap();
+    assert_eq!(parsed.key, key);
+}
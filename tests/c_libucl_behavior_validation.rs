@@ -475,7 +475,7 @@ EOF
                 println!("Parsed structure: {:#}", parsed);
 
                 // Validate individual syntax patterns
-                let validations = vec![
+                let validations = [
                     validator.validate_syntax(
                         "nginx_implicit_object",
                         "events { worker_connections = 1024 }",
@@ -4,8 +4,10 @@
 //! reference implementation by testing against real-world UCL configurations
 //! and edge cases that are known to work with C libucl.
 
+#![allow(clippy::approx_constant)]
+
 use serde_json::Value;
-use ucl_lexer::{UclError, from_str};
+use ucl_lexer::{UclError, UclValue, from_str};
 
 #[cfg(test)]
 mod c_libucl_compatibility {
@@ -463,11 +465,11 @@ EOF
                 // Test null conversion
                 assert_eq!(obj["empty"], Value::Null);
 
-                // Test special float values
-                if let Some(inf_val) = obj.get("infinity") {
-                    assert!(inf_val.as_f64().unwrap().is_infinite());
-                    assert!(inf_val.as_f64().unwrap() > 0.0);
-                }
+                // Test special float values, which JSON values cannot hold
+                let tree: UclValue = config.parse().unwrap();
+                assert_eq!(tree["infinity"], f64::INFINITY);
+                assert_eq!(tree["negative_infinity"], f64::NEG_INFINITY);
+                assert!(matches!(tree["not_a_number"], UclValue::Float(value) if value.is_nan()));
 
                 // Test string values
                 assert_eq!(obj["environment"], "production");
@@ -572,7 +574,7 @@ EOF
     #[test]
     fn test_error_handling_compatibility() {
         // Test error handling for various malformed inputs
-        let invalid_configs = vec![
+        let invalid_configs = [
             // Unterminated string
             r#"key = "unterminated"#,
             // Invalid number
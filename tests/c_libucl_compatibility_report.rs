@@ -3,6 +3,8 @@
 //! This module generates a comprehensive report of compatibility with C libucl
 //! by testing various UCL features and documenting what works and what doesn't.
 
+#![allow(clippy::approx_constant)]
+
 use serde_json::Value;
 use ucl_lexer::from_str;
 
@@ -22,6 +24,12 @@ pub struct CompatibilityReport {
     pub failed_tests: usize,
 }
 
+impl Default for CompatibilityReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CompatibilityReport {
     pub fn new() -> Self {
         Self {
@@ -277,10 +285,10 @@ fn test_extended_unicode() -> CompatibilityTestResult {
             let obj = parsed.as_object().unwrap();
             let mut notes = Vec::new();
 
-            if let Some(basic) = obj.get("basic") {
-                if basic.as_str().unwrap().contains("AB") {
-                    notes.push("Basic Unicode escapes work".to_string());
-                }
+            if let Some(basic) = obj.get("basic")
+                && basic.as_str().unwrap().contains("AB")
+            {
+                notes.push("Basic Unicode escapes work".to_string());
             }
 
             if let Some(extended) = obj.get("extended") {
@@ -387,10 +395,10 @@ fn test_bare_word_values() -> CompatibilityTestResult {
             if obj.get("empty") == Some(&Value::Null) {
                 notes.push("Null keyword works".to_string());
             }
-            if let Some(inf_val) = obj.get("infinity") {
-                if inf_val.as_f64().map(|f| f.is_infinite()).unwrap_or(false) {
-                    notes.push("Infinity keyword works".to_string());
-                }
+            if let Some(inf_val) = obj.get("infinity")
+                && inf_val.as_f64().map(|f| f.is_infinite()).unwrap_or(false)
+            {
+                notes.push("Infinity keyword works".to_string());
             }
 
             CompatibilityTestResult {
@@ -518,10 +526,10 @@ fn test_number_formats() -> CompatibilityTestResult {
             if obj.get("decimal") == Some(&Value::Number(42.into())) {
                 notes.push("Decimal integers work".to_string());
             }
-            if let Some(float_val) = obj.get("float") {
-                if (float_val.as_f64().unwrap() - 3.14).abs() < 0.01 {
-                    notes.push("Float numbers work".to_string());
-                }
+            if let Some(float_val) = obj.get("float")
+                && (float_val.as_f64().unwrap() - 3.14).abs() < 0.01
+            {
+                notes.push("Float numbers work".to_string());
             }
             if obj.get("scientific").is_some() {
                 notes.push("Scientific notation parsing attempted".to_string());
@@ -567,20 +575,22 @@ fn test_array_formats() -> CompatibilityTestResult {
             let obj = parsed.as_object().unwrap();
             let mut notes = Vec::new();
 
-            if let Some(simple) = obj.get("simple") {
-                if simple.is_array() && simple.as_array().unwrap().len() == 3 {
-                    notes.push("Simple arrays work".to_string());
-                }
-            }
-            if let Some(mixed) = obj.get("mixed") {
-                if mixed.is_array() && mixed.as_array().unwrap().len() == 3 {
-                    notes.push("Mixed-type arrays work".to_string());
-                }
-            }
-            if let Some(nested) = obj.get("nested") {
-                if nested.is_array() {
-                    notes.push("Nested arrays work".to_string());
-                }
+            if let Some(simple) = obj.get("simple")
+                && simple.is_array()
+                && simple.as_array().unwrap().len() == 3
+            {
+                notes.push("Simple arrays work".to_string());
+            }
+            if let Some(mixed) = obj.get("mixed")
+                && mixed.is_array()
+                && mixed.as_array().unwrap().len() == 3
+            {
+                notes.push("Mixed-type arrays work".to_string());
+            }
+            if let Some(nested) = obj.get("nested")
+                && nested.is_array()
+            {
+                notes.push("Nested arrays work".to_string());
             }
 
             CompatibilityTestResult {
@@ -0,0 +1,133 @@
+//! Tests for cancelling a parse through `ParserConfig::cancellation`
+
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use ucl_lexer::cancellation::CHECK_INTERVAL;
+use ucl_lexer::{
+    CancellationFlag, ParseError, ParserConfig, UclDeserializer, UclError, UclParser, UclValue,
+    ValidationHook, VariableContext,
+};
+
+fn cancelled_config() -> ParserConfig {
+    let flag = CancellationFlag::new();
+    flag.cancel();
+    ParserConfig::new().with_cancellation(Some(flag))
+}
+
+/// Signals the first value parsed, and counts the values parsed after the flag was set
+struct Progress {
+    flag: CancellationFlag,
+    started: Arc<AtomicBool>,
+    after_cancel: Arc<AtomicUsize>,
+}
+
+impl ValidationHook for Progress {
+    fn validate_value(
+        &self,
+        _value: &UclValue,
+        _context: &VariableContext,
+    ) -> Result<Option<UclValue>, ParseError> {
+        self.started.store(true, Ordering::Relaxed);
+        if self.flag.is_cancelled() {
+            self.after_cancel.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(None)
+    }
+}
+
+#[test]
+fn test_cancel_from_another_thread() {
+    let input: String = (0..1_000_000)
+        .map(|i| format!("key_{i} = {i};\n"))
+        .collect();
+    let flag = CancellationFlag::new();
+    let started = Arc::new(AtomicBool::new(false));
+    let after_cancel = Arc::new(AtomicUsize::new(0));
+
+    let parse = {
+        let config = ParserConfig::new()
+            .with_cancellation(Some(flag.clone()))
+            .with_max_document_size(usize::MAX);
+        let progress = Progress {
+            flag: flag.clone(),
+            started: Arc::clone(&started),
+            after_cancel: Arc::clone(&after_cancel),
+        };
+        thread::spawn(move || {
+            let mut parser = UclParser::new(&input).with_config(config);
+            parser.add_validation_hook(Box::new(progress));
+            let result = parser.parse_document();
+            (result, Instant::now())
+        })
+    };
+
+    while !started.load(Ordering::Relaxed) {
+        thread::yield_now();
+    }
+    let cancelled_at = Instant::now();
+    flag.cancel();
+
+    let (result, finished_at) = parse.join().unwrap();
+    match result {
+        Err(ParseError::Cancelled { position }) => assert!(position.line > 1),
+        other => panic!("expected a cancelled parse, got {:?}", other.map(|_| ())),
+    }
+    // A check every CHECK_INTERVAL tokens, at three tokens per entry
+    assert!(after_cancel.load(Ordering::Relaxed) <= CHECK_INTERVAL / 3 + 1);
+    assert!(finished_at - cancelled_at < Duration::from_secs(1));
+}
+
+#[test]
+fn test_flag_set_before_parsing() {
+    let err = UclParser::new("a = 1")
+        .with_config(cancelled_config())
+        .parse_document()
+        .unwrap_err();
+    assert!(matches!(err, ParseError::Cancelled { .. }), "{err:?}");
+    assert_eq!(err.code(), "parse/cancelled");
+    assert_eq!(err.to_string(), "Parsing was cancelled at 1:1");
+
+    // The lenient parser stops instead of collecting the error and going on
+    let (value, errors) = UclParser::new("a { b = 1 }\nc { d = 2 }")
+        .with_config(cancelled_config())
+        .parse_document_lenient();
+    assert_eq!(value, None);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code(), "ucl/cancelled");
+}
+
+#[test]
+fn test_deserializer_reports_cancelled() {
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        _name: String,
+    }
+
+    let err = Config::deserialize(UclDeserializer::with_parser_config(
+        "name = web",
+        cancelled_config(),
+    ))
+    .unwrap_err();
+    assert!(matches!(err, UclError::Cancelled { .. }), "{err:?}");
+    assert_eq!(err.code(), "ucl/cancelled");
+    assert_eq!(err.position().map(|position| position.line), Some(1));
+}
+
+#[test]
+fn test_unset_flag_changes_nothing() {
+    let input: String = (0..5_000)
+        .map(|i| format!("s{i} {{ v = {i} }}\n"))
+        .collect();
+    let flag = CancellationFlag::from_arc(Arc::new(AtomicBool::new(false)));
+    let config = ParserConfig::new().with_cancellation(Some(flag.clone()));
+    let value = UclParser::new(&input)
+        .with_config(config.clone())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, UclParser::new(&input).parse_document().unwrap());
+    assert!(!flag.is_cancelled());
+    assert!(config.cancellation().is_some());
+}
@@ -0,0 +1,154 @@
+//! Tests for comment length and nesting limits on string and streaming input
+
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+use ucl_lexer::{
+    LexError, LexerConfig, ParseError, Token, UclError, UclLexer, UclParser,
+    streaming_lexer_from_reader,
+};
+
+/// Reads every token from a streaming lexer and returns the first error
+fn stream(input: &str, nesting: usize) -> Result<Vec<Token<'static>>, LexError> {
+    let mut lexer = streaming_lexer_from_reader(Cursor::new(input.as_bytes().to_vec()))
+        .with_max_comment_nesting(nesting);
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token()? {
+            Token::Eof => return Ok(tokens),
+            token => tokens.push(token),
+        }
+    }
+}
+
+fn nested(depth: usize) -> String {
+    format!("{}{}a = 1", "/*".repeat(depth), "*/".repeat(depth))
+}
+
+#[test]
+fn test_openers_only_fail_fast() {
+    let input = "/*".repeat(100_000);
+    let started = Instant::now();
+
+    let err = UclLexer::new(&input).next_token().unwrap_err();
+    assert!(matches!(
+        err,
+        LexError::CommentNestingTooDeep { limit: 32, position } if position.column == 65
+    ));
+
+    let err = UclParser::new(&input).parse_document().unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ParseError::Lex(LexError::CommentNestingTooDeep { limit: 32, position })
+                if position.column == 65
+        ),
+        "{err:?}"
+    );
+    assert_eq!(err.code(), "lex/comment-nesting-too-deep");
+
+    let errors = UclParser::new(&input).validate().unwrap_err();
+    assert!(matches!(
+        errors[..],
+        [UclError::Parse(ParseError::Lex(
+            LexError::CommentNestingTooDeep { limit: 32, .. }
+        ))]
+    ));
+
+    let (_, errors) = UclParser::parse_lenient(&input);
+    assert!(!errors.is_empty());
+    assert!(matches!(
+        errors[0],
+        UclError::Parse(ParseError::Lex(LexError::CommentNestingTooDeep {
+            limit: 32,
+            ..
+        }))
+    ));
+
+    let err = stream(&input, 32).unwrap_err();
+    assert!(matches!(
+        err,
+        LexError::CommentNestingTooDeep { limit: 32, .. }
+    ));
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_nesting_within_limit() {
+    let value = UclParser::new(&nested(32)).parse_document().unwrap();
+    assert_eq!(value["a"], 1);
+
+    assert!(matches!(
+        UclParser::new(&nested(33)).parse_document(),
+        Err(ParseError::Lex(LexError::CommentNestingTooDeep {
+            limit: 32,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn test_configured_nesting_limit() {
+    let config = LexerConfig::new().with_max_comment_nesting(2);
+    let (within, beyond) = (nested(2), nested(3));
+    let mut lexer = UclLexer::with_config(&within, config.clone());
+    assert_eq!(lexer.next_token().unwrap(), Token::Key("a".into()));
+
+    let mut lexer = UclLexer::with_config(&beyond, config);
+    assert!(matches!(
+        lexer.next_token(),
+        Err(LexError::CommentNestingTooDeep { limit: 2, .. })
+    ));
+}
+
+#[test]
+fn test_streaming_nested_comments() {
+    let tokens = stream(&nested(3), 32).unwrap();
+    assert_eq!(tokens[0], Token::Key("a".into()));
+
+    assert!(matches!(
+        stream(&nested(3), 2),
+        Err(LexError::CommentNestingTooDeep { limit: 2, .. })
+    ));
+    assert!(matches!(
+        stream("/* /* */ a = 1", 32),
+        Err(LexError::UnterminatedComment { .. })
+    ));
+}
+
+#[test]
+fn test_streaming_skips_strings_in_comments() {
+    let tokens = stream(r#"/* "/*" '/*' "\" /*" */ a = 1"#, 32).unwrap();
+    assert_eq!(tokens[0], Token::Key("a".into()));
+}
+
+#[test]
+fn test_comment_length_counts_bytes() {
+    let config = LexerConfig::new().with_max_comment_length(16);
+    // Seven two-byte characters fit in 16 bytes, nine do not
+    let fits = format!("# {}\na = 1", "é".repeat(7));
+    let too_long = format!("# {}\na = 1", "é".repeat(9));
+
+    assert!(
+        UclLexer::with_config(&fits, config.clone())
+            .next_token()
+            .is_ok()
+    );
+    assert!(matches!(
+        UclLexer::with_config(&too_long, config.clone()).next_token(),
+        Err(LexError::UnterminatedComment { .. })
+    ));
+
+    let too_long = format!("/* {} */a = 1", "é".repeat(9));
+    assert!(matches!(
+        UclLexer::with_config(&too_long, config).next_token(),
+        Err(LexError::UnterminatedComment { .. })
+    ));
+
+    let mut lexer =
+        streaming_lexer_from_reader(Cursor::new(too_long.into_bytes())).with_max_comment_length(16);
+    assert!(matches!(
+        lexer.next_token(),
+        Err(LexError::UnterminatedComment { .. })
+    ));
+}
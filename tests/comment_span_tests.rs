@@ -0,0 +1,128 @@
+//! Tests for the spans and terminators recorded for saved comments
+
+use ucl_lexer::lexer::{CommentInfo, CommentTerminator, CommentType};
+use ucl_lexer::{LexerConfig, Token, UclLexer, UclParser};
+
+/// Lexes `input` to the end and returns the comments it saved
+fn comments(input: &str) -> Vec<CommentInfo<'_>> {
+    let mut lexer = UclLexer::with_config(input, LexerConfig::new().with_save_comments(true));
+    while lexer.next_token().unwrap() != Token::Eof {}
+    lexer.comments().to_vec()
+}
+
+/// Returns the source text a comment's span covers
+fn raw<'a>(input: &'a str, comment: &CommentInfo) -> &'a str {
+    &input[comment.span.start.offset..comment.span.end.offset]
+}
+
+#[test]
+fn test_spans_include_markers() {
+    let input = "# hash\na = 1 // slashes\r\nb = /* block */ 2\n";
+    let comments = comments(input);
+    assert_eq!(comments.len(), 3);
+
+    assert_eq!(comments[0].comment_type, CommentType::SingleLine);
+    assert_eq!(
+        (comments[0].span.start.offset, comments[0].span.end.offset),
+        (0, 6)
+    );
+    assert_eq!(raw(input, &comments[0]), "# hash");
+    assert_eq!(comments[0].terminator, CommentTerminator::LineFeed);
+
+    assert_eq!(comments[1].comment_type, CommentType::CppStyle);
+    assert_eq!(
+        (comments[1].span.start.offset, comments[1].span.end.offset),
+        (13, 23)
+    );
+    assert_eq!(raw(input, &comments[1]), "// slashes");
+    assert_eq!(comments[1].terminator, CommentTerminator::CrLf);
+
+    assert_eq!(comments[2].comment_type, CommentType::MultiLine);
+    assert_eq!(
+        (comments[2].span.start.offset, comments[2].span.end.offset),
+        (29, 40)
+    );
+    assert_eq!(raw(input, &comments[2]), "/* block */");
+    assert_eq!(
+        comments[2].terminator,
+        CommentTerminator::Closed { nested: false }
+    );
+
+    // End positions are line and column too, and the start is the position
+    let end = &comments[2].span.end;
+    assert_eq!((end.line, end.column), (3, 16));
+    for comment in &comments {
+        assert_eq!(comment.span.start, comment.position);
+    }
+}
+
+#[test]
+fn test_comment_at_the_end_of_the_input() {
+    for (input, start, terminator) in [
+        ("a = 1\n# last", 6, CommentTerminator::EndOfInput),
+        ("a = 1 // last", 6, CommentTerminator::EndOfInput),
+        ("a = 1\n#", 6, CommentTerminator::EndOfInput),
+        ("a = 1\n# last\r", 6, CommentTerminator::CarriageReturn),
+        (
+            "a = 1 /* last */",
+            6,
+            CommentTerminator::Closed { nested: false },
+        ),
+    ] {
+        let comments = comments(input);
+        assert_eq!(comments.len(), 1, "{input:?}");
+        let span = &comments[0].span;
+        assert_eq!(span.start.offset, start, "{input:?}");
+        assert_eq!(
+            span.end.offset,
+            input.trim_end_matches('\r').len(),
+            "{input:?}"
+        );
+        assert_eq!(comments[0].terminator, terminator, "{input:?}");
+    }
+}
+
+#[test]
+fn test_nested_block_comments() {
+    let input = "/* outer /* inner */ still outer */ a = 1 /* \"/* quoted */\" */";
+    let comments = comments(input);
+    assert_eq!(
+        raw(input, &comments[0]),
+        "/* outer /* inner */ still outer */"
+    );
+    assert_eq!(
+        comments[0].terminator,
+        CommentTerminator::Closed { nested: true }
+    );
+    // A marker inside a quoted string does not nest
+    assert_eq!(raw(input, &comments[1]), "/* \"/* quoted */\" */");
+    assert_eq!(
+        comments[1].terminator,
+        CommentTerminator::Closed { nested: false }
+    );
+}
+
+#[test]
+fn test_terminators_match_the_input() {
+    let input = "# header\r\nport = 80 # http\n/* a\n/* b */ */\nname = web // last";
+    let mut parser =
+        UclParser::with_lexer_config(input, LexerConfig::new().with_save_comments(true));
+    parser.parse_document().unwrap();
+    assert_eq!(parser.comments().len(), 4);
+
+    // The input after each comment starts with the recorded line break
+    for comment in parser.comments() {
+        let rest = &input[comment.span.end.offset..];
+        match comment.terminator {
+            CommentTerminator::LineFeed => assert!(rest.starts_with('\n')),
+            CommentTerminator::CrLf => assert!(rest.starts_with("\r\n")),
+            CommentTerminator::CarriageReturn => assert!(rest.starts_with('\r')),
+            CommentTerminator::EndOfInput => assert!(rest.is_empty()),
+            CommentTerminator::Closed { .. } => assert!(raw(input, comment).ends_with("*/")),
+        }
+    }
+    assert_eq!(
+        parser.comments()[2].terminator,
+        CommentTerminator::Closed { nested: true }
+    );
+}
@@ -3,9 +3,11 @@
 //! These tests verify that our UCL parser is compatible with configurations
 //! that work with other UCL implementations like libucl.
 
+#![allow(clippy::approx_constant)]
+
 use serde::Deserialize;
 use std::collections::HashMap;
-use ucl_lexer::from_str;
+use ucl_lexer::{LexerConfig, UclDeserializer, from_str};
 
 fn assert_special_infinite(value: &serde_json::Value, positive: bool) {
     if let Some(f) = value.as_f64() {
@@ -324,7 +326,13 @@ SCRIPT
         "1f2e3d4c5b6a7890abcdef1234567890"
     );
     assert_eq!(parsed.directories.len(), 2);
-    assert!(parsed.scripts.get("pre-install").unwrap().contains("Installing"));
+    assert!(
+        parsed
+            .scripts
+            .get("pre-install")
+            .unwrap()
+            .contains("Installing")
+    );
     assert_eq!(parsed.options.get("SSL").map(String::as_str), Some("on"));
     assert_eq!(parsed.categories.len(), 2);
     assert_eq!(parsed.licenses[0], "BSD2CLAUSE");
@@ -554,7 +562,9 @@ fn test_ucl_number_formats_compatibility() {
         not_a_number: f64,
     }
 
-    let parsed: NumberFormats = from_str(config).expect("Failed to parse number formats");
+    // libucl reads `100m` as a size
+    let deserializer = UclDeserializer::with_lexer_config(config, LexerConfig::libucl_compat());
+    let parsed = NumberFormats::deserialize(deserializer).expect("Failed to parse number formats");
 
     // Verify integer parsing
     assert_eq!(parsed.integers.decimal, 42);
@@ -573,10 +583,7 @@ fn test_ucl_number_formats_compatibility() {
     assert_eq!(parsed.sizes_binary.kilobytes, 64 * 1024);
     assert_eq!(parsed.sizes_binary.megabytes, 512 * 1024 * 1024);
     assert_eq!(parsed.sizes_binary.gigabytes, 2 * 1024 * 1024 * 1024);
-    assert_eq!(
-        parsed.sizes_binary.terabytes,
-        1 * 1024 * 1024 * 1024 * 1024
-    );
+    assert_eq!(parsed.sizes_binary.terabytes, 1024 * 1024 * 1024 * 1024);
     assert_eq!(parsed.sizes_decimal.kilobytes_decimal, 1_000_000);
     assert_eq!(parsed.sizes_decimal.megabytes_decimal, 100_000_000);
     assert_eq!(parsed.sizes_decimal.gigabytes_decimal, 5_000_000_000);
@@ -968,8 +975,6 @@ fn test_mixed_syntax_styles_compatibility() {
 fn test_error_handling_backward_compatibility() {
     // Test that existing error handling for malformed explicit syntax is preserved
     let malformed_configs = vec![
-        // Missing separator
-        r#"{ key "value" }"#,
         // Invalid separator
         r#"{ key ~ "value" }"#,
         // Missing value
@@ -977,6 +982,7 @@ fn test_error_handling_backward_compatibility() {
         // Unterminated string
         r#"{ key = "unterminated }"#,
         // Note: 123abc now parses as a string "123abc", not an error
+        // Note: `{ key "value" }` is a relaxed assignment, rejected only by ParserConfig::strict()
     ];
 
     for config in malformed_configs {
@@ -1093,7 +1099,6 @@ fn test_bare_word_validation_errors() {
         r#"key = bare:word"#, // Colon not allowed
         r#"key = bare,word"#, // Comma not allowed
         r#"key = bare;word"#, // Semicolon not allowed
-        r#"key = bare#word"#, // Hash not allowed
         r#"key = bare"word"#, // Quote not allowed
     ];
 
@@ -0,0 +1,121 @@
+//! Tests for constants registered on `ParserConfig`
+
+use std::collections::HashMap;
+use ucl_lexer::{ParseError, ParserConfig, UclParser, UclValue, ucl};
+
+fn parse_with(input: &str, config: ParserConfig) -> Result<UclValue, ParseError> {
+    UclParser::new(input).with_config(config).parse_document()
+}
+
+fn log_levels() -> ParserConfig {
+    let mut config = ParserConfig::new();
+    config
+        .register_constants(HashMap::from([
+            ("DEBUG".to_string(), UclValue::Integer(10)),
+            ("WARN".to_string(), UclValue::Integer(30)),
+        ]))
+        .unwrap();
+    config
+}
+
+#[test]
+fn test_constants_resolve_to_integers() {
+    let value = parse_with(
+        "log_level = DEBUG\nlevels = [ DEBUG, WARN ]\nserver { level WARN }",
+        log_levels(),
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "log_level": 10, "levels": [10, 30], "server": { "level": 30 } })
+    );
+}
+
+#[test]
+fn test_constants_resolve_to_nested_objects() {
+    let mut config = ParserConfig::new();
+    config
+        .register_constant(
+            "round_robin",
+            ucl!({ "strategy": "rr", "weights": { "primary": 2, "backup": 1 } }),
+        )
+        .unwrap();
+
+    let value = parse_with("upstream { mode = round_robin }", config).unwrap();
+    assert_eq!(value["upstream"]["mode"]["strategy"], "rr");
+    assert_eq!(value["upstream"]["mode"]["weights"]["primary"], 2);
+}
+
+#[test]
+fn test_constants_never_apply_to_keys() {
+    let value = parse_with("DEBUG = WARN\nDEBUG_MODE = DEBUG", log_levels()).unwrap();
+    assert_eq!(value, ucl!({ "DEBUG": 30, "DEBUG_MODE": 10 }));
+}
+
+#[test]
+fn test_unregistered_words_stay_strings() {
+    let value = parse_with("log_level = INFO\nhost = localhost", log_levels()).unwrap();
+    assert_eq!(value, ucl!({ "log_level": "INFO", "host": "localhost" }));
+
+    // Quoted strings are never replaced
+    let value = parse_with("log_level = \"DEBUG\"", log_levels()).unwrap();
+    assert_eq!(value["log_level"], "DEBUG");
+}
+
+#[test]
+fn test_strict_constants_reject_unknown_words() {
+    let config = log_levels().with_strict_constants(true);
+    let err = parse_with("log_level = DEBUG\nfallback = INFO", config.clone()).unwrap_err();
+    match err {
+        ParseError::UnknownConstant { name, position } => {
+            assert_eq!(name, "INFO");
+            assert_eq!((position.line, position.column), (2, 12));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+
+    // Keywords and quoted strings are still accepted
+    let value = parse_with("a = DEBUG\nb = true\nc = null\nd = \"INFO\"", config).unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "a": 10, "b": true, "c": UclValue::Null, "d": "INFO" })
+    );
+}
+
+#[test]
+fn test_constant_case_sensitivity() {
+    let input = "a = debug\nb = Debug";
+    let value = parse_with(input, log_levels()).unwrap();
+    assert_eq!(value, ucl!({ "a": "debug", "b": "Debug" }));
+
+    let config = log_levels().with_constants_case_sensitive(false);
+    let value = parse_with(input, config).unwrap();
+    assert_eq!(value, ucl!({ "a": 10, "b": 10 }));
+}
+
+#[test]
+fn test_shadowing_keywords_is_rejected() {
+    let mut config = ParserConfig::new();
+    for name in ["true", "TRUE", "off", "null", "nan"] {
+        let err = config
+            .register_constant(name, UclValue::Integer(1))
+            .unwrap_err();
+        assert!(
+            matches!(&err, ParseError::ReservedConstant { name: rejected } if rejected == name),
+            "{err}"
+        );
+    }
+
+    // A bulk registration with one keyword registers nothing
+    let err = config
+        .register_constants(HashMap::from([
+            ("DEBUG".to_string(), UclValue::Integer(10)),
+            ("yes".to_string(), UclValue::Integer(0)),
+        ]))
+        .unwrap_err();
+    assert!(matches!(err, ParseError::ReservedConstant { .. }));
+    assert!(config.constants.is_empty());
+
+    let value = parse_with("enabled = true", config).unwrap();
+    assert_eq!(value["enabled"], true);
+}
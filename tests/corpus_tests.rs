@@ -0,0 +1,131 @@
+//! Snapshot tests over a corpus of real-world UCL files
+//!
+//! Each `tests/corpus/<file>` is parsed into a `UclValue` and written as JSON
+//! with keys in document order, then compared with `<file>.json` next to it.
+//! Run with `UPDATE_GOLDEN=1` to rewrite the snapshots after an intended
+//! change. Adding a file to the corpus takes one line in `corpus_tests!`.
+
+use std::fs;
+use std::path::Path;
+use ucl_lexer::{MapVariableHandler, UclParser, UclValue};
+
+/// Variables the corpus files refer to, with the values their programs would set
+const VARIABLES: &[(&str, &str)] = &[
+    ("ABI", "FreeBSD:14:amd64"),
+    ("VERSION_MINOR", "1"),
+    ("CONFDIR", "/usr/local/etc/rspamd"),
+    ("LOCAL_CONFDIR", "/usr/local/etc/rspamd"),
+    ("DBDIR", "/var/db/rspamd"),
+    ("PLUGINSDIR", "/usr/local/share/rspamd/plugins"),
+    ("WWWDIR", "/usr/local/share/rspamd/www"),
+];
+
+macro_rules! corpus_tests {
+    ($($(#[$attr:meta])* $name:ident => $file:literal,)*) => {
+        $(
+            #[test]
+            $(#[$attr])*
+            fn $name() {
+                check_snapshot($file);
+            }
+        )*
+    };
+}
+
+corpus_tests! {
+    freebsd_pkg => "freebsd_pkg.conf",
+    freebsd_repo => "freebsd_repo.conf",
+    nginx_server => "nginx_server.ucl",
+    readme_numbers => "readme_numbers.ucl",
+    readme_strings => "readme_strings.ucl",
+    rspamd_actions => "rspamd_actions.ucl",
+    rspamd_options => "rspamd_options.ucl",
+    rspamd_worker => "rspamd_worker.ucl",
+
+    // Values written without `=` after a key keep their suffix as text
+    // instead of becoming durations as in libucl; the snapshot holds the
+    // libucl result.
+    #[ignore = "suffixed values without a separator are read as strings"]
+    nginx_durations => "nginx_durations.ucl",
+    // Only top-level `.include` directives are resolved; those inside a
+    // section, and libucl's `;` between include parameters, are not
+    // supported yet. The snapshot is written once they are.
+    #[ignore = "include directives inside sections are not supported"]
+    rspamd_include => "rspamd_include.ucl",
+}
+
+fn check_snapshot(file: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let sample = dir.join(file);
+    let input = fs::read_to_string(&sample).unwrap();
+
+    let mut handler = MapVariableHandler::new();
+    for (name, value) in VARIABLES {
+        handler.insert(name.to_string(), value.to_string());
+    }
+    let value = UclParser::with_variable_handler(&input, Box::new(handler))
+        .parse_document()
+        .unwrap_or_else(|e| panic!("{}: {}", sample.display(), e));
+
+    let mut actual = String::new();
+    write_json(&value, 0, &mut actual);
+    actual.push('\n');
+
+    let snapshot = dir.join(format!("{}.json", file));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&snapshot, &actual).unwrap();
+        return;
+    }
+    let expected =
+        fs::read_to_string(&snapshot).unwrap_or_else(|_| panic!("missing {}", snapshot.display()));
+    assert_eq!(actual, expected, "{}", sample.display());
+}
+
+/// Writes a value as indented JSON, keeping object keys in document order
+///
+/// Durations are written as seconds and non-finite floats as strings, so
+/// every value has one stable spelling.
+fn write_json(value: &UclValue, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent + 1);
+    match value {
+        UclValue::String(s) => out.push_str(&serde_json::to_string(s).unwrap()),
+        UclValue::Integer(i) => out.push_str(&i.to_string()),
+        UclValue::Decimal(text) => out.push_str(text),
+        UclValue::Float(f) | UclValue::Time(f) => match serde_json::Number::from_f64(*f) {
+            Some(number) => out.push_str(&number.to_string()),
+            None => out.push_str(&format!("\"{}\"", f)),
+        },
+        UclValue::Boolean(b) => out.push_str(&b.to_string()),
+        UclValue::Null => out.push_str("null"),
+        UclValue::Object(object) if object.is_empty() => out.push_str("{}"),
+        UclValue::Object(object) => {
+            out.push_str("{\n");
+            for (index, (key, value)) in object.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                out.push_str(&serde_json::to_string(key.as_str()).unwrap());
+                out.push_str(": ");
+                write_json(value, indent + 1, out);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+            out.push('}');
+        }
+        UclValue::Array(array) if array.is_empty() => out.push_str("[]"),
+        UclValue::Array(array) => {
+            out.push_str("[\n");
+            for (index, value) in array.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&pad);
+                write_json(value, indent + 1, out);
+            }
+            out.push('\n');
+            out.push_str(&"  ".repeat(indent));
+            out.push(']');
+        }
+    }
+}
@@ -155,14 +155,12 @@ mod cpp_comment_tests {
         assert!(result.is_object(), "Top-level value should be an object");
 
         // Verify that comments are collected when preservation is enabled
-        let mut lexer_config = LexerConfig::default();
-        lexer_config.save_comments = true;
+        let lexer_config = LexerConfig::new().with_save_comments(true);
         let mut lexer = UclLexer::with_config(config, lexer_config);
 
         loop {
-            match lexer.next_token().expect("Lexer should produce tokens") {
-                Token::Eof => break,
-                _ => {}
+            if lexer.next_token().expect("Lexer should produce tokens") == Token::Eof {
+                break;
             }
         }
 
@@ -0,0 +1,291 @@
+//! Tests for lossless editing with `UclDocument`
+
+use ucl_lexer::{CstNode, EditError, UclDocument, UclParser, UclValue, ucl};
+
+const NGINX: &str = r#"# Main server configuration
+user nginx;
+worker_processes   auto;   # one per core
+
+events {
+    worker_connections 1024;
+    multi_accept on;
+}
+
+http {
+    sendfile        on;
+    keepalive_timeout  65s;
+
+    server {
+        listen 80;
+        server_name "example.com";
+        /* static files */
+        root /var/www/html;
+    }
+}
+"#;
+
+const PKG: &str = r#"# FreeBSD pkg repository
+FreeBSD: {
+  url: "pkg+http://pkg.FreeBSD.org/${ABI}/quarterly",
+  mirror_type: "srv",
+  signature_type: "fingerprints",
+  fingerprints: "/usr/share/keys/pkg",
+  enabled: yes
+}
+"#;
+
+const RSPAMD: &str = r#"// Rspamd options
+options {
+    pidfile = "$RUNDIR/rspamd.pid";
+    filters = ["chartable", "dkim", "regexp"];
+    dns {
+        timeout = 1s;
+        sockets = 16;
+        retransmits = 5;
+    }
+    classify_headers = [
+        "User-Agent",
+        "X-Mailer",
+        "Content-Type",
+    ];
+}
+
+upstream backend {
+    weight = 10
+}
+
+banner = <<EOD
+Welcome to the
+mail server
+EOD
+max_size = 50mb
+"#;
+
+const JSON: &str =
+    "{\n  \"name\": \"demo\",\n  \"ports\": [8080, 8443],\n  \"tls\": {\"enabled\": true}\n}\n";
+
+/// Entries sharing a line, which the parser splits after a value written with `=` or `:`
+const SAME_LINE: &str = "x = 5 y = 6\nz { a = \"s\" + \"t\" b: on }\nw 1 2 = 3\n";
+
+const CORPUS: [&str; 6] = [
+    NGINX,
+    PKG,
+    RSPAMD,
+    JSON,
+    "\u{feff}a = 1 // trailing\n\n",
+    SAME_LINE,
+];
+
+/// Asserts that every value below `value` other than an object has a node at its path
+///
+/// Objects are skipped as `section a { }` defines `section` only through its label.
+fn assert_paths_resolve(document: &UclDocument, value: &UclValue, path: &str) {
+    let join = |segment: &str| {
+        if path.is_empty() {
+            segment.to_string()
+        } else {
+            format!("{path}.{segment}")
+        }
+    };
+    let children: Vec<(String, &UclValue)> = match value {
+        UclValue::Object(object) => object
+            .iter()
+            .map(|(key, value)| (join(key), value))
+            .collect(),
+        UclValue::Array(array) => array
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (join(&index.to_string()), value))
+            .collect(),
+        _ => return,
+    };
+    for (path, value) in children {
+        assert!(
+            value.as_object().is_some() || document.get(&path).is_some(),
+            "no node at {path:?}"
+        );
+        assert_paths_resolve(document, value, &path);
+    }
+}
+
+#[test]
+fn test_unmodified_documents_round_trip() {
+    for source in CORPUS {
+        let document = UclDocument::parse(source).unwrap();
+        assert_eq!(document.to_string(), source);
+
+        // Tokens and their trivia cover the source up to the last token
+        let mut rebuilt = String::new();
+        for token in document.tokens() {
+            rebuilt.push_str(document.leading_trivia(token));
+            rebuilt.push_str(document.token_text(token));
+        }
+        let end = document
+            .tokens()
+            .last()
+            .map_or(0, |token| token.span.end.offset);
+        assert_eq!(rebuilt, source[..end]);
+
+        let expected = UclParser::new(source).parse_document().unwrap();
+        assert_eq!(document.value(), &expected);
+        assert_paths_resolve(&document, &expected, "");
+    }
+
+    let document = UclDocument::parse(SAME_LINE).unwrap();
+    assert_eq!(document.value_text("x"), Some("5"));
+    assert_eq!(document.value_text("y"), Some("6"));
+    assert_eq!(document.value_text("z.a"), Some("\"s\" + \"t\""));
+    assert_eq!(document.value_text("z.b"), Some("on"));
+    // Without a separator the value runs to the end of the line
+    assert_eq!(document.value_text("w"), Some("1 2 = 3"));
+}
+
+#[test]
+fn test_navigation() {
+    let document = UclDocument::parse(NGINX).unwrap();
+
+    assert_eq!(document.value_text("user"), Some("nginx"));
+    assert_eq!(document.value_text("http.keepalive_timeout"), Some("65s"));
+    assert_eq!(
+        document.value_text("http.server.server_name"),
+        Some("\"example.com\"")
+    );
+    assert!(
+        matches!(document.get("events"), Some(CstNode::Object { entries, .. }) if entries.len() == 2)
+    );
+    assert!(matches!(document.get(""), Some(CstNode::Object { .. })));
+    assert!(document.get("http.missing").is_none());
+    assert!(document.get("user.name").is_none());
+
+    let span = document.value_span("events.worker_connections").unwrap();
+    assert_eq!((span.start.line, span.start.column), (6, 24));
+
+    let document = UclDocument::parse(RSPAMD).unwrap();
+    assert_eq!(document.value_text("options.filters.1"), Some("\"dkim\""));
+    assert_eq!(document.value_text("upstream.backend.weight"), Some("10"));
+    assert_eq!(document.value_text("max_size"), Some("50mb"));
+    assert!(document.value_text("options.filters.3").is_none());
+
+    let document = UclDocument::parse(JSON).unwrap();
+    assert_eq!(document.value_text("ports"), Some("[8080, 8443]"));
+    assert_eq!(document.value_text("tls.enabled"), Some("true"));
+}
+
+/// Asserts that `edited` differs from `original` only by replacing `old` with `new`
+fn assert_replaced(original: &str, edited: &str, old: &str, new: &str) {
+    assert_eq!(original.matches(old).count(), 1, "{old:?} must be unique");
+    assert_eq!(edited, original.replacen(old, new, 1));
+}
+
+#[test]
+fn test_set_scalar_keeps_formatting() {
+    let mut document = UclDocument::parse(NGINX).unwrap();
+    document
+        .set_value("events.worker_connections", &UclValue::from(4096))
+        .unwrap();
+    assert_replaced(
+        NGINX,
+        &document.to_string(),
+        "connections 1024;",
+        "connections 4096;",
+    );
+    assert_eq!(document.value()["events"]["worker_connections"], 4096);
+
+    let mut document = UclDocument::parse(NGINX).unwrap();
+    document
+        .set_value("worker_processes", &UclValue::from(8))
+        .unwrap();
+    document
+        .set_value("http.server.root", &UclValue::from("/srv/www"))
+        .unwrap();
+    let expected = NGINX
+        .replacen("processes   auto;", "processes   8;", 1)
+        .replacen("root /var/www/html;", "root \"/srv/www\";", 1);
+    assert_eq!(document.to_string(), expected);
+    assert_eq!(document.value()["http"]["server"]["root"], "/srv/www");
+}
+
+#[test]
+fn test_set_array_element_and_json() {
+    let mut document = UclDocument::parse(RSPAMD).unwrap();
+    document
+        .set_value("options.filters.1", &UclValue::from("spf"))
+        .unwrap();
+    assert_replaced(RSPAMD, &document.to_string(), "\"dkim\"", "\"spf\"");
+
+    let mut document = UclDocument::parse(JSON).unwrap();
+    document
+        .set_value("tls.enabled", &UclValue::from(false))
+        .unwrap();
+    assert_replaced(JSON, &document.to_string(), "true", "false");
+}
+
+#[test]
+fn test_set_nested_value_is_indented() {
+    let mut document = UclDocument::parse(RSPAMD).unwrap();
+    let value = ucl!({ "timeout": 2, "servers": ["a", "b"] });
+    document.set_value("options.dns.sockets", &value).unwrap();
+
+    assert_replaced(
+        RSPAMD,
+        &document.to_string(),
+        "sockets = 16;",
+        "sockets = {\n            timeout = 2;\n            servers = [\n                \"a\",\n                \"b\",\n            ];\n        };",
+    );
+    assert_eq!(document.value()["options"]["dns"]["sockets"], value);
+    assert_eq!(document.value()["options"]["dns"]["retransmits"], 5);
+}
+
+#[test]
+fn test_set_heredoc_keeps_following_line() {
+    let mut document = UclDocument::parse(RSPAMD).unwrap();
+    document
+        .set_value("banner", &UclValue::from("hello"))
+        .unwrap();
+
+    assert_replaced(
+        RSPAMD,
+        &document.to_string(),
+        "<<EOD\nWelcome to the\nmail server\nEOD\n",
+        "\"hello\"\n",
+    );
+    assert_eq!(document.value()["banner"], "hello");
+    assert_eq!(document.value()["max_size"], 50 * 1024 * 1024);
+}
+
+#[test]
+fn test_replace_object_with_scalar() {
+    let mut document = UclDocument::parse(PKG).unwrap();
+    document
+        .set_value("FreeBSD", &UclValue::from(false))
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        "# FreeBSD pkg repository\nFreeBSD: false\n"
+    );
+}
+
+#[test]
+fn test_missing_path() {
+    let mut document = UclDocument::parse(PKG).unwrap();
+    let err = document
+        .set_value("FreeBSD.priority", &UclValue::from(1))
+        .unwrap_err();
+
+    assert!(matches!(err, EditError::PathNotFound { ref path } if path == "FreeBSD.priority"));
+    assert_eq!(err.to_string(), "No value at path 'FreeBSD.priority'");
+    assert_eq!(document.to_string(), PKG);
+}
+
+#[test]
+fn test_duplicate_keys_address_last_definition() {
+    let source = "port = 1\nport = 2 # override\n";
+    let mut document = UclDocument::parse(source).unwrap();
+    document.set_value("port", &UclValue::from(3)).unwrap();
+    assert_eq!(document.to_string(), "port = 1\nport = 3 # override\n");
+}
+
+#[test]
+fn test_invalid_document() {
+    assert!(UclDocument::parse("a = [1, 2").is_err());
+}
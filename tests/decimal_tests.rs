@@ -0,0 +1,152 @@
+//! Tests for keeping decimal numbers as written with `DecimalMode::PreserveString`
+
+use serde::Deserialize;
+use serde::de::{self, Deserializer, Visitor};
+use std::fmt;
+use ucl_lexer::{
+    DecimalMode, ParseError, ParserConfig, UclDeserializer, UclParser, UclValue, to_ucl_string,
+};
+
+fn decimal_config() -> ParserConfig {
+    ParserConfig::new().with_decimal_mode(DecimalMode::PreserveString)
+}
+
+fn parse_decimal(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input)
+        .with_config(decimal_config())
+        .parse_document()
+}
+
+/// An amount in cents, read from the decimal text as an exact decimal type would be
+#[derive(Debug, PartialEq)]
+struct Cents(i64);
+
+impl<'de> Deserialize<'de> for Cents {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CentsVisitor;
+
+        impl Visitor<'_> for CentsVisitor {
+            type Value = Cents;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("an amount with at most two decimal places")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Cents, E> {
+                let (units, cents) = text.split_once('.').unwrap_or((text, "0"));
+                let cents = format!("{cents:0<2}");
+                match (units.parse::<i64>(), cents.parse::<i64>()) {
+                    (Ok(units), Ok(cents)) if cents < 100 => Ok(Cents(units * 100 + cents)),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(text), &self)),
+                }
+            }
+
+            fn visit_i64<E: de::Error>(self, units: i64) -> Result<Cents, E> {
+                Ok(Cents(units * 100))
+            }
+        }
+
+        deserializer.deserialize_any(CentsVisitor)
+    }
+}
+
+#[test]
+fn test_decimals_keep_their_text() {
+    let value = parse_decimal(
+        "rate = 0.1\nprice = -12.50\ntotal = 0.30000000000000000001\n\
+         tiers = [0.2, 1.005]\nlimits { daily = 100.00 }\ncount = 3\n",
+    )
+    .unwrap();
+    assert_eq!(value["rate"], UclValue::Decimal("0.1".to_string()));
+    assert_eq!(value["price"].as_decimal(), Some("-12.50"));
+    assert_eq!(value["total"].as_decimal(), Some("0.30000000000000000001"));
+    assert_eq!(value["tiers"][0].as_decimal(), Some("0.2"));
+    assert_eq!(value["tiers"][1].as_decimal(), Some("1.005"));
+    assert_eq!(value["limits"]["daily"].as_decimal(), Some("100.00"));
+    assert_eq!(value["count"], 3);
+    assert_eq!(value["rate"].type_name(), "decimal");
+
+    // As floats the same text no longer reads back exactly
+    let total: f64 = "0.30000000000000000001".parse().unwrap();
+    assert_eq!(total.to_string(), "0.3");
+    assert_ne!(0.1 + 0.2, 0.3);
+
+    // The default mode still reads floats
+    let value = UclParser::new("rate = 0.1").parse_document().unwrap();
+    assert_eq!(value["rate"], UclValue::Float(0.1));
+}
+
+#[test]
+fn test_exponents_and_suffixes_are_rejected() {
+    for (input, number, column) in [
+        ("rate = 1e5", "1e5", 8),
+        ("rate = 2.5E-3", "2.5E-3", 8),
+        ("a = 1\nrate = [0.1, 1.0e3]", "1.0e3", 14),
+        ("rate = .5", ".5", 8),
+    ] {
+        let err = parse_decimal(input).unwrap_err();
+        match &err {
+            ParseError::InvalidDecimal { value, position } => {
+                assert_eq!(value, number, "{input}");
+                assert_eq!(position.column, column, "{input}");
+            }
+            other => panic!("{input}: expected an invalid decimal, got {other:?}"),
+        }
+        assert_eq!(err.code(), "parse/invalid-decimal");
+    }
+
+    let err = parse_decimal("rate = 1e5").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Number '1e5' at 1:8 is not a plain decimal; exponents and suffixes are not allowed in decimal mode"
+    );
+
+    // Durations and numbers followed by words read as before
+    let value = parse_decimal("timeout = 1.5s\nversion = 1.5beta").unwrap();
+    assert_eq!(value["timeout"], UclValue::Time(1.5));
+    assert_eq!(value["version"], "1.5beta");
+}
+
+#[test]
+fn test_deserializing_decimals() {
+    #[derive(Debug, Deserialize)]
+    struct Billing {
+        rate: String,
+        ratio: f64,
+        fee: Cents,
+        fees: Vec<Cents>,
+    }
+
+    let input = "rate = 0.10\nratio = 0.25\nfee = 12.5\nfees = [0.99, 100]\n";
+    let billing =
+        Billing::deserialize(UclDeserializer::with_parser_config(input, decimal_config())).unwrap();
+    assert_eq!(billing.rate, "0.10");
+    assert_eq!(billing.ratio, 0.25);
+    assert_eq!(billing.fee, Cents(1250));
+    assert_eq!(billing.fees, [Cents(99), Cents(10000)]);
+
+    // Integer targets do not truncate a decimal
+    #[derive(Debug, Deserialize)]
+    struct Count {
+        _count: u32,
+    }
+    let err = Count::deserialize(UclDeserializer::with_parser_config(
+        "_count = 1.5",
+        decimal_config(),
+    ))
+    .unwrap_err();
+    assert!(err.to_string().contains("1.5"), "{err}");
+}
+
+#[test]
+fn test_decimals_are_written_as_read() {
+    let value = parse_decimal("rate = 0.10\ntiers = [1.005, -2.50]\n").unwrap();
+    assert_eq!(
+        value.to_string(),
+        "{\"rate\": 0.10, \"tiers\": [1.005, -2.50]}"
+    );
+
+    let text = to_ucl_string(&value);
+    assert_eq!(parse_decimal(&text).unwrap(), value);
+    assert_eq!(f64::try_from(value["rate"].clone()).unwrap(), 0.1);
+}
@@ -0,0 +1,166 @@
+//! Tests for lint and deprecation directives read from comments
+
+use ucl_lexer::{
+    Diagnostic, DirectiveKind, DirectiveParser, LexerConfig, LintCode, LintConfig, ParserConfig,
+    UclParser,
+};
+
+fn config() -> ParserConfig {
+    ParserConfig::new()
+        .with_lints(LintConfig::all())
+        .with_directives(DirectiveParser::new())
+}
+
+fn diagnostics_with(config: ParserConfig, input: &str) -> Vec<Diagnostic> {
+    let mut parser = UclParser::new(input).with_config(config);
+    parser.parse_document().unwrap();
+    parser.take_diagnostics()
+}
+
+fn codes(diagnostics: &[Diagnostic]) -> Vec<LintCode> {
+    diagnostics.iter().filter_map(|d| d.code).collect()
+}
+
+#[test]
+fn test_disable_applies_to_the_next_key_only() {
+    let input = "# ucl-lint: disable=typed-string\nport = \"8080\"\ntimeout = \"30s\"\n";
+    let diagnostics = diagnostics_with(config(), input);
+    assert_eq!(codes(&diagnostics), [LintCode::TypedString]);
+    assert_eq!(diagnostics[0].position.line, 3);
+
+    // Without directives the comment is an ordinary comment
+    let lints = ParserConfig::new().with_lints(LintConfig::all());
+    assert_eq!(codes(&diagnostics_with(lints, input)).len(), 2);
+}
+
+#[test]
+fn test_disable_covers_nested_entries() {
+    let input = "\
+server {
+    # ucl-lint: disable=shadowed-key, typed-string
+    server {
+        port = \"80\"
+    }
+    name = \"true\"
+}
+";
+    let diagnostics = diagnostics_with(config(), input);
+    assert_eq!(codes(&diagnostics), [LintCode::TypedString]);
+    assert_eq!(diagnostics[0].position.line, 6);
+}
+
+#[test]
+fn test_deprecated_keys_are_reported() {
+    let input = "\
+# @deprecated use listen instead
+port = 80
+listen = 8080
+upstream {
+    # @deprecated
+    weight = 2
+}
+";
+    let mut parser = UclParser::new(input).with_config(config());
+    parser.parse_document().unwrap();
+
+    let messages: Vec<_> = parser
+        .diagnostics()
+        .iter()
+        .map(|d| (d.message.as_str(), d.position.line, d.code))
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            ("`port` is deprecated: use listen instead", 2, None),
+            ("`upstream.weight` is deprecated", 6, None),
+        ]
+    );
+
+    let directives = parser.directives();
+    assert_eq!(directives.len(), 2);
+    assert_eq!(
+        directives[0].kind,
+        DirectiveKind::Deprecated("use listen instead".to_string())
+    );
+    assert_eq!(directives[0].key_path, ["port"]);
+    assert_eq!(
+        (directives[0].position.line, directives[0].key_position.line),
+        (1, 2)
+    );
+    assert_eq!(directives[1].key_path, ["upstream", "weight"]);
+}
+
+#[test]
+fn test_directives_do_not_leak_past_closing_braces() {
+    let input = "\
+server {
+    port = 80
+    # @deprecated
+}
+host = \"localhost\"
+list = [
+    # @deprecated
+    1
+]
+name = 2
+";
+    let mut parser = UclParser::new(input).with_config(config());
+    parser.parse_document().unwrap();
+    assert!(parser.directives().is_empty());
+    assert!(parser.diagnostics().is_empty());
+}
+
+#[test]
+fn test_custom_prefixes() {
+    let directives = DirectiveParser::new()
+        .with_lint_prefix("lint:")
+        .with_deprecated_prefix("DEPRECATED:");
+    let config = config().with_directives(directives);
+    let input = "\
+// lint: disable=typed-string
+a = \"1\"
+/* DEPRECATED: gone in 2.0 */
+b = 2
+# ucl-lint: disable=typed-string
+c = \"3\"
+";
+    let diagnostics = diagnostics_with(config, input);
+    let summary: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.position.line, d.code))
+        .collect();
+    assert_eq!(summary, [(4, None), (6, Some(LintCode::TypedString))]);
+    assert_eq!(diagnostics[0].message, "`b` is deprecated: gone in 2.0");
+}
+
+#[test]
+fn test_invalid_directives_are_reported() {
+    let input = "# ucl-lint: disable=no-such-lint\na = 1\n# ucl-lint: enable=typed-string\nb = 2\n";
+    let diagnostics = diagnostics_with(config(), input);
+    assert_eq!(diagnostics.len(), 2);
+    assert!(
+        diagnostics[0]
+            .message
+            .starts_with("invalid directive: unknown lint `no-such-lint`"),
+        "{}",
+        diagnostics[0].message
+    );
+    assert_eq!(diagnostics[0].position.line, 1);
+    assert_eq!(diagnostics[1].position.line, 3);
+}
+
+#[test]
+fn test_directives_with_saved_comments_and_nginx_syntax() {
+    let lexer_config = LexerConfig::new().with_save_comments(true);
+    let input =
+        "# @deprecated\nlocation /api {\n    # @deprecated old\n    proxy_pass backend;\n}\n";
+    let mut parser = UclParser::with_lexer_config(input, lexer_config).with_config(config());
+    parser.parse_document().unwrap();
+
+    let paths: Vec<_> = parser
+        .directives()
+        .iter()
+        .map(|d| d.key_path.join("."))
+        .collect();
+    assert_eq!(paths, ["location", "location./api.proxy_pass"]);
+}
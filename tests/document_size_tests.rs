@@ -0,0 +1,134 @@
+//! Tests for `ParserConfig::max_document_size` on string and streaming input
+
+use serde::Deserialize;
+use std::io::Cursor;
+use ucl_lexer::{
+    DEFAULT_MAX_DOCUMENT_SIZE, LexError, ParseError, ParserConfig, Token, UclDeserializer,
+    UclError, UclParser, UclParserBuilder, from_str, streaming_lexer_from_reader,
+};
+
+/// A document of exactly `size` bytes
+fn document(size: usize) -> String {
+    let mut input = String::from("key = \"");
+    input.push_str(&"x".repeat(size - input.len() - 2));
+    input.push_str("\"\n");
+    assert_eq!(input.len(), size);
+    input
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Doc {
+    key: String,
+}
+
+fn deserialize(input: &str, limit: usize) -> Result<Doc, UclError> {
+    let config = ParserConfig::default().with_max_document_size(limit);
+    Doc::deserialize(UclDeserializer::with_parser_config(input, config))
+}
+
+/// Reads every token and returns the first error
+fn stream(input: &str, limit: usize) -> Result<usize, LexError> {
+    let mut lexer = streaming_lexer_from_reader(Cursor::new(input.as_bytes().to_vec()))
+        .with_max_document_size(limit);
+    let mut tokens = 0;
+    while lexer.next_token()? != Token::Eof {
+        tokens += 1;
+    }
+    Ok(tokens)
+}
+
+#[test]
+fn test_default_limit() {
+    assert_eq!(
+        ParserConfig::default().max_document_size,
+        DEFAULT_MAX_DOCUMENT_SIZE
+    );
+    assert_eq!(DEFAULT_MAX_DOCUMENT_SIZE, 64 * 1024 * 1024);
+    assert!(from_str::<Doc>(&document(1024)).is_ok());
+}
+
+#[test]
+fn test_string_boundary() {
+    let input = document(100);
+
+    assert_eq!(deserialize(&input, 100).unwrap().key.len(), 91);
+    match deserialize(&input, 99).unwrap_err() {
+        UclError::DocumentTooLarge { size, limit } => assert_eq!((size, limit), (100, 99)),
+        other => panic!("expected document too large, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parser_entry_points() {
+    let input = document(64);
+    let config = ParserConfig::default().with_max_document_size(63);
+
+    let err = UclParser::new(&input)
+        .with_config(config.clone())
+        .parse_document()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DocumentTooLarge {
+            size: 64,
+            limit: 63
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Document of 64 bytes exceeds the maximum document size of 63 bytes"
+    );
+
+    let shared = UclParserBuilder::new("")
+        .with_parser_config(config)
+        .build_shared()
+        .unwrap();
+    assert!(shared.parse(&input[..63]).is_ok());
+    assert!(matches!(
+        shared.parse(&input),
+        Err(ParseError::DocumentTooLarge { .. })
+    ));
+
+    let (value, errors) = UclParser::new(&input)
+        .with_config(ParserConfig::default().with_max_document_size(63))
+        .parse_document_lenient();
+    assert!(value.is_none());
+    assert!(matches!(
+        errors[..],
+        [UclError::DocumentTooLarge {
+            size: 64,
+            limit: 63
+        }]
+    ));
+}
+
+#[test]
+fn test_unlimited() {
+    let input = document(4096);
+    assert!(deserialize(&input, usize::MAX).is_ok());
+    assert!(stream(&input, usize::MAX).is_ok());
+}
+
+#[test]
+fn test_streaming_boundary() {
+    // Larger than one read chunk, so the limit trips on a running total
+    let input = document(10_000);
+
+    assert_eq!(stream(&input, 10_000).unwrap(), 3);
+    let err = stream(&input, 9_999).unwrap_err();
+    assert!(
+        matches!(err, LexError::DocumentTooLarge { limit: 9_999, .. }),
+        "{err:?}"
+    );
+
+    // The error is reported once the running total passes the limit
+    let err = stream(&input, 5_000).unwrap_err();
+    match err {
+        LexError::DocumentTooLarge { size, limit } => {
+            assert_eq!(limit, 5_000);
+            assert!(size > 5_000 && size <= 10_000, "{size}");
+        }
+        other => panic!("expected document too large, got {other:?}"),
+    }
+}
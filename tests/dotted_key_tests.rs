@@ -0,0 +1,150 @@
+//! Tests for expanding dotted keys with `ParserConfig::expand_dotted_keys`
+
+use ucl_lexer::{
+    DuplicateKeyBehavior, ParseError, ParserConfig, Position, SourceMap, UclParser, UclValue, ucl,
+};
+
+fn config() -> ParserConfig {
+    ParserConfig::new().with_expand_dotted_keys(true)
+}
+
+fn strict() -> ParserConfig {
+    config().with_duplicate_key_behavior(DuplicateKeyBehavior::Error)
+}
+
+fn parse(input: &str, config: ParserConfig) -> Result<UclValue, ParseError> {
+    UclParser::new(input).with_config(config).parse_document()
+}
+
+fn at(line: usize, column: usize, offset: usize) -> Position {
+    Position {
+        line,
+        column,
+        offset,
+    }
+}
+
+#[test]
+fn test_dotted_keys_build_nested_objects() {
+    let input = "server.tls.port = 443\nserver.tls.cert = \"site.pem\"\nserver.name = \"api\"";
+    assert_eq!(
+        parse(input, config()).unwrap(),
+        ucl!({ "server": { "tls": { "port": 443, "cert": "site.pem" }, "name": "api" } })
+    );
+
+    // Inside braces, after a braced section and with `+=`
+    let input = "outer { a.b = 1, a.c = 2 }\nsection { x = 1 }\nsection.y = 2\nlist.items += [1]\nlist.items += [2]";
+    assert_eq!(
+        parse(input, config()).unwrap(),
+        ucl!({
+            "outer": { "a": { "b": 1, "c": 2 } },
+            "section": { "x": 1, "y": 2 },
+            "list": { "items": [1, 2] }
+        })
+    );
+
+    // A repeated dotted key follows the duplicate key behavior at its last level
+    assert_eq!(
+        parse("a.b = 1\na.b = 2", config()).unwrap(),
+        ucl!({ "a": { "b": [1, 2] } })
+    );
+
+    // Quoted keys, malformed paths and named section names are not expanded
+    assert_eq!(
+        parse(
+            "\"q.r\" = 1\na..b = 2\nserver example.com { c = 3 }",
+            config()
+        )
+        .unwrap(),
+        ucl!({ "q.r": 1, "a..b": 2, "server": { "example.com": { "c": 3 } } })
+    );
+
+    // Off by default
+    assert_eq!(
+        parse("a.b = 1", ParserConfig::new()).unwrap(),
+        ucl!({ "a.b": 1 })
+    );
+}
+
+#[test]
+fn test_braced_definition_after_dotted_key() {
+    let err = parse("a.b.c = 1\na { b { c = 2 } }", strict()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateKey { ref key, first_occurrence, second_occurrence }
+            if key == "c" && first_occurrence == at(1, 1, 0) && second_occurrence == at(2, 1, 10)
+    ));
+
+    // A braced `a` extended by a dotted key is still defined twice
+    let input = "a { x = 1 }\na.b.c = 1\na { b { c = 2 } }";
+    let err = parse(input, strict()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateKey { ref key, first_occurrence, second_occurrence }
+            if key == "a" && first_occurrence == at(1, 1, 0) && second_occurrence.line == 3
+    ));
+
+    // The same inside an explicit object
+    let input = "outer {\n  a { x = 1 }\n  a.b.c = 1\n  a { b { c = 2 } }\n}";
+    let err = parse(input, strict()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateKey { ref key, first_occurrence, second_occurrence }
+            if key == "a" && first_occurrence == at(2, 3, 10) && second_occurrence.line == 4
+    ));
+
+    // Levels created by dotted keys take later definitions
+    let input = "a.b.c = 1\na { b { d = 2 } }";
+    assert_eq!(
+        parse(input, strict()).unwrap(),
+        ucl!({ "a": { "b": { "c": 1, "d": 2 } } })
+    );
+}
+
+#[test]
+fn test_dotted_key_after_braced_definition() {
+    let err = parse("a { b { c = 2 } }\na.b.c = 1", strict()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateKey { ref key, second_occurrence, .. }
+            if key == "c" && second_occurrence == at(2, 1, 18)
+    ));
+
+    // A dotted key that runs into a scalar collides at that level
+    let err = parse("a.b = 1\na.b.c = 2", strict()).unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::DuplicateKey { ref key, first_occurrence, second_occurrence }
+            if key == "b" && first_occurrence == at(1, 1, 0) && second_occurrence == at(2, 1, 8)
+    ));
+
+    let mut parser = UclParser::new("a.b.c = 1\na.b.c = 2")
+        .with_config(config().with_duplicate_key_behavior(DuplicateKeyBehavior::Override));
+    assert_eq!(parser.parse_document().unwrap()["a"]["b"]["c"], 2);
+    let diagnostics = parser.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].position, at(2, 1, 10));
+    assert!(diagnostics[0].message.contains("first defined at line 1"));
+}
+
+#[test]
+fn test_provenance_and_source_map_point_at_the_dotted_key() {
+    let input = "name = \"api\"\n  server.tls.port = 443\nserver { host = \"a\" }";
+    let mut parser = UclParser::new(input).with_config(strict().with_track_provenance(true));
+    parser.parse_document().unwrap();
+    let provenance = parser.take_provenance();
+
+    for path in ["server", "server.tls", "server.tls.port"] {
+        let origin = provenance.origin(path).unwrap();
+        assert_eq!(origin.span.start, at(2, 3, 15), "{path}");
+        assert_eq!(origin.span.end.column, 18, "{path}");
+    }
+    assert_eq!(provenance.origin("server.host").unwrap().span.start.line, 3);
+
+    let source_map = SourceMap::from(provenance);
+    assert_eq!(
+        source_map.line_col_for_path("server.tls.port"),
+        Some((2, 3))
+    );
+    assert_eq!(source_map.line_col_for_path("server.tls"), Some((2, 3)));
+}
@@ -0,0 +1,135 @@
+//! Tests for parsing byte input with `from_slice` and `UclParser::parse_bytes`
+
+use serde::Deserialize;
+use ucl_lexer::{ParseError, ParserConfig, UclError, UclParser, from_slice};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Config {
+    name: String,
+    port: u16,
+}
+
+const CONFIG: &str = "name = \"café\"\nport = 8080\n";
+
+fn expected() -> Config {
+    Config {
+        name: "café".to_string(),
+        port: 8080,
+    }
+}
+
+/// Encodes text as UTF-16 with a byte order mark
+fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    let mut bytes = if big_endian {
+        vec![0xFE, 0xFF]
+    } else {
+        vec![0xFF, 0xFE]
+    };
+    for unit in text.encode_utf16() {
+        bytes.extend_from_slice(&if big_endian {
+            unit.to_be_bytes()
+        } else {
+            unit.to_le_bytes()
+        });
+    }
+    bytes
+}
+
+#[test]
+fn test_utf8_slice() {
+    let config: Config = from_slice(CONFIG.as_bytes()).unwrap();
+    assert_eq!(config, expected());
+
+    let with_bom = [b"\xEF\xBB\xBF".as_slice(), CONFIG.as_bytes()].concat();
+    let config: Config = from_slice(&with_bom).unwrap();
+    assert_eq!(config, expected());
+
+    let value = UclParser::parse_bytes(CONFIG.as_bytes()).unwrap();
+    assert_eq!(value["port"], 8080);
+}
+
+#[test]
+fn test_parse_bytes_with_config_enforces_limits() {
+    let config = ParserConfig::untrusted().with_max_document_size(16);
+    let err = UclParser::parse_bytes_with_config(CONFIG.as_bytes(), &config).unwrap_err();
+    assert!(
+        matches!(err, UclError::DocumentTooLarge { limit: 16, .. }),
+        "{err:?}"
+    );
+    assert!(UclParser::parse_bytes(CONFIG.as_bytes()).is_ok());
+
+    let config = ParserConfig::untrusted().with_max_depth(1);
+    let err = UclParser::parse_bytes_with_config(b"a { b { c = 1 } }", &config).unwrap_err();
+    assert!(
+        matches!(err, UclError::Parse(ParseError::MaxDepthExceeded { .. })),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_truncated_multi_byte_sequence() {
+    // "é" is 0xC3 0xA9; the second byte is missing
+    let bytes = b"name = \"caf\xC3\"\nport = 8080\n";
+    let error = from_slice::<Config>(bytes).unwrap_err();
+
+    assert!(matches!(
+        error,
+        UclError::InvalidEncoding {
+            detected: "UTF-8",
+            position: 11
+        }
+    ));
+    assert_eq!(error.to_string(), "Invalid UTF-8 input at byte 11");
+    assert!(UclParser::parse_bytes(bytes).is_err());
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_utf16le_with_bom() {
+    let config: Config = from_slice(&utf16(CONFIG, false)).unwrap();
+    assert_eq!(config, expected());
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_utf16be_with_bom() {
+    let value = UclParser::parse_bytes(&utf16(CONFIG, true)).unwrap();
+    assert_eq!(value["name"], "café");
+}
+
+#[cfg(feature = "encoding")]
+#[test]
+fn test_undecodable_utf16() {
+    // An unpaired high surrogate after "a = "
+    let mut bytes = utf16("a = ", false);
+    bytes.extend_from_slice(&[0x00, 0xD8, 0x31, 0x00]);
+    assert!(matches!(
+        UclParser::parse_bytes(&bytes),
+        Err(UclError::InvalidEncoding {
+            detected: "UTF-16LE",
+            position: 10
+        })
+    ));
+
+    let mut odd = utf16("a = 1", true);
+    odd.push(0x00);
+    assert!(matches!(
+        from_slice::<serde_json::Value>(&odd),
+        Err(UclError::InvalidEncoding {
+            detected: "UTF-16BE",
+            position: 12
+        })
+    ));
+}
+
+#[cfg(not(feature = "encoding"))]
+#[test]
+fn test_utf16_needs_encoding_feature() {
+    assert!(matches!(
+        from_slice::<Config>(&utf16(CONFIG, false)),
+        Err(UclError::InvalidEncoding {
+            detected: "UTF-16LE",
+            position: 0
+        })
+    ));
+}
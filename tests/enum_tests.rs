@@ -0,0 +1,111 @@
+//! Tests for deserializing enums from bare words, quoted strings and integers
+
+use serde::{Deserialize, Deserializer};
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{UclError, from_str};
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Logging {
+    level: Level,
+}
+
+/// Reads the discriminant the way `serde_repr` does
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Fast = 1,
+    Safe = 2,
+}
+
+impl<'de> Deserialize<'de> for Mode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match u8::deserialize(deserializer)? {
+            1 => Ok(Mode::Fast),
+            2 => Ok(Mode::Safe),
+            other => Err(serde::de::Error::custom(format!("invalid mode {}", other))),
+        }
+    }
+}
+
+#[test]
+fn test_bare_word_variants() {
+    let logging: Logging = from_str("level = info").unwrap();
+    assert_eq!(logging.level, Level::Info);
+
+    let levels: Vec<Level> = from_str("[\n  warn,\n  error\n]").unwrap();
+    assert_eq!(levels, [Level::Warn, Level::Error]);
+
+    assert_eq!(from_str::<Level>("error").unwrap(), Level::Error);
+}
+
+#[test]
+fn test_quoted_variants() {
+    let logging: Logging = from_str(r#"level = "warn""#).unwrap();
+    assert_eq!(logging.level, Level::Warn);
+}
+
+#[test]
+fn test_integer_selects_variant_by_index() {
+    let logging: Logging = from_str("level = 2").unwrap();
+    assert_eq!(logging.level, Level::Error);
+
+    let err = from_str::<Logging>("level = 3").unwrap_err();
+    assert!(err.to_string().contains("variant index"), "{err}");
+}
+
+#[test]
+fn test_integer_discriminants() {
+    #[derive(Deserialize)]
+    struct Settings {
+        mode: Mode,
+    }
+
+    let settings: Settings = from_str("mode = 2").unwrap();
+    assert_eq!(settings.mode, Mode::Safe);
+    assert!(from_str::<Settings>("mode = 7").is_err());
+}
+
+#[test]
+fn test_unknown_variant_lists_allowed_names() {
+    let err = from_str::<Logging>("# logging\nlevel = debug").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: Unknown variant `debug` for 'level' at line 2, expected one of `info`, `warn`, `error`"
+    );
+    match err {
+        UclError::Serde(SerdeError::UnknownVariant {
+            variant,
+            expected,
+            path,
+            position,
+        }) => {
+            assert_eq!(variant, "debug");
+            assert_eq!(expected, ["info", "warn", "error"]);
+            assert_eq!(path, ["level"]);
+            assert_eq!(position.map(|p| (p.line, p.column)), Some((2, 1)));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn test_unknown_variant_in_nested_section() {
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        #[allow(dead_code)]
+        logging: Logging,
+    }
+
+    let err = from_str::<Config>("logging {\n  level = \"verbose\"\n}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: Unknown variant `verbose` for 'logging.level' at line 2, expected one of `info`, `warn`, `error`"
+    );
+}
@@ -0,0 +1,123 @@
+//! Tests for error sources and the key paths of deserialization errors
+
+use serde::Deserialize;
+use std::error::Error;
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{LexError, ParseError, UclError, from_str};
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Config {
+    server: Server,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Server {
+    name: String,
+    tls: Tls,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Tls {
+    cert_file: u16,
+}
+
+/// Collects the messages of an error and each of its sources
+fn chain(error: &dyn Error) -> Vec<String> {
+    let mut messages = vec![error.to_string()];
+    let mut source = error.source();
+    while let Some(error) = source {
+        messages.push(error.to_string());
+        source = error.source();
+    }
+    messages
+}
+
+#[test]
+fn test_nested_failure_has_path_and_position() {
+    let input = "server {\n  name = \"web\"\n  tls {\n    cert_file = \"cert.pem\"\n  }\n}\n";
+    let err = from_str::<Config>(input).unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("server.tls.cert_file"));
+    match &err {
+        UclError::Serde(SerdeError::Message {
+            message,
+            path,
+            position,
+        }) => {
+            assert_eq!(message, "invalid type: string \"cert.pem\", expected u16");
+            assert_eq!(path, &["server", "tls", "cert_file"]);
+            let position = position.unwrap();
+            assert_eq!((position.line, position.column), (4, 5));
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Serde error: error at server.tls.cert_file (line 4, col 5): \
+         invalid type: string \"cert.pem\", expected u16"
+    );
+}
+
+#[test]
+fn test_missing_field_is_reported_at_its_object() {
+    let err = from_str::<Config>("server { name = \"web\"; tls {} }").unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("server.tls"));
+    assert!(
+        err.to_string()
+            .starts_with("Serde error: error at server.tls (line 1, col 24): missing field"),
+        "{err}"
+    );
+
+    // Errors about the document root have no path
+    let err = from_str::<Config>("other = 1").unwrap_err();
+    assert_eq!(err.key_path(), None);
+    assert_eq!(err.to_string(), "Serde error: missing field `server`");
+}
+
+#[test]
+fn test_array_elements_are_part_of_the_path() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Ports {
+        ports: Vec<u16>,
+    }
+
+    let err = from_str::<Ports>("ports = [80, 443, \"ssh\"]").unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("ports.2"));
+}
+
+#[test]
+fn test_lex_errors_are_the_source_of_parse_errors() {
+    let err = from_str::<Config>("server { name = \"web }").unwrap_err();
+    assert!(matches!(
+        &err,
+        UclError::Parse(ParseError::Lex(LexError::UnterminatedString { .. }))
+    ));
+    assert_eq!(
+        chain(&err),
+        [
+            "Parse error: Lexical error: Unterminated string at 1:17",
+            "Lexical error: Unterminated string at 1:17",
+            "Unterminated string at 1:17",
+        ]
+    );
+    assert_eq!(err.key_path(), None);
+}
+
+#[test]
+fn test_sources_of_other_errors() {
+    let err = UclError::from(ParseError::MaxDepthExceeded {
+        position: Default::default(),
+    });
+    assert_eq!(chain(&err).len(), 2);
+
+    let err = UclError::from(LexError::UnterminatedComment {
+        position: Default::default(),
+    });
+    assert!(err.source().unwrap().is::<LexError>());
+
+    let err = from_str::<Config>("server { name = 1 ").unwrap_err();
+    assert!(err.source().unwrap().is::<ParseError>(), "{err:?}");
+}
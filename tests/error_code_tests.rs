@@ -0,0 +1,529 @@
+//! Tests for the stable codes of errors and their JSON records
+
+use ucl_lexer::error::{Position, SerdeError};
+use ucl_lexer::{ConversionError, LexError, ParseError, UclError, UclParser, from_str};
+
+/// The full list of error codes
+///
+/// Codes are part of the public API: a change to this list is a breaking
+/// change, so it must be made on purpose and never by renaming a variant.
+const CODE_SNAPSHOT: &[&str] = &[
+    "lex/unexpected-character",
+    "lex/unterminated-string",
+    "lex/invalid-escape",
+    "lex/invalid-unicode-escape",
+    "lex/unterminated-comment",
+    "lex/comment-nesting-too-deep",
+    "lex/invalid-number",
+    "lex/invalid-heredoc",
+    "lex/invalid-utf8",
+    "lex/invalid-cpp-comment",
+    "lex/invalid-extended-unicode-escape",
+    "lex/heredoc-terminator-not-found",
+    "lex/invalid-bare-word-character",
+    "lex/key-too-long",
+    "lex/ambiguous-suffix",
+    "lex/document-too-large",
+    "lex/token-limit-exceeded",
+    "lex/trailing-input",
+    "lex/invalid-scalar",
+    "lex/internal",
+    "parse/unexpected-token",
+    "parse/unexpected-character",
+    "parse/variable-not-found",
+    "parse/duplicate-key",
+    "parse/invalid-object",
+    "parse/invalid-array",
+    "parse/key-value-in-array",
+    "parse/non-ascii-key",
+    "parse/repeated-assignment",
+    "parse/leading-separator",
+    "parse/repeated-separator",
+    "parse/missing-array-element",
+    "parse/missing-separator",
+    "parse/trailing-content",
+    "parse/not-json",
+    "parse/plugin-conflict",
+    "parse/variable-expansion",
+    "parse/expanded-string-too-long",
+    "parse/max-depth-exceeded",
+    "parse/document-too-large",
+    "parse/cancelled",
+    "parse/non-finite-number",
+    "parse/invalid-decimal",
+    "parse/reserved-constant",
+    "parse/unknown-constant",
+    "parse/unknown-unit",
+    "parse/undefined-anchor",
+    "parse/recursive-anchor",
+    "parse/duplicate-anchor",
+    "parse/alias-expansion-too-large",
+    "parse/nginx-syntax",
+    "parse/invalid-comment-syntax",
+    "parse/ambiguous-bare-word",
+    "parse/invalid-implicit-syntax",
+    "parse/mixed-syntax-styles",
+    "parse/invalid-include",
+    "parse/include-failed",
+    "serde/custom",
+    "serde/message",
+    "serde/type-mismatch",
+    "serde/missing-field",
+    "serde/unknown-field",
+    "serde/invalid-length",
+    "serde/invalid-bytes",
+    "serde/invalid-coercion",
+    "serde/ambiguous-suffix",
+    "serde/duplicate-field",
+    "serde/unknown-variant",
+    "conversion/type-mismatch",
+    "conversion/out-of-range",
+    "conversion/element-type-mismatch",
+    "ucl/document-too-large",
+    "ucl/cancelled",
+    "ucl/invalid-encoding",
+    "ucl/variable-expansion-disabled",
+    "ucl/path-not-found",
+    "ucl/io",
+];
+
+/// One error of each lexer variant, in declaration order
+fn lex_errors() -> Vec<LexError> {
+    let position = Position::new();
+    let text = String::new;
+    vec![
+        LexError::UnexpectedCharacter {
+            character: '@',
+            position,
+        },
+        LexError::UnterminatedString { position },
+        LexError::InvalidEscape {
+            sequence: text(),
+            position,
+        },
+        LexError::InvalidUnicodeEscape {
+            sequence: text(),
+            position,
+        },
+        LexError::UnterminatedComment { position },
+        LexError::CommentNestingTooDeep { limit: 1, position },
+        LexError::InvalidNumber {
+            message: text(),
+            position,
+        },
+        LexError::InvalidHeredoc {
+            message: text(),
+            position,
+        },
+        LexError::InvalidUtf8 { position },
+        LexError::InvalidCppComment {
+            message: text(),
+            position,
+        },
+        LexError::InvalidExtendedUnicodeEscape {
+            sequence: text(),
+            position,
+        },
+        LexError::HeredocTerminatorNotFound {
+            terminator: text(),
+            position,
+        },
+        LexError::InvalidBareWordCharacter {
+            character: '@',
+            position,
+            suggestion: text(),
+        },
+        LexError::KeyTooLong {
+            length: 2,
+            limit: 1,
+            position,
+        },
+        LexError::AmbiguousSuffix {
+            number: text(),
+            position,
+        },
+        LexError::DocumentTooLarge { size: 2, limit: 1 },
+        LexError::TokenLimitExceeded {
+            limit: 1,
+            bound: "max_tokens",
+            input_size: 2,
+            position,
+        },
+        LexError::TrailingInput {
+            text: text(),
+            position,
+        },
+        LexError::InvalidScalar {
+            expected: "integer",
+            found: text(),
+            position,
+        },
+        LexError::Internal {
+            detail: "",
+            position,
+        },
+    ]
+}
+
+/// One error of each parser variant with a code of its own, in declaration order
+fn parse_errors() -> Vec<ParseError> {
+    let position = Position::new();
+    let text = String::new;
+    vec![
+        ParseError::UnexpectedToken {
+            found: "ObjectEnd",
+            expected: vec!["value"],
+            position,
+        },
+        ParseError::UnexpectedCharacter {
+            character: '@',
+            position,
+        },
+        ParseError::VariableNotFound {
+            name: text(),
+            position,
+        },
+        ParseError::DuplicateKey {
+            key: text(),
+            first_occurrence: position,
+            second_occurrence: position,
+        },
+        ParseError::InvalidObject {
+            message: text(),
+            position,
+        },
+        ParseError::InvalidArray {
+            message: text(),
+            position,
+        },
+        ParseError::KeyValueInArray {
+            key: text(),
+            position,
+        },
+        ParseError::NonAsciiKey {
+            key: text(),
+            character: '\u{430}',
+            position,
+        },
+        ParseError::RepeatedAssignment { position },
+        ParseError::LeadingSeparator {
+            separator: ',',
+            container: "array",
+            position,
+        },
+        ParseError::RepeatedSeparator { position },
+        ParseError::MissingArrayElement {
+            index: 1,
+            separator: ',',
+            position,
+        },
+        ParseError::MissingSeparator {
+            found: "integer",
+            position,
+        },
+        ParseError::TrailingContent {
+            found: "Key",
+            root: "array",
+            position,
+        },
+        ParseError::NotJson {
+            construct: "comments",
+            position,
+        },
+        ParseError::PluginConflict {
+            plugin_a: text(),
+            plugin_b: text(),
+            detail: text(),
+        },
+        ParseError::VariableExpansion {
+            message: text(),
+            position,
+        },
+        ParseError::ExpandedStringTooLong {
+            length: 2,
+            limit: 1,
+            position,
+        },
+        ParseError::MaxDepthExceeded { position },
+        ParseError::DocumentTooLarge { size: 2, limit: 1 },
+        ParseError::Cancelled { position },
+        ParseError::NonFiniteNumber {
+            value: text(),
+            position,
+        },
+        ParseError::InvalidDecimal {
+            value: text(),
+            position,
+        },
+        ParseError::ReservedConstant { name: text() },
+        ParseError::UnknownConstant {
+            name: text(),
+            position,
+        },
+        ParseError::UnknownUnit {
+            unit: text(),
+            family: text(),
+            valid_units: Vec::new(),
+            position,
+        },
+        ParseError::UndefinedAnchor {
+            name: text(),
+            position,
+        },
+        ParseError::RecursiveAnchor {
+            name: text(),
+            position,
+        },
+        ParseError::DuplicateAnchor {
+            name: text(),
+            first_definition: position,
+            position,
+        },
+        ParseError::AliasExpansionTooLarge { limit: 1, position },
+        ParseError::NginxSyntaxError {
+            message: text(),
+            position,
+            suggestion: text(),
+        },
+        ParseError::InvalidCommentSyntax {
+            message: text(),
+            position,
+        },
+        ParseError::AmbiguousBareWord {
+            word: text(),
+            position,
+            suggestion: text(),
+        },
+        ParseError::InvalidImplicitSyntax {
+            message: text(),
+            position,
+            expected_pattern: text(),
+        },
+        ParseError::MixedSyntaxStyles {
+            message: text(),
+            position,
+            suggestion: text(),
+        },
+        ParseError::InvalidInclude {
+            message: text(),
+            position,
+        },
+        ParseError::IncludeFailed {
+            path: text(),
+            message: text(),
+            position,
+        },
+    ]
+}
+
+/// One error of each deserialization variant, in declaration order
+fn serde_errors() -> Vec<SerdeError> {
+    let position = Position::new();
+    let text = String::new;
+    vec![
+        SerdeError::Custom(text()),
+        SerdeError::Message {
+            message: text(),
+            path: Vec::new(),
+            position: None,
+        },
+        SerdeError::TypeMismatch {
+            expected: text(),
+            found: text(),
+            position,
+        },
+        SerdeError::MissingField {
+            field: text(),
+            position,
+        },
+        SerdeError::UnknownField {
+            field: text(),
+            position,
+        },
+        SerdeError::InvalidLength {
+            expected: 2,
+            found: 1,
+            target: text(),
+            path: Vec::new(),
+            position: None,
+        },
+        SerdeError::InvalidBytes {
+            message: text(),
+            path: Vec::new(),
+            position: None,
+        },
+        SerdeError::InvalidCoercion {
+            value: text(),
+            target: text(),
+            path: Vec::new(),
+            position: None,
+        },
+        SerdeError::AmbiguousSuffix {
+            number: text(),
+            path: Vec::new(),
+            position: None,
+        },
+        SerdeError::DuplicateField {
+            first: text(),
+            second: text(),
+            path: Vec::new(),
+            positions: Vec::new(),
+        },
+        SerdeError::UnknownVariant {
+            variant: text(),
+            expected: Vec::new(),
+            path: Vec::new(),
+            position: None,
+        },
+    ]
+}
+
+/// One error of each conversion variant, in declaration order
+fn conversion_errors() -> Vec<ConversionError> {
+    vec![
+        ConversionError::TypeMismatch {
+            expected: "integer",
+            found: "string",
+        },
+        ConversionError::OutOfRange {
+            value: 300,
+            target: "u8",
+        },
+        ConversionError::ElementTypeMismatch {
+            index: 0,
+            expected: "integer",
+            found: "string",
+        },
+    ]
+}
+
+/// One error of each top-level variant that does not wrap another error
+fn ucl_errors() -> Vec<UclError> {
+    vec![
+        UclError::DocumentTooLarge { size: 2, limit: 1 },
+        UclError::Cancelled {
+            position: Position::new(),
+        },
+        UclError::InvalidEncoding {
+            detected: "UTF-8",
+            position: 0,
+        },
+        UclError::VariableExpansionDisabled,
+        UclError::PathNotFound {
+            path: String::new(),
+            closest: None,
+        },
+        UclError::Io(std::io::ErrorKind::NotFound.into()),
+    ]
+}
+
+#[test]
+fn test_code_snapshot() {
+    let mut codes: Vec<&str> = lex_errors().iter().map(LexError::code).collect();
+    codes.extend(parse_errors().iter().map(ParseError::code));
+    codes.extend(serde_errors().iter().map(SerdeError::code));
+    codes.extend(conversion_errors().iter().map(ConversionError::code));
+    codes.extend(ucl_errors().iter().map(UclError::code));
+    assert_eq!(codes, CODE_SNAPSHOT);
+
+    let listed: Vec<&str> = [
+        LexError::CODES,
+        ParseError::CODES,
+        SerdeError::CODES,
+        ConversionError::CODES,
+        UclError::CODES,
+    ]
+    .concat();
+    assert_eq!(listed, CODE_SNAPSHOT);
+}
+
+#[test]
+fn test_wrapped_errors_report_the_inner_code() {
+    let err = UclParser::new("a = \"open").parse_document().unwrap_err();
+    assert!(matches!(err, ParseError::Lex(_)));
+    assert_eq!(err.code(), "lex/unterminated-string");
+    assert_eq!(err.position().map(|position| position.line), Some(1));
+
+    let err = from_str::<serde_json::Value>("a = 1;\nb = [1,,2]").unwrap_err();
+    assert_eq!(err.code(), "parse/missing-array-element");
+    assert_eq!(err.position().map(|position| position.line), Some(2));
+
+    let inner = ParseError::RepeatedAssignment {
+        position: Position::new(),
+    };
+    let err = ParseError::InIncludedFile {
+        path: "a.ucl".to_string(),
+        source: Box::new(inner),
+    };
+    assert_eq!(err.code(), "parse/repeated-assignment");
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Server {
+        port: u16,
+    }
+    let err = from_str::<Server>("port = \"http\"").unwrap_err();
+    assert_eq!(err.code(), "serde/message");
+    assert_eq!(err.key_path().as_deref(), Some("port"));
+}
+
+#[cfg(feature = "serde-errors")]
+#[test]
+fn test_json_records() {
+    use serde_json::json;
+
+    let err = from_str::<serde_json::Value>("a = 1\nb = [1,,2]").unwrap_err();
+    let record = serde_json::to_value(&err).unwrap();
+    assert_eq!(record["code"], "parse/missing-array-element");
+    assert_eq!(record["message"], json!(err.to_string()));
+    assert_eq!(record["line"], 2);
+    assert!(record["column"].is_u64() && record["offset"].is_u64());
+    assert_eq!(record["hint"], json!(null));
+    assert_eq!(record["path"], json!(null));
+
+    let err = LexError::InvalidBareWordCharacter {
+        character: '@',
+        position: Position {
+            line: 3,
+            column: 5,
+            offset: 20,
+        },
+        suggestion: "Quote the value".to_string(),
+    };
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "code": "lex/invalid-bare-word-character",
+            "message": "Invalid character '@' in bare word at 3:5",
+            "line": 3,
+            "column": 5,
+            "offset": 20,
+            "hint": "Quote the value",
+            "path": null,
+        })
+    );
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Config {
+        server: Server,
+    }
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Server {
+        port: u16,
+    }
+    let err = from_str::<Config>("server {\n  port = \"http\"\n}").unwrap_err();
+    let record = serde_json::to_value(&err).unwrap();
+    assert_eq!(record["code"], "serde/message");
+    assert_eq!(record["path"], "server.port");
+    assert_eq!(record["line"], 2);
+
+    let err = UclError::DocumentTooLarge { size: 2, limit: 1 };
+    let record = serde_json::to_value(&err).unwrap();
+    assert_eq!(record["code"], "ucl/document-too-large");
+    assert_eq!(
+        (&record["line"], &record["column"], &record["offset"]),
+        (&json!(null), &json!(null), &json!(null))
+    );
+}
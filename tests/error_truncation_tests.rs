@@ -0,0 +1,155 @@
+//! Tests that errors about huge inputs stay short
+//!
+//! Messages quote at most `DEFAULT_MAX_WIDTH` characters of a token or key,
+//! and the source snippet shows that many columns of a line around the error.
+
+use ucl_lexer::error::{DEFAULT_MAX_WIDTH, ErrorContext};
+use ucl_lexer::scalar::parse_scalar;
+use ucl_lexer::{LexError, Position, Token, UclError, UclLexer, UclParser};
+
+const MEGABYTE: usize = 1 << 20;
+
+/// Returns the first error the lexer reports for `input`
+fn lex_error(input: &str) -> LexError {
+    let mut lexer = UclLexer::new(input);
+    loop {
+        match lexer.next_token() {
+            Ok(Token::Eof) => panic!("no error in the input"),
+            Ok(_) => {}
+            Err(error) => return error,
+        }
+    }
+}
+
+#[test]
+fn test_error_far_into_a_long_line() {
+    let text = "x".repeat(MEGABYTE / 2);
+    let input = format!("s = \"{text}\\q{text}\"");
+    let error = UclError::Lex(lex_error(&input));
+    assert!(!error.message_truncated());
+    assert_eq!(error.code(), "lex/invalid-escape");
+
+    let formatted = error.with_source_context(&input).format();
+    assert!(formatted.len() < 1000, "{} bytes", formatted.len());
+
+    // The line is cut on both sides of the escape, with the carets under it
+    let lines: Vec<&str> = formatted.lines().collect();
+    let line = lines
+        .iter()
+        .position(|line| line.starts_with("1 | "))
+        .unwrap();
+    let shown = &lines[line][4..];
+    assert!(shown.starts_with('…') && shown.ends_with('…'), "{shown}");
+    assert_eq!(shown.chars().count(), DEFAULT_MAX_WIDTH);
+    // The position of an invalid escape is the character after the backslash
+    let escape = shown.find('q').unwrap();
+    let caret = lines[line + 1].find('^').unwrap() - 4;
+    assert_eq!(shown[..escape].chars().count(), caret);
+    assert!(caret.abs_diff(DEFAULT_MAX_WIDTH / 2) <= 2, "{caret}");
+}
+
+#[test]
+fn test_unterminated_string_on_a_long_line() {
+    let input = format!("s = \"{}", "x".repeat(MEGABYTE));
+    let error = UclError::Lex(lex_error(&input));
+    let formatted = error.with_source_context(&input).format();
+    assert!(formatted.len() < 1000, "{} bytes", formatted.len());
+    assert!(formatted.contains("\n1 | s = \"xxx"), "{formatted}");
+}
+
+#[test]
+fn test_quoted_text_is_cut() {
+    // The rest of the line after a scalar
+    let input = format!("1 {}", "y".repeat(MEGABYTE));
+    let error = UclError::from(parse_scalar(&input).unwrap_err());
+    assert!(error.message_truncated());
+    let message = error.to_string();
+    assert!(message.len() < 300, "{message}");
+    assert!(message.contains(&format!("'{}…'", "y".repeat(DEFAULT_MAX_WIDTH))));
+
+    // A key written as an array element, quoted twice in the message
+    let input = format!("a = [\n{} = 1\n]", "k".repeat(60_000));
+    let error = UclError::from(UclParser::new(&input).parse_document().unwrap_err());
+    assert_eq!(error.code(), "parse/key-value-in-array");
+    assert!(error.message_truncated());
+    assert!(error.to_string().len() < 600, "{error}");
+
+    // Short text is quoted whole
+    let error = UclError::from(parse_scalar("1 two").unwrap_err());
+    assert!(!error.message_truncated());
+    assert!(error.to_string().contains("'two'"), "{error}");
+}
+
+#[test]
+fn test_max_width_is_configurable() {
+    let input = format!("key = {} bad", "v".repeat(500));
+    let position = ucl_lexer::Position::from_offset(&input, input.find("bad").unwrap());
+    for width in [10, 40, 200] {
+        let snippet = ErrorContext::for_token(input.clone(), position, "bad")
+            .with_max_width(width)
+            .source_snippet();
+        let line = snippet.lines().next().unwrap();
+        assert_eq!(line[4..].chars().count(), width, "{line}");
+        assert!(line.ends_with("bad"), "{line}");
+    }
+}
+
+#[test]
+fn test_columns_past_the_cap_are_not_written_exactly() {
+    let entries: String = (0..20_000).map(|i| format!("key_{i} = {i}; ")).collect();
+    let input = format!("{entries}bad = \"a\\qb\"");
+    let error = UclParser::new(&input).parse_document().unwrap_err();
+    let position = error.position().unwrap();
+    assert_eq!((position.line, position.column), (1, input.len() - 2));
+    assert_eq!(
+        error.to_string(),
+        "Lexical error: Invalid escape sequence '\\q' at line 1, column >100000"
+    );
+
+    let formatted = UclError::from(error).with_source_context(&input).format();
+    assert!(formatted.contains("line 1, column >100000"), "{formatted}");
+
+    // Up to the cap the column is written as before
+    let mut position = Position::new();
+    position.column = Position::DISPLAY_COLUMN_CAP;
+    assert_eq!(position.to_string(), "1:100000");
+    position.column += 1;
+    assert_eq!(position.to_string(), "line 1, column >100000");
+}
+
+#[test]
+fn test_unescape_errors_on_long_lines() {
+    let text = "x".repeat(MEGABYTE);
+    for (input, sequence) in [
+        (format!("a\n{text}\\q"), "q"),
+        (format!("a\n{text}\\x4"), "x"),
+    ] {
+        match UclLexer::unescape_json_string(&input).unwrap_err() {
+            LexError::InvalidEscape {
+                sequence: found,
+                position,
+            } => {
+                assert_eq!(found, sequence);
+                assert_eq!(position.line, 2);
+                // Past the backslash, or the `x` of a hex escape
+                let after = input.rfind('\\').unwrap() + 1;
+                let expected = if sequence == "x" { after + 1 } else { after };
+                assert_eq!(position.offset, expected);
+                assert_eq!(position.column, expected - 1);
+            }
+            other => panic!("expected an invalid escape, got {other:?}"),
+        }
+    }
+
+    let input = format!("{text}\\u{{110000}}");
+    match UclLexer::unescape_json_string(&input).unwrap_err() {
+        LexError::InvalidUnicodeEscape { position, .. } => {
+            assert_eq!(position.offset, MEGABYTE + 2);
+        }
+        other => panic!("expected an invalid unicode escape, got {other:?}"),
+    }
+    assert_eq!(
+        UclLexer::unescape_json_string(&format!("{text}\\ud83d\\ude00")).unwrap(),
+        format!("{text}😀")
+    );
+}
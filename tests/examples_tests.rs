@@ -0,0 +1,86 @@
+//! Runs the examples that read the sample files in `examples/data`
+
+#[path = "../examples/config_layers.rs"]
+mod config_layers;
+// `main` reads the command line, which belongs to the test harness here
+#[allow(dead_code)]
+#[path = "../examples/streaming_transform.rs"]
+mod streaming_transform;
+
+use std::collections::HashMap;
+use std::path::Path;
+use ucl_lexer::{
+    MapVariableHandler, from_str, streaming_lexer_from_file, streaming_lexer_from_reader,
+};
+
+#[test]
+fn test_examples_run() {
+    config_layers::main().unwrap();
+
+    let output = Path::new(env!("CARGO_TARGET_TMPDIR")).join("services.jsonl");
+    let entries =
+        streaming_transform::run(streaming_transform::DEFAULT_INPUT, output.to_str()).unwrap();
+    let written = std::fs::read_to_string(&output).unwrap();
+    assert_eq!(written.lines().count(), entries);
+}
+
+#[test]
+fn test_streaming_transform_matches_tree_parser() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/data/services.ucl");
+    let mut output = Vec::new();
+    let entries =
+        streaming_transform::transform(streaming_lexer_from_file(&path).unwrap(), &mut output)
+            .unwrap();
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), entries);
+
+    // Repeated top-level keys become arrays, as in the tree parser
+    let mut folded = serde_json::Map::new();
+    for line in lines {
+        let (key, value) = line.as_object().unwrap().iter().next().unwrap();
+        match folded.get_mut(key) {
+            Some(serde_json::Value::Array(values)) => values.push(value.clone()),
+            Some(existing) => *existing = serde_json::json!([existing.clone(), value]),
+            None => {
+                folded.insert(key.clone(), value.clone());
+            }
+        }
+    }
+
+    let expected: serde_json::Value = from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(serde_json::Value::Object(folded), expected);
+}
+
+#[test]
+fn test_streaming_transform_reports_unbalanced_input() {
+    let lexer = streaming_lexer_from_reader("a { b = [1, 2 }".as_bytes());
+    let err = streaming_transform::transform(lexer, Vec::new()).unwrap_err();
+    assert!(err.to_string().contains("unexpected '}'"), "{err}");
+}
+
+#[test]
+fn test_config_layers_expand_variables() {
+    let variables = HashMap::from([
+        ("APP_ENV".to_string(), "staging".to_string()),
+        ("DB_HOST".to_string(), "db.staging".to_string()),
+    ]);
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/data");
+    let config =
+        config_layers::load_config(&dir, Box::new(MapVariableHandler::from_map(variables)))
+            .unwrap();
+
+    assert_eq!(config.app.environment, "staging");
+    assert_eq!(
+        config.database.url,
+        "postgres://inventory@db.staging:5432/inventory"
+    );
+    assert_eq!(config.logging.level, "warn");
+    assert_eq!(config.server.host, "0.0.0.0");
+    assert_eq!(config.server.request_timeout, 30.0);
+    assert_eq!(config.database.connect_timeout, 5.0);
+}
@@ -0,0 +1,104 @@
+//! Tests for literal `$` in expanded strings and heredocs
+
+use std::collections::HashMap;
+use ucl_lexer::{
+    MapVariableHandler, StringFormat, Token, UclLexer, UclParser, UclValue, to_ucl_string,
+};
+
+fn parse_with_vars(input: &str) -> UclValue {
+    let handler = MapVariableHandler::from_map(HashMap::from([
+        ("HOST".to_string(), "example.com".to_string()),
+        ("PORT".to_string(), "8080".to_string()),
+    ]));
+    UclParser::with_variable_handler(input, Box::new(handler))
+        .parse_document()
+        .unwrap()
+}
+
+#[test]
+fn test_escapes_in_json_strings() {
+    let value = parse_with_vars(r#"a = "$$HOST", b = "\${HOST} is ${HOST}", c = "\$5""#);
+    assert_eq!(value["a"], "$HOST");
+    assert_eq!(value["b"], "${HOST} is example.com");
+    assert_eq!(value["c"], "$5");
+
+    // A backslash written as `\\` does not escape the expansion
+    let value = parse_with_vars(r#"path = "C:\\${HOST}""#);
+    assert_eq!(value["path"], "C:\\example.com");
+}
+
+#[test]
+fn test_escapes_apply_without_a_variable_handler() {
+    let value = UclParser::new(r#"a = "$$HOST", b = "\${HOST}", c = "${HOST}""#)
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["a"], "$HOST");
+    assert_eq!(value["b"], "${HOST}");
+    assert_eq!(value["c"], "${HOST}");
+}
+
+#[test]
+fn test_nginx_template_in_heredoc() {
+    let input = "\
+nginx = <<EOF
+server {
+    listen ${PORT};
+    server_name ${HOST};
+    set $$backend \\${upstream};
+    proxy_pass http://$$backend;
+}
+EOF
+";
+    let value = parse_with_vars(input);
+    assert_eq!(
+        value["nginx"],
+        "server {\n    listen 8080;\n    server_name example.com;\n    set $backend ${upstream};\n    proxy_pass http://$backend;\n}\n"
+    );
+}
+
+#[test]
+fn test_quoted_terminator_disables_expansion() {
+    let script = "#!/bin/sh\necho \"${HOST:-localhost}\" $$ \\${PORT}\nexit $?\n";
+    let input = format!("script = <<'EOF'\n{}EOF\nhost = \"${{HOST}}\"\n", script);
+    let value = parse_with_vars(&input);
+    assert_eq!(value["script"], script);
+    assert_eq!(value["host"], "example.com");
+
+    let mut lexer = UclLexer::new("<<'SH'\necho $HOME\nSH\n");
+    assert_eq!(
+        lexer.next_token().unwrap(),
+        Token::String {
+            value: "echo $HOME\n".into(),
+            format: StringFormat::Heredoc,
+            needs_expansion: false,
+        }
+    );
+
+    // The quote is not part of the terminator and combines with `<<-`
+    let value = parse_with_vars("a {\n    run = <<-'EOF'\n        echo $PORT\n    EOF\n}\n");
+    assert_eq!(value["a"]["run"], "echo $PORT\n");
+}
+
+#[test]
+fn test_unclosed_quoted_terminator() {
+    let err = UclParser::new("script = <<'EOF\necho\nEOF\n")
+        .parse_document()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Heredoc terminator 'EOF' is missing its closing quote"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_serializer_round_trips_dollar_signs() {
+    let mut object = ucl_lexer::UclObject::new();
+    object.insert("price".into(), UclValue::from("$5 or $$10 ${HOST}"));
+    object.insert("$key".into(), UclValue::from(1));
+    let value = UclValue::Object(object);
+
+    let text = to_ucl_string(&value);
+    assert_eq!(parse_with_vars(&text), value);
+    assert_eq!(UclParser::new(&text).parse_document().unwrap(), value);
+}
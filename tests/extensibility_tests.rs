@@ -5,6 +5,7 @@
 
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::Arc;
 use ucl_lexer::{
     ChainedVariableHandler, ConfigValidationPlugin, CssUnitsPlugin, CustomUnitSuffixHandler,
     EnvironmentVariableHandler, MapVariableHandler, NumberSuffixHandler, ParseError, ParsingHooks,
@@ -97,16 +98,13 @@ impl ValidationHook for RestrictiveValidationHook {
         value: &UclValue,
         context: &VariableContext,
     ) -> Result<Option<UclValue>, ParseError> {
-        match value {
-            UclValue::String(s) => {
-                if self.forbidden_values.contains(s) {
-                    return Err(ParseError::InvalidObject {
-                        message: format!("Value '{}' is not allowed", s),
-                        position: context.position,
-                    });
-                }
-            }
-            _ => {}
+        if let UclValue::String(s) = value
+            && self.forbidden_values.contains(s)
+        {
+            return Err(ParseError::InvalidObject {
+                message: format!("Value '{}' is not allowed", s),
+                position: context.position,
+            });
         }
         Ok(None) // Don't modify the value
     }
@@ -493,6 +491,42 @@ fn test_parser_builder_with_plugin_config() {
     let _ = parser;
 }
 
+#[test]
+fn test_shared_parser_across_threads() {
+    let mut config = PluginConfig::new();
+    config.set("uppercase".to_string(), "true".to_string());
+
+    let mut registry = PluginRegistry::new();
+    registry.set_plugin_config("test-plugin".to_string(), config);
+    registry
+        .register_plugin(Box::new(TestPlugin::new()))
+        .unwrap();
+
+    let parser = UclParserBuilder::new("")
+        .with_plugin_registry(registry)
+        .build_shared()
+        .unwrap();
+
+    let documents: Vec<String> = (0..100)
+        .map(|i| format!("id = {}\nname = \"${{TEST_VAR}}\"", i))
+        .collect();
+
+    std::thread::scope(|scope| {
+        for chunk in documents.chunks(25) {
+            let parser = Arc::clone(&parser);
+            scope.spawn(move || {
+                for document in chunk {
+                    let value = parser.parse(document).unwrap();
+                    assert_eq!(value["name"], "TEST_VALUE");
+                    assert!(value["id"].as_integer().is_some());
+                }
+            });
+        }
+    });
+
+    assert_eq!(parser.parse("id = 7").unwrap()["id"], 7);
+}
+
 #[test]
 fn test_chained_variable_handlers() {
     let mut chained = ChainedVariableHandler::new();
@@ -826,7 +860,7 @@ fn test_environment_variable_handler() {
         std::env::set_var("UCL_TEST_VAR", "test_env_value");
     }
 
-    let handler = EnvironmentVariableHandler;
+    let handler = EnvironmentVariableHandler::new();
 
     // Test existing environment variable
     assert_eq!(
@@ -0,0 +1,146 @@
+//! Tests for float precision, negative zero and round-trip formatting
+
+use ucl_lexer::{
+    NonFinitePolicy, ParseError, ParserConfig, UclParser, UclValue, UclWriter, to_ucl_string,
+};
+
+fn parse_float(input: &str) -> f64 {
+    let value = UclParser::new(&format!("a = {}", input))
+        .parse_document()
+        .unwrap();
+    value["a"].as_float().unwrap()
+}
+
+fn serialize(value: f64) -> String {
+    let mut object = ucl_lexer::UclObject::new();
+    object.insert("a".into(), UclValue::Float(value));
+    to_ucl_string(&UclValue::Object(object))
+}
+
+/// Deterministic xorshift sequence of float bit patterns
+fn random_floats(count: usize) -> impl Iterator<Item = f64> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    std::iter::repeat_with(move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        f64::from_bits(state)
+    })
+    .filter(|value| value.is_finite())
+    .take(count)
+}
+
+#[test]
+fn test_extreme_literals() {
+    assert_eq!(parse_float("5e-324"), 5e-324);
+    assert_eq!(parse_float("5e-324").to_bits(), 1);
+    assert_eq!(parse_float("1.7976931348623157e308"), f64::MAX);
+    assert_eq!(parse_float("-1.7976931348623157e308"), f64::MIN);
+    assert_eq!(parse_float("2.2250738585072014e-308"), f64::MIN_POSITIVE);
+    // Below half the smallest subnormal rounds to zero, keeping the sign
+    assert_eq!(parse_float("1e-400").to_bits(), 0.0f64.to_bits());
+    assert_eq!(parse_float("-1e-400").to_bits(), (-0.0f64).to_bits());
+}
+
+#[test]
+fn test_overflow_follows_non_finite_policy() {
+    assert_eq!(parse_float("1e400"), f64::INFINITY);
+    assert_eq!(parse_float("-1e400"), f64::NEG_INFINITY);
+
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Error);
+    let err = UclParser::new("limits { max = 1e400 }")
+        .with_config(config)
+        .parse_document()
+        .unwrap_err();
+    match err {
+        ParseError::NonFiniteNumber { value, position } => {
+            assert_eq!(value, "1e400");
+            assert_eq!((position.line, position.column), (1, 16));
+        }
+        other => panic!("Expected NonFiniteNumber, got {other:?}"),
+    }
+
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Null);
+    let mut parser = UclParser::new("a = -1e400").with_config(config);
+    let value = parser.parse_document().unwrap();
+    assert!(value["a"].is_null());
+    assert_eq!(
+        parser.diagnostics()[0].message,
+        "Non-finite number '-1e400' replaced with null"
+    );
+}
+
+#[test]
+fn test_negative_zero() {
+    let value = parse_float("-0.0");
+    assert!(value == 0.0 && value.is_sign_negative());
+    assert_eq!(UclValue::Float(-0.0), UclValue::Float(0.0));
+    assert_eq!(serialize(-0.0), "a = -0.0;\n");
+    assert_eq!(UclValue::Float(-0.0).to_string(), "-0.0");
+
+    let mut value = UclValue::Float(-0.0);
+    value.canonicalize();
+    assert_eq!(value.to_string(), "0.0");
+}
+
+#[test]
+fn test_large_and_small_magnitudes_use_exponents() {
+    assert_eq!(UclValue::Float(1e16).to_string(), "1e16");
+    assert_eq!(
+        UclValue::Float(f64::MAX).to_string(),
+        "1.7976931348623157e308"
+    );
+    assert_eq!(UclValue::Float(5e-324).to_string(), "5e-324");
+    assert_eq!(UclValue::Float(1.5e-7).to_string(), "1.5e-7");
+    assert_eq!(UclValue::Float(123456.0).to_string(), "123456.0");
+    assert_eq!(
+        UclValue::Float(0.1 + 0.2).to_string(),
+        "0.30000000000000004"
+    );
+    assert_eq!(UclValue::Time(1e-7).to_string(), "1e-7s");
+
+    // Integral floats beyond the plain range still read back as floats
+    let text = serialize(1e17);
+    assert_eq!(
+        UclParser::new(&text).parse_document().unwrap()["a"],
+        UclValue::Float(1e17)
+    );
+}
+
+#[test]
+fn test_random_floats_round_trip_bitwise() {
+    for value in random_floats(20_000).chain([0.0, -0.0, 1e-5, 1e16, 9.999999999999998e15]) {
+        let text = serialize(value);
+        let parsed = UclParser::new(&text).parse_document().unwrap();
+        let UclValue::Float(read) = parsed["a"] else {
+            panic!("{text} read back as {:?}", parsed["a"]);
+        };
+        assert_eq!(read.to_bits(), value.to_bits(), "{text}");
+    }
+}
+
+#[test]
+fn test_writer_and_display_round_trip() {
+    let values: Vec<f64> = random_floats(1_000).collect();
+    let mut writer = UclWriter::new(Vec::new());
+    writer.begin_array("values").unwrap();
+    for value in &values {
+        writer.write_scalar(None, *value).unwrap();
+    }
+    writer.end_array().unwrap();
+    let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    let parsed = UclParser::new(&text).parse_document().unwrap();
+    let read: Vec<u64> = parsed["values"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_float().unwrap().to_bits())
+        .collect();
+    assert_eq!(read, values.iter().map(|v| v.to_bits()).collect::<Vec<_>>());
+
+    for value in &values {
+        let text = UclValue::Float(*value).to_string();
+        assert_eq!(text.parse::<UclValue>().unwrap(), UclValue::Float(*value));
+    }
+}
@@ -0,0 +1,216 @@
+//! Tests for lexing, parsing and deserializing input split into fragments
+
+use serde::Deserialize;
+use std::borrow::Cow;
+use ucl_lexer::{Position, Token, UclLexer, UclParser, from_fragments};
+
+const DOCUMENT: &str = r#"# Service settings
+name = "café ☕ server";
+path = '/srv/data';
+port = 8080;
+ratio = -12.5e3;
+timeout = 30s;
+cache = 64mb;
+limit = infinity;
+enabled = yes;
+tags = ["α", "β", "γ"];
+motd = <<EOT
+Welcome to ünïcode
+EOT
+
+/* block
+   comment */
+upstream {
+    host = "10.0.0.1";
+    weight = 0x1F;
+}
+"#;
+
+/// Lexes every token with its span
+fn lex(mut lexer: UclLexer<'_>) -> Vec<(Token<'static>, Position, Position)> {
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token().unwrap().into_owned();
+        tokens.push((
+            token.clone(),
+            lexer.last_token_start(),
+            lexer.last_token_end(),
+        ));
+        if token == Token::Eof {
+            return tokens;
+        }
+    }
+}
+
+/// Splits `input` at each character boundary into two fragments
+fn splits(input: &str) -> impl Iterator<Item = (&str, &str)> {
+    (0..=input.len())
+        .filter(|&at| input.is_char_boundary(at))
+        .map(|at| input.split_at(at))
+}
+
+#[test]
+fn test_tokens_match_at_every_split() {
+    let expected = lex(UclLexer::new(DOCUMENT));
+    for (head, tail) in splits(DOCUMENT) {
+        let tokens = lex(UclLexer::from_fragments(&[head, tail]));
+        assert_eq!(tokens, expected, "split after {:?}", head);
+    }
+}
+
+#[test]
+fn test_values_match_across_many_fragments() {
+    let expected = UclParser::new(DOCUMENT).parse_document().unwrap();
+    for size in 1..8 {
+        let mut fragments = Vec::new();
+        let mut rest = DOCUMENT;
+        while !rest.is_empty() {
+            let mut at = size.min(rest.len());
+            while !rest.is_char_boundary(at) {
+                at += 1;
+            }
+            let (fragment, tail) = rest.split_at(at);
+            fragments.push(fragment);
+            rest = tail;
+        }
+        let value = UclParser::from_fragments(&fragments)
+            .parse_document()
+            .unwrap();
+        assert_eq!(value, expected, "fragments of {} bytes", size);
+    }
+}
+
+#[test]
+fn test_boundary_inside_tokens() {
+    let cases: &[(&[&str], Token<'static>)] = &[
+        (
+            &["key = \"hel", "lo\""],
+            Token::String {
+                value: "hello".into(),
+                format: ucl_lexer::StringFormat::Json,
+                needs_expansion: false,
+            },
+        ),
+        (&["key = 12", "34"], Token::Integer(1234)),
+        (&["key = 10", "mb"], Token::Integer(10 * 1024 * 1024)),
+        (&["key = inf", "inity"], Token::Float(f64::INFINITY)),
+        (&["na", "me = 1"], Token::Key("name".into())),
+        (
+            &["key = \"é", "☕\""],
+            Token::String {
+                value: "é☕".into(),
+                format: ucl_lexer::StringFormat::Json,
+                needs_expansion: false,
+            },
+        ),
+    ];
+
+    for (fragments, expected) in cases {
+        let tokens = lex(UclLexer::from_fragments(fragments));
+        assert!(
+            tokens.iter().any(|(token, _, _)| token == expected),
+            "{:?} in {:?}",
+            expected,
+            tokens
+        );
+    }
+}
+
+#[test]
+fn test_heredoc_across_several_fragments() {
+    let fragments = [
+        "text = <<E",
+        "OT\nfirst li",
+        "ne\n",
+        "second line\nE",
+        "OT\n",
+    ];
+    let value = UclParser::from_fragments(&fragments)
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["text"], "first line\nsecond line\n");
+}
+
+#[test]
+fn test_positions_are_global() {
+    let mut lexer = UclLexer::from_fragments(&["a = 1\n", "bé = ", "\"x\""]);
+    let mut spans = Vec::new();
+    while lexer.next_token().unwrap() != Token::Eof {
+        let start = lexer.last_token_start();
+        spans.push((start.line, start.column, start.offset));
+    }
+    assert_eq!(
+        spans,
+        [
+            (1, 1, 0),
+            (1, 3, 2),
+            (1, 5, 4),
+            (2, 1, 6),
+            (2, 4, 10),
+            (2, 6, 12)
+        ]
+    );
+}
+
+#[test]
+fn test_tokens_inside_a_fragment_are_borrowed() {
+    let mut lexer =
+        UclLexer::from_fragments(&["first = \"one\"\nlevel = 1\n", "second = \"tw", "o\"\n"]);
+    let mut strings = Vec::new();
+    loop {
+        match lexer.next_token().unwrap() {
+            Token::Eof => break,
+            Token::String { value, .. } => strings.push(value),
+            _ => {}
+        }
+    }
+    assert!(matches!(&strings[0], Cow::Borrowed("one")));
+    assert!(matches!(&strings[1], Cow::Owned(value) if value == "two"));
+}
+
+#[test]
+fn test_errors_report_global_positions() {
+    let err = UclParser::from_fragments(&["a = 1\n", "b = \"open"])
+        .parse_document()
+        .unwrap_err();
+    assert!(err.to_string().contains("2:"), "{err}");
+}
+
+#[test]
+fn test_empty_fragments_are_skipped() {
+    let value = UclParser::from_fragments(&["", "a = ", "", "1", ""])
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["a"], 1);
+}
+
+#[test]
+fn test_deserialize_fragments() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Upstream {
+        host: String,
+        weight: u32,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Service {
+        name: String,
+        port: u16,
+        tags: Vec<String>,
+        upstream: Upstream,
+    }
+
+    let (header, rest) = DOCUMENT.split_at(DOCUMENT.find("8080").unwrap() + 2);
+    let (body, footer) = rest.split_at(rest.find("host").unwrap() + 2);
+    let service: Service = from_fragments(&[header, body, footer]).unwrap();
+    assert_eq!(service.name, "café ☕ server");
+    assert_eq!(service.port, 8080);
+    assert_eq!(service.tags, ["α", "β", "γ"]);
+    assert_eq!(
+        service.upstream,
+        Upstream {
+            host: "10.0.0.1".to_string(),
+            weight: 31,
+        }
+    );
+}
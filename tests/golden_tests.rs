@@ -0,0 +1,80 @@
+//! Golden-file tests comparing lexer output with checked-in JSON
+//!
+//! Each `tests/golden/*.ucl` sample is tokenized with `tokens_to_json` and
+//! compared with the `.json` file next to it. Run with `UPDATE_GOLDEN=1` to
+//! rewrite the expected output after an intended change.
+#![cfg(feature = "serde-token")]
+
+use std::fs;
+use std::path::Path;
+use ucl_lexer::{Position, Span, StringFormat, Token, UclLexer, tokens_to_json};
+
+#[test]
+fn test_golden_tokenization() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut samples: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ucl"))
+        .collect();
+    samples.sort();
+    assert!(!samples.is_empty());
+
+    for sample in samples {
+        let input = fs::read_to_string(&sample).unwrap();
+        let actual = tokens_to_json(&input).unwrap() + "\n";
+        let golden = sample.with_extension("json");
+
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        let expected =
+            fs::read_to_string(&golden).unwrap_or_else(|_| panic!("missing {}", golden.display()));
+        assert_eq!(actual, expected, "{}", sample.display());
+    }
+}
+
+#[test]
+fn test_token_round_trip() {
+    let mut lexer = UclLexer::new("key = \"a\\tb\" # note\nlist [1, 2.5, 10s]");
+    let mut tokens = Vec::new();
+    loop {
+        match lexer.next_token().unwrap() {
+            Token::Eof => break,
+            token => tokens.push(token),
+        }
+    }
+
+    let json = serde_json::to_string(&tokens).unwrap();
+    let restored: Vec<Token> = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, tokens);
+}
+
+#[test]
+fn test_string_token_value_is_plain_string() {
+    let token = Token::String {
+        value: "hello".into(),
+        format: StringFormat::Single,
+        needs_expansion: false,
+    };
+    let json = serde_json::to_value(&token).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "String": { "value": "hello", "format": "Single", "needs_expansion": false }
+        })
+    );
+}
+
+#[test]
+fn test_span_round_trip() {
+    let span = Span::new(Position::new(), Position::from_offset("ab\ncd", 4));
+    let json = serde_json::to_string(&span).unwrap();
+    assert_eq!(serde_json::from_str::<Span>(&json).unwrap(), span);
+}
+
+#[test]
+fn test_lex_error_is_reported() {
+    assert!(tokens_to_json("key = \"unterminated").is_err());
+}
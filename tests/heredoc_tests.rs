@@ -1,5 +1,6 @@
 use serde_json::Value;
-use ucl_lexer::{UclError, from_str};
+use std::io::Cursor;
+use ucl_lexer::{LexerConfig, StreamingUclLexer, Token, UclError, UclParser, from_str};
 
 #[cfg(test)]
 mod heredoc_tests {
@@ -308,4 +309,104 @@ EOF
         assert!(sql.contains("CREATE TABLE users"));
         assert!(sql.contains("id SERIAL PRIMARY KEY"));
     }
+
+    #[test]
+    fn test_indented_heredoc_in_nested_blocks() {
+        let config = "server {\n    location {\n        script = <<-EOF\n            echo start\n              echo nested\n\n            echo done\n        EOF\n        after = 1\n    }\n}\n";
+
+        let result: Value = from_str(config).expect("Should parse indented heredoc");
+        let location = &result["server"]["location"];
+        assert_eq!(
+            location["script"],
+            "echo start\n  echo nested\n\necho done\n"
+        );
+        assert_eq!(location["after"], 1);
+    }
+
+    #[test]
+    fn test_indented_heredoc_mixed_tabs_and_spaces() {
+        // Tabs and spaces are not interchangeable, only the shared prefix is removed
+        let config = "text = <<-EOF\n\t  one\n\t\ttwo\n\t  three\n\tEOF\n";
+
+        let result: Value = from_str(config).expect("Should parse indented heredoc");
+        assert_eq!(result["text"], "  one\n\ttwo\n  three\n");
+    }
+
+    #[test]
+    fn test_indented_heredoc_content_less_indented_than_terminator() {
+        // The terminator's own indentation does not limit what is stripped
+        let config = "a {\n    text = <<-EOF\n  shallow\n      deep\n        EOF\n}\n";
+
+        let result: Value = from_str(config).expect("Should parse indented heredoc");
+        assert_eq!(result["a"]["text"], "shallow\n    deep\n");
+    }
+
+    #[test]
+    fn test_plain_heredoc_keeps_strict_terminator() {
+        // Without '-' an indented terminator is still content
+        let config = "a {\n    text = <<EOF\n    line\n    EOF\n}\n";
+        assert!(from_str::<Value>(config).is_err());
+
+        let config = "text = <<EOF\n    line\n    EOF\nEOF\n";
+        let result: Value = from_str(config).unwrap();
+        assert_eq!(result["text"], "    line\n    EOF\n");
+    }
+
+    fn relaxed() -> LexerConfig {
+        LexerConfig::new().with_strict_heredoc_terminators(false)
+    }
+
+    #[test]
+    fn test_relaxed_terminators() {
+        let config = "a = <<eof\nline\neof\nb = <<Mixed_Case1\nEOF\nMIXED_CASE1\nMixed_Case1\n";
+        let value = UclParser::with_lexer_config(config, relaxed())
+            .parse_document()
+            .unwrap();
+        assert_eq!(value["a"], "line\n");
+        // Matching is case-sensitive, so other spellings are content
+        assert_eq!(value["b"], "EOF\nMIXED_CASE1\n");
+
+        // The default still requires uppercase
+        assert!(LexerConfig::default().strict_heredoc_terminators);
+        let err = from_str::<Value>("a = <<eof\nline\neof\n").unwrap_err();
+        assert!(err.to_string().contains("uppercase ASCII letters"), "{err}");
+    }
+
+    #[test]
+    fn test_relaxed_terminator_errors() {
+        let err = UclParser::with_lexer_config("a = <<\nline\n", relaxed())
+            .parse_document()
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot be empty"), "{err}");
+
+        let err = UclParser::with_lexer_config("a = <<End\nline\nEND\n", relaxed())
+            .parse_document()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("expected terminator 'End'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_streaming_relaxed_terminators() {
+        let input = "a = <<eof\nline\neof\n";
+        let mut lexer =
+            StreamingUclLexer::new(Cursor::new(input)).with_strict_heredoc_terminators(false);
+        let mut strings = Vec::new();
+        loop {
+            match lexer.next_token().unwrap() {
+                Token::Eof => break,
+                Token::String { value, .. } => strings.push(value.into_owned()),
+                _ => {}
+            }
+        }
+        assert_eq!(strings, ["line\n"]);
+
+        let mut lexer = StreamingUclLexer::new(Cursor::new(input));
+        let err = std::iter::from_fn(|| Some(lexer.next_token()))
+            .find_map(Result::err)
+            .unwrap();
+        assert!(err.to_string().contains("uppercase ASCII letters"), "{err}");
+    }
 }
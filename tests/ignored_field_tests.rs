@@ -0,0 +1,131 @@
+//! Tests for deserializing structs that leave sections of the document unread
+
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+use std::collections::HashMap;
+use ucl_lexer::{MapVariableHandler, ParserConfig, UclDeserializer, UclError, UclParser, from_str};
+
+const DOCUMENT: &str = r#"
+name = "api"
+metrics {
+    series_a {
+        query = "rate(requests{job='${JOB}'}[5m])"
+        buckets = [0.1, [0.5, 1], { le = 5 }]
+    }
+    # } a brace in a comment
+    note = <<EOD
+} not the end of the section {
+EOD
+    hosts = [ "a", "b" ]
+}
+listen { port = 443, address = "0.0.0.0" }
+extra = [1, 2, 3]
+"#;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Listen {
+    port: u16,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Service {
+    name: String,
+    listen: Listen,
+}
+
+fn skipping_config() -> ParserConfig {
+    ParserConfig::new().with_skip_undeclared_sections(true)
+}
+
+/// Deserializes `input` with undeclared sections skipped
+fn skipping<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, UclError> {
+    T::deserialize(UclDeserializer::with_parser_config(
+        input,
+        skipping_config(),
+    ))
+}
+
+#[test]
+fn test_fields_after_ignored_sections() {
+    let service: Service = skipping(DOCUMENT).unwrap();
+    assert_eq!(
+        service,
+        Service {
+            name: "api".to_string(),
+            listen: Listen { port: 443 },
+        }
+    );
+
+    // Variables in ignored sections are not expanded, so a handler that
+    // lacks them is never asked
+    let handler = Box::new(MapVariableHandler::from_map(HashMap::new()));
+    let parser = UclParser::with_variable_handler(DOCUMENT, handler).with_config(skipping_config());
+    let service = Service::deserialize(UclDeserializer::from_parser(parser)).unwrap();
+    assert_eq!(service.listen.port, 443);
+
+    // Without skipping the result is the same
+    assert_eq!(from_str::<Service>(DOCUMENT).unwrap(), service);
+}
+
+#[test]
+fn test_sections_are_validated_by_default() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Port {
+        port: u16,
+    }
+
+    for input in ["port = 80\nother = [1, 2\n", "port = 80\nother { a = = 1 }"] {
+        assert!(from_str::<Port>(input).is_err(), "{input}");
+        assert!(from_str::<serde_json::Value>(input).is_err(), "{input}");
+        assert!(
+            IgnoredAny::deserialize(UclDeserializer::new(input)).is_err(),
+            "{input}"
+        );
+
+        // Opting in skips the broken section
+        assert_eq!(skipping::<Port>(input).unwrap().port, 80, "{input}");
+    }
+}
+
+#[test]
+fn test_unknown_fields_are_still_reported() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Strict {
+        name: String,
+    }
+
+    for err in [
+        from_str::<Strict>(DOCUMENT).unwrap_err(),
+        skipping::<Strict>(DOCUMENT).unwrap_err(),
+    ] {
+        assert!(err.to_string().contains("unknown field `metrics`"), "{err}");
+    }
+}
+
+#[test]
+fn test_aliases_and_ignored_documents() {
+    #[derive(Debug, Deserialize)]
+    struct Aliased {
+        #[serde(alias = "metrics")]
+        telemetry: HashMap<String, IgnoredAny>,
+    }
+
+    let aliased: Aliased = skipping(DOCUMENT).unwrap();
+    assert_eq!(aliased.telemetry.len(), 3);
+
+    IgnoredAny::deserialize(UclDeserializer::with_parser_config(
+        DOCUMENT,
+        skipping_config(),
+    ))
+    .unwrap();
+
+    // Skipped sections are still lexed
+    let err = skipping::<Service>("other = \"unterminated\nname = \"api\"").unwrap_err();
+    assert!(
+        matches!(err, UclError::Parse(_) | UclError::Lex(_)),
+        "{err}"
+    );
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::approx_constant)]
+
 use serde_json::Value;
 use ucl_lexer::from_str;
 
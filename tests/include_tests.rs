@@ -0,0 +1,292 @@
+//! Tests for `.include` directives with glob, optional and priority parameters
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use ucl_lexer::{
+    FsIncludeResolver, IncludeResolver, ParseError, ParserConfig, UclParser, UclValue,
+};
+
+/// Creates an empty directory for one test under the system temp directory
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ucl-include-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("conf.d")).unwrap();
+    dir
+}
+
+/// Resolver serving files from a map
+struct MapResolver(HashMap<&'static str, &'static str>);
+
+impl MapResolver {
+    fn new(files: &[(&'static str, &'static str)]) -> Self {
+        Self(files.iter().copied().collect())
+    }
+}
+
+impl IncludeResolver for MapResolver {
+    fn read(&self, path: &str) -> io::Result<String> {
+        self.0
+            .get(path)
+            .map(|text| text.to_string())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+
+    fn glob(&self, pattern: &str) -> io::Result<Vec<String>> {
+        let prefix = pattern.trim_end_matches('*');
+        Ok(self
+            .0
+            .keys()
+            .filter(|path| path.starts_with(prefix))
+            .map(|path| path.to_string())
+            .collect())
+    }
+}
+
+#[test]
+fn test_glob_try_and_priority_from_the_file_system() {
+    let dir = temp_dir("conf-d");
+    fs::write(
+        dir.join("main.ucl"),
+        "port = 80\nworkers = 2\n\
+         .include(glob=true) \"conf.d/*.ucl\"\n\
+         .include(try=true) \"local.ucl\"\n",
+    )
+    .unwrap();
+    // Written out of order, and read in file name order
+    fs::write(dir.join("conf.d/30-late.ucl"), "port = 3000\nlevel = late").unwrap();
+    fs::write(dir.join("conf.d/10-base.ucl"), "port = 1000\nlevel = base").unwrap();
+    fs::write(dir.join("conf.d/20-tls.ucl"), "tls { enabled = true }").unwrap();
+    fs::write(dir.join("conf.d/notes.txt"), "not ucl {").unwrap();
+
+    let input = fs::read_to_string(dir.join("main.ucl")).unwrap();
+    let config = ParserConfig::new().with_track_provenance(true);
+    let mut parser = UclParser::new(&input)
+        .with_config(config)
+        .with_source_name("main.ucl")
+        .with_include_resolver(FsIncludeResolver::new(&dir));
+    let tracked = parser.parse_tracked().unwrap();
+
+    assert_eq!(tracked.value["port"], 3000);
+    assert_eq!(tracked.value["level"], "late");
+    assert_eq!(tracked.value["workers"], 2);
+    assert_eq!(tracked.value["tls"]["enabled"], true);
+    assert_eq!(
+        tracked.origin("port").unwrap().source_name,
+        "conf.d/30-late.ucl"
+    );
+    assert_eq!(tracked.origin("workers").unwrap().source_name, "main.ucl");
+
+    let messages: Vec<_> = parser
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.message.as_str())
+        .collect();
+    assert_eq!(
+        messages,
+        [
+            "included 'conf.d/10-base.ucl'",
+            "included 'conf.d/20-tls.ucl'",
+            "included 'conf.d/30-late.ucl'",
+            "skipped missing optional include 'local.ucl'",
+        ]
+    );
+    assert_eq!(parser.diagnostics()[0].position.line, 3);
+    assert_eq!(parser.diagnostics()[3].position.line, 4);
+
+    // A priority parameter keeps the lowest file from being overridden
+    let input = ".include(glob=true) \"conf.d/3*.ucl\"\n\
+                 .include(priority=5) \"conf.d/10-base.ucl\"\n";
+    let value = UclParser::new(input)
+        .with_include_resolver(FsIncludeResolver::new(&dir))
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["port"], 1000);
+    assert_eq!(value["level"], "base");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_priorities_decide_between_document_and_includes() {
+    let resolver = || {
+        MapResolver::new(&[
+            ("low.ucl", "port = 1\nname = low"),
+            ("high.ucl", ".priority 9\nport = 2"),
+        ])
+    };
+
+    // Includes are layered over the document, wherever the directive is
+    let value = UclParser::new("port = 0\n.include \"low.ucl\"\nname = main")
+        .with_include_resolver(resolver())
+        .parse_document()
+        .unwrap();
+    assert_eq!(
+        (&value["port"], &value["name"]),
+        (&UclValue::from(1), &UclValue::from("low"))
+    );
+
+    // The document wins when its priority is higher
+    let value = UclParser::new(".priority 3\nport = 0\n.include \"low.ucl\"")
+        .with_include_resolver(resolver())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["port"], 0);
+
+    // A priority parameter replaces the file's own priority
+    let input = ".include(priority=1) \"high.ucl\"\n.include \"low.ucl\"";
+    let value = UclParser::new(input)
+        .with_include_resolver(resolver())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["port"], 2);
+    let input = ".include(priority=0) \"high.ucl\"\n.include \"low.ucl\"";
+    let value = UclParser::new(input)
+        .with_include_resolver(resolver())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["port"], 1);
+}
+
+#[test]
+fn test_nested_includes() {
+    let resolver = MapResolver::new(&[("a.ucl", "a = 1\n.include \"b.ucl\""), ("b.ucl", "b = 2")]);
+    let value = UclParser::new(".include \"a.ucl\"")
+        .with_include_resolver(resolver)
+        .parse_document()
+        .unwrap();
+    assert_eq!(
+        (&value["a"], &value["b"]),
+        (&UclValue::from(1), &UclValue::from(2))
+    );
+
+    let resolver = MapResolver::new(&[("self.ucl", ".include \"self.ucl\"")]);
+    let err = UclParser::new(".include \"self.ucl\"")
+        .with_include_resolver(resolver)
+        .parse_document()
+        .unwrap_err();
+    assert!(err.to_string().contains("nested more than"), "{err}");
+}
+
+#[test]
+fn test_included_files_are_limited() {
+    // Each level includes the next ten times, which reads 1111 files within
+    // the nesting limit
+    let tenfold =
+        |next: &str| -> &'static str { format!(".include \"{}\"\n", next).repeat(10).leak() };
+    let resolver = MapResolver::new(&[
+        ("a.ucl", tenfold("b.ucl")),
+        ("b.ucl", tenfold("c.ucl")),
+        ("c.ucl", tenfold("d.ucl")),
+        ("d.ucl", "x = 1"),
+    ]);
+    let err = UclParser::new(".include \"a.ucl\"")
+        .with_include_resolver(resolver)
+        .parse_document()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("more than 1024 files are included"),
+        "{err}"
+    );
+
+    let resolver = MapResolver::new(&[(
+        "big.ucl",
+        "padding = \"0123456789012345678901234567890123456789\"",
+    )]);
+    let parse = |max_document_size| {
+        UclParser::new(".include \"big.ucl\"\n.include \"big.ucl\"")
+            .with_config(ParserConfig::new().with_max_document_size(max_document_size))
+            .with_include_resolver(MapResolver(resolver.0.clone()))
+            .parse_document()
+    };
+    assert!(parse(200).is_ok());
+    let err = parse(100).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot include 'big.ucl' at 2:1: included files hold more than the maximum document \
+         size of 100 bytes"
+    );
+}
+
+#[test]
+fn test_file_system_includes_stay_inside_the_base() {
+    let dir = temp_dir("sandbox");
+    fs::write(dir.join("secret.ucl"), "secret = 1").unwrap();
+    fs::write(dir.join("conf.d/app.ucl"), "port = 80").unwrap();
+    let resolver = FsIncludeResolver::new(dir.join("conf.d"));
+
+    assert_eq!(resolver.read("app.ucl").unwrap(), "port = 80");
+    let secret = dir.join("secret.ucl").canonicalize().unwrap();
+    for path in ["../secret.ucl", secret.to_str().unwrap()] {
+        let err = resolver.read(path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied, "{path}");
+    }
+    let err = resolver.glob("../*.ucl").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+    // `try=true` only skips missing files
+    let err = UclParser::new(".include(try=true) \"../secret.ucl\"")
+        .with_include_resolver(resolver)
+        .parse_document()
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot include '../secret.ucl' at 1:1: '../secret.ucl' is outside the include directory"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_include_errors() {
+    let parse = |input: &str| {
+        UclParser::new(input)
+            .with_include_resolver(MapResolver::new(&[("bad.ucl", "a = [1,")]))
+            .parse_document()
+    };
+
+    let err = parse(".include \"missing.ucl\"").unwrap_err();
+    assert!(matches!(&err, ParseError::IncludeFailed { path, .. } if path == "missing.ucl"));
+
+    let err = parse(".include(glob=true) \"none/*\"").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Cannot include 'none/*' at 1:1: no files match the pattern"
+    );
+    assert!(parse(".include(glob=true, try=true) \"none/*\"").is_ok());
+
+    let err = parse(".include \"bad.ucl\"").unwrap_err();
+    match &err {
+        ParseError::InIncludedFile { path, source } => {
+            assert_eq!(path, "bad.ucl");
+            assert!(
+                matches!(**source, ParseError::UnexpectedToken { .. }),
+                "{source:?}"
+            );
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    let err = parse(".include(recursive=true) \"a.ucl\"").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid include at 1:1: unknown parameter `recursive`, expected glob, try or priority"
+    );
+    let err = parse(".include(priority=high) \"a.ucl\"").unwrap_err();
+    assert!(matches!(err, ParseError::InvalidInclude { .. }));
+    let err = parse(".include(glob=true \"a.ucl\"").unwrap_err();
+    assert!(
+        err.to_string().contains("Unexpected character '('"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_without_a_resolver_include_is_a_key() {
+    let value = UclParser::new(".include \"a.ucl\"")
+        .parse_document()
+        .unwrap();
+    assert_eq!(value[".include"], "a.ucl");
+}
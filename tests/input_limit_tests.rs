@@ -0,0 +1,163 @@
+//! Tests for the length limits on keys, identifiers and heredocs
+
+use ucl_lexer::{
+    LexError, LexerConfig, ParseError, Token, UclDocument, UclError, UclLexer, UclParser,
+};
+
+fn config() -> LexerConfig {
+    LexerConfig::new()
+        .with_max_key_length(8)
+        .with_max_string_length(32)
+}
+
+fn lex_all(input: &str, config: LexerConfig) -> Result<Vec<Token<'_>>, LexError> {
+    let mut lexer = UclLexer::with_config(input, config);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token()?;
+        if token == Token::Eof {
+            return Ok(tokens);
+        }
+        tokens.push(token);
+    }
+}
+
+#[test]
+fn test_default_key_limit() {
+    assert_eq!(LexerConfig::default().max_key_length, 64 * 1024);
+
+    let long_key = "k".repeat(64 * 1024 + 1);
+    let err = UclLexer::new(&long_key).next_token().unwrap_err();
+    assert!(matches!(
+        err,
+        LexError::KeyTooLong { length, limit, .. } if length == long_key.len() && limit == 64 * 1024
+    ));
+}
+
+#[test]
+fn test_identifier_at_limit_is_accepted() {
+    let tokens = lex_all("abcdefgh = 1", config()).unwrap();
+    assert_eq!(tokens[0], Token::Key("abcdefgh".into()));
+}
+
+#[test]
+fn test_overlong_identifier_is_rejected() {
+    let err = lex_all("name = 1\nabcdefghi = 2", config()).unwrap_err();
+    match err {
+        LexError::KeyTooLong {
+            length,
+            limit,
+            position,
+        } => {
+            assert_eq!((length, limit), (9, 8));
+            assert_eq!((position.line, position.column), (2, 1));
+        }
+        other => panic!("expected key too long error, got {other:?}"),
+    }
+
+    // Unquoted values go through the same identifier path
+    assert!(matches!(
+        lex_all("name = abcdefghij", config()),
+        Err(LexError::KeyTooLong { .. })
+    ));
+}
+
+#[test]
+fn test_overlong_quoted_key_is_rejected() {
+    let mut parser = UclParser::with_lexer_config("ok = 1\n\"abcdefghi\" = 2\n", config());
+    let err = parser.parse_document().unwrap_err();
+    match err {
+        ParseError::Lex(LexError::KeyTooLong {
+            limit, position, ..
+        }) => {
+            assert_eq!(limit, 8);
+            assert_eq!((position.line, position.column), (2, 1));
+        }
+        other => panic!("expected key too long error, got {other:?}"),
+    }
+
+    // Quoted values are bounded by the string limit only
+    let mut parser = UclParser::with_lexer_config("ok = \"abcdefghijkl\"\n", config());
+    assert_eq!(parser.parse_document().unwrap()["ok"], "abcdefghijkl");
+}
+
+#[test]
+fn test_overlong_quoted_key_in_braced_object() {
+    let mut parser = UclParser::with_lexer_config("{ \"abcdefghi\": 1 }", config());
+    assert!(matches!(
+        parser.parse_document(),
+        Err(ParseError::Lex(LexError::KeyTooLong { .. }))
+    ));
+}
+
+#[test]
+fn test_overlong_key_error_is_kept_by_every_entry_point() {
+    for input in ["ok = 1\nabcdefghi = 2\n", "ok = 1\n\"abcdefghi\" = 2\n"] {
+        let errors = UclParser::with_lexer_config(input, config())
+            .validate()
+            .unwrap_err();
+        assert!(
+            matches!(
+                errors[..],
+                [UclError::Parse(ParseError::Lex(LexError::KeyTooLong {
+                    length: 9,
+                    limit: 8,
+                    ..
+                }))]
+            ),
+            "{input}: {errors:?}"
+        );
+    }
+
+    let input = format!("\"{}\" = 1\n", "k".repeat(64 * 1024 + 1));
+    let err = ucl_lexer::from_str::<serde_json::Value>(&input).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            UclError::Parse(ParseError::Lex(LexError::KeyTooLong { .. }))
+        ),
+        "{err:?}"
+    );
+    assert_eq!(err.code(), "lex/key-too-long");
+    assert!(matches!(
+        UclDocument::parse(&input),
+        Err(ParseError::Lex(LexError::KeyTooLong { .. }))
+    ));
+}
+
+#[test]
+fn test_heredoc_content_is_bounded() {
+    let fits = format!("<<EOD\n{}\nEOD\n", "x".repeat(30));
+    assert!(lex_all(&fits, config()).is_ok());
+
+    let too_long = format!("<<EOD\n{}\nEOD\n", "x".repeat(40));
+    match lex_all(&too_long, config()) {
+        Err(LexError::InvalidHeredoc { message, position }) => {
+            assert!(message.contains("maximum string length of 32"), "{message}");
+            assert_eq!(position.line, 1);
+        }
+        other => panic!("expected heredoc length error, got {other:?}"),
+    }
+
+    // An unterminated heredoc stops at the limit instead of reading the whole input
+    let unterminated = format!("<<EOD\n{}", "line\n".repeat(1000));
+    assert!(matches!(
+        lex_all(&unterminated, config()),
+        Err(LexError::InvalidHeredoc { ref message, .. }) if message.contains("maximum string length")
+    ));
+}
+
+#[test]
+fn test_heredoc_terminator_is_bounded() {
+    let input = format!("<<{}\ncontent\n", "T".repeat(10_000));
+    match lex_all(&input, LexerConfig::default()) {
+        Err(LexError::InvalidHeredoc { message, .. }) => {
+            assert!(
+                message.contains("is too long (max 64 characters)"),
+                "{message}"
+            );
+            assert!(message.len() < 200);
+        }
+        other => panic!("expected heredoc terminator error, got {other:?}"),
+    }
+}
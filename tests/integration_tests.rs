@@ -1536,8 +1536,7 @@ fn test_cpp_comments_preservation() {
         }
     "#;
 
-    let mut lexer_config = LexerConfig::default();
-    lexer_config.save_comments = true;
+    let lexer_config = LexerConfig::new().with_save_comments(true);
     let mut lexer = UclLexer::with_config(config, lexer_config);
 
     let mut cpp_comments = 0;
@@ -1547,10 +1546,10 @@ fn test_cpp_comments_preservation() {
             Ok(Token::Comment(_)) => {
                 // Check if this is a C++ style comment
                 let comments = lexer.comments();
-                if let Some(last_comment) = comments.last() {
-                    if last_comment.comment_type == CommentType::CppStyle {
-                        cpp_comments += 1;
-                    }
+                if let Some(last_comment) = comments.last()
+                    && last_comment.comment_type == CommentType::CppStyle
+                {
+                    cpp_comments += 1;
                 }
             }
             Ok(_) => {}
@@ -0,0 +1,203 @@
+//! JSON conformance tests over a subset of JSONTestSuite
+//!
+//! Each `tests/json_suite/y_*.json` is a document every JSON parser must
+//! accept, named as in https://github.com/nst/JSONTestSuite. It is read into a
+//! `serde_json::Value` through this crate and compared with `serde_json`'s own
+//! result, both with the default configuration and with
+//! `ParserConfig::json_strict`.
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use ucl_lexer::{ParseError, ParserConfig, UclDeserializer, UclError, UclParser, from_str};
+
+/// Files whose repeated keys UCL reads as an implicit array
+const DUPLICATE_KEYS: &[&str] = &[
+    "y_object_duplicated_key.json",
+    "y_object_duplicated_key_and_value.json",
+];
+
+/// Reads every file of the suite as `(name, text)`
+fn suite() -> Vec<(String, String)> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/json_suite");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn from_json_strict(input: &str) -> Result<Value, UclError> {
+    let parser = UclParser::new(input).with_config(ParserConfig::json_strict());
+    Value::deserialize(UclDeserializer::from_parser(parser))
+}
+
+/// Compares two values, with numbers compared by value, so `-0` equals `-0.0`
+fn same(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|((ka, a), (kb, b))| ka == kb && same(a, b))
+        }
+        _ => actual == expected,
+    }
+}
+
+#[test]
+fn test_suite_is_read_as_json() {
+    let files = suite();
+    assert!(files.len() > 90);
+    for (name, input) in files {
+        if DUPLICATE_KEYS.contains(&name.as_str()) {
+            continue;
+        }
+        let expected: Value = serde_json::from_str(&input).unwrap();
+        let actual: Value = from_str(&input).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(same(&actual, &expected), "{name}: {actual} != {expected}");
+    }
+}
+
+#[test]
+fn test_suite_in_json_strict_mode() {
+    for (name, input) in suite() {
+        let expected: Value = serde_json::from_str(&input).unwrap();
+        let actual = from_json_strict(&input).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(same(&actual, &expected), "{name}: {actual} != {expected}");
+    }
+}
+
+#[test]
+fn test_repeated_keys() {
+    let input = r#"{"a": {"x": 1}, "a": {"y": 2}, "b": 1, "b": 2}"#;
+    // UCL merges repeated sections and collects other repeated values
+    let ucl: Value = from_str(input).unwrap();
+    assert_eq!(
+        ucl,
+        serde_json::json!({ "a": { "x": 1, "y": 2 }, "b": [1, 2] })
+    );
+    // JSON keeps the last value
+    let json = from_json_strict(input).unwrap();
+    assert_eq!(json, serde_json::json!({ "a": { "y": 2 }, "b": 2 }));
+}
+
+#[test]
+fn test_reported_edge_cases() {
+    let cases = [
+        (r#"{"a":1e+2}"#, serde_json::json!({ "a": 100.0 })),
+        ("{\"a\":[\n]}\n", serde_json::json!({ "a": [] })),
+        ("{\"a\":[1,\n2\n]}\n", serde_json::json!({ "a": [1, 2] })),
+        (
+            r#"{"a": null, "b": {}, "c": {"d": {}}}"#,
+            serde_json::json!({ "a": null, "b": {}, "c": { "d": {} } }),
+        ),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(from_str::<Value>(input).unwrap(), expected, "{input:?}");
+        assert_eq!(from_json_strict(input).unwrap(), expected, "{input:?}");
+    }
+}
+
+#[test]
+fn test_surrogate_pairs() {
+    assert_eq!(
+        from_str::<Value>(r#"["\ud83d\ude00", "\uD834\uDd1e"]"#).unwrap(),
+        serde_json::json!(["\u{1F600}", "\u{1D11E}"])
+    );
+    // A surrogate that is not part of a pair is not a character
+    for input in [r#"["\ud83d"]"#, r#"["\ude00"]"#, r#"["\ud83dA"]"#] {
+        let error = from_str::<Value>(input).unwrap_err().to_string();
+        assert!(error.contains("Invalid unicode escape '\\u"), "{error}");
+        assert!(!error.contains("\\uu"), "{error}");
+    }
+}
+
+#[test]
+fn test_json_strict_rejects_ucl_extensions() {
+    let cases = [
+        ("{a: 1}", "an unquoted key or value", 2),
+        (r#"{"a" = 1}"#, "'='", 6),
+        (r#"{"a": 1;}"#, "';'", 8),
+        ("{\"a\": 1}\n# note", "a comment", 1),
+        ("/* note */ []", "a comment", 1),
+        (r#"{"a": 1,}"#, "a trailing ','", 8),
+        ("[1, 2,]", "a trailing ','", 6),
+        ("[1],", "a trailing ','", 4),
+        (r#""a": 1"#, "an object without braces", 1),
+        ("", "an empty document", 1),
+        ("[10s]", "a number with a suffix", 2),
+        ("[0x10, 1]", "a number outside JSON syntax", 2),
+        ("[+1]", "a number outside JSON syntax", 2),
+        ("[1_000]", "a number outside JSON syntax", 2),
+        ("[yes ]", "an unquoted key or value", 2),
+        ("[inf]", "a number outside JSON syntax", 2),
+        ("['a']", "a single-quoted string", 2),
+        ("[<<EOT\nx\nEOT\n]", "a heredoc", 2),
+        (r#"["\x41"]"#, "an escape sequence outside JSON", 2),
+        (r#"["\u{41}"]"#, "an escape sequence outside JSON", 2),
+        ("[\"a\tb\"]", "a tab that is not escaped", 2),
+        (r#"{1: 2}"#, "a key that is not a string", 2),
+        (r#"{"a" += 1}"#, "'+'", 6),
+        (r#"["a" + "b"]"#, "'+'", 6),
+    ];
+    for (input, expected, column) in cases {
+        let error = UclParser::new(input)
+            .with_config(ParserConfig::json_strict())
+            .parse_document()
+            .unwrap_err();
+        match error {
+            ParseError::NotJson {
+                construct,
+                position,
+            } => {
+                assert_eq!(construct, expected, "{input:?}");
+                assert_eq!(position.column, column, "{input:?}");
+            }
+            other => panic!("{input:?}: unexpected error {other}"),
+        }
+        // Each of them is UCL
+        assert!(UclParser::new(input).parse_document().is_ok(), "{input:?}");
+    }
+}
+
+#[test]
+fn test_json_strict_requires_colons_and_commas() {
+    let parse = |input| {
+        UclParser::new(input)
+            .with_config(ParserConfig::json_strict())
+            .parse_document()
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        parse(r#"{"a" {}}"#),
+        "Unexpected token at 1:7: expected ':'; found '{'"
+    );
+    assert!(parse(r#"{"a": 1 "b": 2}"#).contains("expected one of ',', '}'; found string"));
+    assert!(parse(r#"["a" "b"]"#).contains("expected one of ',', ']'; found string"));
+}
+
+#[test]
+fn test_json_strict_keeps_strings_as_written() {
+    let value = from_json_strict(r#"{"path": "${HOME}/$$", "del": "a\u007fb"}"#).unwrap();
+    assert_eq!(
+        value,
+        serde_json::json!({ "path": "${HOME}/$$", "del": "a\u{7f}b" })
+    );
+    // DEL and the C1 controls need no escape, in JSON or UCL
+    assert_eq!(
+        from_str::<Value>("[\"a\u{7f}\u{85}b\"]").unwrap(),
+        serde_json::json!(["a\u{7f}\u{85}b"])
+    );
+}
@@ -0,0 +1,159 @@
+//! Tests for reading kebab-case keys into snake_case fields with `ParserConfig::key_alias_style`
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{AliasStyle, ParserConfig, UclDeserializer, UclError, UclValue, from_value};
+
+fn config() -> ParserConfig {
+    ParserConfig::new().with_key_alias_style(Some(AliasStyle::KebabSnakeEquivalent))
+}
+
+fn from_str_aliased<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, UclError> {
+    T::deserialize(UclDeserializer::with_parser_config(input, config()))
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Server {
+    max_connections: u32,
+    #[serde(default)]
+    read_timeout: Option<String>,
+    tls: Option<Tls>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Tls {
+    cert_file: String,
+}
+
+#[test]
+fn test_kebab_keys_fill_snake_fields() {
+    let input = "max-connections = 10\nread_timeout = \"5s\"\ntls { cert-file = \"a.pem\" }\n";
+    let server: Server = from_str_aliased(input).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            max_connections: 10,
+            read_timeout: Some("5s".to_string()),
+            tls: Some(Tls {
+                cert_file: "a.pem".to_string()
+            }),
+        }
+    );
+
+    // Without the setting kebab-case keys are unknown fields
+    let err = ucl_lexer::from_str::<Server>("max-connections = 10").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: missing field `max_connections`"
+    );
+}
+
+#[test]
+fn test_rename_all_accepts_either_spelling() {
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Limits {
+        max_connections: u32,
+    }
+
+    let limits: Limits = from_str_aliased("max_connections = 4").unwrap();
+    assert_eq!(limits.max_connections, 4);
+    let limits: Limits = from_str_aliased("max-connections = 4").unwrap();
+    assert_eq!(limits.max_connections, 4);
+    let limits: Limits = ucl_lexer::from_str("max-connections = 4").unwrap();
+    assert_eq!(limits.max_connections, 4);
+}
+
+#[test]
+fn test_both_spellings_are_a_duplicate_key() {
+    let input = "tls { cert_file = \"a.pem\" }\nmax-connections = 10\nmax_connections = 20\n";
+    let err = from_str_aliased::<Server>(input).unwrap_err();
+    match &err {
+        UclError::Serde(SerdeError::DuplicateField {
+            first,
+            second,
+            positions,
+            ..
+        }) => {
+            assert_eq!(
+                (first.as_str(), second.as_str()),
+                ("max-connections", "max_connections")
+            );
+            let lines: Vec<_> = positions.iter().map(|position| position.line).collect();
+            assert_eq!(lines, [2, 3]);
+        }
+        other => panic!("unexpected error {other:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Serde error: Duplicate key: 'max-connections' at 2:1 and 'max_connections' at 3:1 \
+         name the same field"
+    );
+
+    let input = "max_connections = 1\ntls { cert_file = \"a\"\n  cert-file = \"b\" }\n";
+    let err = from_str_aliased::<Server>(input).unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("tls"));
+    assert!(
+        err.to_string()
+            .contains("'cert_file' at 2:7 and 'cert-file' at 3:3"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_unknown_fields_keep_their_spelling() {
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    #[allow(dead_code)]
+    struct Strict {
+        max_connections: u32,
+    }
+
+    let strict: Strict = from_str_aliased("max-connections = 1").unwrap();
+    assert_eq!(strict.max_connections, 1);
+
+    let err = from_str_aliased::<Strict>("max-connections = 1\nmin-connections = 0").unwrap_err();
+    assert!(
+        err.to_string().contains("unknown field `min-connections`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_serde_aliases_still_match() {
+    #[derive(Debug, Deserialize)]
+    struct Pool {
+        #[serde(alias = "max_conns")]
+        max_connections: u32,
+    }
+
+    let pool: Pool = from_str_aliased("max_conns = 3").unwrap();
+    assert_eq!(pool.max_connections, 3);
+    let pool: Pool = from_str_aliased("max-connections = 3").unwrap();
+    assert_eq!(pool.max_connections, 3);
+}
+
+#[test]
+fn test_maps_keep_their_keys() {
+    #[derive(Debug, Deserialize)]
+    struct Headers {
+        extra_headers: BTreeMap<String, String>,
+    }
+
+    let input = "extra-headers { x-request-id = a; x_request_id = b }";
+    let headers: Headers = from_str_aliased(input).unwrap();
+    assert_eq!(
+        headers.extra_headers.keys().collect::<Vec<_>>(),
+        ["x-request-id", "x_request_id"]
+    );
+
+    // Values read without a parser configuration match fields exactly
+    let value = UclValue::Object(
+        [("max-connections".into(), UclValue::Integer(2))]
+            .into_iter()
+            .collect(),
+    );
+    let err = from_value::<Server>(value).unwrap_err();
+    assert!(err.to_string().contains("max_connections"), "{err}");
+}
@@ -0,0 +1,132 @@
+//! Tests for `LexerConfig::key_charset`
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use ucl_lexer::deserializer::from_str_with_config;
+use ucl_lexer::{
+    DuplicateKeyBehavior, KeyCharset, LexerConfig, ParseError, ParserConfig, UclParser, UclValue,
+    ucl,
+};
+
+/// Two keys that look the same, the second starting with a Cyrillic `а`
+const CONFUSABLE: &str = "api_key = \"latin\"\n\u{430}pi_key = \"cyrillic\"\n";
+
+/// The same key written with a precomposed `é` and with `e` and a combining accent
+const DECOMPOSED: &str = "caf\u{e9} = 1\n\"cafe\u{301}\" = 2\n";
+
+fn parse(input: &str, charset: KeyCharset) -> Result<UclValue, ParseError> {
+    UclParser::with_lexer_config(input, LexerConfig::new().with_key_charset(charset))
+        .parse_document()
+}
+
+#[test]
+fn test_confusable_keys() {
+    // Unicode keys are kept as written, so the keys differ
+    for charset in [KeyCharset::Unicode, KeyCharset::UnicodeNfc] {
+        let value = parse(CONFUSABLE, charset).unwrap();
+        assert_eq!(
+            value,
+            ucl!({ "api_key": "latin", "\u{430}pi_key": "cyrillic" }),
+            "{charset:?}"
+        );
+    }
+
+    // ASCII keys reject the Cyrillic letter where the key starts
+    let err = parse(CONFUSABLE, KeyCharset::Ascii).unwrap_err();
+    match &err {
+        ParseError::NonAsciiKey {
+            key,
+            character,
+            position,
+        } => {
+            assert_eq!(key, "\u{430}pi_key");
+            assert_eq!(*character, '\u{430}');
+            assert_eq!((position.line, position.column), (2, 1));
+        }
+        other => panic!("expected a non-ASCII key error, got {other:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Key '\u{430}pi_key' at 2:1 contains the non-ASCII character U+0430 '\u{430}'; keys \
+         must be ASCII"
+    );
+    assert_eq!(err.code(), "parse/non-ascii-key");
+}
+
+#[test]
+fn test_ascii_keys_in_every_position() {
+    for input in [
+        "\"cl\u{e9}\" = 1",
+        "server { p\u{f6}rt = 80 }",
+        "section \"n\u{e4}me\" { a = 1 }",
+        "upstream b\u{e4}ckend { a = 1 }",
+    ] {
+        let err = parse(input, KeyCharset::Ascii).unwrap_err();
+        assert!(
+            matches!(err, ParseError::NonAsciiKey { .. }),
+            "{input:?}: {err:?}"
+        );
+    }
+
+    // Values are not keys
+    let value = parse(
+        "name = \"caf\u{e9}\"\nlist = [ \u{e9}t\u{e9} ]",
+        KeyCharset::Ascii,
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "name": "caf\u{e9}", "list": ["\u{e9}t\u{e9}"] })
+    );
+}
+
+#[test]
+fn test_nfc_keys_are_the_same_key() {
+    // Without normalization the two spellings are two keys
+    let value = parse(DECOMPOSED, KeyCharset::Unicode).unwrap();
+    assert_eq!(value.as_object().unwrap().len(), 2);
+
+    // Normalized, the second is a duplicate of the first
+    let value = parse(DECOMPOSED, KeyCharset::UnicodeNfc).unwrap();
+    assert_eq!(value, ucl!({ "caf\u{e9}": [1, 2] }));
+
+    let config = LexerConfig::new().with_key_charset(KeyCharset::UnicodeNfc);
+    let err = UclParser::with_lexer_config(DECOMPOSED, config)
+        .with_config(ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error))
+        .parse_document()
+        .unwrap_err();
+    assert!(
+        matches!(&err, ParseError::DuplicateKey { key, .. } if key == "caf\u{e9}"),
+        "{err:?}"
+    );
+
+    // Nested keys and the keys of sections are normalized too
+    let input = "\"re\u{301}sume\u{301}\" { \"\u{212B}\" = 1 }";
+    let value = parse(input, KeyCharset::UnicodeNfc).unwrap();
+    assert_eq!(value["r\u{e9}sum\u{e9}"]["\u{c5}"], 1);
+}
+
+#[test]
+fn test_deserializer_uses_normalized_keys() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Menu {
+        #[serde(rename = "caf\u{e9}")]
+        cafe: i64,
+    }
+
+    let input = "\"cafe\u{301}\" = 3";
+    let nfc = LexerConfig::new().with_key_charset(KeyCharset::UnicodeNfc);
+    let menu: Menu = from_str_with_config(input, nfc.clone()).unwrap();
+    assert_eq!(menu, Menu { cafe: 3 });
+
+    // As written, the field is missing
+    let err = from_str_with_config::<Menu>(input, LexerConfig::new()).unwrap_err();
+    assert!(err.to_string().contains("missing field"), "{err}");
+
+    let map: BTreeMap<String, Vec<i64>> = from_str_with_config(DECOMPOSED, nfc).unwrap();
+    assert_eq!(map, BTreeMap::from([("caf\u{e9}".to_string(), vec![1, 2])]));
+
+    let ascii = LexerConfig::new().with_key_charset(KeyCharset::Ascii);
+    let err = from_str_with_config::<BTreeMap<String, String>>(CONFUSABLE, ascii).unwrap_err();
+    assert_eq!(err.code(), "parse/non-ascii-key");
+}
@@ -0,0 +1,91 @@
+//! Tests for deserializing objects in source key order
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+use ucl_lexer::{
+    DuplicateKeyBehavior, ParserConfig, UclDeserializer, UclParser, UclValue, from_str,
+};
+
+/// Fifty key names in a fixed shuffled order
+fn shuffled_keys() -> Vec<String> {
+    // 17 is coprime with 50, so this visits every index once
+    (0..50).map(|i| format!("key_{:02}", i * 17 % 50)).collect()
+}
+
+fn document(keys: &[String]) -> String {
+    keys.iter()
+        .enumerate()
+        .map(|(value, key)| format!("{} = {};\n", key, value))
+        .collect()
+}
+
+#[test]
+fn test_index_map_follows_file_order() {
+    let keys = shuffled_keys();
+    let map: IndexMap<String, i64> = from_str(&document(&keys)).unwrap();
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        keys.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        map.values().copied().collect::<Vec<_>>(),
+        (0..50).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_duplicate_keys_keep_their_first_position() {
+    let keys = shuffled_keys();
+    let mut input = document(&keys);
+    input.push_str(&format!("{} = 100;\n", keys[7]));
+
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Override);
+    let map =
+        IndexMap::<String, i64>::deserialize(UclDeserializer::with_parser_config(&input, config))
+            .unwrap();
+    assert_eq!(
+        map.keys().collect::<Vec<_>>(),
+        keys.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(map[&keys[7]], 100);
+
+    // Implicit arrays stay where the key first appeared
+    let value = UclParser::new(&input).parse_document().unwrap();
+    let object = value.as_object().unwrap();
+    assert_eq!(
+        object.keys().collect::<Vec<_>>(),
+        keys.iter().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        object[keys[7].as_str()],
+        UclValue::Array(Box::new([7.into(), 100.into()].into_iter().collect()))
+    );
+}
+
+#[test]
+fn test_objects_as_entry_lists() {
+    #[derive(Deserialize)]
+    struct Upstreams {
+        servers: Vec<(String, u16)>,
+        weights: Vec<u32>,
+    }
+
+    let upstreams: Upstreams =
+        from_str("servers { zeta = 80, alpha = 8080, mid = 443 }\nweights { b = 2, a = 1 }")
+            .unwrap();
+    assert_eq!(
+        upstreams.servers,
+        [
+            ("zeta".to_string(), 80),
+            ("alpha".to_string(), 8080),
+            ("mid".to_string(), 443),
+        ]
+    );
+    // Other element types still receive only the values
+    assert_eq!(upstreams.weights, [2, 1]);
+
+    let keys = shuffled_keys();
+    let entries: Vec<(String, i64)> = from_str(&document(&keys)).unwrap();
+    let expected: Vec<(String, i64)> = keys.into_iter().zip(0..).collect();
+    assert_eq!(entries, expected);
+}
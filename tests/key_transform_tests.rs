@@ -0,0 +1,197 @@
+//! Tests for rewriting and rejecting keys with `KeyTransformHook`
+
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use ucl_lexer::{
+    ConfigValidationPlugin, DuplicateKeyBehavior, KeyPrefixStripper, KeyTransformHook, ParseError,
+    ParserConfig, Position, Span, UclParser, UclParserBuilder, UclValue, ucl,
+};
+
+/// Rejects keys containing whitespace
+struct DenyWhitespace;
+
+impl KeyTransformHook for DenyWhitespace {
+    fn transform_key<'k>(
+        &self,
+        key: &'k str,
+        _path: &[&str],
+        span: Span,
+    ) -> Result<Cow<'k, str>, ParseError> {
+        if key.contains(char::is_whitespace) {
+            return Err(ParseError::InvalidObject {
+                message: format!("key '{}' contains whitespace", key),
+                position: span.start,
+            });
+        }
+        Ok(Cow::Borrowed(key))
+    }
+}
+
+/// Records every key it sees with its path and span offsets
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl KeyTransformHook for Recorder {
+    fn transform_key<'k>(
+        &self,
+        key: &'k str,
+        path: &[&str],
+        span: Span,
+    ) -> Result<Cow<'k, str>, ParseError> {
+        self.0.lock().unwrap().push(format!(
+            "{key} in '{}' at {}..{}",
+            path.join("."),
+            span.start.offset,
+            span.end.offset
+        ));
+        Ok(Cow::Borrowed(key))
+    }
+
+    fn priority(&self) -> u32 {
+        // Sees keys before the prefix is stripped
+        200
+    }
+}
+
+fn parse_with(
+    input: &str,
+    config: ParserConfig,
+    hooks: Vec<Box<dyn KeyTransformHook>>,
+) -> Result<UclValue, ParseError> {
+    let mut parser = UclParser::new(input).with_config(config);
+    for hook in hooks {
+        parser.add_key_transform(hook);
+    }
+    parser.parse_document()
+}
+
+fn at(line: usize, column: usize, offset: usize) -> Position {
+    Position {
+        line,
+        column,
+        offset,
+    }
+}
+
+#[test]
+fn test_stripped_prefix_collides_with_existing_key() {
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+    let input = "port = 80\nserver {\n    x-port = 8080\n}\nx-port = 81\n";
+    let err = parse_with(input, config, vec![Box::new(KeyPrefixStripper::new("x-"))]).unwrap_err();
+    match err {
+        ParseError::DuplicateKey {
+            key,
+            first_occurrence,
+            second_occurrence,
+        } => {
+            assert_eq!(key, "port");
+            assert_eq!(first_occurrence, at(1, 1, 0));
+            assert_eq!(second_occurrence, at(5, 1, 39));
+        }
+        other => panic!("expected a duplicate key, got {other:?}"),
+    }
+
+    // Without the error behavior the renamed keys merge as written ones would
+    let value = parse_with(
+        input,
+        ParserConfig::new(),
+        vec![Box::new(KeyPrefixStripper::new("x-"))],
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "port": [80, 81], "server": { "port": 8080 } })
+    );
+}
+
+#[test]
+fn test_rejected_keys_report_the_hook_error() {
+    let hooks = || -> Vec<Box<dyn KeyTransformHook>> { vec![Box::new(DenyWhitespace)] };
+    let err = parse_with(
+        "name = web\n\"log level\" = debug\n",
+        ParserConfig::new(),
+        hooks(),
+    )
+    .unwrap_err();
+    match err {
+        ParseError::InvalidObject { message, position } => {
+            assert_eq!(message, "key 'log level' contains whitespace");
+            assert_eq!(position, at(2, 1, 11));
+        }
+        other => panic!("expected the hook's error, got {other:?}"),
+    }
+
+    // Nested keys and keys of named sections go through the hook too
+    for input in [
+        "server { \"max conn\" = 5 }",
+        "section \"web site\" { a = 1 }",
+    ] {
+        let err = parse_with(input, ParserConfig::new(), hooks()).unwrap_err();
+        assert!(
+            matches!(&err, ParseError::InvalidObject { message, .. } if message.contains("whitespace")),
+            "{input}: {err:?}"
+        );
+    }
+    assert!(parse_with("log_level = debug", ParserConfig::new(), hooks()).is_ok());
+}
+
+#[test]
+fn test_dotted_keys_are_transformed_per_segment() {
+    let recorder = Recorder::default();
+    let config = ParserConfig::new().with_expand_dotted_keys(true);
+    let value = parse_with(
+        "x-server.x-tls.port = 443\nx-server.x-name = api\n",
+        config,
+        vec![
+            Box::new(KeyPrefixStripper::new("x-")),
+            Box::new(recorder.clone()),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "server": { "tls": { "port": 443 }, "name": "api" } })
+    );
+
+    // Each segment has its own span, and its path holds the transformed keys before it
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        [
+            "x-server in '' at 0..8",
+            "x-tls in 'server' at 9..14",
+            "port in 'server.tls' at 15..19",
+            "x-server in '' at 26..34",
+            "x-name in 'server' at 35..41",
+        ]
+    );
+}
+
+#[test]
+fn test_builder_and_plugin_transforms() {
+    let parser = UclParserBuilder::new("x-name = web\n\"bad key\" = 1\n")
+        .with_key_transform(Box::new(KeyPrefixStripper::new("x-")))
+        .with_key_transform(Box::new(DenyWhitespace))
+        .build_shared()
+        .unwrap();
+    assert_eq!(
+        parser.parse("x-name = web").unwrap(),
+        ucl!({ "name": "web" })
+    );
+    assert!(matches!(
+        parser.parse("\"bad key\" = 1"),
+        Err(ParseError::InvalidObject { .. })
+    ));
+
+    // Allowed keys are checked after the legacy prefix is stripped
+    let plugin = ConfigValidationPlugin::new()
+        .allow_keys(vec!["name".to_string(), "port".to_string()])
+        .strip_key_prefix("legacy_".to_string());
+    let value = UclParserBuilder::new("legacy_name = web\nport = 80\n")
+        .with_plugin(Box::new(plugin))
+        .unwrap()
+        .build()
+        .unwrap()
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, ucl!({ "name": "web", "port": 80 }));
+}
@@ -0,0 +1,43 @@
+//! Tests for keys spelled like keywords or numbers, such as `true`, `infinity` or `404`
+
+use ucl_lexer::{ParserConfig, UclParser, UclValue, from_str, ucl};
+
+fn parse(input: &str) -> UclValue {
+    UclParser::new(input).parse_document().unwrap()
+}
+
+#[test]
+fn test_keys_keep_their_text() {
+    let value = parse("true = 1\nnull = 2\n404 = 3\ninfinity = 4\n1.50 = 5\n0x1F = 6\n");
+    assert_eq!(
+        value,
+        ucl!({ "true": 1, "null": 2, "404": 3, "infinity": 4, "1.50": 5, "0x1F": 6 })
+    );
+
+    let value = parse("codes { 404 = \"missing\"; 500 = \"failed\" }\nlimits { inf = 0, nan = 1 }");
+    assert_eq!(value["codes"]["404"], "missing");
+    assert_eq!(value["codes"]["500"], "failed");
+    assert_eq!(value["limits"], ucl!({ "inf": 0, "nan": 1 }));
+}
+
+#[test]
+fn test_keyword_keys_as_fields() {
+    #[derive(serde::Deserialize)]
+    struct Special {
+        infinity: f64,
+        null: Option<u8>,
+    }
+
+    let special: Special = from_str("infinity = inf\nnull = null").unwrap();
+    assert_eq!(special.infinity, f64::INFINITY);
+    assert_eq!(special.null, None);
+}
+
+#[test]
+fn test_strict_mode_keeps_keyword_keys() {
+    let value = UclParser::new("true = 1\n404: 2")
+        .with_config(ParserConfig::strict())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, ucl!({ "true": 1, "404": 2 }));
+}
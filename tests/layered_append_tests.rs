@@ -0,0 +1,139 @@
+//! Tests for `+=` across documents merged as layers
+
+use ucl_lexer::{ParserConfig, TrackedValue, UclParser, ucl};
+
+fn parse_tracked(source_name: &str, input: &str) -> TrackedValue {
+    UclParser::new(input)
+        .with_config(ParserConfig::new().with_track_provenance(true))
+        .with_source_name(source_name)
+        .parse_tracked()
+        .unwrap()
+}
+
+/// Merges the layers in the given order
+fn merged(layers: &[(&str, &str)]) -> TrackedValue {
+    let mut layers = layers
+        .iter()
+        .map(|(name, input)| parse_tracked(name, input));
+    let mut merged = layers.next().unwrap();
+    for layer in layers {
+        merged.merge_from(layer);
+    }
+    merged
+}
+
+const BASE: (&str, &str) = ("base.ucl", "modules = [\"core\"]\n");
+
+#[test]
+fn test_append_is_recorded_at_parse_time() {
+    let tracked = parse_tracked("x", "a += [1]\nb = [1]\nc = [1]\nc += [2]\nd { e += 1 }");
+    assert!(tracked.provenance.is_append("a"));
+    assert!(!tracked.provenance.is_append("b"));
+    assert!(!tracked.provenance.is_append("c"));
+    assert!(tracked.provenance.is_append("d.e"));
+    assert!(!tracked.provenance.is_append("d"));
+
+    let tracked = parse_tracked("x", "section x { y += [2] }\nsection x z += [3]");
+    assert!(tracked.provenance.is_append("section.x.y"));
+    assert!(tracked.provenance.is_append("section.x.z"));
+}
+
+#[test]
+fn test_two_layers_append_in_either_order() {
+    let extra = ("extra.ucl", "modules += [\"extra\"]\n");
+    for layers in [[BASE, extra], [extra, BASE]] {
+        let merged = merged(&layers);
+        assert_eq!(
+            merged.value,
+            ucl!({ "modules": ["core", "extra"] }),
+            "{layers:?}"
+        );
+        assert!(!merged.provenance.is_append("modules"));
+    }
+}
+
+#[test]
+fn test_two_layers_append_with_priorities() {
+    let high = ("extra.ucl", ".priority 5\nmodules += [\"extra\"]\n");
+    let low = ("base.ucl", ".priority 1\nmodules = [\"core\"]\n");
+    for layers in [[BASE, high], [high, BASE], [low, high], [high, low]] {
+        assert_eq!(
+            merged(&layers).value,
+            ucl!({ "modules": ["core", "extra"] }),
+            "{layers:?}"
+        );
+    }
+}
+
+#[test]
+fn test_plain_assignment_replaces() {
+    let replace = ("local.ucl", "modules = [\"local\"]\n");
+    assert_eq!(
+        merged(&[BASE, replace]).value,
+        ucl!({ "modules": ["local"] })
+    );
+
+    // With priorities, the higher one replaces whichever is merged first
+    let high = ("local.ucl", ".priority 5\nmodules = [\"local\"]\n");
+    assert_eq!(merged(&[high, BASE]).value, ucl!({ "modules": ["local"] }));
+    assert_eq!(merged(&[BASE, high]).value, ucl!({ "modules": ["local"] }));
+}
+
+#[test]
+fn test_three_layers() {
+    let site = ("site.ucl", ".priority 1\nmodules += [\"site\"]\n");
+    let host = ("host.ucl", ".priority 2\nmodules += [\"host\"]\n");
+    for layers in [[BASE, site, host], [host, site, BASE]] {
+        assert_eq!(
+            merged(&layers).value,
+            ucl!({ "modules": ["core", "site", "host"] }),
+            "{layers:?}"
+        );
+    }
+
+    // A plain assignment in the middle layer discards the base
+    let site = ("site.ucl", ".priority 1\nmodules = [\"site\"]\n");
+    for layers in [[BASE, site, host], [host, site, BASE]] {
+        assert_eq!(
+            merged(&layers).value,
+            ucl!({ "modules": ["site", "host"] }),
+            "{layers:?}"
+        );
+    }
+
+    // Without priorities, layers stack in merge order
+    let site = ("site.ucl", "modules += [\"site\"]\n");
+    let host = ("host.ucl", "modules = [\"host\"]\n");
+    assert_eq!(
+        merged(&[BASE, site, host]).value,
+        ucl!({ "modules": ["host"] })
+    );
+    assert_eq!(
+        merged(&[BASE, host, site]).value,
+        ucl!({ "modules": ["host", "site"] })
+    );
+}
+
+#[test]
+fn test_nested_and_scalar_appends() {
+    let base = ("base.ucl", "server { modules = [\"core\"]; port = 80 }\n");
+    let extra = (
+        "extra.ucl",
+        "server { modules += \"extra\"; port += 8080; workers += 4 }\n",
+    );
+    for layers in [[base, extra], [extra, base]] {
+        let merged = merged(&layers);
+        assert_eq!(
+            merged.value,
+            ucl!({
+                "server": { "modules": ["core", "extra"], "port": 8080, "workers": 4 }
+            }),
+            "{layers:?}"
+        );
+        // An append that cannot combine replaces, as the layer above
+        assert_eq!(
+            merged.origin("server.port").unwrap().source_name,
+            "extra.ucl"
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Tests for converting between strings and primitives in lenient deserialization
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{ParserConfig, UclDeserializer, UclError, from_str};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Settings {
+    port: u16,
+    debug: bool,
+    ratio: f32,
+    cache: u64,
+    timeout: f64,
+    offset: i8,
+    name: String,
+}
+
+fn lenient<'de, T: Deserialize<'de>>(input: &'de str) -> Result<T, UclError> {
+    T::deserialize(UclDeserializer::new(input).lenient())
+}
+
+#[test]
+fn test_quoted_primitives() {
+    let input = r#"
+        port = "8080"
+        debug = "yes"
+        ratio = "0.25"
+        cache = "1gb"
+        timeout = "30s"
+        offset = " -5 "
+        name = "app"
+    "#;
+    let settings: Settings = lenient(input).unwrap();
+    assert_eq!(
+        settings,
+        Settings {
+            port: 8080,
+            debug: true,
+            ratio: 0.25,
+            cache: 1 << 30,
+            timeout: 30.0,
+            offset: -5,
+            name: "app".to_string(),
+        }
+    );
+
+    // The same document is rejected without lenient coercion
+    assert!(from_str::<Settings>(input).is_err());
+}
+
+#[test]
+fn test_booleans_accept_parser_keywords() {
+    #[derive(Deserialize)]
+    struct Flags {
+        flags: Vec<bool>,
+    }
+
+    let flags: Flags =
+        lenient(r#"flags = ["yes", "NO", "on", "Off", "true", "false", true]"#).unwrap();
+    assert_eq!(flags.flags, [true, false, true, false, true, false, true]);
+    assert!(lenient::<Flags>(r#"flags = ["1"]"#).is_err());
+}
+
+#[test]
+fn test_numbers_become_strings() {
+    #[derive(Deserialize)]
+    struct Labels {
+        version: String,
+        ratio: String,
+        timeout: String,
+        names: Vec<String>,
+    }
+
+    let labels: Labels =
+        lenient("version = 3\nratio = 0.5\ntimeout = 90s\nnames = [ 1, two, 3 ]").unwrap();
+    assert_eq!(labels.version, "3");
+    assert_eq!(labels.ratio, "0.5");
+    assert_eq!(labels.timeout, "90");
+    assert_eq!(labels.names, ["1", "two", "3"]);
+}
+
+#[test]
+fn test_optional_and_map_values() {
+    #[derive(Deserialize)]
+    struct Limits {
+        max: Option<u32>,
+        per_user: HashMap<String, u16>,
+    }
+
+    let limits: Limits = lenient(r#"max = "10k", per_user { alice = "5", bob = 7 }"#).unwrap();
+    assert_eq!(limits.max, Some(10_000));
+    assert_eq!(limits.per_user["alice"], 5);
+    assert_eq!(limits.per_user["bob"], 7);
+}
+
+#[test]
+fn test_invalid_strings_name_value_and_target() {
+    #[derive(Debug, Deserialize)]
+    struct Server {
+        #[allow(dead_code)]
+        port: u16,
+    }
+
+    let err = lenient::<HashMap<String, Server>>("web {\n  port = \"abc\"\n}").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: Cannot convert \"abc\" to u16 for 'web.port' at line 2"
+    );
+    match err {
+        UclError::Serde(SerdeError::InvalidCoercion {
+            value,
+            target,
+            path,
+            position,
+        }) => {
+            assert_eq!(value, "abc");
+            assert_eq!(target, "u16");
+            assert_eq!(path, ["web", "port"]);
+            assert_eq!(position.map(|p| (p.line, p.column)), Some((2, 3)));
+        }
+        other => panic!("unexpected error: {other}"),
+    }
+
+    // Values out of range for the target are reported the same way
+    let err = lenient::<Server>("port = \"70000\"").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Cannot convert \"70000\" to u16 for 'port' at line 1"),
+        "{err}"
+    );
+    let err = lenient::<Settings>("debug = \"maybe\"").unwrap_err();
+    assert!(err.to_string().contains("\"maybe\" to bool"), "{err}");
+}
+
+#[test]
+fn test_parser_config_enables_coercion() {
+    #[derive(Deserialize)]
+    struct Port {
+        port: u16,
+    }
+
+    let config = ParserConfig::new().with_lenient_coercion(true);
+    let port = Port::deserialize(UclDeserializer::with_parser_config(
+        "port = \"443\"",
+        config,
+    ))
+    .unwrap();
+    assert_eq!(port.port, 443);
+}
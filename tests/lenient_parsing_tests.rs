@@ -0,0 +1,93 @@
+//! Tests for lenient parsing, which reports every error in a document
+//!
+//! Broken entries are replaced by `UclValue::Null` and parsing resumes at the
+//! next key on the same nesting level.
+
+use ucl_lexer::{
+    DuplicateKeyBehavior, ParseError, ParserConfig, UclError, UclParser, UclValue, ucl,
+};
+
+const THREE_ERRORS: &str = r#"
+name = "app"
+port = [1, 2
+workers = 4
+retries = ;
+timeout = 30s
+server {
+    host = "localhost"
+    weight = @3
+    ssl = true
+}
+debug = false
+"#;
+
+/// Returns the line of a parse error
+fn error_line(error: &UclError) -> usize {
+    match error {
+        UclError::Parse(
+            ParseError::UnexpectedToken { position, .. }
+            | ParseError::UnexpectedCharacter { position, .. },
+        ) => position.line,
+        other => panic!("unexpected error: {other}"),
+    }
+}
+
+#[test]
+fn test_three_independent_errors() {
+    let (value, errors) = UclParser::parse_lenient(THREE_ERRORS);
+
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    let lines: Vec<usize> = errors.iter().map(error_line).collect();
+    assert_eq!(lines, vec![4, 5, 9]);
+
+    let value = value.expect("partial value");
+    assert_eq!(value["name"], "app");
+    assert!(value["port"].is_null());
+    assert_eq!(value["workers"], 4);
+    assert!(value["retries"].is_null());
+    assert_eq!(value["timeout"], UclValue::Time(30.0));
+    assert_eq!(value["server"]["host"], "localhost");
+    assert!(value["server"]["weight"].is_null());
+    assert_eq!(value["server"]["ssl"], true);
+    assert_eq!(value["debug"], false);
+}
+
+#[test]
+fn test_strict_parse_stops_at_first_error() {
+    let mut parser = UclParser::new(THREE_ERRORS);
+    assert!(parser.parse_document().is_err());
+}
+
+#[test]
+fn test_valid_document_has_no_errors() {
+    let (value, errors) = UclParser::parse_lenient("a = 1\nb { c = [1, 2] }\n");
+
+    assert!(errors.is_empty());
+    assert_eq!(value, Some(ucl!({ "a": 1, "b": { "c": [1, 2] } })));
+}
+
+#[test]
+fn test_braced_document_recovers() {
+    let (value, errors) = UclParser::parse_lenient("{ a = ; b = 2, c = [1 2]; d = 4 }");
+
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    let value = value.expect("partial value");
+    assert!(value["a"].is_null());
+    assert_eq!(value["b"], 2);
+    assert!(value["c"].is_null());
+    assert_eq!(value["d"], 4);
+}
+
+#[test]
+fn test_duplicate_keys_are_collected() {
+    let config = ParserConfig::default().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+    let mut parser = UclParser::new("a = 1\na = 2\nb = 3\nb = 4\n").with_config(config);
+    let (value, errors) = parser.parse_document_lenient();
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        errors[0],
+        UclError::Parse(ParseError::DuplicateKey { ref key, .. }) if key == "a"
+    ));
+    assert!(value.is_some());
+}
@@ -1,9 +1,11 @@
+//! Tests based on real libucl test cases from vstakhov/libucl repository
+//! Source: https://github.com/vstakhov/libucl/tree/master/tests/basic
+
+#![allow(clippy::approx_constant)]
+
 use std::collections::HashMap;
 use ucl_lexer::{UclParser, UclValue};
 
-/// Tests based on real libucl test cases from vstakhov/libucl repository
-/// Source: https://github.com/vstakhov/libucl/tree/master/tests/basic
-
 /// Helper function to parse UCL string directly to UclValue
 fn parse_ucl(input: &str) -> Result<UclValue, Box<dyn std::error::Error>> {
     let mut parser = UclParser::new(input);
@@ -57,9 +59,9 @@ fn test_libucl_basic_1_value_types() {
     assert_eq!(arr[2].as_str().unwrap(), "value;");
     assert_eq!(arr[3].as_float().unwrap(), 1.0);
     assert_eq!(arr[4].as_integer().unwrap(), -3735928559); // -0xdeadbeef
-    assert_eq!(arr[5].as_bool().unwrap(), true);
-    assert_eq!(arr[6].as_bool().unwrap(), false); // no
-    assert_eq!(arr[7].as_bool().unwrap(), true); // yes
+    assert!(arr[5].as_bool().unwrap());
+    assert!(!arr[6].as_bool().unwrap()); // no
+    assert!(arr[7].as_bool().unwrap()); // yes
 }
 
 #[test]
@@ -323,9 +325,9 @@ fn test_libucl_basic_4_heredoc_strings() {
     input.push_str("EOD   \n"); // 3 trailing spaces - not a valid terminator
     input.push_str("EOF\n"); // Different terminator name
     input.push_str("# This should be in content\n");
-    input.push_str("\n");
+    input.push('\n');
     input.push_str("EOD\n"); // Valid terminator
-    input.push_str("\n");
+    input.push('\n');
     input.push_str("normal-key : \"value\"\n");
 
     let result = parse_ucl(&input);
@@ -395,21 +397,19 @@ directories : {
     );
 
     let directories = obj.get("directories").unwrap().as_object().unwrap();
-    assert_eq!(
+    assert!(
         directories
             .get("/usr/local/share/licenses/")
             .unwrap()
             .as_bool()
-            .unwrap(),
-        true
+            .unwrap()
     );
-    assert_eq!(
-        directories
+    assert!(
+        !directories
             .get("/usr/local/share/licenses/pkgconf-0.9.3/")
             .unwrap()
             .as_bool()
-            .unwrap(),
-        false
+            .unwrap()
     );
 }
 
@@ -516,6 +516,6 @@ param3 = ["value1", value2, 100500, true, 3.14]
     assert_eq!(arr[0].as_str().unwrap(), "value1");
     assert_eq!(arr[1].as_str().unwrap(), "value2");
     assert_eq!(arr[2].as_integer().unwrap(), 100500);
-    assert_eq!(arr[3].as_bool().unwrap(), true);
+    assert!(arr[3].as_bool().unwrap());
     assert_eq!(arr[4].as_float().unwrap(), 3.14);
 }
@@ -0,0 +1,330 @@
+//! Differential tests against the C libucl library
+//!
+//! Every corpus file is parsed by libucl and by this crate, both results are
+//! turned into a canonical JSON value, and the two are compared path by path.
+//! A path that differs must be listed in `KNOWN_DIFFERENCES` with the README
+//! section under "Differences from libucl" that explains it, and a listed
+//! path that no longer differs fails the test too, so the list shrinks as
+//! parity lands.
+//!
+//! Needs the system libucl and runs only with the `libucl-ffi-tests` feature:
+//!
+//! ```text
+//! cargo test --features libucl-ffi-tests --test libucl_differential_tests
+//! ```
+#![cfg(feature = "libucl-ffi-tests")]
+
+use serde_json::{Map, Number, Value};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use ucl_lexer::{
+    KeyPath, LexerConfig, MapVariableHandler, ParserConfig, PathSegment, UclParserBuilder, UclValue,
+};
+
+/// Directories whose `.ucl` and `.conf` files are compared
+const CORPUS_DIRS: &[&str] = &["tests/corpus", "tests/golden"];
+
+/// Variables the corpus files refer to, as in `corpus_tests.rs`
+const VARIABLES: &[(&str, &str)] = &[
+    ("ABI", "FreeBSD:14:amd64"),
+    ("VERSION_MINOR", "1"),
+    ("CONFDIR", "/usr/local/etc/rspamd"),
+    ("LOCAL_CONFDIR", "/usr/local/etc/rspamd"),
+    ("DBDIR", "/var/db/rspamd"),
+    ("PLUGINSDIR", "/usr/local/share/rspamd/plugins"),
+    ("WWWDIR", "/usr/local/share/rspamd/www"),
+];
+
+/// Intended differences as (file, path, README section)
+///
+/// The path is written as `KeyPath` displays it, the empty path standing for
+/// the whole document when one side fails to parse. The section is the
+/// anchor of a heading under "Differences from libucl" in the README.
+const KNOWN_DIFFERENCES: &[(&str, &str, &str)] = &[
+    (
+        "nginx_durations.ucl",
+        "keepalive_timeout",
+        "suffixed-values-without-a-separator",
+    ),
+    (
+        "nginx_durations.ucl",
+        "client_body_timeout",
+        "suffixed-values-without-a-separator",
+    ),
+    (
+        "nginx_durations.ucl",
+        "server.proxy_read_timeout",
+        "suffixed-values-without-a-separator",
+    ),
+    (
+        "nginx_durations.ucl",
+        "server.proxy_connect_timeout",
+        "suffixed-values-without-a-separator",
+    ),
+    ("rspamd_include.ucl", "", "include-macros"),
+];
+
+/// Bindings to the parts of libucl the comparison needs
+mod libucl {
+    use std::ffi::{CStr, CString, c_char, c_int, c_uchar, c_void};
+
+    #[repr(C)]
+    struct Parser {
+        _private: [u8; 0],
+    }
+
+    #[repr(C)]
+    struct Object {
+        _private: [u8; 0],
+    }
+
+    /// `UCL_PARSER_DEFAULT` from `enum ucl_parser_flags`
+    const PARSER_DEFAULT: c_int = 0;
+
+    /// `UCL_EMIT_JSON_COMPACT` from `enum ucl_emitter`
+    const EMIT_JSON_COMPACT: c_int = 1;
+
+    #[link(name = "ucl")]
+    unsafe extern "C" {
+        fn ucl_parser_new(flags: c_int) -> *mut Parser;
+        fn ucl_parser_register_variable(
+            parser: *mut Parser,
+            var: *const c_char,
+            value: *const c_char,
+        );
+        fn ucl_parser_add_chunk(parser: *mut Parser, data: *const c_uchar, len: usize) -> bool;
+        fn ucl_parser_get_error(parser: *mut Parser) -> *const c_char;
+        fn ucl_parser_get_object(parser: *mut Parser) -> *mut Object;
+        fn ucl_parser_free(parser: *mut Parser);
+        fn ucl_object_emit(object: *const Object, emit_type: c_int) -> *mut c_uchar;
+        fn ucl_object_unref(object: *mut Object);
+    }
+
+    unsafe extern "C" {
+        fn free(ptr: *mut c_void);
+    }
+
+    /// Parses `input` with libucl and returns the document as compact JSON
+    pub fn parse_to_json(input: &str, variables: &[(&str, &str)]) -> Result<String, String> {
+        // SAFETY: the parser is freed on every path, the object is released
+        // after it is emitted, and the emitted buffer is freed after it is
+        // copied. Strings passed in outlive the calls that read them.
+        unsafe {
+            let parser = ucl_parser_new(PARSER_DEFAULT);
+            if parser.is_null() {
+                return Err("ucl_parser_new failed".to_string());
+            }
+            for (name, value) in variables {
+                let name = CString::new(*name).unwrap();
+                let value = CString::new(*value).unwrap();
+                ucl_parser_register_variable(parser, name.as_ptr(), value.as_ptr());
+            }
+
+            let result = if ucl_parser_add_chunk(parser, input.as_ptr(), input.len()) {
+                let object = ucl_parser_get_object(parser);
+                let json = ucl_object_emit(object, EMIT_JSON_COMPACT);
+                let text = CStr::from_ptr(json.cast()).to_string_lossy().into_owned();
+                free(json.cast());
+                ucl_object_unref(object);
+                Ok(text)
+            } else {
+                let error = ucl_parser_get_error(parser);
+                Err(if error.is_null() {
+                    "unknown libucl error".to_string()
+                } else {
+                    CStr::from_ptr(error).to_string_lossy().into_owned()
+                })
+            };
+            ucl_parser_free(parser);
+            result
+        }
+    }
+}
+
+/// Returns the files of the corpus, sorted by name
+fn corpus() -> Vec<PathBuf> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut files: Vec<PathBuf> = CORPUS_DIRS
+        .iter()
+        .flat_map(|dir| fs::read_dir(root.join(dir)).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "ucl" || ext == "conf")
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Parses `input` with this crate and returns the canonical form
+fn parse_with_crate(input: &str) -> Result<Value, String> {
+    let mut handler = MapVariableHandler::new();
+    for (name, value) in VARIABLES {
+        handler.insert(name.to_string(), value.to_string());
+    }
+    let value = UclParserBuilder::new(input)
+        .with_lexer_config(LexerConfig::libucl_compat())
+        .with_parser_config(ParserConfig::libucl_compat())
+        .with_variable_handler(Box::new(handler))
+        .build()
+        .map_err(|e| e.to_string())?
+        .parse_document()
+        .map_err(|e| e.to_string())?;
+    Ok(canonical(&value))
+}
+
+/// Converts a value into the form both sides are compared in
+///
+/// Keys are sorted, durations are seconds, and every number is a float, as
+/// libucl's JSON does not say whether `1.0` was written as an integer.
+fn canonical(value: &UclValue) -> Value {
+    match value {
+        UclValue::String(s) => Value::String(s.clone()),
+        UclValue::Integer(i) => number(*i as f64),
+        UclValue::Float(f) | UclValue::Time(f) => number(*f),
+        UclValue::Decimal(text) => number(text.parse().unwrap()),
+        UclValue::Boolean(b) => Value::Bool(*b),
+        UclValue::Null => Value::Null,
+        UclValue::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| (key.as_str().to_string(), canonical(value)))
+                .collect(),
+        ),
+        UclValue::Array(array) => Value::Array(array.iter().map(canonical).collect()),
+    }
+}
+
+/// Applies the canonical form to JSON written by libucl
+fn canonical_json(value: Value) -> Value {
+    match value {
+        Value::Number(n) => number(n.as_f64().unwrap()),
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, canonical_json(value)))
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(canonical_json).collect()),
+        other => other,
+    }
+}
+
+fn number(n: f64) -> Value {
+    Number::from_f64(n).map_or_else(|| Value::String(n.to_string()), Value::Number)
+}
+
+/// Collects the paths at which `ours` and `theirs` differ
+fn diff(path: &KeyPath, ours: &Value, theirs: &Value, out: &mut Vec<(String, String)>) {
+    match (ours, theirs) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let child = path.join(PathSegment::Key(key.clone()));
+                match (a.get(key), b.get(key)) {
+                    (Some(a), Some(b)) => diff(&child, a, b, out),
+                    (a, b) => out.push((child.to_string(), describe(a, b))),
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            for (index, (a, b)) in a.iter().zip(b).enumerate() {
+                diff(&path.join(PathSegment::Index(index)), a, b, out);
+            }
+        }
+        (a, b) if a != b => out.push((path.to_string(), describe(Some(a), Some(b)))),
+        _ => {}
+    }
+}
+
+fn describe(ours: Option<&Value>, theirs: Option<&Value>) -> String {
+    let show = |value: Option<&Value>| value.map_or("nothing".to_string(), Value::to_string);
+    format!("ours {}, libucl {}", show(ours), show(theirs))
+}
+
+/// Returns the anchors of the headings under "Differences from libucl"
+fn documented_differences() -> BTreeSet<String> {
+    let readme =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("README.md")).unwrap();
+    let section = readme
+        .split("\n## Differences from libucl\n")
+        .nth(1)
+        .expect("README has a section on differences from libucl");
+    let section = section.split("\n## ").next().unwrap();
+    section
+        .lines()
+        .filter_map(|line| line.strip_prefix("### "))
+        .map(|heading| {
+            heading
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-')
+                .map(|c| if c == ' ' { '-' } else { c })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_known_differences_are_documented() {
+    let documented = documented_differences();
+    for (file, path, section) in KNOWN_DIFFERENCES {
+        assert!(
+            documented.contains(*section),
+            "{file} {path:?}: no README section '{section}' in {documented:?}"
+        );
+    }
+}
+
+#[test]
+fn test_corpus_matches_libucl() {
+    let files = corpus();
+    assert!(!files.is_empty());
+
+    let mut unexpected = Vec::new();
+    let mut stale = Vec::new();
+    for file in files {
+        let name = file.file_name().unwrap().to_str().unwrap();
+        let input = fs::read_to_string(&file).unwrap();
+
+        let theirs = libucl::parse_to_json(&input, VARIABLES)
+            .map(|json| canonical_json(serde_json::from_str(&json).unwrap()));
+        let ours = parse_with_crate(&input);
+        let mut differences = Vec::new();
+        match (&ours, &theirs) {
+            (Ok(ours), Ok(theirs)) => diff(&KeyPath::new(), ours, theirs, &mut differences),
+            (Err(e), Ok(_)) => differences.push((String::new(), format!("ours failed: {e}"))),
+            (Ok(_), Err(e)) => differences.push((String::new(), format!("libucl failed: {e}"))),
+            (Err(_), Err(_)) => {}
+        }
+
+        let allowed: Vec<&str> = KNOWN_DIFFERENCES
+            .iter()
+            .filter(|(known, _, _)| *known == name)
+            .map(|(_, path, _)| *path)
+            .collect();
+        for (path, detail) in &differences {
+            if !allowed.contains(&path.as_str()) {
+                unexpected.push(format!("{name} {path:?}: {detail}"));
+            }
+        }
+        for path in allowed {
+            if !differences.iter().any(|(found, _)| found == path) {
+                stale.push(format!("{name} {path:?}"));
+            }
+        }
+    }
+
+    assert!(
+        unexpected.is_empty(),
+        "undocumented differences from libucl:\n{}",
+        unexpected.join("\n")
+    );
+    assert!(
+        stale.is_empty(),
+        "known differences that are gone, remove them from KNOWN_DIFFERENCES:\n{}",
+        stale.join("\n")
+    );
+}
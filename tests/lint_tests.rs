@@ -0,0 +1,118 @@
+//! Tests for the structural lints reported through parser diagnostics
+
+use ucl_lexer::{Diagnostic, LintCode, LintConfig, ParserConfig, UclParser};
+
+fn lint_with(lints: LintConfig, input: &str) -> Vec<Diagnostic> {
+    let mut parser = UclParser::new(input).with_config(ParserConfig::new().with_lints(lints));
+    parser.parse_document().unwrap();
+    parser.take_diagnostics()
+}
+
+fn lint(input: &str) -> Vec<Diagnostic> {
+    lint_with(LintConfig::all(), input)
+}
+
+fn codes(diagnostics: &[Diagnostic]) -> Vec<LintCode> {
+    diagnostics.iter().filter_map(|d| d.code).collect()
+}
+
+#[test]
+fn test_lints_are_disabled_by_default() {
+    let mut parser = UclParser::new("a = \"true\"\nb: 1\na { a {} }\n");
+    parser.parse_document().unwrap();
+    assert!(parser.diagnostics().is_empty());
+}
+
+#[test]
+fn test_mixed_separators() {
+    let diagnostics = lint("a = 1\nb: 2\nc = 3\nserver { x: 1; y: 2 }\n");
+
+    assert_eq!(codes(&diagnostics), vec![LintCode::MixedSeparators]);
+    assert_eq!(diagnostics[0].position.line, 2);
+    assert_eq!(diagnostics[0].position.column, 2);
+    assert!(diagnostics[0].message.contains("`b` is assigned with ':'"));
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "mixed-separators: `b` is assigned with ':' but earlier entries of this object use '=' at 2:2"
+    );
+
+    // Appends and implicit values do not count
+    assert!(lint("a: [1]\na += [2]\nb 3\nc: 4\n").is_empty());
+}
+
+#[test]
+fn test_shadowed_key() {
+    let diagnostics = lint("server {\n  listen = 80\n  server {\n    name = \"x\"\n  }\n}\n");
+
+    assert_eq!(codes(&diagnostics), vec![LintCode::ShadowedKey]);
+    assert_eq!(diagnostics[0].position.line, 3);
+    assert!(lint("server { name = \"server\" }\nname = 1\n").is_empty());
+}
+
+#[test]
+fn test_shadowed_key_in_named_section() {
+    let diagnostics = lint("section foo foo { a = 1 }\n");
+    assert_eq!(codes(&diagnostics), vec![LintCode::ShadowedKey]);
+}
+
+#[test]
+fn test_empty_object_for_scalar_key() {
+    let diagnostics = lint("limits { upstream = 4 }\nproxy { upstream {} }\n");
+
+    assert_eq!(codes(&diagnostics), vec![LintCode::EmptyObject]);
+    assert_eq!(diagnostics[0].position.line, 2);
+    assert!(diagnostics[0].message.contains("scalar at line 1"));
+    assert!(lint("a {}\nb { c = 1 }\n").is_empty());
+}
+
+#[test]
+fn test_typed_strings() {
+    let input = "a = \"true\"\nb = '8080'\nc = \"30s\"\nd = \"2.5\"\ne \"off\"\nf = \"v8080\"\ng = \"8080 \"\nh = \"nan\"\ni = true\n";
+    let diagnostics = lint(input);
+
+    let lines: Vec<usize> = diagnostics.iter().map(|d| d.position.line).collect();
+    assert_eq!(lines, vec![1, 2, 3, 4, 5]);
+    assert!(
+        codes(&diagnostics)
+            .iter()
+            .all(|&code| code == LintCode::TypedString)
+    );
+    assert!(diagnostics[0].message.contains("looks like a boolean"));
+    assert!(diagnostics[1].message.contains("looks like a number"));
+    assert!(diagnostics[2].message.contains("looks like a duration"));
+    assert_eq!(diagnostics[1].position.column, 5);
+}
+
+#[test]
+fn test_lints_are_individually_suppressible() {
+    let input = "a = \"1\"\nb: 2\nb { b {} }\n";
+    assert_eq!(lint(input).len(), 4);
+
+    let lints = LintConfig::all()
+        .with_lint(LintCode::TypedString, false)
+        .with_lint(LintCode::ShadowedKey, false);
+    let diagnostics = lint_with(lints, input);
+    assert_eq!(
+        codes(&diagnostics),
+        vec![LintCode::MixedSeparators, LintCode::EmptyObject]
+    );
+
+    let only = LintConfig::new().with_lint(LintCode::ShadowedKey, true);
+    assert!(only.is_enabled(LintCode::ShadowedKey));
+    assert!(!only.is_enabled(LintCode::EmptyObject));
+    assert_eq!(codes(&lint_with(only, input)), vec![LintCode::ShadowedKey]);
+}
+
+#[test]
+fn test_lint_codes_are_stable() {
+    let names: Vec<&str> = LintCode::ALL.iter().map(|code| code.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "mixed-separators",
+            "shadowed-key",
+            "empty-object",
+            "typed-string"
+        ]
+    );
+}
@@ -0,0 +1,140 @@
+//! Tests for the meaning of a bare `m` suffix, as in `timeout = 5m`
+
+use serde::Deserialize;
+use std::io::Cursor;
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{
+    LexError, LexerConfig, MSuffix, Token, UclDeserializer, UclError, UclLexer, UclParser,
+    UclValue, from_str, streaming_lexer_from_reader,
+};
+
+fn lex(input: &str, meaning: MSuffix) -> Result<Token<'_>, LexError> {
+    let config = LexerConfig::new().with_m_suffix_meaning(meaning);
+    UclLexer::with_config(input, config).next_token()
+}
+
+#[test]
+fn test_unambiguous_suffixes_ignore_the_setting() {
+    for meaning in [MSuffix::Size, MSuffix::Minutes, MSuffix::Error] {
+        assert_eq!(lex("5min", meaning).unwrap(), Token::Time(300.0));
+        assert_eq!(
+            lex("5mb", meaning).unwrap(),
+            Token::Integer(5 * 1024 * 1024)
+        );
+        assert_eq!(lex("5M", meaning).unwrap(), Token::Integer(5_000_000));
+        assert_eq!(lex("5ms", meaning).unwrap(), Token::Time(0.005));
+    }
+}
+
+#[test]
+fn test_size_setting() {
+    assert_eq!(lex("5m", MSuffix::Size).unwrap(), Token::Integer(5_000_000));
+    let binary = LexerConfig::new()
+        .with_m_suffix_meaning(MSuffix::Size)
+        .with_size_suffix_binary(true);
+    assert_eq!(
+        UclLexer::with_config("5m", binary).next_token().unwrap(),
+        Token::Integer(5 * 1024 * 1024)
+    );
+    assert_eq!(
+        LexerConfig::libucl_compat().m_suffix_meaning(),
+        MSuffix::Size
+    );
+}
+
+#[test]
+fn test_minutes_setting() {
+    assert_eq!(lex("5m", MSuffix::Minutes).unwrap(), Token::Time(300.0));
+    assert_eq!(lex("1.5m", MSuffix::Minutes).unwrap(), Token::Time(90.0));
+
+    let config = LexerConfig::new().with_m_suffix_meaning(MSuffix::Minutes);
+    let value = UclParser::with_lexer_config("timeout = 5m", config)
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["timeout"], UclValue::Time(300.0));
+}
+
+#[test]
+fn test_error_setting_is_the_default() {
+    assert_eq!(LexerConfig::default().m_suffix_meaning(), MSuffix::Error);
+
+    match lex("15m", MSuffix::Error) {
+        Err(LexError::AmbiguousSuffix { number, position }) => {
+            assert_eq!(number, "15");
+            assert_eq!(position.column, 1);
+        }
+        other => panic!("unexpected result {other:?}"),
+    }
+
+    let error = UclParser::new("timeout = 5m").parse_document().unwrap_err();
+    assert!(
+        error.to_string().contains(
+            "Ambiguous suffix 'm' in '5m' at 1:11: write '5min' for minutes or '5mb' for a size"
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn test_streaming_lexer_follows_the_setting() {
+    let tokens = |meaning| {
+        let mut lexer =
+            streaming_lexer_from_reader(Cursor::new(b"5m".to_vec())).with_m_suffix_meaning(meaning);
+        lexer.next_token()
+    };
+    assert_eq!(tokens(MSuffix::Size).unwrap(), Token::Integer(5_000_000));
+    assert_eq!(tokens(MSuffix::Minutes).unwrap(), Token::Time(300.0));
+    assert!(matches!(
+        tokens(MSuffix::Error),
+        Err(LexError::AmbiguousSuffix { ref number, .. }) if number == "5"
+    ));
+}
+
+#[test]
+fn test_number_fields_report_the_ambiguity() {
+    #[derive(Debug, Deserialize)]
+    struct Job {
+        #[allow(dead_code)]
+        timeout: u64,
+    }
+
+    let error = from_str::<Job>("name = job\ntimeout = 5m").unwrap_err();
+    assert!(
+        matches!(&error, UclError::Serde(SerdeError::AmbiguousSuffix { number, .. }) if number == "5"),
+        "{error:?}"
+    );
+    assert_eq!(
+        error.to_string(),
+        "Serde error: Ambiguous suffix 'm' in '5m' for 'timeout' at line 2: write '5min' for \
+         minutes or '5mb' for a size"
+    );
+
+    // A chosen meaning applies to deserialized fields too
+    let read = |meaning| {
+        let config = LexerConfig::new().with_m_suffix_meaning(meaning);
+        let deserializer = UclDeserializer::with_lexer_config("timeout = 5m", config);
+        Job::deserialize(deserializer).map(|job| job.timeout)
+    };
+    assert_eq!(read(MSuffix::Size).unwrap(), 5_000_000);
+    assert_eq!(read(MSuffix::Minutes).unwrap(), 300);
+}
+
+#[test]
+fn test_lenient_coercion_follows_the_setting() {
+    #[derive(Debug, Deserialize)]
+    struct Job {
+        timeout: f64,
+    }
+
+    let read = |meaning| {
+        let config = LexerConfig::new().with_m_suffix_meaning(meaning);
+        let deserializer = UclDeserializer::with_lexer_config("timeout = \"5m\"", config).lenient();
+        Job::deserialize(deserializer).map(|job| job.timeout)
+    };
+    assert_eq!(read(MSuffix::Minutes).unwrap(), 300.0);
+    assert_eq!(read(MSuffix::Size).unwrap(), 5_000_000.0);
+    assert!(matches!(
+        read(MSuffix::Error),
+        Err(UclError::Serde(SerdeError::AmbiguousSuffix { .. }))
+    ));
+}
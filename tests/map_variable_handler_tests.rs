@@ -0,0 +1,118 @@
+//! Tests for the matching and usage options of `MapVariableHandler`
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use ucl_lexer::{ChainedVariableHandler, MapVariableHandler, UclParser, UclValue, VariableHandler};
+
+fn variables() -> HashMap<String, String> {
+    HashMap::from([
+        ("DATABASE_URL".to_string(), "postgres://db\n".to_string()),
+        ("Region".to_string(), "  eu-west ".to_string()),
+    ])
+}
+
+/// Parses `input`, returning the value and the diagnostic messages
+fn parse(input: &str, handler: impl VariableHandler + 'static) -> (UclValue, Vec<String>) {
+    let mut parser = UclParser::with_variable_handler(input, Box::new(handler));
+    let value = parser.parse_document().unwrap();
+    let messages = parser
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| diagnostic.message.clone())
+        .collect();
+    (value, messages)
+}
+
+#[test]
+fn test_case_insensitive_names() {
+    let handler = MapVariableHandler::from_map(variables()).case_insensitive(true);
+    assert_eq!(
+        handler.resolve_variable("database_url").as_deref(),
+        Some("postgres://db\n")
+    );
+    assert_eq!(
+        handler.resolve_variable("REGION").as_deref(),
+        Some("  eu-west ")
+    );
+    assert_eq!(handler.resolve_variable("missing"), None);
+    // Names keep their case for iteration
+    assert!(handler.variables().contains_key("Region"));
+
+    // Names inserted afterwards are folded too, the last one read
+    let mut handler = handler;
+    handler.insert("Cache".to_string(), "a".to_string());
+    handler.insert("CACHE".to_string(), "b".to_string());
+    assert_eq!(handler.resolve_variable("cache").as_deref(), Some("b"));
+
+    // Matching is exact by default
+    let handler = MapVariableHandler::from_map(variables());
+    assert_eq!(handler.resolve_variable("database_url"), None);
+}
+
+#[test]
+fn test_trimmed_values() {
+    let handler = MapVariableHandler::from_map(variables())
+        .case_insensitive(true)
+        .trim_values(true);
+    let (value, _) = parse(
+        "url = \"${database_url}/app\"\nregion = \"$region\"",
+        handler,
+    );
+    assert_eq!(value["url"], "postgres://db/app");
+    assert_eq!(value["region"], "eu-west");
+
+    let (value, _) = parse(
+        "url = \"${DATABASE_URL}/app\"",
+        MapVariableHandler::from_map(variables()),
+    );
+    assert_eq!(value["url"], "postgres://db\n/app");
+}
+
+#[test]
+fn test_unused_variables_are_reported() {
+    let mut handler = MapVariableHandler::from_map(variables()).require_all_used(true);
+    handler.insert("PORT".to_string(), "80".to_string());
+    let (_, messages) = parse("url = \"${DATABASE_URL}\"", handler);
+    assert_eq!(
+        messages,
+        ["variables provided but never used: PORT, Region"]
+    );
+
+    let handler = MapVariableHandler::from_map(variables()).require_all_used(true);
+    let (_, messages) = parse("a = \"$DATABASE_URL\"\nb = \"${Region}\"", handler);
+    assert!(messages.is_empty(), "{messages:?}");
+
+    // Without the option nothing is reported
+    let (_, messages) = parse("a = 1", MapVariableHandler::from_map(variables()));
+    assert!(messages.is_empty(), "{messages:?}");
+}
+
+#[test]
+fn test_unused_variables_through_shared_and_chained_handlers() {
+    let handler = Arc::new(
+        MapVariableHandler::from_map(variables())
+            .case_insensitive(true)
+            .require_all_used(true),
+    );
+    let (_, messages) = parse("a = \"$region\"", Arc::clone(&handler));
+    assert_eq!(
+        messages,
+        ["variables provided but never used: DATABASE_URL"]
+    );
+    // Usage carries over to later documents
+    let (_, messages) = parse("a = \"$database_url\"", Arc::clone(&handler));
+    assert!(messages.is_empty(), "{messages:?}");
+
+    let first = MapVariableHandler::from_map(variables()).require_all_used(true);
+    let second = MapVariableHandler::from_map(HashMap::from([(
+        "DATABASE_URL".to_string(),
+        "unused".to_string(),
+    )]))
+    .require_all_used(true);
+    let chained = ChainedVariableHandler::from_handlers(vec![Box::new(first), Box::new(second)]);
+    let (_, messages) = parse("a = \"$DATABASE_URL\"", chained);
+    assert_eq!(
+        messages,
+        ["variables provided but never used: Region, DATABASE_URL"]
+    );
+}
@@ -0,0 +1,131 @@
+//! Tests for deserializing newtype structs, unit structs and `()` values
+
+use serde::Deserialize;
+use std::marker::PhantomData;
+use ucl_lexer::{UclParser, from_str, from_value};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Port(u16);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Name(String);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Tls {
+    cert_file: String,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Secure(Tls);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Marker;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Server {
+    port: Port,
+    name: Option<Name>,
+    tls: Option<Secure>,
+    backups: Option<Vec<Port>>,
+}
+
+#[test]
+fn test_newtypes_are_transparent() {
+    let input = "port = 8080\nname = web\ntls { cert_file = \"a.pem\" }\nbackups = [8081, 8082]\n";
+    let expected = Server {
+        port: Port(8080),
+        name: Some(Name("web".to_string())),
+        tls: Some(Secure(Tls {
+            cert_file: "a.pem".to_string(),
+        })),
+        backups: Some(vec![Port(8081), Port(8082)]),
+    };
+    assert_eq!(from_str::<Server>(input).unwrap(), expected);
+
+    let value = UclParser::new(input).parse_document().unwrap();
+    assert_eq!(from_value::<Server>(value).unwrap(), expected);
+
+    assert_eq!(from_str::<Port>("8080").unwrap(), Port(8080));
+}
+
+#[test]
+fn test_newtype_mismatch_names_the_inner_type() {
+    let err = from_str::<Server>("port = \"http\"").unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("port"));
+    assert!(
+        err.to_string()
+            .ends_with("invalid type: string \"http\", expected u16"),
+        "{err}"
+    );
+
+    let err = from_str::<Server>("port = 1\nbackups = [2, \"x\"]").unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("backups.1"));
+    assert!(err.to_string().contains("expected u16"), "{err}");
+
+    let err = from_str::<Server>("port = 1\ntls { cert_file = [] }").unwrap_err();
+    assert_eq!(err.key_path().as_deref(), Some("tls.cert_file"));
+    assert!(err.to_string().contains("expected a string"), "{err}");
+}
+
+#[test]
+fn test_unit_values_accept_null_and_empty_objects() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Flags {
+        marker: Option<Marker>,
+        nothing: Option<()>,
+        #[serde(default)]
+        phantom: PhantomData<u8>,
+    }
+
+    let flags: Flags = from_str("marker = null\nnothing = null").unwrap();
+    assert_eq!((flags.marker, flags.nothing), (None, None));
+
+    let flags: Flags = from_str("marker {}\nnothing = {}\nphantom = {}").unwrap();
+    assert_eq!((flags.marker, flags.nothing), (Some(Marker), Some(())));
+
+    // Absent fields wrapped in `Option` are `None`
+    let flags: Flags = from_str("").unwrap();
+    assert_eq!(
+        flags,
+        Flags {
+            marker: None,
+            nothing: None,
+            phantom: PhantomData,
+        }
+    );
+
+    from_str::<()>("null").unwrap();
+    from_str::<()>("{}").unwrap();
+    from_str::<()>("").unwrap();
+    assert_eq!(from_str::<Marker>("").unwrap(), Marker);
+    from_str::<PhantomData<String>>("null").unwrap();
+}
+
+#[test]
+fn test_unit_mismatch_names_the_expected_type() {
+    #[derive(Debug, Deserialize)]
+    #[allow(dead_code)]
+    struct Flags {
+        marker: Option<Marker>,
+        nothing: Option<()>,
+    }
+
+    let err = from_str::<Flags>("marker = 1").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: error at marker (line 1, col 1): \
+         invalid type: integer `1`, expected unit struct Marker"
+    );
+    let err = from_str::<Flags>("nothing = { a = 1 }").unwrap_err();
+    assert!(
+        err.to_string()
+            .ends_with("invalid type: map, expected unit"),
+        "{err}"
+    );
+    let err = from_str::<()>("[1]").unwrap_err();
+    assert!(
+        err.to_string()
+            .ends_with("invalid type: sequence, expected unit"),
+        "{err}"
+    );
+}
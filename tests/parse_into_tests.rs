@@ -0,0 +1,130 @@
+//! Tests for reparsing into an existing tree with `UclParser::parse_into`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use ucl_lexer::{ParserConfig, UclContainerExt, UclObject, UclParser, UclValue};
+
+/// Counts the allocations made by the current thread
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations `f` makes
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn document(services: usize) -> String {
+    let mut text = String::from("version = 3\n");
+    for service in 0..services {
+        text.push_str(&format!(
+            "service_{0} {{\n    name = \"service-{0}\"\n    port = {1}\n    enabled = {2}\n    \
+             tags = [\"web\", \"tier-{3}\", \"zone-{4}\"]\n    limits {{ memory = 512mb, timeout = 30s }}\n}}\n",
+            service,
+            8000 + service,
+            service % 2 == 0,
+            service % 3,
+            service % 5
+        ));
+    }
+    text
+}
+
+fn fresh(input: &str) -> UclValue {
+    UclParser::new(input).parse_document().unwrap()
+}
+
+#[test]
+fn test_results_equal_fresh_parses() {
+    let mut parser = UclParser::new("");
+    let mut value = UclValue::Null;
+
+    // Growing, shrinking and changing the shape of the document
+    let inputs = [
+        document(20),
+        document(50),
+        document(3),
+        "a = [1, 2, [3, 4, 5, 6, 7]], b { c = \"longer string than before\" }".to_string(),
+        "[\"root\", \"array\"]".to_string(),
+        String::new(),
+        document(10),
+    ];
+    for input in &inputs {
+        parser.parse_into(input, &mut value).unwrap();
+        assert_eq!(value, fresh(input));
+    }
+}
+
+#[test]
+fn test_configuration_applies_and_errors_leave_null() {
+    let config = ParserConfig::new().with_preserve_key_order(true);
+    let mut parser = UclParser::new("").with_config(config);
+    let mut value = UclValue::Null;
+
+    parser.parse_into("b = 1\na = 2", &mut value).unwrap();
+    assert_eq!(
+        value.as_object().unwrap().keys().collect::<Vec<_>>(),
+        ["b", "a"]
+    );
+
+    let err = parser.parse_into("a = [1, 2", &mut value).unwrap_err();
+    assert!(err.to_string().contains("Unexpected token"), "{err}");
+    assert_eq!(value, UclValue::Null);
+
+    // Buffers kept through the error are used by the next call
+    parser.parse_into(&document(5), &mut value).unwrap();
+    assert_eq!(value, fresh(&document(5)));
+}
+
+#[test]
+fn test_reparsing_allocates_less() {
+    let input = document(200);
+    let mut parser = UclParser::new("");
+    let mut value = UclValue::Null;
+    parser.parse_into(&input, &mut value).unwrap();
+
+    let fresh_allocations = allocations(|| drop(fresh(&input)));
+    let reused_allocations = allocations(|| parser.parse_into(&input, &mut value).unwrap());
+    assert!(
+        reused_allocations * 3 < fresh_allocations,
+        "parse_into made {} allocations, parse_document {}",
+        reused_allocations,
+        fresh_allocations
+    );
+    assert_eq!(value, fresh(&input));
+}
+
+#[test]
+fn test_clear_retaining_capacity() {
+    let mut object: UclObject = (0..32)
+        .map(|i| (format!("key_{}", i).into(), UclValue::from(i)))
+        .collect();
+    let capacity = object.capacity();
+    object.clear_retaining_capacity();
+    assert!(object.is_empty());
+    assert_eq!(object.capacity(), capacity);
+
+    let mut array: ucl_lexer::UclArray = (0..32).map(UclValue::from).collect();
+    let capacity = array.capacity();
+    array.clear_retaining_capacity();
+    assert!(array.is_empty());
+    assert_eq!(array.capacity(), capacity);
+}
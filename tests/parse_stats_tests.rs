@@ -0,0 +1,74 @@
+//! Tests for the statistics collected while parsing
+
+use std::collections::HashMap;
+use ucl_lexer::{MapVariableHandler, UclParser};
+
+const DOCUMENT: &str = r#"# service config
+name = "api-${ENV}"
+path = "C:\\tmp"
+server {
+    port = 8080 /* default */
+    tags = [a, "b"]
+}
+name = "$HOST:${MISSING}"
+"#;
+
+fn parser(input: &str) -> UclParser<'_> {
+    let mut variables = HashMap::new();
+    variables.insert("ENV".to_string(), "prod".to_string());
+    variables.insert("HOST".to_string(), "example".to_string());
+    UclParser::with_variable_handler(input, Box::new(MapVariableHandler::from_map(variables)))
+}
+
+#[test]
+fn test_stats_for_fixed_document() {
+    let (value, stats) = parser(DOCUMENT).parse_with_stats().unwrap();
+    assert_eq!(value["name"][1], "example:${MISSING}");
+
+    // 23 tokens including end of file, plus the two comments
+    assert_eq!(stats.tokens, 25);
+    assert_eq!(stats.peak_nesting_depth, 2);
+    assert_eq!(stats.strings_expanded, 2);
+    // `${MISSING}` has no value and is kept as written
+    assert_eq!(stats.variables_expanded, 2);
+    // The escaped path and both strings with variables need an owned copy
+    assert_eq!(stats.zero_copy_strings, 8);
+    assert_eq!(stats.owned_strings, 3);
+    assert_eq!(stats.comments_skipped, 2);
+    assert_eq!(stats.duplicate_keys_encountered, 1);
+    assert_eq!(stats.bytes_processed, DOCUMENT.len());
+}
+
+#[test]
+fn test_stats_accessor_matches_parse_with_stats() {
+    let mut parser = parser(DOCUMENT);
+    parser.parse_document().unwrap();
+    let stats = parser.stats();
+
+    assert_eq!(stats.tokens, 25);
+    assert_eq!(stats.duplicate_keys_encountered, 1);
+}
+
+#[test]
+fn test_stats_without_variable_handler() {
+    let mut parser = UclParser::new("a = \"$HOME\"\nb = 1\n");
+    parser.parse_document().unwrap();
+    let stats = parser.stats();
+
+    assert_eq!(stats.strings_expanded, 1);
+    assert_eq!(stats.variables_expanded, 0);
+    assert_eq!(stats.duplicate_keys_encountered, 0);
+    assert_eq!(stats.peak_nesting_depth, 0);
+}
+
+#[test]
+fn test_backtracking_does_not_double_count() {
+    // Implicit objects are detected with a lookahead that is rolled back
+    let mut parser = UclParser::new("section \"name\" { key = value }\n");
+    parser.parse_document().unwrap();
+    let stats = parser.stats();
+
+    assert_eq!(stats.tokens, 8);
+    assert_eq!(stats.zero_copy_strings, 4);
+    assert_eq!(stats.peak_nesting_depth, 1);
+}
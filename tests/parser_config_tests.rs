@@ -0,0 +1,306 @@
+//! Tests for parser configuration options
+//!
+//! Each option is exercised through the public parser and deserializer APIs.
+
+use serde::Deserialize;
+use ucl_lexer::{
+    DuplicateKeyBehavior, LexerConfig, NonFinitePolicy, ParseError, ParserConfig, UclDeserializer,
+    UclParser, UclValue,
+};
+
+fn parse_with(input: &str, config: ParserConfig) -> Result<UclValue, ParseError> {
+    UclParser::new(input).with_config(config).parse_document()
+}
+
+#[test]
+fn test_non_finite_allowed_by_default() {
+    let value = parse_with("a = inf; b = -infinity; c = nan", ParserConfig::default()).unwrap();
+
+    assert_eq!(value["a"].as_float(), Some(f64::INFINITY));
+    assert_eq!(value["b"].as_float(), Some(f64::NEG_INFINITY));
+    assert!(value["c"].as_float().unwrap().is_nan());
+}
+
+#[test]
+fn test_non_finite_error_policy() {
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Error);
+
+    let err = parse_with("port = 80\nratio = nan", config.clone()).unwrap_err();
+    match err {
+        ParseError::NonFiniteNumber { value, position } => {
+            assert_eq!(value, "nan");
+            assert_eq!(position.line, 2);
+            assert_eq!(position.column, 9);
+        }
+        other => panic!("Expected NonFiniteNumber, got {:?}", other),
+    }
+
+    assert!(parse_with("limit = -inf", config.clone()).is_err());
+    assert!(parse_with("values = [1.0, infinity]", config.clone()).is_err());
+    assert!(parse_with("ratio = 0.5", config).is_ok());
+}
+
+#[test]
+fn test_non_finite_overflow_is_covered() {
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Error);
+    assert!(parse_with("huge = 1e999", config).is_err());
+}
+
+#[test]
+fn test_non_finite_null_policy_records_diagnostic() {
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Null);
+    let mut parser = UclParser::new("a = 1.5\nb = inf\nc = [nan]").with_config(config);
+    let value = parser.parse_document().unwrap();
+
+    assert_eq!(value["a"], UclValue::Float(1.5));
+    assert!(value["b"].is_null());
+    assert!(value["c"][0].is_null());
+
+    let diagnostics = parser.take_diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].position.line, 2);
+    assert!(diagnostics[0].message.contains("inf"));
+    assert!(parser.diagnostics().is_empty());
+}
+
+#[test]
+fn test_deserializer_respects_non_finite_policy() {
+    #[derive(Debug, Deserialize)]
+    struct Limits {
+        ratio: Option<f64>,
+    }
+
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Null);
+    let limits =
+        Limits::deserialize(UclDeserializer::with_parser_config("ratio = nan", config)).unwrap();
+    assert_eq!(limits.ratio, None);
+
+    let config = ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Error);
+    let result = Limits::deserialize(UclDeserializer::with_parser_config("ratio = inf", config));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_non_finite_keywords_can_be_disabled() {
+    let lexer_config = LexerConfig::new().with_non_finite_keywords(false);
+    let mut parser = UclParser::with_lexer_config("mode = nan\nlevel = inf\nnan = 1", lexer_config)
+        .with_config(ParserConfig::new().with_non_finite_numbers(NonFinitePolicy::Error));
+    let value = parser.parse_document().unwrap();
+
+    assert_eq!(value["mode"], "nan");
+    assert_eq!(value["level"], "inf");
+    assert_eq!(value["nan"], 1);
+}
+
+#[test]
+fn test_duplicate_key_error_reports_both_occurrences() {
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+    let input = "name = \"a\"\nport = 80\n\nname = \"b\"\n";
+
+    let err = parse_with(input, config.clone()).unwrap_err();
+    match &err {
+        ParseError::DuplicateKey {
+            key,
+            first_occurrence,
+            second_occurrence,
+        } => {
+            assert_eq!(key, "name");
+            assert_eq!((first_occurrence.line, first_occurrence.column), (1, 1));
+            assert_eq!((second_occurrence.line, second_occurrence.column), (4, 1));
+        }
+        other => panic!("Expected DuplicateKey, got {:?}", other),
+    }
+    assert_eq!(
+        err.to_string(),
+        "duplicate key `name` at line 4 (first defined at line 1)"
+    );
+
+    let err = parse_with("server {\n  host = a\n  host = b\n}", config).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "duplicate key `host` at line 3 (first defined at line 2)"
+    );
+}
+
+#[test]
+fn test_duplicate_keys_are_scoped_per_object() {
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+
+    let value = parse_with(
+        "servers = [ { name = \"a\" }, { name = \"a\" } ]",
+        config.clone(),
+    )
+    .unwrap();
+    assert_eq!(value["servers"][1]["name"], "a");
+
+    let value = parse_with("a { x = 1 } b { x = 2 }", config.clone()).unwrap();
+    assert_eq!(value["b"]["x"], 2);
+
+    let input = "outer { list = [ { inner { x = 1 } }, { inner { x = 1 } } ] }";
+    let value = parse_with(input, config.clone()).unwrap();
+    assert_eq!(value["outer"]["list"][1]["inner"]["x"], 1);
+}
+
+#[test]
+fn test_duplicate_key_error_merges_distinct_named_sections() {
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+
+    let value = parse_with("server a { x = 1 }\nserver b { x = 1 }", config.clone()).unwrap();
+    assert_eq!(value["server"]["a"]["x"], 1);
+    assert_eq!(value["server"]["b"]["x"], 1);
+
+    let input = "section eu a { x = 1 }\nsection eu b { x = 2 }";
+    let value = parse_with(input, config.clone()).unwrap();
+    assert_eq!(value["section"]["eu"]["a"]["x"], 1);
+    assert_eq!(value["section"]["eu"]["b"]["x"], 2);
+
+    let input = "http {\n  server a { x = 1 }\n  server b { x = 1 }\n}";
+    let value = parse_with(input, config.clone()).unwrap();
+    assert_eq!(value["http"]["server"]["b"]["x"], 1);
+}
+
+#[test]
+fn test_duplicate_key_error_rejects_repeated_sections() {
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Error);
+
+    // (input, key, first occurrence, second occurrence)
+    let cases = [
+        ("a { x = 1 }\na { y = 2 }", "a", (1, 1), (2, 1)),
+        ("a { x = 1 }\na { x = 2 }", "a", (1, 1), (2, 1)),
+        ("k {\n  s { x = 1 }\n  s { y = 2 }\n}", "s", (2, 3), (3, 3)),
+        (
+            "server a { x = 1 }\nserver a { y = 2 }",
+            "a",
+            (1, 1),
+            (2, 1),
+        ),
+        (
+            "server b { }\nserver a { x = 1 }\nserver a { y = 2 }",
+            "a",
+            (2, 1),
+            (3, 1),
+        ),
+        (
+            "section eu a { x = 1 }\nsection eu a { y = 2 }",
+            "a",
+            (1, 1),
+            (2, 1),
+        ),
+        (
+            "http {\n  server a { }\n  server a { }\n}",
+            "a",
+            (2, 3),
+            (3, 3),
+        ),
+        ("server = 1\nserver a { x = 1 }", "server", (1, 1), (2, 1)),
+        (
+            "server a { x = 1 }\nserver { y = 2 }",
+            "server",
+            (1, 1),
+            (2, 1),
+        ),
+    ];
+    for (input, expected_key, first, second) in cases {
+        match parse_with(input, config.clone()).unwrap_err() {
+            ParseError::DuplicateKey {
+                key,
+                first_occurrence,
+                second_occurrence,
+            } => {
+                assert_eq!(key, expected_key, "{input}");
+                assert_eq!(
+                    (first_occurrence.line, first_occurrence.column),
+                    first,
+                    "{input}"
+                );
+                assert_eq!(
+                    (second_occurrence.line, second_occurrence.column),
+                    second,
+                    "{input}"
+                );
+            }
+            other => panic!("{input}: expected DuplicateKey, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_duplicate_key_override_and_keep_first_record_diagnostics() {
+    let input = "level = 1\nserver { host = a; host = b }\nlevel = 2";
+
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::Override);
+    let mut parser = UclParser::new(input).with_config(config);
+    let value = parser.parse_document().unwrap();
+    assert_eq!(value["level"], 2);
+    assert_eq!(value["server"]["host"], "b");
+
+    let diagnostics = parser.take_diagnostics();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].position.line, 2);
+    assert_eq!(diagnostics[1].position.line, 3);
+    assert!(diagnostics[1].message.contains("first defined at line 1"));
+
+    let config = ParserConfig::new().with_duplicate_key_behavior(DuplicateKeyBehavior::KeepFirst);
+    let mut parser = UclParser::new(input).with_config(config);
+    let value = parser.parse_document().unwrap();
+    assert_eq!(value["level"], 1);
+    assert_eq!(value["server"]["host"], "a");
+    assert_eq!(parser.diagnostics().len(), 2);
+}
+
+#[test]
+fn test_builders_and_accessors() {
+    let lexer_config = LexerConfig::new()
+        .with_save_comments(true)
+        .with_max_key_length(16)
+        .with_shadowed_suffixes(["ms"]);
+    assert!(lexer_config.save_comments());
+    assert_eq!(lexer_config.max_key_length(), 16);
+    assert_eq!(lexer_config.shadowed_suffixes(), ["ms"]);
+    assert_eq!(
+        lexer_config.max_tokens(),
+        LexerConfig::default().max_tokens()
+    );
+
+    let config = ParserConfig::new()
+        .with_max_depth(4)
+        .with_lenient_coercion(true);
+    assert_eq!(config.max_depth(), 4);
+    assert!(config.lenient_coercion());
+    assert!(config.directives().is_none());
+    assert!(config.constants().is_empty());
+}
+
+#[test]
+fn test_presets() {
+    assert!(LexerConfig::strict().strict_unicode());
+    assert!(!LexerConfig::strict().allow_digit_separators());
+    assert!(!LexerConfig::libucl_compat().allow_digit_separators());
+    assert!(!ParserConfig::strict().relaxed_assignment());
+    assert!(ParserConfig::libucl_compat().bare_key_as_true());
+
+    let lexer_config = LexerConfig::untrusted();
+    assert!(lexer_config.max_nesting_depth() < LexerConfig::default().max_nesting_depth());
+    assert!(lexer_config.max_tokens() < LexerConfig::default().max_tokens());
+    assert!(!lexer_config.detect_variables());
+    let config = ParserConfig::untrusted();
+    assert!(config.max_depth() < ParserConfig::default().max_depth());
+    assert!(config.max_document_size() < ParserConfig::default().max_document_size());
+
+    // Nesting that the defaults accept is rejected from untrusted input
+    let input = format!("a = {}1{}", "[".repeat(40), "]".repeat(40));
+    assert!(UclParser::new(&input).parse_document().is_ok());
+    assert!(
+        UclParser::with_lexer_config(&input, LexerConfig::untrusted())
+            .with_config(ParserConfig::untrusted())
+            .parse_document()
+            .is_err()
+    );
+
+    // `$` is read literally from untrusted input
+    let value = UclParser::with_lexer_config("home = \"${HOME}\"", LexerConfig::untrusted())
+        .with_config(ParserConfig::untrusted())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["home"], "${HOME}");
+}
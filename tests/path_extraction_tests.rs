@@ -0,0 +1,141 @@
+//! Tests for parsing selected sections with `from_str_at_path` and `parse_document_keys`
+
+use serde::Deserialize;
+use ucl_lexer::{
+    ParserConfig, UclError, UclObject, UclParser, UclValue, from_str_at_path, from_str_at_paths,
+    ucl,
+};
+
+const DOCUMENT: &str = r#"
+version = 3
+server {
+    listen = [
+        "0.0.0.0:80",
+        "[::]:80"
+    ]
+    tls { cert = "/etc/ssl/site.pem", port = 443 }
+}
+upstream backend { host = "10.0.0.1"; weight = 5 }
+motd = "Welcome, " +
+    "visitor"
+banner = <<EOD
+} not a brace {
+EOD
+mode fast
+logging { level = "info" }
+a = 1; b = [1, [2, 3]], c = { d = 4 } e { f = 5 }
+logging { format = "json" }
+tags = ["x", "y"]
+last
+= "value"
+"#;
+
+fn full() -> UclValue {
+    UclParser::new(DOCUMENT).parse_document().unwrap()
+}
+
+fn only(keys: &[&str]) -> UclObject {
+    UclParser::new(DOCUMENT).parse_document_keys(keys).unwrap()
+}
+
+#[test]
+fn test_sections_equal_a_full_parse() {
+    let full = full();
+    let full = full.as_object().unwrap();
+    for key in full.keys() {
+        let sections = only(&[key.as_str()]);
+        assert_eq!(sections.len(), 1, "{key}");
+        assert_eq!(sections[key.as_str()], full[key.as_str()], "{key}");
+    }
+
+    // Repeated sections are merged as in a full parse
+    assert_eq!(
+        only(&["logging"])["logging"],
+        ucl!([{ "level": "info" }, { "format": "json" }])
+    );
+
+    let sections = only(&["motd", "e", "missing"]);
+    assert_eq!(sections.keys().collect::<Vec<_>>(), ["motd", "e"]);
+    assert_eq!(sections["motd"], "Welcome, visitor");
+}
+
+#[test]
+fn test_from_str_at_path() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Tls {
+        cert: String,
+        port: u16,
+    }
+
+    let tls: Tls = from_str_at_path(DOCUMENT, "server.tls").unwrap();
+    assert_eq!(
+        tls,
+        Tls {
+            cert: "/etc/ssl/site.pem".to_string(),
+            port: 443,
+        }
+    );
+
+    let listen: String = from_str_at_path(DOCUMENT, "server.listen.1").unwrap();
+    assert_eq!(listen, "[::]:80");
+
+    let values: Vec<serde_json::Value> =
+        from_str_at_paths(DOCUMENT, &["upstream.backend.weight", "version", "c.d"]).unwrap();
+    assert_eq!(values, [5, 3, 4]);
+}
+
+#[test]
+fn test_path_not_found() {
+    let err = from_str_at_path::<String>(DOCUMENT, "server.tls.key").unwrap_err();
+    assert!(matches!(
+        err,
+        UclError::PathNotFound { ref path, closest: Some(ref closest) }
+            if path == "server.tls.key" && closest == "server.tls"
+    ));
+
+    let err = from_str_at_path::<String>(DOCUMENT, "database.host").unwrap_err();
+    assert!(matches!(err, UclError::PathNotFound { closest: None, .. }));
+    assert_eq!(err.to_string(), "No value at path 'database.host'");
+}
+
+#[test]
+fn test_other_roots_and_errors() {
+    // A braced root is parsed in full and then filtered
+    let sections = UclParser::new("{ a = 1, b = 2 }")
+        .parse_document_keys(&["b"])
+        .unwrap();
+    assert_eq!(UclValue::Object(sections), ucl!({ "b": 2 }));
+
+    // Arrays and scalars have no sections
+    assert!(
+        UclParser::new("[1, 2]")
+            .parse_document_keys(&["a"])
+            .unwrap()
+            .is_empty()
+    );
+
+    // Skipped sections are still lexed
+    assert!(
+        UclParser::new("a = \"unterminated\nb = 1")
+            .parse_document_keys(&["b"])
+            .is_err()
+    );
+
+    // Bare keys read as `true` end at the line as in a full parse
+    let sections = UclParser::new("enabled\nserver { port = 80 }")
+        .with_config(ParserConfig::libucl_compat())
+        .parse_document_keys(&["server"])
+        .unwrap();
+    assert_eq!(sections["server"], ucl!({ "port": 80 }));
+}
+
+#[test]
+fn test_anchors_resolve_across_skipped_sections() {
+    let input = "defaults = &defaults { retries = 3 }\nservice { *defaults, port = 80 }";
+    let sections = UclParser::new(input)
+        .with_config(ParserConfig::new().with_enable_anchors(true))
+        .parse_document_keys(&["service"])
+        .unwrap();
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections["service"], ucl!({ "retries": 3, "port": 80 }));
+}
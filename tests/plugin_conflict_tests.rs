@@ -0,0 +1,225 @@
+//! Tests for plugins that claim the same number suffixes or key paths
+
+use ucl_lexer::{
+    CssUnitsPlugin, NumberSuffixHandler, ParseError, PluginConfig, PluginRegistry,
+    StringPostProcessor, UclParserBuilder, UclPlugin, UclValue, VariableContext,
+};
+
+/// Reads `px` as device pixels, at twice the CSS pixel
+struct RetinaPlugin;
+
+struct RetinaSuffixHandler;
+
+impl NumberSuffixHandler for RetinaSuffixHandler {
+    fn parse_suffix(&self, suffix: &str) -> Option<f64> {
+        (suffix == "px").then_some(2.0)
+    }
+
+    fn suffixes(&self) -> Vec<String> {
+        vec!["px".to_string()]
+    }
+}
+
+impl UclPlugin for RetinaPlugin {
+    fn name(&self) -> &str {
+        "retina"
+    }
+
+    fn number_suffix_handlers(&self) -> Vec<Box<dyn NumberSuffixHandler>> {
+        vec![Box::new(RetinaSuffixHandler)]
+    }
+}
+
+/// Rewrites the strings at one key path with a fixed prefix
+struct PrefixPlugin {
+    name: &'static str,
+    path: &'static str,
+    prefix: &'static str,
+}
+
+struct PrefixProcessor {
+    path: &'static str,
+    prefix: &'static str,
+}
+
+impl StringPostProcessor for PrefixProcessor {
+    fn process_string(&self, value: &str, context: &VariableContext) -> Result<String, ParseError> {
+        let path: Vec<_> = context
+            .current_object_path
+            .iter()
+            .map(|key| key.as_str())
+            .collect();
+        if path.join(".") == self.path {
+            Ok(format!("{}{}", self.prefix, value))
+        } else {
+            Ok(value.to_string())
+        }
+    }
+
+    fn paths(&self) -> Vec<String> {
+        vec![self.path.to_string()]
+    }
+}
+
+impl UclPlugin for PrefixPlugin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn string_processors(&self) -> Vec<Box<dyn StringPostProcessor>> {
+        vec![Box::new(PrefixProcessor {
+            path: self.path,
+            prefix: self.prefix,
+        })]
+    }
+}
+
+fn width(builder: UclParserBuilder<'_>) -> UclValue {
+    let mut parser = builder.build().unwrap();
+    let document = parser.parse_document().unwrap();
+    document.get_path("width").unwrap().clone()
+}
+
+#[test]
+fn test_same_suffix_is_a_conflict() {
+    let error = UclParserBuilder::new("width = 10px")
+        .with_plugin(Box::new(CssUnitsPlugin::new()))
+        .unwrap()
+        .with_plugin(Box::new(RetinaPlugin))
+        .unwrap()
+        .build()
+        .err()
+        .unwrap();
+
+    match &error {
+        ParseError::PluginConflict {
+            plugin_a,
+            plugin_b,
+            detail,
+        } => {
+            assert_eq!(plugin_a, "css-units");
+            assert_eq!(plugin_b, "retina");
+            assert_eq!(detail, "both read the number suffix 'px'");
+        }
+        other => panic!("unexpected error {other}"),
+    }
+    assert_eq!(
+        error.to_string(),
+        "Plugins 'css-units' and 'retina' conflict: both read the number suffix 'px'; \
+         allow one of them to override the other"
+    );
+}
+
+#[test]
+fn test_allowed_override_takes_precedence() {
+    let builder = || {
+        UclParserBuilder::new("width = 10px")
+            .with_plugin(Box::new(CssUnitsPlugin::new()))
+            .unwrap()
+            .with_plugin(Box::new(RetinaPlugin))
+            .unwrap()
+    };
+
+    // The retina plugin has a lower priority, yet reads `px` once allowed to
+    assert_eq!(
+        width(builder().allow_override("retina")),
+        UclValue::Float(20.0)
+    );
+    assert_eq!(
+        width(builder().allow_override("css-units")),
+        UclValue::Float(10.0)
+    );
+}
+
+#[test]
+fn test_priority_decides_between_overrides() {
+    let build = |retina_priority: Option<u32>| {
+        let mut registry = PluginRegistry::new();
+        let mut config = PluginConfig::new();
+        config.set_priority_override(retina_priority);
+        registry.set_plugin_config("retina".to_string(), config);
+        registry.allow_override("css-units");
+        registry.allow_override("retina");
+        registry
+            .register_plugin(Box::new(CssUnitsPlugin::new()))
+            .unwrap();
+        registry.register_plugin(Box::new(RetinaPlugin)).unwrap();
+        registry
+    };
+
+    let mut registry = build(Some(200));
+    registry.initialize().unwrap();
+    assert_eq!(registry.suffix_owner("px"), Some("retina"));
+    assert_eq!(registry.suffix_owner("em"), Some("css-units"));
+
+    let mut registry = build(None);
+    registry.initialize().unwrap();
+    assert_eq!(registry.suffix_owner("px"), Some("css-units"));
+
+    // Equal priorities give no precedence
+    let mut registry = build(Some(100));
+    assert!(matches!(
+        registry.initialize(),
+        Err(ParseError::PluginConflict { .. })
+    ));
+}
+
+#[test]
+fn test_disabled_plugins_claim_nothing() {
+    let mut registry = PluginRegistry::new();
+    let mut config = PluginConfig::new();
+    config.set_enabled(false);
+    registry.set_plugin_config("retina".to_string(), config);
+    let parser = UclParserBuilder::new("width = 10px")
+        .with_plugin_registry(registry)
+        .with_plugin(Box::new(CssUnitsPlugin::new()))
+        .unwrap()
+        .with_plugin(Box::new(RetinaPlugin))
+        .unwrap();
+    assert_eq!(width(parser), UclValue::Float(10.0));
+}
+
+#[test]
+fn test_same_path_is_a_conflict() {
+    let plugin = |name, path, prefix| Box::new(PrefixPlugin { name, path, prefix });
+    let input = "server { root = \"www\" }\nlogs = \"log\"";
+    let builder = || {
+        UclParserBuilder::new(input)
+            .with_plugin(plugin("static", "server.root", "/srv/"))
+            .unwrap()
+            .with_plugin(plugin("home", "server.root", "~/"))
+            .unwrap()
+    };
+
+    let error = builder().build().err().unwrap();
+    assert!(matches!(
+        &error,
+        ParseError::PluginConflict { detail, .. } if detail == "both rewrite strings at 'server.root'"
+    ));
+
+    let document = builder()
+        .allow_override("home")
+        .build()
+        .unwrap()
+        .parse_document()
+        .unwrap();
+    let root = document.get_path("server.root").unwrap();
+    assert_eq!(root.as_str(), Some("~/www"));
+
+    // Different paths do not conflict
+    let document = UclParserBuilder::new(input)
+        .with_plugin(plugin("static", "server.root", "/srv/"))
+        .unwrap()
+        .with_plugin(plugin("logs", "logs", "/var/"))
+        .unwrap()
+        .build()
+        .unwrap()
+        .parse_document()
+        .unwrap();
+    let root = document.get_path("server.root").unwrap();
+    assert_eq!(root.as_str(), Some("/srv/www"));
+    assert_eq!(
+        document.get_path("logs").unwrap().as_str(),
+        Some("/var/log")
+    );
+}
@@ -0,0 +1,138 @@
+//! Tests for merge priorities and the `.priority` directive
+
+use ucl_lexer::{ParseError, ParserConfig, TrackedValue, UclParser, from_str};
+
+fn parse_tracked(source_name: &str, input: &str) -> TrackedValue {
+    UclParser::new(input)
+        .with_config(ParserConfig::new().with_track_provenance(true))
+        .with_source_name(source_name)
+        .parse_tracked()
+        .unwrap()
+}
+
+fn base() -> TrackedValue {
+    parse_tracked(
+        "base.ucl",
+        ".priority 5;\nport = 80\nserver { host = \"base\" }\n",
+    )
+}
+
+fn local() -> TrackedValue {
+    parse_tracked(
+        "local.ucl",
+        ".priority 1\nport = 8080\nserver { host = \"local\", workers = 4 }\nextra = true\n",
+    )
+}
+
+#[test]
+fn test_directive_sets_document_priority() {
+    let mut parser = UclParser::new("a = 1\n.priority 7;\nb = 2\n");
+    let value = parser.parse_document().unwrap();
+
+    assert_eq!(parser.priority(), Some(7));
+    assert!(value.get_path(".priority").is_none());
+    assert_eq!(value["b"], 2);
+
+    let tracked = base();
+    assert_eq!(tracked.priority(""), 5);
+    assert_eq!(tracked.priority("server.host"), 5);
+    assert_eq!(parse_tracked("x", "a = 1").priority("a"), 0);
+}
+
+#[test]
+fn test_priority_ignored_by_a_plain_parse_is_reported() {
+    let mut parser = UclParser::new("a = 1\n.priority 7;\n");
+    parser.parse_document().unwrap();
+    let diagnostics = parser.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    let message = &diagnostics[0].message;
+    assert!(message.contains("'.priority 7' has no effect"), "{message}");
+    assert_eq!(
+        (diagnostics[0].position.line, diagnostics[0].position.column),
+        (2, 1)
+    );
+
+    let mut parser = UclParser::new("a = 1\n.priority 7;\n");
+    parser.parse_tracked().unwrap();
+    assert!(parser.diagnostics().is_empty());
+}
+
+#[test]
+fn test_higher_priority_wins_when_merged_first() {
+    let mut merged = base();
+    merged.merge_from(local());
+
+    assert_eq!(merged.value["port"], 80);
+    assert_eq!(merged.value["server"]["host"], "base");
+    assert_eq!(merged.value["server"]["workers"], 4);
+    assert_eq!(merged.value["extra"], true);
+    assert_eq!(merged.origin("port").unwrap().source_name, "base.ucl");
+    assert!(merged.provenance.overridden("port").is_empty());
+    assert_eq!(merged.origin("extra").unwrap().source_name, "local.ucl");
+}
+
+#[test]
+fn test_higher_priority_wins_when_merged_last() {
+    let mut merged = local();
+    merged.merge_from(base());
+
+    assert_eq!(merged.value["port"], 80);
+    assert_eq!(merged.value["server"]["host"], "base");
+    assert_eq!(merged.value["server"]["workers"], 4);
+    assert_eq!(merged.origin("port").unwrap().source_name, "base.ucl");
+    assert_eq!(
+        merged.provenance.overridden("port")[0].source_name,
+        "local.ucl"
+    );
+}
+
+#[test]
+fn test_priorities_survive_merges() {
+    let mut merged = base();
+    merged.merge_from(local());
+    assert_eq!(merged.priority("port"), 5);
+    assert_eq!(merged.priority("extra"), 1);
+    assert_eq!(merged.priority("server.workers"), 1);
+
+    let third = parse_tracked("third.ucl", ".priority 3\nport = 9090\nextra = false\n");
+    merged.merge_from(third);
+    assert_eq!(merged.value["port"], 80);
+    assert_eq!(merged.value["extra"], false);
+    assert_eq!(merged.priority("extra"), 3);
+}
+
+#[test]
+fn test_equal_priorities_keep_merge_order() {
+    let mut merged = parse_tracked("a.ucl", "port = 1\n");
+    merged.merge_from(parse_tracked("b.ucl", "port = 2\n"));
+    assert_eq!(merged.value["port"], 2);
+}
+
+#[test]
+fn test_set_priority_on_a_path() {
+    let mut merged = local();
+    merged.set_priority("server.host", 9);
+    merged.merge_from(base());
+
+    assert_eq!(merged.value["server"]["host"], "local");
+    assert_eq!(merged.value["port"], 80);
+    assert_eq!(merged.priority("server"), 1);
+    assert_eq!(merged.priority("server.host"), 9);
+}
+
+#[test]
+fn test_priority_is_not_part_of_the_value() {
+    let json: serde_json::Value = from_str(".priority 5;\nport = 80\n").unwrap();
+    assert_eq!(json, serde_json::json!({ "port": 80 }));
+}
+
+#[test]
+fn test_invalid_priority() {
+    for input in [".priority 256", ".priority high", ".priority -1"] {
+        let err = UclParser::new(input).parse_document().unwrap_err();
+        assert!(
+            matches!(err, ParseError::InvalidObject { ref message, .. } if message.contains("between 0 and 255")),
+            "{input}: {err:?}"
+        );
+    }
+}
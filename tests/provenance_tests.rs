@@ -0,0 +1,147 @@
+//! Tests for source provenance tracking and layered merges
+
+use ucl_lexer::{ParserConfig, SourceMap, TrackedValue, UclParser, UclValue, ucl};
+
+fn parse_tracked(source_name: &str, input: &str) -> TrackedValue {
+    UclParser::new(input)
+        .with_config(ParserConfig::new().with_track_provenance(true))
+        .with_source_name(source_name)
+        .parse_tracked()
+        .unwrap()
+}
+
+#[test]
+fn test_origins_are_recorded_per_entry() {
+    let tracked = parse_tracked(
+        "site.ucl",
+        "name = \"api\"\nserver {\n    port = 8080\n    hosts = [{ addr = \"a\" }]\n}\n",
+    );
+
+    let origin = tracked.origin("server.port").unwrap();
+    assert_eq!(origin.source_name, "site.ucl");
+    assert_eq!((origin.span.start.line, origin.span.start.column), (3, 5));
+    assert_eq!(origin.span.end.column, 9);
+    assert_eq!(origin.to_string(), "site.ucl:3");
+
+    assert_eq!(tracked.origin("name").unwrap().span.start.line, 1);
+    assert_eq!(tracked.origin("server").unwrap().span.start.line, 2);
+    assert_eq!(
+        tracked
+            .origin("server.hosts.0.addr")
+            .unwrap()
+            .span
+            .start
+            .line,
+        4
+    );
+    assert!(tracked.origin("missing").is_none());
+}
+
+#[test]
+fn test_provenance_is_off_by_default() {
+    let tracked = UclParser::new("a = 1").parse_tracked().unwrap();
+    assert_eq!(tracked.value, ucl!({ "a": 1 }));
+    assert!(tracked.provenance.is_empty());
+}
+
+#[test]
+fn test_merge_keeps_origin_of_winning_value() {
+    let mut config = parse_tracked(
+        "defaults.ucl",
+        "server {\n  port = 80\n  host = \"localhost\"\n}\nlog = \"info\"",
+    );
+    config.merge_from(parse_tracked("site.ucl", "\n\nserver { port = 8080 }"));
+    config.merge_from(parse_tracked("local.ucl", "server {\n  port = 9090\n}"));
+
+    assert_eq!(config.value["server"]["port"], 9090);
+    assert_eq!(config.value["server"]["host"], "localhost");
+    assert_eq!(
+        config.origin("server.port").unwrap().source_name,
+        "local.ucl"
+    );
+    assert_eq!(
+        config.origin("server.host").unwrap().source_name,
+        "defaults.ucl"
+    );
+    assert_eq!(config.origin("server").unwrap().source_name, "defaults.ucl");
+
+    assert_eq!(
+        config.explain("server.port"),
+        "server.port = 9090 (from local.ucl:2, overriding site.ucl:3, defaults.ucl:2)"
+    );
+    assert_eq!(
+        config.explain("log"),
+        "log = \"info\" (from defaults.ucl:5)"
+    );
+    assert_eq!(config.explain("server.tls"), "server.tls is not defined");
+}
+
+#[test]
+fn test_merge_drops_origins_of_replaced_objects() {
+    let mut config = parse_tracked("a.ucl", "db { user = \"root\" }");
+    config.merge_from(parse_tracked("b.ucl", "db = \"sqlite://memory\""));
+
+    assert_eq!(config.value["db"], "sqlite://memory");
+    assert_eq!(config.origin("db").unwrap().source_name, "b.ucl");
+    assert!(config.origin("db.user").is_none());
+}
+
+#[test]
+fn test_duplicate_key_override_records_history() {
+    let tracked = parse_tracked("app.ucl", "port = 1\nport = 2");
+    assert_eq!(tracked.value["port"], ucl!([1, 2]));
+    assert_eq!(tracked.origin("port").unwrap().span.start.line, 1);
+
+    let tracked = UclParser::new("port = 1\nport = 2")
+        .with_config(
+            ParserConfig::new()
+                .with_track_provenance(true)
+                .with_duplicate_key_behavior(ucl_lexer::DuplicateKeyBehavior::Override),
+        )
+        .with_source_name("app.ucl")
+        .parse_tracked()
+        .unwrap();
+    assert_eq!(
+        tracked.explain("port"),
+        "port = 2 (from app.ucl:2, overriding app.ucl:1)"
+    );
+}
+
+#[test]
+fn test_index_and_quoted_key_paths() {
+    let input = "servers = [\n    { port = 80 },\n    { port = 443 },\n]\nhosts {\n    \"example.com\" = \"10.0.0.1\"\n}\n";
+    let tracked = parse_tracked("site.ucl", input);
+    let source_map = SourceMap::from(tracked.provenance.clone());
+
+    let span = source_map.span_for_path("servers[1].port").unwrap();
+    assert_eq!((span.start.line, span.start.column), (3, 7));
+    assert_eq!(source_map.span_for_path("servers.1.port"), Some(span));
+    let span = source_map.span_for_path("hosts.\"example.com\"").unwrap();
+    assert_eq!(span.slice(input), "\"example.com\"");
+    assert_eq!(source_map.span_for_path("hosts.example.com"), None);
+    assert_eq!(source_map.span_for_path("servers[1"), None);
+
+    assert_eq!(
+        tracked.explain("servers[1].port"),
+        "servers[1].port = 443 (from site.ucl:3)"
+    );
+    assert_eq!(
+        tracked.explain("hosts.\"example.com\""),
+        "hosts.\"example.com\" = \"10.0.0.1\" (from site.ucl:6)"
+    );
+    assert_eq!(
+        tracked.explain("servers[2].port"),
+        "servers[2].port is not defined"
+    );
+}
+
+#[test]
+fn test_value_merge_and_path_lookup() {
+    let mut value = ucl!({ "a": { "b": 1, "c": [10, 20] } });
+    value.merge_from(ucl!({ "a": { "b": 2, "d": true } }));
+
+    assert_eq!(value, ucl!({ "a": { "b": 2, "c": [10, 20], "d": true } }));
+    assert_eq!(value.get_path("a.c.1"), Some(&UclValue::Integer(20)));
+    assert!(value.get_path("a.c.5").is_none());
+    assert!(value.get_path("a.b.x").is_none());
+}
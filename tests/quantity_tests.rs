@@ -0,0 +1,145 @@
+//! Tests for `QuantityPlugin` unit families and number suffix handling
+
+use ucl_lexer::{
+    BuiltinSuffixes, NumberSuffixHandler, ParseError, QuantityOutput, QuantityPlugin,
+    UclParserBuilder, UclValue, UnitFamily, ucl,
+};
+
+fn parse_with(plugin: QuantityPlugin, input: &str) -> Result<UclValue, ParseError> {
+    UclParserBuilder::new(input)
+        .with_plugin(Box::new(plugin))?
+        .build()?
+        .parse_document()
+}
+
+fn css_plugin() -> QuantityPlugin {
+    QuantityPlugin::new()
+        .with_family(UnitFamily::length(), BuiltinSuffixes::Defer)
+        .with_family(UnitFamily::frequency(), BuiltinSuffixes::Defer)
+        .with_family(UnitFamily::percentage(), BuiltinSuffixes::Defer)
+}
+
+fn distance() -> UnitFamily {
+    UnitFamily::new("distance")
+        .with_unit("m", 1.0)
+        .with_unit("km", 1000.0)
+        .with_unit("cm", 0.01)
+}
+
+#[test]
+fn test_normalized_quantities() {
+    let input = "width = 12px\nmargin = 2em\nsize = 1.5rem\nclock = 2.4ghz\nopacity = 50%\n";
+    let value = parse_with(css_plugin(), input).unwrap();
+
+    assert_eq!(value["width"], 12.0);
+    assert_eq!(value["margin"], 32.0);
+    assert_eq!(value["size"], 24.0);
+    assert_eq!(value["clock"], 2.4e9);
+    assert_eq!(value["opacity"], 0.5);
+}
+
+#[test]
+fn test_quantities_in_arrays_and_implicit_syntax() {
+    let value = parse_with(css_plugin(), "padding = [4px, 8px]\nband 5khz\n").unwrap();
+
+    assert_eq!(value["padding"], ucl!([4.0, 8.0]));
+    assert_eq!(value["band"], 5000.0);
+}
+
+#[test]
+fn test_structured_quantities() {
+    let plugin = css_plugin().with_output(QuantityOutput::Structured);
+    let value = parse_with(plugin, "margin = 2em").unwrap();
+
+    assert_eq!(value["margin"], ucl!({ "value": 2.0, "unit": "em" }));
+}
+
+#[test]
+fn test_unregistered_suffix_stays_a_string() {
+    let value = parse_with(css_plugin(), "version = 10abc").unwrap();
+    assert_eq!(value["version"], "10abc");
+}
+
+#[test]
+fn test_unknown_unit_for_bound_key() {
+    let plugin = css_plugin().with_key_family("width", "length");
+    let err = parse_with(plugin, "name = \"box\"\nwidth = 12hz").unwrap_err();
+
+    match &err {
+        ParseError::UnknownUnit {
+            unit,
+            family,
+            valid_units,
+            position,
+        } => {
+            assert_eq!(unit, "hz");
+            assert_eq!(family, "length");
+            assert_eq!(valid_units, &["px", "pt", "em", "rem"]);
+            assert_eq!((position.line, position.column), (2, 9));
+        }
+        other => panic!("expected unknown unit error, got {other:?}"),
+    }
+    assert!(err.to_string().contains("expected one of: px, pt, em, rem"));
+
+    let plugin = css_plugin().with_key_family("width", "length");
+    let err = parse_with(plugin, "width = 12parsecs").unwrap_err();
+    assert!(matches!(err, ParseError::UnknownUnit { ref unit, .. } if unit == "parsecs"));
+}
+
+#[test]
+fn test_deferred_family_keeps_builtin_suffixes() {
+    let plugin = QuantityPlugin::new().with_family(distance(), BuiltinSuffixes::Defer);
+    let value = parse_with(plugin.clone(), "a = 10M\nb = 10min\nc = 3km\nd = 5cm\n").unwrap();
+
+    // `M` is the built-in mega suffix and `min` is minutes
+    assert_eq!(value["a"], 10_000_000);
+    assert_eq!(value["b"], UclValue::Time(600.0));
+    assert_eq!(value["c"], 3000.0);
+    assert_eq!(value["d"], 0.05);
+    // and a bare `m` is left to the lexer, which asks for `min` or `mb`
+    let err = parse_with(plugin, "a = 10m").unwrap_err();
+    assert!(
+        err.to_string().contains("Ambiguous suffix 'm' in '10m'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_shadowing_family_takes_over_builtin_suffixes() {
+    let plugin = QuantityPlugin::new().with_family(distance(), BuiltinSuffixes::Shadow);
+    let value = parse_with(plugin, "a = 10m\nb = 1.5m\nc = 10min\nd = 2mb\n").unwrap();
+
+    assert_eq!(value["a"], 10.0);
+    assert_eq!(value["b"], 1.5);
+    // Built-in suffixes that the family does not define still apply
+    assert_eq!(value["c"], UclValue::Time(600.0));
+    assert_eq!(value["d"], 2 * 1024 * 1024);
+}
+
+#[test]
+fn test_shadowing_applies_to_shared_parsers() {
+    let plugin = QuantityPlugin::new().with_family(distance(), BuiltinSuffixes::Shadow);
+    let shared = UclParserBuilder::new("")
+        .with_plugin(Box::new(plugin))
+        .unwrap()
+        .build_shared()
+        .unwrap();
+
+    assert_eq!(shared.parse("a = 10m").unwrap()["a"], 10.0);
+    assert_eq!(shared.parse("a = 4km").unwrap()["a"], 4000.0);
+}
+
+#[test]
+fn test_reregistering_family_replaces_it() {
+    let plugin = QuantityPlugin::new()
+        .with_family(UnitFamily::length(), BuiltinSuffixes::Defer)
+        .with_family(
+            UnitFamily::new("length").with_unit("px", 2.0),
+            BuiltinSuffixes::Defer,
+        );
+
+    assert_eq!(plugin.handler().parse_suffix("px"), Some(2.0));
+    let value = parse_with(plugin, "a = 3px\nb = 1em").unwrap();
+    assert_eq!(value["a"], 6.0);
+    assert_eq!(value["b"], "1em");
+}
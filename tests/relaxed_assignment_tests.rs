@@ -0,0 +1,84 @@
+//! Tests for scalar values that follow a key without ':' or '='
+
+use ucl_lexer::{ParseError, ParserConfig, UclParser, UclValue, ucl};
+
+fn parse_strict(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input)
+        .with_config(ParserConfig::strict())
+        .parse_document()
+}
+
+#[test]
+fn test_terminators() {
+    let value = UclParser::new("port 8080;\nname \"app\", debug true\nratio 0.5\n")
+        .parse_document()
+        .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "port": 8080, "name": "app", "debug": true, "ratio": 0.5 })
+    );
+}
+
+#[test]
+fn test_value_terminated_by_closing_brace() {
+    let value = UclParser::new("server { host localhost }\nlimits { a 1; b \"two\" }")
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["server"]["host"], "localhost");
+    assert_eq!(value["limits"], ucl!({ "a": 1, "b": "two" }));
+}
+
+#[test]
+fn test_brace_after_value_is_a_section_label() {
+    let value = UclParser::new("upstream backend { port 80 }\nlocation api { limit 5 }\n")
+        .parse_document()
+        .unwrap();
+    assert_eq!(value["upstream"]["backend"]["port"], 80);
+    assert_eq!(value["location"]["api"]["limit"], 5);
+}
+
+#[test]
+fn test_array_after_key() {
+    let value = UclParser::new("levels [debug, \"info\"]\nserver { tags [\"a\", \"b\"] }\nport 80")
+        .parse_document()
+        .unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "levels": ["debug", "info"], "server": { "tags": ["a", "b"] }, "port": 80 })
+    );
+
+    let err = parse_strict("levels [debug]").unwrap_err();
+    assert!(
+        matches!(err, ParseError::UnexpectedToken { found: "'['", .. }),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn test_strict_mode_requires_separator() {
+    let err = parse_strict("name = \"app\"\nport 8080;\n").unwrap_err();
+    match err {
+        ParseError::UnexpectedToken {
+            found,
+            expected,
+            position,
+        } => {
+            assert_eq!(found, "integer");
+            assert_eq!(expected, ["'='", "':'", "'{'"]);
+            assert_eq!((position.line, position.column), (2, 6));
+        }
+        other => panic!("expected unexpected token error, got {other:?}"),
+    }
+
+    assert!(parse_strict("server { host localhost }").is_err());
+}
+
+#[test]
+fn test_strict_mode_keeps_sections() {
+    let value =
+        parse_strict("a = 1\nb: 2\nserver { port = 80 }\nupstream main { port = 81 }\n").unwrap();
+    assert_eq!(value["a"], 1);
+    assert_eq!(value["b"], 2);
+    assert_eq!(value["server"]["port"], 80);
+    assert_eq!(value["upstream"]["main"]["port"], 81);
+}
@@ -0,0 +1,75 @@
+//! Tests for parsing with `LexerConfig::save_comments` enabled
+
+use ucl_lexer::{LexerConfig, UclParser, UclValue};
+
+const DOCUMENT: &str = r#"# Service configuration
+name = "api" # trailing
+port /* before the value */ = 8080; // after the separator
+hosts = [ # opening
+    "a.example.com", # first
+    "b.example.com" /* second */ ,
+    /* before the last */ "c.example.com"
+] # closing
+server # before the brace
+{
+    listen = 80 // nested
+    tls { enabled = true } # after a nested object
+}
+upstream backend /* between the name and the brace */ { weight = 5 }
+motd = "Welcome, " + # between the parts
+    "visitor"
+banner = <<EOD
+# not a comment
+EOD
+# before the last key
+timeout 30s # relaxed assignment
+"#;
+
+fn saving() -> LexerConfig {
+    LexerConfig::new().with_save_comments(true)
+}
+
+fn parse(input: &str, config: LexerConfig) -> UclValue {
+    UclParser::with_lexer_config(input, config)
+        .parse_document()
+        .unwrap()
+}
+
+#[test]
+fn test_saved_comments_do_not_change_the_value() {
+    let value = parse(DOCUMENT, saving());
+    assert_eq!(value, parse(DOCUMENT, LexerConfig::new()));
+    assert_eq!(value["hosts"][2], "c.example.com");
+    assert_eq!(value["banner"], "# not a comment\n");
+
+    // The comments are still collected by the lexer
+    let mut parser = UclParser::with_lexer_config(DOCUMENT, saving());
+    parser.parse_document().unwrap();
+    let comments = parser.comments();
+    assert_eq!(comments.len(), 16);
+    assert_eq!(comments[0].text, " Service configuration");
+}
+
+#[test]
+fn test_comments_before_explicit_roots() {
+    for (input, expected) in [
+        ("# c\n{ a = 1 }", "{ a = 1 }"),
+        ("/* c */ [1, 2] // d", "[1, 2]"),
+        ("// c\n42 # d", "42"),
+    ] {
+        assert_eq!(
+            parse(input, saving()),
+            parse(expected, LexerConfig::new()),
+            "{input}"
+        );
+    }
+
+    let value = UclParser::with_lexer_config("# c\n{ a = 1 }", saving())
+        .parse_object()
+        .unwrap();
+    assert_eq!(value["a"], 1);
+    let value = UclParser::with_lexer_config("/* c */ [1 # d\n]", saving())
+        .parse_array()
+        .unwrap();
+    assert_eq!(value[0], 1);
+}
@@ -0,0 +1,167 @@
+//! Tests for reading single scalars outside a document
+
+use ucl_lexer::LexError;
+use ucl_lexer::UclValue;
+use ucl_lexer::scalar::{parse_bool, parse_duration_secs, parse_scalar, parse_size};
+
+/// Returns the text and column of a trailing-input error
+fn trailing(error: LexError) -> (String, usize) {
+    match error {
+        LexError::TrailingInput { text, position } => (text, position.column),
+        other => panic!("unexpected error {other}"),
+    }
+}
+
+#[test]
+fn test_size_suffixes() {
+    let cases = [
+        ("512", 512),
+        ("1k", 1000),
+        ("1kb", 1024),
+        ("64kb", 64 * 1024),
+        ("5m", 5_000_000),
+        ("5mb", 5 * 1024 * 1024),
+        ("2g", 2_000_000_000),
+        ("2gb", 2 * 1024 * 1024 * 1024),
+        ("  8kb\n", 8 * 1024),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse_size(input).unwrap(), expected, "{input:?}");
+    }
+}
+
+#[test]
+fn test_size_rejects_other_values() {
+    assert!(matches!(
+        parse_size("-1kb"),
+        Err(LexError::InvalidNumber { ref message, .. }) if message == "a size cannot be negative"
+    ));
+    for input in ["30s", "yes", "\"10kb\"", "1.5"] {
+        assert!(
+            matches!(parse_size(input), Err(LexError::InvalidScalar { .. })),
+            "{input:?}"
+        );
+    }
+    assert_eq!(
+        parse_size("1.5").unwrap_err().to_string(),
+        "Expected a size at 1:1, found '1.5'"
+    );
+}
+
+#[test]
+fn test_duration_suffixes() {
+    let cases = [
+        ("30", 30.0),
+        ("2.5", 2.5),
+        ("30s", 30.0),
+        ("500ms", 0.5),
+        ("5min", 300.0),
+        ("5m", 300.0),
+        ("1.5h", 5400.0),
+        ("1d", 86400.0),
+        ("1w", 604800.0),
+        ("1h30m", 5400.0),
+        ("1h30min", 5400.0),
+        ("1h30min15s", 5415.0),
+        ("-1h30min", -5400.0),
+        (" 10s ", 10.0),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse_duration_secs(input).unwrap(), expected, "{input:?}");
+    }
+}
+
+#[test]
+fn test_booleans() {
+    for input in ["true", "yes", "on", "TRUE", "Yes", "ON"] {
+        assert!(parse_bool(input).unwrap(), "{input:?}");
+    }
+    for input in ["false", "no", "off", "False", "NO", "Off"] {
+        assert!(!parse_bool(input).unwrap(), "{input:?}");
+    }
+    assert_eq!(
+        parse_bool("maybe").unwrap_err().to_string(),
+        "Expected a boolean at 1:1, found 'maybe'"
+    );
+    assert!(matches!(
+        parse_bool("1"),
+        Err(LexError::InvalidScalar { .. })
+    ));
+}
+
+#[test]
+fn test_scalars_read_as_in_a_document() {
+    let cases = [
+        ("42", UclValue::Integer(42)),
+        ("0x10", UclValue::Integer(16)),
+        ("1kb", UclValue::Integer(1024)),
+        ("2.5", UclValue::Float(2.5)),
+        ("on", UclValue::Boolean(true)),
+        ("false", UclValue::Boolean(false)),
+        ("null", UclValue::Null),
+        ("30s", UclValue::String("30s".to_string())),
+        ("localhost", UclValue::String("localhost".to_string())),
+        ("\"two words\"", UclValue::String("two words".to_string())),
+        ("'${HOME}'", UclValue::String("${HOME}".to_string())),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(parse_scalar(input).unwrap(), expected, "{input:?}");
+    }
+    assert!(matches!(
+        parse_scalar("{"),
+        Err(LexError::InvalidScalar { .. })
+    ));
+}
+
+#[test]
+fn test_trailing_input_is_rejected() {
+    assert_eq!(
+        trailing(parse_size("10kb extra").unwrap_err()),
+        ("extra".to_string(), 6)
+    );
+    assert_eq!(
+        trailing(parse_size("10kbx").unwrap_err()),
+        ("kbx".to_string(), 3)
+    );
+    assert_eq!(
+        trailing(parse_size("1 2").unwrap_err()),
+        ("2".to_string(), 3)
+    );
+    assert_eq!(
+        trailing(parse_duration_secs("1h 30min").unwrap_err()),
+        ("30min".to_string(), 4)
+    );
+    assert_eq!(
+        trailing(parse_duration_secs("30 s").unwrap_err()),
+        ("s".to_string(), 4)
+    );
+    assert_eq!(
+        trailing(parse_bool("yes please").unwrap_err()),
+        ("please".to_string(), 5)
+    );
+    assert_eq!(
+        trailing(parse_scalar("a = 1").unwrap_err()),
+        ("=".to_string(), 3)
+    );
+}
+
+#[test]
+fn test_empty_input_is_rejected() {
+    for input in ["", "   ", "\n"] {
+        let error = parse_size(input).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                LexError::InvalidScalar {
+                    expected: "a value",
+                    ..
+                }
+            ),
+            "{input:?}"
+        );
+    }
+    assert_eq!(
+        parse_bool("").unwrap_err().to_string(),
+        "Expected a value at 1:1, found the end of input"
+    );
+}
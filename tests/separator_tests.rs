@@ -0,0 +1,199 @@
+//! Tests for runs of `,` and `;` separators in documents, objects and arrays
+//!
+//! Each context is checked against the same separator sequences, so the grid
+//! below records which runs are accepted where:
+//!
+//! | sequence         | document | object | array            |
+//! |------------------|----------|--------|------------------|
+//! | leading `,`/`;`  | error    | error  | error            |
+//! | `;`, `;;`, `;;;` | ok       | ok     | `;;` is an error |
+//! | `,`              | ok       | ok     | ok               |
+//! | `,,`             | error    | error  | error            |
+//! | `,;` and `;,`    | ok       | ok     | error            |
+//! | trailing run     | ok       | ok     | single only      |
+
+use ucl_lexer::{ParseError, UclError, UclParser, UclValue, ucl};
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input).parse_document()
+}
+
+/// Builds a document, an object and an array with `run` between two entries
+fn contexts(run: &str) -> [(&'static str, String); 3] {
+    [
+        ("document", format!("a = 1{run} b = 2")),
+        ("object", format!("o {{ a = 1{run} b = 2 }}")),
+        ("array", format!("a = [1{run} 2]")),
+    ]
+}
+
+#[test]
+fn test_separator_grid() {
+    // (run, accepted in document, object, array)
+    let grid: &[(&str, [bool; 3])] = &[
+        ("", [true, true, false]),
+        (";", [true, true, true]),
+        (",", [true, true, true]),
+        (";;", [true, true, false]),
+        (";;;", [true, true, false]),
+        (",,", [false, false, false]),
+        (",;", [true, true, false]),
+        (";,", [true, true, false]),
+        ("; ;", [true, true, false]),
+        (";\n;", [true, true, false]),
+        ("; /* c */ ;", [true, true, false]),
+        (", ; ;", [true, true, false]),
+        ("; , ,", [false, false, false]),
+    ];
+
+    for (run, accepted) in grid {
+        for ((name, input), accepted) in contexts(run).iter().zip(accepted) {
+            let result = parse(input);
+            assert_eq!(result.is_ok(), *accepted, "{name} {input:?}: {result:?}");
+        }
+    }
+}
+
+#[test]
+fn test_accepted_runs_are_ignored() {
+    for run in [";", ",", ";;", ",;", ";,", ";\n;"] {
+        let [document, object, array] = contexts(run).map(|(_, input)| parse(&input));
+        assert_eq!(document.unwrap(), ucl!({ "a": 1, "b": 2 }), "{run:?}");
+        assert_eq!(
+            object.unwrap(),
+            ucl!({ "o": { "a": 1, "b": 2 } }),
+            "{run:?}"
+        );
+        if run.len() == 1 {
+            assert_eq!(array.unwrap(), ucl!({ "a": [1, 2] }), "{run:?}");
+        }
+    }
+}
+
+#[test]
+fn test_trailing_runs() {
+    let accepted = [
+        ("key = 1;;", ucl!({ "key": 1 })),
+        ("key = 1 ,", ucl!({ "key": 1 })),
+        ("key = 1,;", ucl!({ "key": 1 })),
+        ("o { a = 1 };;", ucl!({ "o": { "a": 1 } })),
+        ("o { a = 1 },", ucl!({ "o": { "a": 1 } })),
+        ("a = {};;", ucl!({ "a": {} })),
+        ("o { a = 1;; }", ucl!({ "o": { "a": 1 } })),
+        ("a = [1, 2];", ucl!({ "a": [1, 2] })),
+        ("a = [1, 2,]", ucl!({ "a": [1, 2] })),
+        ("a = [1; 2;]", ucl!({ "a": [1, 2] })),
+        ("{ a = 1 };", ucl!({ "a": 1 })),
+        ("[1, 2];;", ucl!([1, 2])),
+    ];
+    for (input, expected) in accepted {
+        assert_eq!(parse(input).unwrap(), expected, "{input:?}");
+    }
+
+    assert!(matches!(
+        parse("key = 1,,").unwrap_err(),
+        ParseError::RepeatedSeparator { .. }
+    ));
+    assert!(matches!(
+        parse("a = [1, 2,,]").unwrap_err(),
+        ParseError::MissingArrayElement { index: 2, .. }
+    ));
+}
+
+#[test]
+fn test_leading_separator_points_at_it() {
+    let cases = [
+        (";", ';', "document", 1),
+        (", a = 1", ',', "document", 1),
+        ("  ; a = 1", ';', "document", 3),
+        ("{ , a = 1 }", ',', "object", 3),
+        ("o { ; a = 1 }", ';', "object", 5),
+        ("o {\n  ,\n  a = 1\n}", ',', "object", 3),
+        ("a = [ , 1 ]", ',', "array", 7),
+        ("a = [; 1]", ';', "array", 6),
+        ("a = [ /* none */ , ]", ',', "array", 18),
+    ];
+    for (input, expected_separator, expected_container, column) in cases {
+        match parse(input).unwrap_err() {
+            ParseError::LeadingSeparator {
+                separator,
+                container,
+                position,
+            } => {
+                assert_eq!(separator, expected_separator, "{input:?}");
+                assert_eq!(container, expected_container, "{input:?}");
+                assert_eq!(position.column, column, "{input:?}");
+            }
+            other => panic!("{input:?}: unexpected error {other}"),
+        }
+    }
+
+    assert_eq!(
+        parse("o { ; a = 1 }").unwrap_err().to_string(),
+        "Unexpected ';' at 1:5 at the start of the object; a separator can only come after a value"
+    );
+}
+
+#[test]
+fn test_missing_array_element_is_named() {
+    let err = parse("a = [1,,2]").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::MissingArrayElement { index: 1, separator: ',', position }
+            if position.column == 8
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Missing element 1 of the array before ',' at 1:8; remove the extra separator"
+    );
+
+    assert!(matches!(
+        parse("a = [\"x\", \"y\";\n;\n\"z\"]").unwrap_err(),
+        ParseError::MissingArrayElement { index: 2, separator: ';', position }
+            if position.line == 2 && position.column == 1
+    ));
+    assert!(matches!(
+        parse("a = [ [1],, [2] ]").unwrap_err(),
+        ParseError::MissingArrayElement { index: 1, .. }
+    ));
+}
+
+#[test]
+fn test_repeated_comma_in_object() {
+    let err = parse("o { a = 1,, b = 2 }").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::RepeatedSeparator { position } if position.column == 11
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Repeated ',' at 1:11; entries are separated by at most one ','"
+    );
+}
+
+#[test]
+fn test_lenient_parsing_skips_bad_separators() {
+    let (value, errors) = UclParser::parse_lenient("; a = 1,, b = 2\nc { , d = 3 }");
+    assert_eq!(value.unwrap(), ucl!({ "a": 1, "b": 2, "c": { "d": 3 } }));
+    assert_eq!(errors.len(), 3, "{errors:?}");
+    assert!(matches!(
+        errors[0],
+        UclError::Parse(ParseError::LeadingSeparator { .. })
+    ));
+    assert!(matches!(
+        errors[1],
+        UclError::Parse(ParseError::RepeatedSeparator { .. })
+    ));
+}
+
+#[test]
+fn test_skipped_sections_keep_separator_rules() {
+    let input = "a = 1;; b { x = 1 };\nc = 3";
+    let sections = UclParser::new(input).parse_document_keys(&["c"]).unwrap();
+    assert_eq!(sections["c"], 3);
+
+    assert!(matches!(
+        UclParser::new("a = 1,, c = 3").parse_document_keys(&["c"]),
+        Err(ParseError::RepeatedSeparator { .. })
+    ));
+}
@@ -0,0 +1,99 @@
+//! Tests for UCL text output and deterministic ordering
+//!
+//! Output must round-trip through the parser and be byte-identical for
+//! identical input.
+
+use std::collections::HashMap;
+use ucl_lexer::{
+    FormatConfig, TimeSuffixStyle, UclValue, to_ucl_string, to_ucl_string_with_config, ucl,
+};
+
+const DOCUMENT: &str = r#"
+zeta = 1
+alpha {
+    port = 8080
+    hosts = ["a", "b"]
+    timeout = 90s
+}
+"needs quoting" = "line\nbreak"
+mid = [{ x = 1.5 }, {}, []]
+"true" = null
+"#;
+
+#[test]
+fn test_output_format() {
+    let value: UclValue = "name = \"app\"\nlimits { cpu = 0.5; tags = [1, 2] }\nempty {}"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+        to_ucl_string(&value),
+        "name = \"app\";\nlimits {\n    cpu = 0.5;\n    tags = [\n        1,\n        2,\n    ];\n}\nempty {}\n"
+    );
+    assert_eq!(to_ucl_string(&ucl!([1, "a"])), "[\n    1,\n    \"a\",\n]\n");
+}
+
+#[test]
+fn test_output_round_trips() {
+    let value: UclValue = DOCUMENT.parse().unwrap();
+    let reparsed: UclValue = to_ucl_string(&value).parse().unwrap();
+    assert_eq!(reparsed, value);
+
+    let config = FormatConfig::new()
+        .with_indent(2)
+        .with_time_style(TimeSuffixStyle::ShortDecimal);
+    let reparsed: UclValue = to_ucl_string_with_config(&value, &config).parse().unwrap();
+    assert_eq!(reparsed, value);
+}
+
+#[test]
+fn test_sort_keys() {
+    let value = ucl!({ "b": 1, "a": { "z": true, "y": false } });
+    let config = FormatConfig::new().with_sort_keys(true);
+
+    assert_eq!(
+        to_ucl_string_with_config(&value, &config),
+        "a {\n    y = false;\n    z = true;\n}\nb = 1;\n"
+    );
+    assert!(to_ucl_string(&value).starts_with("b = 1;"));
+}
+
+#[test]
+fn test_canonicalize() {
+    let mut value = ucl!({ "b": [{ "d": -0.0, "c": f64::NAN }], "a": 1 });
+    value.canonicalize();
+
+    let keys: Vec<_> = value.as_object().unwrap().keys().cloned().collect();
+    assert_eq!(keys, ["a", "b"]);
+    let inner = value["b"][0].as_object().unwrap();
+    assert_eq!(inner.keys().collect::<Vec<_>>(), ["c", "d"]);
+    assert!(inner["d"].as_float().unwrap().is_sign_positive());
+}
+
+#[test]
+fn test_parse_and_emit_is_deterministic() {
+    let expected = to_ucl_string(&DOCUMENT.parse().unwrap());
+    let mut canonical: UclValue = DOCUMENT.parse().unwrap();
+    canonical.canonicalize();
+    let expected_canonical = to_ucl_string(&canonical);
+
+    for _ in 0..1000 {
+        let mut value: UclValue = DOCUMENT.parse().unwrap();
+        assert_eq!(to_ucl_string(&value), expected);
+        value.canonicalize();
+        assert_eq!(to_ucl_string(&value), expected_canonical);
+    }
+}
+
+#[test]
+fn test_hash_map_conversion_is_deterministic() {
+    let build = || {
+        let map: HashMap<String, i64> = (0..32).map(|i| (format!("key{}", i), i)).collect();
+        to_ucl_string(&UclValue::from(map))
+    };
+
+    let expected = build();
+    for _ in 0..100 {
+        assert_eq!(build(), expected);
+    }
+}
@@ -0,0 +1,77 @@
+//! Tests for parsing with a borrowed `VariableHandler`
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::thread;
+use ucl_lexer::{
+    MapVariableHandler, UclParser, UclValue, VariableHandler, from_str_with_variable_handler,
+    from_str_with_variables_ref,
+};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Service {
+    host: String,
+    port: u16,
+}
+
+fn handler() -> MapVariableHandler {
+    MapVariableHandler::from_map(HashMap::from([
+        ("HOST".to_string(), "example.com".to_string()),
+        ("SCHEME".to_string(), "https".to_string()),
+    ]))
+}
+
+#[test]
+fn test_one_handler_across_parallel_parses() {
+    let handler = handler();
+    let shared: &dyn VariableHandler = &handler;
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..4)
+            .map(|worker| {
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        let input = format!("host = \"{worker}-{i}.${{HOST}}\"\nport = {i}");
+                        let service: Service = from_str_with_variables_ref(&input, shared).unwrap();
+                        assert_eq!(service.host, format!("{worker}-{i}.example.com"));
+                        assert_eq!(service.port, i);
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    });
+
+    // The handler is still owned here and unchanged
+    assert_eq!(handler.variables().len(), 2);
+}
+
+#[test]
+fn test_parser_keeps_borrowed_handler_for_reparsing() {
+    let handler = handler();
+    let mut parser = UclParser::with_variable_handler_ref("", &handler);
+    let mut value = UclValue::Null;
+    for suffix in ["a", "b"] {
+        let input = format!("url = \"${{SCHEME}}://${{HOST}}/{suffix}\"");
+        parser.parse_into(&input, &mut value).unwrap();
+        assert_eq!(
+            value["url"],
+            format!("https://example.com/{suffix}").as_str()
+        );
+    }
+}
+
+#[test]
+fn test_handler_by_value() {
+    let service: Service =
+        from_str_with_variable_handler("host = \"${HOST}\", port = 1", handler()).unwrap();
+    assert_eq!(
+        service,
+        Service {
+            host: "example.com".to_string(),
+            port: 1,
+        }
+    );
+}
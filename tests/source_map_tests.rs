@@ -0,0 +1,94 @@
+//! Tests for resolving key paths to source locations after deserialization
+
+use serde::Deserialize;
+use ucl_lexer::{UclError, from_str_with_source_map};
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    name: String,
+    server: Server,
+    replicas: Vec<Server>,
+    tags: Vec<String>,
+}
+
+const CONFIG: &str = r#"name = "app"
+server {
+    host = "localhost"
+    port = 80
+}
+replicas = [
+    { host = "a", port = 8080 },
+    { host = "b", port = 443 },
+]
+tags = ["web", "edge"]
+"#;
+
+#[test]
+fn test_span_for_deserialized_field() {
+    let (config, source_map) = from_str_with_source_map::<Config>(CONFIG).unwrap();
+    assert_eq!(config.name, "app");
+    assert_eq!(config.server.port, 80);
+
+    let span = source_map.span_for_path("server.port").unwrap();
+    assert_eq!(span.slice(CONFIG), "port");
+    assert_eq!(source_map.line_col_for_path("server.port"), Some((4, 5)));
+    assert_eq!(source_map.line_col_for_path("name"), Some((1, 1)));
+}
+
+#[test]
+fn test_array_element_paths() {
+    let (config, source_map) = from_str_with_source_map::<Config>(CONFIG).unwrap();
+
+    let invalid: Vec<String> = config
+        .replicas
+        .iter()
+        .enumerate()
+        .filter(|(_, server)| server.port < 1024)
+        .map(|(index, server)| {
+            let (line, column) = source_map
+                .line_col_for_path(&format!("replicas.{}.port", index))
+                .unwrap();
+            format!(
+                "{}:{}: port {} of {} must be above 1024",
+                line, column, server.port, server.host
+            )
+        })
+        .collect();
+    assert_eq!(invalid, vec!["8:19: port 443 of b must be above 1024"]);
+
+    // Scalar elements have no key of their own, the closest one is their array's
+    assert_eq!(config.tags.len(), 2);
+    assert!(source_map.span_for_path("tags.1").is_none());
+    let span = source_map.closest_span_for_path("tags.1").unwrap();
+    assert_eq!((span.start.line, span.start.column), (10, 1));
+}
+
+#[test]
+fn test_unknown_path() {
+    let (_, source_map) = from_str_with_source_map::<Config>(CONFIG).unwrap();
+    assert!(source_map.span_for_path("missing").is_none());
+    assert!(source_map.line_col_for_path("missing.port").is_none());
+
+    // A missing key below an existing one is not given its parent's span
+    assert!(source_map.span_for_path("server.missing").is_none());
+    assert!(source_map.line_col_for_path("server.port.x").is_none());
+    assert_eq!(
+        source_map.closest_span_for_path("server.missing"),
+        source_map.span_for_path("server")
+    );
+}
+
+#[test]
+fn test_deserialization_errors_are_returned() {
+    let error = from_str_with_source_map::<Server>("host = \"a\"\nport = \"high\"\n").unwrap_err();
+    assert!(matches!(error, UclError::Serde(_)), "{error}");
+
+    let error = from_str_with_source_map::<Server>("host = ").unwrap_err();
+    assert!(error.to_string().contains("expected value"), "{error}");
+}
@@ -0,0 +1,53 @@
+//! Tests that the streaming lexer reads numbers as the in-memory lexer does
+
+use ucl_lexer::{LexError, LexerConfig, MSuffix, UclLexer, streaming_lexer_from_reader};
+
+#[test]
+fn test_numbers_match_the_in_memory_lexer() {
+    for input in [
+        "30s", "1.5h", "500ms", "2d", "512mb", "64kb", "2gb", "100M", "0x1F", "-0xff", "1_000",
+        "-2.5e3", "+7", "0", "inf", "-inf",
+    ] {
+        let expected = UclLexer::new(input).next_token().unwrap();
+        let token = streaming_lexer_from_reader(input.as_bytes())
+            .next_token()
+            .unwrap();
+        assert_eq!(token, expected, "{input}");
+    }
+}
+
+#[test]
+fn test_m_suffix_follows_the_configured_meaning() {
+    let mut lexer = streaming_lexer_from_reader("5m".as_bytes());
+    assert!(matches!(
+        lexer.next_token(),
+        Err(LexError::AmbiguousSuffix { position, .. }) if position.offset == 0
+    ));
+
+    let mut lexer =
+        streaming_lexer_from_reader("5m".as_bytes()).with_m_suffix_meaning(MSuffix::Minutes);
+    let expected = UclLexer::with_config(
+        "5m",
+        LexerConfig::new().with_m_suffix_meaning(MSuffix::Minutes),
+    )
+    .next_token()
+    .unwrap();
+    assert_eq!(lexer.next_token().unwrap(), expected);
+}
+
+#[test]
+fn test_invalid_numbers_point_at_their_start() {
+    let mut lexer = streaming_lexer_from_reader("a = 10abc".as_bytes());
+    lexer.next_token().unwrap();
+    lexer.next_token().unwrap();
+    assert!(matches!(
+        lexer.next_token(),
+        Err(LexError::InvalidNumber { position, .. }) if position.offset == 4
+    ));
+
+    let mut lexer = streaming_lexer_from_reader("1__0".as_bytes());
+    assert!(matches!(
+        lexer.next_token(),
+        Err(LexError::InvalidNumber { position, .. }) if position.offset == 0
+    ));
+}
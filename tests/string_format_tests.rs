@@ -0,0 +1,222 @@
+//! Tests for recording how string values were written
+
+use serde::Deserialize;
+use ucl_lexer::{
+    FormatConfig, ParserConfig, StringFormat, TrackedValue, UclParser, UclValue,
+    from_str_with_source_map, to_ucl_string_tracked, ucl,
+};
+
+const DOCUMENT: &str = "json = \"web\"
+single = 'web'
+heredoc = <<EOD
+web
+EOD
+bare = web
+flag = true
+quoted_flag = \"true\"
+port = 80
+server {
+    host = db.internal
+    paths = [/srv, '/var/www', \"/tmp\"]
+}
+";
+
+fn parse_tracked(input: &str) -> TrackedValue {
+    UclParser::new(input)
+        .with_config(ParserConfig::new().with_track_provenance(true))
+        .parse_tracked()
+        .unwrap()
+}
+
+/// Replaces the string of a top-level entry, keeping its recorded format
+fn set(tracked: &mut TrackedValue, key: &str, text: &str) {
+    let UclValue::Object(object) = &mut tracked.value else {
+        panic!("expected an object");
+    };
+    object.insert(key.into(), text.into());
+}
+
+#[test]
+fn test_all_formats_are_recorded() {
+    let tracked = parse_tracked(DOCUMENT);
+    assert_eq!(tracked.string_format("json"), Some(StringFormat::Json));
+    assert_eq!(tracked.string_format("single"), Some(StringFormat::Single));
+    assert_eq!(
+        tracked.string_format("heredoc"),
+        Some(StringFormat::Heredoc)
+    );
+    assert_eq!(tracked.string_format("bare"), Some(StringFormat::Unquoted));
+    assert_eq!(
+        tracked.string_format("quoted_flag"),
+        Some(StringFormat::Json)
+    );
+
+    // Nested entries and array elements have their own formats
+    assert_eq!(
+        tracked.string_format("server.host"),
+        Some(StringFormat::Unquoted)
+    );
+    assert_eq!(
+        tracked.string_format("server.paths.0"),
+        Some(StringFormat::Unquoted)
+    );
+    assert_eq!(
+        tracked.string_format("server.paths.1"),
+        Some(StringFormat::Single)
+    );
+    assert_eq!(
+        tracked.string_format("server.paths.2"),
+        Some(StringFormat::Json)
+    );
+
+    // Only strings have a format
+    for path in ["flag", "port", "server", "server.paths", "missing"] {
+        assert_eq!(tracked.string_format(path), None, "{path}");
+    }
+
+    // A number followed by letters is a bare string
+    let tracked = parse_tracked("size = 10abc\n");
+    assert_eq!(tracked.value["size"], "10abc");
+    assert_eq!(tracked.string_format("size"), Some(StringFormat::Unquoted));
+}
+
+#[test]
+fn test_formats_are_not_recorded_without_tracking() {
+    let mut parser = UclParser::new(DOCUMENT);
+    parser.parse_document().unwrap();
+    assert_eq!(parser.provenance().string_format("bare"), None);
+}
+
+#[test]
+fn test_source_map_exposes_formats() {
+    #[derive(Deserialize)]
+    struct Server {
+        host: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Config {
+        json: String,
+        single: String,
+        heredoc: String,
+        bare: String,
+        server: Server,
+    }
+
+    let (config, source_map) = from_str_with_source_map::<Config>(DOCUMENT).unwrap();
+    assert_eq!(
+        [config.json, config.single, config.bare],
+        ["web", "web", "web"]
+    );
+    assert_eq!(config.heredoc, "web\n");
+    assert_eq!(config.server.host, "db.internal");
+
+    assert_eq!(
+        source_map.string_format_for_path("json"),
+        Some(StringFormat::Json)
+    );
+    assert_eq!(
+        source_map.string_format_for_path("single"),
+        Some(StringFormat::Single)
+    );
+    assert_eq!(
+        source_map.string_format_for_path("heredoc"),
+        Some(StringFormat::Heredoc)
+    );
+    assert_eq!(
+        source_map.string_format_for_path("bare"),
+        Some(StringFormat::Unquoted)
+    );
+    assert_eq!(
+        source_map.string_format_for_path("server.host"),
+        Some(StringFormat::Unquoted)
+    );
+    assert_eq!(source_map.string_format_for_path("server"), None);
+}
+
+#[test]
+fn test_serializer_keeps_formats() {
+    let tracked = parse_tracked(DOCUMENT);
+    let text = to_ucl_string_tracked(&tracked, &FormatConfig::default());
+    assert_eq!(
+        text,
+        "json = \"web\";
+single = 'web';
+heredoc = <<EOD
+web
+EOD
+;
+bare = web;
+flag = true;
+quoted_flag = \"true\";
+port = 80;
+server {
+    host = db.internal;
+    paths = [
+        /srv,
+        '/var/www',
+        \"/tmp\",
+    ];
+}
+"
+    );
+
+    // Reading the output back gives the same value and the same formats
+    let reparsed = parse_tracked(&text);
+    assert_eq!(reparsed.value, tracked.value);
+    for path in ["json", "single", "heredoc", "bare", "server.paths.1"] {
+        assert_eq!(
+            reparsed.string_format(path),
+            tracked.string_format(path),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn test_serializer_quotes_strings_that_no_longer_fit() {
+    let mut tracked = parse_tracked("bare = web\nsingle = 'it'\nheredoc = <<EOD\nx\nEOD\n");
+    set(&mut tracked, "bare", "two words");
+    set(&mut tracked, "single", "back\\slash");
+    set(&mut tracked, "heredoc", "no line break");
+    let text = to_ucl_string_tracked(&tracked, &FormatConfig::default());
+    assert_eq!(
+        text,
+        "bare = \"two words\";\nsingle = \"back\\\\slash\";\nheredoc = \"no line break\";\n"
+    );
+
+    // Keywords would read back as other values
+    let mut tracked = parse_tracked("a = web\n");
+    set(&mut tracked, "a", "yes");
+    let text = to_ucl_string_tracked(&tracked, &FormatConfig::default());
+    assert_eq!(text, "a = \"yes\";\n");
+
+    // Quotes in single-quoted strings are escaped, and a heredoc holding
+    // its terminator or a variable reference changes terminator
+    let mut tracked = parse_tracked("single = 'x'\nheredoc = <<EOD\nx\nEOD\n");
+    set(&mut tracked, "single", "it's");
+    set(&mut tracked, "heredoc", "EOD\n$HOME\n");
+    let text = to_ucl_string_tracked(&tracked, &FormatConfig::default());
+    assert_eq!(
+        text,
+        "single = 'it\\'s';\nheredoc = <<'EOD_1'\nEOD\n$HOME\nEOD_1\n;\n"
+    );
+    let reparsed = parse_tracked(&text);
+    assert_eq!(
+        reparsed.value,
+        ucl!({ "single": "it's", "heredoc": "EOD\n$HOME\n" })
+    );
+}
+
+#[test]
+fn test_merged_layers_keep_formats_of_winning_values() {
+    let mut base = parse_tracked("name = web\nport = 80\n");
+    let layer = parse_tracked("name = \"api\"\nport = \"eighty\"\n");
+    base.merge_from(layer);
+    assert_eq!(base.string_format("name"), Some(StringFormat::Json));
+    assert_eq!(base.string_format("port"), Some(StringFormat::Json));
+
+    let mut base = parse_tracked("name = web\n");
+    base.merge_from(parse_tracked("name = 8080\n"));
+    assert_eq!(base.string_format("name"), None);
+}
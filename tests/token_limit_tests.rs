@@ -0,0 +1,127 @@
+//! Tests for the token limit and its scaling with the input size
+
+use std::io::Cursor;
+use ucl_lexer::{LexError, LexerConfig, StreamingUclLexer, Token, UclLexer, UclParser};
+
+/// Lexes `input` to the end, returning the number of tokens
+fn lex_all(input: &str, config: LexerConfig) -> Result<usize, LexError> {
+    let mut lexer = UclLexer::with_config(input, config);
+    let mut count = 0;
+    while lexer.next_token()? != Token::Eof {
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[test]
+fn test_large_config_passes() {
+    // More tokens than the old absolute limit of one million
+    let input: String = (0..300_000).map(|i| format!("key_{i} = {i};\n")).collect();
+    assert!(lex_all(&input, LexerConfig::default()).unwrap() > 1_000_000);
+
+    let value = UclParser::new(&input).parse_document().unwrap();
+    assert_eq!(value.as_object().unwrap().len(), 300_000);
+    assert_eq!(value["key_299999"], 299_999);
+
+    // Hand-written configuration is far below half a token per byte
+    let config = LexerConfig::default().with_max_tokens_per_input_byte(Some(0.5));
+    assert!(lex_all(&input, config).is_ok());
+}
+
+#[test]
+fn test_comma_flood_fails_early() {
+    let input = ",".repeat(1024);
+    let config = LexerConfig::default().with_max_tokens_per_input_byte(Some(0.5));
+    let err = lex_all(&input, config).unwrap_err();
+    match &err {
+        LexError::TokenLimitExceeded {
+            limit,
+            bound,
+            input_size,
+            position,
+        } => {
+            assert_eq!(*limit, 512 + 64);
+            assert_eq!(*bound, "max_tokens_per_input_byte");
+            assert_eq!(*input_size, 1024);
+            assert_eq!(position.offset, 576);
+        }
+        other => panic!("expected a token limit error, got {other:?}"),
+    }
+    assert_eq!(
+        err.to_string(),
+        "Token limit of 576 exceeded at 1:577: the limit is set by \
+         max_tokens_per_input_byte for 1024 bytes of input"
+    );
+    assert_eq!(err.code(), "lex/token-limit-exceeded");
+
+    // The default ratio of two tokens per byte lets the lexer read it all
+    assert_eq!(lex_all(&input, LexerConfig::default()).unwrap(), 1024);
+}
+
+#[test]
+fn test_absolute_cap_still_applies() {
+    let input = "a = 1\n".repeat(100);
+    let config = LexerConfig::default()
+        .with_max_tokens(50)
+        .with_max_tokens_per_input_byte(Some(2.0));
+    match lex_all(&input, config).unwrap_err() {
+        LexError::TokenLimitExceeded {
+            limit,
+            bound,
+            input_size,
+            ..
+        } => {
+            assert_eq!((limit, bound, input_size), (50, "max_tokens", 600));
+        }
+        other => panic!("expected a token limit error, got {other:?}"),
+    }
+
+    // Without a ratio only the cap is left
+    let config = LexerConfig::default()
+        .with_max_tokens(1000)
+        .with_max_tokens_per_input_byte(None);
+    assert_eq!(lex_all(&",".repeat(999), config).unwrap(), 999);
+}
+
+#[test]
+fn test_streaming_applies_the_ratio_to_bytes_read() {
+    let input = ",".repeat(1 << 20);
+    let mut lexer =
+        StreamingUclLexer::new(Cursor::new(input)).with_max_tokens_per_input_byte(Some(0.5));
+    let err = loop {
+        match lexer.next_token() {
+            Ok(Token::Eof) => panic!("no error in the input"),
+            Ok(_) => {}
+            Err(err) => break err,
+        }
+    };
+    match err {
+        LexError::TokenLimitExceeded {
+            bound, input_size, ..
+        } => {
+            assert_eq!(bound, "max_tokens_per_input_byte");
+            // Stopped after a hundred-odd bytes of a megabyte
+            assert_eq!(input_size, 130);
+        }
+        other => panic!("expected a token limit error, got {other:?}"),
+    }
+
+    // The absolute cap holds for streams too
+    let mut lexer = StreamingUclLexer::new(Cursor::new("a = 1\n".repeat(100))).with_max_tokens(10);
+    let tokens = std::iter::from_fn(|| match lexer.next_token() {
+        Ok(Token::Eof) => None,
+        result => Some(result),
+    });
+    let err = tokens.filter_map(Result::err).next().unwrap();
+    assert!(
+        matches!(
+            err,
+            LexError::TokenLimitExceeded {
+                limit: 10,
+                bound: "max_tokens",
+                ..
+            }
+        ),
+        "{err:?}"
+    );
+}
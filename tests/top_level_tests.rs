@@ -0,0 +1,168 @@
+//! Tests for documents whose top level is an array or a scalar
+
+use serde::Deserialize;
+use ucl_lexer::{ParseError, ParserConfig, UclError, UclParser, UclValue, from_str, ucl};
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input).parse_document()
+}
+
+#[test]
+fn test_top_level_array() {
+    assert_eq!(
+        parse("[ {a=1}, {b=2} ]").unwrap(),
+        ucl!([{ "a": 1 }, { "b": 2 }])
+    );
+    assert_eq!(
+        parse("# hosts\n/* list */\n[\"a\", \"b\"]  # end\n").unwrap(),
+        ucl!(["a", "b"])
+    );
+}
+
+#[test]
+fn test_top_level_scalars() {
+    assert_eq!(parse("42").unwrap(), 42);
+    assert_eq!(parse("  \"hello\"\n").unwrap(), "hello");
+    assert_eq!(parse("# flag\ntrue").unwrap(), true);
+    assert_eq!(parse("null").unwrap(), UclValue::Null);
+    assert_eq!(parse("-2.5").unwrap(), -2.5);
+
+    // An empty document is still an empty object
+    assert_eq!(parse("  # nothing\n").unwrap(), ucl!({}));
+
+    // With bare_key_as_true a lone key is an entry
+    let value = UclParser::new("daemonize")
+        .with_config(ParserConfig::libucl_compat())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, ucl!({ "daemonize": true }));
+}
+
+#[test]
+fn test_content_after_the_root_is_an_error() {
+    let err = parse("[1, 2]\nname = \"app\"").unwrap_err();
+    assert!(matches!(
+        err,
+        ParseError::TrailingContent { found: "key", root: "array", position }
+            if position.line == 2 && position.column == 1
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Unexpected key at 2:1 after the top-level array; a document is a single value or a \
+         list of keys without enclosing braces"
+    );
+
+    assert!(matches!(
+        parse("{ a = 1 }\nb = 2").unwrap_err(),
+        ParseError::TrailingContent { root: "object", .. }
+    ));
+    assert!(matches!(
+        parse("[1]\n[2]").unwrap_err(),
+        ParseError::TrailingContent { found: "'['", .. }
+    ));
+    assert!(matches!(
+        parse("{ a = 1 }\nwhat is this").unwrap_err(),
+        ParseError::TrailingContent { found: "key", position, .. }
+            if position.line == 2 && position.column == 1
+    ));
+}
+
+#[test]
+fn test_closing_bracket_after_an_implicit_document_is_an_error() {
+    for (input, found, line, column) in [
+        ("a = 1\n}\nb = 2", "'}'", 2, 1),
+        ("a = 1\nb = [2] ]", "']'", 2, 9),
+        ("a { b = 1 }\n}", "'}'", 2, 1),
+    ] {
+        match parse(input).unwrap_err() {
+            ParseError::TrailingContent {
+                found: token,
+                root: "object",
+                position,
+            } => {
+                assert_eq!(token, found, "{input}");
+                assert_eq!((position.line, position.column), (line, column), "{input}");
+            }
+            other => panic!("{input}: expected trailing content, got {other:?}"),
+        }
+    }
+
+    // Words after the last entry are more entries, not trailing content
+    assert_eq!(
+        parse("a = 1\nwhat is this").unwrap(),
+        ucl!({ "a": 1, "what": "is this" })
+    );
+}
+
+#[test]
+fn test_trailing_whitespace_and_comments_are_not_content() {
+    for input in [
+        "{ a = 1 }\n\n   \t\n",
+        "{ a = 1 }\n# end\n/* block */\n// line",
+        "{ a = 1 };\n",
+        "a = 1\n# end\n",
+        "a = 1;\n/* block */ ",
+    ] {
+        let mut parser = UclParser::new(input)
+            .with_config(ParserConfig::new().with_allow_trailing_content(true));
+        assert_eq!(
+            parser.parse_document().unwrap(),
+            ucl!({ "a": 1 }),
+            "{input}"
+        );
+        assert_eq!(parser.trailing_content_offset(), None, "{input}");
+        assert_eq!(parse(input).unwrap(), ucl!({ "a": 1 }), "{input}");
+    }
+}
+
+#[test]
+fn test_allow_trailing_content_stops_the_parse() {
+    let config = ParserConfig::new().with_allow_trailing_content(true);
+    for (input, expected, offset) in [
+        ("{ a = 1 }\nwhat is this", ucl!({ "a": 1 }), 10),
+        ("[1, 2] # list\n[3]", ucl!([1, 2]), 14),
+        ("a = 1\nb = 2\n}\nc = 3", ucl!({ "a": 1, "b": 2 }), 12),
+        // Content that is not UCL at all
+        ("{ a = 1 }\n--- end ---\n", ucl!({ "a": 1 }), 10),
+        ("[1]\n\"unterminated", ucl!([1]), 4),
+    ] {
+        let mut parser = UclParser::new(input).with_config(config.clone());
+        assert_eq!(parser.parse_document().unwrap(), expected, "{input}");
+        assert_eq!(parser.trailing_content_offset(), Some(offset), "{input}");
+    }
+
+    // The offset belongs to the last parse
+    let mut parser = UclParser::new("").with_config(config);
+    let mut value = UclValue::Null;
+    parser.parse_into("{ a = 1 } rest", &mut value).unwrap();
+    assert_eq!(parser.trailing_content_offset(), Some(10));
+    parser.parse_into("{ a = 1 }", &mut value).unwrap();
+    assert_eq!(parser.trailing_content_offset(), None);
+}
+
+#[test]
+fn test_deserialize_top_level_forms() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Peer {
+        host: String,
+        weight: u32,
+    }
+
+    let peers: Vec<Peer> = from_str(
+        "[ { host = \"a.example.com\", weight = 1 }, { host = \"b.example.com\", weight = 3 } ]",
+    )
+    .unwrap();
+    assert_eq!(peers[1].host, "b.example.com");
+    assert_eq!(peers.iter().map(|peer| peer.weight).sum::<u32>(), 4);
+
+    assert_eq!(from_str::<i32>("42").unwrap(), 42);
+    assert_eq!(from_str::<String>("\"hello\"").unwrap(), "hello");
+    assert!(from_str::<Vec<i32>>("[1, 2] x = 3").is_err());
+
+    // from_str keeps the strict default
+    let err = from_str::<std::collections::HashMap<String, i32>>("a = 1\n}").unwrap_err();
+    assert!(
+        matches!(err, UclError::Parse(ParseError::TrailingContent { .. })),
+        "{err:?}"
+    );
+}
@@ -0,0 +1,106 @@
+//! Tests for deserializing arrays into tuples, tuple structs and fixed-size arrays
+
+use serde::Deserialize;
+use ucl_lexer::error::SerdeError;
+use ucl_lexer::{UclError, from_str};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Point(f64, f64);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Path(Vec<Point>);
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Canvas {
+    origin: (f64, f64),
+    rgb: [u8; 3],
+    corners: Vec<(i32, i32)>,
+}
+
+#[test]
+fn test_tuples_and_fixed_size_arrays() {
+    let input = "origin = [1.5, 2.5]\nrgb = [255, 0, 0]\ncorners = [[0, 0], [10, 20]]\n";
+    let canvas: Canvas = from_str(input).unwrap();
+
+    assert_eq!(
+        canvas,
+        Canvas {
+            origin: (1.5, 2.5),
+            rgb: [255, 0, 0],
+            corners: vec![(0, 0), (10, 20)],
+        }
+    );
+}
+
+#[test]
+fn test_top_level_and_nested_tuples() {
+    let value: (i32, (String, bool), [f64; 2]) = from_str("[1, [\"a\", true], [0.5, 1]]").unwrap();
+    assert_eq!(value, (1, ("a".to_string(), true), [0.5, 1.0]));
+}
+
+#[test]
+fn test_tuple_struct_and_newtype_around_sequence() {
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        start: Point,
+        path: Path,
+    }
+
+    let config: Config = from_str("start = [0, 1]\npath = [[1, 2], [3, 4]]\n").unwrap();
+    assert_eq!(config.start, Point(0.0, 1.0));
+    assert_eq!(config.path, Path(vec![Point(1.0, 2.0), Point(3.0, 4.0)]));
+
+    #[derive(Debug, Deserialize)]
+    struct Tags(Vec<String>);
+    let tags: Tags = from_str(r#"["a", "b"]"#).unwrap();
+    assert_eq!(tags.0, ["a", "b"]);
+}
+
+#[test]
+fn test_wrong_arity_reports_line() {
+    let err =
+        from_str::<Canvas>("origin = [1, 2]\nrgb = [255, 0, 0, 1]\ncorners = []\n").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Serde error: expected 3 elements for [u8; 3], found 4 at line 2"
+    );
+
+    match err {
+        UclError::Serde(SerdeError::InvalidLength { path, position, .. }) => {
+            assert_eq!(path, ["rgb"]);
+            assert_eq!(position.map(|p| p.line), Some(2));
+        }
+        other => panic!("expected invalid length error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_wrong_arity_inside_vec() {
+    let input = "origin = [1, 2]\nrgb = [1, 2, 3]\n\ncorners = [[0, 0], [1]]\n";
+    let err = from_str::<Canvas>(input).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("expected 2 elements for (i32, i32), found 1 at line 4"),
+        "{err}"
+    );
+
+    let err = from_str::<Path>("[[1, 2], [3, 4, 5]]").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("expected 2 elements for Point, found 3 at '1'"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_too_few_elements() {
+    let err = from_str::<(u8, u8, u8)>("[1, 2]").unwrap_err();
+    assert!(matches!(
+        err,
+        UclError::Serde(SerdeError::InvalidLength {
+            expected: 3,
+            found: 2,
+            ..
+        })
+    ));
+}
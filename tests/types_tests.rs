@@ -0,0 +1,159 @@
+//! Tests for the `ByteSize` and `Seconds` newtypes
+#![cfg(feature = "types")]
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use ucl_lexer::from_str;
+use ucl_lexer::types::{ByteSize, Seconds};
+
+#[derive(Debug, Deserialize, Serialize, PartialEq)]
+struct Limits {
+    memory: ByteSize,
+    timeout: Seconds,
+}
+
+#[test]
+fn test_byte_size_sources() {
+    #[derive(Deserialize)]
+    struct Sizes {
+        sizes: Vec<ByteSize>,
+    }
+
+    let sizes: Sizes =
+        from_str("sizes = [1024, 512mb, \"512mb\", \"4k\", \" 2gb \", 3.0]").unwrap();
+    assert_eq!(
+        sizes.sizes,
+        vec![
+            ByteSize(1024),
+            ByteSize(512 << 20),
+            ByteSize(512 << 20),
+            ByteSize(4000),
+            ByteSize(2 << 30),
+            ByteSize(3),
+        ]
+    );
+}
+
+#[test]
+fn test_byte_size_display() {
+    assert_eq!(ByteSize(0).to_string(), "0");
+    assert_eq!(ByteSize(1000).to_string(), "1000");
+    assert_eq!(ByteSize(1536).to_string(), "1536");
+    assert_eq!(ByteSize(2048).to_string(), "2kb");
+    assert_eq!(ByteSize(3 << 30).to_string(), "3gb");
+    assert_eq!(ByteSize(5 << 40).to_string(), "5tb");
+    assert_eq!(ByteSize(1 << 50).to_string(), "1024tb");
+}
+
+#[test]
+fn test_byte_size_rejects_negative_and_invalid_values() {
+    for input in [
+        "memory = -1\ntimeout = 1",
+        "memory = -4kb\ntimeout = 1",
+        "memory = \"-512mb\"\ntimeout = 1",
+    ] {
+        let err = from_str::<Limits>(input).unwrap_err();
+        assert!(err.to_string().contains("size cannot be negative"), "{err}");
+    }
+
+    assert!(from_str::<Limits>("memory = 1.5\ntimeout = 1").is_err());
+    assert!(from_str::<Limits>("memory = \"lots\"\ntimeout = 1").is_err());
+    assert!("12 mb".parse::<ByteSize>().is_err());
+    assert!("30s".parse::<ByteSize>().is_err());
+}
+
+#[test]
+fn test_seconds_sources() {
+    #[derive(Deserialize)]
+    struct Durations {
+        durations: Vec<Seconds>,
+    }
+
+    let durations: Durations = from_str(
+        "durations = [30s, 45, 0.5, \"1h30m\", \"1h30min\", \"2d12h\", \"500ms\", \"90\", \"-5s\"]",
+    )
+    .unwrap();
+    assert_eq!(
+        durations.durations,
+        vec![
+            Seconds(30.0),
+            Seconds(45.0),
+            Seconds(0.5),
+            Seconds(5400.0),
+            Seconds(5400.0),
+            Seconds(216000.0),
+            Seconds(0.5),
+            Seconds(90.0),
+            Seconds(-5.0),
+        ]
+    );
+}
+
+#[test]
+fn test_bare_m_suffix_follows_the_field_type() {
+    // With the default `MSuffix::Error`, a duration reads minutes and a size a mega suffix
+    let limits: Limits = from_str("memory = 5m\ntimeout = 5m").unwrap();
+    assert_eq!(limits.memory, ByteSize(5_000_000));
+    assert_eq!(limits.timeout, Seconds(300.0));
+
+    #[derive(Debug, Deserialize)]
+    struct Times {
+        timeouts: Vec<Seconds>,
+    }
+    let times: Times = from_str("timeouts = [5m, 5min, \"5m\", 90s]").unwrap();
+    assert_eq!(
+        times.timeouts,
+        vec![
+            Seconds(300.0),
+            Seconds(300.0),
+            Seconds(300.0),
+            Seconds(90.0)
+        ]
+    );
+    assert_eq!("4m".parse::<ByteSize>().unwrap(), ByteSize(4_000_000));
+}
+
+#[test]
+fn test_seconds_display_and_conversion() {
+    assert_eq!(Seconds(0.0).to_string(), "0s");
+    assert_eq!(Seconds(90.0).to_string(), "1min30s");
+    assert_eq!(Seconds(93784.5).to_string(), "1d2h3min4s500ms");
+    assert_eq!(Seconds(-3600.0).to_string(), "-1h");
+    assert_eq!(Seconds(0.0001).to_string(), "0.0001s");
+
+    assert_eq!(Seconds(1.5).as_duration(), Duration::from_millis(1500));
+    assert_eq!(Seconds(-1.0).as_duration(), Duration::ZERO);
+    assert_eq!(Seconds::from(Duration::from_secs(60)), Seconds(60.0));
+}
+
+#[test]
+fn test_seconds_rejects_invalid_strings() {
+    for input in ["", "h", "1x", "1h 30m", "inf", "1..5s"] {
+        assert!(input.parse::<Seconds>().is_err(), "{input:?}");
+    }
+    let err = "10parsecs".parse::<Seconds>().unwrap_err();
+    assert_eq!(err, "unknown time unit 'parsecs' in 10parsecs");
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let limits = Limits {
+        memory: ByteSize(768 << 20),
+        timeout: Seconds(5400.0),
+    };
+
+    let json = serde_json::to_string(&limits).unwrap();
+    assert_eq!(json, r#"{"memory":"768mb","timeout":"1h30min"}"#);
+    assert_eq!(serde_json::from_str::<Limits>(&json).unwrap(), limits);
+    assert_eq!(from_str::<Limits>(&json).unwrap(), limits);
+
+    // Sizes display as UCL literals, compound durations need quotes
+    let text = format!(
+        "memory = {}\ntimeout = \"{}\"",
+        limits.memory,
+        Seconds(90.0)
+    );
+    let parsed: Limits = from_str(&text).unwrap();
+    assert_eq!(parsed.memory, limits.memory);
+    assert_eq!(parsed.timeout, Seconds(90.0));
+}
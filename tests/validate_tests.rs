@@ -0,0 +1,192 @@
+//! Tests for checking documents without building their values
+
+use std::fs;
+use std::path::Path;
+use ucl_lexer::{DuplicateKeyBehavior, ParseError, ParserConfig, UclError, UclParser, validate};
+
+/// Pieces of documents, combined into inputs that are mostly valid
+const PIECES: &[&str] = &[
+    "a = 1",
+    "b: \"x\"",
+    "c { d = 2 }",
+    "e = [1, 2]",
+    "f = [ ]",
+    "g {}",
+    "h = yes",
+    "i = 30s",
+    "j = word",
+    "k = 'q'",
+    "\"l\" = 1.5",
+    "m = { n = [ { o = 1 } ] }",
+    "p = \"s\" + \"t\"",
+    "q = [info, warn]",
+    "r = [a ]",
+    "s = 10.0.0.1",
+    "t = 1kb",
+    "u = <<EOT\ntext\nEOT\n",
+    "v = [\"a\",\n\"b\",\n]",
+    "w = -inf",
+    "x = null",
+    "a = 2",
+    "c { d = 3 }",
+    "y += 1",
+    "z",
+    "z zz",
+    "z zz {}",
+    ".priority 1",
+    "a.b = 1",
+    "n {",
+    "}",
+    "]",
+    "[",
+    "= 1",
+    "k = = 1",
+    "o = [1,,2]",
+    "p = {;}",
+    "q = [host = a]",
+    "r = 1 2",
+    "s = hello world",
+    "t = word}",
+    "# comment\n",
+    "/* c */",
+    ";",
+    ",",
+    ";;",
+    ",,",
+    "\n",
+];
+
+/// Generates inputs from `PIECES` with a fixed linear congruential generator
+fn inputs() -> impl Iterator<Item = String> {
+    let mut state = 0x2545_f491_u64;
+    let mut next = move |bound: usize| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize % bound
+    };
+    (0..3000).map(move |_| {
+        let mut input = String::new();
+        let braced = next(4) == 0;
+        if braced {
+            input.push('{');
+        }
+        for _ in 0..1 + next(6) {
+            input.push_str(PIECES[next(PIECES.len())]);
+            input.push_str(["\n", "; ", ", ", " "][next(4)]);
+        }
+        if braced {
+            input.push('}');
+        }
+        input
+    })
+}
+
+fn messages(errors: &[UclError]) -> Vec<String> {
+    errors.iter().map(ToString::to_string).collect()
+}
+
+fn configs() -> Vec<ParserConfig> {
+    let mut unique_keys = ParserConfig::default();
+    unique_keys.duplicate_key_behavior = DuplicateKeyBehavior::Error;
+    let mut shallow = ParserConfig::default();
+    shallow.max_depth = 2;
+    vec![
+        ParserConfig::default(),
+        ParserConfig::strict(),
+        ParserConfig::libucl_compat(),
+        unique_keys,
+        shallow,
+    ]
+}
+
+#[test]
+fn test_validate_reports_what_parsing_reports() {
+    for config in configs() {
+        for input in inputs() {
+            let (_, expected) = UclParser::new(&input)
+                .with_config(config.clone())
+                .parse_document_lenient();
+            let errors = validate(&input, &config).err().unwrap_or_default();
+            assert_eq!(messages(&errors), messages(&expected), "{input:?}");
+        }
+    }
+}
+
+#[test]
+fn test_corpus_matches_parsing() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            continue;
+        }
+        let input = fs::read_to_string(&path).unwrap();
+        let parsed = UclParser::new(&input).parse_document_lenient().1;
+        let checked = validate(&input, &ParserConfig::default()).err();
+        assert_eq!(
+            messages(&checked.unwrap_or_default()),
+            messages(&parsed),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn test_all_errors_are_reported() {
+    let input = "a = 1,,\nb = [,]\nc { d = 1 }\nc { d = 2 }\ne = \"${}\"";
+    let mut config = ParserConfig::default();
+    config.duplicate_key_behavior = DuplicateKeyBehavior::Error;
+    let errors = validate(input, &config).unwrap_err();
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [
+                UclError::Parse(ParseError::RepeatedSeparator { .. }),
+                UclError::Parse(ParseError::LeadingSeparator { .. }),
+                UclError::Parse(ParseError::DuplicateKey { .. }),
+                UclError::Parse(ParseError::VariableExpansion { .. }),
+            ]
+        ),
+        "{errors:?}"
+    );
+}
+
+#[test]
+fn test_variables_are_checked_but_not_resolved() {
+    let config = ParserConfig::default();
+    assert!(validate("url = \"${HOST:-localhost}/${PATH}\"", &config).is_ok());
+
+    let errors = validate("url = \"${HOST:-${}}\"", &config).unwrap_err();
+    assert!(matches!(
+        errors.as_slice(),
+        [UclError::Parse(ParseError::VariableExpansion { .. })]
+    ));
+    // Parsing only reads variables when a handler is set
+    assert!(UclParser::new("url = \"${}\"").parse_document().is_ok());
+}
+
+#[test]
+fn test_nesting_limit() {
+    let mut config = ParserConfig::default();
+    config.max_depth = 3;
+    assert!(validate("a { b { c = 1 } }", &config).is_ok());
+    let errors = validate("a { b { c { d = [1] } } }", &config).unwrap_err();
+    assert!(matches!(
+        errors.as_slice(),
+        [UclError::Parse(ParseError::MaxDepthExceeded { .. })]
+    ));
+}
+
+#[test]
+fn test_validate_collects_no_diagnostics() {
+    let mut config = ParserConfig::default();
+    config.lints.empty_objects = true;
+    config.lints.mixed_separators = true;
+    for input in ["a = 1\nb: 2\nc {}", "a = 1\nb: 2\nc {}\nd += 1"] {
+        let mut parser = UclParser::new(input).with_config(config.clone());
+        assert!(parser.validate().is_ok(), "{input:?}");
+        assert!(parser.diagnostics().is_empty(), "{input:?}");
+    }
+}
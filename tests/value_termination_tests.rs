@@ -0,0 +1,147 @@
+//! Tests for how the value of an entry ends
+//!
+//! After a complete value, the entry ends at a newline, `;`, `,`, the `}`
+//! of the enclosing object or the end of input, whatever the kind of value.
+//! Another entry may follow on the same line, but a second value there is
+//! a `ParseError::MissingSeparator`, unless relaxed assignment reads it as
+//! the key of an nginx-style `key value` entry.
+
+use ucl_lexer::{ParseError, ParserConfig, UclParser, UclValue, ucl};
+
+/// Values of each kind, as written and as parsed
+///
+/// A heredoc ends with the line break after its terminator, which therefore
+/// comes before the terminator being tested.
+fn values() -> Vec<(&'static str, UclValue)> {
+    vec![
+        ("\"text\"", ucl!("text")),
+        ("'text'", ucl!("text")),
+        ("word", ucl!("word")),
+        ("/usr/local", ucl!("/usr/local")),
+        ("42", ucl!(42)),
+        ("-3", ucl!(-3)),
+        ("0x10", ucl!(16)),
+        ("1.5", ucl!(1.5)),
+        ("10s", UclValue::Time(10.0)),
+        ("10kb", ucl!(10240)),
+        ("true", ucl!(true)),
+        ("null", ucl!(null)),
+        ("{ x = 1 }", ucl!({ "x": 1 })),
+        ("[1, 2]", ucl!([1, 2])),
+        ("<<EOD\nline\nEOD\n", ucl!("line\n")),
+    ]
+}
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    UclParser::new(input).parse_document()
+}
+
+#[test]
+fn test_terminators_end_every_kind_of_value() {
+    // (terminator, document with the value in place of `V`)
+    let terminators = [
+        ("newline", "a = V\nb = 2"),
+        (";", "a = V; b = 2"),
+        (",", "a = V, b = 2"),
+        ("}", "o { a = V }"),
+        ("newline and }", "o { a = V\n}"),
+        ("end of input", "a = V"),
+        ("comment", "a = V # note\nb = 2"),
+    ];
+
+    for (source, value) in values() {
+        for (terminator, template) in terminators {
+            let input = template.replace('V', source);
+            let expected = match terminator {
+                "}" | "newline and }" => ucl!({ "o": { "a": value.clone() } }),
+                "end of input" => ucl!({ "a": value.clone() }),
+                _ => ucl!({ "a": value.clone(), "b": 2 }),
+            };
+            match parse(&input) {
+                Ok(parsed) => assert_eq!(parsed, expected, "{terminator} after {input:?}"),
+                Err(error) => panic!("{terminator} after {input:?}: {error}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_second_value_on_the_line_is_a_missing_separator() {
+    for (source, _) in values() {
+        // A heredoc takes its line, so the next value is on a line of its own
+        if source.starts_with("<<") {
+            continue;
+        }
+        for template in ["a = V 2", "o { a = V 2 }", "a = V 2\nb = 3"] {
+            let input = template.replace('V', source);
+            let column = template.find('V').unwrap() + source.len() + 2;
+            match parse(&input) {
+                Err(ParseError::MissingSeparator { found, position }) => {
+                    assert_eq!(found, "integer", "{input:?}");
+                    assert_eq!((position.line, position.column), (1, column), "{input:?}");
+                }
+                other => panic!("{input:?}: expected a missing separator, got {other:?}"),
+            }
+        }
+    }
+
+    let err = parse("name = hello world").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Missing separator before key at 1:14; a value ends at a newline, ',' or ';'"
+    );
+}
+
+#[test]
+fn test_entry_on_the_same_line() {
+    // A key with its own `=`, `:` or `{` starts the next entry
+    let value = parse("a = 1 b = 2 c: \"x\" d { e = 3 }").unwrap();
+    assert_eq!(value, ucl!({ "a": 1, "b": 2, "c": "x", "d": { "e": 3 } }));
+
+    // With relaxed assignment, `key value` is an entry too; strict mode
+    // requires the `=` or `:`
+    let input = "a = 1 b 2";
+    assert_eq!(parse(input).unwrap(), ucl!({ "a": 1, "b": 2 }));
+    let strict = UclParser::new(input)
+        .with_config(ParserConfig::strict())
+        .parse_document()
+        .unwrap_err();
+    assert!(
+        matches!(strict, ParseError::MissingSeparator { found: "key", .. }),
+        "{strict:?}"
+    );
+}
+
+#[test]
+fn test_relaxed_value_starts_on_the_line_of_its_key() {
+    // The line after a key without a value is the next entry, not the value
+    let err = parse("a = 1\nb\nc = 2").unwrap_err();
+    match err {
+        ParseError::UnexpectedToken {
+            found, position, ..
+        } => {
+            assert_eq!(found, "end of line");
+            assert_eq!((position.line, position.column), (2, 2));
+        }
+        other => panic!("expected an unexpected token error, got {other:?}"),
+    }
+    assert!(parse("server {\n  listen\n  port 80\n}").is_err());
+
+    // A trailing key on the line of a value is a second value
+    let err = parse("a = 1 b\nc = 2").unwrap_err();
+    assert!(
+        matches!(err, ParseError::MissingSeparator { found: "key", .. }),
+        "{err:?}"
+    );
+
+    // `{`, `=` and `:` on the next line still belong to the key
+    let value = parse("server\n{\n  port 80\n}\nname\n= \"web\"").unwrap();
+    assert_eq!(value, ucl!({ "server": { "port": 80 }, "name": "web" }));
+
+    // With `bare_key_as_true`, a key without a value is `true`
+    let value = UclParser::new("a = 1\nb\nc = 2")
+        .with_config(ParserConfig::libucl_compat())
+        .parse_document()
+        .unwrap();
+    assert_eq!(value, ucl!({ "a": 1, "b": true, "c": 2 }));
+}
@@ -0,0 +1,363 @@
+//! Tests for `UclValue` conversions, formatting and construction
+//!
+//! Covers `Display`, `FromStr`, the `From`/`TryFrom` conversions and the
+//! `ucl!` macro.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use ucl_lexer::{
+    ConversionError, EquivalenceOptions, TimeSuffixStyle, UclArray, UclObject, UclValue,
+    format_time, from_str, ucl,
+};
+
+#[test]
+fn test_display_scalars() {
+    assert_eq!(UclValue::Integer(-42).to_string(), "-42");
+    assert_eq!(UclValue::Float(1.5).to_string(), "1.5");
+    assert_eq!(UclValue::Float(3.0).to_string(), "3.0");
+    assert_eq!(UclValue::Float(f64::INFINITY).to_string(), "inf");
+    assert_eq!(UclValue::Float(f64::NAN).to_string(), "nan");
+    assert_eq!(UclValue::Boolean(true).to_string(), "true");
+    assert_eq!(UclValue::Null.to_string(), "null");
+    assert_eq!(
+        UclValue::String("say \"hi\"\n".to_string()).to_string(),
+        r#""say \"hi\"\n""#
+    );
+}
+
+#[test]
+fn test_display_nested() {
+    let value = ucl!({
+        "name": "server",
+        "ports": [80, 443],
+        "tls": {}
+    });
+    assert_eq!(
+        value.to_string(),
+        r#"{"name": "server", "ports": [80, 443], "tls": {}}"#
+    );
+}
+
+#[test]
+fn test_display_round_trips_through_from_str() {
+    let value = ucl!({
+        "name": "app",
+        "ratio": 0.25,
+        "count": 3,
+        "enabled": false,
+        "items": ["a", { "b": null }]
+    });
+    let reparsed: UclValue = value.to_string().parse().unwrap();
+    assert_eq!(reparsed, value);
+}
+
+#[test]
+fn test_from_str_scalars() {
+    assert_eq!("42".parse::<UclValue>().unwrap(), UclValue::Integer(42));
+    assert_eq!("  2.5 ".parse::<UclValue>().unwrap(), UclValue::Float(2.5));
+    assert_eq!("true".parse::<UclValue>().unwrap(), UclValue::Boolean(true));
+    assert_eq!("null".parse::<UclValue>().unwrap(), UclValue::Null);
+    assert_eq!(
+        r#""hello""#.parse::<UclValue>().unwrap(),
+        UclValue::String("hello".to_string())
+    );
+}
+
+#[test]
+fn test_from_str_document() {
+    let value: UclValue = "port = 8080\nhost = \"localhost\"".parse().unwrap();
+    assert_eq!(value, ucl!({ "port": 8080, "host": "localhost" }));
+
+    let array: UclValue = "[1, 2, 3]".parse().unwrap();
+    assert_eq!(array, ucl!([1, 2, 3]));
+}
+
+#[test]
+fn test_from_str_error() {
+    assert!("key = [1, 2".parse::<UclValue>().is_err());
+}
+
+#[test]
+fn test_from_primitives() {
+    assert_eq!(UclValue::from(7i32), UclValue::Integer(7));
+    assert_eq!(UclValue::from(7u16), UclValue::Integer(7));
+    assert_eq!(UclValue::from(1.25f64), UclValue::Float(1.25));
+    assert_eq!(UclValue::from(false), UclValue::Boolean(false));
+    assert_eq!(UclValue::from("x"), UclValue::String("x".to_string()));
+    assert_eq!(
+        UclValue::from("y".to_string()),
+        UclValue::String("y".to_string())
+    );
+    assert_eq!(UclValue::from(None::<i64>), UclValue::Null);
+    assert_eq!(UclValue::from(Some(3i64)), UclValue::Integer(3));
+}
+
+#[test]
+fn test_from_collections() {
+    let array = UclValue::from(vec![1i64, 2, 3]);
+    assert_eq!(array.as_array().unwrap().len(), 3);
+
+    let mut map = HashMap::new();
+    map.insert("key".to_string(), UclValue::from("value"));
+    let object = UclValue::from(map);
+    assert_eq!(
+        object.as_object().unwrap().get("key"),
+        Some(&UclValue::String("value".to_string()))
+    );
+
+    let mut ordered = UclObject::new();
+    ordered.insert("a".into(), UclValue::Null);
+    assert!(UclValue::from(ordered).is_object());
+    assert!(UclValue::from(UclArray::new()).is_array());
+}
+
+#[test]
+fn test_try_from_success() {
+    assert_eq!(i64::try_from(UclValue::Integer(5)), Ok(5));
+    assert_eq!(u16::try_from(UclValue::Integer(8080)), Ok(8080));
+    assert_eq!(f64::try_from(UclValue::Integer(2)), Ok(2.0));
+    assert_eq!(bool::try_from(UclValue::Boolean(true)), Ok(true));
+    assert_eq!(
+        String::try_from(UclValue::String("s".to_string())),
+        Ok("s".to_string())
+    );
+    assert_eq!(Vec::<UclValue>::try_from(ucl!([1])).unwrap().len(), 1);
+    assert!(UclObject::try_from(ucl!({})).unwrap().is_empty());
+}
+
+#[test]
+fn test_try_from_errors() {
+    let err = i64::try_from(UclValue::String("5".to_string())).unwrap_err();
+    assert_eq!(
+        err,
+        ConversionError::TypeMismatch {
+            expected: "integer",
+            found: "string",
+        }
+    );
+    assert_eq!(err.to_string(), "expected integer, found string");
+
+    let err = u8::try_from(UclValue::Integer(300)).unwrap_err();
+    assert_eq!(
+        err,
+        ConversionError::OutOfRange {
+            value: 300,
+            target: "u8",
+        }
+    );
+    assert!(u64::try_from(UclValue::Integer(-1)).is_err());
+    assert!(bool::try_from(UclValue::Null).is_err());
+}
+
+#[test]
+fn test_ucl_macro() {
+    let name = "api";
+    let value = ucl!({
+        "service": name,
+        "replicas": 2 + 1,
+        "offset": -4,
+        "limits": { "cpu": 0.5, "memory": "512mb" },
+        "flags": [true, false, null,],
+        (format!("{}_id", name)): 7,
+    });
+
+    let obj = value.as_object().unwrap();
+    assert_eq!(obj["service"], UclValue::String("api".to_string()));
+    assert_eq!(obj["replicas"], UclValue::Integer(3));
+    assert_eq!(obj["offset"], UclValue::Integer(-4));
+    assert_eq!(
+        obj["limits"].as_object().unwrap()["cpu"],
+        UclValue::Float(0.5)
+    );
+    assert_eq!(obj["flags"].as_array().unwrap().len(), 3);
+    assert_eq!(obj["api_id"], UclValue::Integer(7));
+
+    assert_eq!(ucl!(null), UclValue::Null);
+    assert_eq!(ucl!([]), UclValue::Array(Box::default()));
+    assert_eq!(ucl!({}), UclValue::Object(UclObject::new()));
+}
+
+#[test]
+fn test_partial_eq_with_primitives() {
+    let cfg: UclValue = "port = 8080\nhost = \"localhost\"\nratio = 0.5\ndebug = true"
+        .parse()
+        .unwrap();
+
+    assert_eq!(cfg["port"], 8080);
+    assert_eq!(cfg["port"], 8080u16);
+    assert_eq!(cfg["host"], "localhost");
+    assert_eq!(cfg["host"], "localhost".to_string());
+    assert_eq!(cfg["ratio"], 0.5);
+    assert_eq!(cfg["debug"], true);
+    assert_eq!(8080, cfg["port"]);
+    assert_eq!("localhost", cfg["host"]);
+
+    assert_ne!(cfg["port"], "8080");
+    assert_ne!(cfg["host"], 1);
+    assert!(cfg["missing"].is_null());
+    assert!(cfg["port"]["nested"].is_null());
+}
+
+#[test]
+fn test_index_by_position() {
+    let value = ucl!([10, 20]);
+    assert_eq!(value[1], 20);
+    assert!(value[5].is_null());
+}
+
+#[test]
+fn test_equivalent_key_order() {
+    let a = ucl!({ "x": 1, "y": 2 });
+    let b = ucl!({ "y": 2, "x": 1 });
+
+    assert!(!a.equivalent(&b, &EquivalenceOptions::new()));
+    assert!(a.equivalent(&b, &EquivalenceOptions::new().with_ignore_key_order(true)));
+}
+
+#[test]
+fn test_equivalent_numeric() {
+    let a = ucl!({ "timeout": 5 });
+    let b = ucl!({ "timeout": 5.0 });
+
+    assert!(!a.equivalent(&b, &EquivalenceOptions::new()));
+    assert!(a.equivalent(
+        &b,
+        &EquivalenceOptions::new().with_numeric_equivalence(true)
+    ));
+    assert!(!a.equivalent(
+        &ucl!({ "timeout": 5.5 }),
+        &EquivalenceOptions::new().with_numeric_equivalence(true)
+    ));
+}
+
+#[test]
+fn test_equivalent_nan() {
+    let a = ucl!([f64::NAN]);
+    let b = ucl!([f64::NAN]);
+
+    assert_ne!(a, b);
+    assert!(!a.equivalent(&b, &EquivalenceOptions::new()));
+    assert!(a.equivalent(&b, &EquivalenceOptions::new().with_nan_equal(true)));
+}
+
+#[test]
+fn test_equivalent_ignored_paths() {
+    let staging = ucl!({
+        "server": { "host": "staging.local", "port": 80 },
+        "nodes": [{ "id": 1, "role": "db" }, { "id": 2, "role": "web" }]
+    });
+    let production = ucl!({
+        "server": { "host": "prod.example.com", "port": 80 },
+        "nodes": [{ "id": 7, "role": "db" }, { "id": 8, "role": "web" }],
+        "build": "abc123"
+    });
+
+    let options = EquivalenceOptions::new()
+        .with_ignored_path("server.host")
+        .with_ignored_path("nodes.*.id")
+        .with_ignored_path("build");
+    assert!(staging.equivalent(&production, &options));
+
+    let options = EquivalenceOptions::new().with_ignored_path("server.host");
+    assert!(!staging.equivalent(&production, &options));
+}
+
+#[test]
+fn test_time_values_are_distinct() {
+    let cfg: UclValue = "timeout = 90s\nretry = 250ms\nratio = 1.5".parse().unwrap();
+
+    assert_eq!(cfg["timeout"], UclValue::Time(90.0));
+    assert!(cfg["timeout"].is_time());
+    assert_eq!(cfg["timeout"].type_name(), "time");
+    assert_eq!(cfg["timeout"].as_time(), Some(90.0));
+    assert_eq!(cfg["timeout"].as_float(), None);
+    assert_eq!(cfg["retry"].as_duration(), Some(Duration::from_millis(250)));
+    assert_eq!(cfg["ratio"].as_time(), None);
+    assert_eq!(UclValue::Time(-1.0).as_duration(), None);
+
+    let braced: UclValue = "{ interval = 2min, values = [1h, 5s] }".parse().unwrap();
+    assert_eq!(braced["interval"], UclValue::Time(120.0));
+    assert_eq!(braced["values"][0], UclValue::Time(3600.0));
+}
+
+#[test]
+fn test_time_deserializes_as_seconds() {
+    #[derive(Deserialize)]
+    struct Timeouts {
+        connect: f64,
+        read: u64,
+        write: i32,
+    }
+
+    let timeouts: Timeouts = from_str("connect = 1.5s\nread = 2min\nwrite = 1h").unwrap();
+    assert_eq!(timeouts.connect, 1.5);
+    assert_eq!(timeouts.read, 120);
+    assert_eq!(timeouts.write, 3600);
+    assert_eq!(f64::try_from(UclValue::Time(3.0)), Ok(3.0));
+}
+
+#[test]
+fn test_time_formatting() {
+    assert_eq!(format_time(90.0, TimeSuffixStyle::Integral), "90s");
+    assert_eq!(format_time(90.0, TimeSuffixStyle::ShortDecimal), "1.5min");
+    assert_eq!(format_time(7200.0, TimeSuffixStyle::Integral), "2h");
+    assert_eq!(format_time(0.25, TimeSuffixStyle::Integral), "250ms");
+    assert_eq!(format_time(86400.0 * 14.0, TimeSuffixStyle::Integral), "2w");
+    assert_eq!(format_time(0.0, TimeSuffixStyle::Integral), "0s");
+    assert_eq!(
+        format_time(1.2345, TimeSuffixStyle::ShortDecimal),
+        "1234.5ms"
+    );
+
+    assert_eq!(UclValue::Time(300.0).to_string(), "5min");
+    let reparsed: UclValue = UclValue::Time(300.0).to_string().parse().unwrap();
+    assert_eq!(reparsed, UclValue::Time(300.0));
+}
+
+/// Nests `leaf` `depth` times, alternating arrays and objects
+fn nested(depth: usize, leaf: UclValue) -> UclValue {
+    let mut value = leaf;
+    for level in 0..depth {
+        value = if level % 2 == 0 {
+            UclValue::from(vec![value, UclValue::from(level as i64)])
+        } else {
+            let mut object = UclObject::new();
+            object.insert("child".into(), value);
+            object.insert("level".into(), UclValue::from(level as i64));
+            UclValue::Object(object)
+        };
+    }
+    value
+}
+
+#[test]
+fn test_deep_values_clone_compare_and_drop() {
+    // Recursive drop glue would overflow the test thread's stack long before this depth
+    let value = nested(100_000, UclValue::from("bottom"));
+    let copy = value.clone();
+    assert_eq!(copy, value);
+    assert_ne!(nested(100_000, UclValue::from("other")), value);
+    drop(copy);
+    drop(value);
+}
+
+#[test]
+fn test_clone_keeps_order_and_eq_ignores_key_order() {
+    let value = ucl!({ "b": [1, 2.5, { "c": null }], "a": "x", "t": true });
+    let copy = value.clone();
+    assert_eq!(copy, value);
+    assert_eq!(
+        copy.as_object().unwrap().keys().collect::<Vec<_>>(),
+        ["b", "a", "t"]
+    );
+
+    // Objects compare without regard to key order, arrays in order
+    assert_eq!(ucl!({ "a": 1, "b": 2 }), ucl!({ "b": 2, "a": 1 }));
+    assert_ne!(ucl!([1, 2]), ucl!([2, 1]));
+    assert_ne!(ucl!({ "a": 1 }), ucl!({ "a": 1, "b": 2 }));
+    assert_ne!(ucl!({ "a": [1] }), ucl!({ "a": 1 }));
+    assert_ne!(UclValue::Integer(1), UclValue::Float(1.0));
+    assert_ne!(UclValue::Float(f64::NAN), UclValue::Float(f64::NAN));
+    assert_eq!(UclValue::default(), UclValue::Null);
+}
@@ -0,0 +1,126 @@
+//! Tests for reading `$` as an ordinary character with `LexerConfig::detect_variables`
+
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Cursor;
+use ucl_lexer::{
+    LexerConfig, MapVariableHandler, StreamingUclLexer, Token, UclLexer, UclParserBuilder,
+    UclValue, from_str_with_variables,
+};
+
+fn handler() -> Box<MapVariableHandler> {
+    Box::new(MapVariableHandler::from_map(HashMap::from([(
+        "FOO".to_string(),
+        "expanded".to_string(),
+    )])))
+}
+
+fn no_detection() -> LexerConfig {
+    LexerConfig::new().with_detect_variables(false)
+}
+
+fn parse(input: &str, config: LexerConfig) -> UclValue {
+    UclParserBuilder::new(input)
+        .with_lexer_config(config)
+        .with_variable_handler(handler())
+        .build()
+        .unwrap()
+        .parse_document()
+        .unwrap()
+}
+
+#[test]
+fn test_literal_references_survive_into_values() {
+    let input = "\
+json = \"${FOO} and $FOO\"
+escaped = \"\\${FOO} costs \\$5\"
+triple = \"\"\"${FOO}\"\"\"
+heredoc = <<EOD
+labels: {job=\"${FOO}\"}
+EOD
+";
+    let value = parse(input, no_detection());
+    assert_eq!(value["json"], "${FOO} and $FOO");
+    assert_eq!(value["escaped"], "${FOO} costs $5");
+    assert_eq!(value["triple"], "${FOO}");
+    assert_eq!(value["heredoc"], "labels: {job=\"${FOO}\"}\n");
+
+    // The same document with detection on expands through the handler
+    #[cfg(not(feature = "no-variable-expansion"))]
+    {
+        let value = parse(input, LexerConfig::default());
+        assert_eq!(value["json"], "expanded and expanded");
+        assert_eq!(value["escaped"], "${FOO} costs $5");
+    }
+}
+
+#[test]
+fn test_strings_with_dollar_stay_borrowed() {
+    let input = r#""rate(http_requests_total{job='$job'}[5m])""#;
+    let mut lexer = UclLexer::with_config(input, no_detection());
+    match lexer.next_token().unwrap() {
+        Token::String {
+            value,
+            needs_expansion,
+            ..
+        } => {
+            assert!(matches!(value, Cow::Borrowed(_)));
+            assert_eq!(value, &input[1..input.len() - 1]);
+            assert!(!needs_expansion);
+        }
+        other => panic!("expected a string, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_streaming_lexer() {
+    let input = "a = \"${FOO}\"\nb = <<EOD\n$FOO\nEOD\n";
+    let mut lexer = StreamingUclLexer::new(Cursor::new(input)).with_detect_variables(false);
+    let mut strings = Vec::new();
+    loop {
+        match lexer.next_token().unwrap() {
+            Token::Eof => break,
+            Token::String {
+                value,
+                needs_expansion,
+                ..
+            } => strings.push((value.into_owned(), needs_expansion)),
+            _ => {}
+        }
+    }
+    assert_eq!(
+        strings,
+        [("${FOO}".to_string(), false), ("$FOO\n".to_string(), false)]
+    );
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[allow(dead_code)]
+    name: String,
+}
+
+#[cfg(not(feature = "no-variable-expansion"))]
+#[test]
+fn test_enabled_by_default() {
+    assert!(LexerConfig::default().detect_variables);
+    let config: Config = from_str_with_variables("name = \"${FOO}\"", handler()).unwrap();
+    assert_eq!(config.name, "expanded");
+}
+
+#[cfg(feature = "no-variable-expansion")]
+#[test]
+fn test_feature_disables_expansion() {
+    // The feature wins over the runtime flag
+    assert!(!LexerConfig::default().detect_variables);
+    let config = LexerConfig::new().with_detect_variables(true);
+    assert_eq!(parse("name = \"${FOO}\"", config)["name"], "${FOO}");
+
+    let err = from_str_with_variables::<Config>("name = \"${FOO}\"", handler()).unwrap_err();
+    assert!(matches!(
+        err,
+        ucl_lexer::UclError::VariableExpansionDisabled
+    ));
+    assert!(err.to_string().contains("no-variable-expansion"), "{err}");
+}
@@ -0,0 +1,44 @@
+//! Tests for `${NAME:-fallback}` defaults, including nested references
+
+use std::collections::HashMap;
+use ucl_lexer::{MapVariableHandler, ParseError, UclParser, UclValue};
+
+fn parse(input: &str) -> Result<UclValue, ParseError> {
+    let handler = MapVariableHandler::from_map(HashMap::from([(
+        "HOST".to_string(),
+        "10.0.0.1".to_string(),
+    )]));
+    UclParser::with_variable_handler(input, Box::new(handler)).parse_document()
+}
+
+#[test]
+fn test_fallback_used_for_unset_variables() {
+    let value = parse("a = \"${PORT:-80}\"\nb = \"${HOST:-localhost}\"").unwrap();
+    assert_eq!(value["a"], "80");
+    assert_eq!(value["b"], "10.0.0.1");
+}
+
+#[test]
+fn test_nested_fallbacks() {
+    let value = parse(
+        "a = \"${NAME:-${HOST}}\"\nb = \"${NAME:-${OTHER:-last}}\"\nc = \"${HOST:-${OTHER}}\"",
+    )
+    .unwrap();
+    assert_eq!(value["a"], "10.0.0.1");
+    assert_eq!(value["b"], "last");
+    assert_eq!(value["c"], "10.0.0.1");
+
+    // Braces in a fallback pair up before the reference closes
+    let value = parse("a = \"${NAME:-{x}}\"\nb = \"${NAME:-x}}\"").unwrap();
+    assert_eq!(value["a"], "{x}");
+    assert_eq!(value["b"], "x}");
+}
+
+#[test]
+fn test_unclosed_nested_fallback() {
+    let err = parse("a = \"${NAME:-${HOST}\"").unwrap_err();
+    assert!(
+        matches!(err, ParseError::VariableExpansion { ref message, .. } if message.contains("missing '}'")),
+        "{err:?}"
+    );
+}
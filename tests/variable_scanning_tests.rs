@@ -0,0 +1,81 @@
+//! Tests for `ucl_lexer::expand` and the expanded string length limit
+
+use std::collections::HashMap;
+use ucl_lexer::expand::{Segment, scan_variables};
+use ucl_lexer::{LexerConfig, MapVariableHandler, ParseError, UclParser, UclParserBuilder};
+
+fn handler() -> Box<MapVariableHandler> {
+    Box::new(MapVariableHandler::from_map(HashMap::from([
+        ("HOST".to_string(), "10.0.0.1".to_string()),
+        ("BLOCK".to_string(), "x".repeat(1000)),
+    ])))
+}
+
+#[test]
+fn test_variable_spans_without_expanding() {
+    let input = "bind ${HOST:-0.0.0.0}:$PORT # $$ kept, ${BAD and $1 literal";
+    let references: Vec<&str> = scan_variables(input)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Variable { span, .. } | Segment::Malformed { span, .. } => Some(&input[span]),
+            Segment::Literal(_) => None,
+        })
+        .collect();
+    assert_eq!(references, ["${HOST:-0.0.0.0}", "$PORT", "${BAD "]);
+
+    // Joining the literals and references gives back the input, less one `$` per `$$`
+    let rejoined: String = scan_variables(input)
+        .into_iter()
+        .map(|segment| match segment {
+            Segment::Literal(text) => text,
+            Segment::Variable { span, .. } | Segment::Malformed { span, .. } => &input[span],
+        })
+        .collect();
+    assert_eq!(rejoined, input.replacen("$$", "$", 1));
+}
+
+#[test]
+fn test_many_references_expand_in_one_pass() {
+    let template: String = (0..10_000)
+        .map(|i| format!("server s{i} ${{HOST}}:${{PORT:-80}} $$weight\n"))
+        .collect();
+    let parser = UclParser::with_variable_handler("", handler());
+    let expanded = parser.expand_variables(&template).unwrap();
+
+    let expected: String = (0..10_000)
+        .map(|i| format!("server s{i} 10.0.0.1:80 $weight\n"))
+        .collect();
+    assert_eq!(expanded, expected);
+    assert_eq!(expanded.capacity(), expanded.len());
+}
+
+#[test]
+fn test_expanded_length_is_limited() {
+    let input = format!("value = \"{}\"", "${BLOCK}".repeat(20));
+    let parse = |limit| {
+        UclParserBuilder::new(&input)
+            .with_lexer_config(LexerConfig::new().with_max_string_length(limit))
+            .with_variable_handler(handler())
+            .build()
+            .unwrap()
+            .parse_document()
+    };
+
+    assert_eq!(
+        parse(20_000).unwrap()["value"].as_str().unwrap().len(),
+        20_000
+    );
+
+    let err = parse(5_000).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            ParseError::ExpandedStringTooLong { length, limit: 5_000, .. } if length > 5_000
+        ),
+        "{err}"
+    );
+    assert!(
+        err.to_string()
+            .contains("maximum string length of 5000 bytes")
+    );
+}
@@ -0,0 +1,251 @@
+//! Tests for walking values with `UclValue::walk` and `UclValue::visit`
+
+use ucl_lexer::{
+    KeyPath, PathSegment, UclArray, UclObject, UclParser, UclValue, UclVisitor, WalkNodes, ucl,
+};
+
+const DOCUMENT: &str = r#"
+name = "web"
+servers [
+    { host = "a.example.com", port = 80, tags = [] },
+    { host = "b.example.com", port = 8080, "v1.2" = yes },
+]
+hosts {
+    "example.com" { port = 443 }
+    "" = "empty key"
+    "[x]" = 1
+    "say \"hi\"" = 2
+}
+limits { }
+matrix = [[1, 2], [3, [4, 5]]]
+timeout = 30s
+ratio = 0.5
+nothing = null
+"#;
+
+fn document() -> UclValue {
+    UclParser::new(DOCUMENT).parse_document().unwrap()
+}
+
+/// Sets `leaf` at `path`, creating the objects and arrays on the way
+fn insert(root: &mut UclValue, path: &[PathSegment], leaf: UclValue) {
+    let Some((segment, rest)) = path.split_first() else {
+        *root = leaf;
+        return;
+    };
+    let child = match segment {
+        PathSegment::Key(key) => {
+            if !root.is_object() {
+                *root = UclValue::Object(UclObject::default());
+            }
+            let UclValue::Object(object) = root else {
+                unreachable!()
+            };
+            object.entry(key.as_str().into()).or_insert(UclValue::Null)
+        }
+        PathSegment::Index(index) => {
+            if !root.is_array() {
+                *root = UclValue::Array(Box::default());
+            }
+            let UclValue::Array(array) = root else {
+                unreachable!()
+            };
+            if array.len() <= *index {
+                array.resize(index + 1, UclValue::Null);
+            }
+            &mut array[*index]
+        }
+    };
+    insert(child, rest, leaf);
+}
+
+#[test]
+fn test_leaves_rebuild_the_document() {
+    let value = document();
+    let mut rebuilt = UclValue::Null;
+    for (path, leaf) in value.walk(WalkNodes::Leaves) {
+        insert(&mut rebuilt, path.segments(), leaf.clone());
+    }
+    assert_eq!(rebuilt, value);
+
+    // The same through the written paths
+    let mut rebuilt = UclValue::Null;
+    for path in value.leaf_paths() {
+        let leaf = value.get_path(&path).unwrap().clone();
+        insert(
+            &mut rebuilt,
+            KeyPath::parse(&path).unwrap().segments(),
+            leaf,
+        );
+    }
+    assert_eq!(rebuilt, value);
+}
+
+#[test]
+fn test_leaf_paths() {
+    assert_eq!(
+        document().leaf_paths(),
+        [
+            "name",
+            "servers[0].host",
+            "servers[0].port",
+            "servers[0].tags",
+            "servers[1].host",
+            "servers[1].port",
+            "servers[1].\"v1.2\"",
+            "hosts.\"example.com\".port",
+            "hosts.\"\"",
+            "hosts.\"[x]\"",
+            "hosts.say \"hi\"",
+            "limits",
+            "matrix[0][0]",
+            "matrix[0][1]",
+            "matrix[1][0]",
+            "matrix[1][1][0]",
+            "matrix[1][1][1]",
+            "timeout",
+            "ratio",
+            "nothing",
+        ]
+    );
+
+    // A scalar root is its own only leaf
+    assert_eq!(ucl!(1).leaf_paths(), [""]);
+    assert_eq!(ucl!({}).leaf_paths(), [""]);
+}
+
+#[test]
+fn test_walk_nodes() {
+    let value = ucl!({ "a": { "b": [1, { "c": true }] }, "d": "x" });
+    let paths = |nodes| -> Vec<String> {
+        value
+            .walk(nodes)
+            .map(|(path, _)| path.to_string())
+            .collect()
+    };
+
+    assert_eq!(
+        paths(WalkNodes::All),
+        ["", "a", "a.b", "a.b[0]", "a.b[1]", "a.b[1].c", "d"]
+    );
+    assert_eq!(paths(WalkNodes::Objects), ["", "a", "a.b[1]"]);
+    assert_eq!(paths(WalkNodes::Leaves), ["a.b[0]", "a.b[1].c", "d"]);
+
+    for (path, node) in value.walk(WalkNodes::All) {
+        assert!(
+            std::ptr::eq(value.get_key_path(&path).unwrap(), node),
+            "{path}"
+        );
+    }
+}
+
+#[test]
+fn test_walk_deep_nesting() {
+    let mut value = ucl!(1);
+    for _ in 0..10_000 {
+        value = ucl!([value]);
+    }
+    let leaves: Vec<_> = value.walk(WalkNodes::Leaves).collect();
+    assert_eq!(leaves.len(), 1);
+    assert_eq!(leaves[0].0.len(), 10_000);
+    assert_eq!(leaves[0].1, &ucl!(1));
+}
+
+/// Records every callback as a line
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl UclVisitor for Recorder {
+    fn enter_object(&mut self, path: &KeyPath, object: &UclObject) {
+        self.0
+            .push(format!("enter object {path} ({})", object.len()));
+    }
+
+    fn exit_object(&mut self, path: &KeyPath, _object: &UclObject) {
+        self.0.push(format!("exit object {path}"));
+    }
+
+    fn enter_array(&mut self, path: &KeyPath, array: &UclArray) {
+        self.0.push(format!("enter array {path} ({})", array.len()));
+    }
+
+    fn exit_array(&mut self, path: &KeyPath, _array: &UclArray) {
+        self.0.push(format!("exit array {path}"));
+    }
+
+    fn visit_scalar(&mut self, path: &KeyPath, value: &UclValue) {
+        self.0.push(format!("{path} = {}", value.type_name()));
+    }
+}
+
+#[test]
+fn test_visitor_callbacks() {
+    let value = ucl!({ "a": [1, {}], "b": { "c.d": null } });
+    let mut recorder = Recorder::default();
+    value.visit(&mut recorder);
+    assert_eq!(
+        recorder.0,
+        [
+            "enter object  (2)",
+            "enter array a (2)",
+            "a[0] = integer",
+            "enter object a[1] (0)",
+            "exit object a[1]",
+            "exit array a",
+            "enter object b (1)",
+            "b.\"c.d\" = null",
+            "exit object b",
+            "exit object ",
+        ]
+    );
+
+    // A visitor with no methods of its own does nothing
+    struct Nothing;
+    impl UclVisitor for Nothing {}
+    value.visit(&mut Nothing);
+}
+
+#[test]
+fn test_get_path_syntax() {
+    let value = document();
+    assert_eq!(value.get_path(""), Some(&value));
+    assert_eq!(value.get_path("servers.1.port"), Some(&ucl!(8080)));
+    assert_eq!(value.get_path("servers[1].port"), Some(&ucl!(8080)));
+    assert_eq!(value.get_path("servers[1].\"v1.2\""), Some(&ucl!(true)));
+    assert_eq!(
+        value.get_path("hosts.\"example.com\".port"),
+        Some(&ucl!(443))
+    );
+    assert_eq!(value.get_path("hosts.say \"hi\""), Some(&ucl!(2)));
+    assert_eq!(value.get_path("hosts.\"say \\\"hi\\\"\""), Some(&ucl!(2)));
+    assert_eq!(value.get_path("matrix[1][1][0]"), Some(&ucl!(4)));
+
+    for path in [
+        "servers[2]",
+        "servers[x]",
+        "servers[0",
+        "servers..host",
+        "servers.",
+        "hosts.\"example.com",
+        "name[0]",
+        "hosts.example.com",
+    ] {
+        assert_eq!(value.get_path(path), None, "{path}");
+    }
+}
+
+#[test]
+fn test_key_path_display_round_trip() {
+    let path = KeyPath::from(vec![
+        PathSegment::Key("a".into()),
+        PathSegment::Index(3),
+        PathSegment::Key("b.c".into()),
+        PathSegment::Key("\"q\\".into()),
+        PathSegment::Key(String::new()),
+    ]);
+    let written = path.to_string();
+    assert_eq!(written, "a[3].\"b.c\".\"\\\"q\\\\\".\"\"");
+    assert_eq!(KeyPath::parse(&written), Some(path));
+    assert_eq!(KeyPath::parse(""), Some(KeyPath::new()));
+    assert_eq!(KeyPath::parse("[0].a").unwrap().to_string(), "[0].a");
+}
@@ -0,0 +1,203 @@
+//! Tests for writing UCL entry by entry with `UclWriter`
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io;
+use ucl_lexer::{FormatConfig, UclParser, UclScalar, UclValue, UclWriter, WriteError, ucl};
+
+/// Counts the bytes allocated and not yet freed by the current thread
+struct CountingAllocator;
+
+thread_local! {
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.with(|live| live.set(live.get() + layout.size() as isize));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.with(|live| live.set(live.get() - layout.size() as isize));
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn live_bytes() -> isize {
+    LIVE_BYTES.with(Cell::get)
+}
+
+/// Writes `groups` objects of 100 entries, covering every scalar type and nesting kind
+fn write_entries<W: io::Write>(writer: &mut UclWriter<W>, groups: usize) -> Result<(), WriteError> {
+    writer.write_comment("generated entries")?;
+    for group in 0..groups {
+        writer.begin_object(format!("group_{}", group).as_str())?;
+        for item in 0..98 {
+            let key = format!("item-{}", item);
+            let index = (group * 100 + item) as i64;
+            match item % 6 {
+                0 => writer.write_scalar(key.as_str(), index)?,
+                1 => writer.write_scalar(key.as_str(), format!("name \"{}\"\n", index))?,
+                2 => writer.write_scalar(key.as_str(), index as f64 + 0.5)?,
+                3 => writer.write_scalar(key.as_str(), index % 2 == 0)?,
+                4 => writer.write_scalar(key.as_str(), UclScalar::Time(index as f64))?,
+                _ => writer.write_scalar(key.as_str(), UclScalar::Null)?,
+            }
+        }
+        writer.begin_array("list")?;
+        writer.write_scalar(None, group as i64)?;
+        writer.begin_object(None)?;
+        writer.write_scalar("true", "reserved key")?;
+        writer.end_object()?;
+        writer.begin_array(None)?;
+        writer.write_scalar(None, "nested")?;
+        writer.end_array()?;
+        writer.end_array()?;
+        writer.begin_object("nested")?;
+        writer.write_scalar("id", group as i64)?;
+        writer.end_object()?;
+        writer.end_object()?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_round_trip_of_large_document() {
+    let mut writer = UclWriter::new(Vec::new());
+    write_entries(&mut writer, 1000).unwrap();
+    let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    let value = UclParser::new(&output).parse_document().unwrap();
+    let document = value.as_object().unwrap();
+    assert_eq!(document.len(), 1000);
+    let entries: usize = document
+        .values()
+        .map(|group| group.as_object().unwrap().len())
+        .sum();
+    assert_eq!(entries, 100_000);
+
+    for group in (0..1000).step_by(97) {
+        let object = &value[format!("group_{}", group).as_str()];
+        for item in (0..98).step_by(7) {
+            let index = (group * 100 + item) as i64;
+            let expected = match item % 6 {
+                0 => UclValue::Integer(index),
+                1 => UclValue::String(format!("name \"{}\"\n", index)),
+                2 => UclValue::Float(index as f64 + 0.5),
+                3 => UclValue::Boolean(index % 2 == 0),
+                4 => UclValue::Time(index as f64),
+                _ => UclValue::Null,
+            };
+            assert_eq!(object[format!("item-{}", item).as_str()], expected);
+        }
+        assert_eq!(
+            object["list"],
+            ucl!([group as i64, { "true": "reserved key" }, ["nested"]])
+        );
+        assert_eq!(object["nested"]["id"], group as i64);
+    }
+}
+
+#[test]
+fn test_writer_holds_only_the_nesting_stack() {
+    let mut writer = UclWriter::new(io::sink());
+    // Grow the stack once so that later entries reuse its allocation
+    writer.begin_array("warmup").unwrap();
+    writer.begin_array(None).unwrap();
+    writer.begin_array(None).unwrap();
+    writer.begin_array(None).unwrap();
+    for _ in 0..4 {
+        writer.end_array().unwrap();
+    }
+
+    let before = live_bytes();
+    write_entries(&mut writer, 1000).unwrap();
+    assert_eq!(writer.depth(), 0);
+    assert_eq!(live_bytes(), before);
+    writer.finish().unwrap();
+}
+
+#[test]
+fn test_output_matches_serializer() {
+    let config = FormatConfig::new().with_indent(2);
+    let mut writer = UclWriter::with_config(Vec::new(), config.clone());
+    writer.write_scalar("name", "app").unwrap();
+    writer.begin_object("server").unwrap();
+    writer.write_scalar("port", 8080).unwrap();
+    writer.begin_array("weights").unwrap();
+    writer.write_scalar(None, 1.5).unwrap();
+    writer.end_array().unwrap();
+    writer.end_object().unwrap();
+    let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    let value = ucl!({ "name": "app", "server": { "port": 8080, "weights": [1.5] } });
+    assert_eq!(
+        output,
+        ucl_lexer::to_ucl_string_with_config(&value, &config)
+    );
+}
+
+#[test]
+fn test_comments_and_raw_text() {
+    let mut writer = UclWriter::new(Vec::new());
+    writer.begin_object("server").unwrap();
+    writer
+        .write_comment("port to listen on\n\nrequired")
+        .unwrap();
+    writer.write_scalar("port", 80).unwrap();
+    writer.end_object().unwrap();
+    writer.write_raw("include_me = yes;\n").unwrap();
+    let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    assert_eq!(
+        output,
+        "server {\n    # port to listen on\n    #\n    # required\n    port = 80;\n}\ninclude_me = yes;\n"
+    );
+    let value = UclParser::new(&output).parse_document().unwrap();
+    assert_eq!(
+        value,
+        ucl!({ "server": { "port": 80 }, "include_me": true })
+    );
+}
+
+#[test]
+fn test_invalid_nesting_is_rejected() {
+    let mut writer = UclWriter::new(Vec::new());
+    assert!(matches!(
+        writer.end_object(),
+        Err(WriteError::NotOpen {
+            container: "object"
+        })
+    ));
+    assert!(matches!(
+        writer.write_scalar(None, 1),
+        Err(WriteError::MissingKey)
+    ));
+
+    writer.begin_array("list").unwrap();
+    assert!(matches!(
+        writer.end_object(),
+        Err(WriteError::NotOpen {
+            container: "object"
+        })
+    ));
+    let err = writer.write_scalar("key", 1).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Array elements cannot have a key, found 'key'"
+    );
+
+    writer.begin_object(None).unwrap();
+    assert!(matches!(
+        writer.end_array(),
+        Err(WriteError::NotOpen { container: "array" })
+    ));
+    assert!(matches!(
+        writer.finish(),
+        Err(WriteError::Unclosed { depth: 2 })
+    ));
+}